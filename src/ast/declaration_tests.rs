@@ -1,4 +1,4 @@
-use crate::ast::nodes::{Expression, Statement};
+use crate::ast::nodes::{Expression, Pattern, Statement};
 use crate::ast::parser::Parser;
 use crate::interpreter::Interpreter;
 use crate::lexer::tokenizer::Tokenizer;
@@ -8,8 +8,7 @@ use crate::typechecker::TypeChecker;
 #[test]
 fn test_function_declaration_parsing() {
     let input = "fn add(x) -> Int { x + 1 }";
-    let mut tokenizer = Tokenizer::new(input);
-    let tokens = tokenizer.tokenize(input).unwrap();
+    let tokens = Tokenizer::new(input).tokenize().unwrap();
     let mut parser = Parser::new(tokens);
     let program = parser.parse().unwrap();
 
@@ -25,8 +24,7 @@ fn test_function_declaration_parsing() {
 #[test]
 fn test_function_declaration_with_typed_parameter() {
     let input = "fn add(x: Int) -> Int { x + 1 }";
-    let mut tokenizer = Tokenizer::new(input);
-    let tokens = tokenizer.tokenize(input).unwrap();
+    let tokens = Tokenizer::new(input).tokenize().unwrap();
     let mut parser = Parser::new(tokens);
     let program = parser.parse().unwrap();
 
@@ -50,8 +48,7 @@ fn test_function_declaration_with_typed_parameter() {
 #[test]
 fn test_function_declaration_with_typed_parameter_no_return() {
     let input = "fn double(y: Int) { y * 2 }";
-    let mut tokenizer = Tokenizer::new(input);
-    let tokens = tokenizer.tokenize(input).unwrap();
+    let tokens = Tokenizer::new(input).tokenize().unwrap();
     let mut parser = Parser::new(tokens);
     let program = parser.parse().unwrap();
 
@@ -75,25 +72,58 @@ fn test_function_declaration_with_typed_parameter_no_return() {
 #[test]
 fn test_import_parsing() {
     let input = "import \"math.corr\" as math;";
-    let mut tokenizer = Tokenizer::new(input);
-    let tokens = tokenizer.tokenize(input).unwrap();
+    let tokens = Tokenizer::new(input).tokenize().unwrap();
     let mut parser = Parser::new(tokens);
     let program = parser.parse().unwrap();
 
     match &program.statements[0] {
-        Statement::Import { path, alias, .. } => {
+        Statement::Import {
+            path,
+            alias,
+            exported,
+            ..
+        } => {
             assert_eq!(path, "math.corr");
             assert_eq!(alias, &Some("math".to_string()));
+            assert!(!exported);
         }
         _ => panic!("Expected import statement"),
     }
+
+    let input = "export import \"strings.corr\" as strings;";
+    let tokens = Tokenizer::new(input).tokenize().unwrap();
+    let program = Parser::new(tokens).parse().unwrap();
+    assert!(matches!(
+        &program.statements[0],
+        Statement::Import { exported: true, .. }
+    ));
+
+    let input = "import \"math.corr\";";
+    let tokens = Tokenizer::new(input).tokenize().unwrap();
+    let program = Parser::new(tokens).parse().unwrap();
+    assert!(matches!(
+        &program.statements[0],
+        Statement::Import { alias: None, .. }
+    ));
+}
+
+#[test]
+fn test_function_with_several_parameters_suggests_currying() {
+    for input in [
+        "fn add(a, b) { a + b }",
+        "let add = fn(a: Int, b) { a + b };",
+    ] {
+        let tokens = Tokenizer::new(input).tokenize().unwrap();
+        let error = Parser::new(tokens).parse().unwrap_err().to_string();
+        assert!(error.contains("functions take one parameter"), "{}", error);
+        assert!(error.contains("fn(a) { fn(b) { a + b } }"), "{}", error);
+    }
 }
 
 #[test]
 fn test_qualified_identifier_parsing() {
     let input = "math.square(5);";
-    let mut tokenizer = Tokenizer::new(input);
-    let tokens = tokenizer.tokenize(input).unwrap();
+    let tokens = Tokenizer::new(input).tokenize().unwrap();
     let mut parser = Parser::new(tokens);
     let program = parser.parse().unwrap();
 
@@ -112,11 +142,29 @@ fn test_qualified_identifier_parsing() {
     }
 }
 
+#[test]
+fn test_nested_qualified_identifier_parsing() {
+    let input = "utils.strings.trim;";
+    let tokens = Tokenizer::new(input).tokenize().unwrap();
+    let mut parser = Parser::new(tokens);
+    let program = parser.parse().unwrap();
+
+    match &program.statements[0] {
+        Statement::Expression {
+            expression: Expression::QualifiedIdentifier { module, name, .. },
+            ..
+        } => {
+            assert_eq!(module, "utils.strings");
+            assert_eq!(name, "trim");
+        }
+        _ => panic!("Expected qualified identifier"),
+    }
+}
+
 #[test]
 fn test_function_declaration_type_checking() {
     let input = "fn add(x) -> Int { x + 1 }";
-    let mut tokenizer = Tokenizer::new(input);
-    let tokens = tokenizer.tokenize(input).unwrap();
+    let tokens = Tokenizer::new(input).tokenize().unwrap();
     let mut parser = Parser::new(tokens);
     let program = parser.parse().unwrap();
 
@@ -134,8 +182,7 @@ fn test_function_declaration_type_checking() {
 #[test]
 fn test_function_declaration_with_typed_parameter_type_checking() {
     let input = "fn add(x: Int) -> Int { x + 1 }";
-    let mut tokenizer = Tokenizer::new(input);
-    let tokens = tokenizer.tokenize(input).unwrap();
+    let tokens = Tokenizer::new(input).tokenize().unwrap();
     let mut parser = Parser::new(tokens);
     let program = parser.parse().unwrap();
 
@@ -153,8 +200,7 @@ fn test_function_declaration_with_typed_parameter_type_checking() {
 #[test]
 fn test_function_declaration_interpretation() {
     let input = "fn add(x) { x + 1 } let result = add(5);";
-    let mut tokenizer = Tokenizer::new(input);
-    let tokens = tokenizer.tokenize(input).unwrap();
+    let tokens = Tokenizer::new(input).tokenize().unwrap();
     let mut parser = Parser::new(tokens);
     let program = parser.parse().unwrap();
 
@@ -167,8 +213,7 @@ fn test_function_declaration_interpretation() {
 #[test]
 fn test_anonymous_function_with_typed_parameter() {
     let input = "let f = fn(x: Int) { x + 1 };";
-    let mut tokenizer = Tokenizer::new(input);
-    let tokens = tokenizer.tokenize(input).unwrap();
+    let tokens = Tokenizer::new(input).tokenize().unwrap();
     let mut parser = Parser::new(tokens);
     let program = parser.parse().unwrap();
 
@@ -189,8 +234,7 @@ fn test_anonymous_function_with_typed_parameter() {
 #[test]
 fn test_anonymous_function_backward_compatibility() {
     let input = "let f = fn(x) { x + 1 };";
-    let mut tokenizer = Tokenizer::new(input);
-    let tokens = tokenizer.tokenize(input).unwrap();
+    let tokens = Tokenizer::new(input).tokenize().unwrap();
     let mut parser = Parser::new(tokens);
     let program = parser.parse().unwrap();
 
@@ -207,3 +251,217 @@ fn test_anonymous_function_backward_compatibility() {
         _ => panic!("Expected variable declaration"),
     }
 }
+
+#[test]
+fn test_constant_declaration_parsing() {
+    let input = "const SIZE: Int = 4 * 256;";
+    let tokens = Tokenizer::new(input).tokenize().unwrap();
+    let program = Parser::new(tokens).parse().unwrap();
+
+    match &program.statements[0] {
+        Statement::ConstantDeclaration {
+            name,
+            type_annotation,
+            ..
+        } => {
+            assert_eq!(name, "SIZE");
+            assert!(type_annotation.is_some());
+        }
+        _ => panic!("Expected constant declaration"),
+    }
+
+    // Constants are only declared at the top level
+    let input = "fn f(x) { const y = 1; x }";
+    let tokens = Tokenizer::new(input).tokenize().unwrap();
+    assert!(Parser::new(tokens).parse().is_err());
+}
+
+#[test]
+fn test_enum_declaration_and_case_arms_parsing() {
+    let input = "enum Color { Red, Green, Blue, }\ncase c of Color.Red => 1 | _ => 2;";
+    let tokens = Tokenizer::new(input).tokenize().unwrap();
+    let program = Parser::new(tokens).parse().unwrap();
+
+    match &program.statements[0] {
+        Statement::EnumDeclaration { name, variants, .. } => {
+            assert_eq!(name, "Color");
+            assert_eq!(variants, &["Red", "Green", "Blue"]);
+        }
+        _ => panic!("Expected enum declaration"),
+    }
+    match &program.statements[1] {
+        Statement::Expression {
+            expression: Expression::Match { arms, .. },
+            ..
+        } => {
+            assert_eq!(arms.len(), 2);
+            assert!(matches!(
+                &arms[0].pattern,
+                Pattern::Variant { enum_name, variant, .. } if enum_name == "Color" && variant == "Red"
+            ));
+            assert!(matches!(arms[1].pattern, Pattern::Wildcard { .. }));
+        }
+        _ => panic!("Expected case expression"),
+    }
+
+    for input in [
+        "enum Empty { }",
+        "fn f(x) { enum E { A }; x }",
+        "case c of Color => 1;",
+    ] {
+        let tokens = Tokenizer::new(input).tokenize().unwrap();
+        assert!(Parser::new(tokens).parse().is_err(), "{}", input);
+    }
+}
+
+#[test]
+fn test_string_case_arms_parsing() {
+    let input = "case command of \"go\" => 1 | \"stop\" => 2 | _ => 3;";
+    let tokens = Tokenizer::new(input).tokenize().unwrap();
+    let program = Parser::new(tokens).parse().unwrap();
+
+    match &program.statements[0] {
+        Statement::Expression {
+            expression: Expression::Match { arms, .. },
+            ..
+        } => {
+            let labels: Vec<&str> = arms.iter().map(|arm| arm.pattern.label()).collect();
+            assert_eq!(labels, ["go", "stop", "_"]);
+            assert!(matches!(arms[0].pattern, Pattern::String { .. }));
+        }
+        _ => panic!("Expected case expression"),
+    }
+}
+
+#[test]
+fn test_int_case_arms_parsing() {
+    let input = "case n of -1 => 0 | ..0 => 1 | 1..10 => 2 | 10.. => 3;";
+    let tokens = Tokenizer::new(input).tokenize().unwrap();
+    let program = Parser::new(tokens).parse().unwrap();
+
+    match &program.statements[0] {
+        Statement::Expression {
+            expression: Expression::Match { arms, .. },
+            ..
+        } => {
+            let patterns: Vec<(Option<i64>, Option<i64>)> = arms
+                .iter()
+                .map(|arm| match arm.pattern {
+                    Pattern::Int { value, .. } => (Some(value), Some(value + 1)),
+                    Pattern::Range { start, end, .. } => (start, end),
+                    _ => panic!("Expected an integer pattern"),
+                })
+                .collect();
+            assert_eq!(
+                patterns,
+                [
+                    (Some(-1), Some(0)),
+                    (None, Some(0)),
+                    (Some(1), Some(10)),
+                    (Some(10), None)
+                ]
+            );
+        }
+        _ => panic!("Expected case expression"),
+    }
+
+    for input in ["case n of 5..5 => 1 | _ => 2;", "case n of .. => 1;"] {
+        let tokens = Tokenizer::new(input).tokenize().unwrap();
+        assert!(Parser::new(tokens).parse().is_err(), "{}", input);
+    }
+}
+
+#[test]
+fn test_reserved_words_are_refused_as_names() {
+    let cases = [
+        (
+            "let head = 1;",
+            "'head' is a reserved word and cannot be used as a variable name",
+        ),
+        (
+            "fn range(x) { x }",
+            "'range' is a reserved word and cannot be used as a function name",
+        ),
+        (
+            "let f = fn(type) { 1 };",
+            "'type' is a reserved word and cannot be used as a parameter name",
+        ),
+        (
+            "for print in [1] { 1; };",
+            "'print' is a reserved word and cannot be used as a loop variable",
+        ),
+        (
+            "import \"a.corr\" as fix;",
+            "'fix' is a reserved word and cannot be used as a module alias",
+        ),
+    ];
+    for (input, message) in cases {
+        let tokens = Tokenizer::new(input).tokenize().unwrap();
+        let error = Parser::new(tokens).parse().unwrap_err().to_string();
+        assert!(error.contains(message), "{}", error);
+    }
+
+    let tokens = Tokenizer::new("let head = 1;").tokenize().unwrap();
+    let error = Parser::new(tokens).parse().unwrap_err().to_string();
+    assert!(
+        error.contains("use another name such as 'head_'"),
+        "{}",
+        error
+    );
+    // The suggested name is an ordinary identifier
+    let tokens = Tokenizer::new("let head_ = 1;").tokenize().unwrap();
+    assert!(Parser::new(tokens).parse().is_ok());
+}
+
+#[test]
+fn test_reserved_words_read_as_values_are_named() {
+    let cases = [
+        (
+            "let x = 1 + in;",
+            "'in' is a reserved word and cannot be used as a name; rename the variable",
+        ),
+        ("print(of);", "'of' is a reserved word"),
+        ("let ys = [case, 2];", "'case' is a reserved word"),
+        (
+            "let f = map(head);",
+            "'head' is a builtin and cannot be used as a name; call it as head(...)",
+        ),
+    ];
+    for (input, message) in cases {
+        let tokens = Tokenizer::new(input).tokenize().unwrap();
+        let error = Parser::new(tokens).parse().unwrap_err();
+        assert_eq!(error.code(), "E0103");
+        assert!(error.to_string().contains(message), "{}", error);
+    }
+
+    // Keywords that start a construct are still read as one
+    for input in [
+        "let y = if -1 < 0 { 1 } else { 2 };",
+        "let z = [case 1 of _ => 2];",
+    ] {
+        let tokens = Tokenizer::new(input).tokenize().unwrap();
+        assert!(Parser::new(tokens).parse().is_ok(), "{}", input);
+    }
+}
+
+#[test]
+fn test_wildcard_let_binds_nothing() {
+    let input = "let _ = 1; let _ = true; let x = 2;";
+    let tokens = Tokenizer::new(input).tokenize().unwrap();
+    let program = Parser::new(tokens).parse().unwrap();
+    assert!(matches!(
+        &program.statements[0],
+        Statement::VariableDeclaration { name, .. } if name == "_"
+    ));
+
+    let mut checker = TypeChecker::new();
+    checker.check_program(&program).unwrap();
+    assert!(checker.get_environment().lookup("_").is_none());
+    let mut interpreter = Interpreter::new();
+    interpreter.interpret_program(&program).unwrap();
+    assert!(interpreter.environment().lookup("_").is_none());
+
+    // Constants must be named
+    let tokens = Tokenizer::new("const _ = 1;").tokenize().unwrap();
+    assert!(Parser::new(tokens).parse().is_err());
+}
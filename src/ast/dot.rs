@@ -0,0 +1,375 @@
+//! Graphviz DOT export of parsed programs, used by `--emit=ast-dot` and
+//! `--emit=callgraph-dot`
+
+use super::nodes::{
+    BinaryOperator, Child, Expression, Program, Statement, TypeExpression, UnaryOperator, hole_text,
+};
+use std::collections::BTreeSet;
+
+/// Name of the call graph node for calls made at the top level of the program
+const TOP_LEVEL: &str = "<top level>";
+
+impl Program {
+    /// Render the syntax tree as a DOT digraph, one node per statement and expression
+    pub fn to_ast_dot(&self) -> String {
+        let mut graph = DotGraph::new("ast");
+        let root = graph.node("Program", "box");
+        for statement in &self.statements {
+            let child = graph.statement(statement);
+            graph.edge(root, child, None);
+        }
+        graph.finish()
+    }
+
+    /// Render which named functions call which as a DOT digraph. Calls to
+    /// module members are included as separate nodes; calls through
+    /// variables holding functions are not followed.
+    pub fn to_call_graph_dot(&self) -> String {
+        let mut functions = BTreeSet::new();
+        for statement in &self.statements {
+            if let Some(name) = named_function(statement) {
+                functions.insert(name);
+            }
+        }
+
+        let mut edges = BTreeSet::new();
+        for statement in &self.statements {
+            let caller = named_function(statement).unwrap_or(TOP_LEVEL);
+            let mut callees = Vec::new();
+            statement_calls(statement, &mut callees);
+            for callee in callees {
+                edges.insert((caller.to_string(), callee));
+            }
+        }
+
+        // Unknown plain names are parameters or locals holding functions
+        edges.retain(|(_, callee)| callee.contains('.') || functions.contains(callee.as_str()));
+
+        let mut out = String::from("digraph callgraph {\n    node [shape=box];\n");
+        for name in &functions {
+            out.push_str(&format!("    {};\n", quote(name)));
+        }
+        if edges.iter().any(|(caller, _)| caller == TOP_LEVEL) {
+            out.push_str(&format!("    {} [shape=ellipse];\n", quote(TOP_LEVEL)));
+        }
+        let members: BTreeSet<&String> = edges
+            .iter()
+            .map(|(_, callee)| callee)
+            .filter(|callee| callee.contains('.'))
+            .collect();
+        for member in members {
+            out.push_str(&format!("    {} [style=dashed];\n", quote(member)));
+        }
+        for (caller, callee) in &edges {
+            out.push_str(&format!("    {} -> {};\n", quote(caller), quote(callee)));
+        }
+        out.push_str("}\n");
+        out
+    }
+}
+
+/// Name of a top-level function, declared with `fn` or bound to a function literal
+fn named_function(statement: &Statement) -> Option<&str> {
+    match statement {
+        Statement::FunctionDeclaration { name, .. } => Some(name),
+        Statement::VariableDeclaration {
+            name,
+            value: Expression::Function { .. },
+            ..
+        } => Some(name),
+        _ => None,
+    }
+}
+
+fn statement_calls(statement: &Statement, calls: &mut Vec<String>) {
+    match statement {
+        Statement::VariableDeclaration { value, .. }
+        | Statement::ConstantDeclaration { value, .. } => expression_calls(value, calls),
+        Statement::FunctionDeclaration { body, .. } => expression_calls(body, calls),
+        Statement::Expression { expression, .. }
+        | Statement::Return {
+            value: expression, ..
+        } => expression_calls(expression, calls),
+        Statement::Import { .. } | Statement::EnumDeclaration { .. } => {}
+    }
+}
+
+fn expression_calls(expression: &Expression, calls: &mut Vec<String>) {
+    if let Expression::FunctionCall { function, .. } = expression {
+        match function.as_ref() {
+            Expression::Identifier { name, .. } => calls.push(name.clone()),
+            Expression::QualifiedIdentifier { module, name, .. } => {
+                calls.push(format!("{}.{}", module, name))
+            }
+            _ => {}
+        }
+    }
+    for child in expression.children() {
+        match child {
+            Child::Statement(statement) => statement_calls(statement, calls),
+            Child::Expression(_, expression) => expression_calls(expression, calls),
+        }
+    }
+}
+
+/// Builds the DOT source, numbering nodes in the order they are created
+struct DotGraph {
+    out: String,
+    next_id: usize,
+}
+
+impl DotGraph {
+    fn new(name: &str) -> Self {
+        Self {
+            out: format!("digraph {} {{\n    node [shape=ellipse];\n", name),
+            next_id: 0,
+        }
+    }
+
+    fn node(&mut self, label: &str, shape: &str) -> usize {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.out.push_str(&format!(
+            "    n{} [label={}, shape={}];\n",
+            id,
+            quote(label),
+            shape
+        ));
+        id
+    }
+
+    fn edge(&mut self, from: usize, to: usize, label: Option<&str>) {
+        match label {
+            Some(label) => self.out.push_str(&format!(
+                "    n{} -> n{} [label={}];\n",
+                from,
+                to,
+                quote(label)
+            )),
+            None => self.out.push_str(&format!("    n{} -> n{};\n", from, to)),
+        }
+    }
+
+    fn statement(&mut self, statement: &Statement) -> usize {
+        let (label, body) = match statement {
+            Statement::VariableDeclaration {
+                name,
+                type_annotation,
+                value,
+                ..
+            } => {
+                let label = match type_annotation {
+                    Some(ty) => format!("let {}: {}", name, type_text(ty)),
+                    None => format!("let {}", name),
+                };
+                (label, Some(value))
+            }
+            Statement::ConstantDeclaration {
+                name,
+                type_annotation,
+                value,
+                ..
+            } => {
+                let label = match type_annotation {
+                    Some(ty) => format!("const {}: {}", name, type_text(ty)),
+                    None => format!("const {}", name),
+                };
+                (label, Some(value))
+            }
+            Statement::FunctionDeclaration {
+                name,
+                param,
+                param_type,
+                return_type,
+                body,
+                ..
+            } => {
+                let mut label = format!("fn {}({}", name, param);
+                if let Some(ty) = param_type {
+                    label.push_str(&format!(": {}", type_text(ty)));
+                }
+                label.push(')');
+                if let Some(ty) = return_type {
+                    label.push_str(&format!(" -> {}", type_text(ty)));
+                }
+                (label, Some(body))
+            }
+            Statement::Import {
+                path,
+                alias,
+                exported,
+                ..
+            } => {
+                let mut label = format!("import {:?}", path);
+                if *exported {
+                    label = format!("export {}", label);
+                }
+                if let Some(alias) = alias {
+                    label.push_str(&format!(" as {}", alias));
+                }
+                (label, None)
+            }
+            Statement::EnumDeclaration { name, variants, .. } => {
+                (format!("enum {} {{ {} }}", name, variants.join(", ")), None)
+            }
+            Statement::Expression { expression, .. } => return self.expression(expression),
+            Statement::Return { value, .. } => ("return".to_string(), Some(value)),
+        };
+        let id = self.node(&label, "box");
+        if let Some(body) = body {
+            let child = self.expression(body);
+            self.edge(id, child, None);
+        }
+        id
+    }
+
+    fn expression(&mut self, expression: &Expression) -> usize {
+        let id = self.node(&expression_label(expression), "ellipse");
+        for child in expression.children() {
+            let (child_id, label) = match child {
+                Child::Statement(statement) => (self.statement(statement), None),
+                Child::Expression(label, expression) => (
+                    self.expression(expression),
+                    Some(label).filter(|l| !l.is_empty()),
+                ),
+            };
+            self.edge(id, child_id, label);
+        }
+        id
+    }
+
+    fn finish(mut self) -> String {
+        self.out.push_str("}\n");
+        self.out
+    }
+}
+
+fn expression_label(expression: &Expression) -> String {
+    match expression {
+        Expression::Identifier { name, .. } => name.clone(),
+        Expression::QualifiedIdentifier { module, name, .. } => format!("{}.{}", module, name),
+        Expression::Number { value, .. } => value.to_string(),
+        Expression::BigNumber { value, .. } => format!("{}n", value),
+        Expression::Boolean { value, .. } => value.to_string(),
+        Expression::String { value, .. } => format!("{:?}", value),
+        Expression::Bytes { value, .. } => format!("bytes ({} long)", value.len()),
+        Expression::Hole { name, .. } => hole_text(name.as_deref()),
+        Expression::BinaryOp { operator, .. } => binary_operator_text(operator).to_string(),
+        Expression::UnaryOp { operator, .. } => match operator {
+            UnaryOperator::LogicalNot => "!".to_string(),
+            UnaryOperator::Negate => "-".to_string(),
+        },
+        Expression::Function {
+            param, param_type, ..
+        } => match param_type {
+            Some(ty) => format!("fn({}: {})", param, type_text(ty)),
+            None => format!("fn({})", param),
+        },
+        Expression::FunctionCall { .. } => "call".to_string(),
+        Expression::List { .. } => "list".to_string(),
+        Expression::Pair { .. } => "pair".to_string(),
+        Expression::Tuple { .. } => "tuple".to_string(),
+        Expression::LeftInject { .. } => "inl".to_string(),
+        Expression::RightInject { .. } => "inr".to_string(),
+        Expression::Fix { .. } => "fix".to_string(),
+        Expression::Block { .. } => "block".to_string(),
+        Expression::FirstProjection { .. } => "fst".to_string(),
+        Expression::SecondProjection { .. } => "snd".to_string(),
+        Expression::TupleProjection { index, .. } => format!(".{}", index),
+        Expression::Cons { .. } => "::".to_string(),
+        Expression::HeadProjection { .. } => "head".to_string(),
+        Expression::TailProjection { .. } => "tail".to_string(),
+        Expression::Print { .. } => "print".to_string(),
+        Expression::If { .. } => "if".to_string(),
+        Expression::For { variable, .. } => format!("for {}", variable),
+        Expression::With { name, .. } => format!("with {}", name),
+        Expression::Range { .. } => "range".to_string(),
+        Expression::DivMod { .. } => "divmod".to_string(),
+        Expression::SafeDiv { .. } => "safeDiv".to_string(),
+        Expression::Concat { .. } => "concat".to_string(),
+        Expression::CharAt { .. } => "char".to_string(),
+        Expression::Length { .. } => "length".to_string(),
+        Expression::Graphemes { .. } => "graphemes".to_string(),
+        Expression::Spawn { .. } => "spawn".to_string(),
+        Expression::Join { .. } => "join".to_string(),
+        Expression::Sleep { .. } => "sleep".to_string(),
+        Expression::Random { .. } => "random".to_string(),
+        Expression::Channel { .. } => "channel".to_string(),
+        Expression::Input { .. } => "input".to_string(),
+        Expression::ReadAll { .. } => "readAll".to_string(),
+        Expression::Send { .. } => "send".to_string(),
+        Expression::Recv { .. } => "recv".to_string(),
+        Expression::ReadFileBytes { .. } => "readFileBytes".to_string(),
+        Expression::ByteAt { .. } => "byteAt".to_string(),
+        Expression::ToBytes { .. } => "toBytes".to_string(),
+        Expression::FromBytes { .. } => "fromBytes".to_string(),
+        Expression::ToInt { .. } => "toInt".to_string(),
+        Expression::ToString { .. } => "toString".to_string(),
+        Expression::TypeOf { .. } => "type".to_string(),
+        Expression::Case {
+            left_pattern,
+            right_pattern,
+            ..
+        } => format!("case inl({}) | inr({})", left_pattern, right_pattern),
+        Expression::Match { .. } => "case".to_string(),
+        Expression::TypeTest {
+            type_annotation, ..
+        } => format!("is {}", type_text(type_annotation)),
+        Expression::TypeAscription {
+            type_annotation, ..
+        } => format!(": {}", type_text(type_annotation)),
+    }
+}
+
+fn binary_operator_text(operator: &BinaryOperator) -> &'static str {
+    match operator {
+        BinaryOperator::Add => "+",
+        BinaryOperator::Subtract => "-",
+        BinaryOperator::Multiply => "*",
+        BinaryOperator::Divide => "/",
+        BinaryOperator::Assign => "=",
+        BinaryOperator::Equal => "==",
+        BinaryOperator::NotEqual => "!=",
+        BinaryOperator::LessThan => "<",
+        BinaryOperator::LessThanEqual => "<=",
+        BinaryOperator::GreaterThan => ">",
+        BinaryOperator::GreaterThanEqual => ">=",
+        BinaryOperator::LogicalAnd => "&&",
+        BinaryOperator::LogicalOr => "||",
+        BinaryOperator::ComposeForward => ">>",
+        BinaryOperator::ComposeBackward => "<<",
+        BinaryOperator::Append => "++",
+    }
+}
+
+/// Type annotation as it would be written in source
+fn type_text(ty: &TypeExpression) -> String {
+    match ty {
+        TypeExpression::Int { .. } => "Int".to_string(),
+        TypeExpression::Bool { .. } => "Bool".to_string(),
+        TypeExpression::String { .. } => "String".to_string(),
+        TypeExpression::List { element, .. } => format!("List {}", type_text(element)),
+        TypeExpression::Handle { result, .. } => format!("Handle {}", type_text(result)),
+        TypeExpression::Channel { element, .. } => format!("Channel {}", type_text(element)),
+        TypeExpression::Function { param, result, .. } => {
+            format!("({} -> {})", type_text(param), type_text(result))
+        }
+        TypeExpression::Pair { first, second, .. } => {
+            format!("({}, {})", type_text(first), type_text(second))
+        }
+        TypeExpression::Tuple { elements, .. } => {
+            let elements: Vec<String> = elements.iter().map(type_text).collect();
+            format!("({})", elements.join(", "))
+        }
+        TypeExpression::Sum { left, right, .. } => {
+            format!("({} + {})", type_text(left), type_text(right))
+        }
+        TypeExpression::Recursive { inner, .. } => format!("Rec {}", type_text(inner)),
+        TypeExpression::Named { name, .. } => name.clone(),
+    }
+}
+
+/// Quote a string as a DOT identifier
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
@@ -0,0 +1,672 @@
+//! Source formatting of syntax trees. The output uses one canonical layout and
+//! only the parentheses the grammar needs, so parsing it gives back the same
+//! tree apart from spans. Comments and blank lines are not part of the tree
+//! and are not preserved.
+
+use super::nodes::{
+    BinaryOperator, Expression, Pattern, Program, Statement, TypeExpression, UnaryOperator,
+    hole_text,
+};
+use super::parser::{Associativity, precedence};
+use crate::lexer::tokens::Token;
+
+impl Program {
+    /// Source text for the program in canonical layout
+    pub fn format(&self) -> String {
+        let mut formatter = Formatter { indent: 0 };
+        let mut out = String::new();
+        for (i, statement) in self.statements.iter().enumerate() {
+            // Function declarations are set off from their neighbours
+            let is_function = matches!(statement, Statement::FunctionDeclaration { .. });
+            let follows_function = i > 0
+                && matches!(
+                    self.statements[i - 1],
+                    Statement::FunctionDeclaration { .. }
+                );
+            if i > 0 && (is_function || follows_function) {
+                out.push('\n');
+            }
+            out.push_str(&formatter.statement(statement));
+            out.push('\n');
+        }
+        out
+    }
+}
+
+/// Where an operand sits relative to its infix operator
+#[derive(Clone, Copy, PartialEq)]
+enum Side {
+    Left,
+    Right,
+}
+
+struct Formatter {
+    indent: usize,
+}
+
+impl Formatter {
+    fn pad(&self) -> String {
+        "    ".repeat(self.indent)
+    }
+
+    fn statement(&mut self, statement: &Statement) -> String {
+        match statement {
+            Statement::VariableDeclaration {
+                name,
+                type_annotation,
+                value,
+                ..
+            } => match type_annotation {
+                Some(ty) => format!(
+                    "let {}: {} = {};",
+                    name,
+                    type_text(ty),
+                    self.expression(value)
+                ),
+                None => format!("let {} = {};", name, self.expression(value)),
+            },
+            Statement::EnumDeclaration { name, variants, .. } => {
+                format!("enum {} {{ {} }}", name, variants.join(", "))
+            }
+            Statement::ConstantDeclaration {
+                name,
+                type_annotation,
+                value,
+                ..
+            } => match type_annotation {
+                Some(ty) => format!(
+                    "const {}: {} = {};",
+                    name,
+                    type_text(ty),
+                    self.expression(value)
+                ),
+                None => format!("const {} = {};", name, self.expression(value)),
+            },
+            Statement::FunctionDeclaration {
+                name,
+                param,
+                param_type,
+                return_type,
+                body,
+                ..
+            } => {
+                let mut header = format!("fn {}({}", name, param);
+                if let Some(ty) = param_type {
+                    header.push_str(&format!(": {}", type_text(ty)));
+                }
+                header.push(')');
+                if let Some(ty) = return_type {
+                    header.push_str(&format!(" -> {}", type_text(ty)));
+                }
+                format!("{} {}", header, self.block(body, true))
+            }
+            Statement::Import {
+                path,
+                alias,
+                exported,
+                ..
+            } => {
+                let mut text = format!("import {}", string_literal(path));
+                if *exported {
+                    text = format!("export {}", text);
+                }
+                if let Some(alias) = alias {
+                    text.push_str(&format!(" as {}", alias));
+                }
+                text.push(';');
+                text
+            }
+            Statement::Expression { expression, .. } => {
+                let text = self.expression(expression);
+                // A statement starting with `fn` would be read as a declaration
+                if text.starts_with("fn(") {
+                    format!("({});", text)
+                } else {
+                    format!("{};", text)
+                }
+            }
+            Statement::Return { value, .. } => format!("return {};", self.expression(value)),
+        }
+    }
+
+    /// A braced block. Short blocks holding a single expression stay on one
+    /// line unless `multiline` is set.
+    fn block(&mut self, block: &Expression, multiline: bool) -> String {
+        let (statements, result) = match block {
+            Expression::Block {
+                statements,
+                expression,
+                ..
+            } => (statements.as_slice(), expression.as_deref()),
+            expression => (&[][..], Some(expression)),
+        };
+
+        self.indent += 1;
+        let mut lines: Vec<String> = statements
+            .iter()
+            .map(|statement| self.statement(statement))
+            .collect();
+        if let Some(result) = result {
+            lines.push(self.expression(result));
+        }
+        self.indent -= 1;
+
+        match lines.as_slice() {
+            [] => "{}".to_string(),
+            [line] if !multiline && statements.is_empty() && !line.contains('\n') => {
+                format!("{{ {} }}", line)
+            }
+            _ => {
+                let pad = format!("{}    ", self.pad());
+                let body: Vec<String> = lines
+                    .iter()
+                    .map(|line| format!("{}{}", pad, line))
+                    .collect();
+                format!("{{\n{}\n{}}}", body.join("\n"), self.pad())
+            }
+        }
+    }
+
+    /// The body of a `case` arm. A `case` in any arm but the last would take
+    /// the following arms as its own, so it is parenthesized.
+    fn case_body(&mut self, body: &Expression, last: bool) -> String {
+        let text = self.expression(body);
+        parenthesize(text, !last && matches!(binding(body), Binding::Greedy))
+    }
+
+    /// A builtin applied to its parenthesized arguments, named by its keyword
+    fn call(&mut self, builtin: Token, arguments: &[&Expression]) -> String {
+        let arguments: Vec<String> = arguments
+            .iter()
+            .map(|argument| self.expression(argument))
+            .collect();
+        let name = builtin.keyword().unwrap_or_default();
+        format!("{}({})", name, arguments.join(", "))
+    }
+
+    /// An operand of an infix operator with the given binding
+    fn operand(
+        &mut self,
+        expression: &Expression,
+        parent: (u8, Associativity),
+        side: Side,
+    ) -> String {
+        let text = self.expression(expression);
+        let needs_parens = match binding(expression) {
+            Binding::Infix(precedence, associativity) => {
+                // Equal precedence only groups without parentheses in the
+                // direction both operators associate
+                let grouped = match side {
+                    Side::Left => Associativity::Left,
+                    Side::Right => Associativity::Right,
+                };
+                precedence < parent.0
+                    || (precedence == parent.0 && (associativity != grouped || parent.1 != grouped))
+            }
+            Binding::Greedy => true,
+            Binding::Atom => false,
+        };
+        parenthesize(text, needs_parens)
+    }
+
+    /// The expression a call, projection or prefix operator applies to
+    fn postfix_operand(&mut self, expression: &Expression) -> String {
+        let text = self.expression(expression);
+        let needs_parens = !matches!(binding(expression), Binding::Atom)
+            || matches!(expression, Expression::UnaryOp { .. });
+        parenthesize(text, needs_parens)
+    }
+
+    fn expression(&mut self, expression: &Expression) -> String {
+        match expression {
+            Expression::Identifier { name, .. } => name.clone(),
+            Expression::QualifiedIdentifier { module, name, .. } => {
+                format!("{}.{}", module, name)
+            }
+            Expression::Number { value, .. } => value.to_string(),
+            Expression::BigNumber { value, .. } => format!("{}n", value),
+            Expression::Boolean { value, .. } => value.to_string(),
+            Expression::String { value, .. } => string_literal(value),
+            Expression::Bytes { value, .. } => bytes_literal(value),
+            Expression::Hole { name, .. } => hole_text(name.as_deref()),
+            Expression::BinaryOp {
+                left,
+                operator,
+                right,
+                ..
+            } => {
+                let parent = operator_binding(operator);
+                format!(
+                    "{} {} {}",
+                    self.operand(left, parent, Side::Left),
+                    operator_text(operator),
+                    self.operand(right, parent, Side::Right)
+                )
+            }
+            Expression::Cons { head, tail, .. } => {
+                let parent = (precedence::CONS, Associativity::Right);
+                format!(
+                    "{} :: {}",
+                    self.operand(head, parent, Side::Left),
+                    self.operand(tail, parent, Side::Right)
+                )
+            }
+            Expression::TypeTest {
+                expression,
+                type_annotation,
+                ..
+            } => {
+                let parent = (precedence::COMPARISON, Associativity::Left);
+                format!(
+                    "{} is {}",
+                    self.operand(expression, parent, Side::Left),
+                    type_text(type_annotation)
+                )
+            }
+            Expression::UnaryOp {
+                operator, operand, ..
+            } => {
+                let operator = match operator {
+                    UnaryOperator::LogicalNot => "!",
+                    UnaryOperator::Negate => "-",
+                };
+                let operand = match operand.as_ref() {
+                    Expression::UnaryOp { .. } => self.expression(operand),
+                    _ => self.postfix_operand(operand),
+                };
+                format!("{}{}", operator, operand)
+            }
+            Expression::Function {
+                param,
+                param_type,
+                body,
+                ..
+            } => {
+                let param = match param_type {
+                    Some(ty) => format!("{}: {}", param, type_text(ty)),
+                    None => param.clone(),
+                };
+                format!("fn({}) {}", param, self.block(body, false))
+            }
+            Expression::FunctionCall {
+                function, argument, ..
+            } => {
+                let function = self.postfix_operand(function);
+                format!("{}({})", function, self.expression(argument))
+            }
+            Expression::TupleProjection { tuple, index, .. } => {
+                let tuple = match tuple.as_ref() {
+                    // `1.0` would read as a number followed by `.0`, which is
+                    // what it means anyway, but reads badly
+                    Expression::Number { .. } => format!("({})", self.expression(tuple)),
+                    _ => self.postfix_operand(tuple),
+                };
+                format!("{}.{}", tuple, index)
+            }
+            Expression::List { elements, .. } => {
+                let elements: Vec<String> = elements
+                    .iter()
+                    .map(|element| self.expression(element))
+                    .collect();
+                format!("[{}]", elements.join(", "))
+            }
+            Expression::Pair { first, second, .. } => {
+                format!("({}, {})", self.expression(first), self.expression(second))
+            }
+            Expression::Tuple { elements, .. } => {
+                let elements: Vec<String> = elements
+                    .iter()
+                    .map(|element| self.expression(element))
+                    .collect();
+                format!("({})", elements.join(", "))
+            }
+            Expression::TypeAscription {
+                expression,
+                type_annotation,
+                ..
+            } => format!(
+                "({}: {})",
+                self.expression(expression),
+                type_text(type_annotation)
+            ),
+            Expression::Block { .. } => self.block(expression, false),
+            Expression::If {
+                condition,
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                let mut text = format!(
+                    "if {} {}",
+                    self.expression(condition),
+                    self.block(then_branch, false)
+                );
+                match else_branch.as_deref() {
+                    Some(Expression::Block {
+                        statements,
+                        expression: Some(nested),
+                        ..
+                    }) if statements.is_empty() && matches!(**nested, Expression::If { .. }) => {
+                        text.push_str(&format!(" else {}", self.expression(nested)));
+                    }
+                    Some(else_branch) => {
+                        text.push_str(&format!(" else {}", self.block(else_branch, false)));
+                    }
+                    None => {}
+                }
+                text
+            }
+            Expression::For {
+                variable,
+                iterable,
+                body,
+                ..
+            } => format!(
+                "for {} in {} {}",
+                variable,
+                self.expression(iterable),
+                self.block(body, false)
+            ),
+            Expression::With {
+                name, value, body, ..
+            } => format!(
+                "with {} = {} {}",
+                name,
+                self.expression(value),
+                self.block(body, false)
+            ),
+            Expression::Case {
+                expression: scrutinee,
+                left_pattern,
+                left_body,
+                right_pattern,
+                right_body,
+                ..
+            } => {
+                let if_let = is_if_let(left_pattern, left_body, right_pattern, right_body);
+                if if_let && right_pattern == "_" {
+                    return format!(
+                        "if let inl({}) = {} {} else {}",
+                        left_pattern,
+                        self.expression(scrutinee),
+                        self.block(left_body, false),
+                        self.block(right_body, false)
+                    );
+                }
+                if if_let {
+                    return format!(
+                        "if let inr({}) = {} {} else {}",
+                        right_pattern,
+                        self.expression(scrutinee),
+                        self.block(right_body, false),
+                        self.block(left_body, false)
+                    );
+                }
+                let scrutinee = self.expression(scrutinee);
+                let left_body = self.case_body(left_body, false);
+                format!(
+                    "case {} of inl {} => {} | inr {} => {}",
+                    scrutinee,
+                    left_pattern,
+                    left_body,
+                    right_pattern,
+                    self.expression(right_body)
+                )
+            }
+            Expression::Match {
+                expression: scrutinee,
+                arms,
+                ..
+            } => {
+                let scrutinee = self.expression(scrutinee);
+                let arms: Vec<String> = arms
+                    .iter()
+                    .enumerate()
+                    .map(|(i, arm)| {
+                        let body = self.case_body(&arm.body, i + 1 == arms.len());
+                        format!("{} => {}", pattern_text(&arm.pattern), body)
+                    })
+                    .collect();
+                format!("case {} of {}", scrutinee, arms.join(" | "))
+            }
+            Expression::LeftInject { value, .. } => self.call(Token::Inl, &[value]),
+            Expression::RightInject { value, .. } => self.call(Token::Inr, &[value]),
+            Expression::Fix { function, .. } => self.call(Token::Fix, &[function]),
+            Expression::FirstProjection { pair, .. } => self.call(Token::Fst, &[pair]),
+            Expression::SecondProjection { pair, .. } => self.call(Token::Snd, &[pair]),
+            Expression::HeadProjection { list, .. } => self.call(Token::Head, &[list]),
+            Expression::TailProjection { list, .. } => self.call(Token::Tail, &[list]),
+            Expression::Print { value, .. } => self.call(Token::Print, &[value]),
+            Expression::Range { start, end, .. } => self.call(Token::Range, &[start, end]),
+            Expression::DivMod {
+                dividend, divisor, ..
+            } => self.call(Token::DivMod, &[dividend, divisor]),
+            Expression::SafeDiv {
+                dividend, divisor, ..
+            } => self.call(Token::SafeDiv, &[dividend, divisor]),
+            Expression::Concat { left, right, .. } => self.call(Token::Concat, &[left, right]),
+            Expression::CharAt { string, index, .. } => self.call(Token::Char, &[string, index]),
+            Expression::Length { string, .. } => self.call(Token::Length, &[string]),
+            Expression::Graphemes { string, .. } => self.call(Token::Graphemes, &[string]),
+            Expression::ReadFileBytes { path, .. } => self.call(Token::ReadFileBytes, &[path]),
+            Expression::ByteAt { bytes, index, .. } => self.call(Token::ByteAt, &[bytes, index]),
+            Expression::ToBytes { string, .. } => self.call(Token::ToBytes, &[string]),
+            Expression::FromBytes { bytes, .. } => self.call(Token::FromBytes, &[bytes]),
+            Expression::ToInt { string, .. } => self.call(Token::ToInt, &[string]),
+            Expression::Spawn { function, .. } => self.call(Token::Spawn, &[function]),
+            Expression::Join { handle, .. } => self.call(Token::Join, &[handle]),
+            Expression::Sleep { milliseconds, .. } => self.call(Token::Sleep, &[milliseconds]),
+            Expression::Random { bound, .. } => self.call(Token::Random, &[bound]),
+            Expression::Channel { .. } => self.call(Token::MakeChannel, &[]),
+            Expression::Input { .. } => self.call(Token::Input, &[]),
+            Expression::ReadAll { .. } => self.call(Token::ReadAll, &[]),
+            Expression::Send { channel, value, .. } => self.call(Token::Send, &[channel, value]),
+            Expression::Recv { channel, .. } => self.call(Token::Recv, &[channel]),
+            Expression::ToString { expression, .. } => self.call(Token::ToString, &[expression]),
+            Expression::TypeOf { expression, .. } => self.call(Token::Type, &[expression]),
+        }
+    }
+}
+
+/// How an expression combines with the operators around it
+enum Binding {
+    /// An infix expression with this precedence and associativity
+    Infix(u8, Associativity),
+    /// A `case`, whose last branch extends as far to the right as it can
+    Greedy,
+    /// Anything else, which never needs parentheses as an operand
+    Atom,
+}
+
+fn binding(expression: &Expression) -> Binding {
+    match expression {
+        Expression::BinaryOp { operator, .. } => {
+            let (precedence, associativity) = operator_binding(operator);
+            Binding::Infix(precedence, associativity)
+        }
+        Expression::Cons { .. } => Binding::Infix(precedence::CONS, Associativity::Right),
+        Expression::TypeTest { .. } => Binding::Infix(precedence::COMPARISON, Associativity::Left),
+        Expression::Case {
+            left_pattern,
+            left_body,
+            right_pattern,
+            right_body,
+            ..
+        } if !is_if_let(left_pattern, left_body, right_pattern, right_body) => Binding::Greedy,
+        Expression::Match { .. } => Binding::Greedy,
+        _ => Binding::Atom,
+    }
+}
+
+/// Whether a `case` is written as the `if let` it was parsed from: its
+/// branches are blocks, which a `case` arm cannot be, and one binds `_`
+fn is_if_let(
+    left_pattern: &str,
+    left_body: &Expression,
+    right_pattern: &str,
+    right_body: &Expression,
+) -> bool {
+    matches!(left_body, Expression::Block { .. })
+        && matches!(right_body, Expression::Block { .. })
+        && (left_pattern == "_" || right_pattern == "_")
+}
+
+fn operator_binding(operator: &BinaryOperator) -> (u8, Associativity) {
+    use Associativity::{Left, Right};
+    use precedence::*;
+    match operator {
+        BinaryOperator::Add | BinaryOperator::Subtract => (ADDITIVE, Left),
+        BinaryOperator::Multiply | BinaryOperator::Divide => (MULTIPLICATIVE, Left),
+        BinaryOperator::Append => (APPEND, Left),
+        BinaryOperator::Equal
+        | BinaryOperator::NotEqual
+        | BinaryOperator::LessThan
+        | BinaryOperator::LessThanEqual
+        | BinaryOperator::GreaterThan
+        | BinaryOperator::GreaterThanEqual => (COMPARISON, Left),
+        BinaryOperator::LogicalAnd => (LOGICAL_AND, Left),
+        BinaryOperator::LogicalOr => (LOGICAL_OR, Left),
+        BinaryOperator::ComposeForward => (COMPOSITION, Left),
+        BinaryOperator::ComposeBackward => (COMPOSITION, Right),
+        // Never produced by the parser; parenthesized wherever it appears
+        BinaryOperator::Assign => (0, Left),
+    }
+}
+
+fn operator_text(operator: &BinaryOperator) -> &'static str {
+    match operator {
+        BinaryOperator::Add => "+",
+        BinaryOperator::Subtract => "-",
+        BinaryOperator::Multiply => "*",
+        BinaryOperator::Divide => "/",
+        BinaryOperator::Assign => "=",
+        BinaryOperator::Equal => "==",
+        BinaryOperator::NotEqual => "!=",
+        BinaryOperator::LessThan => "<",
+        BinaryOperator::LessThanEqual => "<=",
+        BinaryOperator::GreaterThan => ">",
+        BinaryOperator::GreaterThanEqual => ">=",
+        BinaryOperator::LogicalAnd => "&&",
+        BinaryOperator::LogicalOr => "||",
+        BinaryOperator::ComposeForward => ">>",
+        BinaryOperator::ComposeBackward => "<<",
+        BinaryOperator::Append => "++",
+    }
+}
+
+fn parenthesize(text: String, needs_parens: bool) -> String {
+    if needs_parens {
+        format!("({})", text)
+    } else {
+        text
+    }
+}
+
+fn pattern_text(pattern: &Pattern) -> String {
+    match pattern {
+        Pattern::Wildcard { .. } => "_".to_string(),
+        Pattern::Variant {
+            enum_name, variant, ..
+        } => format!("{}.{}", enum_name, variant),
+        Pattern::String { value, .. } => string_literal(value),
+        Pattern::Int { value, .. } => value.to_string(),
+        Pattern::Range { start, end, .. } => format!(
+            "{}..{}",
+            start.map(|start| start.to_string()).unwrap_or_default(),
+            end.map(|end| end.to_string()).unwrap_or_default()
+        ),
+    }
+}
+
+fn type_text(ty: &TypeExpression) -> String {
+    type_operand(ty, false)
+}
+
+/// A type, parenthesized where needed. `List`, `Handle`, `Channel` and `Rec`
+/// take everything to their right as their argument, so they are wrapped when
+/// `followed` says more of the enclosing type comes after them.
+fn type_operand(ty: &TypeExpression, followed: bool) -> String {
+    match ty {
+        TypeExpression::Int { .. } => "Int".to_string(),
+        TypeExpression::Bool { .. } => "Bool".to_string(),
+        TypeExpression::String { .. } => "String".to_string(),
+        TypeExpression::Named { name, .. } => name.clone(),
+        TypeExpression::List { element: inner, .. }
+        | TypeExpression::Handle { result: inner, .. }
+        | TypeExpression::Channel { element: inner, .. }
+        | TypeExpression::Recursive { inner, .. } => {
+            let constructor = match ty {
+                TypeExpression::List { .. } => "List",
+                TypeExpression::Handle { .. } => "Handle",
+                TypeExpression::Channel { .. } => "Channel",
+                _ => "Rec",
+            };
+            parenthesize(
+                format!("{} {}", constructor, type_operand(inner, false)),
+                followed,
+            )
+        }
+        // Both `->` and `+` group to the left
+        TypeExpression::Function { param, result, .. } => {
+            let result = match result.as_ref() {
+                TypeExpression::Function { .. } => format!("({})", type_text(result)),
+                _ => type_operand(result, followed),
+            };
+            format!("{} -> {}", type_operand(param, true), result)
+        }
+        TypeExpression::Sum { left, right, .. } => {
+            let left = match left.as_ref() {
+                TypeExpression::Function { .. } => format!("({})", type_text(left)),
+                _ => type_operand(left, true),
+            };
+            let right = match right.as_ref() {
+                TypeExpression::Function { .. } | TypeExpression::Sum { .. } => {
+                    format!("({})", type_text(right))
+                }
+                _ => type_operand(right, followed),
+            };
+            format!("{} + {}", left, right)
+        }
+        TypeExpression::Pair { first, second, .. } => {
+            format!("({}, {})", type_text(first), type_text(second))
+        }
+        TypeExpression::Tuple { elements, .. } => {
+            let elements: Vec<String> = elements.iter().map(type_text).collect();
+            format!("({})", elements.join(", "))
+        }
+    }
+}
+
+fn string_literal(value: &str) -> String {
+    let mut literal = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => literal.push_str("\\\""),
+            '\\' => literal.push_str("\\\\"),
+            '\n' => literal.push_str("\\n"),
+            '\r' => literal.push_str("\\r"),
+            '\t' => literal.push_str("\\t"),
+            c => literal.push(c),
+        }
+    }
+    literal.push('"');
+    literal
+}
+
+fn bytes_literal(bytes: &[u8]) -> String {
+    let mut literal = String::from("b\"");
+    for &byte in bytes {
+        match byte {
+            b'"' => literal.push_str("\\\""),
+            b'\\' => literal.push_str("\\\\"),
+            b'\n' => literal.push_str("\\n"),
+            b'\r' => literal.push_str("\\r"),
+            b'\t' => literal.push_str("\\t"),
+            b'\0' => literal.push_str("\\0"),
+            0x20..=0x7e => literal.push(byte as char),
+            _ => literal.push_str(&format!("\\x{:02x}", byte)),
+        }
+    }
+    literal.push('"');
+    literal
+}
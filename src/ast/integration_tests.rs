@@ -4,8 +4,7 @@ use crate::lexer::Tokenizer;
 #[test]
 fn test_end_to_end_parsing() {
     // Test 1: Simple number expression
-    let mut tokenizer = Tokenizer::new("");
-    let tokens = tokenizer.tokenize("42;").unwrap();
+    let tokens = Tokenizer::new("42;").tokenize().unwrap();
     let mut parser = Parser::new(tokens);
     let program = parser.parse().unwrap();
 
@@ -13,8 +12,7 @@ fn test_end_to_end_parsing() {
     println!("Test 1 passed: {:#?}", program);
 
     // Test 2: Variable declaration
-    let mut tokenizer = Tokenizer::new("");
-    let tokens = tokenizer.tokenize("let x = 42;").unwrap();
+    let tokens = Tokenizer::new("let x = 42;").tokenize().unwrap();
     let mut parser = Parser::new(tokens);
     let program = parser.parse().unwrap();
 
@@ -22,8 +20,7 @@ fn test_end_to_end_parsing() {
     println!("Test 2 passed: {:#?}", program);
 
     // Test 3: Multiple statements
-    let mut tokenizer = Tokenizer::new("");
-    let tokens = tokenizer.tokenize("let x = 42; y;").unwrap();
+    let tokens = Tokenizer::new("let x = 42; y;").tokenize().unwrap();
     let mut parser = Parser::new(tokens);
     let program = parser.parse().unwrap();
 
@@ -34,8 +31,7 @@ fn test_end_to_end_parsing() {
 #[test]
 fn test_list_parsing_integration() {
     // Test 1: Empty list
-    let mut tokenizer = Tokenizer::new("");
-    let tokens = tokenizer.tokenize("[];").unwrap();
+    let tokens = Tokenizer::new("[];").tokenize().unwrap();
     let mut parser = Parser::new(tokens);
     let program = parser.parse().unwrap();
 
@@ -43,8 +39,7 @@ fn test_list_parsing_integration() {
     println!("List Test 1 passed: {:#?}", program);
 
     // Test 2: List with numbers
-    let mut tokenizer = Tokenizer::new("");
-    let tokens = tokenizer.tokenize("[1, 2, 3];").unwrap();
+    let tokens = Tokenizer::new("[1, 2, 3];").tokenize().unwrap();
     let mut parser = Parser::new(tokens);
     let program = parser.parse().unwrap();
 
@@ -52,8 +47,7 @@ fn test_list_parsing_integration() {
     println!("List Test 2 passed: {:#?}", program);
 
     // Test 3: List with trailing comma
-    let mut tokenizer = Tokenizer::new("");
-    let tokens = tokenizer.tokenize("[42,];").unwrap();
+    let tokens = Tokenizer::new("[42,];").tokenize().unwrap();
     let mut parser = Parser::new(tokens);
     let program = parser.parse().unwrap();
 
@@ -61,9 +55,8 @@ fn test_list_parsing_integration() {
     println!("List Test 3 passed: {:#?}", program);
 
     // Test 4: List variable declaration
-    let mut tokenizer = Tokenizer::new("");
-    let tokens = tokenizer
-        .tokenize("let numbers: List Int = [1, 2, 3];")
+    let tokens = Tokenizer::new("let numbers: List Int = [1, 2, 3];")
+        .tokenize()
         .unwrap();
     let mut parser = Parser::new(tokens);
     let program = parser.parse().unwrap();
@@ -75,8 +68,7 @@ fn test_list_parsing_integration() {
 #[test]
 fn test_list_operations_integration() {
     // Test 1: cons operation
-    let mut tokenizer = Tokenizer::new("");
-    let tokens = tokenizer.tokenize("cons(1, [2, 3]);").unwrap();
+    let tokens = Tokenizer::new("cons(1, [2, 3]);").tokenize().unwrap();
     let mut parser = Parser::new(tokens);
     let program = parser.parse().unwrap();
 
@@ -84,8 +76,7 @@ fn test_list_operations_integration() {
     println!("List Op Test 1 (cons) passed: {:#?}", program);
 
     // Test 2: head operation
-    let mut tokenizer = Tokenizer::new("");
-    let tokens = tokenizer.tokenize("head([1, 2, 3]);").unwrap();
+    let tokens = Tokenizer::new("head([1, 2, 3]);").tokenize().unwrap();
     let mut parser = Parser::new(tokens);
     let program = parser.parse().unwrap();
 
@@ -93,8 +84,7 @@ fn test_list_operations_integration() {
     println!("List Op Test 2 (head) passed: {:#?}", program);
 
     // Test 3: tail operation
-    let mut tokenizer = Tokenizer::new("");
-    let tokens = tokenizer.tokenize("tail([1, 2, 3]);").unwrap();
+    let tokens = Tokenizer::new("tail([1, 2, 3]);").tokenize().unwrap();
     let mut parser = Parser::new(tokens);
     let program = parser.parse().unwrap();
 
@@ -102,9 +92,8 @@ fn test_list_operations_integration() {
     println!("List Op Test 3 (tail) passed: {:#?}", program);
 
     // Test 4: nested list operations
-    let mut tokenizer = Tokenizer::new("");
-    let tokens = tokenizer
-        .tokenize("cons(head([1, 2]), tail([3, 4, 5]));")
+    let tokens = Tokenizer::new("cons(head([1, 2]), tail([3, 4, 5]));")
+        .tokenize()
         .unwrap();
     let mut parser = Parser::new(tokens);
     let program = parser.parse().unwrap();
@@ -113,9 +102,8 @@ fn test_list_operations_integration() {
     println!("List Op Test 4 (nested) passed: {:#?}", program);
 
     // Test 5: list operations in variable declarations
-    let mut tokenizer = Tokenizer::new("");
-    let tokens = tokenizer
-        .tokenize("let first_elem = head(numbers);")
+    let tokens = Tokenizer::new("let first_elem = head(numbers);")
+        .tokenize()
         .unwrap();
     let mut parser = Parser::new(tokens);
     let program = parser.parse().unwrap();
@@ -123,3 +111,466 @@ fn test_list_operations_integration() {
     assert_eq!(program.statements.len(), 1);
     println!("List Op Test 5 (variable decl) passed: {:#?}", program);
 }
+
+fn parse_single_expression(source: &str) -> crate::ast::Expression {
+    let tokens = Tokenizer::new(source).tokenize().unwrap();
+    let mut parser = Parser::new(tokens);
+    let program = parser.parse().unwrap();
+    match program.statements.into_iter().next() {
+        Some(crate::ast::Statement::Expression { expression, .. }) => expression,
+        other => panic!("Expected expression statement, found {:?}", other),
+    }
+}
+
+#[test]
+fn test_mixed_comparison_and_logical_precedence() {
+    use crate::ast::{BinaryOperator, Expression};
+
+    // Comparisons bind tighter than &&, which binds tighter than ||
+    let expr = parse_single_expression("a < b && c == d || e;");
+    let Expression::BinaryOp { left, operator, .. } = expr else {
+        panic!("Expected binary operation");
+    };
+    assert_eq!(operator, BinaryOperator::LogicalOr);
+
+    let Expression::BinaryOp {
+        left: and_left,
+        operator: and_operator,
+        right: and_right,
+        ..
+    } = *left
+    else {
+        panic!("Expected && on the left of ||");
+    };
+    assert_eq!(and_operator, BinaryOperator::LogicalAnd);
+    assert!(matches!(
+        *and_left,
+        Expression::BinaryOp {
+            operator: BinaryOperator::LessThan,
+            ..
+        }
+    ));
+    assert!(matches!(
+        *and_right,
+        Expression::BinaryOp {
+            operator: BinaryOperator::Equal,
+            ..
+        }
+    ));
+}
+
+#[test]
+fn test_application_binds_tighter_than_unary_operators() {
+    use crate::ast::{Expression, UnaryOperator};
+
+    let expr = parse_single_expression("-f(x);");
+    match expr {
+        Expression::UnaryOp {
+            operator: UnaryOperator::Negate,
+            operand,
+            ..
+        } => assert!(matches!(*operand, Expression::FunctionCall { .. })),
+        other => panic!("Expected negated call, found {:?}", other),
+    }
+
+    let expr = parse_single_expression("!is_empty(xs) && ready;");
+    match expr {
+        Expression::BinaryOp { left, .. } => assert!(matches!(
+            *left,
+            Expression::UnaryOp {
+                operator: UnaryOperator::LogicalNot,
+                ..
+            }
+        )),
+        other => panic!("Expected && expression, found {:?}", other),
+    }
+}
+
+#[test]
+fn test_operator_associativity() {
+    use crate::ast::{BinaryOperator, Expression};
+
+    // Arithmetic is left-associative: (a - b) - c
+    match parse_single_expression("a - b - c;") {
+        Expression::BinaryOp { left, right, .. } => {
+            assert!(matches!(*left, Expression::BinaryOp { .. }));
+            assert!(matches!(*right, Expression::Identifier { .. }));
+        }
+        other => panic!("Expected binary operation, found {:?}", other),
+    }
+
+    // Backward composition is right-associative: f << (g << h)
+    match parse_single_expression("f << g << h;") {
+        Expression::BinaryOp {
+            left,
+            operator,
+            right,
+            ..
+        } => {
+            assert_eq!(operator, BinaryOperator::ComposeBackward);
+            assert!(matches!(*left, Expression::Identifier { .. }));
+            assert!(matches!(*right, Expression::BinaryOp { .. }));
+        }
+        other => panic!("Expected binary operation, found {:?}", other),
+    }
+}
+
+#[test]
+fn test_cons_operator_is_right_associative() {
+    use crate::ast::Expression;
+
+    // 1 :: 2 :: [] parses as cons(1, cons(2, []))
+    match parse_single_expression("1 :: 2 :: [];") {
+        Expression::Cons { head, tail, .. } => {
+            assert!(matches!(*head, Expression::Number { value: 1, .. }));
+            match *tail {
+                Expression::Cons { head, tail, .. } => {
+                    assert!(matches!(*head, Expression::Number { value: 2, .. }));
+                    assert!(matches!(*tail, Expression::List { .. }));
+                }
+                other => panic!("Expected nested cons, found {:?}", other),
+            }
+        }
+        other => panic!("Expected cons expression, found {:?}", other),
+    }
+
+    // Arithmetic binds tighter than ::, comparisons looser
+    match parse_single_expression("x + 1 :: xs == ys;") {
+        Expression::BinaryOp { left, .. } => match *left {
+            Expression::Cons { head, .. } => {
+                assert!(matches!(*head, Expression::BinaryOp { .. }))
+            }
+            other => panic!("Expected cons on the left of ==, found {:?}", other),
+        },
+        other => panic!("Expected comparison, found {:?}", other),
+    }
+}
+
+#[test]
+fn test_if_let_parses_as_case() {
+    use crate::ast::Expression;
+
+    match parse_single_expression("if let inr(err) = result { err } else { 0 };") {
+        Expression::Case {
+            left_pattern,
+            right_pattern,
+            right_body,
+            ..
+        } => {
+            assert_eq!(left_pattern, "_");
+            assert_eq!(right_pattern, "err");
+            assert!(matches!(*right_body, Expression::Block { .. }));
+        }
+        other => panic!("Expected case expression, found {:?}", other),
+    }
+}
+
+#[test]
+fn test_if_let_binds_nothing_with_an_underscore() {
+    use crate::ast::Expression;
+
+    match parse_single_expression("if let inl(_) = result { 1 } else { 0 };") {
+        Expression::Case {
+            left_pattern,
+            right_pattern,
+            ..
+        } => assert_eq!((left_pattern.as_str(), right_pattern.as_str()), ("_", "_")),
+        other => panic!("Expected case expression, found {:?}", other),
+    }
+}
+
+#[test]
+fn test_type_ascription_parsing() {
+    use crate::ast::{Expression, TypeExpression};
+
+    match parse_single_expression("([] : List Int);") {
+        Expression::TypeAscription {
+            expression,
+            type_annotation,
+            ..
+        } => {
+            assert!(matches!(*expression, Expression::List { .. }));
+            assert!(matches!(type_annotation, TypeExpression::List { .. }));
+        }
+        other => panic!("Expected type ascription, found {:?}", other),
+    }
+}
+
+#[test]
+fn test_dot_export() {
+    let tokens = Tokenizer::new(
+        "fn double(x: Int) -> Int { x * 2 }
+             fn quad(x: Int) -> Int { double(double(x)) }
+             fn apply(f: Int -> Int) -> Int { f(1) }
+             print(quad(strings.parse(\"2\")));",
+    )
+    .tokenize()
+    .unwrap();
+    let program = Parser::new(tokens).parse().unwrap();
+
+    let ast = program.to_ast_dot();
+    assert!(ast.starts_with("digraph ast {"));
+    assert!(ast.contains("n1 [label=\"fn double(x: Int) -> Int\", shape=box];"));
+    assert!(ast.contains("[label=\"\\\"2\\\"\", shape=ellipse];"));
+
+    let calls = program.to_call_graph_dot();
+    assert!(calls.contains("\"quad\" -> \"double\";"));
+    assert!(calls.contains("\"<top level>\" -> \"quad\";"));
+    assert!(calls.contains("\"strings.parse\" [style=dashed];"));
+    assert!(calls.contains("\"<top level>\" -> \"strings.parse\";"));
+    // Calling a parameter is not an edge to a named function
+    assert!(!calls.contains("\"apply\" ->"));
+}
+
+#[test]
+fn test_case_parsing_with_nested_cases_and_either_branch_order() {
+    use crate::ast::{Expression, Spanned};
+
+    let source = "case a of inl x => case x of inl y => y | inr z => 0 | inr w => 1;";
+    match parse_single_expression(source) {
+        Expression::Case {
+            left_pattern,
+            left_body,
+            right_pattern,
+            span,
+            ..
+        } => {
+            assert_eq!((left_pattern.as_str(), right_pattern.as_str()), ("x", "w"));
+            // The nested case takes exactly two branches, leaving `| inr w` to the outer one
+            match *left_body {
+                Expression::Case {
+                    left_pattern,
+                    right_pattern,
+                    ..
+                } => assert_eq!((left_pattern.as_str(), right_pattern.as_str()), ("y", "z")),
+                other => panic!("Expected nested case expression, found {:?}", other),
+            }
+            assert_eq!((span.column, span.end_column), (1, source.len()));
+        }
+        other => panic!("Expected case expression, found {:?}", other),
+    }
+
+    match parse_single_expression("case r of inr e => e | inl _ => 0;") {
+        Expression::Case {
+            left_pattern,
+            right_pattern,
+            right_body,
+            ..
+        } => {
+            assert_eq!((left_pattern.as_str(), right_pattern.as_str()), ("_", "e"));
+            assert_eq!(right_body.span().column, 20);
+        }
+        other => panic!("Expected case expression, found {:?}", other),
+    }
+
+    let tokens = Tokenizer::new("case r of inl a => 1 | inl b => 2;")
+        .tokenize()
+        .unwrap();
+    assert!(Parser::new(tokens).parse().is_err());
+}
+
+#[test]
+fn test_if_parsing_with_else_if_chains_and_nested_ifs() {
+    use crate::ast::Expression;
+
+    // `else if` is an else block holding only the nested if
+    let source = "if a { 1 } else if b { 2 } else { 3 };";
+    match parse_single_expression(source) {
+        Expression::If {
+            else_branch: Some(else_branch),
+            span,
+            ..
+        } => {
+            match *else_branch {
+                Expression::Block {
+                    statements,
+                    expression: Some(nested),
+                    ..
+                } => {
+                    assert!(statements.is_empty());
+                    assert!(matches!(
+                        *nested,
+                        Expression::If {
+                            else_branch: Some(_),
+                            ..
+                        }
+                    ));
+                }
+                other => panic!("Expected else block, found {:?}", other),
+            }
+            assert_eq!((span.column, span.end_column), (1, source.len()));
+        }
+        other => panic!("Expected if expression, found {:?}", other),
+    }
+
+    // The else belongs to the if whose block closes just before it
+    match parse_single_expression("if a { if b { 1 } else { 2 } };") {
+        Expression::If {
+            then_branch,
+            else_branch: None,
+            ..
+        } => match *then_branch {
+            Expression::Block {
+                expression: Some(inner),
+                ..
+            } => assert!(matches!(
+                *inner,
+                Expression::If {
+                    else_branch: Some(_),
+                    ..
+                }
+            )),
+            other => panic!("Expected then block, found {:?}", other),
+        },
+        other => panic!("Expected if expression, found {:?}", other),
+    }
+
+    let tokens = Tokenizer::new("if a { 1 } else 2;").tokenize().unwrap();
+    assert!(Parser::new(tokens).parse().is_err());
+}
+
+#[test]
+fn test_builtin_forms_parse_to_their_nodes() {
+    use crate::ast::{Expression, Spanned};
+
+    let cases = [
+        ("for i in range(0, 3) { print(i); };", "For"),
+        ("range(1, 5);", "Range"),
+        ("concat(\"a\", \"b\");", "Concat"),
+        ("char(\"abc\", 1);", "CharAt"),
+        ("length(\"abc\");", "Length"),
+        ("toString(5);", "ToString"),
+        ("type(5);", "TypeOf"),
+        ("print(\"hi\");", "Print"),
+    ];
+    for (source, expected) in cases {
+        let expression = parse_single_expression(source);
+        let kind = match &expression {
+            Expression::For { iterable, .. } => {
+                assert!(matches!(**iterable, Expression::Range { .. }));
+                "For"
+            }
+            Expression::Range { .. } => "Range",
+            Expression::Concat { .. } => "Concat",
+            Expression::CharAt { .. } => "CharAt",
+            Expression::Length { .. } => "Length",
+            Expression::ToString { .. } => "ToString",
+            Expression::TypeOf { .. } => "TypeOf",
+            Expression::Print { .. } => "Print",
+            other => panic!("Unexpected expression for {}: {:?}", source, other),
+        };
+        assert_eq!(kind, expected, "{}", source);
+        // Each form spans everything before the ';'
+        assert_eq!(expression.span().end_column, source.len(), "{}", source);
+    }
+
+    for source in ["range(1);", "char(\"abc\");", "concat(\"a\");", "length();"] {
+        let tokens = Tokenizer::new(source).tokenize().unwrap();
+        assert!(Parser::new(tokens).parse().is_err(), "{}", source);
+    }
+}
+
+#[test]
+fn test_sum_injection_and_fix_parsing() {
+    use crate::ast::Expression;
+
+    match parse_single_expression("inl(inr(1 + 2));") {
+        Expression::LeftInject { value, span } => {
+            match *value {
+                Expression::RightInject { value, .. } => {
+                    assert!(matches!(*value, Expression::BinaryOp { .. }))
+                }
+                other => panic!("Expected inr expression, found {:?}", other),
+            }
+            assert_eq!((span.column, span.end_column), (1, 16));
+        }
+        other => panic!("Expected inl expression, found {:?}", other),
+    }
+
+    match parse_single_expression("fix(fn(f) { fn(n) { n } })(3);") {
+        Expression::FunctionCall { function, .. } => match *function {
+            Expression::Fix { function, .. } => {
+                assert!(matches!(*function, Expression::Function { .. }))
+            }
+            other => panic!("Expected fix expression, found {:?}", other),
+        },
+        other => panic!("Expected call of fix, found {:?}", other),
+    }
+
+    for source in ["inl 1;", "inr();", "fix(fn(f) { f };"] {
+        let tokens = Tokenizer::new(source).tokenize().unwrap();
+        assert!(Parser::new(tokens).parse().is_err(), "{}", source);
+    }
+}
+
+#[test]
+fn test_function_body_statements_and_result() {
+    use crate::ast::{Expression, Statement};
+
+    let source = "(fn(x) { let y = x; fn twice(n) { n * 2 } print(y); fn(z) { z } });";
+    match parse_single_expression(source) {
+        Expression::Function { body, .. } => match *body {
+            Expression::Block {
+                statements,
+                expression: Some(result),
+                ..
+            } => {
+                assert!(matches!(
+                    statements.as_slice(),
+                    [
+                        Statement::VariableDeclaration { .. },
+                        Statement::FunctionDeclaration { .. },
+                        Statement::Expression { .. },
+                    ]
+                ));
+                // An anonymous function is an expression, not a declaration
+                assert!(matches!(*result, Expression::Function { .. }));
+            }
+            other => panic!("Expected block body, found {:?}", other),
+        },
+        other => panic!("Expected function expression, found {:?}", other),
+    }
+
+    // A mistake inside a statement is reported where it is, not as a
+    // statement that cannot start an expression
+    let tokens = Tokenizer::new("let f = fn(x) { let y = ; y };")
+        .tokenize()
+        .unwrap();
+    let error = Parser::new(tokens).parse().unwrap_err().to_string();
+    assert!(error.contains("column 25"), "{}", error);
+    assert!(!error.contains("found Let"), "{}", error);
+}
+
+#[test]
+fn test_return_is_only_parsed_in_function_bodies() {
+    use crate::ast::{Expression, Statement};
+
+    match parse_single_expression("(fn(x) { if x { return 1; }; return 2; });") {
+        Expression::Function { body, .. } => match *body {
+            Expression::Block {
+                statements,
+                expression: None,
+                ..
+            } => assert!(matches!(
+                statements.as_slice(),
+                [Statement::Expression { .. }, Statement::Return { .. }]
+            )),
+            other => panic!("Expected block body, found {:?}", other),
+        },
+        other => panic!("Expected function expression, found {:?}", other),
+    }
+
+    for source in [
+        "return 1;",
+        "if true { return 1; };",
+        "fn f(x) { x } return 1;",
+    ] {
+        let tokens = Tokenizer::new(source).tokenize().unwrap();
+        let error = Parser::new(tokens).parse().unwrap_err().to_string();
+        assert!(
+            error.contains("'return' can only be used inside a function body"),
+            "{}",
+            error
+        );
+    }
+}
@@ -1,3 +1,5 @@
+pub mod dot;
+pub mod format;
 pub mod nodes;
 pub mod parser;
 
@@ -1,6 +1,14 @@
 use crate::lexer::tokens::{Span, Token};
 
+/// Identifies a name in a parsed program: a place that binds a variable, or
+/// an identifier referring to one. The parser numbers them from 1 in source
+/// order, so the same source always gets the same ids; nodes built by hand
+/// have the default id 0.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodeId(pub u32);
+
 #[derive(Debug, Clone, PartialEq)]
+#[allow(clippy::large_enum_variant)]
 pub enum AstNode {
     Program(Program),
     Statement(Statement),
@@ -23,6 +31,14 @@ pub enum TypeExpression {
         element: Box<TypeExpression>,
         span: Span,
     },
+    Handle {
+        result: Box<TypeExpression>,
+        span: Span,
+    },
+    Channel {
+        element: Box<TypeExpression>,
+        span: Span,
+    },
     Function {
         param: Box<TypeExpression>,
         result: Box<TypeExpression>,
@@ -33,6 +49,10 @@ pub enum TypeExpression {
         second: Box<TypeExpression>,
         span: Span,
     },
+    Tuple {
+        elements: Vec<TypeExpression>,
+        span: Span,
+    },
     Sum {
         left: Box<TypeExpression>,
         right: Box<TypeExpression>,
@@ -48,6 +68,18 @@ pub enum TypeExpression {
     },
 }
 
+/// How the hole named `name`, if it has a name, is written: `?` or `??name`
+pub fn hole_text(name: Option<&str>) -> String {
+    match name {
+        Some(name) => format!("??{}", name),
+        None => "?".to_string(),
+    }
+}
+
+/// Function the command line runs, with the program arguments, after a file
+/// has been loaded. Imported modules never have their `main` run.
+pub const ENTRY_POINT: &str = "main";
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Program {
     pub statements: Vec<Statement>,
@@ -66,6 +98,23 @@ pub enum Statement {
         name: String,
         type_annotation: Option<TypeExpression>,
         value: Expression,
+        id: NodeId,
+        span: Span,
+    },
+    /// `const N = 1024;`, evaluated by the type checker. Only allowed at the
+    /// top level of a program.
+    ConstantDeclaration {
+        name: String,
+        type_annotation: Option<TypeExpression>,
+        value: Expression,
+        id: NodeId,
+        span: Span,
+    },
+    /// `enum Color { Red, Green, Blue }`, a namespace of tagged constants.
+    /// Only allowed at the top level of a program.
+    EnumDeclaration {
+        name: String,
+        variants: Vec<String>,
         span: Span,
     },
     FunctionDeclaration {
@@ -74,26 +123,82 @@ pub enum Statement {
         param_type: Option<TypeExpression>,
         return_type: Option<TypeExpression>,
         body: Expression,
+        /// Ids of the function's name and of its parameter
+        id: NodeId,
+        param_id: NodeId,
         span: Span,
     },
     Import {
         path: String,
         alias: Option<String>, // Optional alias for the imported module
+        exported: bool,        // `export import` makes it part of this module's exports
         span: Span,
     },
     Expression {
         expression: Expression,
         span: Span,
     },
+    /// `return value;`, leaving the innermost enclosing function with
+    /// `value`. Only allowed inside a function body.
+    Return {
+        value: Expression,
+        span: Span,
+    },
+}
+
+/// One `pattern => body` arm of a `case` on values
+#[derive(Debug, Clone, PartialEq)]
+pub struct CaseArm {
+    pub pattern: Pattern,
+    pub body: Expression,
+}
+
+/// What a `case` arm matches
+#[derive(Debug, Clone, PartialEq)]
+pub enum Pattern {
+    /// `_`, matching any value
+    Wildcard { span: Span },
+    /// `Color.Red`, matching one variant of an enum
+    Variant {
+        enum_name: String,
+        variant: String,
+        span: Span,
+    },
+    /// `"quit"`, matching a string equal to the literal
+    String { value: String, span: Span },
+    /// `0` or `-1`, matching one integer
+    Int { value: i64, span: Span },
+    /// `1..10`, matching the integers from `start` up to but not including
+    /// `end`. A missing bound leaves that side of the range open.
+    Range {
+        start: Option<i64>,
+        end: Option<i64>,
+        span: Span,
+    },
+}
+
+impl Pattern {
+    /// Short text for the pattern, used to label the arm's body
+    pub fn label(&self) -> &str {
+        match self {
+            Pattern::Wildcard { .. } => "_",
+            Pattern::Variant { variant, .. } => variant,
+            Pattern::String { value, .. } => value,
+            Pattern::Int { .. } => "int",
+            Pattern::Range { .. } => "range",
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Expression {
     Identifier {
         name: String,
+        id: NodeId,
         span: Span,
     },
     QualifiedIdentifier {
+        /// Module path, with nested modules separated by '.' (`utils.strings`)
         module: String,
         name: String,
         span: Span,
@@ -102,6 +207,10 @@ pub enum Expression {
         value: i64,
         span: Span,
     },
+    BigNumber {
+        value: num_bigint::BigInt,
+        span: Span,
+    },
     Boolean {
         value: bool,
         span: Span,
@@ -123,8 +232,10 @@ pub enum Expression {
     },
     Function {
         param: String, // Parameter name
-        param_type: Option<TypeExpression>,
+        /// Boxed so that the id does not make every expression bigger
+        param_type: Option<Box<TypeExpression>>,
         body: Box<Expression>,
+        param_id: NodeId,
         span: Span,
     },
     FunctionCall {
@@ -141,6 +252,11 @@ pub enum Expression {
         second: Box<Expression>,
         span: Span,
     },
+    // Tuples with three or more elements
+    Tuple {
+        elements: Vec<Expression>,
+        span: Span,
+    },
     // Sum type constructors
     LeftInject {
         value: Box<Expression>,
@@ -170,6 +286,12 @@ pub enum Expression {
         pair: Box<Expression>,
         span: Span,
     },
+    // Positional projection of pairs and tuples (t.0, t.1, ...)
+    TupleProjection {
+        tuple: Box<Expression>,
+        index: usize,
+        span: Span,
+    },
     // List operations
     Cons {
         head: Box<Expression>,
@@ -201,6 +323,16 @@ pub enum Expression {
         variable: String,
         iterable: Box<Expression>,
         body: Box<Expression>,
+        id: NodeId,
+        span: Span,
+    },
+    /// `with name = value { body }`: runs the body, then releases the
+    /// threads and channels held by the value
+    With {
+        name: String,
+        value: Box<Expression>,
+        body: Box<Expression>,
+        id: NodeId,
         span: Span,
     },
     Range {
@@ -208,6 +340,17 @@ pub enum Expression {
         end: Box<Expression>,
         span: Span,
     },
+    // Integer division
+    DivMod {
+        dividend: Box<Expression>,
+        divisor: Box<Expression>,
+        span: Span,
+    },
+    SafeDiv {
+        dividend: Box<Expression>,
+        divisor: Box<Expression>,
+        span: Span,
+    },
     // String operations
     Concat {
         left: Box<Expression>,
@@ -223,10 +366,81 @@ pub enum Expression {
         string: Box<Expression>,
         span: Span,
     },
+    Graphemes {
+        string: Box<Expression>,
+        span: Span,
+    },
+    Bytes {
+        value: Vec<u8>,
+        span: Span,
+    },
+    /// `?` or `??name`: a part of the program still to be written. The type
+    /// checker reports the type expected there; running it is an error.
+    Hole {
+        name: Option<String>,
+        span: Span,
+    },
+    Spawn {
+        function: Box<Expression>,
+        span: Span,
+    },
+    Join {
+        handle: Box<Expression>,
+        span: Span,
+    },
+    Sleep {
+        milliseconds: Box<Expression>,
+        span: Span,
+    },
+    Random {
+        bound: Box<Expression>,
+        span: Span,
+    },
+    Channel {
+        span: Span,
+    },
+    /// `input()`: the next line of standard input, or `inr(())` at its end
+    Input {
+        span: Span,
+    },
+    /// `readAll()`: the rest of standard input
+    ReadAll {
+        span: Span,
+    },
+    Send {
+        channel: Box<Expression>,
+        value: Box<Expression>,
+        span: Span,
+    },
+    Recv {
+        channel: Box<Expression>,
+        span: Span,
+    },
+    ReadFileBytes {
+        path: Box<Expression>,
+        span: Span,
+    },
+    ByteAt {
+        bytes: Box<Expression>,
+        index: Box<Expression>,
+        span: Span,
+    },
+    ToBytes {
+        string: Box<Expression>,
+        span: Span,
+    },
+    FromBytes {
+        bytes: Box<Expression>,
+        span: Span,
+    },
     ToString {
         expression: Box<Expression>,
         span: Span,
     },
+    ToInt {
+        string: Box<Expression>,
+        span: Span,
+    },
     TypeOf {
         expression: Box<Expression>,
         span: Span,
@@ -238,8 +452,145 @@ pub enum Expression {
         left_body: Box<Expression>,
         right_pattern: String,
         right_body: Box<Expression>,
+        left_id: NodeId,
+        right_id: NodeId,
         span: Span,
     },
+    /// `case e of Color.Red => a | _ => b`: the first arm whose pattern
+    /// matches the value of `e` is taken
+    Match {
+        expression: Box<Expression>,
+        arms: Vec<CaseArm>,
+        span: Span,
+    },
+    // Runtime type test (expr is Type)
+    TypeTest {
+        expression: Box<Expression>,
+        type_annotation: TypeExpression,
+        span: Span,
+    },
+    // Explicit type ascription (expr : Type)
+    TypeAscription {
+        expression: Box<Expression>,
+        type_annotation: TypeExpression,
+        span: Span,
+    },
+}
+
+/// A statement or sub-expression directly inside an expression. Expressions
+/// carry a label naming their role in the parent, empty when it is obvious.
+pub enum Child<'a> {
+    Statement(&'a Statement),
+    Expression(&'a str, &'a Expression),
+}
+
+impl Expression {
+    /// Sub-expressions and statements of this expression, in source order
+    pub fn children(&self) -> Vec<Child<'_>> {
+        use Child::Expression as E;
+        match self {
+            Expression::Identifier { .. }
+            | Expression::QualifiedIdentifier { .. }
+            | Expression::Number { .. }
+            | Expression::BigNumber { .. }
+            | Expression::Boolean { .. }
+            | Expression::String { .. }
+            | Expression::Bytes { .. }
+            | Expression::Hole { .. }
+            | Expression::Channel { .. }
+            | Expression::Input { .. }
+            | Expression::ReadAll { .. } => vec![],
+            Expression::BinaryOp { left, right, .. } => vec![E("", left), E("", right)],
+            Expression::UnaryOp { operand, .. } => vec![E("", operand)],
+            Expression::Function { body, .. } => vec![E("body", body)],
+            Expression::FunctionCall {
+                function, argument, ..
+            } => vec![E("function", function), E("argument", argument)],
+            Expression::List { elements, .. } | Expression::Tuple { elements, .. } => {
+                elements.iter().map(|e| E("", e)).collect()
+            }
+            Expression::Pair { first, second, .. } => vec![E("", first), E("", second)],
+            Expression::Block {
+                statements,
+                expression,
+                ..
+            } => {
+                let mut children: Vec<Child> = statements.iter().map(Child::Statement).collect();
+                if let Some(expression) = expression {
+                    children.push(E("result", expression));
+                }
+                children
+            }
+            Expression::If {
+                condition,
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                let mut children = vec![E("condition", condition), E("then", then_branch)];
+                if let Some(else_branch) = else_branch {
+                    children.push(E("else", else_branch));
+                }
+                children
+            }
+            Expression::For { iterable, body, .. } => vec![E("in", iterable), E("body", body)],
+            Expression::With { value, body, .. } => vec![E("value", value), E("body", body)],
+            Expression::Range { start, end, .. } => vec![E("start", start), E("end", end)],
+            Expression::DivMod {
+                dividend, divisor, ..
+            }
+            | Expression::SafeDiv {
+                dividend, divisor, ..
+            } => vec![E("", dividend), E("", divisor)],
+            Expression::Cons { head, tail, .. } => vec![E("head", head), E("tail", tail)],
+            Expression::Concat { left, right, .. } => vec![E("", left), E("", right)],
+            Expression::CharAt { string, index, .. } => vec![E("", string), E("index", index)],
+            Expression::ByteAt { bytes, index, .. } => vec![E("", bytes), E("index", index)],
+            Expression::Send { channel, value, .. } => {
+                vec![E("channel", channel), E("value", value)]
+            }
+            Expression::Case {
+                expression,
+                left_body,
+                right_body,
+                ..
+            } => vec![E("", expression), E("inl", left_body), E("inr", right_body)],
+            Expression::Match {
+                expression, arms, ..
+            } => std::iter::once(E("", expression))
+                .chain(arms.iter().map(|arm| E(arm.pattern.label(), &arm.body)))
+                .collect(),
+            Expression::LeftInject { value, .. } | Expression::RightInject { value, .. } => {
+                vec![E("", value)]
+            }
+            Expression::Fix { function, .. } | Expression::Spawn { function, .. } => {
+                vec![E("", function)]
+            }
+            Expression::FirstProjection { pair, .. }
+            | Expression::SecondProjection { pair, .. } => {
+                vec![E("", pair)]
+            }
+            Expression::TupleProjection { tuple, .. } => vec![E("", tuple)],
+            Expression::HeadProjection { list, .. } | Expression::TailProjection { list, .. } => {
+                vec![E("", list)]
+            }
+            Expression::Print { value, .. } => vec![E("", value)],
+            Expression::Length { string, .. }
+            | Expression::Graphemes { string, .. }
+            | Expression::ToBytes { string, .. }
+            | Expression::ToInt { string, .. } => vec![E("", string)],
+            Expression::Join { handle, .. } => vec![E("", handle)],
+            Expression::Sleep { milliseconds, .. } => vec![E("", milliseconds)],
+            Expression::Random { bound, .. } => vec![E("", bound)],
+            Expression::Recv { channel, .. } => vec![E("", channel)],
+            Expression::ReadFileBytes { path, .. } => vec![E("", path)],
+            Expression::FromBytes { bytes, .. } => vec![E("", bytes)],
+            Expression::ToString { expression, .. }
+            | Expression::TypeOf { expression, .. }
+            | Expression::TypeTest { expression, .. }
+            | Expression::TypeAscription { expression, .. } => vec![E("", expression)],
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -263,6 +614,9 @@ pub enum BinaryOperator {
     GreaterThanEqual,
     LogicalAnd,
     LogicalOr,
+    ComposeForward,  // f >> g applies f, then g
+    ComposeBackward, // f << g applies g, then f
+    Append,          // List or String concatenation with ++
 }
 
 impl From<Token> for UnaryOperator {
@@ -291,6 +645,9 @@ impl From<Token> for BinaryOperator {
             Token::GreaterThanEqual => BinaryOperator::GreaterThanEqual,
             Token::LogicalAnd => BinaryOperator::LogicalAnd,
             Token::LogicalOr => BinaryOperator::LogicalOr,
+            Token::ComposeForward => BinaryOperator::ComposeForward,
+            Token::ComposeBackward => BinaryOperator::ComposeBackward,
+            Token::Append => BinaryOperator::Append,
             _ => panic!("Unsupported binary operator token: {:?}", token),
         }
     }
@@ -304,9 +661,12 @@ impl Spanned for Statement {
     fn span(&self) -> &Span {
         match self {
             Statement::VariableDeclaration { span, .. } => span,
+            Statement::ConstantDeclaration { span, .. } => span,
+            Statement::EnumDeclaration { span, .. } => span,
             Statement::FunctionDeclaration { span, .. } => span,
             Statement::Import { span, .. } => span,
             Statement::Expression { span, .. } => span,
+            Statement::Return { span, .. } => span,
         }
     }
 }
@@ -317,6 +677,7 @@ impl Spanned for Expression {
             Expression::Identifier { span, .. } => span,
             Expression::QualifiedIdentifier { span, .. } => span,
             Expression::Number { span, .. } => span,
+            Expression::BigNumber { span, .. } => span,
             Expression::Boolean { span, .. } => span,
             Expression::String { span, .. } => span,
             Expression::BinaryOp { span, .. } => span,
@@ -325,25 +686,62 @@ impl Spanned for Expression {
             Expression::FunctionCall { span, .. } => span,
             Expression::List { span, .. } => span,
             Expression::Pair { span, .. } => span,
+            Expression::Tuple { span, .. } => span,
             Expression::LeftInject { span, .. } => span,
             Expression::RightInject { span, .. } => span,
             Expression::Fix { span, .. } => span,
             Expression::Block { span, .. } => span,
             Expression::FirstProjection { span, .. } => span,
             Expression::SecondProjection { span, .. } => span,
+            Expression::TupleProjection { span, .. } => span,
             Expression::Cons { span, .. } => span,
             Expression::HeadProjection { span, .. } => span,
             Expression::TailProjection { span, .. } => span,
             Expression::Print { span, .. } => span,
             Expression::If { span, .. } => span,
             Expression::For { span, .. } => span,
+            Expression::With { span, .. } => span,
             Expression::Range { span, .. } => span,
+            Expression::DivMod { span, .. } => span,
+            Expression::SafeDiv { span, .. } => span,
             Expression::Concat { span, .. } => span,
             Expression::CharAt { span, .. } => span,
             Expression::Length { span, .. } => span,
+            Expression::Graphemes { span, .. } => span,
+            Expression::Bytes { span, .. } => span,
+            Expression::Hole { span, .. } => span,
+            Expression::Spawn { span, .. } => span,
+            Expression::Join { span, .. } => span,
+            Expression::Sleep { span, .. } => span,
+            Expression::Random { span, .. } => span,
+            Expression::Channel { span } => span,
+            Expression::Input { span } => span,
+            Expression::ReadAll { span } => span,
+            Expression::Send { span, .. } => span,
+            Expression::Recv { span, .. } => span,
+            Expression::ReadFileBytes { span, .. } => span,
+            Expression::ByteAt { span, .. } => span,
+            Expression::ToBytes { span, .. } => span,
+            Expression::FromBytes { span, .. } => span,
+            Expression::ToInt { span, .. } => span,
             Expression::ToString { span, .. } => span,
             Expression::TypeOf { span, .. } => span,
             Expression::Case { span, .. } => span,
+            Expression::Match { span, .. } => span,
+            Expression::TypeTest { span, .. } => span,
+            Expression::TypeAscription { span, .. } => span,
+        }
+    }
+}
+
+impl Spanned for Pattern {
+    fn span(&self) -> &Span {
+        match self {
+            Pattern::Wildcard { span } => span,
+            Pattern::Variant { span, .. } => span,
+            Pattern::String { span, .. } => span,
+            Pattern::Int { span, .. } => span,
+            Pattern::Range { span, .. } => span,
         }
     }
 }
@@ -355,8 +753,11 @@ impl Spanned for TypeExpression {
             TypeExpression::Bool { span } => span,
             TypeExpression::String { span } => span,
             TypeExpression::List { span, .. } => span,
+            TypeExpression::Handle { span, .. } => span,
+            TypeExpression::Channel { span, .. } => span,
             TypeExpression::Function { span, .. } => span,
             TypeExpression::Pair { span, .. } => span,
+            TypeExpression::Tuple { span, .. } => span,
             TypeExpression::Sum { span, .. } => span,
             TypeExpression::Recursive { span, .. } => span,
             TypeExpression::Named { span, .. } => span,
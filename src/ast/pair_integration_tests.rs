@@ -4,8 +4,7 @@ use crate::lexer::Tokenizer;
 #[test]
 fn test_pair_integration() {
     let input = "(1, 2);";
-    let mut tokenizer = Tokenizer::new("");
-    let tokens = tokenizer.tokenize(input).unwrap();
+    let tokens = Tokenizer::new(input).tokenize().unwrap();
     let mut parser = Parser::new(tokens);
     let program = parser.parse().unwrap();
 
@@ -32,8 +31,7 @@ fn test_pair_integration() {
 #[test]
 fn test_nested_pair_integration() {
     let input = "((1, true), 42);";
-    let mut tokenizer = Tokenizer::new("");
-    let tokens = tokenizer.tokenize(input).unwrap();
+    let tokens = Tokenizer::new(input).tokenize().unwrap();
     let mut parser = Parser::new(tokens);
     let program = parser.parse().unwrap();
 
@@ -77,8 +75,7 @@ fn test_nested_pair_integration() {
 #[test]
 fn test_parenthesized_expression_integration() {
     let input = "(42);";
-    let mut tokenizer = Tokenizer::new("");
-    let tokens = tokenizer.tokenize(input).unwrap();
+    let tokens = Tokenizer::new(input).tokenize().unwrap();
     let mut parser = Parser::new(tokens);
     let program = parser.parse().unwrap();
 
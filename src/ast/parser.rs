@@ -1,4 +1,6 @@
-use crate::ast::nodes::{Expression, Program, Spanned, Statement, TypeExpression};
+use crate::ast::nodes::{
+    CaseArm, Expression, NodeId, Pattern, Program, Spanned, Statement, TypeExpression,
+};
 use crate::lexer::tokens::{Span, Token, TokenWithSpan};
 
 #[derive(Debug)]
@@ -13,6 +15,42 @@ pub enum ParseError {
         message: String,
         span: Span,
     },
+    /// A bracket or brace that is never closed: the input ends, or a
+    /// different closing delimiter comes, while it is still open
+    UnclosedDelimiter {
+        /// The opening delimiter
+        delimiter: char,
+        /// Boxed to keep the error, which every parse function returns, as
+        /// small as the others
+        opened: Box<Span>,
+        /// The closing delimiter found instead, or `None` at the end of input
+        found: Option<char>,
+        /// Where the closing delimiter was expected
+        span: Span,
+    },
+}
+
+impl ParseError {
+    /// Stable code identifying the kind of error; see `--explain`
+    pub fn code(&self) -> &'static str {
+        match self {
+            ParseError::UnexpectedToken { .. } => "E0101",
+            ParseError::UnexpectedEof => "E0102",
+            ParseError::InvalidExpression { .. } => "E0103",
+            ParseError::UnclosedDelimiter { .. } => "E0104",
+        }
+    }
+
+    /// Location of the offending token, if there is one. For an unclosed
+    /// delimiter this is the opening one.
+    pub fn span(&self) -> Option<&Span> {
+        match self {
+            ParseError::UnexpectedToken { span, .. }
+            | ParseError::InvalidExpression { span, .. } => Some(span),
+            ParseError::UnclosedDelimiter { opened, .. } => Some(opened),
+            ParseError::UnexpectedEof => None,
+        }
+    }
 }
 
 impl std::fmt::Display for ParseError {
@@ -37,6 +75,30 @@ impl std::fmt::Display for ParseError {
                     span.line, span.column, message
                 )
             }
+            ParseError::UnclosedDelimiter {
+                delimiter,
+                opened,
+                found,
+                span,
+            } => {
+                write!(
+                    f,
+                    "Unclosed '{}' opened at line {}, column {}: ",
+                    delimiter, opened.line, opened.column
+                )?;
+                match found {
+                    Some(found) => write!(
+                        f,
+                        "found '{}' at line {}, column {} before it is closed",
+                        found, span.line, span.column
+                    ),
+                    None => write!(
+                        f,
+                        "the input ends at line {}, column {} before it is closed",
+                        span.line, span.column
+                    ),
+                }
+            }
         }
     }
 }
@@ -45,17 +107,104 @@ impl std::error::Error for ParseError {}
 
 pub type ParseResult<T> = Result<T, ParseError>;
 
+/// An infix operator recognized by the precedence climber
+#[derive(Debug, Clone, PartialEq)]
+enum InfixOperator {
+    Binary(crate::ast::BinaryOperator),
+    /// `head :: tail`, sugar for `cons(head, tail)`
+    Cons,
+}
+
+/// How operators of equal precedence group when chained
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) enum Associativity {
+    /// `a op b op c` parses as `(a op b) op c`
+    Left,
+    /// `a op b op c` parses as `a op (b op c)`
+    Right,
+}
+
+/// Binding power of the binary operators, from loosest to tightest.
+///
+/// Expressions are parsed by precedence climbing over this table:
+///
+/// ```text
+/// expression := binary(COMPOSITION)
+/// binary(p)  := unary (operator binary(q))*    for every operator with precedence >= p,
+///                                              q = precedence + 1 (left) or precedence (right)
+/// unary      := ('!' | '-') unary | call
+/// call       := primary ('(' expression ')')*
+/// ```
+///
+/// Function application therefore binds tighter than any prefix operator, so
+/// `-f(x)` negates the result of the call and `!f(x)` negates its boolean result.
+pub(super) mod precedence {
+    /// `>>` (left-associative) and `<<` (right-associative)
+    pub const COMPOSITION: u8 = 1;
+    /// `||`
+    pub const LOGICAL_OR: u8 = 2;
+    /// `&&`
+    pub const LOGICAL_AND: u8 = 3;
+    /// `==`, `!=`, `<`, `<=`, `>`, `>=`
+    pub const COMPARISON: u8 = 5;
+    /// `::` (right-associative)
+    pub const CONS: u8 = 7;
+    /// `++`
+    pub const APPEND: u8 = 8;
+    /// `+`, `-`
+    pub const ADDITIVE: u8 = 10;
+    /// `*`, `/`
+    pub const MULTIPLICATIVE: u8 = 20;
+}
+
+/// How deeply expressions and types may nest before parsing gives up, so that
+/// pathological input is an error rather than a stack overflow
+const MAX_NESTING_DEPTH: usize = 64;
+
 pub struct Parser {
     tokens: Vec<TokenWithSpan>,
     current: usize,
+    /// Expressions and types currently being parsed, innermost last
+    depth: usize,
+    /// Function bodies currently being parsed, which `return` needs
+    functions: usize,
+    /// Span of the name each `NodeId` was given to, by id from 1
+    names: Vec<Span>,
 }
 
 impl Parser {
-    pub fn new(tokens: Vec<TokenWithSpan>) -> Self {
-        Self { tokens, current: 0 }
+    pub fn new(mut tokens: Vec<TokenWithSpan>) -> Self {
+        // The parser relies on an `Eof` token to stop at
+        if tokens.last().is_none_or(|last| last.token != Token::Eof) {
+            let span = tokens
+                .last()
+                .map_or(Span::new(0, 0, 1, 1), |last| last.span.end_point());
+            tokens.push(TokenWithSpan::new(Token::Eof, span));
+        }
+        Self {
+            tokens,
+            current: 0,
+            depth: 0,
+            functions: 0,
+            names: Vec::new(),
+        }
+    }
+
+    /// The span of the name that was given `id` while parsing, such as the
+    /// variable of a `let` or an identifier referring to it
+    pub fn name_span(&self, id: NodeId) -> Option<&Span> {
+        let index = (id.0 as usize).checked_sub(1)?;
+        self.names.get(index)
+    }
+
+    /// Every id given out while parsing with the span of its name, in
+    /// source order
+    pub fn names(&self) -> impl Iterator<Item = (NodeId, &Span)> {
+        (1..).map(NodeId).zip(&self.names)
     }
 
     pub fn parse(&mut self) -> ParseResult<Program> {
+        self.check_delimiters()?;
         let start_span = self.current_span();
         let mut statements = Vec::new();
 
@@ -72,38 +221,175 @@ impl Parser {
             self.previous_span()
         };
 
-        let program_span = Span::new(
-            start_span.start,
-            end_span.end,
-            start_span.line,
-            start_span.column,
-        );
+        let program_span = start_span.to(&end_span);
 
         Ok(Program::new(statements, program_span))
     }
 
+    /// Match every closing bracket and brace with the one it closes before
+    /// parsing, so a missing one is reported where it was opened rather than
+    /// wherever the parser first notices something is wrong. A closing
+    /// delimiter with nothing open is left for the parser to report.
+    fn check_delimiters(&self) -> ParseResult<()> {
+        let mut open: Vec<(char, &Span)> = Vec::new();
+        for TokenWithSpan { token, span } in &self.tokens {
+            let (opening, closing) = match token {
+                Token::LeftParen => ('(', None),
+                Token::LeftBracket => ('[', None),
+                Token::LeftBrace => ('{', None),
+                Token::RightParen => ('(', Some(')')),
+                Token::RightBracket => ('[', Some(']')),
+                Token::RightBrace => ('{', Some('}')),
+                Token::Eof => break,
+                _ => continue,
+            };
+            let Some(closing) = closing else {
+                open.push((opening, span));
+                continue;
+            };
+            match open.pop() {
+                Some((delimiter, opened)) if delimiter != opening => {
+                    return Err(ParseError::UnclosedDelimiter {
+                        delimiter,
+                        opened: Box::new(opened.clone()),
+                        found: Some(closing),
+                        span: span.clone(),
+                    });
+                }
+                _ => {}
+            }
+        }
+        match open.pop() {
+            Some((delimiter, opened)) => Err(ParseError::UnclosedDelimiter {
+                delimiter,
+                opened: Box::new(opened.clone()),
+                found: None,
+                span: self.tokens.last().expect("ends with Eof").span.clone(),
+            }),
+            None => Ok(()),
+        }
+    }
+
     fn parse_statement(&mut self) -> ParseResult<Statement> {
         match &self.peek().token {
             Token::Let => self.parse_variable_declaration(),
+            Token::Const => self.parse_constant_declaration(),
+            Token::Enum => self.parse_enum_declaration(),
             Token::Fn => self.parse_function_declaration(),
-            Token::Import => self.parse_import_statement(),
+            Token::Import | Token::Export => self.parse_import_statement(),
+            Token::Return => self.parse_return_statement(),
             _ => self.parse_expression_statement(),
         }
     }
 
+    fn parse_return_statement(&mut self) -> ParseResult<Statement> {
+        let start_span = self.current_span();
+        self.consume(Token::Return, "Expected 'return'")?;
+        if self.functions == 0 {
+            return Err(ParseError::InvalidExpression {
+                message: "'return' can only be used inside a function body".to_string(),
+                span: start_span,
+            });
+        }
+        let value = self.parse_expression()?;
+        self.consume(Token::Semicolon, "Expected ';' after return value")?;
+        let span = start_span.to(&self.previous_span());
+
+        Ok(Statement::Return { value, span })
+    }
+
     fn parse_variable_declaration(&mut self) -> ParseResult<Statement> {
         let start_span = self.current_span();
         self.consume(Token::Let, "Expected 'let'")?;
+        let (name, id, type_annotation, value) = self.parse_binding(true)?;
+        let span = start_span.to(&self.previous_span());
+
+        Ok(Statement::VariableDeclaration {
+            name,
+            type_annotation,
+            value,
+            id,
+            span,
+        })
+    }
+
+    fn parse_constant_declaration(&mut self) -> ParseResult<Statement> {
+        let start_span = self.current_span();
+        self.consume(Token::Const, "Expected 'const'")?;
+        let (name, id, type_annotation, value) = self.parse_binding(false)?;
+        let span = start_span.to(&self.previous_span());
 
-        let name = if let Token::Identifier(name) = &self.advance().token {
-            name.clone()
+        Ok(Statement::ConstantDeclaration {
+            name,
+            type_annotation,
+            value,
+            id,
+            span,
+        })
+    }
+
+    fn parse_enum_declaration(&mut self) -> ParseResult<Statement> {
+        let start_span = self.current_span();
+        self.consume(Token::Enum, "Expected 'enum'")?;
+        let name = self.parse_name("enum name")?;
+        self.consume(Token::LeftBrace, "Expected '{' after enum name")?;
+
+        let mut variants = vec![self.parse_name("variant name")?];
+        while self.peek().token == Token::Comma {
+            self.advance(); // consume ','
+            if self.peek().token == Token::RightBrace {
+                break; // trailing comma
+            }
+            variants.push(self.parse_name("variant name")?);
+        }
+        self.consume(Token::RightBrace, "Expected '}' after enum variants")?;
+        let span = start_span.to(&self.previous_span());
+
+        Ok(Statement::EnumDeclaration {
+            name,
+            variants,
+            span,
+        })
+    }
+
+    /// A name being declared, where `what` says what it names. Reserved
+    /// words are refused with a suggestion, since builtins such as `head`
+    /// are reserved and look like names.
+    fn parse_name(&mut self, what: &str) -> ParseResult<String> {
+        let token = self.advance();
+        match &token.token {
+            Token::Identifier(name) => Ok(name.clone()),
+            other => match other.keyword() {
+                Some(keyword) => Err(ParseError::InvalidExpression {
+                    message: format!(
+                        "'{}' is a reserved word and cannot be used as a {}; use another name such as '{}_'",
+                        keyword, what, keyword
+                    ),
+                    span: token.span.clone(),
+                }),
+                None => Err(ParseError::UnexpectedToken {
+                    expected: what.to_string(),
+                    found: other.clone(),
+                    span: token.span.clone(),
+                }),
+            },
+        }
+    }
+
+    /// The `name [: type] = value;` part shared by `let` and `const`. With
+    /// `wildcard` the name may be `_`, which evaluates the value and binds
+    /// nothing.
+    fn parse_binding(
+        &mut self,
+        wildcard: bool,
+    ) -> ParseResult<(String, NodeId, Option<TypeExpression>, Expression)> {
+        let name = if wildcard && self.peek().token == Token::Underscore {
+            self.advance();
+            "_".to_string()
         } else {
-            return Err(ParseError::UnexpectedToken {
-                expected: "identifier".to_string(),
-                found: self.previous().token.clone(),
-                span: self.previous_span(),
-            });
+            self.parse_name("variable name")?
         };
+        let id = self.next_id();
 
         let type_annotation = if self.peek().token == Token::Colon {
             self.advance(); // consume ':'
@@ -116,47 +402,20 @@ impl Parser {
         let value = self.parse_expression()?;
         self.consume(Token::Semicolon, "Expected ';'")?;
 
-        let end_span = self.previous_span();
-        let span = Span::new(
-            start_span.start,
-            end_span.end,
-            start_span.line,
-            start_span.column,
-        );
-
-        Ok(Statement::VariableDeclaration {
-            name,
-            type_annotation,
-            value,
-            span,
-        })
+        Ok((name, id, type_annotation, value))
     }
 
     fn parse_function_declaration(&mut self) -> ParseResult<Statement> {
         let start_span = self.current_span();
         self.consume(Token::Fn, "Expected 'fn'")?;
 
-        let name = if let Token::Identifier(name) = &self.advance().token {
-            name.clone()
-        } else {
-            return Err(ParseError::UnexpectedToken {
-                expected: "function name".to_string(),
-                found: self.previous().token.clone(),
-                span: self.previous_span(),
-            });
-        };
+        let name = self.parse_name("function name")?;
+        let id = self.next_id();
 
         self.consume(Token::LeftParen, "Expected '(' after function name")?;
 
-        let param = if let Token::Identifier(param) = &self.advance().token {
-            param.clone()
-        } else {
-            return Err(ParseError::UnexpectedToken {
-                expected: "parameter name".to_string(),
-                found: self.previous().token.clone(),
-                span: self.previous_span(),
-            });
-        };
+        let param = self.parse_name("parameter name")?;
+        let param_id = self.next_id();
 
         let param_type = if self.peek().token == Token::Colon {
             self.advance(); // consume ':'
@@ -165,7 +424,7 @@ impl Parser {
             None
         };
 
-        self.consume(Token::RightParen, "Expected ')' after parameter")?;
+        self.consume_parameter_end()?;
 
         let return_type = if self.peek().token == Token::Arrow {
             self.advance(); // consume '->'
@@ -175,16 +434,11 @@ impl Parser {
         };
 
         self.consume(Token::LeftBrace, "Expected '{' before function body")?;
-        let body = self.parse_block()?;
+        let body = self.parse_function_body()?;
         self.consume(Token::RightBrace, "Expected '}' after function body")?;
 
         let end_span = self.previous_span();
-        let span = Span::new(
-            start_span.start,
-            end_span.end,
-            start_span.line,
-            start_span.column,
-        );
+        let span = start_span.to(&end_span);
 
         Ok(Statement::FunctionDeclaration {
             name,
@@ -192,12 +446,18 @@ impl Parser {
             param_type,
             return_type,
             body,
+            id,
+            param_id,
             span,
         })
     }
 
     fn parse_import_statement(&mut self) -> ParseResult<Statement> {
         let start_span = self.current_span();
+        let exported = self.check(&Token::Export);
+        if exported {
+            self.advance(); // consume 'export'
+        }
         self.consume(Token::Import, "Expected 'import'")?;
 
         let path = if let Token::StringLiteral(path) = &self.advance().token {
@@ -212,15 +472,7 @@ impl Parser {
 
         let alias = if self.peek().token == Token::As {
             self.advance(); // consume 'as'
-            if let Token::Identifier(alias) = &self.advance().token {
-                Some(alias.clone())
-            } else {
-                return Err(ParseError::UnexpectedToken {
-                    expected: "alias identifier".to_string(),
-                    found: self.previous().token.clone(),
-                    span: self.previous_span(),
-                });
-            }
+            Some(self.parse_name("module alias")?)
         } else {
             None
         };
@@ -228,14 +480,14 @@ impl Parser {
         self.consume(Token::Semicolon, "Expected ';'")?;
 
         let end_span = self.previous_span();
-        let span = Span::new(
-            start_span.start,
-            end_span.end,
-            start_span.line,
-            start_span.column,
-        );
+        let span = start_span.to(&end_span);
 
-        Ok(Statement::Import { path, alias, span })
+        Ok(Statement::Import {
+            path,
+            alias,
+            exported,
+            span,
+        })
     }
 
     fn parse_expression_statement(&mut self) -> ParseResult<Statement> {
@@ -247,35 +499,60 @@ impl Parser {
     }
 
     fn parse_expression(&mut self) -> ParseResult<Expression> {
-        self.parse_binary_expression(0)
+        self.parse_binary_expression(precedence::COMPOSITION)
     }
 
     fn parse_binary_expression(&mut self, min_precedence: u8) -> ParseResult<Expression> {
-        let mut left = self.parse_call_expression()?;
+        self.nested(|parser| parser.parse_binary_operands(min_precedence))
+    }
 
+    fn parse_binary_operands(&mut self, min_precedence: u8) -> ParseResult<Expression> {
+        let left = self.parse_unary_expression()?;
+        self.parse_binary_rest(left, min_precedence)
+    }
+
+    /// Parse the operators following `left` that bind at least as tightly
+    /// as `min_precedence`
+    fn parse_binary_rest(
+        &mut self,
+        mut left: Expression,
+        min_precedence: u8,
+    ) -> ParseResult<Expression> {
         while !self.is_at_end() {
             let token = &self.peek().token;
-            if let Some((precedence, operator)) = self.get_binary_operator(token) {
-                if precedence < min_precedence {
+
+            // `expr is Type` binds like a comparison, but its right side is a type
+            if *token == Token::Is {
+                if precedence::COMPARISON < min_precedence {
                     break;
                 }
 
-                self.advance(); // consume operator
-                let right = self.parse_binary_expression(precedence + 1)?;
+                self.advance(); // consume 'is'
+                let type_annotation = self.parse_type_expression()?;
 
-                let span = Span::new(
-                    left.span().start,
-                    right.span().end,
-                    left.span().line,
-                    left.span().column,
-                );
+                let span = left.span().to(type_annotation.span());
 
-                left = Expression::BinaryOp {
-                    left: Box::new(left),
-                    operator,
-                    right: Box::new(right),
+                left = Expression::TypeTest {
+                    expression: Box::new(left),
+                    type_annotation,
                     span,
                 };
+                continue;
+            }
+
+            if let Some((precedence, associativity, operator)) = self.get_binary_operator(token) {
+                if precedence < min_precedence {
+                    break;
+                }
+
+                self.advance(); // consume operator
+                left = match associativity {
+                    Associativity::Left => {
+                        let right = self.parse_binary_expression(precedence + 1)?;
+                        Self::infix(left, operator, right)
+                    }
+                    Associativity::Right => self.parse_right_chain(left, operator, precedence)?,
+                };
             } else {
                 break;
             }
@@ -284,46 +561,121 @@ impl Parser {
         Ok(left)
     }
 
-    fn parse_call_expression(&mut self) -> ParseResult<Expression> {
-        let mut expr = self.parse_unary_expression()?;
+    /// Parse the rest of `a :: b :: c` after its first operator as a flat
+    /// run of operands folded from the right, so a long chain does not
+    /// recurse once per operand
+    fn parse_right_chain(
+        &mut self,
+        first: Expression,
+        operator: InfixOperator,
+        precedence: u8,
+    ) -> ParseResult<Expression> {
+        let mut pending = vec![(first, operator)];
+        let last = loop {
+            let operand = self.parse_binary_expression(precedence + 1)?;
+            match self.get_binary_operator(&self.peek().token) {
+                Some((next, Associativity::Right, operator)) if next == precedence => {
+                    self.advance(); // consume operator
+                    pending.push((operand, operator));
+                }
+                _ => break self.parse_binary_rest(operand, precedence)?,
+            }
+        };
 
-        while !self.is_at_end() && self.peek().token == Token::LeftParen {
-            self.advance(); // consume '('
-            let argument = Box::new(self.parse_expression()?);
-            self.consume(Token::RightParen, "Expected ')' after function argument")?;
-
-            let span = Span::new(
-                expr.span().start,
-                self.previous_span().end,
-                expr.span().line,
-                expr.span().column,
-            );
-
-            expr = Expression::FunctionCall {
-                function: Box::new(expr),
-                argument,
+        Ok(pending
+            .into_iter()
+            .rev()
+            .fold(last, |right, (left, operator)| {
+                Self::infix(left, operator, right)
+            }))
+    }
+
+    fn infix(left: Expression, operator: InfixOperator, right: Expression) -> Expression {
+        let span = left.span().to(right.span());
+        match operator {
+            InfixOperator::Binary(operator) => Expression::BinaryOp {
+                left: Box::new(left),
+                operator,
+                right: Box::new(right),
                 span,
-            };
+            },
+            InfixOperator::Cons => Expression::Cons {
+                head: Box::new(left),
+                tail: Box::new(right),
+                span,
+            },
+        }
+    }
+
+    fn parse_call_expression(&mut self) -> ParseResult<Expression> {
+        let mut expr = self.parse_primary()?;
+
+        while !self.is_at_end() {
+            match self.peek().token {
+                Token::LeftParen => {
+                    self.advance(); // consume '('
+                    let argument = Box::new(self.parse_expression()?);
+                    self.consume(Token::RightParen, "Expected ')' after function argument")?;
+
+                    let span = expr.span().to(&self.previous_span());
+
+                    expr = Expression::FunctionCall {
+                        function: Box::new(expr),
+                        argument,
+                        span,
+                    };
+                }
+                Token::Period => {
+                    self.advance(); // consume '.'
+                    let index = match self.advance().token {
+                        Token::Number(index) if index >= 0 => index as usize,
+                        _ => {
+                            return Err(ParseError::UnexpectedToken {
+                                expected: "tuple index after '.'".to_string(),
+                                found: self.previous().token.clone(),
+                                span: self.previous_span(),
+                            });
+                        }
+                    };
+
+                    let span = expr.span().to(&self.previous_span());
+
+                    expr = Expression::TupleProjection {
+                        tuple: Box::new(expr),
+                        index,
+                        span,
+                    };
+                }
+                _ => break,
+            }
         }
 
         Ok(expr)
     }
 
-    fn get_binary_operator(&self, token: &Token) -> Option<(u8, crate::ast::BinaryOperator)> {
-        use crate::ast::BinaryOperator;
+    /// Look up the precedence and associativity of an infix operator token
+    fn get_binary_operator(&self, token: &Token) -> Option<(u8, Associativity, InfixOperator)> {
+        use crate::ast::BinaryOperator::*;
+        use Associativity::{Left, Right};
+        use InfixOperator::{Binary, Cons};
+        use precedence::*;
         match token {
-            Token::Plus => Some((10, BinaryOperator::Add)),
-            Token::Minus => Some((10, BinaryOperator::Subtract)),
-            Token::Multiply => Some((20, BinaryOperator::Multiply)),
-            Token::Divide => Some((20, BinaryOperator::Divide)),
-            Token::Equal => Some((5, BinaryOperator::Equal)),
-            Token::NotEqual => Some((5, BinaryOperator::NotEqual)),
-            Token::LessThan => Some((5, BinaryOperator::LessThan)),
-            Token::LessThanEqual => Some((5, BinaryOperator::LessThanEqual)),
-            Token::GreaterThan => Some((5, BinaryOperator::GreaterThan)),
-            Token::GreaterThanEqual => Some((5, BinaryOperator::GreaterThanEqual)),
-            Token::LogicalAnd => Some((3, BinaryOperator::LogicalAnd)),
-            Token::LogicalOr => Some((2, BinaryOperator::LogicalOr)),
+            Token::Plus => Some((ADDITIVE, Left, Binary(Add))),
+            Token::Minus => Some((ADDITIVE, Left, Binary(Subtract))),
+            Token::Multiply => Some((MULTIPLICATIVE, Left, Binary(Multiply))),
+            Token::Divide => Some((MULTIPLICATIVE, Left, Binary(Divide))),
+            Token::DoubleColon => Some((CONS, Right, Cons)),
+            Token::Append => Some((APPEND, Left, Binary(Append))),
+            Token::Equal => Some((COMPARISON, Left, Binary(Equal))),
+            Token::NotEqual => Some((COMPARISON, Left, Binary(NotEqual))),
+            Token::LessThan => Some((COMPARISON, Left, Binary(LessThan))),
+            Token::LessThanEqual => Some((COMPARISON, Left, Binary(LessThanEqual))),
+            Token::GreaterThan => Some((COMPARISON, Left, Binary(GreaterThan))),
+            Token::GreaterThanEqual => Some((COMPARISON, Left, Binary(GreaterThanEqual))),
+            Token::LogicalAnd => Some((LOGICAL_AND, Left, Binary(LogicalAnd))),
+            Token::LogicalOr => Some((LOGICAL_OR, Left, Binary(LogicalOr))),
+            Token::ComposeForward => Some((COMPOSITION, Left, Binary(ComposeForward))),
+            Token::ComposeBackward => Some((COMPOSITION, Right, Binary(ComposeBackward))),
             _ => None,
         }
     }
@@ -331,36 +683,66 @@ impl Parser {
     fn parse_unary_expression(&mut self) -> ParseResult<Expression> {
         use crate::ast::nodes::UnaryOperator;
 
-        let token = &self.peek().token;
-        if matches!(token, Token::LogicalNot | Token::Minus) {
+        // Stacked prefix operators are collected first, so `- - x` does not
+        // recurse once per operator
+        let mut operators = Vec::new();
+        while matches!(self.peek().token, Token::LogicalNot | Token::Minus) {
             let operator_token = self.advance();
             let operator_span = operator_token.span.clone();
-            let operator = UnaryOperator::from(operator_token.token.clone());
-            let operand = Box::new(self.parse_unary_expression()?);
-            let span = Span::new(
-                operator_span.start,
-                operand.span().end,
-                operator_span.line,
-                operator_span.column,
-            );
-
-            Ok(Expression::UnaryOp {
-                operator,
-                operand,
-                span,
-            })
-        } else {
-            self.parse_primary()
+            operators.push((
+                UnaryOperator::from(operator_token.token.clone()),
+                operator_span,
+            ));
         }
+
+        let operand = self.parse_call_expression()?;
+        Ok(operators
+            .into_iter()
+            .rev()
+            .fold(operand, |operand, (operator, operator_span)| {
+                let span = operator_span.to(operand.span());
+                Expression::UnaryOp {
+                    operator,
+                    operand: Box::new(operand),
+                    span,
+                }
+            }))
     }
 
     fn parse_primary(&mut self) -> ParseResult<Expression> {
         let token = self.advance().token.clone();
+        let keyword_span = self.previous_span();
+        // A reserved word followed by something that ends a value, as in
+        // `x + in` or `let y = head;`, was most likely meant as a name
+        if let Some(keyword) = token.keyword()
+            && !matches!(token, Token::True | Token::False)
+            && ends_operand(&self.peek().token)
+        {
+            let message = if token.is_builtin() {
+                format!(
+                    "'{}' is a builtin and cannot be used as a name; call it as {}(...), or use another name such as '{}_'",
+                    keyword, keyword, keyword
+                )
+            } else {
+                format!(
+                    "'{}' is a reserved word and cannot be used as a name; rename the variable, such as to '{}_'",
+                    keyword, keyword
+                )
+            };
+            return Err(ParseError::InvalidExpression {
+                message,
+                span: keyword_span,
+            });
+        }
         match token {
             Token::Number(value) => {
                 let span = self.previous_span();
                 Ok(Expression::Number { value, span })
             }
+            Token::BigNumber(value) => {
+                let span = self.previous_span();
+                Ok(Expression::BigNumber { value, span })
+            }
             Token::True => {
                 let span = self.previous_span();
                 Ok(Expression::Boolean { value: true, span })
@@ -373,37 +755,44 @@ impl Parser {
                 let span = self.previous_span();
                 Ok(Expression::String { value, span })
             }
+            Token::BytesLiteral(value) => {
+                let span = self.previous_span();
+                Ok(Expression::Bytes { value, span })
+            }
+            Token::Hole(name) => {
+                let span = self.previous_span();
+                Ok(Expression::Hole { name, span })
+            }
             Token::Identifier(name) => {
                 let start_span = self.previous_span();
-                // Check for qualified identifier (module.name)
-                if !self.is_at_end() && self.peek().token == Token::Period {
+                // Check for qualified identifier (module.name, or
+                // outer.inner.name for nested modules)
+                let mut segments = vec![name];
+                while !self.is_at_end()
+                    && self.peek().token == Token::Period
+                    && matches!(self.peek_next().token, Token::Identifier(_))
+                {
                     self.advance(); // consume '.'
-                    let member_token = self.advance();
-                    if let Token::Identifier(member) = &member_token.token {
-                        let end_span = member_token.span.clone();
-                        let span = Span::new(
-                            start_span.start,
-                            end_span.end,
-                            start_span.line,
-                            start_span.column,
-                        );
-                        Ok(Expression::QualifiedIdentifier {
-                            module: name,
-                            name: member.clone(),
-                            span,
-                        })
-                    } else {
-                        Err(ParseError::UnexpectedToken {
-                            expected: "member name after '.'".to_string(),
-                            found: self.previous().token.clone(),
-                            span: self.previous_span(),
-                        })
+                    if let Token::Identifier(member) = &self.advance().token {
+                        segments.push(member.clone());
                     }
-                } else {
+                }
+
+                let name = segments.pop().unwrap_or_default();
+                if segments.is_empty() {
                     Ok(Expression::Identifier {
                         name,
+                        id: self.next_id(),
                         span: start_span,
                     })
+                } else {
+                    let end_span = self.previous_span();
+                    let span = start_span.to(&end_span);
+                    Ok(Expression::QualifiedIdentifier {
+                        module: segments.join("."),
+                        name,
+                        span,
+                    })
                 }
             }
             Token::Fn => self.parse_function_expression(),
@@ -415,11 +804,65 @@ impl Parser {
             Token::Print => self.parse_print_expression(),
             Token::If => self.parse_if_expression(),
             Token::For => self.parse_for_expression(),
+            Token::With => self.parse_with_expression(),
             Token::Range => self.parse_range_expression(),
+            Token::DivMod => self.parse_divmod_expression(),
+            Token::SafeDiv => self.parse_safe_div_expression(),
             Token::Concat => self.parse_concat_expression(),
             Token::Char => self.parse_char_at_expression(),
             Token::Length => self.parse_length_expression(),
+            Token::Graphemes => self.parse_graphemes_expression(),
+            Token::ReadFileBytes => {
+                let (path, span) = self.parse_single_argument()?;
+                Ok(Expression::ReadFileBytes { path, span })
+            }
+            Token::ByteAt => self.parse_byte_at_expression(),
+            Token::Spawn => {
+                let (function, span) = self.parse_single_argument()?;
+                Ok(Expression::Spawn { function, span })
+            }
+            Token::Join => {
+                let (handle, span) = self.parse_single_argument()?;
+                Ok(Expression::Join { handle, span })
+            }
+            Token::Sleep => {
+                let (milliseconds, span) = self.parse_single_argument()?;
+                Ok(Expression::Sleep { milliseconds, span })
+            }
+            Token::Random => {
+                let (bound, span) = self.parse_single_argument()?;
+                Ok(Expression::Random { bound, span })
+            }
+            Token::MakeChannel => {
+                let span = self.parse_no_arguments()?;
+                Ok(Expression::Channel { span })
+            }
+            Token::Input => {
+                let span = self.parse_no_arguments()?;
+                Ok(Expression::Input { span })
+            }
+            Token::ReadAll => {
+                let span = self.parse_no_arguments()?;
+                Ok(Expression::ReadAll { span })
+            }
+            Token::Send => self.parse_send_expression(),
+            Token::Recv => {
+                let (channel, span) = self.parse_single_argument()?;
+                Ok(Expression::Recv { channel, span })
+            }
+            Token::ToBytes => {
+                let (string, span) = self.parse_single_argument()?;
+                Ok(Expression::ToBytes { string, span })
+            }
+            Token::FromBytes => {
+                let (bytes, span) = self.parse_single_argument()?;
+                Ok(Expression::FromBytes { bytes, span })
+            }
             Token::ToString => self.parse_tostring_expression(),
+            Token::ToInt => {
+                let (string, span) = self.parse_single_argument()?;
+                Ok(Expression::ToInt { string, span })
+            }
             Token::Type => self.parse_type_of_expression(),
             Token::Fix => self.parse_fix_expression(),
             Token::Inl => self.parse_inl_expression(),
@@ -440,79 +883,89 @@ impl Parser {
 
         self.consume(Token::LeftParen, "Expected '(' after 'fn'")?;
 
-        let param = if let Token::Identifier(name) = &self.advance().token {
-            name.clone()
-        } else {
-            return Err(ParseError::UnexpectedToken {
-                expected: "parameter name".to_string(),
-                found: self.previous().token.clone(),
-                span: self.previous_span(),
-            });
-        };
+        let param = self.parse_name("parameter name")?;
+        let param_id = self.next_id();
 
         let param_type = if self.peek().token == Token::Colon {
             self.advance(); // consume ':'
-            Some(self.parse_type_expression()?)
+            Some(Box::new(self.parse_type_expression()?))
         } else {
             None
         };
 
-        self.consume(Token::RightParen, "Expected ')' after parameter")?;
+        self.consume_parameter_end()?;
         self.consume(Token::LeftBrace, "Expected '{' to start function body")?;
 
-        let body = Box::new(self.parse_block()?);
+        let body = Box::new(self.parse_function_body()?);
 
         self.consume(Token::RightBrace, "Expected '}' to end function body")?;
 
         let end_span = self.previous_span();
-        let span = Span::new(
-            start_span.start,
-            end_span.end,
-            start_span.line,
-            start_span.column,
-        );
+        let span = start_span.to(&end_span);
 
         Ok(Expression::Function {
             param,
             param_type,
             body,
+            param_id,
             span,
         })
     }
 
+    /// A block that `return` leaves
+    fn parse_function_body(&mut self) -> ParseResult<Expression> {
+        self.functions += 1;
+        let body = self.parse_block();
+        self.functions -= 1;
+        body
+    }
+
     fn parse_block(&mut self) -> ParseResult<Expression> {
         let start_span = self.current_span();
         let mut statements = Vec::new();
         let mut final_expression = None;
 
         while !self.is_at_end() && self.peek().token != Token::RightBrace {
-            let checkpoint = self.current;
+            if matches!(self.peek().token, Token::Const | Token::Enum) {
+                return Err(ParseError::InvalidExpression {
+                    message: "constants and enums can only be declared at the top level"
+                        .to_string(),
+                    span: self.current_span(),
+                });
+            }
 
-            if let Ok(stmt) = self.parse_statement() {
-                statements.push(stmt);
-            } else {
-                self.current = checkpoint;
+            // Declarations are always statements. An expression is a
+            // statement when a ';' follows it and otherwise ends the block.
+            let declaration = match self.peek().token {
+                Token::Let | Token::Import | Token::Export | Token::Return => true,
+                Token::Fn => matches!(self.peek_next().token, Token::Identifier(_)),
+                _ => false,
+            };
+            if declaration {
+                statements.push(self.parse_statement()?);
+                continue;
+            }
 
-                let expr = self.parse_expression()?;
-                final_expression = Some(Box::new(expr));
+            let expression = self.parse_expression()?;
+            if self.check(&Token::Semicolon) {
+                self.advance(); // consume ';'
+                let span = expression.span().clone();
+                statements.push(Statement::Expression { expression, span });
+            } else {
+                final_expression = Some(Box::new(expression));
                 break;
             }
         }
 
-        let end_span = if final_expression.is_some() {
-            final_expression.as_ref().unwrap().span().clone()
+        let end_span = if let Some(expr) = &final_expression {
+            expr.span().clone()
         } else if let Some(last_stmt) = statements.last() {
             last_stmt.span().clone()
         } else {
             start_span.clone()
         };
 
-        let span = Span::new(
-            start_span.start,
-            end_span.end,
-            start_span.line,
-            start_span.column,
-        );
+        let span = start_span.to(&end_span);
 
         Ok(Expression::Block {
             statements,
@@ -526,24 +979,43 @@ impl Parser {
 
         let first = self.parse_expression()?;
 
+        if self.peek().token == Token::Colon {
+            self.advance(); // consume ':'
+            let type_annotation = self.parse_type_expression()?;
+            self.consume(Token::RightParen, "Expected ')' after type ascription")?;
+
+            let end_span = self.previous_span();
+            let span = start_span.to(&end_span);
+
+            return Ok(Expression::TypeAscription {
+                expression: Box::new(first),
+                type_annotation,
+                span,
+            });
+        }
+
         if self.peek().token == Token::Comma {
-            self.advance(); // consume ','
-            let second = Box::new(self.parse_expression()?);
+            let mut elements = vec![first];
+            while self.peek().token == Token::Comma {
+                self.advance(); // consume ','
+                elements.push(self.parse_expression()?);
+            }
             self.consume(Token::RightParen, "Expected ')' after pair")?;
 
             let end_span = self.previous_span();
-            let span = Span::new(
-                start_span.start,
-                end_span.end,
-                start_span.line,
-                start_span.column,
-            );
-
-            Ok(Expression::Pair {
-                first: Box::new(first),
-                second,
-                span,
-            })
+            let span = start_span.to(&end_span);
+
+            if elements.len() == 2 {
+                let second = Box::new(elements.pop().unwrap());
+                let first = Box::new(elements.pop().unwrap());
+                Ok(Expression::Pair {
+                    first,
+                    second,
+                    span,
+                })
+            } else {
+                Ok(Expression::Tuple { elements, span })
+            }
         } else {
             self.consume(Token::RightParen, "Expected ')'")?;
             Ok(first)
@@ -557,12 +1029,7 @@ impl Parser {
         if self.peek().token == Token::RightBracket {
             self.advance(); // consume ']'
             let end_span = self.previous_span();
-            let span = Span::new(
-                start_span.start,
-                end_span.end,
-                start_span.line,
-                start_span.column,
-            );
+            let span = start_span.to(&end_span);
             return Ok(Expression::List { elements, span });
         }
 
@@ -580,18 +1047,13 @@ impl Parser {
 
         self.consume(Token::RightBracket, "Expected ']' to close list")?;
         let end_span = self.previous_span();
-        let span = Span::new(
-            start_span.start,
-            end_span.end,
-            start_span.line,
-            start_span.column,
-        );
+        let span = start_span.to(&end_span);
 
         Ok(Expression::List { elements, span })
     }
 
     fn parse_type_expression(&mut self) -> ParseResult<TypeExpression> {
-        self.parse_function_type()
+        self.nested(Self::parse_function_type)
     }
 
     fn parse_function_type(&mut self) -> ParseResult<TypeExpression> {
@@ -600,12 +1062,7 @@ impl Parser {
         while self.peek().token == Token::Arrow {
             self.advance(); // consume '->'
             let right = self.parse_sum_type()?;
-            let span = Span::new(
-                left.span().start,
-                right.span().end,
-                left.span().line,
-                left.span().column,
-            );
+            let span = left.span().to(right.span());
             left = TypeExpression::Function {
                 param: Box::new(left),
                 result: Box::new(right),
@@ -622,12 +1079,7 @@ impl Parser {
         while self.peek().token == Token::Plus {
             self.advance(); // consume '+'
             let right = self.parse_primary_type()?;
-            let span = Span::new(
-                left.span().start,
-                right.span().end,
-                left.span().line,
-                left.span().column,
-            );
+            let span = left.span().to(right.span());
             left = TypeExpression::Sum {
                 left: Box::new(left),
                 right: Box::new(right),
@@ -655,24 +1107,26 @@ impl Parser {
             }
             Token::List => {
                 let start_span = self.previous_span();
-                let element = Box::new(self.parse_function_type()?);
-                let span = Span::new(
-                    start_span.start,
-                    element.span().end,
-                    start_span.line,
-                    start_span.column,
-                );
+                let element = Box::new(self.parse_type_expression()?);
+                let span = start_span.to(element.span());
                 Ok(TypeExpression::List { element, span })
             }
+            Token::Handle => {
+                let start_span = self.previous_span();
+                let result = Box::new(self.parse_type_expression()?);
+                let span = start_span.to(result.span());
+                Ok(TypeExpression::Handle { result, span })
+            }
+            Token::Channel => {
+                let start_span = self.previous_span();
+                let element = Box::new(self.parse_type_expression()?);
+                let span = start_span.to(element.span());
+                Ok(TypeExpression::Channel { element, span })
+            }
             Token::Rec => {
                 let start_span = self.previous_span();
-                let inner = Box::new(self.parse_function_type()?);
-                let span = Span::new(
-                    start_span.start,
-                    inner.span().end,
-                    start_span.line,
-                    start_span.column,
-                );
+                let inner = Box::new(self.parse_type_expression()?);
+                let span = start_span.to(inner.span());
                 Ok(TypeExpression::Recursive { inner, span })
             }
             Token::Identifier(name) => {
@@ -680,24 +1134,28 @@ impl Parser {
                 Ok(TypeExpression::Named { name, span })
             }
             Token::LeftParen => {
-                // Parse pair type (T1, T2) or parenthesized type
-                let first = self.parse_function_type()?;
+                // Parse pair type (T1, T2), tuple type (T1, T2, T3, ...) or parenthesized type
+                let first = self.parse_type_expression()?;
                 if self.peek().token == Token::Comma {
-                    self.advance(); // consume ','
-                    let second = Box::new(self.parse_function_type()?);
+                    let mut elements = vec![first];
+                    while self.peek().token == Token::Comma {
+                        self.advance(); // consume ','
+                        elements.push(self.parse_type_expression()?);
+                    }
                     self.consume(Token::RightParen, "Expected ')')")?;
                     let end_span = self.previous_span();
-                    let span = Span::new(
-                        first.span().start,
-                        end_span.end,
-                        first.span().line,
-                        first.span().column,
-                    );
-                    Ok(TypeExpression::Pair {
-                        first: Box::new(first),
-                        second,
-                        span,
-                    })
+                    let span = elements[0].span().to(&end_span);
+                    if elements.len() == 2 {
+                        let second = Box::new(elements.pop().unwrap());
+                        let first = Box::new(elements.pop().unwrap());
+                        Ok(TypeExpression::Pair {
+                            first,
+                            second,
+                            span,
+                        })
+                    } else {
+                        Ok(TypeExpression::Tuple { elements, span })
+                    }
                 } else {
                     self.consume(Token::RightParen, "Expected ')')")?;
                     Ok(first) // Parenthesized type
@@ -724,6 +1182,27 @@ impl Parser {
         }
     }
 
+    /// The keyword of the builtin just consumed, for its error messages
+    fn builtin_name(&self) -> &'static str {
+        self.previous().token.keyword().unwrap_or_default()
+    }
+
+    /// The `)` closing a function's only parameter. A `,` there means the
+    /// function was written with several parameters, which the language
+    /// spells as a function returning a function.
+    fn consume_parameter_end(&mut self) -> ParseResult<&TokenWithSpan> {
+        if self.check(&Token::Comma) {
+            return Err(ParseError::UnexpectedToken {
+                expected: "Expected ')' after parameter (functions take one parameter; \
+                           take more by returning a function, as in 'fn(a) { fn(b) { a + b } }')"
+                    .to_string(),
+                found: self.peek().token.clone(),
+                span: self.current_span(),
+            });
+        }
+        self.consume(Token::RightParen, "Expected ')' after parameter")
+    }
+
     fn check(&self, token: &Token) -> bool {
         if self.is_at_end() {
             false
@@ -732,10 +1211,13 @@ impl Parser {
         }
     }
 
+    /// Consume the current token and return it. At the end of the input the
+    /// `Eof` token is returned without being consumed.
     fn advance(&mut self) -> &TokenWithSpan {
-        if !self.is_at_end() {
-            self.current += 1;
+        if self.is_at_end() {
+            return self.peek();
         }
+        self.current += 1;
         self.previous()
     }
 
@@ -744,11 +1226,39 @@ impl Parser {
     }
 
     fn peek(&self) -> &TokenWithSpan {
-        &self.tokens[self.current]
+        let index = self.current.min(self.tokens.len() - 1);
+        &self.tokens[index]
+    }
+
+    fn peek_next(&self) -> &TokenWithSpan {
+        let index = (self.current + 1).min(self.tokens.len() - 1);
+        &self.tokens[index]
     }
 
     fn previous(&self) -> &TokenWithSpan {
-        &self.tokens[self.current - 1]
+        let index = self.current.saturating_sub(1).min(self.tokens.len() - 1);
+        &self.tokens[index]
+    }
+
+    /// Parse one nesting level deeper, failing once the input nests more
+    /// than `MAX_NESTING_DEPTH` levels
+    fn nested<T>(&mut self, parse: impl FnOnce(&mut Self) -> ParseResult<T>) -> ParseResult<T> {
+        if self.depth >= MAX_NESTING_DEPTH {
+            return Err(ParseError::InvalidExpression {
+                message: format!("Nesting is deeper than {} levels", MAX_NESTING_DEPTH),
+                span: self.current_span(),
+            });
+        }
+        self.depth += 1;
+        let result = parse(self);
+        self.depth -= 1;
+        result
+    }
+
+    /// A fresh id for the name just parsed
+    fn next_id(&mut self) -> NodeId {
+        self.names.push(self.previous_span());
+        NodeId(self.names.len() as u32)
     }
 
     fn current_span(&self) -> Span {
@@ -776,12 +1286,7 @@ impl Parser {
         self.consume(Token::RightParen, "Expected ')' after expression in fst")?;
 
         let end_span = self.previous_span();
-        let span = Span::new(
-            start_span.start,
-            end_span.end,
-            start_span.line,
-            start_span.column,
-        );
+        let span = start_span.to(&end_span);
 
         Ok(Expression::FirstProjection { pair, span })
     }
@@ -794,12 +1299,7 @@ impl Parser {
         self.consume(Token::RightParen, "Expected ')' after expression in snd")?;
 
         let end_span = self.previous_span();
-        let span = Span::new(
-            start_span.start,
-            end_span.end,
-            start_span.line,
-            start_span.column,
-        );
+        let span = start_span.to(&end_span);
 
         Ok(Expression::SecondProjection { pair, span })
     }
@@ -814,12 +1314,7 @@ impl Parser {
         self.consume(Token::RightParen, "Expected ')' after tail in cons")?;
 
         let end_span = self.previous_span();
-        let span = Span::new(
-            start_span.start,
-            end_span.end,
-            start_span.line,
-            start_span.column,
-        );
+        let span = start_span.to(&end_span);
 
         Ok(Expression::Cons { head, tail, span })
     }
@@ -832,12 +1327,7 @@ impl Parser {
         self.consume(Token::RightParen, "Expected ')' after expression in head")?;
 
         let end_span = self.previous_span();
-        let span = Span::new(
-            start_span.start,
-            end_span.end,
-            start_span.line,
-            start_span.column,
-        );
+        let span = start_span.to(&end_span);
 
         Ok(Expression::HeadProjection { list, span })
     }
@@ -850,12 +1340,7 @@ impl Parser {
         self.consume(Token::RightParen, "Expected ')' after expression in tail")?;
 
         let end_span = self.previous_span();
-        let span = Span::new(
-            start_span.start,
-            end_span.end,
-            start_span.line,
-            start_span.column,
-        );
+        let span = start_span.to(&end_span);
 
         Ok(Expression::TailProjection { list, span })
     }
@@ -868,12 +1353,7 @@ impl Parser {
         self.consume(Token::RightParen, "Expected ')' after expression in print")?;
 
         let end_span = self.previous_span();
-        let span = Span::new(
-            start_span.start,
-            end_span.end,
-            start_span.line,
-            start_span.column,
-        );
+        let span = start_span.to(&end_span);
 
         Ok(Expression::Print { value, span })
     }
@@ -881,6 +1361,11 @@ impl Parser {
     fn parse_if_expression(&mut self) -> ParseResult<Expression> {
         let start_span = self.previous_span();
 
+        if self.peek().token == Token::Let {
+            self.advance(); // consume 'let'
+            return self.parse_if_let_expression(start_span);
+        }
+
         // Parse condition
         let condition = Box::new(self.parse_expression()?);
 
@@ -889,24 +1374,30 @@ impl Parser {
         let then_branch = Box::new(self.parse_block()?);
         self.consume(Token::RightBrace, "Expected '}' after if block")?;
 
-        // Parse else branch (optional)
+        // Parse else branch (optional). `else if` is an else block holding
+        // only the nested `if`.
         let else_branch = if self.peek().token == Token::Else {
             self.advance(); // consume 'else'
-            self.consume(Token::LeftBrace, "Expected '{' after else")?;
-            let else_block = Box::new(self.parse_block()?);
-            self.consume(Token::RightBrace, "Expected '}' after else block")?;
-            Some(else_block)
+            if self.peek().token == Token::If {
+                self.advance(); // consume 'if'
+                let nested = self.parse_if_expression()?;
+                Some(Box::new(Expression::Block {
+                    statements: Vec::new(),
+                    span: nested.span().clone(),
+                    expression: Some(Box::new(nested)),
+                }))
+            } else {
+                self.consume(Token::LeftBrace, "Expected '{' or 'if' after else")?;
+                let else_block = Box::new(self.parse_block()?);
+                self.consume(Token::RightBrace, "Expected '}' after else block")?;
+                Some(else_block)
+            }
         } else {
             None
         };
 
         let end_span = self.previous_span();
-        let span = Span::new(
-            start_span.start,
-            end_span.end,
-            start_span.line,
-            start_span.column,
-        );
+        let span = start_span.to(&end_span);
 
         Ok(Expression::If {
             condition,
@@ -916,20 +1407,89 @@ impl Parser {
         })
     }
 
-    fn parse_for_expression(&mut self) -> ParseResult<Expression> {
-        let start_span = self.previous_span();
+    /// Parses `if let inl(x) = expr { ... } else { ... }` and desugars it into a
+    /// `case` expression. The branch that is not matched by the pattern runs the
+    /// else block, or evaluates to Unit when there is no else block.
+    fn parse_if_let_expression(&mut self, start_span: Span) -> ParseResult<Expression> {
+        let is_left = match self.advance().token {
+            Token::Inl => true,
+            Token::Inr => false,
+            _ => {
+                return Err(ParseError::UnexpectedToken {
+                    expected: "'inl' or 'inr' pattern after 'if let'".to_string(),
+                    found: self.previous().token.clone(),
+                    span: self.previous_span(),
+                });
+            }
+        };
 
-        // Parse: for variable in iterable { body }
-        let variable = if let Token::Identifier(name) = &self.advance().token {
-            name.clone()
+        let has_paren = self.peek().token == Token::LeftParen;
+        if has_paren {
+            self.advance(); // consume '('
+        }
+        let pattern = if self.peek().token == Token::Underscore {
+            self.advance();
+            "_".to_string()
         } else {
-            return Err(ParseError::UnexpectedToken {
-                expected: "variable name".to_string(),
-                found: self.previous().token.clone(),
+            self.parse_name("pattern variable")?
+        };
+        let id = self.next_id();
+        if has_paren {
+            self.consume(Token::RightParen, "Expected ')' after pattern")?;
+        }
+
+        self.consume(Token::Assign, "Expected '=' after if let pattern")?;
+        let expression = Box::new(self.parse_expression()?);
+
+        self.consume(Token::LeftBrace, "Expected '{' after if let expression")?;
+        let matched_branch = Box::new(self.parse_block()?);
+        self.consume(Token::RightBrace, "Expected '}' after if let block")?;
+
+        let other_branch = if self.peek().token == Token::Else {
+            self.advance(); // consume 'else'
+            self.consume(Token::LeftBrace, "Expected '{' after else")?;
+            let else_block = Box::new(self.parse_block()?);
+            self.consume(Token::RightBrace, "Expected '}' after else block")?;
+            else_block
+        } else {
+            Box::new(Expression::Block {
+                statements: Vec::new(),
+                expression: None,
                 span: self.previous_span(),
-            });
+            })
+        };
+
+        let end_span = self.previous_span();
+        let span = start_span.to(&end_span);
+
+        // The other side binds nothing
+        let ignored = ("_".to_string(), NodeId::default(), other_branch);
+        let ((left_pattern, left_id, left_body), (right_pattern, right_id, right_body)) = if is_left
+        {
+            ((pattern, id, matched_branch), ignored)
+        } else {
+            (ignored, (pattern, id, matched_branch))
         };
 
+        Ok(Expression::Case {
+            expression,
+            left_pattern,
+            left_body,
+            right_pattern,
+            right_body,
+            left_id,
+            right_id,
+            span,
+        })
+    }
+
+    fn parse_for_expression(&mut self) -> ParseResult<Expression> {
+        let start_span = self.previous_span();
+
+        // Parse: for variable in iterable { body }
+        let variable = self.parse_name("loop variable")?;
+        let id = self.next_id();
+
         self.consume(Token::In, "Expected 'in' after for variable")?;
         let iterable = Box::new(self.parse_expression()?);
         self.consume(Token::LeftBrace, "Expected '{' to start for body")?;
@@ -937,17 +1497,38 @@ impl Parser {
         self.consume(Token::RightBrace, "Expected '}' to end for body")?;
 
         let end_span = self.previous_span();
-        let span = Span::new(
-            start_span.start,
-            end_span.end,
-            start_span.line,
-            start_span.column,
-        );
+        let span = start_span.to(&end_span);
 
         Ok(Expression::For {
             variable,
             iterable,
             body,
+            id,
+            span,
+        })
+    }
+
+    fn parse_with_expression(&mut self) -> ParseResult<Expression> {
+        let start_span = self.previous_span();
+
+        // Parse: with name = value { body }
+        let name = self.parse_name("resource name")?;
+        let id = self.next_id();
+
+        self.consume(Token::Assign, "Expected '=' after with name")?;
+        let value = Box::new(self.parse_expression()?);
+        self.consume(Token::LeftBrace, "Expected '{' to start with body")?;
+        let body = Box::new(self.parse_block()?);
+        self.consume(Token::RightBrace, "Expected '}' to end with body")?;
+
+        let end_span = self.previous_span();
+        let span = start_span.to(&end_span);
+
+        Ok(Expression::With {
+            name,
+            value,
+            body,
+            id,
             span,
         })
     }
@@ -962,12 +1543,7 @@ impl Parser {
         self.consume(Token::RightParen, "Expected ')' after range end")?;
 
         let end_span = self.previous_span();
-        let span = Span::new(
-            start_span.start,
-            end_span.end,
-            start_span.line,
-            start_span.column,
-        );
+        let span = start_span.to(&end_span);
 
         Ok(Expression::Range { start, end, span })
     }
@@ -980,12 +1556,7 @@ impl Parser {
         self.consume(Token::RightParen, "Expected ')' after fix function")?;
 
         let end_span = self.previous_span();
-        let span = Span::new(
-            start_span.start,
-            end_span.end,
-            start_span.line,
-            start_span.column,
-        );
+        let span = start_span.to(&end_span);
 
         Ok(Expression::Fix { function, span })
     }
@@ -996,12 +1567,7 @@ impl Parser {
         let value = Box::new(self.parse_expression()?);
         self.consume(Token::RightParen, "Expected ')' after inl value")?;
         let end_span = self.previous_span();
-        let span = Span::new(
-            start_span.start,
-            end_span.end,
-            start_span.line,
-            start_span.column,
-        );
+        let span = start_span.to(&end_span);
         Ok(Expression::LeftInject { value, span })
     }
 
@@ -1011,12 +1577,7 @@ impl Parser {
         let value = Box::new(self.parse_expression()?);
         self.consume(Token::RightParen, "Expected ')' after inr value")?;
         let end_span = self.previous_span();
-        let span = Span::new(
-            start_span.start,
-            end_span.end,
-            start_span.line,
-            start_span.column,
-        );
+        let span = start_span.to(&end_span);
         Ok(Expression::RightInject { value, span })
     }
 
@@ -1026,54 +1587,221 @@ impl Parser {
         // case expression of
         let expression = Box::new(self.parse_expression()?);
         self.consume(Token::Of, "Expected 'of' after case expression")?;
+        if !matches!(self.peek().token, Token::Inl | Token::Inr) {
+            return self.parse_case_arms(start_span, expression);
+        }
 
-        // inl x => body
-        self.consume(Token::Inl, "Expected 'inl' in first case branch")?;
-        let left_pattern = if let Token::Identifier(name) = &self.advance().token {
-            name.clone()
-        } else {
+        // inl x => body | inr y => body, in either order
+        let first = self.parse_sum_arm()?;
+        self.consume(Token::Pipe, "Expected '|' between case branches")?;
+        let second_span = self.current_span();
+        let second = self.parse_sum_arm()?;
+        let end_span = second.3.span().clone();
+        let ((left_pattern, left_id, left_body), (right_pattern, right_id, right_body)) = match (
+            first, second,
+        ) {
+            ((Token::Inl, l, l_id, left), (Token::Inr, r, r_id, right))
+            | ((Token::Inr, r, r_id, right), (Token::Inl, l, l_id, left)) => {
+                ((l, l_id, left), (r, r_id, right))
+            }
+            (_, (side, _, _, _)) => {
+                return Err(ParseError::InvalidExpression {
+                    message: format!(
+                        "a case on a sum needs one 'inl' and one 'inr' branch, found two '{}' branches",
+                        if side == Token::Inl { "inl" } else { "inr" }
+                    ),
+                    span: second_span,
+                });
+            }
+        };
+        let (left_body, right_body) = (Box::new(left_body), Box::new(right_body));
+        let span = start_span.to(&end_span);
+
+        Ok(Expression::Case {
+            expression,
+            left_pattern,
+            left_body,
+            right_pattern,
+            right_body,
+            left_id,
+            right_id,
+            span,
+        })
+    }
+
+    /// One branch of a `case` on a sum: `inl x => body` or `inr y => body`,
+    /// where `_` can stand for a value the body ignores
+    fn parse_sum_arm(&mut self) -> ParseResult<(Token, String, NodeId, Expression)> {
+        let side = self.peek().token.clone();
+        if !matches!(side, Token::Inl | Token::Inr) {
             return Err(ParseError::UnexpectedToken {
-                expected: "identifier".to_string(),
-                found: self.previous().token.clone(),
-                span: self.previous_span(),
+                expected: "Expected 'inl' or 'inr' in case branch".to_string(),
+                found: side,
+                span: self.current_span(),
             });
+        }
+        self.advance();
+        let pattern = if self.peek().token == Token::Underscore {
+            self.advance();
+            "_".to_string()
+        } else {
+            self.parse_name("pattern variable")?
         };
+        let id = self.next_id();
         self.consume(Token::FatArrow, "Expected '=>' after pattern")?;
-        let left_body = Box::new(self.parse_expression()?);
+        let body = self.parse_expression()?;
+        Ok((side, pattern, id, body))
+    }
 
-        // | inr y => body
-        self.consume(Token::Pipe, "Expected '|' between case branches")?;
-        self.consume(Token::Inr, "Expected 'inr' in second case branch")?;
-        let right_pattern = if let Token::Identifier(name) = &self.advance().token {
-            name.clone()
+    /// The arms of a `case` on values: `pattern => body`, separated by `|`
+    fn parse_case_arms(
+        &mut self,
+        start_span: Span,
+        expression: Box<Expression>,
+    ) -> ParseResult<Expression> {
+        let mut arms = Vec::new();
+        loop {
+            let pattern = self.parse_pattern()?;
+            self.consume(Token::FatArrow, "Expected '=>' after pattern")?;
+            let body = self.parse_expression()?;
+            arms.push(CaseArm { pattern, body });
+            if self.peek().token != Token::Pipe {
+                break;
+            }
+            self.advance(); // consume '|'
+        }
+
+        let span = start_span.to(&self.previous_span());
+        Ok(Expression::Match {
+            expression,
+            arms,
+            span,
+        })
+    }
+
+    fn parse_pattern(&mut self) -> ParseResult<Pattern> {
+        let start_span = self.current_span();
+        if self.peek().token == Token::Underscore {
+            self.advance();
+            return Ok(Pattern::Wildcard { span: start_span });
+        }
+        if let Token::StringLiteral(value) = &self.peek().token {
+            let value = value.clone();
+            self.advance();
+            return Ok(Pattern::String {
+                value,
+                span: start_span,
+            });
+        }
+        if matches!(
+            self.peek().token,
+            Token::Number(_) | Token::Minus | Token::DotDot
+        ) {
+            return self.parse_int_pattern(start_span);
+        }
+        let name = self.parse_name("enum name")?;
+        self.consume(Token::Period, "Expected '.' after enum name in pattern")?;
+        let variant = self.parse_name("variant name")?;
+        Ok(Pattern::Variant {
+            enum_name: name,
+            variant,
+            span: start_span.to(&self.previous_span()),
+        })
+    }
+
+    /// An integer pattern, `5`, or a range, `1..10`, `..0` or `10..`
+    fn parse_int_pattern(&mut self, start_span: Span) -> ParseResult<Pattern> {
+        let start = if self.check(&Token::DotDot) {
+            None
         } else {
-            return Err(ParseError::UnexpectedToken {
-                expected: "identifier".to_string(),
-                found: self.previous().token.clone(),
-                span: self.previous_span(),
+            Some(self.parse_pattern_number()?)
+        };
+        if !self.check(&Token::DotDot) {
+            return Ok(Pattern::Int {
+                value: start.unwrap_or_default(),
+                span: start_span.to(&self.previous_span()),
             });
+        }
+        self.advance(); // consume '..'
+        let end = if start.is_none() || matches!(self.peek().token, Token::Number(_) | Token::Minus)
+        {
+            Some(self.parse_pattern_number()?)
+        } else {
+            None
         };
-        self.consume(Token::FatArrow, "Expected '=>' after pattern")?;
-        let right_body = Box::new(self.parse_expression()?);
 
-        let end_span = right_body.span().clone();
-        let span = Span::new(
-            start_span.start,
-            end_span.end,
-            start_span.line,
-            start_span.column,
-        );
+        let span = start_span.to(&self.previous_span());
+        match (start, end) {
+            (Some(start), Some(end)) if start >= end => Err(ParseError::InvalidExpression {
+                message: format!(
+                    "the range {}..{} matches no values; its end is not included",
+                    start, end
+                ),
+                span,
+            }),
+            _ => Ok(Pattern::Range { start, end, span }),
+        }
+    }
 
-        Ok(Expression::Case {
-            expression,
-            left_pattern,
-            left_body,
-            right_pattern,
-            right_body,
+    /// An integer literal in a pattern, possibly negated
+    fn parse_pattern_number(&mut self) -> ParseResult<i64> {
+        let negative = self.check(&Token::Minus);
+        if negative {
+            self.advance();
+        }
+        match self.peek().token {
+            Token::Number(value) => {
+                self.advance();
+                Ok(if negative { -value } else { value })
+            }
+            _ => Err(ParseError::UnexpectedToken {
+                expected: "Expected an integer in pattern".to_string(),
+                found: self.peek().token.clone(),
+                span: self.current_span(),
+            }),
+        }
+    }
+
+    fn parse_divmod_expression(&mut self) -> ParseResult<Expression> {
+        let (dividend, divisor, span) = self.parse_division_arguments()?;
+        Ok(Expression::DivMod {
+            dividend,
+            divisor,
+            span,
+        })
+    }
+
+    fn parse_safe_div_expression(&mut self) -> ParseResult<Expression> {
+        let (dividend, divisor, span) = self.parse_division_arguments()?;
+        Ok(Expression::SafeDiv {
+            dividend,
+            divisor,
             span,
         })
     }
 
+    /// Parses the `(dividend, divisor)` arguments of a division builtin
+    fn parse_division_arguments(
+        &mut self,
+    ) -> ParseResult<(Box<Expression>, Box<Expression>, Span)> {
+        let start_span = self.previous_span();
+        let name = self.builtin_name();
+
+        self.consume(Token::LeftParen, &format!("Expected '(' after '{}'", name))?;
+        let dividend = Box::new(self.parse_expression()?);
+        self.consume(Token::Comma, &format!("Expected ',' in {}", name))?;
+        let divisor = Box::new(self.parse_expression()?);
+        self.consume(
+            Token::RightParen,
+            &format!("Expected ')' after {} divisor", name),
+        )?;
+
+        let end_span = self.previous_span();
+        let span = start_span.to(&end_span);
+
+        Ok((dividend, divisor, span))
+    }
+
     fn parse_concat_expression(&mut self) -> ParseResult<Expression> {
         let start_span = self.previous_span();
 
@@ -1084,12 +1812,7 @@ impl Parser {
         self.consume(Token::RightParen, "Expected ')' after concat right")?;
 
         let end_span = self.previous_span();
-        let span = Span::new(
-            start_span.start,
-            end_span.end,
-            start_span.line,
-            start_span.column,
-        );
+        let span = start_span.to(&end_span);
 
         Ok(Expression::Concat { left, right, span })
     }
@@ -1104,12 +1827,7 @@ impl Parser {
         self.consume(Token::RightParen, "Expected ')' after char index")?;
 
         let end_span = self.previous_span();
-        let span = Span::new(
-            start_span.start,
-            end_span.end,
-            start_span.line,
-            start_span.column,
-        );
+        let span = start_span.to(&end_span);
 
         Ok(Expression::CharAt {
             string,
@@ -1126,16 +1844,93 @@ impl Parser {
         self.consume(Token::RightParen, "Expected ')' after length string")?;
 
         let end_span = self.previous_span();
-        let span = Span::new(
-            start_span.start,
-            end_span.end,
-            start_span.line,
-            start_span.column,
-        );
+        let span = start_span.to(&end_span);
 
         Ok(Expression::Length { string, span })
     }
 
+    fn parse_graphemes_expression(&mut self) -> ParseResult<Expression> {
+        let start_span = self.previous_span();
+
+        self.consume(Token::LeftParen, "Expected '(' after 'graphemes'")?;
+        let string = Box::new(self.parse_expression()?);
+        self.consume(Token::RightParen, "Expected ')' after graphemes string")?;
+
+        let end_span = self.previous_span();
+        let span = start_span.to(&end_span);
+
+        Ok(Expression::Graphemes { string, span })
+    }
+
+    /// Parses the empty `()` after a builtin that takes no arguments, such
+    /// as `channel`, returning the span of the whole call
+    fn parse_no_arguments(&mut self) -> ParseResult<Span> {
+        let start_span = self.previous_span();
+        let name = self.builtin_name();
+
+        self.consume(Token::LeftParen, &format!("Expected '(' after '{}'", name))?;
+        self.consume(
+            Token::RightParen,
+            &format!("Expected ')' after '{}('", name),
+        )?;
+
+        let end_span = self.previous_span();
+        Ok(start_span.to(&end_span))
+    }
+
+    /// Parses the parenthesized argument of the one-argument builtin just
+    /// consumed
+    fn parse_single_argument(&mut self) -> ParseResult<(Box<Expression>, Span)> {
+        let start_span = self.previous_span();
+        let name = self.builtin_name();
+
+        self.consume(Token::LeftParen, &format!("Expected '(' after '{}'", name))?;
+        let argument = Box::new(self.parse_expression()?);
+        self.consume(
+            Token::RightParen,
+            &format!("Expected ')' after {} argument", name),
+        )?;
+
+        let end_span = self.previous_span();
+        let span = start_span.to(&end_span);
+
+        Ok((argument, span))
+    }
+
+    fn parse_send_expression(&mut self) -> ParseResult<Expression> {
+        let start_span = self.previous_span();
+
+        self.consume(Token::LeftParen, "Expected '(' after 'send'")?;
+        let channel = Box::new(self.parse_expression()?);
+        self.consume(Token::Comma, "Expected ',' in send")?;
+        let value = Box::new(self.parse_expression()?);
+        self.consume(Token::RightParen, "Expected ')' after send value")?;
+
+        let end_span = self.previous_span();
+        let span = start_span.to(&end_span);
+
+        Ok(Expression::Send {
+            channel,
+            value,
+            span,
+        })
+    }
+
+    fn parse_byte_at_expression(&mut self) -> ParseResult<Expression> {
+        let start_span = self.previous_span();
+
+        self.consume(Token::LeftParen, "Expected '(' after 'byteAt'")?;
+        let bytes = Box::new(self.parse_expression()?);
+        self.consume(Token::Comma, "Expected ',' in byteAt")?;
+        let index = Box::new(self.parse_expression()?);
+        self.consume(Token::RightParen, "Expected ')' after byteAt index")?;
+
+        let end_span = self.previous_span();
+        let span = start_span.to(&end_span);
+
+        Ok(Expression::ByteAt { bytes, index, span })
+    }
+
     fn parse_tostring_expression(&mut self) -> ParseResult<Expression> {
         let start_span = self.previous_span();
 
@@ -1144,12 +1939,7 @@ impl Parser {
         self.consume(Token::RightParen, "Expected ')' after toString expression")?;
 
         let end_span = self.previous_span();
-        let span = Span::new(
-            start_span.start,
-            end_span.end,
-            start_span.line,
-            start_span.column,
-        );
+        let span = start_span.to(&end_span);
 
         Ok(Expression::ToString { expression, span })
     }
@@ -1162,13 +1952,41 @@ impl Parser {
         self.consume(Token::RightParen, "Expected ')' after type expression")?;
 
         let end_span = self.previous_span();
-        let span = Span::new(
-            start_span.start,
-            end_span.end,
-            start_span.line,
-            start_span.column,
-        );
+        let span = start_span.to(&end_span);
 
         Ok(Expression::TypeOf { expression, span })
     }
 }
+
+/// Whether `token` can follow a complete value but never starts one, so a
+/// keyword before it cannot be the start of the construct it introduces
+fn ends_operand(token: &Token) -> bool {
+    matches!(
+        token,
+        Token::Semicolon
+            | Token::Comma
+            | Token::RightParen
+            | Token::RightBracket
+            | Token::RightBrace
+            | Token::Eof
+            | Token::Assign
+            | Token::Plus
+            | Token::Append
+            | Token::Multiply
+            | Token::Divide
+            | Token::Equal
+            | Token::NotEqual
+            | Token::LessThan
+            | Token::LessThanEqual
+            | Token::GreaterThan
+            | Token::GreaterThanEqual
+            | Token::LogicalAnd
+            | Token::LogicalOr
+            | Token::ComposeForward
+            | Token::ComposeBackward
+            | Token::DoubleColon
+            | Token::Period
+            | Token::FatArrow
+            | Token::Pipe
+    )
+}
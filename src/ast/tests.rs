@@ -1,7 +1,8 @@
 #[cfg(test)]
+#[allow(clippy::module_inception)]
 mod tests {
     use crate::ast::parser::ParseError;
-    use crate::ast::{Expression, Parser, Statement};
+    use crate::ast::{BinaryOperator, Expression, Parser, Statement};
     use crate::lexer::tokens::{Span, Token, TokenWithSpan};
 
     fn create_test_span() -> Span {
@@ -140,7 +141,7 @@ mod tests {
             ParseError::UnexpectedToken {
                 expected, found, ..
             } => {
-                assert_eq!(expected, "identifier");
+                assert_eq!(expected, "variable name");
                 assert_eq!(found, Token::Number(42));
             }
             _ => panic!("Expected unexpected token error"),
@@ -443,4 +444,153 @@ mod tests {
             _ => panic!("Expected expression statement"),
         }
     }
+
+    #[test]
+    fn test_parse_without_eof_token() {
+        let tokens = vec![create_token_with_span(Token::Let)];
+        assert!(Parser::new(tokens).parse().is_err());
+        assert!(Parser::new(vec![]).parse().unwrap().statements.is_empty());
+    }
+
+    #[test]
+    fn test_parse_type_cut_off_at_eof() {
+        let tokens = vec![
+            create_token_with_span(Token::Let),
+            create_token_with_span(Token::Identifier("xs".to_string())),
+            create_token_with_span(Token::Colon),
+            create_token_with_span(Token::List),
+            create_token_with_span(Token::Eof),
+        ];
+        assert!(Parser::new(tokens).parse().is_err());
+    }
+
+    #[test]
+    fn test_parse_rejects_excessive_nesting() {
+        let nested = |depth: usize| {
+            let mut tokens = vec![create_token_with_span(Token::LeftParen); depth];
+            tokens.push(create_token_with_span(Token::Number(1)));
+            tokens.extend(vec![create_token_with_span(Token::RightParen); depth]);
+            tokens.push(create_token_with_span(Token::Semicolon));
+            Parser::new(tokens).parse()
+        };
+
+        assert!(nested(60).is_ok());
+        match nested(10_000) {
+            Err(ParseError::InvalidExpression { message, .. }) => {
+                assert_eq!(message, "Nesting is deeper than 64 levels")
+            }
+            other => panic!("Expected a nesting error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_spans_record_where_constructs_end() {
+        use crate::ast::Spanned;
+        use crate::lexer::Tokenizer;
+
+        let source = "fn f(x) {\n    x\n}\nlet y = f(1);";
+        let tokens = Tokenizer::new(source).tokenize().unwrap();
+        let program = Parser::new(tokens).parse().unwrap();
+
+        let function = program.statements[0].span();
+        assert_eq!((function.line, function.column), (1, 1));
+        assert_eq!((function.end_line, function.end_column), (3, 2));
+
+        let declaration = program.statements[1].span();
+        assert_eq!((declaration.line, declaration.column), (4, 1));
+        assert_eq!((declaration.end_line, declaration.end_column), (4, 14));
+        assert_eq!(&source[declaration.start..declaration.end], "let y = f(1);");
+    }
+
+    #[test]
+    fn test_unclosed_delimiters_point_at_where_they_open() {
+        use crate::lexer::Tokenizer;
+
+        let parse = |source: &str| Parser::new(Tokenizer::new(source).tokenize().unwrap()).parse();
+
+        let error = parse("fn f(x: Int) {\n    let y = x + 1;\n    y\n\nlet z = 3;\n").unwrap_err();
+        assert_eq!(error.code(), "E0104");
+        assert_eq!(
+            error.to_string(),
+            "Unclosed '{' opened at line 1, column 14: the input ends at line 6, column 1 before it is closed"
+        );
+        assert_eq!(error.span().map(|span| span.line), Some(1));
+
+        // A different closing delimiter ends the innermost open one
+        match parse("let xs = [1, (2, 3];") {
+            Err(ParseError::UnclosedDelimiter {
+                delimiter,
+                opened,
+                found,
+                span,
+            }) => {
+                assert_eq!((delimiter, found), ('(', Some(']')));
+                assert_eq!((opened.column, span.column), (14, 19));
+            }
+            other => panic!("Expected an unclosed delimiter, got {:?}", other),
+        }
+
+        // Brackets in strings do not count, and a stray closer is the parser's
+        assert!(parse("let s = \"{(\";").is_ok());
+        assert!(matches!(
+            parse("let x = 1);"),
+            Err(ParseError::UnexpectedToken { .. })
+        ));
+    }
+
+    #[test]
+    fn test_flat_chains_are_not_counted_as_nesting() {
+        use crate::lexer::Tokenizer;
+
+        let parse = |source: &str| Parser::new(Tokenizer::new(source).tokenize().unwrap()).parse();
+
+        let elements: Vec<String> = (0..200).map(|n| n.to_string()).collect();
+        let program = parse(&format!("let xs = {} :: [];", elements.join(" :: "))).unwrap();
+        let Statement::VariableDeclaration { value, .. } = &program.statements[0] else {
+            panic!("Expected a variable declaration");
+        };
+        let mut length = 0;
+        let mut rest = value;
+        while let Expression::Cons { head, tail, .. } = rest {
+            assert!(matches!(**head, Expression::Number { value, .. } if value == length));
+            length += 1;
+            rest = tail;
+        }
+        assert_eq!(length, 200);
+        assert!(matches!(rest, Expression::List { elements, .. } if elements.is_empty()));
+
+        let program = parse(&format!("let x = {}1;", "- ".repeat(200))).unwrap();
+        let Statement::VariableDeclaration { value, .. } = &program.statements[0] else {
+            panic!("Expected a variable declaration");
+        };
+        let mut operators = 0;
+        let mut rest = value;
+        while let Expression::UnaryOp { operand, .. } = rest {
+            operators += 1;
+            rest = operand;
+        }
+        assert_eq!(operators, 200);
+
+        // A left-associative operator of the same precedence still groups
+        // inside the right operand, as `f << (g >> h)`
+        let program = parse("f << g >> h;").unwrap();
+        let Statement::Expression { expression, .. } = &program.statements[0] else {
+            panic!("Expected an expression statement");
+        };
+        let Expression::BinaryOp {
+            operator: BinaryOperator::ComposeBackward,
+            right,
+            ..
+        } = expression
+        else {
+            panic!("Expected '<<' at the top, got {:?}", expression);
+        };
+        assert!(matches!(
+            **right,
+            Expression::BinaryOp {
+                operator: BinaryOperator::ComposeForward,
+                ..
+            }
+        ));
+    }
 }
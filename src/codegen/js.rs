@@ -0,0 +1,807 @@
+//! JavaScript backend used by `--emit=js`. Corrosion functions become arrow
+//! functions and blocks become JavaScript blocks, so the output stays close to
+//! the source; everything else goes through the small runtime in
+//! `runtime/corrosion.js`, which is copied into every generated file.
+
+use super::{CodegenError, CodegenResult};
+use crate::ast::nodes::{
+    BinaryOperator, Child, ENTRY_POINT, Expression, Pattern, Program, Statement, TypeExpression,
+    UnaryOperator, hole_text,
+};
+use crate::lexer::tokens::Span;
+use crate::stdlib::prelude;
+use crate::typechecker::checker::prelude_operator_types;
+use crate::typechecker::{OperatorTypes, Type};
+use std::collections::{HashMap, HashSet};
+
+const RUNTIME: &str = include_str!("runtime/corrosion.js");
+
+/// Names that cannot be used for JavaScript bindings, or that the generated
+/// code relies on. Corrosion names in this list get a `$` appended.
+const RESERVED: &[&str] = &[
+    "arguments",
+    "await",
+    "break",
+    "case",
+    "catch",
+    "class",
+    "const",
+    "continue",
+    "debugger",
+    "default",
+    "delete",
+    "do",
+    "enum",
+    "eval",
+    "extends",
+    "finally",
+    "function",
+    "implements",
+    "instanceof",
+    "interface",
+    "new",
+    "null",
+    "package",
+    "private",
+    "process",
+    "protected",
+    "public",
+    "return",
+    "static",
+    "super",
+    "switch",
+    "this",
+    "throw",
+    "try",
+    "typeof",
+    "undefined",
+    "var",
+    "void",
+    "while",
+    "with",
+    "yield",
+    "Channel",
+    "Handle",
+    "Inl",
+    "Inr",
+    "Tuple",
+    "Variant",
+];
+
+/// Translate a type-checked program into a standalone JavaScript file, along
+/// with the prelude definitions it uses unless `prelude` is false. `types`
+/// are the operator types the type checker found in `program`. When the
+/// program declares `main`, the file calls it with the command line
+/// arguments under Node, or with an empty list in the browser.
+pub fn generate(program: &Program, types: &OperatorTypes, prelude: bool) -> CodegenResult<String> {
+    let mut generator = JsGenerator {
+        indent: 1,
+        types: prelude_operator_types(),
+        scopes: vec![HashMap::new()],
+        renamed: HashMap::new(),
+        in_iife: false,
+        enums: HashSet::new(),
+    };
+
+    let mut body = String::new();
+    if prelude {
+        for statement in prelude::used_by(program) {
+            generator.statement(statement, &mut body)?;
+        }
+    }
+    generator.types = types;
+    for statement in &program.statements {
+        generator.statement(statement, &mut body)?;
+    }
+    if let Some(main) = generator.scopes[0].get(ENTRY_POINT).cloned() {
+        generator.line(
+            &mut body,
+            &format!(
+                "const $status = {}(typeof process !== \"undefined\" ? process.argv.slice(2) : []);",
+                main
+            ),
+        );
+        // An Int returned by main is the exit status, as in the interpreter
+        generator.line(
+            &mut body,
+            "if (typeof $status === \"bigint\" && typeof process !== \"undefined\") process.exitCode = Number(BigInt.asUintN(8, $status));",
+        );
+    }
+
+    Ok(format!(
+        "// Compiled from Corrosion with --emit=js\n\"use strict\";\n\n{}\n{{\n{}}}\n",
+        RUNTIME, body
+    ))
+}
+
+/// What to do with the value of an expression in statement position
+#[derive(Clone, Copy, PartialEq)]
+enum Tail {
+    Return,
+    Discard,
+}
+
+struct JsGenerator<'a> {
+    indent: usize,
+    /// Types the type checker found for the operators being translated
+    types: &'a OperatorTypes,
+    /// Corrosion names in scope, mapped to the JavaScript names they were given
+    scopes: Vec<HashMap<String, String>>,
+    /// How many times each name has been renamed, to keep new names unique
+    renamed: HashMap<String, usize>,
+    /// Whether the code being written is inside an arrow function wrapping a
+    /// block used as a value, where a JavaScript `return` would only leave
+    /// that arrow function
+    in_iife: bool,
+    /// Enums declared so far, whose variants `Color.Red` refers to
+    enums: HashSet<String>,
+}
+
+impl JsGenerator<'_> {
+    fn line(&self, out: &mut String, text: &str) {
+        out.push_str(&"    ".repeat(self.indent));
+        out.push_str(text);
+        out.push('\n');
+    }
+
+    fn pad(&self) -> String {
+        "    ".repeat(self.indent)
+    }
+
+    fn resolve(&self, name: &str) -> String {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name).cloned())
+            .unwrap_or_else(|| mangle(name))
+    }
+
+    /// Bind a name in the innermost scope. A name that is already visible is
+    /// renamed, since a JavaScript `const` cannot refer to the binding it
+    /// shadows in its own initializer.
+    fn declare(&mut self, name: &str) -> String {
+        let visible = self.scopes.iter().any(|scope| scope.contains_key(name));
+        let js_name = if visible {
+            let count = self.renamed.entry(name.to_string()).or_insert(0);
+            *count += 1;
+            format!("{}${}", name, count)
+        } else {
+            mangle(name)
+        };
+        self.bind(name, js_name)
+    }
+
+    /// Bind a function parameter, which may shadow outer names freely
+    fn declare_param(&mut self, name: &str) -> String {
+        self.bind(name, mangle(name))
+    }
+
+    fn bind(&mut self, name: &str, js_name: String) -> String {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.to_string(), js_name.clone());
+        }
+        js_name
+    }
+
+    /// `Int` arithmetic fails on overflow as the interpreter's does, while
+    /// `BigInt` arithmetic, done on the same JavaScript bigints, does not
+    fn arithmetic(&self, span: &Span, code: String) -> String {
+        match self.types.get(span) {
+            Some(Type::Int) => format!("$int({})", strip_parens(code)),
+            _ => code,
+        }
+    }
+
+    fn statement(&mut self, statement: &Statement, out: &mut String) -> CodegenResult<()> {
+        match statement {
+            Statement::VariableDeclaration { name, value, .. }
+            | Statement::ConstantDeclaration { name, value, .. } => {
+                let value = strip_parens(self.expression(value)?);
+                let name = self.declare(name);
+                self.line(out, &format!("const {} = {};", name, value));
+            }
+            Statement::FunctionDeclaration {
+                name, param, body, ..
+            } => {
+                // Declared before the body so the function can call itself
+                let name = self.declare(name);
+                self.scopes.push(HashMap::new());
+                let param = self.declare_param(param);
+                self.line(out, &format!("function {}({}) {{", name, param));
+                self.indent += 1;
+                let in_iife = std::mem::replace(&mut self.in_iife, false);
+                let result = self.block_contents(body, Tail::Return, out);
+                self.in_iife = in_iife;
+                self.indent -= 1;
+                self.scopes.pop();
+                result?;
+                self.line(out, "}");
+            }
+            Statement::Import { span, .. } => {
+                return Err(CodegenError::new(
+                    "Imports are not supported by the JavaScript backend",
+                    span,
+                ));
+            }
+            // A variant is a `Variant` object built where it is used, so the
+            // declaration itself needs no code
+            Statement::EnumDeclaration { name, .. } => {
+                self.enums.insert(name.clone());
+            }
+            Statement::Expression { expression, .. } => {
+                self.tail(expression, Tail::Discard, out)?;
+            }
+            Statement::Return { span, .. } if self.in_iife => {
+                return Err(CodegenError::new(
+                    "'return' inside a block used as a value is not supported by the JavaScript backend",
+                    span,
+                ));
+            }
+            Statement::Return { value, .. } => {
+                let value = strip_parens(self.expression(value)?);
+                self.line(out, &format!("return {};", value));
+            }
+        }
+        Ok(())
+    }
+
+    /// Emit the statements of a block, or of a single expression standing in
+    /// for one, into the JavaScript block currently being written
+    fn block_contents(
+        &mut self,
+        expression: &Expression,
+        tail: Tail,
+        out: &mut String,
+    ) -> CodegenResult<()> {
+        let Expression::Block {
+            statements,
+            expression,
+            ..
+        } = expression
+        else {
+            return self.tail(expression, tail, out);
+        };
+
+        self.scopes.push(HashMap::new());
+        let result = (|| {
+            for statement in statements {
+                self.statement(statement, out)?;
+            }
+            match expression {
+                Some(expression) => self.tail(expression, tail, out),
+                None => Ok(()),
+            }
+        })();
+        self.scopes.pop();
+        result
+    }
+
+    /// Emit an expression in statement position, returning its value or
+    /// discarding it. Blocks, `if` and `for` become JavaScript statements.
+    fn tail(&mut self, expression: &Expression, tail: Tail, out: &mut String) -> CodegenResult<()> {
+        match expression {
+            Expression::Block { .. } if tail == Tail::Return => {
+                self.block_contents(expression, tail, out)
+            }
+            Expression::Block { .. } => {
+                self.line(out, "{");
+                self.indent += 1;
+                let result = self.block_contents(expression, tail, out);
+                self.indent -= 1;
+                result?;
+                self.line(out, "}");
+                Ok(())
+            }
+            Expression::If {
+                condition,
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                let condition = strip_parens(self.expression(condition)?);
+                self.line(out, &format!("if ({}) {{", condition));
+                let mut then_branch = then_branch;
+                let mut else_branch = else_branch.as_deref();
+                loop {
+                    self.branch(then_branch, tail, out)?;
+                    // `else { if .. }` with nothing else in the block reads as `else if`
+                    let Some(branch) = else_branch else {
+                        break;
+                    };
+                    match nested_if(branch) {
+                        Some(Expression::If {
+                            condition,
+                            then_branch: next_then,
+                            else_branch: next_else,
+                            ..
+                        }) => {
+                            let condition = strip_parens(self.expression(condition)?);
+                            self.line(out, &format!("}} else if ({}) {{", condition));
+                            then_branch = next_then;
+                            else_branch = next_else.as_deref();
+                        }
+                        _ => {
+                            self.line(out, "} else {");
+                            self.branch(branch, tail, out)?;
+                            break;
+                        }
+                    }
+                }
+                self.line(out, "}");
+                Ok(())
+            }
+            Expression::For {
+                variable,
+                iterable,
+                body,
+                ..
+            } => {
+                let iterable = self.expression(iterable)?;
+                self.scopes.push(HashMap::new());
+                let variable = self.declare(variable);
+                self.line(out, &format!("for (const {} of {}) {{", variable, iterable));
+                let result = self.branch(body, Tail::Discard, out);
+                self.scopes.pop();
+                result?;
+                self.line(out, "}");
+                Ok(())
+            }
+            Expression::With {
+                name, value, body, ..
+            } => {
+                let value = strip_parens(self.expression(value)?);
+                self.scopes.push(HashMap::new());
+                let name = self.declare(name);
+                self.line(out, "{");
+                self.indent += 1;
+                self.line(out, &format!("const {} = {};", name, value));
+                self.line(out, "try {");
+                let result = self.branch(body, tail, out);
+                self.scopes.pop();
+                result?;
+                self.line(out, "} finally {");
+                self.line(out, &format!("    $release({});", name));
+                self.line(out, "}");
+                self.indent -= 1;
+                self.line(out, "}");
+                Ok(())
+            }
+            _ => {
+                let value = strip_parens(self.expression(expression)?);
+                match tail {
+                    Tail::Return => self.line(out, &format!("return {};", value)),
+                    Tail::Discard => self.line(out, &format!("{};", value)),
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Body of an `if` branch or loop, one level deeper
+    fn branch(
+        &mut self,
+        expression: &Expression,
+        tail: Tail,
+        out: &mut String,
+    ) -> CodegenResult<()> {
+        self.indent += 1;
+        let result = self.block_contents(expression, tail, out);
+        self.indent -= 1;
+        result
+    }
+
+    /// Wrap statements in an immediately invoked arrow function, for blocks
+    /// and loops used as values
+    fn iife(&mut self, expression: &Expression, tail: Tail) -> CodegenResult<String> {
+        let mut body = String::new();
+        self.indent += 1;
+        let in_iife = std::mem::replace(&mut self.in_iife, true);
+        let result = match tail {
+            Tail::Return => self.block_contents(expression, tail, &mut body),
+            Tail::Discard => self.tail(expression, tail, &mut body),
+        };
+        self.in_iife = in_iife;
+        self.indent -= 1;
+        result?;
+        Ok(format!("(() => {{\n{}{}}})()", body, self.pad()))
+    }
+
+    fn function(&mut self, param: &str, body: &Expression) -> CodegenResult<String> {
+        self.scopes.push(HashMap::new());
+        let param = self.declare_param(param);
+        let in_iife = std::mem::replace(&mut self.in_iife, false);
+        let result = match body {
+            Expression::Block { statements, .. } if !statements.is_empty() || returns(body) => {
+                let mut out = String::new();
+                self.indent += 1;
+                let result = self.block_contents(body, Tail::Return, &mut out);
+                self.indent -= 1;
+                result.map(|()| format!("({}) => {{\n{}{}}}", param, out, self.pad()))
+            }
+            _ => self
+                .expression(body)
+                .map(|body| format!("({}) => {}", param, body)),
+        };
+        self.in_iife = in_iife;
+        self.scopes.pop();
+        result
+    }
+
+    fn call(&mut self, function: &str, arguments: &[&Expression]) -> CodegenResult<String> {
+        let arguments = arguments
+            .iter()
+            .map(|argument| self.expression(argument).map(strip_parens))
+            .collect::<CodegenResult<Vec<_>>>()?;
+        Ok(format!("{}({})", function, arguments.join(", ")))
+    }
+
+    fn expression(&mut self, expression: &Expression) -> CodegenResult<String> {
+        Ok(match expression {
+            Expression::Identifier { name, .. } => self.resolve(name),
+            Expression::QualifiedIdentifier { module, name, .. } if self.enums.contains(module) => {
+                variant(module, name)
+            }
+            Expression::QualifiedIdentifier { span, .. } => {
+                return Err(CodegenError::new(
+                    "Modules are not supported by the JavaScript backend",
+                    span,
+                ));
+            }
+            Expression::Hole { name, span } => {
+                return Err(CodegenError::new(
+                    format!(
+                        "The hole {} must be filled in first",
+                        hole_text(name.as_deref())
+                    ),
+                    span,
+                ));
+            }
+            Expression::Number { value, .. } => format!("{}n", value),
+            Expression::BigNumber { value, .. } => format!("{}n", value),
+            Expression::Boolean { value, .. } => value.to_string(),
+            Expression::String { value, .. } => string_literal(value),
+            Expression::Bytes { value, .. } => {
+                let bytes: Vec<String> = value.iter().map(|byte| byte.to_string()).collect();
+                format!("new Uint8Array([{}])", bytes.join(", "))
+            }
+            Expression::BinaryOp {
+                left,
+                operator,
+                right,
+                span,
+            } => {
+                let (l, r) = (self.expression(left)?, self.expression(right)?);
+                match operator {
+                    BinaryOperator::Add => self.arithmetic(span, format!("({} + {})", l, r)),
+                    BinaryOperator::Subtract => self.arithmetic(span, format!("({} - {})", l, r)),
+                    BinaryOperator::Multiply => self.arithmetic(span, format!("({} * {})", l, r)),
+                    BinaryOperator::Divide => self.arithmetic(span, format!("$div({}, {})", l, r)),
+                    BinaryOperator::Equal => format!("$eq({}, {})", l, r),
+                    BinaryOperator::NotEqual => format!("!$eq({}, {})", l, r),
+                    BinaryOperator::LessThan => format!("($compare({}, {}) < 0)", l, r),
+                    BinaryOperator::LessThanEqual => format!("($compare({}, {}) <= 0)", l, r),
+                    BinaryOperator::GreaterThan => format!("($compare({}, {}) > 0)", l, r),
+                    BinaryOperator::GreaterThanEqual => format!("($compare({}, {}) >= 0)", l, r),
+                    BinaryOperator::LogicalAnd => format!("({} && {})", l, r),
+                    BinaryOperator::LogicalOr => format!("({} || {})", l, r),
+                    BinaryOperator::ComposeForward => format!("$compose({}, {})", l, r),
+                    BinaryOperator::ComposeBackward => format!("$compose({}, {})", r, l),
+                    BinaryOperator::Append => format!("$append({}, {})", l, r),
+                    BinaryOperator::Assign => {
+                        return Err(CodegenError::new(
+                            "Assignment operator not supported in expressions",
+                            span,
+                        ));
+                    }
+                }
+            }
+            Expression::UnaryOp {
+                operator,
+                operand,
+                span,
+            } => {
+                let operand = self.expression(operand)?;
+                match operator {
+                    UnaryOperator::LogicalNot => format!("!{}", operand),
+                    UnaryOperator::Negate => self.arithmetic(span, format!("(-{})", operand)),
+                }
+            }
+            Expression::Function { param, body, .. } => self.function(param, body)?,
+            Expression::FunctionCall {
+                function, argument, ..
+            } => {
+                let callee = self.expression(function)?;
+                let callee = match function.as_ref() {
+                    Expression::Identifier { .. } | Expression::FunctionCall { .. } => callee,
+                    _ => format!("({})", callee),
+                };
+                self.call(&callee, &[argument])?
+            }
+            Expression::List { elements, .. } => {
+                let elements = elements
+                    .iter()
+                    .map(|element| self.expression(element).map(strip_parens))
+                    .collect::<CodegenResult<Vec<_>>>()?;
+                format!("[{}]", elements.join(", "))
+            }
+            Expression::Pair { first, second, .. } => format!(
+                "new Tuple([{}, {}])",
+                self.expression(first)?,
+                self.expression(second)?
+            ),
+            Expression::Tuple { elements, .. } => {
+                let elements = elements
+                    .iter()
+                    .map(|element| self.expression(element).map(strip_parens))
+                    .collect::<CodegenResult<Vec<_>>>()?;
+                format!("new Tuple([{}])", elements.join(", "))
+            }
+            Expression::LeftInject { value, .. } => self.call("new Inl", &[value])?,
+            Expression::RightInject { value, .. } => self.call("new Inr", &[value])?,
+            Expression::Fix { function, .. } => self.call("$fix", &[function])?,
+            Expression::Block {
+                statements,
+                expression: inner,
+                ..
+            } => match (statements.is_empty(), inner) {
+                (true, Some(inner)) => self.expression(inner)?,
+                (true, None) => "undefined".to_string(),
+                (false, _) => self.iife(expression, Tail::Return)?,
+            },
+            Expression::FirstProjection { pair, .. } => {
+                format!("$project({}, 0)", self.expression(pair)?)
+            }
+            Expression::SecondProjection { pair, .. } => {
+                format!("$project({}, 1)", self.expression(pair)?)
+            }
+            Expression::TupleProjection { tuple, index, .. } => {
+                format!("$project({}, {})", self.expression(tuple)?, index)
+            }
+            Expression::Cons { head, tail, .. } => {
+                format!(
+                    "[{}, ...{}]",
+                    self.expression(head)?,
+                    self.expression(tail)?
+                )
+            }
+            Expression::HeadProjection { list, .. } => self.call("$head", &[list])?,
+            Expression::TailProjection { list, .. } => self.call("$tail", &[list])?,
+            Expression::Print { value, .. } => self.call("$print", &[value])?,
+            Expression::If {
+                condition,
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                let condition = self.expression(condition)?;
+                let then_branch = self.expression(then_branch)?;
+                let else_branch = match else_branch {
+                    Some(else_branch) => self.expression(else_branch)?,
+                    None => "undefined".to_string(),
+                };
+                format!("({} ? {} : {})", condition, then_branch, else_branch)
+            }
+            Expression::For { .. } => self.iife(expression, Tail::Discard)?,
+            Expression::With { .. } => self.iife(expression, Tail::Return)?,
+            Expression::Range { start, end, .. } => self.call("$range", &[start, end])?,
+            Expression::DivMod {
+                dividend, divisor, ..
+            } => self.call("$divmod", &[dividend, divisor])?,
+            Expression::SafeDiv {
+                dividend, divisor, ..
+            } => self.call("$safeDiv", &[dividend, divisor])?,
+            Expression::Concat { left, right, .. } => {
+                format!("({} + {})", self.expression(left)?, self.expression(right)?)
+            }
+            Expression::CharAt { string, index, .. } => self.call("$charAt", &[string, index])?,
+            Expression::Length { string, .. } => self.call("$length", &[string])?,
+            Expression::Graphemes { string, .. } => self.call("$graphemes", &[string])?,
+            Expression::Spawn { function, .. } => self.call("$spawn", &[function])?,
+            Expression::Join { handle, .. } => self.call("$join", &[handle])?,
+            Expression::Sleep { milliseconds, .. } => self.call("$sleep", &[milliseconds])?,
+            Expression::Random { bound, .. } => self.call("$random", &[bound])?,
+            Expression::Channel { .. } => "new Channel()".to_string(),
+            Expression::Send { channel, value, .. } => self.call("$send", &[channel, value])?,
+            Expression::Recv { channel, .. } => self.call("$recv", &[channel])?,
+            Expression::ReadFileBytes { path, .. } => self.call("$readFileBytes", &[path])?,
+            Expression::Input { .. } => self.call("$input", &[])?,
+            Expression::ReadAll { .. } => self.call("$readAll", &[])?,
+            Expression::ByteAt { bytes, index, .. } => self.call("$byteAt", &[bytes, index])?,
+            Expression::ToBytes { string, .. } => self.call("$toBytes", &[string])?,
+            Expression::FromBytes { bytes, .. } => self.call("$fromBytes", &[bytes])?,
+            Expression::ToInt { string, .. } => self.call("$toInt", &[string])?,
+            Expression::ToString { expression, .. } => {
+                format!("$show({}, false)", self.expression(expression)?)
+            }
+            Expression::TypeOf { expression, .. } => self.call("$typeOf", &[expression])?,
+            Expression::TypeTest {
+                expression,
+                type_annotation,
+                ..
+            } => format!(
+                "$hasType({}, {})",
+                self.expression(expression)?,
+                type_descriptor(type_annotation)
+            ),
+            Expression::TypeAscription { expression, .. } => self.expression(expression)?,
+            Expression::Case {
+                expression,
+                left_pattern,
+                left_body,
+                right_pattern,
+                right_body,
+                ..
+            } => {
+                let value = self.expression(expression)?;
+                let left = self.function(left_pattern, left_body)?;
+                let right = self.function(right_pattern, right_body)?;
+                format!("$case({}, {}, {})", value, left, right)
+            }
+            Expression::Match {
+                expression, arms, ..
+            } => {
+                let value = strip_parens(self.expression(expression)?);
+                let arms = arms
+                    .iter()
+                    .map(|arm| {
+                        let matches = match &arm.pattern {
+                            Pattern::Wildcard { .. } => "() => true".to_string(),
+                            Pattern::String { value, .. } => {
+                                format!("(value) => value === {}", string_literal(value))
+                            }
+                            Pattern::Int { value, .. } => {
+                                format!("(value) => value === {}n", value)
+                            }
+                            Pattern::Range { start, end, .. } => {
+                                let mut tests = vec!["typeof value === \"bigint\"".to_string()];
+                                tests.extend(start.map(|start| format!("value >= {}n", start)));
+                                tests.extend(end.map(|end| format!("value < {}n", end)));
+                                format!("(value) => {}", tests.join(" && "))
+                            }
+                            Pattern::Variant {
+                                enum_name,
+                                variant: name,
+                                ..
+                            } => format!("(value) => $eq(value, {})", variant(enum_name, name)),
+                        };
+                        let body = self.function("_", &arm.body)?;
+                        Ok(format!("[{}, {}]", matches, body))
+                    })
+                    .collect::<CodegenResult<Vec<_>>>()?;
+                format!("$match({}, [{}])", value, arms.join(", "))
+            }
+        })
+    }
+}
+
+/// A variant of an enum, `Color.Red`
+fn variant(enum_name: &str, name: &str) -> String {
+    format!(
+        "new Variant({}, {})",
+        string_literal(enum_name),
+        string_literal(name)
+    )
+}
+
+/// Whether a `return` leaving the function whose body contains `expression`
+/// appears in it
+fn returns(expression: &Expression) -> bool {
+    expression.children().into_iter().any(|child| match child {
+        Child::Statement(Statement::Return { .. }) => true,
+        Child::Statement(
+            Statement::VariableDeclaration { value, .. }
+            | Statement::ConstantDeclaration { value, .. }
+            | Statement::Expression {
+                expression: value, ..
+            },
+        ) => returns(value),
+        Child::Statement(_) => false,
+        Child::Expression(_, Expression::Function { .. }) => false,
+        Child::Expression(_, expression) => returns(expression),
+    })
+}
+
+/// The `if` expression a branch consists of, if that is all it contains
+fn nested_if(branch: &Expression) -> Option<&Expression> {
+    match branch {
+        Expression::If { .. } => Some(branch),
+        Expression::Block {
+            statements,
+            expression: Some(expression),
+            ..
+        } if statements.is_empty() => nested_if(expression),
+        _ => None,
+    }
+}
+
+fn mangle(name: &str) -> String {
+    if RESERVED.contains(&name) {
+        format!("{}$", name)
+    } else {
+        name.to_string()
+    }
+}
+
+/// Drop parentheses around a whole expression where the context already
+/// delimits it, such as an `if` condition or a `return`
+fn strip_parens(expression: String) -> String {
+    if !expression.starts_with('(') {
+        return expression;
+    }
+    let mut depth = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, c) in expression.char_indices() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            '"' => in_string = true,
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return if i == expression.len() - 1 {
+                        expression[1..i].to_string()
+                    } else {
+                        expression
+                    };
+                }
+            }
+            _ => {}
+        }
+    }
+    expression
+}
+
+fn string_literal(value: &str) -> String {
+    let mut literal = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => literal.push_str("\\\""),
+            '\\' => literal.push_str("\\\\"),
+            '\n' => literal.push_str("\\n"),
+            '\r' => literal.push_str("\\r"),
+            '\t' => literal.push_str("\\t"),
+            c if c.is_control() || c == '\u{2028}' || c == '\u{2029}' => {
+                literal.push_str(&format!("\\u{{{:x}}}", c as u32))
+            }
+            c => literal.push(c),
+        }
+    }
+    literal.push('"');
+    literal
+}
+
+/// Type annotation in the form `$hasType` expects: a name, or an array of a
+/// constructor and its arguments. Pairs are two-element tuples at runtime.
+fn type_descriptor(ty: &TypeExpression) -> String {
+    let constructor = |name: &str, args: &[&TypeExpression]| {
+        let mut parts = vec![string_literal(name)];
+        parts.extend(args.iter().map(|arg| type_descriptor(arg)));
+        format!("[{}]", parts.join(", "))
+    };
+    match ty {
+        TypeExpression::Int { .. } => string_literal("Int"),
+        TypeExpression::Bool { .. } => string_literal("Bool"),
+        TypeExpression::String { .. } => string_literal("String"),
+        TypeExpression::Named { name, .. } => string_literal(name),
+        TypeExpression::List { element, .. } => constructor("List", &[element]),
+        TypeExpression::Handle { .. } => constructor("Handle", &[]),
+        TypeExpression::Channel { .. } => constructor("Channel", &[]),
+        TypeExpression::Function { .. } => constructor("Function", &[]),
+        TypeExpression::Pair { first, second, .. } => constructor("Tuple", &[first, second]),
+        TypeExpression::Tuple { elements, .. } => {
+            let elements: Vec<&TypeExpression> = elements.iter().collect();
+            constructor("Tuple", &elements)
+        }
+        TypeExpression::Sum { left, right, .. } => constructor("Sum", &[left, right]),
+        TypeExpression::Recursive { inner, .. } => constructor("Rec", &[inner]),
+    }
+}
@@ -0,0 +1,37 @@
+//! Translation of type-checked programs into other languages
+
+pub mod js;
+pub mod rust;
+
+#[cfg(test)]
+mod tests;
+
+use crate::lexer::tokens::Span;
+
+/// A construct the target language backend cannot translate
+#[derive(Debug, Clone, PartialEq)]
+pub struct CodegenError {
+    pub message: String,
+    pub span: Span,
+}
+
+impl CodegenError {
+    pub fn new(message: impl Into<String>, span: &Span) -> Self {
+        Self {
+            message: message.into(),
+            span: span.clone(),
+        }
+    }
+}
+
+impl std::fmt::Display for CodegenError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} at line {}, column {}",
+            self.message, self.span.line, self.span.column
+        )
+    }
+}
+
+pub type CodegenResult<T> = Result<T, CodegenError>;
@@ -0,0 +1,366 @@
+// Runtime support for Corrosion programs compiled to JavaScript.
+// Int and BigInt values are both JavaScript bigints, lists are arrays, pairs
+// and tuples are Tuple objects, enum variants are Variant objects, and Unit
+// is undefined.
+
+class Tuple {
+    constructor(items) {
+        this.items = items;
+    }
+}
+
+class Inl {
+    constructor(value) {
+        this.value = value;
+    }
+}
+
+class Inr {
+    constructor(value) {
+        this.value = value;
+    }
+}
+
+class Variant {
+    constructor(name, variant) {
+        this.name = name;
+        this.variant = variant;
+    }
+}
+
+class Handle {
+    constructor(result) {
+        this.result = result;
+        this.joined = false;
+    }
+}
+
+class Channel {
+    constructor() {
+        this.queue = [];
+        this.closed = false;
+    }
+}
+
+function $fail(message) {
+    throw new Error(message);
+}
+
+function $escapeBytes(bytes) {
+    let out = "";
+    for (const byte of bytes) {
+        if (byte === 0x09) out += "\\t";
+        else if (byte === 0x0a) out += "\\n";
+        else if (byte === 0x0d) out += "\\r";
+        else if (byte === 0x22) out += "\\\"";
+        else if (byte === 0x27) out += "\\'";
+        else if (byte === 0x5c) out += "\\\\";
+        else if (byte >= 0x20 && byte < 0x7f) out += String.fromCharCode(byte);
+        else out += "\\x" + byte.toString(16).padStart(2, "0");
+    }
+    return out;
+}
+
+// `print` and `toString` differ only in how functions and sums are shown
+function $show(value, forPrint) {
+    const show = (v) => $show(v, forPrint);
+    if (value === undefined) return "()";
+    if (typeof value === "string") return value;
+    if (typeof value === "bigint" || typeof value === "boolean") return String(value);
+    if (typeof value === "function") return "<function>";
+    if (value instanceof Uint8Array) return "b\"" + $escapeBytes(value) + "\"";
+    if (Array.isArray(value)) return "[" + value.map(show).join(", ") + "]";
+    if (value instanceof Tuple) return "(" + value.items.map(show).join(", ") + ")";
+    if (value instanceof Inl) return (forPrint ? "Left(" : "inl(") + show(value.value) + ")";
+    if (value instanceof Inr) return (forPrint ? "Right(" : "inr(") + show(value.value) + ")";
+    if (value instanceof Handle) return "<thread>";
+    if (value instanceof Channel) return "<channel>";
+    if (value instanceof Variant) return value.name + "." + value.variant;
+    return String(value);
+}
+
+function $print(value) {
+    console.log($show(value, true));
+}
+
+function $eq(a, b) {
+    if (Array.isArray(a) && Array.isArray(b)) {
+        return a.length === b.length && a.every((item, i) => $eq(item, b[i]));
+    }
+    if (a instanceof Tuple && b instanceof Tuple) return $eq(a.items, b.items);
+    if (a instanceof Uint8Array && b instanceof Uint8Array) return $eq([...a], [...b]);
+    if ((a instanceof Inl && b instanceof Inl) || (a instanceof Inr && b instanceof Inr)) {
+        return $eq(a.value, b.value);
+    }
+    if (a instanceof Variant && b instanceof Variant) {
+        return a.name === b.name && a.variant === b.variant;
+    }
+    return a === b;
+}
+
+// Lists, pairs and tuples compare lexicographically
+function $compare(a, b) {
+    if (a instanceof Tuple) return $compare(a.items, b.items);
+    if (a instanceof Uint8Array) return $compare([...a], [...b]);
+    if (Array.isArray(a)) {
+        for (let i = 0; i < a.length && i < b.length; i++) {
+            const ordering = $compare(a[i], b[i]);
+            if (ordering !== 0) return ordering;
+        }
+        return a.length - b.length;
+    }
+    return a < b ? -1 : a > b ? 1 : 0;
+}
+
+function $append(a, b) {
+    if (typeof a === "string") return a + b;
+    if (a instanceof Uint8Array) {
+        const out = new Uint8Array(a.length + b.length);
+        out.set(a);
+        out.set(b, a.length);
+        return out;
+    }
+    return [...a, ...b];
+}
+
+function $compose(first, second) {
+    return (x) => second(first(x));
+}
+
+function $fix(f) {
+    return (x) => f($fix(f))(x);
+}
+
+function $case(value, left, right) {
+    if (value instanceof Inl) return left(value.value);
+    if (value instanceof Inr) return right(value.value);
+    $fail("Expected a sum type value");
+}
+
+function $match(value, arms) {
+    for (const [matches, body] of arms) {
+        if (matches(value)) return body(value);
+    }
+    $fail("No case arm matches the value");
+}
+
+function $head(list) {
+    return list.length > 0 ? list[0] : $fail("Cannot get head of empty list");
+}
+
+function $tail(list) {
+    return list.length > 0 ? list.slice(1) : $fail("Cannot get tail of empty list");
+}
+
+function $project(tuple, index) {
+    return index < tuple.items.length
+        ? tuple.items[index]
+        : $fail("Index " + index + " out of bounds (length " + tuple.items.length + ")");
+}
+
+function $range(start, end) {
+    const list = [];
+    for (let i = start; i < end; i++) list.push(i);
+    return list;
+}
+
+function $divmod(dividend, divisor) {
+    if (divisor === 0n) $fail("Division by zero");
+    return new Tuple([dividend / divisor, dividend % divisor]);
+}
+
+function $safeDiv(dividend, divisor) {
+    return divisor === 0n ? new Inr("Division by zero") : new Inl(dividend / divisor);
+}
+
+// Int arithmetic is checked against the range of a 64-bit integer
+function $int(n) {
+    return n < -(2n ** 63n) || n >= 2n ** 63n ? $fail("Integer overflow") : n;
+}
+
+function $div(dividend, divisor) {
+    return divisor === 0n ? $fail("Division by zero") : dividend / divisor;
+}
+
+function $charAt(string, index) {
+    const chars = [...string];
+    if (index < 0n) $fail("String index cannot be negative");
+    if (index >= BigInt(chars.length)) {
+        $fail("String index " + index + " out of bounds (length " + chars.length + ")");
+    }
+    return chars[Number(index)];
+}
+
+function $length(value) {
+    return BigInt(typeof value === "string" ? [...value].length : value.length);
+}
+
+function $graphemes(string) {
+    if (typeof Intl !== "undefined" && Intl.Segmenter) {
+        return [...new Intl.Segmenter().segment(string)].map((part) => part.segment);
+    }
+    return [...string];
+}
+
+function $byteAt(bytes, index) {
+    if (index < 0n || index >= BigInt(bytes.length)) {
+        $fail("Byte index " + index + " out of bounds (length " + bytes.length + ")");
+    }
+    return BigInt(bytes[Number(index)]);
+}
+
+function $toBytes(string) {
+    return new TextEncoder().encode(string);
+}
+
+function $fromBytes(bytes) {
+    try {
+        return new Inl(new TextDecoder("utf-8", { fatal: true }).decode(bytes));
+    } catch (error) {
+        return new Inr("Invalid UTF-8: " + error.message);
+    }
+}
+
+function $toInt(string) {
+    if (!/^[+-]?[0-9]+$/.test(string)) return new Inr("'" + string + "' is not an Int");
+    const value = BigInt(string);
+    if (value < -(2n ** 63n) || value >= 2n ** 63n) {
+        return new Inr("'" + string + "' does not fit in an Int");
+    }
+    return new Inl(value);
+}
+
+function $readFileBytes(path) {
+    if (typeof require !== "function") return new Inr("Failed to read " + path + ": no file system");
+    try {
+        return new Inl(new Uint8Array(require("fs").readFileSync(path)));
+    } catch (error) {
+        return new Inr("Failed to read " + path + ": " + error.message);
+    }
+}
+
+// Standard input is read in one go the first time it is needed, since Node
+// can only read it synchronously as a whole
+let $stdin = null;
+function $stdinText() {
+    if ($stdin === null) {
+        try {
+            $stdin = typeof require === "function" ? require("fs").readFileSync(0, "utf8") : "";
+        } catch (error) {
+            $stdin = "";
+        }
+    }
+    return $stdin;
+}
+
+function $input() {
+    const text = $stdinText();
+    if (text === "") return new Inr(undefined);
+    const end = text.indexOf("\n");
+    const line = end === -1 ? text : text.slice(0, end);
+    $stdin = end === -1 ? "" : text.slice(end + 1);
+    return new Inl(line.endsWith("\r") ? line.slice(0, -1) : line);
+}
+
+function $readAll() {
+    const text = $stdinText();
+    $stdin = "";
+    return text;
+}
+
+// JavaScript has no threads, so a spawned function runs to completion at once
+function $spawn(f) {
+    return new Handle(f(undefined));
+}
+
+function $join(handle) {
+    if (handle.joined) $fail("Thread has already been joined");
+    handle.joined = true;
+    return handle.result;
+}
+
+function $send(channel, value) {
+    if (channel.closed) $fail("Channel is closed");
+    channel.queue.push(value);
+}
+
+function $recv(channel) {
+    if (channel.closed) $fail("Channel is closed");
+    return channel.queue.length > 0 ? channel.queue.shift() : $fail("Receive on an empty channel");
+}
+
+// Spawned functions have already finished, so releasing a thread only marks
+// it joined
+function $release(value) {
+    if (value instanceof Channel) {
+        value.closed = true;
+        value.queue = [];
+    } else if (value instanceof Handle) {
+        value.joined = true;
+    } else if (Array.isArray(value)) {
+        value.forEach($release);
+    } else if (value instanceof Tuple) {
+        value.items.forEach($release);
+    } else if (value instanceof Inl || value instanceof Inr) {
+        $release(value.value);
+    }
+}
+
+function $sleep(milliseconds) {
+    if (milliseconds < 0n) $fail("Cannot sleep for a negative duration: " + milliseconds + "ms");
+    const end = Date.now() + Number(milliseconds);
+    while (Date.now() < end) {}
+}
+
+function $random(bound) {
+    if (bound <= 0n) $fail("random bound must be positive, got " + bound);
+    return BigInt(Math.floor(Math.random() * Number(bound)));
+}
+
+function $typeOf(value) {
+    if (value === undefined) return "Unit";
+    if (typeof value === "bigint") return "Int";
+    if (typeof value === "boolean") return "Bool";
+    if (typeof value === "string") return "String";
+    if (typeof value === "function") return "Function";
+    if (value instanceof Uint8Array) return "Bytes";
+    if (Array.isArray(value)) {
+        return value.length === 0 ? "List Unknown" : "List " + $typeOf(value[0]);
+    }
+    if (value instanceof Tuple) return "(" + value.items.map($typeOf).join(", ") + ")";
+    if (value instanceof Inl) return "(" + $typeOf(value.value) + " + Unknown)";
+    if (value instanceof Inr) return "(Unknown + " + $typeOf(value.value) + ")";
+    if (value instanceof Handle) return "Handle";
+    if (value instanceof Channel) return "Channel";
+    if (value instanceof Variant) return value.name;
+    return "Unknown";
+}
+
+// Types are written as a name, or an array of a constructor and its arguments
+function $hasType(value, type) {
+    if (typeof type === "string") {
+        switch (type) {
+            case "Dyn": return true;
+            case "Int": case "BigInt": return typeof value === "bigint";
+            case "Bool": return typeof value === "boolean";
+            case "String": return typeof value === "string";
+            case "Bytes": return value instanceof Uint8Array;
+            default: return value instanceof Variant && value.name === type;
+        }
+    }
+    const [kind, ...args] = type;
+    switch (kind) {
+        case "List": return Array.isArray(value) && value.every((item) => $hasType(item, args[0]));
+        case "Tuple":
+            return value instanceof Tuple && value.items.length === args.length
+                && value.items.every((item, i) => $hasType(item, args[i]));
+        case "Sum":
+            return (value instanceof Inl && $hasType(value.value, args[0]))
+                || (value instanceof Inr && $hasType(value.value, args[1]));
+        case "Function": return typeof value === "function";
+        case "Handle": return value instanceof Handle;
+        case "Channel": return value instanceof Channel;
+        case "Rec": return $hasType(value, args[0]);
+        default: return false;
+    }
+}
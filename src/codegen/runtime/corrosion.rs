@@ -0,0 +1,651 @@
+/// Runtime support for Corrosion programs compiled to Rust. Every value is a
+/// `Value`, every function is a boxed closure taking one `Value`, and runtime
+/// errors are `Err` strings that end the program.
+mod rt {
+    use std::cell::{Cell, RefCell};
+    use std::cmp::Ordering;
+    use std::collections::VecDeque;
+    use std::rc::Rc;
+
+    pub type Result<T = Value> = std::result::Result<T, String>;
+    pub type Function = Rc<dyn Fn(Value) -> Result>;
+
+    #[derive(Clone)]
+    pub enum Value {
+        Unit,
+        Int(i64),
+        Bool(bool),
+        Str(String),
+        Bytes(Vec<u8>),
+        List(Vec<Value>),
+        /// Pairs are tuples of two elements
+        Tuple(Vec<Value>),
+        Inl(Box<Value>),
+        Inr(Box<Value>),
+        Function(Function, FunctionKind),
+        Handle(usize),
+        Channel(usize),
+        /// A variant of an enum, by the enum's name and its own
+        Enum(&'static str, &'static str),
+    }
+
+    /// How a function is shown: closures by their parameter name, and
+    /// recursive functions (declared with `fn` or built with `fix`) as such
+    #[derive(Clone, Copy)]
+    pub enum FunctionKind {
+        Closure(&'static str),
+        Recursive,
+    }
+
+    /// Runtime type, for `expr is Type`
+    pub enum Ty {
+        Named(&'static str),
+        Int,
+        Bool,
+        String,
+        List(Box<Ty>),
+        Tuple(Vec<Ty>),
+        Sum(Box<Ty>, Box<Ty>),
+        Function,
+        Handle,
+        Channel,
+    }
+
+    thread_local! {
+        /// Results of spawned functions, indexed by handle; emptied by `join`
+        static HANDLES: RefCell<Vec<Option<Value>>> = const { RefCell::new(Vec::new()) };
+        static CHANNELS: RefCell<Vec<Option<VecDeque<Value>>>> = const { RefCell::new(Vec::new()) };
+        static RNG_STATE: Cell<u64> = Cell::new(
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_nanos() as u64)
+                .unwrap_or_default(),
+        );
+    }
+
+    fn type_name(value: &Value) -> &'static str {
+        match value {
+            Value::Unit => "Unit",
+            Value::Int(_) => "Int",
+            Value::Bool(_) => "Bool",
+            Value::Str(_) => "String",
+            Value::Bytes(_) => "Bytes",
+            Value::List(_) => "List",
+            Value::Tuple(items) if items.len() == 2 => "Pair",
+            Value::Tuple(_) => "Tuple",
+            Value::Inl(_) => "LeftInject",
+            Value::Inr(_) => "RightInject",
+            Value::Function(_, FunctionKind::Closure(_)) => "Function",
+            Value::Function(_, FunctionKind::Recursive) => "FixedPoint",
+            Value::Handle(_) => "Handle",
+            Value::Channel(_) => "Channel",
+            Value::Enum(..) => "Enum",
+        }
+    }
+
+    fn type_error<T>(expected: &str, found: &Value) -> Result<T> {
+        Err(format!(
+            "Type error: expected {}, found {}",
+            expected,
+            type_name(found)
+        ))
+    }
+
+    pub fn int(value: Value) -> Result<i64> {
+        match value {
+            Value::Int(n) => Ok(n),
+            other => type_error("Int", &other),
+        }
+    }
+
+    pub fn bool(value: Value) -> Result<bool> {
+        match value {
+            Value::Bool(b) => Ok(b),
+            other => type_error("Bool", &other),
+        }
+    }
+
+    fn string(value: Value) -> Result<String> {
+        match value {
+            Value::Str(s) => Ok(s),
+            other => type_error("String", &other),
+        }
+    }
+
+    fn bytes(value: Value) -> Result<Vec<u8>> {
+        match value {
+            Value::Bytes(b) => Ok(b),
+            other => type_error("Bytes", &other),
+        }
+    }
+
+    pub fn list(value: Value) -> Result<Vec<Value>> {
+        match value {
+            Value::List(items) => Ok(items),
+            other => type_error("List", &other),
+        }
+    }
+
+    pub fn function(param: &'static str, body: impl Fn(Value) -> Result + 'static) -> Value {
+        Value::Function(Rc::new(body), FunctionKind::Closure(param))
+    }
+
+    /// A function that receives itself as its first argument, for `fn`
+    /// declarations that call themselves
+    pub fn recursive(body: impl Fn(Value, Value) -> Result + 'static) -> Value {
+        fn build(body: Rc<dyn Fn(Value, Value) -> Result>) -> Value {
+            let inner = body.clone();
+            Value::Function(
+                Rc::new(move |argument| inner(build(inner.clone()), argument)),
+                FunctionKind::Recursive,
+            )
+        }
+        build(Rc::new(body))
+    }
+
+    pub fn fix(function: Value) -> Result {
+        if !matches!(function, Value::Function(..)) {
+            return Err("Fix can only be applied to functions".to_string());
+        }
+        Ok(Value::Function(
+            Rc::new(move |argument| {
+                let inner = call(function.clone(), fix(function.clone())?)?;
+                call(inner, argument)
+            }),
+            FunctionKind::Recursive,
+        ))
+    }
+
+    pub fn call(function: Value, argument: Value) -> Result {
+        match function {
+            Value::Function(body, _) => body(argument),
+            _ => Err("Attempted to call a non-function value".to_string()),
+        }
+    }
+
+    pub fn compose(first: Value, second: Value) -> Result {
+        for function in [&first, &second] {
+            if !matches!(function, Value::Function(..)) {
+                return type_error("Function", function);
+            }
+        }
+        Ok(function("x", move |x| {
+            call(second.clone(), call(first.clone(), x)?)
+        }))
+    }
+
+    /// `+`, `-`, `*` and `/`, checked for overflow; `+` also joins strings
+    pub fn arithmetic(operator: char, left: Value, right: Value) -> Result {
+        let (l, r) = match (left, right) {
+            (Value::Int(l), Value::Int(r)) => (l, r),
+            (Value::Str(l), Value::Str(r)) if operator == '+' => return Ok(Value::Str(l + &r)),
+            (Value::Int(_), other) | (other, _) => return type_error("Int", &other),
+        };
+        if operator == '/' {
+            return divide(l, r).map(|(quotient, _)| Value::Int(quotient));
+        }
+        let result = match operator {
+            '+' => l.checked_add(r),
+            '-' => l.checked_sub(r),
+            _ => l.checked_mul(r),
+        };
+        result
+            .map(Value::Int)
+            .ok_or_else(|| format!("Integer overflow in {} {} {}", l, operator, r))
+    }
+
+    fn divide(dividend: i64, divisor: i64) -> Result<(i64, i64)> {
+        if divisor == 0 {
+            return Err("Division by zero".to_string());
+        }
+        match (dividend.checked_div(divisor), dividend.checked_rem(divisor)) {
+            (Some(quotient), Some(remainder)) => Ok((quotient, remainder)),
+            _ => Err(format!("Integer overflow in {} / {}", dividend, divisor)),
+        }
+    }
+
+    pub fn negate(value: Value) -> Result {
+        let n = int(value)?;
+        n.checked_neg()
+            .map(Value::Int)
+            .ok_or_else(|| format!("Integer overflow in -{}", n))
+    }
+
+    pub fn not(value: Value) -> Result {
+        Ok(Value::Bool(!bool(value)?))
+    }
+
+    pub fn and(left: Value, right: Value) -> Result {
+        let (left, right) = (bool(left)?, bool(right)?);
+        Ok(Value::Bool(left && right))
+    }
+
+    pub fn or(left: Value, right: Value) -> Result {
+        let (left, right) = (bool(left)?, bool(right)?);
+        Ok(Value::Bool(left || right))
+    }
+
+    fn equal(left: &Value, right: &Value) -> bool {
+        match (left, right) {
+            (Value::Unit, Value::Unit) => true,
+            (Value::Int(l), Value::Int(r)) => l == r,
+            (Value::Bool(l), Value::Bool(r)) => l == r,
+            (Value::Str(l), Value::Str(r)) => l == r,
+            (Value::Bytes(l), Value::Bytes(r)) => l == r,
+            (Value::List(l), Value::List(r)) | (Value::Tuple(l), Value::Tuple(r)) => {
+                l.len() == r.len() && l.iter().zip(r).all(|(l, r)| equal(l, r))
+            }
+            (Value::Inl(l), Value::Inl(r)) | (Value::Inr(l), Value::Inr(r)) => equal(l, r),
+            (Value::Handle(l), Value::Handle(r)) | (Value::Channel(l), Value::Channel(r)) => l == r,
+            (Value::Enum(l, l_variant), Value::Enum(r, r_variant)) => {
+                l == r && l_variant == r_variant
+            }
+            _ => false,
+        }
+    }
+
+    pub fn equals(left: Value, right: Value) -> Value {
+        Value::Bool(equal(&left, &right))
+    }
+
+    pub fn not_equals(left: Value, right: Value) -> Value {
+        Value::Bool(!equal(&left, &right))
+    }
+
+    /// Lists, pairs and tuples are ordered lexicographically
+    fn order(left: &Value, right: &Value) -> Option<Ordering> {
+        match (left, right) {
+            (Value::Unit, Value::Unit) => Some(Ordering::Equal),
+            (Value::Int(l), Value::Int(r)) => Some(l.cmp(r)),
+            (Value::Bool(l), Value::Bool(r)) => Some(l.cmp(r)),
+            (Value::Str(l), Value::Str(r)) => Some(l.cmp(r)),
+            (Value::Bytes(l), Value::Bytes(r)) => Some(l.cmp(r)),
+            (Value::List(l), Value::List(r)) | (Value::Tuple(l), Value::Tuple(r)) => {
+                for (l, r) in l.iter().zip(r) {
+                    match order(l, r)? {
+                        Ordering::Equal => continue,
+                        ordering => return Some(ordering),
+                    }
+                }
+                Some(l.len().cmp(&r.len()))
+            }
+            _ => None,
+        }
+    }
+
+    /// `<`, `<=`, `>` and `>=`
+    pub fn compare(operator: &str, left: Value, right: Value) -> Result {
+        let Some(ordering) = order(&left, &right) else {
+            return Err(format!(
+                "Type error: cannot compare {} {} {}",
+                type_name(&left),
+                operator,
+                type_name(&right)
+            ));
+        };
+        Ok(Value::Bool(match operator {
+            "<" => ordering.is_lt(),
+            "<=" => ordering.is_le(),
+            ">" => ordering.is_gt(),
+            _ => ordering.is_ge(),
+        }))
+    }
+
+    pub fn append(left: Value, right: Value) -> Result {
+        match (left, right) {
+            (Value::List(mut l), Value::List(r)) => {
+                l.extend(r);
+                Ok(Value::List(l))
+            }
+            (Value::Str(l), Value::Str(r)) => Ok(Value::Str(l + &r)),
+            (Value::Bytes(mut l), Value::Bytes(r)) => {
+                l.extend(r);
+                Ok(Value::Bytes(l))
+            }
+            (l, _) => type_error("List, String or Bytes", &l),
+        }
+    }
+
+    pub fn concat(left: Value, right: Value) -> Result {
+        Ok(Value::Str(string(left)? + &string(right)?))
+    }
+
+    pub fn cons(head: Value, tail: Value) -> Result {
+        let mut items = list(tail)?;
+        items.insert(0, head);
+        Ok(Value::List(items))
+    }
+
+    pub fn head(value: Value) -> Result {
+        list(value)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| "Cannot get head of empty list".to_string())
+    }
+
+    pub fn tail(value: Value) -> Result {
+        let items = list(value)?;
+        if items.is_empty() {
+            return Err("Cannot get tail of empty list".to_string());
+        }
+        Ok(Value::List(items[1..].to_vec()))
+    }
+
+    pub fn project(value: Value, index: usize) -> Result {
+        match value {
+            Value::Tuple(mut items) if index < items.len() => Ok(items.swap_remove(index)),
+            Value::Tuple(items) => Err(format!(
+                "Index {} out of bounds (length {})",
+                index,
+                items.len()
+            )),
+            other => type_error("Tuple", &other),
+        }
+    }
+
+    pub fn range(start: Value, end: Value) -> Result {
+        let (start, end) = (int(start)?, int(end)?);
+        Ok(Value::List((start..end).map(Value::Int).collect()))
+    }
+
+    pub fn divmod(dividend: Value, divisor: Value) -> Result {
+        let (quotient, remainder) = divide(int(dividend)?, int(divisor)?)?;
+        Ok(Value::Tuple(vec![
+            Value::Int(quotient),
+            Value::Int(remainder),
+        ]))
+    }
+
+    pub fn safe_div(dividend: Value, divisor: Value) -> Result {
+        let (dividend, divisor) = (int(dividend)?, int(divisor)?);
+        Ok(match divide(dividend, divisor) {
+            Ok((quotient, _)) => Value::Inl(Box::new(Value::Int(quotient))),
+            Err(_) if divisor == 0 => Value::Inr(Box::new(Value::Str("Division by zero".into()))),
+            Err(_) => Value::Inr(Box::new(Value::Str("Integer overflow".into()))),
+        })
+    }
+
+    pub fn char_at(string_value: Value, index: Value) -> Result {
+        let (s, i) = (string(string_value)?, int(index)?);
+        if i < 0 {
+            return Err("String index cannot be negative".to_string());
+        }
+        let chars: Vec<char> = s.chars().collect();
+        chars
+            .get(i as usize)
+            .map(|c| Value::Str(c.to_string()))
+            .ok_or_else(|| format!("String index {} out of bounds (length {})", i, chars.len()))
+    }
+
+    pub fn length(value: Value) -> Result {
+        match value {
+            Value::Str(s) => Ok(Value::Int(s.chars().count() as i64)),
+            Value::Bytes(b) => Ok(Value::Int(b.len() as i64)),
+            other => type_error("String", &other),
+        }
+    }
+
+    pub fn byte_at(bytes_value: Value, index: Value) -> Result {
+        let (b, i) = (bytes(bytes_value)?, int(index)?);
+        usize::try_from(i)
+            .ok()
+            .and_then(|index| b.get(index))
+            .map(|byte| Value::Int(*byte as i64))
+            .ok_or_else(|| format!("Byte index {} out of bounds (length {})", i, b.len()))
+    }
+
+    pub fn to_bytes(value: Value) -> Result {
+        Ok(Value::Bytes(string(value)?.into_bytes()))
+    }
+
+    pub fn from_bytes(value: Value) -> Result {
+        Ok(match String::from_utf8(bytes(value)?) {
+            Ok(s) => Value::Inl(Box::new(Value::Str(s))),
+            Err(error) => Value::Inr(Box::new(Value::Str(format!(
+                "Invalid UTF-8: {}",
+                error.utf8_error()
+            )))),
+        })
+    }
+
+    pub fn to_int(value: Value) -> Result {
+        let s = string(value)?;
+        Ok(match s.parse::<i64>() {
+            Ok(n) => Value::Inl(Box::new(Value::Int(n))),
+            Err(error) => Value::Inr(Box::new(Value::Str(
+                if matches!(
+                    error.kind(),
+                    std::num::IntErrorKind::PosOverflow | std::num::IntErrorKind::NegOverflow
+                ) {
+                    format!("'{}' does not fit in an Int", s)
+                } else {
+                    format!("'{}' is not an Int", s)
+                },
+            ))),
+        })
+    }
+
+    pub fn read_file_bytes(path: Value) -> Result {
+        let path = string(path)?;
+        Ok(match std::fs::read(&path) {
+            Ok(contents) => Value::Inl(Box::new(Value::Bytes(contents))),
+            Err(error) => Value::Inr(Box::new(Value::Str(format!(
+                "Failed to read {}: {}",
+                path, error
+            )))),
+        })
+    }
+
+    /// The next line of standard input without its line ending, or `inr(())`
+    /// once it has ended
+    pub fn input() -> Result {
+        let mut line = String::new();
+        match std::io::stdin().read_line(&mut line) {
+            Ok(0) => Ok(Value::Inr(Box::new(Value::Unit))),
+            Ok(_) => {
+                let line = line.strip_suffix('\n').unwrap_or(&line);
+                let line = line.strip_suffix('\r').unwrap_or(line);
+                Ok(Value::Inl(Box::new(Value::Str(line.to_string()))))
+            }
+            Err(error) => Err(format!("Failed to read input: {}", error)),
+        }
+    }
+
+    pub fn read_all() -> Result {
+        let mut text = String::new();
+        std::io::Read::read_to_string(&mut std::io::stdin(), &mut text)
+            .map_err(|error| format!("Failed to read input: {}", error))?;
+        Ok(Value::Str(text))
+    }
+
+    /// Spawned functions run to completion straight away, on this thread
+    pub fn spawn(function: Value) -> Result {
+        let result = call(function, Value::Unit)?;
+        Ok(HANDLES.with_borrow_mut(|handles| {
+            handles.push(Some(result));
+            Value::Handle(handles.len() - 1)
+        }))
+    }
+
+    pub fn join(handle: Value) -> Result {
+        let Value::Handle(id) = handle else {
+            return type_error("Handle", &handle);
+        };
+        HANDLES
+            .with_borrow_mut(|handles| handles.get_mut(id).and_then(Option::take))
+            .ok_or_else(|| "Thread has already been joined".to_string())
+    }
+
+    pub fn channel() -> Value {
+        CHANNELS.with_borrow_mut(|channels| {
+            channels.push(Some(VecDeque::new()));
+            Value::Channel(channels.len() - 1)
+        })
+    }
+
+    pub fn send(channel: Value, value: Value) -> Result {
+        let Value::Channel(id) = channel else {
+            return type_error("Channel", &channel);
+        };
+        CHANNELS
+            .with_borrow_mut(|channels| channels[id].as_mut().map(|queue| queue.push_back(value)))
+            .ok_or_else(|| "Channel is closed".to_string())?;
+        Ok(Value::Unit)
+    }
+
+    pub fn recv(channel: Value) -> Result {
+        let Value::Channel(id) = channel else {
+            return type_error("Channel", &channel);
+        };
+        CHANNELS.with_borrow_mut(|channels| match channels[id].as_mut() {
+            Some(queue) => queue
+                .pop_front()
+                .ok_or_else(|| "Receive on an empty channel".to_string()),
+            None => Err("Channel is closed".to_string()),
+        })
+    }
+
+    /// Releases the threads and channels in a value when dropped, at the end
+    /// of a `with` block however it is left
+    pub struct Release(pub Value);
+
+    impl Drop for Release {
+        fn drop(&mut self) {
+            release(&self.0);
+        }
+    }
+
+    /// Spawned functions have already finished, so releasing a thread only
+    /// marks it joined
+    fn release(value: &Value) {
+        match value {
+            Value::Handle(id) => HANDLES.with_borrow_mut(|handles| handles[*id] = None),
+            Value::Channel(id) => CHANNELS.with_borrow_mut(|channels| channels[*id] = None),
+            Value::List(items) | Value::Tuple(items) => items.iter().for_each(release),
+            Value::Inl(inner) | Value::Inr(inner) => release(inner),
+            _ => {}
+        }
+    }
+
+    pub fn sleep(milliseconds: Value) -> Result {
+        let ms = int(milliseconds)?;
+        if ms < 0 {
+            return Err(format!("Cannot sleep for a negative duration: {}ms", ms));
+        }
+        std::thread::sleep(std::time::Duration::from_millis(ms as u64));
+        Ok(Value::Unit)
+    }
+
+    /// SplitMix64, as in the interpreter
+    fn next_u64() -> u64 {
+        RNG_STATE.with(|state| {
+            state.set(state.get().wrapping_add(0x9e37_79b9_7f4a_7c15));
+            let mut z = state.get();
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+            z ^ (z >> 31)
+        })
+    }
+
+    pub fn random(bound: Value) -> Result {
+        let n = int(bound)?;
+        if n <= 0 {
+            return Err(format!("random bound must be positive, got {}", n));
+        }
+        let bound = n as u64;
+        let threshold = bound.wrapping_neg() % bound;
+        loop {
+            let value = next_u64();
+            if value >= threshold {
+                return Ok(Value::Int((value % bound) as i64));
+            }
+        }
+    }
+
+    pub fn has_type(value: &Value, ty: &Ty) -> bool {
+        match (value, ty) {
+            (_, Ty::Named("Dyn")) => true,
+            (Value::Bytes(_), Ty::Named("Bytes")) => true,
+            (Value::Int(_), Ty::Int) | (Value::Bool(_), Ty::Bool) => true,
+            (Value::Str(_), Ty::String) => true,
+            (Value::List(items), Ty::List(element)) => {
+                items.iter().all(|item| has_type(item, element))
+            }
+            (Value::Tuple(items), Ty::Tuple(types)) => {
+                items.len() == types.len()
+                    && items.iter().zip(types).all(|(item, ty)| has_type(item, ty))
+            }
+            (Value::Inl(inner), Ty::Sum(left, _)) => has_type(inner, left),
+            (Value::Inr(inner), Ty::Sum(_, right)) => has_type(inner, right),
+            (Value::Function(..), Ty::Function) => true,
+            (Value::Handle(_), Ty::Handle) | (Value::Channel(_), Ty::Channel) => true,
+            (Value::Enum(name, _), Ty::Named(ty)) => name == ty,
+            _ => false,
+        }
+    }
+
+    pub fn type_of(value: Value) -> Value {
+        Value::Str(type_string(&value))
+    }
+
+    fn type_string(value: &Value) -> String {
+        match value {
+            Value::List(items) => match items.first() {
+                Some(first) => format!("List {}", type_string(first)),
+                None => "List Unknown".to_string(),
+            },
+            Value::Tuple(items) => {
+                let types: Vec<String> = items.iter().map(type_string).collect();
+                format!("({})", types.join(", "))
+            }
+            Value::Inl(inner) => format!("({} + Unknown)", type_string(inner)),
+            Value::Inr(inner) => format!("(Unknown + {})", type_string(inner)),
+            Value::Enum(name, _) => name.to_string(),
+            other => type_name(other).to_string(),
+        }
+    }
+
+    fn escape_bytes(bytes: &[u8]) -> String {
+        format!("b\"{}\"", bytes.escape_ascii())
+    }
+
+    /// Text for `print`, or for `toString` when `for_print` is false; they
+    /// differ only in how functions and sums are shown
+    fn show(value: &Value, for_print: bool) -> String {
+        let show_all = |items: &[Value]| {
+            let items: Vec<String> = items.iter().map(|item| show(item, for_print)).collect();
+            items.join(", ")
+        };
+        match value {
+            Value::Unit => "()".to_string(),
+            Value::Int(n) => n.to_string(),
+            Value::Bool(b) => b.to_string(),
+            Value::Str(s) => s.clone(),
+            Value::Bytes(b) => escape_bytes(b),
+            Value::List(items) => format!("[{}]", show_all(items)),
+            Value::Tuple(items) => format!("({})", show_all(items)),
+            Value::Inl(inner) if for_print => format!("Left({})", show(inner, for_print)),
+            Value::Inr(inner) if for_print => format!("Right({})", show(inner, for_print)),
+            Value::Inl(inner) => format!("inl({})", show(inner, for_print)),
+            Value::Inr(inner) => format!("inr({})", show(inner, for_print)),
+            Value::Function(_, FunctionKind::Closure(param)) if for_print => {
+                format!("<function {}>", param)
+            }
+            Value::Function(_, FunctionKind::Closure(_)) => "<function>".to_string(),
+            Value::Function(_, FunctionKind::Recursive) if for_print => "<fixed_point>".to_string(),
+            Value::Function(_, FunctionKind::Recursive) => "<fixed-point>".to_string(),
+            Value::Handle(id) => format!("<thread {}>", id),
+            Value::Channel(id) => format!("<channel {}>", id),
+            Value::Enum(name, variant) => format!("{}.{}", name, variant),
+        }
+    }
+
+    pub fn print(value: Value) -> Value {
+        println!("{}", show(&value, true));
+        Value::Unit
+    }
+
+    pub fn to_string(value: Value) -> Value {
+        Value::Str(show(&value, false))
+    }
+}
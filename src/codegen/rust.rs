@@ -0,0 +1,699 @@
+//! Rust backend used by `--emit=rust`. Every Corrosion value is an `rt::Value`
+//! and every function a reference-counted closure, so the output is a single
+//! file that builds with plain `rustc` and no dependencies. The runtime in
+//! `runtime/corrosion.rs` is copied into every generated file.
+
+use super::{CodegenError, CodegenResult};
+use crate::ast::nodes::{
+    BinaryOperator, CaseArm, Child, ENTRY_POINT, Expression, Pattern, Program, Statement,
+    TypeExpression, UnaryOperator, hole_text,
+};
+use std::collections::{BTreeSet, HashSet};
+
+const RUNTIME: &str = include_str!("runtime/corrosion.rs");
+
+/// Keywords that can be written as raw identifiers
+const KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "do", "dyn",
+    "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl", "in", "let",
+    "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return",
+    "static", "struct", "trait", "true", "try", "type", "typeof", "unsafe", "unsized", "use",
+    "virtual", "where", "while", "yield",
+];
+
+/// Names that cannot be raw identifiers, that a `let` cannot shadow, or that
+/// the generated code relies on. Corrosion names in this list get a `_`
+/// appended.
+const RESERVED: &[&str] = &[
+    "Box", "Err", "None", "Ok", "Self", "Some", "args", "crate", "self", "super",
+];
+
+/// Translate a type-checked program into a standalone Rust file. When the
+/// program declares `main`, the compiled binary calls it with its command
+/// line arguments.
+pub fn generate(program: &Program) -> CodegenResult<String> {
+    let mut generator = RustGenerator {
+        indent: 1,
+        scopes: vec![HashSet::new()],
+        enums: HashSet::new(),
+    };
+
+    let mut body = String::new();
+    for statement in &program.statements {
+        generator.statement(statement, &mut body)?;
+    }
+    if generator.scopes[0].contains(ENTRY_POINT) {
+        // An Int returned by main is the exit status, as in the interpreter
+        generator.line(
+            &mut body,
+            &format!(
+                "if let Value::Int(code) = rt::call({}.clone(), Value::List(args.into_iter().map(Value::Str).collect()))? {{",
+                mangle(ENTRY_POINT)
+            ),
+        );
+        generator.line(&mut body, "    return Ok(code.rem_euclid(256) as i32);");
+        generator.line(&mut body, "}");
+    }
+
+    Ok(format!(
+        "// Compiled from Corrosion with --emit=rust\n#![allow(unused, non_snake_case)]\n\n{}\n\
+         use rt::Value;\n\n\
+         fn run(args: Vec<String>) -> rt::Result<i32> {{\n{}    Ok(0)\n}}\n\n\
+         fn main() {{\n    \
+             match run(std::env::args().skip(1).collect()) {{\n        \
+                 Ok(status) => std::process::exit(status),\n        \
+                 Err(message) => {{\n            \
+                     eprintln!(\"Error: {{}}\", message);\n            \
+                     std::process::exit(1);\n        \
+                 }}\n    \
+             }}\n\
+         }}\n",
+        RUNTIME, body
+    ))
+}
+
+struct RustGenerator {
+    indent: usize,
+    /// Corrosion names in scope. Rust `let` shadows the same way Corrosion
+    /// does, so names are never renamed; the scopes only decide which names a
+    /// closure has to capture.
+    scopes: Vec<HashSet<String>>,
+    /// Enums declared so far, whose variants `Color.Red` refers to
+    enums: HashSet<String>,
+}
+
+impl RustGenerator {
+    fn line(&self, out: &mut String, text: &str) {
+        out.push_str(&self.pad());
+        out.push_str(text);
+        out.push('\n');
+    }
+
+    fn pad(&self) -> String {
+        "    ".repeat(self.indent)
+    }
+
+    fn declare(&mut self, name: &str) -> String {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.to_string());
+        }
+        mangle(name)
+    }
+
+    fn is_visible(&self, name: &str) -> bool {
+        self.scopes.iter().any(|scope| scope.contains(name))
+    }
+
+    fn statement(&mut self, statement: &Statement, out: &mut String) -> CodegenResult<()> {
+        match statement {
+            Statement::VariableDeclaration { name, value, .. }
+            | Statement::ConstantDeclaration { name, value, .. } => {
+                let value = self.expression(value)?;
+                let name = self.declare(name);
+                self.line(out, &format!("let {} = {};", name, value));
+            }
+            Statement::FunctionDeclaration {
+                name, param, body, ..
+            } => {
+                // The function receives itself as an extra first parameter,
+                // which is how its body refers to it
+                let function = self.closure("rt::recursive", &[name, param], None, body)?;
+                let name = self.declare(name);
+                self.line(out, &format!("let {} = {};", name, function));
+            }
+            Statement::Import { span, .. } => {
+                return Err(CodegenError::new(
+                    "Imports are not supported by the Rust backend",
+                    span,
+                ));
+            }
+            // A variant is an `rt::Value::Enum` built where it is used, so
+            // the declaration itself needs no code
+            Statement::EnumDeclaration { name, .. } => {
+                self.enums.insert(name.clone());
+            }
+            Statement::Expression {
+                expression:
+                    Expression::For {
+                        variable,
+                        iterable,
+                        body,
+                        ..
+                    },
+                ..
+            } => {
+                let for_loop = self.for_loop(variable, iterable, body)?;
+                self.line(out, &for_loop);
+            }
+            Statement::Expression { expression, .. } => {
+                let expression = self.expression(expression)?;
+                self.line(out, &format!("{};", expression));
+            }
+            Statement::Return { value, .. } => {
+                let value = self.expression(value)?;
+                self.line(out, &format!("return Ok({});", value));
+            }
+        }
+        Ok(())
+    }
+
+    /// Emit the statements of a block, or nothing for a single expression
+    /// standing in for one, and return the expression giving its value. The
+    /// caller must have pushed a scope for the block.
+    fn block_contents(
+        &mut self,
+        expression: &Expression,
+        out: &mut String,
+    ) -> CodegenResult<String> {
+        let Expression::Block {
+            statements,
+            expression,
+            ..
+        } = expression
+        else {
+            return self.expression(expression);
+        };
+
+        for statement in statements {
+            self.statement(statement, out)?;
+        }
+        match expression {
+            Some(expression) => self.expression(expression),
+            None => Ok("Value::Unit".to_string()),
+        }
+    }
+
+    /// A Rust block evaluating to the value of `expression`, or to `()` when
+    /// `discard` is set, with `bindings` in scope inside it. `prelude` lines
+    /// come first in the block.
+    fn block(
+        &mut self,
+        bindings: &[&str],
+        prelude: &[String],
+        expression: &Expression,
+        discard: bool,
+    ) -> CodegenResult<String> {
+        self.scopes.push(HashSet::new());
+        for binding in bindings {
+            self.declare(binding);
+        }
+        let mut out = String::new();
+        self.indent += 1;
+        for line in prelude {
+            self.line(&mut out, line);
+        }
+        let result = self.block_contents(expression, &mut out);
+        let result = result.map(|value| {
+            match discard {
+                true if value == "Value::Unit" => {}
+                true => self.line(&mut out, &format!("{};", value)),
+                false => self.line(&mut out, &value),
+            }
+            out
+        });
+        self.indent -= 1;
+        self.scopes.pop();
+        Ok(format!("{{\n{}{}}}", result?, self.pad()))
+    }
+
+    /// A function value built by `constructor` around a `move` closure over
+    /// `params`. Free variables of the body are cloned into the closure first.
+    fn closure(
+        &mut self,
+        constructor: &str,
+        params: &[&str],
+        display_name: Option<&str>,
+        body: &Expression,
+    ) -> CodegenResult<String> {
+        let mut free = BTreeSet::new();
+        free_identifiers(body, &mut free);
+        let captures: Vec<&str> = free
+            .into_iter()
+            .filter(|name| !params.contains(name) && self.is_visible(name))
+            .collect();
+
+        // Captures and the closure sit one level deeper, inside a block
+        if !captures.is_empty() {
+            self.indent += 1;
+        }
+        let signature: Vec<String> = params
+            .iter()
+            .map(|param| format!("{}: Value", mangle(param)))
+            .collect();
+        let closure = self
+            .function_body(params, body)
+            .map(|body| format!("move |{}| -> rt::Result {}", signature.join(", "), body));
+        let closure = if captures.is_empty() {
+            closure?
+        } else {
+            let mut out = String::from("{\n");
+            for capture in &captures {
+                let capture = mangle(capture);
+                self.line(&mut out, &format!("let {} = {}.clone();", capture, capture));
+            }
+            let closure = closure.map(|closure| {
+                self.line(&mut out, &closure);
+                out
+            });
+            self.indent -= 1;
+            format!("{}{}}}", closure?, self.pad())
+        };
+
+        Ok(match display_name {
+            Some(name) => format!("{}({:?}, {})", constructor, name, closure),
+            None => format!("{}({})", constructor, closure),
+        })
+    }
+
+    /// Body of a closure: one line for a short expression, otherwise the
+    /// statements of the block followed by its value
+    fn function_body(&mut self, params: &[&str], body: &Expression) -> CodegenResult<String> {
+        self.scopes
+            .push(params.iter().map(|param| param.to_string()).collect());
+        let mut out = String::new();
+        self.indent += 1;
+        let result = self.block_contents(body, &mut out).map(|value| {
+            if out.is_empty() && !value.contains('\n') {
+                return Some(value);
+            }
+            self.line(&mut out, &format!("Ok({})", value));
+            None
+        });
+        self.indent -= 1;
+        self.scopes.pop();
+        Ok(match result? {
+            Some(value) => format!("{{ Ok({}) }}", value),
+            None => format!("{{\n{}{}}}", out, self.pad()),
+        })
+    }
+
+    /// A Rust `for` loop, which evaluates to `()`
+    fn for_loop(
+        &mut self,
+        variable: &str,
+        iterable: &Expression,
+        body: &Expression,
+    ) -> CodegenResult<String> {
+        let iterable = self.expression(iterable)?;
+        let body = self.block(&[variable], &[], body, true)?;
+        Ok(format!(
+            "for {} in rt::list({})? {}",
+            mangle(variable),
+            iterable,
+            body
+        ))
+    }
+
+    fn call(&mut self, function: &str, arguments: &[&Expression]) -> CodegenResult<String> {
+        let arguments = arguments
+            .iter()
+            .map(|argument| self.expression(argument))
+            .collect::<CodegenResult<Vec<_>>>()?;
+        Ok(format!("{}({})", function, arguments.join(", ")))
+    }
+
+    /// Call to a runtime function that can fail
+    fn try_call(&mut self, function: &str, arguments: &[&Expression]) -> CodegenResult<String> {
+        self.call(function, arguments).map(|call| call + "?")
+    }
+
+    fn expression(&mut self, expression: &Expression) -> CodegenResult<String> {
+        Ok(match expression {
+            Expression::Identifier { name, .. } => format!("{}.clone()", mangle(name)),
+            Expression::QualifiedIdentifier { module, name, .. } if self.enums.contains(module) => {
+                format!("Value::Enum({:?}, {:?})", module, name)
+            }
+            Expression::QualifiedIdentifier { span, .. } => {
+                return Err(CodegenError::new(
+                    "Modules are not supported by the Rust backend",
+                    span,
+                ));
+            }
+            Expression::Hole { name, span } => {
+                return Err(CodegenError::new(
+                    format!(
+                        "The hole {} must be filled in first",
+                        hole_text(name.as_deref())
+                    ),
+                    span,
+                ));
+            }
+            Expression::Number { value, .. } => format!("Value::Int({})", value),
+            Expression::BigNumber { span, .. } => {
+                return Err(CodegenError::new(
+                    "BigInt is not supported by the Rust backend",
+                    span,
+                ));
+            }
+            Expression::Boolean { value, .. } => format!("Value::Bool({})", value),
+            Expression::String { value, .. } => format!("Value::Str({:?}.to_string())", value),
+            Expression::Bytes { value, .. } => {
+                let bytes: Vec<String> = value.iter().map(|byte| byte.to_string()).collect();
+                format!("Value::Bytes(vec![{}])", bytes.join(", "))
+            }
+            Expression::BinaryOp {
+                left,
+                operator,
+                right,
+                span,
+            } => {
+                let (l, r) = (self.expression(left)?, self.expression(right)?);
+                match operator {
+                    BinaryOperator::Add => format!("rt::arithmetic('+', {}, {})?", l, r),
+                    BinaryOperator::Subtract => format!("rt::arithmetic('-', {}, {})?", l, r),
+                    BinaryOperator::Multiply => format!("rt::arithmetic('*', {}, {})?", l, r),
+                    BinaryOperator::Divide => format!("rt::arithmetic('/', {}, {})?", l, r),
+                    BinaryOperator::Equal => format!("rt::equals({}, {})", l, r),
+                    BinaryOperator::NotEqual => format!("rt::not_equals({}, {})", l, r),
+                    BinaryOperator::LessThan => format!("rt::compare(\"<\", {}, {})?", l, r),
+                    BinaryOperator::LessThanEqual => {
+                        format!("rt::compare(\"<=\", {}, {})?", l, r)
+                    }
+                    BinaryOperator::GreaterThan => format!("rt::compare(\">\", {}, {})?", l, r),
+                    BinaryOperator::GreaterThanEqual => {
+                        format!("rt::compare(\">=\", {}, {})?", l, r)
+                    }
+                    BinaryOperator::LogicalAnd => format!("rt::and({}, {})?", l, r),
+                    BinaryOperator::LogicalOr => format!("rt::or({}, {})?", l, r),
+                    BinaryOperator::ComposeForward => format!("rt::compose({}, {})?", l, r),
+                    BinaryOperator::ComposeBackward => format!("rt::compose({}, {})?", r, l),
+                    BinaryOperator::Append => format!("rt::append({}, {})?", l, r),
+                    BinaryOperator::Assign => {
+                        return Err(CodegenError::new(
+                            "Assignment operator not supported in expressions",
+                            span,
+                        ));
+                    }
+                }
+            }
+            Expression::UnaryOp {
+                operator, operand, ..
+            } => match operator {
+                UnaryOperator::LogicalNot => self.try_call("rt::not", &[operand])?,
+                UnaryOperator::Negate => self.try_call("rt::negate", &[operand])?,
+            },
+            Expression::Function { param, body, .. } => {
+                self.closure("rt::function", &[param], Some(param), body)?
+            }
+            Expression::FunctionCall {
+                function, argument, ..
+            } => self.try_call("rt::call", &[function, argument])?,
+            Expression::List { elements, .. } => {
+                let elements = elements
+                    .iter()
+                    .map(|element| self.expression(element))
+                    .collect::<CodegenResult<Vec<_>>>()?;
+                format!("Value::List(vec![{}])", elements.join(", "))
+            }
+            Expression::Pair { first, second, .. } => format!(
+                "Value::Tuple(vec![{}, {}])",
+                self.expression(first)?,
+                self.expression(second)?
+            ),
+            Expression::Tuple { elements, .. } => {
+                let elements = elements
+                    .iter()
+                    .map(|element| self.expression(element))
+                    .collect::<CodegenResult<Vec<_>>>()?;
+                format!("Value::Tuple(vec![{}])", elements.join(", "))
+            }
+            Expression::LeftInject { value, .. } => {
+                format!("Value::Inl(Box::new({}))", self.expression(value)?)
+            }
+            Expression::RightInject { value, .. } => {
+                format!("Value::Inr(Box::new({}))", self.expression(value)?)
+            }
+            Expression::Fix { function, .. } => self.try_call("rt::fix", &[function])?,
+            Expression::Block {
+                statements,
+                expression: inner,
+                ..
+            } => match (statements.is_empty(), inner) {
+                (true, Some(inner)) => self.expression(inner)?,
+                (true, None) => "Value::Unit".to_string(),
+                (false, _) => self.block(&[], &[], expression, false)?,
+            },
+            Expression::FirstProjection { pair, .. } => {
+                format!("rt::project({}, 0)?", self.expression(pair)?)
+            }
+            Expression::SecondProjection { pair, .. } => {
+                format!("rt::project({}, 1)?", self.expression(pair)?)
+            }
+            Expression::TupleProjection { tuple, index, .. } => {
+                format!("rt::project({}, {})?", self.expression(tuple)?, index)
+            }
+            Expression::Cons { head, tail, .. } => self.try_call("rt::cons", &[head, tail])?,
+            Expression::HeadProjection { list, .. } => self.try_call("rt::head", &[list])?,
+            Expression::TailProjection { list, .. } => self.try_call("rt::tail", &[list])?,
+            Expression::Print { value, .. } => self.call("rt::print", &[value])?,
+            Expression::If {
+                condition,
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                let condition = self.expression(condition)?;
+                let then_branch = self.block(&[], &[], then_branch, false)?;
+                let else_branch = match else_branch {
+                    // `else { if .. }` with nothing else in the block reads as `else if`
+                    Some(else_branch) => match nested_if(else_branch) {
+                        Some(nested) => self.expression(nested)?,
+                        None => self.block(&[], &[], else_branch, false)?,
+                    },
+                    None => "{ Value::Unit }".to_string(),
+                };
+                format!(
+                    "if rt::bool({})? {} else {}",
+                    condition, then_branch, else_branch
+                )
+            }
+            Expression::For {
+                variable,
+                iterable,
+                body,
+                ..
+            } => {
+                self.indent += 1;
+                let for_loop = self.for_loop(variable, iterable, body);
+                self.indent -= 1;
+                let pad = self.pad();
+                format!("{{\n{pad}    {}\n{pad}    Value::Unit\n{pad}}}", for_loop?)
+            }
+            Expression::With {
+                name, value, body, ..
+            } => {
+                let value = self.expression(value)?;
+                let prelude = [
+                    format!("let {} = {};", mangle(name), value),
+                    format!("let _release = rt::Release({}.clone());", mangle(name)),
+                ];
+                self.block(&[name], &prelude, body, false)?
+            }
+            Expression::Range { start, end, .. } => self.try_call("rt::range", &[start, end])?,
+            Expression::DivMod {
+                dividend, divisor, ..
+            } => self.try_call("rt::divmod", &[dividend, divisor])?,
+            Expression::SafeDiv {
+                dividend, divisor, ..
+            } => self.try_call("rt::safe_div", &[dividend, divisor])?,
+            Expression::Concat { left, right, .. } => {
+                self.try_call("rt::concat", &[left, right])?
+            }
+            Expression::CharAt { string, index, .. } => {
+                self.try_call("rt::char_at", &[string, index])?
+            }
+            Expression::Length { string, .. } => self.try_call("rt::length", &[string])?,
+            Expression::Graphemes { span, .. } => {
+                return Err(CodegenError::new(
+                    "graphemes is not supported by the Rust backend",
+                    span,
+                ));
+            }
+            Expression::Spawn { function, .. } => self.try_call("rt::spawn", &[function])?,
+            Expression::Join { handle, .. } => self.try_call("rt::join", &[handle])?,
+            Expression::Sleep { milliseconds, .. } => {
+                self.try_call("rt::sleep", &[milliseconds])?
+            }
+            Expression::Random { bound, .. } => self.try_call("rt::random", &[bound])?,
+            Expression::Channel { .. } => "rt::channel()".to_string(),
+            Expression::Send { channel, value, .. } => {
+                self.try_call("rt::send", &[channel, value])?
+            }
+            Expression::Recv { channel, .. } => self.try_call("rt::recv", &[channel])?,
+            Expression::ReadFileBytes { path, .. } => {
+                self.try_call("rt::read_file_bytes", &[path])?
+            }
+            Expression::Input { .. } => self.try_call("rt::input", &[])?,
+            Expression::ReadAll { .. } => self.try_call("rt::read_all", &[])?,
+            Expression::ByteAt { bytes, index, .. } => {
+                self.try_call("rt::byte_at", &[bytes, index])?
+            }
+            Expression::ToBytes { string, .. } => self.try_call("rt::to_bytes", &[string])?,
+            Expression::FromBytes { bytes, .. } => self.try_call("rt::from_bytes", &[bytes])?,
+            Expression::ToInt { string, .. } => self.try_call("rt::to_int", &[string])?,
+            Expression::ToString { expression, .. } => self.call("rt::to_string", &[expression])?,
+            Expression::TypeOf { expression, .. } => self.call("rt::type_of", &[expression])?,
+            Expression::TypeTest {
+                expression,
+                type_annotation,
+                ..
+            } => format!(
+                "Value::Bool(rt::has_type(&{}, &{}))",
+                self.expression(expression)?,
+                type_descriptor(type_annotation)
+            ),
+            Expression::TypeAscription { expression, .. } => self.expression(expression)?,
+            Expression::Case {
+                expression,
+                left_pattern,
+                left_body,
+                right_pattern,
+                right_body,
+                ..
+            } => {
+                let value = self.expression(expression)?;
+                self.indent += 1;
+                let arms = [
+                    ("Inl", left_pattern, left_body),
+                    ("Inr", right_pattern, right_body),
+                ]
+                .map(|(variant, pattern, body)| {
+                    let binding = mangle(pattern);
+                    // A `_` binder is not a variable and has nothing to unbox
+                    let unbox = match binding.as_str() {
+                        "_" => Vec::new(),
+                        _ => vec![format!("let {} = *{};", binding, binding)],
+                    };
+                    self.block(&[pattern], &unbox, body, false)
+                        .map(|body| format!("Value::{}({}) => {}", variant, binding, body))
+                });
+                self.indent -= 1;
+                let pad = self.pad();
+                let [left, right] = arms;
+                format!(
+                    "match {} {{\n{pad}    {}\n{pad}    {}\n{pad}    \
+                     _ => return Err(\"Expected a sum type value\".to_string()),\n{pad}}}",
+                    value, left?, right?,
+                )
+            }
+            Expression::Match {
+                expression, arms, ..
+            } => {
+                let value = self.expression(expression)?;
+                self.indent += 1;
+                let arms = self.case_arms(arms);
+                self.indent -= 1;
+                let pad = self.pad();
+                format!(
+                    "match {} {{\n{pad}    {}\n{pad}}}",
+                    value,
+                    arms?.join(&format!("\n{pad}    "))
+                )
+            }
+        })
+    }
+
+    /// The arms of a Rust `match` for a `case` on values, ending with one
+    /// that fails if no earlier arm matches
+    fn case_arms(&mut self, arms: &[CaseArm]) -> CodegenResult<Vec<String>> {
+        let mut translated = Vec::new();
+        for arm in arms {
+            let pattern = match &arm.pattern {
+                Pattern::Wildcard { .. } => "_".to_string(),
+                Pattern::String { value, .. } => format!("Value::Str(s) if s == {:?}", value),
+                Pattern::Int { value, .. } => format!("Value::Int({})", value),
+                Pattern::Range { start, end, .. } => format!(
+                    "Value::Int({}..{})",
+                    start.map(|start| start.to_string()).unwrap_or_default(),
+                    end.map(|end| end.to_string()).unwrap_or_default()
+                ),
+                Pattern::Variant {
+                    enum_name, variant, ..
+                } => format!("Value::Enum({:?}, {:?})", enum_name, variant),
+            };
+            let body = self.block(&[], &[], &arm.body, false)?;
+            translated.push(format!("{} => {}", pattern, body));
+            if matches!(arm.pattern, Pattern::Wildcard { .. }) {
+                return Ok(translated);
+            }
+        }
+        translated
+            .push("_ => return Err(\"No case arm matches the value\".to_string()),".to_string());
+        Ok(translated)
+    }
+}
+
+/// Names used anywhere in an expression. Local bindings are included too,
+/// which at worst makes a closure capture a variable it does not need.
+fn free_identifiers<'a>(expression: &'a Expression, names: &mut BTreeSet<&'a str>) {
+    if let Expression::Identifier { name, .. } = expression {
+        names.insert(name);
+    }
+    for child in expression.children() {
+        match child {
+            Child::Expression(_, expression) => free_identifiers(expression, names),
+            Child::Statement(
+                Statement::VariableDeclaration { value: body, .. }
+                | Statement::ConstantDeclaration { value: body, .. }
+                | Statement::FunctionDeclaration { body, .. }
+                | Statement::Expression {
+                    expression: body, ..
+                }
+                | Statement::Return { value: body, .. },
+            ) => free_identifiers(body, names),
+            Child::Statement(Statement::Import { .. } | Statement::EnumDeclaration { .. }) => {}
+        }
+    }
+}
+
+/// The `if` expression a branch consists of, if that is all it contains
+fn nested_if(branch: &Expression) -> Option<&Expression> {
+    match branch {
+        Expression::If { .. } => Some(branch),
+        Expression::Block {
+            statements,
+            expression: Some(expression),
+            ..
+        } if statements.is_empty() => nested_if(expression),
+        _ => None,
+    }
+}
+
+fn mangle(name: &str) -> String {
+    if RESERVED.contains(&name) {
+        format!("{}_", name)
+    } else if KEYWORDS.contains(&name) {
+        format!("r#{}", name)
+    } else {
+        name.to_string()
+    }
+}
+
+/// Type annotation as an `rt::Ty`. Pairs are two-element tuples at runtime,
+/// and a recursive type is checked against its body.
+fn type_descriptor(ty: &TypeExpression) -> String {
+    let boxed = |ty: &TypeExpression| format!("Box::new({})", type_descriptor(ty));
+    match ty {
+        TypeExpression::Int { .. } => "rt::Ty::Int".to_string(),
+        TypeExpression::Bool { .. } => "rt::Ty::Bool".to_string(),
+        TypeExpression::String { .. } => "rt::Ty::String".to_string(),
+        TypeExpression::Named { name, .. } => format!("rt::Ty::Named({:?})", name),
+        TypeExpression::List { element, .. } => format!("rt::Ty::List({})", boxed(element)),
+        TypeExpression::Handle { .. } => "rt::Ty::Handle".to_string(),
+        TypeExpression::Channel { .. } => "rt::Ty::Channel".to_string(),
+        TypeExpression::Function { .. } => "rt::Ty::Function".to_string(),
+        TypeExpression::Pair { first, second, .. } => format!(
+            "rt::Ty::Tuple(vec![{}, {}])",
+            type_descriptor(first),
+            type_descriptor(second)
+        ),
+        TypeExpression::Tuple { elements, .. } => {
+            let elements: Vec<String> = elements.iter().map(type_descriptor).collect();
+            format!("rt::Ty::Tuple(vec![{}])", elements.join(", "))
+        }
+        TypeExpression::Sum { left, right, .. } => {
+            format!("rt::Ty::Sum({}, {})", boxed(left), boxed(right))
+        }
+        TypeExpression::Recursive { inner, .. } => type_descriptor(inner),
+    }
+}
@@ -0,0 +1,224 @@
+use crate::ast::{Parser, Program};
+use crate::lexer::Tokenizer;
+use crate::typechecker::{OperatorTypes, TypeChecker};
+
+fn parse(source: &str) -> Program {
+    let tokens = Tokenizer::new(source).tokenize().unwrap();
+    Parser::new(tokens).parse().unwrap()
+}
+
+/// Type check a program and translate it to JavaScript, leaving out the prelude
+fn generate_js(source: &str) -> super::CodegenResult<String> {
+    let program = parse(source);
+    let mut type_checker = TypeChecker::new();
+    type_checker.check_program(&program).unwrap();
+    super::js::generate(&program, type_checker.operator_types(), false)
+}
+
+/// The translated program, without the runtime that precedes it
+fn js(source: &str) -> String {
+    let output = generate_js(source).unwrap();
+    output[output.rfind("\n{\n").unwrap()..].to_string()
+}
+
+#[test]
+fn test_js_functions_and_blocks() {
+    let output = js("fn fact(n: Int) -> Int {
+             if n <= 1 { 1 } else { n * fact(n - 1) }
+         }
+         let add = fn(a: Int) { fn(b: Int) { a + b } };
+         print(add(fact(3))(1));");
+
+    assert!(output.contains("    function fact(n) {\n        if ($compare(n, 1n) <= 0) {\n            return 1n;\n        } else {\n            return $int(n * fact($int(n - 1n)));\n        }\n    }\n"));
+    assert!(output.contains("    const add = (a) => (b) => $int(a + b);\n"));
+    assert!(output.contains("    $print(add(fact(3n))(1n));\n"));
+}
+
+#[test]
+fn test_js_shadowing_and_reserved_names() {
+    let output = js("let x = 1;
+         let new = 2;
+         for i in [1, 2] { let x = x + i; print(x); };");
+
+    assert!(output.contains("const new$ = 2n;"));
+    assert!(output.contains(
+        "for (const i of [1n, 2n]) {\n        const x$1 = $int(x + i);\n        $print(x$1);\n    }"
+    ));
+}
+
+#[test]
+fn test_js_calls_main_with_arguments() {
+    let output = js("fn main(args: List String) { print(args); }");
+    assert!(output.contains(
+        "const $status = main(typeof process !== \"undefined\" ? process.argv.slice(2) : []);"
+    ));
+    assert!(output.contains("process.exitCode = Number(BigInt.asUintN(8, $status));"));
+}
+
+#[test]
+fn test_js_rejects_imports() {
+    let program = parse("import \"math.corr\" as math;");
+    let error = super::js::generate(&program, &OperatorTypes::new(), false).unwrap_err();
+    assert_eq!(
+        error.message,
+        "Imports are not supported by the JavaScript backend"
+    );
+}
+
+#[test]
+fn test_js_returns_leave_the_function() {
+    let output = js("let f = fn(n: Int) { if n < 0 { return 0; } else { n } };");
+    assert!(output.contains("    const f = (n) => {\n        if ($compare(n, 0n) < 0) {\n            return 0n;\n        } else {\n            return n;\n        }\n    };\n"));
+
+    // In a block used as a value, a JavaScript `return` would only leave the
+    // arrow function wrapping the block
+    let source = "let f = fn(n: Int) { let x = if n < 0 { return 0; } else { n }; x };";
+    let error = generate_js(source).unwrap_err();
+    assert_eq!(
+        error.message,
+        "'return' inside a block used as a value is not supported by the JavaScript backend"
+    );
+}
+
+/// The translated `run` function, without the runtime and `main` around it
+fn rust(source: &str) -> String {
+    let output = super::rust::generate(&parse(source)).unwrap();
+    let start = output.find("fn run(").unwrap();
+    let end = output.rfind("\nfn main()").unwrap();
+    output[start..end].to_string()
+}
+
+#[test]
+fn test_rust_functions_capture_free_variables() {
+    let output = rust(
+        "fn fact(n: Int) -> Int {
+             if n <= 1 { 1 } else { n * fact(n - 1) }
+         }
+         let add = fn(a: Int) { fn(b: Int) { a + b } };",
+    );
+
+    assert!(output.contains(
+        "    let fact = rt::recursive(move |fact: Value, n: Value| -> rt::Result {\n        Ok(if rt::bool(rt::compare(\"<=\", n.clone(), Value::Int(1))?)? {"
+    ));
+    assert!(output.contains(
+        "        Ok(rt::function(\"b\", {\n            let a = a.clone();\n            move |b: Value| -> rt::Result { Ok(rt::arithmetic('+', a.clone(), b.clone())?) }\n        }))"
+    ));
+}
+
+#[test]
+fn test_rust_loops_cases_and_keywords() {
+    let output = rust(
+        "let match = [1, 2];
+         for i in match { print(i); };
+         let r = case inl(1) of inl v => v | inr e => 0;",
+    );
+
+    assert!(
+        output.contains("    let r#match = Value::List(vec![Value::Int(1), Value::Int(2)]);\n")
+    );
+    assert!(output.contains(
+        "    for i in rt::list(r#match.clone())? {\n        rt::print(i.clone());\n    }\n"
+    ));
+    assert!(output.contains(
+        "        Value::Inl(v) => {\n            let v = *v;\n            v.clone()\n        }\n"
+    ));
+}
+
+#[test]
+fn test_rust_calls_main_with_arguments() {
+    let output = rust("fn main(args: List String) { print(args); }");
+    assert!(output.contains("move |main: Value, args_: Value|"));
+    assert!(output.contains(
+        "    if let Value::Int(code) = rt::call(main.clone(), Value::List(args.into_iter().map(Value::Str).collect()))? {\n        return Ok(code.rem_euclid(256) as i32);\n    }\n"
+    ));
+}
+
+#[test]
+fn test_rust_rejects_big_integers() {
+    let error = super::rust::generate(&parse("print(1n);")).unwrap_err();
+    assert_eq!(error.message, "BigInt is not supported by the Rust backend");
+}
+
+#[test]
+fn test_rust_wildcard_binders_are_not_unboxed() {
+    let output = rust("let r = case inl(1) of inl _ => 1 | inr e => 0;");
+    assert!(output.contains("        Value::Inl(_) => {\n            Value::Int(1)\n        }\n"));
+    assert!(!output.contains("*_"));
+}
+
+/// Compile the generated crate with `rustc` and run it, returning its output
+fn compile_and_run_rust(name: &str, source: &str) -> String {
+    let dir =
+        std::env::temp_dir().join(format!("corrosion-codegen-{}-{}", name, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("main.rs");
+    std::fs::write(&file, super::rust::generate(&parse(source)).unwrap()).unwrap();
+    let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    let compiled = std::process::Command::new(rustc)
+        .args(["--edition", "2021", "-o"])
+        .arg(dir.join("main"))
+        .arg(&file)
+        .output()
+        .unwrap();
+    assert!(
+        compiled.status.success(),
+        "{}",
+        String::from_utf8_lossy(&compiled.stderr)
+    );
+    let run = std::process::Command::new(dir.join("main"))
+        .output()
+        .unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    String::from_utf8(run.stdout).unwrap()
+}
+
+#[test]
+fn test_rust_output_with_wildcard_binders_compiles() {
+    let output = compile_and_run_rust(
+        "wildcard",
+        "let r = case inl(1) of inl _ => 1 | inr e => 0;
+         let s = case inr(2) of inl v => v | inr _ => 3;
+         print(r + s);",
+    );
+    assert_eq!(output, "4\n");
+}
+
+#[test]
+fn test_js_enums_are_variant_objects() {
+    let output = js("enum Color { Red, Green }
+         let c = Color.Green;
+         print(case c of Color.Red => 1 | _ => 2);");
+
+    assert!(output.contains("    const c = new Variant(\"Color\", \"Green\");\n"));
+    assert!(output.contains("(value) => $eq(value, new Variant(\"Color\", \"Red\"))"));
+}
+
+#[test]
+fn test_rust_output_with_enums_compiles() {
+    let output = compile_and_run_rust(
+        "enums",
+        "enum Color { Red, Green, Blue }
+         let name = fn(c: Color) { case c of Color.Red => \"red\" | Color.Green => \"green\" | _ => \"other\" };
+         print(name(Color.Green));
+         print(name(Color.Blue));
+         print(Color.Red == Color.Red);
+         print([Color.Red, Color.Blue]);
+         print(Color.Blue is Color);",
+    );
+    assert_eq!(
+        output,
+        "green\nother\ntrue\n[Color.Red, Color.Blue]\ntrue\n"
+    );
+}
+
+#[test]
+fn test_js_int_arithmetic_follows_the_checked_types() {
+    // Int and BigInt are both JavaScript bigints, so only the types tell
+    // which arithmetic fails on overflow
+    let output = js("let big = 9223372036854775807n + 1;
+         let small = -(2 * 3);
+         let count = 1 + length(\"abc\");");
+    assert!(output.contains("    const big = 9223372036854775807n + 1n;\n"));
+    assert!(output.contains("    const small = $int(-$int(2n * 3n));\n"));
+    assert!(output.contains("    const count = $int(1n + $length(\"abc\"));\n"));
+}
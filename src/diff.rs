@@ -0,0 +1,125 @@
+//! Semantic differences between two versions of a program: which top-level
+//! definitions were added, removed or changed, and how their types changed.
+//! Definitions are compared as the formatter writes them, so changes to
+//! layout and comments are not differences.
+
+use crate::ast::{Program, Statement};
+use crate::typechecker::{Type, TypedProgram, TypedStatement};
+use std::fmt;
+
+/// A top-level `let`, `const`, `fn` or `enum` of a checked program
+#[derive(Debug, Clone, PartialEq)]
+pub struct Definition {
+    /// The keyword declaring it
+    pub kind: &'static str,
+    pub name: String,
+    pub ty: Type,
+    /// The declaration in canonical layout
+    text: String,
+}
+
+impl Definition {
+    /// The top-level definitions of `program`, in order, with their types
+    /// from `typed`, the result of checking it
+    pub fn of_program(program: &Program, typed: &TypedProgram) -> Vec<Definition> {
+        program
+            .statements
+            .iter()
+            .zip(&typed.statements)
+            .filter_map(|(statement, typed)| {
+                let (kind, name, ty) = match typed {
+                    TypedStatement::VariableDeclaration { name, ty, .. } => {
+                        ("let", name, ty.clone())
+                    }
+                    TypedStatement::ConstantDeclaration { name, ty, .. } => {
+                        ("const", name, ty.clone())
+                    }
+                    TypedStatement::FunctionDeclaration {
+                        name,
+                        param_type,
+                        return_type,
+                        ..
+                    } => (
+                        "fn",
+                        name,
+                        Type::function(param_type.clone(), return_type.clone()),
+                    ),
+                    TypedStatement::EnumDeclaration { name, .. } => {
+                        ("enum", name, Type::enumeration(name))
+                    }
+                    _ => return None,
+                };
+                Some(Definition {
+                    kind,
+                    name: name.clone(),
+                    ty,
+                    text: format_statement(statement, program),
+                })
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for Definition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}: {}", self.kind, self.name, self.ty)
+    }
+}
+
+fn format_statement(statement: &Statement, program: &Program) -> String {
+    Program::new(vec![statement.clone()], program.span.clone()).format()
+}
+
+/// How a top-level definition differs between two versions of a program
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    Added(Definition),
+    Removed(Definition),
+    /// A definition of the same name that is declared, typed or written
+    /// differently
+    Changed {
+        old: Definition,
+        new: Definition,
+    },
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Change::Added(definition) => write!(f, "+ {}", definition),
+            Change::Removed(definition) => write!(f, "- {}", definition),
+            Change::Changed { old, new } if old.kind != new.kind || old.ty != new.ty => {
+                write!(f, "~ {}, now {}", old, new)
+            }
+            Change::Changed { new, .. } => write!(f, "~ {}, definition changed", new),
+        }
+    }
+}
+
+/// The changes from the definitions `old` to `new`: removed and changed
+/// definitions in the order of `old`, then added ones in the order of `new`
+pub fn diff(old: &[Definition], new: &[Definition]) -> Vec<Change> {
+    let find = |definitions: &[Definition], name: &str| {
+        definitions
+            .iter()
+            .find(|definition| definition.name == name)
+            .cloned()
+    };
+    let mut changes = Vec::new();
+    for before in old {
+        match find(new, &before.name) {
+            None => changes.push(Change::Removed(before.clone())),
+            Some(after) if after != *before => changes.push(Change::Changed {
+                old: before.clone(),
+                new: after,
+            }),
+            Some(_) => {}
+        }
+    }
+    for after in new {
+        if find(old, &after.name).is_none() {
+            changes.push(Change::Added(after.clone()));
+        }
+    }
+    changes
+}
@@ -0,0 +1,470 @@
+//! An [`Engine`] evaluates independent pieces of Corrosion source, from any
+//! number of threads at once. It defines the prelude once and gives each
+//! evaluation its own interpreter and type checker starting from it, so no
+//! state is shared between evaluations.
+//!
+//! Source run many times, such as a template or a rule, can be compiled once
+//! with [`Engine::compile`] and run with different inputs each time.
+
+use crate::ast::nodes::{Program, Spanned, Statement};
+use crate::ast::parser::{ParseError, Parser};
+use crate::interpreter::{
+    DEFAULT_MAX_CALL_DEPTH, Environment, ExecOptions, Interpreter, InterpreterError, Value,
+    with_stack,
+};
+use crate::lexer::edition::Edition;
+use crate::lexer::tokenizer::{TokenizeError, Tokenizer};
+use crate::typechecker::{Type, TypeChecker, TypeError};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, OnceLock};
+
+/// An error from one stage of evaluating source with an [`Engine`]
+#[derive(Debug)]
+pub enum EngineError {
+    Tokenize(TokenizeError),
+    Parse(ParseError),
+    Type(TypeError),
+    Runtime(InterpreterError),
+}
+
+impl EngineError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            EngineError::Tokenize(error) => error.code(),
+            EngineError::Parse(error) => error.code(),
+            EngineError::Type(error) => error.code(),
+            EngineError::Runtime(error) => error.code(),
+        }
+    }
+}
+
+impl std::fmt::Display for EngineError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EngineError::Tokenize(error) => write!(f, "{}", error),
+            EngineError::Parse(error) => write!(f, "{}", error),
+            EngineError::Type(error) => write!(f, "{}", error),
+            EngineError::Runtime(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for EngineError {}
+
+/// Settings and prelude shared by every evaluation. It is `Send + Sync`, so
+/// one engine behind an `Arc` can serve every thread of a server. Cloning
+/// it is cheap.
+#[derive(Debug, Clone)]
+pub struct Engine {
+    /// The bindings every evaluation starts from
+    globals: Arc<Environment>,
+    prelude: bool,
+    current_directory: Option<PathBuf>,
+    search_path: Vec<PathBuf>,
+    edition: Edition,
+    max_call_depth: usize,
+}
+
+impl Engine {
+    /// An engine whose evaluations have the prelude defined
+    pub fn new() -> Self {
+        Self::create(true)
+    }
+
+    /// An engine whose evaluations start with nothing defined
+    pub fn without_prelude() -> Self {
+        Self::create(false)
+    }
+
+    fn create(prelude: bool) -> Self {
+        let interpreter = Interpreter::builder().prelude(prelude).build();
+        Self {
+            globals: Arc::new(interpreter.environment().clone()),
+            prelude,
+            current_directory: None,
+            search_path: Vec::new(),
+            edition: Edition::default(),
+            max_call_depth: DEFAULT_MAX_CALL_DEPTH,
+        }
+    }
+
+    /// Resolve imports relative to `directory`
+    pub fn with_current_directory(mut self, directory: impl Into<PathBuf>) -> Self {
+        self.current_directory = Some(directory.into());
+        self
+    }
+
+    /// Look for imports in these directories when they are not found in the
+    /// current directory
+    pub fn with_module_search_path(mut self, search_path: Vec<PathBuf>) -> Self {
+        self.search_path = search_path;
+        self
+    }
+
+    /// Tokenize source, and read imported modules, in `edition`
+    pub fn with_edition(mut self, edition: Edition) -> Self {
+        self.edition = edition;
+        self
+    }
+
+    /// Stop an evaluation with a runtime error when function calls nest
+    /// deeper than `depth`, instead of `DEFAULT_MAX_CALL_DEPTH`
+    pub fn with_max_call_depth(mut self, depth: usize) -> Self {
+        self.max_call_depth = depth;
+        self
+    }
+
+    /// Check and run `source` as `-e` does, returning the value of its last
+    /// statement
+    pub fn evaluate(&self, source: &str) -> Result<Value, EngineError> {
+        self.evaluate_with(source, &ExecOptions::repl())
+    }
+
+    /// Check and run `source` as set out by `options`, such as to capture
+    /// what it prints
+    pub fn evaluate_with(&self, source: &str, options: &ExecOptions) -> Result<Value, EngineError> {
+        self.compile(source, &[])?.run_with(HashMap::new(), options)
+    }
+
+    /// Check and evaluate the single expression `source` with `variables`
+    /// bound, each with the type its value has, such as a spreadsheet
+    /// formula given its cells
+    pub fn evaluate_expression_with(
+        &self,
+        source: &str,
+        variables: HashMap<String, Value>,
+    ) -> Result<Value, EngineError> {
+        // An expression is a program of one statement, without its `;`. The
+        // `;` goes on a line of its own so a trailing comment cannot hide it.
+        let source = source.trim_end();
+        let program = self.parse(&format!(
+            "{}\n;",
+            source.strip_suffix(';').unwrap_or(source)
+        ))?;
+        if let Some(statement) = program
+            .statements
+            .iter()
+            .find(|statement| !matches!(statement, Statement::Expression { .. }))
+            .or(program.statements.get(1))
+        {
+            return Err(EngineError::Parse(ParseError::InvalidExpression {
+                message: "Expected a single expression".to_string(),
+                span: statement.span().clone(),
+            }));
+        }
+
+        let types: Vec<(&str, Type)> = variables
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.static_type()))
+            .collect();
+        // A record is read a field at a time, as `point.x`, so its fields are
+        // checked like the exports of a module
+        let records: Vec<(&str, HashMap<String, Type>)> = variables
+            .iter()
+            .filter_map(|(name, value)| match value {
+                Value::Module { exports, .. } => Some((
+                    name.as_str(),
+                    exports
+                        .iter()
+                        .map(|(field, value)| (field.clone(), value.static_type()))
+                        .collect(),
+                )),
+                _ => None,
+            })
+            .collect();
+        self.check(program, &types, &records)?.run(variables)
+    }
+
+    /// Tokenize, parse and check `source` once, for running it any number
+    /// of times. `inputs` are the names the source uses without defining,
+    /// with their types; each run is given their values.
+    pub fn compile(
+        &self,
+        source: &str,
+        inputs: &[(&str, Type)],
+    ) -> Result<CompiledProgram, EngineError> {
+        self.check(self.parse(source)?, inputs, &[])
+    }
+
+    fn parse(&self, source: &str) -> Result<Program, EngineError> {
+        let tokens = Tokenizer::new(source)
+            .with_edition(self.edition)
+            .tokenize()
+            .map_err(EngineError::Tokenize)?;
+        Parser::new(tokens).parse().map_err(EngineError::Parse)
+    }
+
+    fn check(
+        &self,
+        program: Program,
+        inputs: &[(&str, Type)],
+        records: &[(&str, HashMap<String, Type>)],
+    ) -> Result<CompiledProgram, EngineError> {
+        // Inputs are bound in a scope of their own, so the source can define
+        // the same names again
+        let mut type_checker = self.type_checker();
+        for (name, ty) in inputs {
+            type_checker
+                .get_environment_mut()
+                .bind(name.to_string(), ty.clone());
+        }
+        for (name, fields) in records {
+            type_checker.bind_module(name, fields.clone());
+        }
+        type_checker.get_environment_mut().enter_scope();
+        type_checker
+            .check_program(&program)
+            .map_err(EngineError::Type)?;
+
+        Ok(CompiledProgram {
+            engine: self.clone(),
+            program,
+            inputs: inputs
+                .iter()
+                .map(|(name, ty)| (name.to_string(), ty.clone()))
+                .collect(),
+        })
+    }
+
+    /// A type checker set up for one evaluation
+    fn type_checker(&self) -> TypeChecker {
+        let mut type_checker = if self.prelude {
+            TypeChecker::new()
+        } else {
+            TypeChecker::without_prelude()
+        };
+        type_checker.set_edition(self.edition);
+        type_checker.set_module_search_path(self.search_path.clone());
+        if let Some(directory) = &self.current_directory {
+            type_checker.set_current_directory(directory);
+        }
+        type_checker
+    }
+
+    /// An interpreter for one evaluation, starting from the globals
+    fn interpreter(&self) -> Interpreter {
+        let mut builder = Interpreter::builder()
+            .prelude(self.prelude)
+            .environment(Environment::clone(&self.globals))
+            .module_search_path(self.search_path.clone())
+            .edition(self.edition)
+            .max_call_depth(self.max_call_depth);
+        if let Some(directory) = &self.current_directory {
+            builder = builder.current_directory(directory);
+        }
+        builder.build()
+    }
+}
+
+/// Source checked by [`Engine::compile`], ready to run. Running it does not
+/// tokenize, parse or check it again.
+#[derive(Debug, Clone)]
+pub struct CompiledProgram {
+    engine: Engine,
+    program: Program,
+    /// The names each run is given values for, and their types
+    inputs: Vec<(String, Type)>,
+}
+
+impl CompiledProgram {
+    /// The names each run must be given values for, with their types
+    pub fn inputs(&self) -> &[(String, Type)] {
+        &self.inputs
+    }
+
+    /// Run the program with `inputs` bound, returning the value of its last
+    /// statement
+    pub fn run(&self, inputs: HashMap<String, Value>) -> Result<Value, EngineError> {
+        self.run_with(inputs, &ExecOptions::repl())
+    }
+
+    /// Run the program with `inputs` bound, as set out by `options`. Each
+    /// value is trusted to have the type it was compiled with. It runs on a
+    /// thread of its own with the interpreter's stack, so deep recursion
+    /// stops with an error rather than overflowing the caller's stack.
+    pub fn run_with(
+        &self,
+        inputs: HashMap<String, Value>,
+        options: &ExecOptions,
+    ) -> Result<Value, EngineError> {
+        with_stack(|| self.run_here(inputs, options))
+    }
+
+    fn run_here(
+        &self,
+        mut inputs: HashMap<String, Value>,
+        options: &ExecOptions,
+    ) -> Result<Value, EngineError> {
+        let mut interpreter = self.engine.interpreter();
+        let environment = interpreter.environment_mut();
+        for (name, _) in &self.inputs {
+            let value = inputs.remove(name).ok_or_else(|| {
+                EngineError::Runtime(InterpreterError::RuntimeError {
+                    message: format!("No value given for the input '{}'", name),
+                    span: None,
+                })
+            })?;
+            environment.bind(name.clone(), value);
+        }
+        environment.push_scope();
+        interpreter
+            .execute(&self.program, options)
+            .map_err(EngineError::Runtime)
+    }
+}
+
+impl Default for Engine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Check and evaluate the single expression `source` with `variables` bound,
+/// using an engine with the prelude shared by every call
+pub fn eval_expression_with(
+    source: &str,
+    variables: HashMap<String, Value>,
+) -> Result<Value, EngineError> {
+    static ENGINE: OnceLock<Engine> = OnceLock::new();
+    ENGINE
+        .get_or_init(Engine::new)
+        .evaluate_expression_with(source, variables)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_engine_evaluates_on_many_threads() {
+        fn shareable<T: Send + Sync>() {}
+        shareable::<Engine>();
+
+        let engine = Arc::new(Engine::new());
+        let threads: Vec<_> = (0..4)
+            .map(|n| {
+                let engine = engine.clone();
+                std::thread::spawn(move || {
+                    engine.evaluate(&format!(
+                        "let x = {}; x * 10 + count(map(fn(y) {{ y + x }})([1, 2]));",
+                        n
+                    ))
+                })
+            })
+            .collect();
+        for (n, thread) in threads.into_iter().enumerate() {
+            assert_eq!(
+                thread.join().unwrap().unwrap(),
+                Value::Int(n as i64 * 10 + 2)
+            );
+        }
+
+        // Nothing one evaluation defines is seen by the next
+        engine.evaluate("let x = 1;").unwrap();
+        let error = engine.evaluate("x;").unwrap_err();
+        assert_eq!(error.code(), "E0001");
+        assert!(matches!(
+            Engine::without_prelude().evaluate("map;"),
+            Err(EngineError::Type(_))
+        ));
+    }
+
+    #[test]
+    fn test_compiled_programs_run_with_different_inputs() {
+        let engine = Engine::new();
+        let greeting = engine
+            .compile(
+                "let punctuation = \"!\";\nname ++ punctuation;",
+                &[("name", Type::String)],
+            )
+            .unwrap();
+        let greet =
+            |name: &str| greeting.run(HashMap::from([("name".to_string(), Value::from(name))]));
+        assert_eq!(greet("Ada").unwrap(), Value::from("Ada!"));
+        assert_eq!(greet("Grace").unwrap(), Value::from("Grace!"));
+
+        let error = greeting.run(HashMap::new()).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Runtime error: No value given for the input 'name'"
+        );
+
+        // Inputs are checked like any other binding
+        let error = engine
+            .compile("name + 1;", &[("name", Type::String)])
+            .unwrap_err();
+        assert!(matches!(error, EngineError::Type(_)));
+    }
+
+    #[test]
+    fn test_expressions_are_evaluated_with_the_given_variables() {
+        let cells = HashMap::from([
+            ("price".to_string(), Value::Int(12)),
+            ("quantity".to_string(), Value::Int(3)),
+            ("names".to_string(), Value::from(vec!["a", "b"])),
+        ]);
+        assert_eq!(
+            eval_expression_with("price * quantity + count(names)", cells.clone()).unwrap(),
+            Value::Int(38)
+        );
+        assert_eq!(
+            eval_expression_with("price > 10;", cells.clone()).unwrap(),
+            Value::Bool(true)
+        );
+        assert_eq!(
+            eval_expression_with("price // before tax", cells.clone()).unwrap(),
+            Value::Int(12)
+        );
+
+        // Variables have the types of their values
+        let error = eval_expression_with("price ++ \"!\"", cells.clone()).unwrap_err();
+        assert!(matches!(error, EngineError::Type(_)));
+        let error = eval_expression_with("let x = 1; x", cells).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid expression at line 1, column 1: Expected a single expression"
+        );
+    }
+
+    #[test]
+    fn test_deep_recursion_is_an_error_not_a_crash() {
+        let engine = Engine::new();
+        let source = "fn down(n: Int) -> Int { if n == 0 { 0 } else { down(n - 1) } }\n";
+        let error = engine
+            .evaluate(&format!("{}down(100000);", source))
+            .unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("Calls nested deeper than the limit of"),
+            "{}",
+            error
+        );
+        assert_eq!(
+            engine.evaluate(&format!("{}down(50);", source)).unwrap(),
+            Value::Int(0)
+        );
+    }
+
+    #[test]
+    fn test_records_are_read_by_field() {
+        struct Point {
+            x: i64,
+            y: i64,
+        }
+        crate::value_record!(Point { x, y });
+
+        let point = || HashMap::from([("p".to_string(), Value::from(Point { x: 3, y: 4 }))]);
+        assert_eq!(
+            eval_expression_with("p.x * p.y", point()).unwrap(),
+            Value::Int(12)
+        );
+        // Fields are checked with the types of their values
+        let error = eval_expression_with("p.x ++ \"!\"", point()).unwrap_err();
+        assert!(matches!(error, EngineError::Type(_)), "{}", error);
+        let error = eval_expression_with("p.z", point()).unwrap_err();
+        assert_eq!(error.code(), "E0001", "{}", error);
+    }
+}
@@ -0,0 +1,191 @@
+//! Longer descriptions of error codes, shown by `--explain` and `:explain`
+
+/// Code, title and explanation (with an example) for every error and warning
+const EXPLANATIONS: &[(&str, &str, &str)] = &[
+    (
+        "E0001",
+        "Undefined variable",
+        "A name was used that is not bound in the current scope.\n\n    let total = 1;\n    totl + 1;   // E0001: did you mean 'total'?\n\nDeclare the name with `let` or `fn` before using it, or check its spelling.",
+    ),
+    (
+        "E0002",
+        "Type mismatch",
+        "A value has a different type from the one required where it is used.\n\n    let x: Int = \"five\";   // E0002: expected Int, found String\n\nWhen both types have the same shape, such as two lists, they are repeated\nbelow the message with the parts that differ marked:\n\n      expected: List (Int, >>Bool<<)\n         found: List (Int, >>String<<)\n\nConvert the value (for example with `toString`) or fix the annotation.",
+    ),
+    (
+        "E0003",
+        "Invalid binary operation",
+        "An operator was applied to operands it does not support.\n\n    \"a\" + 1;   // E0003: convert the Int with toString(...)\n\nArithmetic needs numbers, `++` needs two strings, lists or byte strings, and `&&`/`||` need booleans.\nValues are never converted implicitly, except that an `Int` meeting a `BigInt` is\nwidened. Use `toString(value)` to turn a value into text and `toInt(text)`, which\ngives `inl(number)` or `inr(reason)`, to read a number from a string.",
+    ),
+    (
+        "E0004",
+        "Redefined variable",
+        "A name was declared twice in the same scope. Bindings are immutable.\n\n    let x = 1;\n    let x = 2;   // E0004\n\nChoose a new name, or declare the second binding inside a block.",
+    ),
+    (
+        "E0005",
+        "Import error",
+        "A module could not be found, read, parsed or checked.\n\n    import \"missing.corr\" as m;   // E0005\n\nPaths are relative to the importing file. Standard modules start with `std:`.",
+    ),
+    (
+        "E0006",
+        "Tuple index out of range",
+        "A tuple was projected with an index it does not have.\n\n    let t = (1, 2, 3);\n    t.3;   // E0006: indices are 0, 1 and 2",
+    ),
+    (
+        "E0007",
+        "Unsatisfied constraint",
+        "A comparison was used on a type that does not support it.\n\n    let f = fn(x: Int) { x };\n    f == f;   // E0007: functions cannot be compared\n\n`==` and `!=` need comparable values; `<`, `<=`, `>` and `>=` need ordered ones.",
+    ),
+    (
+        "E0008",
+        "Imprecise type",
+        "The type of a declaration or of a function's parameter could not be fully\ninferred.\n\n    let empty = [];         // E0008: List of what?\n    let id = fn(x) { x };   // E0008: x could be anything\n\nAdd an annotation such as `let empty: List Int = [];`, or write `Dyn` to opt\nout of checking. Programs in the 2025 edition, or run with `--no-strict`, are\nnot checked for this.",
+    ),
+    (
+        "E0009",
+        "IO at the top level of an imported module",
+        "With `--pure-imports`, imported modules may not call IO functions directly at their top level.\n\n    // logger.corr\n    print(\"loading\");   // E0009\n\nMove the IO into a function so it only runs when called. Only IO written at the top level is caught: a top-level call to a function that does IO is not.",
+    ),
+    (
+        "E0010",
+        "Not a constant expression",
+        "The value of a `const` must be computable while the program is checked.\nIt may use literals, earlier constants, arithmetic, comparisons, `&&`, `||`,\n`!`, string concatenation and `if`, but not calls or `let` bindings.\n\n    const SIZE = 1024;\n    const HALF = SIZE / 2;     // fine\n    let n = 3;\n    const BAD = n * 2;         // E0010: 'n' is not a constant\n\nUse `let` for values that are only known when the program runs.",
+    ),
+    (
+        "E0011",
+        "Non-exhaustive case",
+        "A `case` on an enum has no arm for some of its variants, the arms of a\n`case` on integers leave some `Int` values out, or a `case` on strings has\nno `_` arm, so there would be no value for the values left over.\n\n    enum Color { Red, Green, Blue }\n    let n = case c of Color.Red => 1 | Color.Blue => 2;   // E0011: Color.Green\n    let m = case s of \"yes\" => 1 | \"no\" => 0;          // E0011: every String\n    let k = case i of 0 => 1 | 1.. => 2;                 // E0011: ..0\n\nAdd an arm for each missing variant, or a `_` arm that matches the rest.",
+    ),
+    (
+        "E0012",
+        "Wrong number of arguments",
+        "A function was given more or fewer arguments than it takes. Functions of\nseveral arguments are curried: each call passes one argument and gives a\nfunction taking the next.\n\n    fn add(a: Int) { fn(b: Int) { a + b } }\n    let x = add(1) + 2;       // E0012: expected 2 arguments, found 1\n    let y = add(1)(2)(3);     // E0012: expected 2 arguments, found 3\n\nPass each argument in its own parentheses, as in `add(1)(2)`.",
+    ),
+    (
+        "E0013",
+        "Not callable",
+        "A value whose type is not a function was called.\n\n    let n = 5;\n    n(1);   // E0013: 'n' is of type 'Int', declared at line 1\n\nThe message names the binding called, when it was called by name, and\nwhere it was declared. Values of type `Dyn` are only found not to be\nfunctions when the program runs; see E0204.",
+    ),
+    (
+        "E0014",
+        "Deprecated use denied",
+        "A deprecated builtin or piece of syntax was used while running with\n`--deny-deprecated`, which makes the W0004 warning an error.\n\n    let s = concat(\"a\", \"b\");   // E0014: use a + b instead\n\nWrite the replacement the message suggests, or run without\n`--deny-deprecated` to only be warned.",
+    ),
+    (
+        "W0001",
+        "Shadowed declaration",
+        "A `let` or `fn` inside a block, function body or branch reuses a name\ndeclared in an enclosing scope. This is allowed: the inner binding hides the\nouter one until the scope ends. It is reported because it is easy to mean the\nouter binding.\n\n    let total = 0;\n    fn add(x: Int) {\n        let total = x + 1;   // W0001\n        total\n    }\n\nRename one of the bindings if they are meant to be different values.",
+    ),
+    (
+        "W0002",
+        "Unreachable case arm",
+        "A `case` arm comes after arms that already match every value it does, so it\nis never taken.\n\n    let n = case c of\n        Color.Red => 1\n      | _ => 2\n      | Color.Blue => 3;   // W0002\n\nRemove the arm, or move it before the arms that hide it.",
+    ),
+    (
+        "W0003",
+        "Overlapping case arm",
+        "An integer or range arm of a `case` matches some of the values an earlier\narm matches. Arms are tried in order, so those values take the earlier arm\nand only the rest reach this one.\n\n    let size = case n of\n        0..10 => \"small\"\n      | 5..100 => \"medium\"   // W0003: 5 to 9 are \"small\"\n      | _ => \"large\";\n\nAdjust the ranges so they do not share values.",
+    ),
+    (
+        "W0004",
+        "Deprecated builtin",
+        "A builtin or piece of syntax is used that still works but has a\nreplacement, and may be removed in a later edition.\n\n    let s = concat(\"a\", \"b\");   // W0004: use a + b instead\n\nWrite the replacement the warning suggests. Running with\n`--deny-deprecated` makes these warnings errors (E0014).",
+    ),
+    (
+        "W0005",
+        "Typed hole",
+        "A hole, `?` or `??name`, stands where an expression is still to be\nwritten. The program is checked as if the hole had the type it needs there,\nand that type is reported with the names in scope that have it.\n\n    let count: Int = 3;\n    let total: Int = count * ??factor;   // W0005: needs Int; count\n\nReplace the hole with an expression. Running into one is a runtime error.",
+    ),
+    (
+        "W0006",
+        "Unused result",
+        "An expression statement has a value other than `Unit`, which is thrown\naway. This is often a comparison written where a binding was meant.\n\n    let x = 1;\n    x == 5;   // W0006: the Bool value is not used\n\nBind the value, or write `let _ = x == 5;` to discard it on purpose. The\nlast statement of a program is its result and is not reported.",
+    ),
+    (
+        "W0007",
+        "Constant condition",
+        "The condition of an `if` is `true` or `false` every time, because it is\nmade only of literals, so one branch is never taken.\n\n    if 1 > 2 { print(\"never\"); };   // W0007: always false\n\nThis is often a leftover from debugging or a typo for a variable. A\ncondition naming a `const`, such as `if DEBUG { ... }`, is not reported.",
+    ),
+    (
+        "W0008",
+        "Recursion without a base case",
+        "A function calls itself on every path through its body, so each call\nmakes another and none of them ever returns.\n\n    fn countdown(n: Int) -> Int {\n        countdown(n - 1)   // W0008: nothing stops at 0\n    }\n\nAdd a branch that gives a result without the recursive call, such as\n`if n == 0 { 0 } else { countdown(n - 1) }`. The check is a heuristic: it\ndoes not look into closures or loop bodies.",
+    ),
+    (
+        "W0100",
+        "Keyword read leniently",
+        "Under `--lenient`, a keyword written in the wrong case or with a common\ntypo is read as the keyword it was meant to be, instead of being an error.\n\n    Let done = True;   // W0100 twice: 'let' and 'true'\n    if done { 1 } esle { 2 }   // W0100: 'else'\n\nFix the spelling; without `--lenient` these words are ordinary names.",
+    ),
+    (
+        "E0100",
+        "Invalid token",
+        "The source contains text that is not part of the language, such as an\nunterminated string or an integer literal that does not fit in 64 bits.\nA string or block comment that is never closed is reported where it opens.\n\n    let x = \"unterminated;   // E0100",
+    ),
+    (
+        "E0101",
+        "Unexpected token",
+        "The parser found a token where it expected something else.\n\n    let y = 1 +;   // E0101: expected an expression after '+'",
+    ),
+    (
+        "E0102",
+        "Unexpected end of input",
+        "The input ended before a statement or expression was complete. Most\nincomplete input is reported as E0101 with `found Eof`, naming what was\nexpected instead.",
+    ),
+    (
+        "E0103",
+        "Invalid expression",
+        "The tokens form something that is not a valid expression, such as a\nmalformed `case`, an empty type annotation, a `return` outside a\nfunction body or a reserved word such as `head` used as a name. Expressions and types may also nest at most 64 levels deep.",
+    ),
+    (
+        "E0104",
+        "Unclosed delimiter",
+        "A `(`, `[` or `{` is never closed. The error points at the delimiter that\nis still open, and says where its closing one was expected: at the end of\nthe input, or where a different closing delimiter came first.\n\n    fn f(x: Int) {      // E0104: unclosed '{'\n        x + 1\n\n    let xs = [1, (2, 3];   // E0104: ']' before the '(' is closed\n\nAdd the missing closing delimiter where the construct it opened ends.",
+    ),
+    (
+        "E0200",
+        "Runtime error",
+        "A builtin or operation failed while the program was running, for example\n`head` of an empty list or an integer overflow.\n\n    head([]);   // E0200\n\nUse sum-returning variants such as `safeDiv`, or check inputs first.",
+    ),
+    (
+        "E0201",
+        "Division by zero",
+        "An integer was divided by zero with `/` or `divmod`.\n\n    10 / 0;   // E0201\n\n`safeDiv(a, b)` returns `inr(message)` instead of failing.",
+    ),
+    (
+        "E0202",
+        "Undefined variable at runtime",
+        "A name was not bound when the program ran. The type checker normally\ncatches this as E0001; at runtime it usually means a module does not export\nthe name.",
+    ),
+    (
+        "E0203",
+        "Runtime type error",
+        "A value had the wrong type while running. This can happen with `Dyn`\nvalues, which are only checked at runtime.\n\n    let x: Dyn = \"text\";\n    x + 1;   // E0203",
+    ),
+    (
+        "E0204",
+        "Not callable",
+        "A value that is not a function was called. The type checker catches this\nfor values of known type (E0013), so at runtime it involves `Dyn`.\n\n    let x: Dyn = 5;\n    x(1);   // E0204: 'x' is 5 of type Int, declared at line 1\n\nThe message shows the value that was called and, when it was called by a\nname bound with `let` or `const`, where that name was declared.",
+    ),
+    (
+        "E0205",
+        "Index out of bounds",
+        "A tuple was projected past its last element while the program ran. When\nthe tuple's type is known, the type checker reports this as E0006 instead.",
+    ),
+];
+
+/// The title and explanation for an error code, ignoring case
+pub fn explanation(code: &str) -> Option<(&'static str, &'static str)> {
+    EXPLANATIONS
+        .iter()
+        .find(|(known, _, _)| known.eq_ignore_ascii_case(code))
+        .map(|(_, title, text)| (*title, *text))
+}
+
+/// Text printed by `--explain` and `:explain`
+pub fn render(code: &str) -> String {
+    match explanation(code) {
+        Some((title, text)) => format!("{}: {}\n\n{}", code.to_ascii_uppercase(), title, text),
+        None => format!("Unknown error code '{}'", code),
+    }
+}
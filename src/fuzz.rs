@@ -0,0 +1,28 @@
+//! Entry points for fuzzing the front end, for use from `cargo fuzz` targets.
+//! Any input is acceptable: invalid UTF-8 is ignored and errors are expected,
+//! so a panic in either function is a bug.
+
+use crate::ast::Parser;
+use crate::lexer::Tokenizer;
+
+/// Tokenize arbitrary bytes, checking that lossless tokenization gives back
+/// the input exactly
+pub fn fuzz_tokenize(data: &[u8]) {
+    let Ok(source) = std::str::from_utf8(data) else {
+        return;
+    };
+    if let Ok(tokens) = Tokenizer::new(source).tokenize_lossless() {
+        let text: String = tokens.iter().map(|token| token.source_text()).collect();
+        assert_eq!(text, source, "lossless tokens do not reproduce the input");
+    }
+}
+
+/// Tokenize and parse arbitrary bytes, discarding the result
+pub fn fuzz_parse(data: &[u8]) {
+    let Ok(source) = std::str::from_utf8(data) else {
+        return;
+    };
+    if let Ok(tokens) = Tokenizer::new(source).tokenize() {
+        let _ = Parser::new(tokens).parse();
+    }
+}
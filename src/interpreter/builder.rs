@@ -0,0 +1,138 @@
+//! Settings for creating an [`Interpreter`] with [`Interpreter::builder`]
+
+use super::{Coverage, Environment, Input, Interpreter, Output};
+use crate::lexer::edition::Edition;
+use std::io::{BufRead, Write};
+use std::path::PathBuf;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+
+/// How to set up an interpreter. The default is what `Interpreter::new`
+/// gives: the prelude defined, imports found next to the working directory,
+/// standard input and output, and no limits.
+pub struct InterpreterBuilder {
+    pub(super) prelude: bool,
+    pub(super) environment: Option<Environment>,
+    pub(super) current_directory: Option<PathBuf>,
+    pub(super) search_path: Vec<PathBuf>,
+    pub(super) edition: Edition,
+    pub(super) rng_seed: Option<u64>,
+    pub(super) output: Option<Output>,
+    pub(super) input: Option<Input>,
+    pub(super) interrupt: Option<Arc<AtomicBool>>,
+    pub(super) max_call_depth: Option<usize>,
+    pub(super) coverage: Option<Coverage>,
+}
+
+impl Default for InterpreterBuilder {
+    fn default() -> Self {
+        Self {
+            prelude: true,
+            environment: None,
+            current_directory: None,
+            search_path: Vec::new(),
+            edition: Edition::default(),
+            rng_seed: None,
+            output: None,
+            input: None,
+            interrupt: None,
+            max_call_depth: None,
+            coverage: None,
+        }
+    }
+}
+
+impl InterpreterBuilder {
+    /// Define the prelude, or leave it out as `--no-prelude` does
+    pub fn prelude(mut self, prelude: bool) -> Self {
+        self.prelude = prelude;
+        self
+    }
+
+    /// Start from these bindings instead of defining the prelude again, such
+    /// as the environment of another interpreter created with the same
+    /// `prelude` setting
+    pub fn environment(mut self, environment: Environment) -> Self {
+        self.environment = Some(environment);
+        self
+    }
+
+    /// Resolve imports relative to `directory`, usually the program's own
+    pub fn current_directory(mut self, directory: impl Into<PathBuf>) -> Self {
+        self.current_directory = Some(directory.into());
+        self
+    }
+
+    /// Look for imports in these directories when they are not found in the
+    /// current directory, such as where packages are fetched to
+    pub fn module_search_path(mut self, search_path: Vec<PathBuf>) -> Self {
+        self.search_path = search_path;
+        self
+    }
+
+    /// Read imported modules in `edition`, the one the program is in
+    pub fn edition(mut self, edition: Edition) -> Self {
+        self.edition = edition;
+        self
+    }
+
+    /// Seed the generator behind `random`, making runs reproducible
+    pub fn rng_seed(mut self, seed: u64) -> Self {
+        self.rng_seed = Some(seed);
+        self
+    }
+
+    /// Send what `print` writes to `writer` instead of standard output
+    pub fn output(mut self, writer: impl Write + Send + 'static) -> Self {
+        self.output = Some(Arc::new(Mutex::new(writer)));
+        self
+    }
+
+    /// Read `input` and `readAll` from `reader` instead of standard input
+    pub fn input(mut self, reader: impl BufRead + Send + 'static) -> Self {
+        self.input = Some(Arc::new(Mutex::new(reader)));
+        self
+    }
+
+    /// Stop the program with a runtime error once `interrupt` is set, such
+    /// as by a timer enforcing a time limit
+    pub fn interrupt(mut self, interrupt: Arc<AtomicBool>) -> Self {
+        self.interrupt = Some(interrupt);
+        self
+    }
+
+    /// Stop with a runtime error when function calls nest deeper than
+    /// `depth`, rather than running out of stack
+    pub fn max_call_depth(mut self, depth: usize) -> Self {
+        self.max_call_depth = Some(depth);
+        self
+    }
+
+    /// Count which statements run, as `--coverage` does
+    pub fn coverage(mut self, coverage: Coverage) -> Self {
+        self.coverage = Some(coverage);
+        self
+    }
+
+    pub fn build(self) -> Interpreter {
+        Interpreter::from_builder(self)
+    }
+}
+
+impl std::fmt::Debug for InterpreterBuilder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("InterpreterBuilder")
+            .field("prelude", &self.prelude)
+            .field("environment", &self.environment.as_ref().map(|_| "given"))
+            .field("current_directory", &self.current_directory)
+            .field("search_path", &self.search_path)
+            .field("edition", &self.edition)
+            .field("rng_seed", &self.rng_seed)
+            .field("output", &self.output.as_ref().map(|_| "writer"))
+            .field("input", &self.input.as_ref().map(|_| "reader"))
+            .field("interrupt", &self.interrupt)
+            .field("max_call_depth", &self.max_call_depth)
+            .field("coverage", &self.coverage.as_ref().map(|_| "counting"))
+            .finish()
+    }
+}
@@ -0,0 +1,302 @@
+//! Conversions between `Value` and Rust types, so embedding code can pass
+//! arguments in and read results out without matching on `Value` by hand.
+//!
+//! Lists convert to and from `Vec`, pairs to and from two-element tuples and
+//! `()` to and from `Unit`. Structs become modules of their fields through
+//! [`value_record!`](crate::value_record), so an expression given one by
+//! [`Engine::evaluate_expression_with`](crate::engine::Engine::evaluate_expression_with)
+//! reads them as `point.x`.
+
+use super::Value;
+use num_bigint::BigInt;
+use std::collections::HashMap;
+
+/// A value that does not have the shape a Rust type needs
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConversionError {
+    /// The kind of value the Rust type converts from
+    pub expected: String,
+    /// The kind of value found instead
+    pub found: String,
+}
+
+impl ConversionError {
+    pub fn new(expected: impl Into<String>, found: &Value) -> Self {
+        Self {
+            expected: expected.into(),
+            found: found.type_name().to_string(),
+        }
+    }
+}
+
+impl std::fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Cannot convert value: expected {}, found {}",
+            self.expected, self.found
+        )
+    }
+}
+
+impl std::error::Error for ConversionError {}
+
+impl From<i64> for Value {
+    fn from(n: i64) -> Self {
+        Value::Int(n)
+    }
+}
+
+impl From<BigInt> for Value {
+    fn from(n: BigInt) -> Self {
+        Value::BigInt(n)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Self {
+        Value::Bool(b)
+    }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Self {
+        Value::String(s)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Self {
+        Value::String(s.to_string())
+    }
+}
+
+impl From<()> for Value {
+    fn from(_: ()) -> Self {
+        Value::Unit
+    }
+}
+
+impl<T: Into<Value>> From<Vec<T>> for Value {
+    fn from(elements: Vec<T>) -> Self {
+        Value::List(elements.into_iter().map(Into::into).collect())
+    }
+}
+
+impl<A: Into<Value>, B: Into<Value>> From<(A, B)> for Value {
+    fn from((first, second): (A, B)) -> Self {
+        Value::Pair(Box::new(first.into()), Box::new(second.into()))
+    }
+}
+
+impl TryFrom<Value> for i64 {
+    type Error = ConversionError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Int(n) => Ok(n),
+            other => Err(ConversionError::new("Int", &other)),
+        }
+    }
+}
+
+/// Widens an `Int`, as arithmetic does
+impl TryFrom<Value> for BigInt {
+    type Error = ConversionError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        value
+            .to_big_int()
+            .ok_or_else(|| ConversionError::new("BigInt", &value))
+    }
+}
+
+impl TryFrom<Value> for bool {
+    type Error = ConversionError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Bool(b) => Ok(b),
+            other => Err(ConversionError::new("Bool", &other)),
+        }
+    }
+}
+
+impl TryFrom<Value> for String {
+    type Error = ConversionError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::String(s) => Ok(s),
+            other => Err(ConversionError::new("String", &other)),
+        }
+    }
+}
+
+impl TryFrom<Value> for () {
+    type Error = ConversionError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Unit => Ok(()),
+            other => Err(ConversionError::new("Unit", &other)),
+        }
+    }
+}
+
+impl<T: TryFrom<Value, Error = ConversionError>> TryFrom<Value> for Vec<T> {
+    type Error = ConversionError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::List(elements) => elements.into_iter().map(T::try_from).collect(),
+            other => Err(ConversionError::new("List", &other)),
+        }
+    }
+}
+
+impl<A, B> TryFrom<Value> for (A, B)
+where
+    A: TryFrom<Value, Error = ConversionError>,
+    B: TryFrom<Value, Error = ConversionError>,
+{
+    type Error = ConversionError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Pair(first, second) => Ok((A::try_from(*first)?, B::try_from(*second)?)),
+            other => Err(ConversionError::new("Pair", &other)),
+        }
+    }
+}
+
+/// Build the module `value_record!` turns a struct into
+pub fn record(name: &str, fields: Vec<(&str, Value)>) -> Value {
+    Value::Module {
+        name: name.to_string(),
+        exports: fields
+            .into_iter()
+            .map(|(field, value)| (field.to_string(), value))
+            .collect(),
+    }
+}
+
+/// The fields of a module made by `record`, checking it was made for `name`
+pub fn record_fields(name: &str, value: Value) -> Result<HashMap<String, Value>, ConversionError> {
+    match value {
+        Value::Module {
+            name: found,
+            exports,
+        } if found == name => Ok(exports),
+        other => Err(ConversionError::new(name, &other)),
+    }
+}
+
+/// Take one field out of a record's fields and convert it
+pub fn take_field<T: TryFrom<Value, Error = ConversionError>>(
+    fields: &mut HashMap<String, Value>,
+    field: &str,
+) -> Result<T, ConversionError> {
+    let value = fields.remove(field).ok_or_else(|| ConversionError {
+        expected: format!("field '{}'", field),
+        found: "nothing".to_string(),
+    })?;
+    T::try_from(value)
+}
+
+/// Implement `From<Struct> for Value` and `TryFrom<Value> for Struct` for a
+/// struct whose fields all convert, representing it as a module of its fields:
+///
+/// ```
+/// use corrosion_language::value_record;
+///
+/// struct Point {
+///     x: i64,
+///     y: i64,
+/// }
+/// value_record!(Point { x, y });
+/// ```
+#[macro_export]
+macro_rules! value_record {
+    ($name:ident { $($field:ident),* $(,)? }) => {
+        impl From<$name> for $crate::interpreter::Value {
+            fn from(record: $name) -> Self {
+                $crate::interpreter::convert::record(
+                    stringify!($name),
+                    vec![$((stringify!($field), record.$field.into())),*],
+                )
+            }
+        }
+
+        impl TryFrom<$crate::interpreter::Value> for $name {
+            type Error = $crate::interpreter::convert::ConversionError;
+
+            fn try_from(value: $crate::interpreter::Value) -> Result<Self, Self::Error> {
+                #[allow(unused_mut)]
+                let mut fields =
+                    $crate::interpreter::convert::record_fields(stringify!($name), value)?;
+                Ok($name {
+                    $($field: $crate::interpreter::convert::take_field(
+                        &mut fields,
+                        stringify!($field),
+                    )?),*
+                })
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Point {
+        x: i64,
+        label: String,
+    }
+    crate::value_record!(Point { x, label });
+
+    #[test]
+    fn test_values_round_trip_through_rust_types() {
+        let value = Value::from(vec![(1i64, true), (2, false)]);
+        assert_eq!(format!("{}", value), "[(1, true), (2, false)]");
+        let pairs: Vec<(i64, bool)> = value.try_into().unwrap();
+        assert_eq!(pairs, vec![(1, true), (2, false)]);
+
+        assert_eq!(BigInt::try_from(Value::Int(7)), Ok(BigInt::from(7)));
+        assert_eq!(
+            Vec::<String>::try_from(Value::from(vec![Value::from("a"), Value::Int(1)])),
+            Err(ConversionError {
+                expected: "String".to_string(),
+                found: "Int".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_structs_convert_to_records() {
+        let point = Point {
+            x: 3,
+            label: "origin".to_string(),
+        };
+        let value = Value::from(point);
+        let Value::Module { exports, .. } = &value else {
+            panic!("expected a module, got {}", value);
+        };
+        assert_eq!(exports.get("x"), Some(&Value::Int(3)));
+        assert_eq!(
+            Point::try_from(value),
+            Ok(Point {
+                x: 3,
+                label: "origin".to_string(),
+            })
+        );
+
+        let wrong = record("Point", vec![("x", Value::Int(1))]);
+        assert_eq!(
+            Point::try_from(wrong).unwrap_err().to_string(),
+            "Cannot convert value: expected field 'label', found nothing"
+        );
+    }
+}
@@ -0,0 +1,88 @@
+//! Which parts of a program ran, reported as lcov for `--coverage`
+
+use crate::ast::nodes::{Child, Expression, Program, Spanned, Statement};
+use crate::lexer::tokens::Span;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// How many times each statement and block result ran, by span. Shared by
+/// every interpreter running the program, spawned threads included.
+#[derive(Clone, Default)]
+pub struct Coverage {
+    hits: Arc<Mutex<HashMap<Span, usize>>>,
+}
+
+impl Coverage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Count one run of the code at `span`
+    pub(crate) fn hit(&self, span: &Span) {
+        let mut hits = self.hits.lock().unwrap_or_else(PoisonError::into_inner);
+        *hits.entry(span.clone()).or_insert(0) += 1;
+    }
+
+    /// Run counts for each line of `program` that starts a statement or the
+    /// result of a block. A line is counted as often as the code on it that
+    /// ran the most.
+    ///
+    /// Code from the prelude and imported modules runs too. Its spans are in
+    /// other files, and only the spans found in `program` are looked up.
+    pub fn lines(&self, program: &Program) -> BTreeMap<usize, usize> {
+        let mut spans = Vec::new();
+        for statement in &program.statements {
+            statement_spans(statement, &mut spans);
+        }
+        let hits = self.hits.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut lines = BTreeMap::new();
+        for span in spans {
+            let count = hits.get(span).copied().unwrap_or(0);
+            let line = lines.entry(span.line).or_insert(0);
+            *line = count.max(*line);
+        }
+        lines
+    }
+
+    /// An lcov tracefile for `program`, which was read from `path`
+    pub fn lcov(&self, program: &Program, path: &str) -> String {
+        let lines = self.lines(program);
+        let mut report = format!("TN:\nSF:{}\n", path);
+        for (line, count) in &lines {
+            report.push_str(&format!("DA:{},{}\n", line, count));
+        }
+        let hit = lines.values().filter(|count| **count > 0).count();
+        report.push_str(&format!("LF:{}\nLH:{}\nend_of_record\n", lines.len(), hit));
+        report
+    }
+}
+
+fn statement_spans<'a>(statement: &'a Statement, spans: &mut Vec<&'a Span>) {
+    spans.push(statement.span());
+    match statement {
+        Statement::VariableDeclaration { value, .. }
+        | Statement::ConstantDeclaration { value, .. } => expression_spans(value, spans),
+        Statement::FunctionDeclaration { body, .. } => expression_spans(body, spans),
+        Statement::Expression { expression, .. }
+        | Statement::Return {
+            value: expression, ..
+        } => expression_spans(expression, spans),
+        Statement::Import { .. } | Statement::EnumDeclaration { .. } => {}
+    }
+}
+
+fn expression_spans<'a>(expression: &'a Expression, spans: &mut Vec<&'a Span>) {
+    if let Expression::Block {
+        expression: Some(result),
+        ..
+    } = expression
+    {
+        spans.push(result.span());
+    }
+    for child in expression.children() {
+        match child {
+            Child::Statement(statement) => statement_spans(statement, spans),
+            Child::Expression(_, expression) => expression_spans(expression, spans),
+        }
+    }
+}
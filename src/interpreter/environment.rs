@@ -1,40 +1,115 @@
 use super::Value;
-use std::collections::HashMap;
+use crate::lexer::tokens::Span;
+use std::collections::{BTreeMap, HashMap};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Environment {
     scopes: Vec<HashMap<String, Value>>,
+    /// Where the bindings of each scope made by a declaration were declared
+    definitions: Vec<HashMap<String, Span>>,
+}
+
+/// A saved copy of an environment, taken by `Environment::snapshot`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot(Environment);
+
+impl Snapshot {
+    /// The environment as it was when the snapshot was taken
+    pub fn environment(&self) -> &Environment {
+        &self.0
+    }
+}
+
+/// How a visible binding differs between two environments
+#[derive(Debug, Clone, PartialEq)]
+pub enum BindingChange {
+    Added {
+        name: String,
+        value: Value,
+    },
+    Removed {
+        name: String,
+        value: Value,
+    },
+    Changed {
+        name: String,
+        old: Value,
+        new: Value,
+    },
+}
+
+impl BindingChange {
+    pub fn name(&self) -> &str {
+        match self {
+            BindingChange::Added { name, .. }
+            | BindingChange::Removed { name, .. }
+            | BindingChange::Changed { name, .. } => name,
+        }
+    }
 }
 
 impl Environment {
     pub fn new() -> Self {
         Self {
             scopes: vec![HashMap::new()], // Start with one global scope
+            definitions: vec![HashMap::new()],
         }
     }
 
     pub fn with_parent(parent: &Environment) -> Self {
         let mut env = Environment::new();
         env.scopes = parent.scopes.clone();
+        env.definitions = parent.definitions.clone();
         env
     }
 
     pub fn push_scope(&mut self) {
         self.scopes.push(HashMap::new());
+        self.definitions.push(HashMap::new());
     }
 
     pub fn pop_scope(&mut self) {
         if self.scopes.len() > 1 {
             self.scopes.pop();
+            self.definitions.pop();
         }
     }
 
     pub fn bind(&mut self, name: String, value: Value) {
+        if let Some(definitions) = self.definitions.last_mut() {
+            definitions.remove(&name);
+        }
         if let Some(current_scope) = self.scopes.last_mut() {
             current_scope.insert(name, value);
         }
     }
 
+    /// Bind `name` in the current scope by the declaration at `span`
+    pub fn define(&mut self, name: String, value: Value, span: &Span) {
+        self.bind(name.clone(), value);
+        if let Some(definitions) = self.definitions.last_mut() {
+            definitions.insert(name, span.clone());
+        }
+    }
+
+    /// Where the binding `name` refers to was declared, if it was bound by a
+    /// declaration rather than as a parameter or by the interpreter itself
+    pub fn definition(&self, name: &str) -> Option<&Span> {
+        let (_, definitions) = self
+            .scopes
+            .iter()
+            .zip(&self.definitions)
+            .rev()
+            .find(|(scope, _)| scope.contains_key(name))?;
+        definitions.get(name)
+    }
+
+    /// Remove a binding from the current scope, returning its value
+    pub fn unbind(&mut self, name: &str) -> Option<Value> {
+        self.definitions.last_mut()?.remove(name);
+        self.scopes.last_mut()?.remove(name)
+    }
+
     pub fn update(&mut self, name: String, value: Value) {
         if let Some(current_scope) = self.scopes.last_mut() {
             current_scope.insert(name, value);
@@ -49,6 +124,11 @@ impl Environment {
         all_bindings
     }
 
+    /// Bindings of the innermost scope
+    pub fn local_bindings(&self) -> HashMap<String, Value> {
+        self.scopes.last().cloned().unwrap_or_default()
+    }
+
     pub fn lookup(&self, name: &str) -> Option<&Value> {
         for scope in self.scopes.iter().rev() {
             if let Some(value) = scope.get(name) {
@@ -61,7 +141,7 @@ impl Environment {
     pub fn is_bound_locally(&self, name: &str) -> bool {
         self.scopes
             .last()
-            .map_or(false, |scope| scope.contains_key(name))
+            .is_some_and(|scope| scope.contains_key(name))
     }
 
     pub fn is_bound(&self, name: &str) -> bool {
@@ -72,6 +152,67 @@ impl Environment {
         self.scopes.len()
     }
 
+    /// Every binding with the depth of its scope, the global scope being 0,
+    /// outermost first and by name within a scope. Shadowed bindings are
+    /// included
+    pub fn bindings(&self) -> impl Iterator<Item = (usize, &str, &Value)> {
+        self.scopes.iter().enumerate().flat_map(|(depth, scope)| {
+            let mut names: Vec<_> = scope.iter().collect();
+            names.sort_by_key(|(name, _)| *name);
+            names
+                .into_iter()
+                .map(move |(name, value)| (depth, name.as_str(), value))
+        })
+    }
+
+    /// The binding each name refers to, by name
+    fn visible_bindings(&self) -> BTreeMap<&str, &Value> {
+        self.bindings()
+            .map(|(_, name, value)| (name, value))
+            .collect()
+    }
+
+    /// Save the bindings and scopes so `restore` can return to them
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot(self.clone())
+    }
+
+    pub fn restore(&mut self, snapshot: Snapshot) {
+        *self = snapshot.0;
+    }
+
+    /// How the bindings visible in `newer` differ from those visible here,
+    /// by name
+    pub fn diff(&self, newer: &Environment) -> Vec<BindingChange> {
+        let old = self.visible_bindings();
+        let new = newer.visible_bindings();
+        let mut changes = Vec::new();
+        for (name, value) in &old {
+            match new.get(name) {
+                None => changes.push(BindingChange::Removed {
+                    name: name.to_string(),
+                    value: (*value).clone(),
+                }),
+                Some(new) if new != value => changes.push(BindingChange::Changed {
+                    name: name.to_string(),
+                    old: (*value).clone(),
+                    new: (*new).clone(),
+                }),
+                Some(_) => {}
+            }
+        }
+        for (name, value) in &new {
+            if !old.contains_key(name) {
+                changes.push(BindingChange::Added {
+                    name: name.to_string(),
+                    value: (*value).clone(),
+                });
+            }
+        }
+        changes.sort_by(|a, b| a.name().cmp(b.name()));
+        changes
+    }
+
     pub fn with_new_scope<F, R>(&mut self, f: F) -> R
     where
         F: FnOnce(&mut Environment) -> R,
@@ -131,4 +272,71 @@ mod tests {
         assert_eq!(result, Value::Int(2));
         assert_eq!(env.lookup("x"), Some(&Value::Int(1))); // Original binding restored
     }
+
+    #[test]
+    fn test_definitions_follow_the_binding_in_scope() {
+        let span = |line| Span::new(0, 1, line, 1);
+        let mut env = Environment::new();
+        env.define("x".to_string(), Value::Int(1), &span(1));
+        assert_eq!(env.definition("x").map(|span| span.line), Some(1));
+
+        // A parameter hides the declaration, and has none of its own
+        env.push_scope();
+        env.bind("x".to_string(), Value::Int(2));
+        assert_eq!(env.definition("x"), None);
+
+        env.pop_scope();
+        assert_eq!(env.definition("x").map(|span| span.line), Some(1));
+        env.bind("x".to_string(), Value::Int(3));
+        assert_eq!(env.definition("x"), None);
+    }
+
+    #[test]
+    fn test_bindings_snapshots_and_diffs() {
+        let mut env = Environment::new();
+        env.bind("y".to_string(), Value::Int(1));
+        env.bind("x".to_string(), Value::Int(2));
+        let before = env.snapshot();
+
+        env.push_scope();
+        env.bind("x".to_string(), Value::Int(3));
+        env.bind("z".to_string(), Value::Bool(true));
+        let bindings: Vec<_> = env.bindings().collect();
+        assert_eq!(
+            bindings,
+            vec![
+                (0, "x", &Value::Int(2)),
+                (0, "y", &Value::Int(1)),
+                (1, "x", &Value::Int(3)),
+                (1, "z", &Value::Bool(true)),
+            ]
+        );
+
+        let mut old = Environment::new();
+        old.restore(before.clone());
+        old.unbind("y");
+        assert_eq!(
+            old.diff(&env),
+            vec![
+                BindingChange::Changed {
+                    name: "x".to_string(),
+                    old: Value::Int(2),
+                    new: Value::Int(3),
+                },
+                BindingChange::Added {
+                    name: "y".to_string(),
+                    value: Value::Int(1),
+                },
+                BindingChange::Added {
+                    name: "z".to_string(),
+                    value: Value::Bool(true),
+                },
+            ]
+        );
+
+        env.restore(before);
+        assert_eq!(env.scope_count(), 1);
+        assert_eq!(env.lookup("x"), Some(&Value::Int(2)));
+        assert!(env.diff(&old).iter().all(|change| change.name() == "y"));
+    }
 }
@@ -1,4 +1,5 @@
 #[cfg(test)]
+#[allow(clippy::module_inception)]
 mod fix_tests {
     use crate::ast::{Parser, nodes::*};
     use crate::interpreter::Interpreter;
@@ -9,8 +10,7 @@ mod fix_tests {
     fn test_fix_expression_parsing() {
         let input = "fix(fn(f) { fn(x) { x } });";
 
-        let mut tokenizer = Tokenizer::new("");
-        let tokens = tokenizer.tokenize(input).unwrap();
+        let tokens = Tokenizer::new(input).tokenize().unwrap();
 
         let mut parser = Parser::new(tokens);
         let program = parser.parse().unwrap();
@@ -31,8 +31,7 @@ mod fix_tests {
     fn test_fix_expression_interpretation() {
         let input = "let identity = fix(fn(f) { fn(x) { x } }); print(identity(42));";
 
-        let mut tokenizer = Tokenizer::new("");
-        let tokens = tokenizer.tokenize(input).unwrap();
+        let tokens = Tokenizer::new(input).tokenize().unwrap();
 
         let mut parser = Parser::new(tokens);
         let program = parser.parse().unwrap();
@@ -1,60 +1,387 @@
-use super::{Environment, InterpreterError, InterpreterResult, Value};
-use crate::ast::nodes::{BinaryOperator, Expression, Program, Spanned, Statement};
+use super::module_cache;
+use super::rng::Rng;
+use super::threads::{self, Handles};
+use super::{
+    Coverage, Environment, ExecOptions, Input, InterpreterBuilder, InterpreterError,
+    InterpreterResult, Output, Value,
+};
+use crate::ast::nodes::{
+    BinaryOperator, ENTRY_POINT, Expression, NodeId, Pattern, Program, Spanned, Statement,
+    TypeExpression, hole_text,
+};
+use crate::lexer::edition::Edition;
 use crate::lexer::tokens::Span;
+use crate::stdlib::prelude;
+use crate::typechecker::Type;
+use crate::typechecker::module_loader::{
+    MODULE_DIRECTORY_FILE, module_directory_entries, resolve_import,
+};
+use num_traits::Zero;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
 use std::fs;
+use std::num::IntErrorKind;
 use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, PoisonError};
+use unicode_segmentation::UnicodeSegmentation;
 
 pub struct Interpreter {
     environment: Environment,
     current_directory: PathBuf,
+    /// Directories searched for imports not found in `current_directory`
+    search_path: Vec<PathBuf>,
+    /// Edition imported modules are read in
+    edition: Edition,
+    /// Shared with every nested interpreter so all randomness comes from one stream
+    rng: Rc<RefCell<Rng>>,
+    /// Modules imported without `export`, left out of this module's exports
+    private_imports: HashSet<String>,
+    /// Run imported modules with the prelude defined
+    prelude: bool,
+    /// Where `print` writes; standard output when `None`
+    output: Option<Output>,
+    /// Where `input` and `readAll` read; standard input when `None`
+    input: Option<Input>,
+    /// Set to stop the program, such as by Ctrl-C in the REPL
+    interrupt: Option<Arc<AtomicBool>>,
+    /// Threads and channels opened and not yet released
+    handles: Handles,
+    /// Where to count the statements that run, for `--coverage`
+    coverage: Option<Coverage>,
+    /// Function calls in progress around the code being run
+    call_depth: usize,
+    /// Stop with a runtime error when calls nest deeper than this
+    max_call_depth: Option<usize>,
 }
 
 impl Interpreter {
+    /// Create an interpreter with the prelude defined
     pub fn new() -> Self {
+        let mut interpreter = Self::without_prelude();
+        interpreter.prelude = true;
+        interpreter.load_prelude();
+        interpreter
+    }
+
+    /// Create an interpreter with nothing defined, for running programs and
+    /// their imports without the prelude
+    pub fn without_prelude() -> Self {
+        Self::with_environment(Environment::new())
+    }
+
+    pub fn with_environment(environment: Environment) -> Self {
         Self {
-            environment: Environment::new(),
+            environment,
             current_directory: std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")),
+            search_path: Vec::new(),
+            edition: Edition::default(),
+            rng: Rc::new(RefCell::new(Rng::from_time())),
+            private_imports: HashSet::new(),
+            prelude: false,
+            output: None,
+            input: None,
+            interrupt: None,
+            handles: Handles::default(),
+            coverage: None,
+            call_depth: 0,
+            max_call_depth: None,
         }
     }
 
-    pub fn with_environment(environment: Environment) -> Self {
+    /// Configure an interpreter before creating it
+    pub fn builder() -> InterpreterBuilder {
+        InterpreterBuilder::default()
+    }
+
+    pub(super) fn from_builder(builder: InterpreterBuilder) -> Self {
+        let mut interpreter = Self::without_prelude();
+        if let Some(directory) = builder.current_directory {
+            interpreter.current_directory = directory;
+        }
+        interpreter.search_path = builder.search_path;
+        interpreter.edition = builder.edition;
+        if let Some(seed) = builder.rng_seed {
+            interpreter.set_rng_seed(seed);
+        }
+        interpreter.output = builder.output;
+        interpreter.input = builder.input;
+        interpreter.interrupt = builder.interrupt;
+        interpreter.coverage = builder.coverage;
+        interpreter.max_call_depth = builder.max_call_depth;
+        interpreter.prelude = builder.prelude;
+        match builder.environment {
+            Some(environment) => interpreter.environment = environment,
+            None if builder.prelude => interpreter.load_prelude(),
+            None => {}
+        }
+        interpreter
+    }
+
+    /// Interpreter for a nested scope, sharing this one's directory and RNG
+    fn child(&self, environment: Environment) -> Self {
         Self {
             environment,
-            current_directory: std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")),
+            current_directory: self.current_directory.clone(),
+            search_path: self.search_path.clone(),
+            edition: self.edition,
+            rng: self.rng.clone(),
+            private_imports: HashSet::new(),
+            prelude: self.prelude,
+            output: self.output.clone(),
+            input: self.input.clone(),
+            interrupt: self.interrupt.clone(),
+            handles: self.handles.clone(),
+            coverage: self.coverage.clone(),
+            call_depth: self.call_depth,
+            max_call_depth: self.max_call_depth,
         }
     }
 
+    /// Interpreter for the body of a function called at `span`, one call
+    /// deeper than this one
+    fn callee(&self, environment: Environment, span: &Span) -> InterpreterResult<Self> {
+        let mut callee = self.child(environment);
+        callee.call_depth += 1;
+        match self.max_call_depth {
+            Some(max) if callee.call_depth > max => Err(InterpreterError::RuntimeError {
+                message: format!("Calls nested deeper than the limit of {}", max),
+                span: Some(span.clone()),
+            }),
+            _ => Ok(callee),
+        }
+    }
+
+    /// Define the prelude in the outermost scope and open the program's own
+    /// scope inside it. Each definition is evaluated in an empty environment,
+    /// so its closure does not capture a copy of the rest of the prelude.
+    fn load_prelude(&mut self) {
+        for statement in &prelude::program().statements {
+            let mut definition = self.child(Environment::new());
+            definition
+                .interpret_statement(statement)
+                .expect("the prelude runs");
+            for (name, value) in definition.environment.get_all_bindings() {
+                self.environment.bind(name, value);
+            }
+        }
+        self.environment.push_scope();
+    }
+
     pub fn set_current_directory<P: AsRef<Path>>(&mut self, path: P) {
         self.current_directory = path.as_ref().to_path_buf();
     }
 
-    pub fn interpret_program(&mut self, program: &Program) -> InterpreterResult<Value> {
+    /// Look for imports in these directories when they are not found next to
+    /// the importing file, such as where packages are fetched to
+    pub fn set_module_search_path(&mut self, search_path: Vec<PathBuf>) {
+        self.search_path = search_path;
+    }
+
+    /// Read imported modules in `edition`, the one the program is in
+    pub fn set_edition(&mut self, edition: Edition) {
+        self.edition = edition;
+    }
+
+    /// Reseed the generator behind `random`, making runs reproducible
+    pub fn set_rng_seed(&mut self, seed: u64) {
+        *self.rng.borrow_mut() = Rng::new(seed);
+    }
+
+    /// Run a program's statements in order, as set out by `options`
+    pub fn execute(
+        &mut self,
+        program: &Program,
+        options: &ExecOptions,
+    ) -> InterpreterResult<Value> {
+        if let Some(output) = &options.output {
+            self.output = Some(output.clone());
+        }
+        if let Some(input) = &options.input {
+            self.input = Some(input.clone());
+        }
+        if let Some(interrupt) = &options.interrupt {
+            self.interrupt = Some(interrupt.clone());
+        }
+        if let Some(coverage) = &options.coverage {
+            self.coverage = Some(coverage.clone());
+        }
+
+        let mut last_result = Value::Unit;
         for statement in &program.statements {
-            self.interpret_statement(statement)?;
+            if !options.allow_redefinition
+                && let Statement::VariableDeclaration { name, span, .. }
+                | Statement::ConstantDeclaration { name, span, .. }
+                | Statement::EnumDeclaration { name, span, .. }
+                | Statement::FunctionDeclaration { name, span, .. } = statement
+                && self.environment.is_bound_locally(name)
+            {
+                return Err(InterpreterError::RuntimeError {
+                    message: format!("'{}' is already defined", name),
+                    span: Some(span.clone()),
+                });
+            }
+
+            let value = self.interpret_statement(statement)?;
+            if options.echo_results
+                && matches!(statement, Statement::Expression { .. })
+                && value != Value::Unit
+            {
+                self.write_line(&format!("{}", value), statement.span())?;
+            }
+            if options.return_last_value {
+                last_result = value;
+            }
         }
 
-        Ok(Value::Unit)
+        Ok(last_result)
     }
 
+    /// Run a program as a file, with `ExecOptions::file`
+    pub fn interpret_program(&mut self, program: &Program) -> InterpreterResult<Value> {
+        self.execute(program, &ExecOptions::file())
+    }
+
+    /// Call the program's `main` function with the command line arguments.
+    /// Returns `None` when the program does not define `main`.
+    pub fn run_main(&mut self, args: &[String]) -> InterpreterResult<Option<Value>> {
+        let Some(main) = self.environment.lookup(ENTRY_POINT).cloned() else {
+            return Ok(None);
+        };
+        let args = Value::List(args.iter().cloned().map(Value::String).collect());
+        let span = Span::new(0, 0, 1, 1);
+        self.apply_function(main, args, &span).map(Some)
+    }
+
+    /// Run a program as a REPL entry, with `ExecOptions::repl`
     pub fn interpret_program_repl(&mut self, program: &Program) -> InterpreterResult<Value> {
-        let mut last_result = Value::Unit;
+        self.execute(program, &ExecOptions::repl())
+    }
 
-        for statement in &program.statements {
-            last_result = self.interpret_statement(statement)?;
+    /// Write a line of program output
+    fn write_line(&self, text: &str, span: &Span) -> InterpreterResult<()> {
+        let Some(output) = &self.output else {
+            println!("{}", text);
+            return Ok(());
+        };
+        let mut output = output.lock().unwrap_or_else(PoisonError::into_inner);
+        writeln!(output, "{}", text).map_err(|error| InterpreterError::RuntimeError {
+            message: format!("Failed to write output: {}", error),
+            span: Some(span.clone()),
+        })
+    }
+
+    /// Close every channel and join every thread the program opened and has
+    /// not released, such as when the program ends. Results of the threads
+    /// are discarded.
+    pub fn release_handles(&mut self) {
+        // Threads still running may open more handles before they finish
+        loop {
+            let open = std::mem::take(&mut *self.lock_handles());
+            if open.is_empty() {
+                break;
+            }
+            threads::release_all(open);
         }
+    }
 
-        Ok(last_result)
+    /// Release the threads and channels held by `value`, as a `with`
+    /// expression does at the end of its block
+    fn release(&self, value: &Value) {
+        let mut held = Vec::new();
+        threads::collect_handles(value, &mut held);
+        self.lock_handles().retain(|open| !held.contains(open));
+        threads::release_all(held);
+    }
+
+    /// Record a thread or channel as open until it is released
+    fn open(&self, handle: Value) -> Value {
+        self.lock_handles().push(handle.clone());
+        handle
+    }
+
+    /// Join a thread, unless interrupted while waiting for it, and drop it
+    /// from the handle table
+    fn join_thread(
+        &self,
+        id: usize,
+        span: &Span,
+    ) -> InterpreterResult<Option<std::thread::Result<InterpreterResult<Value>>>> {
+        if !threads::wait(id, self.interrupt.as_deref()) {
+            self.check_interrupt(span)?;
+        }
+        self.lock_handles()
+            .retain(|open| *open != Value::Handle(id));
+        Ok(threads::join(id))
+    }
+
+    fn lock_handles(&self) -> std::sync::MutexGuard<'_, Vec<Value>> {
+        self.handles.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Sleep for `ms` milliseconds in short steps, so an interrupt is not
+    /// delayed. Kept out of `interpret_expression` so its locals do not add
+    /// to the stack frame of every nested expression.
+    #[inline(never)]
+    fn sleep(&self, ms: u64, span: &Span) -> InterpreterResult<Value> {
+        let end = std::time::Instant::now() + std::time::Duration::from_millis(ms);
+        while let Some(left) = end.checked_duration_since(std::time::Instant::now()) {
+            self.check_interrupt(span)?;
+            std::thread::sleep(left.min(std::time::Duration::from_millis(50)));
+        }
+        Ok(Value::Unit)
+    }
+
+    /// Stop with a runtime error if the program has been interrupted
+    fn check_interrupt(&self, span: &Span) -> InterpreterResult<()> {
+        match &self.interrupt {
+            Some(interrupt) if interrupt.load(Ordering::Relaxed) => {
+                Err(InterpreterError::RuntimeError {
+                    message: "Interrupted".to_string(),
+                    span: Some(span.clone()),
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Read program input with `read`, from standard input unless another
+    /// source was given
+    fn read_input<T>(
+        &self,
+        span: &Span,
+        read: impl FnOnce(&mut dyn std::io::BufRead) -> std::io::Result<T>,
+    ) -> InterpreterResult<T> {
+        let result = match &self.input {
+            Some(input) => read(&mut *input.lock().unwrap_or_else(PoisonError::into_inner)),
+            None => read(&mut std::io::stdin().lock()),
+        };
+        result.map_err(|error| InterpreterError::RuntimeError {
+            message: format!("Failed to read input: {}", error),
+            span: Some(span.clone()),
+        })
     }
 
     pub fn interpret_statement(&mut self, statement: &Statement) -> InterpreterResult<Value> {
+        if let Some(coverage) = &self.coverage {
+            coverage.hit(statement.span());
+        }
         match statement {
-            Statement::VariableDeclaration { name, value, .. } => {
+            Statement::VariableDeclaration { name, value, .. }
+            | Statement::ConstantDeclaration { name, value, .. } => {
                 let val = self.interpret_expression(value)?;
-                self.environment.bind(name.clone(), val);
+                if name != "_" {
+                    self.environment.define(name.clone(), val, statement.span());
+                }
                 Ok(Value::Unit)
             }
             Statement::FunctionDeclaration {
-                name, param, body, ..
+                name,
+                param,
+                body,
+                param_id,
+                ..
             } => {
                 let recursive_function = Value::Function {
                     param: name.clone(), // The recursive reference parameter
@@ -62,6 +389,7 @@ impl Interpreter {
                         param: param.clone(),
                         param_type: None,
                         body: Box::new(body.clone()),
+                        param_id: *param_id,
                         span: body.span().clone(),
                     }),
                     env: self.environment.clone(),
@@ -71,20 +399,71 @@ impl Interpreter {
                     function: Box::new(recursive_function),
                 };
 
-                self.environment.bind(name.clone(), function_val);
+                self.environment
+                    .define(name.clone(), function_val, statement.span());
+                Ok(Value::Unit)
+            }
+            Statement::EnumDeclaration { name, variants, .. } => {
+                let exports = variants
+                    .iter()
+                    .map(|variant| {
+                        let value = Value::Enum {
+                            name: name.clone(),
+                            variant: variant.clone(),
+                        };
+                        (variant.clone(), value)
+                    })
+                    .collect();
+                let namespace = Value::Module {
+                    name: name.clone(),
+                    exports,
+                };
+                self.environment.bind(name.clone(), namespace);
                 Ok(Value::Unit)
             }
-            Statement::Import { path, alias, span } => {
+            Statement::Import {
+                path,
+                alias,
+                exported,
+                span,
+            } => {
                 let import_name = alias.as_ref().unwrap_or(path);
 
-                let import_path = self.current_directory.join(path);
-
-                let module_val = self.load_module(&import_path, import_name, span)?;
+                let module_val = match crate::stdlib::native_module(path) {
+                    Some(functions) => Value::Module {
+                        name: import_name.clone(),
+                        exports: functions
+                            .iter()
+                            .map(|function| {
+                                let native = Value::Native {
+                                    module: path.clone(),
+                                    name: function.name.to_string(),
+                                    arguments: Vec::new(),
+                                };
+                                (function.name.to_string(), native)
+                            })
+                            .collect(),
+                    },
+                    None => {
+                        let import_path =
+                            resolve_import(&self.current_directory, &self.search_path, path);
+                        self.load_module(&import_path, import_name, span)?
+                    }
+                };
 
+                if *exported {
+                    self.private_imports.remove(import_name);
+                } else {
+                    self.private_imports.insert(import_name.clone());
+                }
                 self.environment.bind(import_name.clone(), module_val);
                 Ok(Value::Unit)
             }
             Statement::Expression { expression, .. } => self.interpret_expression(expression),
+            Statement::Return { value, span } => Err(InterpreterError::Return {
+                value: Box::new(self.interpret_expression(value)?),
+                span: span.clone(),
+            }),
         }
     }
 
@@ -94,26 +473,50 @@ impl Interpreter {
         module_name: &str,
         span: &Span,
     ) -> InterpreterResult<Value> {
+        if path.is_dir() {
+            return self.load_module_directory(path, module_name, span);
+        }
+
         let content = fs::read_to_string(path).map_err(|_| InterpreterError::RuntimeError {
             message: format!("Failed to read module file: {}", path.display()),
             span: Some(span.clone()),
         })?;
 
-        let mut lexer = crate::lexer::tokenizer::Tokenizer::new("");
-        let tokens = lexer
-            .tokenize(&content)
+        // Pure modules always produce the same exports, so reuse earlier results
+        let key = module_cache::Key::new(path, &content, self.edition, self.prelude);
+        if let Some(exports) = module_cache::lookup(&key) {
+            return Ok(Value::Module {
+                name: module_name.to_string(),
+                exports,
+            });
+        }
+
+        let tokens = crate::lexer::tokenizer::Tokenizer::new(&content)
+            .with_edition(self.edition)
+            .tokenize()
             .map_err(|e| InterpreterError::RuntimeError {
                 message: format!("Failed to tokenize module {}: {}", module_name, e),
                 span: Some(span.clone()),
             })?;
-
         let mut parser = crate::ast::parser::Parser::new(tokens);
         let program = parser.parse().map_err(|e| InterpreterError::RuntimeError {
             message: format!("Failed to parse module {}: {}", module_name, e),
             span: Some(span.clone()),
         })?;
-
-        let mut module_interpreter = Interpreter::new();
+        let pure = module_cache::is_pure(&program);
+
+        let mut module_interpreter = if self.prelude {
+            Interpreter::new()
+        } else {
+            Interpreter::without_prelude()
+        };
+        module_interpreter.rng = self.rng.clone();
+        module_interpreter.output = self.output.clone();
+        module_interpreter.input = self.input.clone();
+        module_interpreter.interrupt = self.interrupt.clone();
+        module_interpreter.handles = self.handles.clone();
+        module_interpreter.search_path = self.search_path.clone();
+        module_interpreter.edition = self.edition;
 
         if let Some(parent) = path.parent() {
             module_interpreter.set_current_directory(parent);
@@ -126,7 +529,52 @@ impl Interpreter {
                 span: Some(span.clone()),
             })?;
 
-        let exports = module_interpreter.environment.get_all_bindings();
+        let mut exports = module_interpreter.environment.local_bindings();
+        exports.retain(|name, value| {
+            !(module_interpreter.private_imports.contains(name)
+                && matches!(value, Value::Module { .. }))
+        });
+        if pure {
+            module_cache::store(key, exports.clone());
+        }
+
+        Ok(Value::Module {
+            name: module_name.to_string(),
+            exports,
+        })
+    }
+
+    /// Load a directory as a module: `mod.corr` provides its own exports, and
+    /// every other `.corr` file and subdirectory becomes a nested module
+    fn load_module_directory(
+        &mut self,
+        path: &Path,
+        module_name: &str,
+        span: &Span,
+    ) -> InterpreterResult<Value> {
+        let own_file = path.join(MODULE_DIRECTORY_FILE);
+        let mut exports = HashMap::new();
+        if own_file.is_file()
+            && let Value::Module {
+                exports: own_exports,
+                ..
+            } = self.load_module(&own_file, module_name, span)?
+        {
+            exports = own_exports;
+        }
+
+        let submodules =
+            module_directory_entries(path).map_err(|e| InterpreterError::RuntimeError {
+                message: format!("Failed to read module directory {}: {}", path.display(), e),
+                span: Some(span.clone()),
+            })?;
+        for submodule in submodules {
+            let Some(stem) = submodule.file_stem().and_then(|stem| stem.to_str()) else {
+                continue;
+            };
+            let value = self.load_module(&submodule, stem, span)?;
+            exports.insert(stem.to_string(), value);
+        }
 
         Ok(Value::Module {
             name: module_name.to_string(),
@@ -137,12 +585,13 @@ impl Interpreter {
     pub fn interpret_expression(&mut self, expr: &Expression) -> InterpreterResult<Value> {
         match expr {
             Expression::Number { value, .. } => Ok(Value::Int(*value)),
+            Expression::BigNumber { value, .. } => Ok(Value::BigInt(value.clone())),
 
             Expression::Boolean { value, .. } => Ok(Value::Bool(*value)),
 
             Expression::String { value, .. } => Ok(Value::String(value.clone())),
 
-            Expression::Identifier { name, span } => {
+            Expression::Identifier { name, span, .. } => {
                 self.environment.lookup(name).cloned().ok_or_else(|| {
                     InterpreterError::UndefinedVariable {
                         name: name.clone(),
@@ -152,28 +601,33 @@ impl Interpreter {
             }
 
             Expression::QualifiedIdentifier { module, name, span } => {
-                if let Some(module_val) = self.environment.lookup(module) {
-                    if let Value::Module { exports, .. } = module_val {
-                        // Look up the name in the module's exports
-                        exports.get(name).cloned().ok_or_else(|| {
-                            InterpreterError::UndefinedVariable {
-                                name: format!("{}.{}", module, name),
-                                span: span.clone(),
-                            }
-                        })
-                    } else {
-                        Err(InterpreterError::TypeError {
+                let mut segments = module.split('.');
+                let root = segments.next().unwrap_or_default();
+                let mut module_val = self.environment.lookup(root).ok_or_else(|| {
+                    InterpreterError::UndefinedVariable {
+                        name: root.to_string(),
+                        span: span.clone(),
+                    }
+                })?;
+
+                // Walk down through nested modules, then look up the name
+                for member in segments.chain(std::iter::once(name.as_str())) {
+                    let Value::Module { exports, .. } = module_val else {
+                        return Err(InterpreterError::TypeError {
                             expected: "Module".to_string(),
                             found: module_val.type_name().to_string(),
                             span: span.clone(),
-                        })
-                    }
-                } else {
-                    Err(InterpreterError::UndefinedVariable {
-                        name: module.clone(),
-                        span: span.clone(),
-                    })
+                        });
+                    };
+                    module_val =
+                        exports
+                            .get(member)
+                            .ok_or_else(|| InterpreterError::UndefinedVariable {
+                                name: format!("{}.{}", module, name),
+                                span: span.clone(),
+                            })?;
                 }
+                Ok(module_val.clone())
             }
 
             Expression::List { elements, .. } => {
@@ -190,6 +644,14 @@ impl Interpreter {
                 Ok(Value::Pair(first_val, second_val))
             }
 
+            Expression::Tuple { elements, .. } => {
+                let mut values = Vec::new();
+                for element in elements {
+                    values.push(self.interpret_expression(element)?);
+                }
+                Ok(Value::Tuple(values))
+            }
+
             Expression::BinaryOp {
                 left,
                 operator,
@@ -214,7 +676,13 @@ impl Interpreter {
                         }),
                     },
                     crate::ast::nodes::UnaryOperator::Negate => match operand_val {
-                        Value::Int(n) => Ok(Value::Int(-n)),
+                        Value::Int(n) => n.checked_neg().map(Value::Int).ok_or_else(|| {
+                            InterpreterError::RuntimeError {
+                                message: format!("Integer overflow in -{}", n),
+                                span: Some(span.clone()),
+                            }
+                        }),
+                        Value::BigInt(n) => Ok(Value::BigInt(-n)),
                         _ => Err(InterpreterError::TypeError {
                             expected: "Int".to_string(),
                             found: operand_val.type_name().to_string(),
@@ -269,8 +737,9 @@ impl Interpreter {
                 ..
             } => {
                 // Execute block in a new scope
+                let parent = self.child(Environment::new());
                 self.environment.with_new_scope(|env| {
-                    let mut interpreter = Interpreter::with_environment(env.clone());
+                    let mut interpreter = parent.child(env.clone());
 
                     // Execute all statements
                     for stmt in statements {
@@ -279,6 +748,9 @@ impl Interpreter {
 
                     // Return the final expression if present, otherwise Unit
                     if let Some(expr) = expression {
+                        if let Some(coverage) = &interpreter.coverage {
+                            coverage.hit(expr.span());
+                        }
                         interpreter.interpret_expression(expr)
                     } else {
                         Ok(Value::Unit)
@@ -310,6 +782,31 @@ impl Interpreter {
                 }
             }
 
+            Expression::TupleProjection { tuple, index, span } => {
+                let tuple_val = self.interpret_expression(tuple)?;
+                let mut elements = match tuple_val {
+                    Value::Pair(first, second) => vec![*first, *second],
+                    Value::Tuple(elements) => elements,
+                    _ => {
+                        return Err(InterpreterError::TypeError {
+                            expected: "Tuple".to_string(),
+                            found: tuple_val.type_name().to_string(),
+                            span: span.clone(),
+                        });
+                    }
+                };
+
+                if *index < elements.len() {
+                    Ok(elements.swap_remove(*index))
+                } else {
+                    Err(InterpreterError::IndexOutOfBounds {
+                        index: *index as i64,
+                        length: elements.len(),
+                        span: span.clone(),
+                    })
+                }
+            }
+
             Expression::Cons { head, tail, span } => {
                 let head_val = self.interpret_expression(head)?;
                 let tail_val = self.interpret_expression(tail)?;
@@ -371,9 +868,9 @@ impl Interpreter {
                 }
             }
 
-            Expression::Print { value, span: _ } => {
+            Expression::Print { value, span } => {
                 let val = self.interpret_expression(value)?;
-                println!("{}", self.format_for_print(&val));
+                self.write_line(&self.format_for_print(&val), span)?;
                 Ok(Value::Unit)
             }
 
@@ -381,17 +878,19 @@ impl Interpreter {
                 variable,
                 iterable,
                 body,
-                span: _,
+                ..
             } => {
                 let iterable_val = self.interpret_expression(iterable)?;
 
                 match iterable_val {
                     Value::List(elements) => {
                         // Execute the body for each element
+                        let parent = self.child(Environment::new());
                         self.environment.with_new_scope(|env| {
-                            let mut for_interpreter = Interpreter::with_environment(env.clone());
+                            let mut for_interpreter = parent.child(env.clone());
 
                             for element in elements {
+                                for_interpreter.check_interrupt(body.span())?;
                                 // Bind the loop variable to the current element
                                 for_interpreter.environment.bind(variable.clone(), element);
 
@@ -410,6 +909,23 @@ impl Interpreter {
                 }
             }
 
+            Expression::With {
+                name, value, body, ..
+            } => {
+                let resource = self.interpret_expression(value)?;
+                let parent = self.child(Environment::new());
+                let result = self.environment.with_new_scope(|env| {
+                    let mut with_interpreter = parent.child(env.clone());
+                    with_interpreter
+                        .environment
+                        .bind(name.clone(), resource.clone());
+                    with_interpreter.interpret_expression(body)
+                });
+                // Release even when the body fails or returns early
+                self.release(&resource);
+                result
+            }
+
             Expression::Range {
                 start,
                 end,
@@ -439,6 +955,39 @@ impl Interpreter {
                 }
             }
 
+            Expression::DivMod {
+                dividend,
+                divisor,
+                span,
+            } => {
+                let (l, r) = self.interpret_int_operands(dividend, divisor)?;
+                let (quotient, remainder) = Self::divide_ints(l, r, span)?;
+                Ok(Value::Pair(
+                    Box::new(Value::Int(quotient)),
+                    Box::new(Value::Int(remainder)),
+                ))
+            }
+
+            Expression::SafeDiv {
+                dividend,
+                divisor,
+                span,
+            } => {
+                let (l, r) = self.interpret_int_operands(dividend, divisor)?;
+                match Self::divide_ints(l, r, span) {
+                    Ok((quotient, _)) => Ok(Value::LeftInject(Box::new(Value::Int(quotient)))),
+                    Err(error) => {
+                        let message = match error {
+                            InterpreterError::DivisionByZero { .. } => "Division by zero",
+                            _ => "Integer overflow",
+                        };
+                        Ok(Value::RightInject(Box::new(Value::String(
+                            message.to_string(),
+                        ))))
+                    }
+                }
+            }
+
             Expression::Concat { left, right, .. } => {
                 let left_val = self.interpret_expression(left)?;
                 let right_val = self.interpret_expression(right)?;
@@ -513,6 +1062,260 @@ impl Interpreter {
                         let length = s.chars().count() as i64;
                         Ok(Value::Int(length))
                     }
+                    Value::Bytes(bytes) => Ok(Value::Int(bytes.len() as i64)),
+                    other => Err(InterpreterError::TypeError {
+                        expected: "String".to_string(),
+                        found: other.type_name().to_string(),
+                        span: string.span().clone(),
+                    }),
+                }
+            }
+
+            Expression::Bytes { value, .. } => Ok(Value::Bytes(value.clone())),
+            Expression::Hole { name, span } => Err(InterpreterError::RuntimeError {
+                message: format!(
+                    "reached the hole {}, which has not been filled in",
+                    hole_text(name.as_deref())
+                ),
+                span: Some(span.clone()),
+            }),
+
+            Expression::Spawn { function, span } => {
+                // The thread gets its own copy of the function and everything
+                // it captured; no values are shared between threads
+                let function_val = self.interpret_expression(function)?;
+                let current_directory = self.current_directory.clone();
+                // Seed the thread from this RNG so seeded runs stay reproducible
+                let seed = self.rng.borrow_mut().next_u64();
+                let call_span = span.clone();
+                let output = self.output.clone();
+                let input = self.input.clone();
+                let interrupt = self.interrupt.clone();
+                let handles = self.handles.clone();
+                let coverage = self.coverage.clone();
+                let max_call_depth = self.max_call_depth;
+                let id = threads::spawn(move || {
+                    // The function brings its own environment, prelude included
+                    let mut interpreter = Interpreter::without_prelude();
+                    interpreter.output = output;
+                    interpreter.input = input;
+                    interpreter.interrupt = interrupt;
+                    interpreter.handles = handles;
+                    interpreter.coverage = coverage;
+                    interpreter.max_call_depth = max_call_depth;
+                    interpreter.set_current_directory(current_directory);
+                    interpreter.set_rng_seed(seed);
+                    interpreter.apply_function(function_val, Value::Unit, &call_span)
+                })
+                .map_err(|error| InterpreterError::RuntimeError {
+                    message: format!("Failed to spawn thread: {}", error),
+                    span: Some(span.clone()),
+                })?;
+                Ok(self.open(Value::Handle(id)))
+            }
+
+            Expression::Join { handle, span } => match self.interpret_expression(handle)? {
+                Value::Handle(id) => match self.join_thread(id, span)? {
+                    Some(Ok(result)) => result,
+                    Some(Err(_)) => Err(InterpreterError::RuntimeError {
+                        message: "Spawned thread panicked".to_string(),
+                        span: Some(span.clone()),
+                    }),
+                    None => Err(InterpreterError::RuntimeError {
+                        message: "Thread has already been joined".to_string(),
+                        span: Some(span.clone()),
+                    }),
+                },
+                other => Err(InterpreterError::TypeError {
+                    expected: "Handle".to_string(),
+                    found: other.type_name().to_string(),
+                    span: handle.span().clone(),
+                }),
+            },
+
+            Expression::Channel { .. } => Ok(self.open(Value::Channel(threads::channel()))),
+
+            Expression::Input { span } => {
+                let mut line = String::new();
+                if self.read_input(span, |input| input.read_line(&mut line))? == 0 {
+                    return Ok(Value::RightInject(Box::new(Value::Unit)));
+                }
+                let line = line.strip_suffix('\n').unwrap_or(&line);
+                let line = line.strip_suffix('\r').unwrap_or(line);
+                Ok(Value::LeftInject(Box::new(Value::String(line.to_string()))))
+            }
+
+            Expression::ReadAll { span } => {
+                let mut text = String::new();
+                self.read_input(span, |input| input.read_to_string(&mut text))?;
+                Ok(Value::String(text))
+            }
+
+            Expression::Send {
+                channel,
+                value,
+                span,
+            } => {
+                let channel_val = self.interpret_expression(channel)?;
+                let value_val = self.interpret_expression(value)?;
+                match channel_val {
+                    Value::Channel(id) if threads::send(id, value_val) => Ok(Value::Unit),
+                    Value::Channel(id) => Err(InterpreterError::RuntimeError {
+                        message: format!("Channel {} is closed", id),
+                        span: Some(span.clone()),
+                    }),
+                    other => Err(InterpreterError::TypeError {
+                        expected: "Channel".to_string(),
+                        found: other.type_name().to_string(),
+                        span: channel.span().clone(),
+                    }),
+                }
+            }
+
+            Expression::Recv { channel, span } => match self.interpret_expression(channel)? {
+                Value::Channel(id) => {
+                    let value = threads::recv(id, self.interrupt.as_deref());
+                    if value.is_none() {
+                        self.check_interrupt(span)?;
+                    }
+                    value.ok_or_else(|| InterpreterError::RuntimeError {
+                        message: format!("Channel {} is closed", id),
+                        span: Some(span.clone()),
+                    })
+                }
+                other => Err(InterpreterError::TypeError {
+                    expected: "Channel".to_string(),
+                    found: other.type_name().to_string(),
+                    span: channel.span().clone(),
+                }),
+            },
+
+            Expression::Sleep { milliseconds, span } => {
+                match self.interpret_expression(milliseconds)? {
+                    Value::Int(ms) if ms >= 0 => self.sleep(ms as u64, span),
+                    Value::Int(ms) => Err(InterpreterError::RuntimeError {
+                        message: format!("Cannot sleep for a negative duration: {}ms", ms),
+                        span: Some(span.clone()),
+                    }),
+                    other => Err(InterpreterError::TypeError {
+                        expected: "Int".to_string(),
+                        found: other.type_name().to_string(),
+                        span: milliseconds.span().clone(),
+                    }),
+                }
+            }
+
+            Expression::Random { bound, span } => match self.interpret_expression(bound)? {
+                Value::Int(n) if n > 0 => {
+                    Ok(Value::Int(self.rng.borrow_mut().below(n as u64) as i64))
+                }
+                Value::Int(n) => Err(InterpreterError::RuntimeError {
+                    message: format!("random bound must be positive, got {}", n),
+                    span: Some(span.clone()),
+                }),
+                other => Err(InterpreterError::TypeError {
+                    expected: "Int".to_string(),
+                    found: other.type_name().to_string(),
+                    span: bound.span().clone(),
+                }),
+            },
+
+            Expression::ReadFileBytes { path, .. } => match self.interpret_expression(path)? {
+                Value::String(path) => match fs::read(self.current_directory.join(&path)) {
+                    Ok(contents) => Ok(Value::LeftInject(Box::new(Value::Bytes(contents)))),
+                    Err(error) => Ok(Value::RightInject(Box::new(Value::String(format!(
+                        "Failed to read {}: {}",
+                        path, error
+                    ))))),
+                },
+                other => Err(InterpreterError::TypeError {
+                    expected: "String".to_string(),
+                    found: other.type_name().to_string(),
+                    span: path.span().clone(),
+                }),
+            },
+
+            Expression::ByteAt { bytes, index, span } => {
+                let bytes_val = self.interpret_expression(bytes)?;
+                let index_val = self.interpret_expression(index)?;
+
+                match (bytes_val, index_val) {
+                    (Value::Bytes(b), Value::Int(i)) => usize::try_from(i)
+                        .ok()
+                        .and_then(|index| b.get(index))
+                        .map(|byte| Value::Int(*byte as i64))
+                        .ok_or_else(|| InterpreterError::RuntimeError {
+                            message: format!("Byte index {} out of bounds (length {})", i, b.len()),
+                            span: Some(span.clone()),
+                        }),
+                    (Value::Bytes(_), other) => Err(InterpreterError::TypeError {
+                        expected: "Int".to_string(),
+                        found: other.type_name().to_string(),
+                        span: index.span().clone(),
+                    }),
+                    (other, _) => Err(InterpreterError::TypeError {
+                        expected: "Bytes".to_string(),
+                        found: other.type_name().to_string(),
+                        span: bytes.span().clone(),
+                    }),
+                }
+            }
+
+            Expression::ToBytes { string, .. } => match self.interpret_expression(string)? {
+                Value::String(s) => Ok(Value::Bytes(s.into_bytes())),
+                other => Err(InterpreterError::TypeError {
+                    expected: "String".to_string(),
+                    found: other.type_name().to_string(),
+                    span: string.span().clone(),
+                }),
+            },
+
+            Expression::ToInt { string, .. } => match self.interpret_expression(string)? {
+                Value::String(s) => Ok(match s.parse::<i64>() {
+                    Ok(n) => Value::LeftInject(Box::new(Value::Int(n))),
+                    Err(error) => Value::RightInject(Box::new(Value::String(
+                        if matches!(
+                            error.kind(),
+                            IntErrorKind::PosOverflow | IntErrorKind::NegOverflow
+                        ) {
+                            format!("'{}' does not fit in an Int", s)
+                        } else {
+                            format!("'{}' is not an Int", s)
+                        },
+                    ))),
+                }),
+                other => Err(InterpreterError::TypeError {
+                    expected: "String".to_string(),
+                    found: other.type_name().to_string(),
+                    span: string.span().clone(),
+                }),
+            },
+
+            Expression::FromBytes { bytes, .. } => match self.interpret_expression(bytes)? {
+                Value::Bytes(b) => match String::from_utf8(b) {
+                    Ok(s) => Ok(Value::LeftInject(Box::new(Value::String(s)))),
+                    Err(error) => Ok(Value::RightInject(Box::new(Value::String(format!(
+                        "Invalid UTF-8: {}",
+                        error.utf8_error()
+                    ))))),
+                },
+                other => Err(InterpreterError::TypeError {
+                    expected: "Bytes".to_string(),
+                    found: other.type_name().to_string(),
+                    span: bytes.span().clone(),
+                }),
+            },
+
+            Expression::Graphemes { string, .. } => {
+                let string_val = self.interpret_expression(string)?;
+
+                match string_val {
+                    // Extended grapheme clusters, so "e\u{301}" and flag emoji stay whole
+                    Value::String(s) => Ok(Value::List(
+                        s.graphemes(true)
+                            .map(|grapheme| Value::String(grapheme.to_string()))
+                            .collect(),
+                    )),
                     other => Err(InterpreterError::TypeError {
                         expected: "String".to_string(),
                         found: other.type_name().to_string(),
@@ -533,6 +1336,17 @@ impl Interpreter {
                 Ok(Value::String(type_string))
             }
 
+            Expression::TypeTest {
+                expression,
+                type_annotation,
+                ..
+            } => {
+                let value = self.interpret_expression(expression)?;
+                Ok(Value::Bool(Self::value_has_type(&value, type_annotation)))
+            }
+
+            Expression::TypeAscription { expression, .. } => self.interpret_expression(expression),
+
             Expression::Case {
                 expression,
                 left_pattern,
@@ -540,6 +1354,7 @@ impl Interpreter {
                 right_pattern,
                 right_body,
                 span,
+                ..
             } => {
                 let val = self.interpret_expression(expression)?;
                 match val {
@@ -565,6 +1380,22 @@ impl Interpreter {
                 }
             }
 
+            Expression::Match {
+                expression,
+                arms,
+                span,
+            } => {
+                let value = self.interpret_expression(expression)?;
+                let arm = arms
+                    .iter()
+                    .find(|arm| Self::pattern_matches(&arm.pattern, &value))
+                    .ok_or_else(|| InterpreterError::RuntimeError {
+                        message: format!("No case arm matches {}", value),
+                        span: Some(span.clone()),
+                    })?;
+                self.interpret_expression(&arm.body)
+            }
+
             Expression::If {
                 condition,
                 then_branch,
@@ -605,7 +1436,9 @@ impl Interpreter {
         match operator {
             // Arithmetic operations
             BinaryOperator::Add => match (&left_val, &right_val) {
-                (Value::Int(l), Value::Int(r)) => Ok(Value::Int(l + r)),
+                (Value::Int(_) | Value::BigInt(_), Value::Int(_) | Value::BigInt(_)) => {
+                    Self::integer_arithmetic(operator, &left_val, &right_val, span)
+                }
                 (Value::String(l), Value::String(r)) => Ok(Value::String(format!("{}{}", l, r))),
                 _ => Err(InterpreterError::TypeError {
                     expected: "Int + Int or String + String".to_string(),
@@ -615,7 +1448,9 @@ impl Interpreter {
             },
 
             BinaryOperator::Subtract => match (&left_val, &right_val) {
-                (Value::Int(l), Value::Int(r)) => Ok(Value::Int(l - r)),
+                (Value::Int(_) | Value::BigInt(_), Value::Int(_) | Value::BigInt(_)) => {
+                    Self::integer_arithmetic(operator, &left_val, &right_val, span)
+                }
                 _ => Err(InterpreterError::TypeError {
                     expected: "Int - Int".to_string(),
                     found: format!("{} - {}", left_val.type_name(), right_val.type_name()),
@@ -624,7 +1459,9 @@ impl Interpreter {
             },
 
             BinaryOperator::Multiply => match (&left_val, &right_val) {
-                (Value::Int(l), Value::Int(r)) => Ok(Value::Int(l * r)),
+                (Value::Int(_) | Value::BigInt(_), Value::Int(_) | Value::BigInt(_)) => {
+                    Self::integer_arithmetic(operator, &left_val, &right_val, span)
+                }
                 _ => Err(InterpreterError::TypeError {
                     expected: "Int * Int".to_string(),
                     found: format!("{} * {}", left_val.type_name(), right_val.type_name()),
@@ -633,12 +1470,8 @@ impl Interpreter {
             },
 
             BinaryOperator::Divide => match (&left_val, &right_val) {
-                (Value::Int(l), Value::Int(r)) => {
-                    if *r == 0 {
-                        Err(InterpreterError::DivisionByZero { span: span.clone() })
-                    } else {
-                        Ok(Value::Int(l / r))
-                    }
+                (Value::Int(_) | Value::BigInt(_), Value::Int(_) | Value::BigInt(_)) => {
+                    Self::integer_arithmetic(operator, &left_val, &right_val, span)
                 }
                 _ => Err(InterpreterError::TypeError {
                     expected: "Int / Int".to_string(),
@@ -652,49 +1485,93 @@ impl Interpreter {
 
             BinaryOperator::NotEqual => Ok(Value::Bool(left_val != right_val)),
 
-            BinaryOperator::LessThan => match (&left_val, &right_val) {
-                (Value::Int(l), Value::Int(r)) => Ok(Value::Bool(l < r)),
-                _ => Err(InterpreterError::TypeError {
-                    expected: "Int < Int".to_string(),
+            BinaryOperator::LessThan => match left_val.compare(&right_val) {
+                Some(ordering) => Ok(Value::Bool(ordering.is_lt())),
+                None => Err(InterpreterError::TypeError {
+                    expected: "comparable values".to_string(),
                     found: format!("{} < {}", left_val.type_name(), right_val.type_name()),
                     span: span.clone(),
                 }),
             },
 
-            BinaryOperator::LessThanEqual => match (&left_val, &right_val) {
-                (Value::Int(l), Value::Int(r)) => Ok(Value::Bool(l <= r)),
-                _ => Err(InterpreterError::TypeError {
-                    expected: "Int <= Int".to_string(),
+            BinaryOperator::LessThanEqual => match left_val.compare(&right_val) {
+                Some(ordering) => Ok(Value::Bool(ordering.is_le())),
+                None => Err(InterpreterError::TypeError {
+                    expected: "comparable values".to_string(),
                     found: format!("{} <= {}", left_val.type_name(), right_val.type_name()),
                     span: span.clone(),
                 }),
             },
 
-            BinaryOperator::GreaterThan => match (&left_val, &right_val) {
-                (Value::Int(l), Value::Int(r)) => Ok(Value::Bool(l > r)),
-                _ => Err(InterpreterError::TypeError {
-                    expected: "Int > Int".to_string(),
+            BinaryOperator::GreaterThan => match left_val.compare(&right_val) {
+                Some(ordering) => Ok(Value::Bool(ordering.is_gt())),
+                None => Err(InterpreterError::TypeError {
+                    expected: "comparable values".to_string(),
                     found: format!("{} > {}", left_val.type_name(), right_val.type_name()),
                     span: span.clone(),
                 }),
             },
 
-            BinaryOperator::GreaterThanEqual => match (&left_val, &right_val) {
-                (Value::Int(l), Value::Int(r)) => Ok(Value::Bool(l >= r)),
-                _ => Err(InterpreterError::TypeError {
-                    expected: "Int >= Int".to_string(),
+            BinaryOperator::GreaterThanEqual => match left_val.compare(&right_val) {
+                Some(ordering) => Ok(Value::Bool(ordering.is_ge())),
+                None => Err(InterpreterError::TypeError {
+                    expected: "comparable values".to_string(),
                     found: format!("{} >= {}", left_val.type_name(), right_val.type_name()),
                     span: span.clone(),
                 }),
             },
 
-            BinaryOperator::LogicalAnd => {
-                Ok(Value::Bool(left_val.is_truthy() && right_val.is_truthy()))
+            BinaryOperator::LogicalAnd | BinaryOperator::LogicalOr => {
+                let and = *operator == BinaryOperator::LogicalAnd;
+                match (&left_val, &right_val) {
+                    (Value::Bool(l), Value::Bool(r)) => {
+                        Ok(Value::Bool(if and { *l && *r } else { *l || *r }))
+                    }
+                    _ if self.edition.has_truthiness() => {
+                        let (l, r) = (truthy(&left_val), truthy(&right_val));
+                        Ok(Value::Bool(if and { l && r } else { l || r }))
+                    }
+                    // Values are not truthy, so anything but a Bool is an error
+                    _ => {
+                        let symbol = if and { "&&" } else { "||" };
+                        Err(InterpreterError::RuntimeError {
+                            message: format!(
+                                "'{}' needs two Bools, found {} {} {}; other values are not treated as true or false, so compare them explicitly (for example 'n != 0' or 'xs != []')",
+                                symbol,
+                                left_val.type_name(),
+                                symbol,
+                                right_val.type_name()
+                            ),
+                            span: Some(span.clone()),
+                        })
+                    }
+                }
             }
 
-            BinaryOperator::LogicalOr => {
-                Ok(Value::Bool(left_val.is_truthy() || right_val.is_truthy()))
-            }
+            BinaryOperator::Append => match (left_val, right_val) {
+                // Reuse the left operand's buffer so chains of ++ stay linear
+                (Value::List(mut l), Value::List(r)) => {
+                    l.extend(r);
+                    Ok(Value::List(l))
+                }
+                (Value::String(mut l), Value::String(r)) => {
+                    l.push_str(&r);
+                    Ok(Value::String(l))
+                }
+                (Value::Bytes(mut l), Value::Bytes(r)) => {
+                    l.extend(r);
+                    Ok(Value::Bytes(l))
+                }
+                (l, r) => Err(InterpreterError::TypeError {
+                    expected: "List ++ List or String ++ String".to_string(),
+                    found: format!("{} ++ {}", l.type_name(), r.type_name()),
+                    span: span.clone(),
+                }),
+            },
+
+            BinaryOperator::ComposeForward => self.compose_functions(left_val, right_val, span),
+
+            BinaryOperator::ComposeBackward => self.compose_functions(right_val, left_val, span),
 
             BinaryOperator::Assign => Err(InterpreterError::RuntimeError {
                 message: "Assignment operator not supported in expressions".to_string(),
@@ -703,6 +1580,52 @@ impl Interpreter {
         }
     }
 
+    /// Build a closure that applies `first`, then `second`, to its argument
+    fn compose_functions(
+        &self,
+        first: Value,
+        second: Value,
+        span: &Span,
+    ) -> InterpreterResult<Value> {
+        for function in [&first, &second] {
+            if !matches!(function, Value::Function { .. } | Value::FixedPoint { .. }) {
+                return Err(InterpreterError::TypeError {
+                    expected: "Function".to_string(),
+                    found: function.type_name().to_string(),
+                    span: span.clone(),
+                });
+            }
+        }
+
+        // The composed functions live under names that cannot be written in
+        // source code, so they never clash with user bindings
+        let mut env = self.environment.clone();
+        env.push_scope();
+        env.bind("$first".to_string(), first);
+        env.bind("$second".to_string(), second);
+
+        let identifier = |name: &str| Expression::Identifier {
+            name: name.to_string(),
+            id: NodeId::default(),
+            span: span.clone(),
+        };
+        let body = Expression::FunctionCall {
+            function: Box::new(identifier("$second")),
+            argument: Box::new(Expression::FunctionCall {
+                function: Box::new(identifier("$first")),
+                argument: Box::new(identifier("x")),
+                span: span.clone(),
+            }),
+            span: span.clone(),
+        };
+
+        Ok(Value::Function {
+            param: "x".to_string(),
+            body: Box::new(body),
+            env,
+        })
+    }
+
     /// Interpret a function call
     fn interpret_function_call(
         &mut self,
@@ -712,18 +1635,79 @@ impl Interpreter {
     ) -> InterpreterResult<Value> {
         let func_val = self.interpret_expression(function)?;
         let arg_val = self.interpret_expression(argument)?;
+        match self.apply_function(func_val, arg_val, span) {
+            // Name the binding called, and where it comes from
+            Err(InterpreterError::NotCallable {
+                value,
+                type_name,
+                binding: None,
+                span: at,
+            }) if at == *span => {
+                let binding = match function {
+                    Expression::Identifier { name, .. } => Some(Box::new((
+                        name.clone(),
+                        self.environment.definition(name).cloned(),
+                    ))),
+                    _ => None,
+                };
+                Err(InterpreterError::NotCallable {
+                    value,
+                    type_name,
+                    binding,
+                    span: at,
+                })
+            }
+            result => result,
+        }
+    }
 
+    /// Apply an already evaluated function value to an argument
+    fn apply_function(
+        &mut self,
+        func_val: Value,
+        arg_val: Value,
+        span: &Span,
+    ) -> InterpreterResult<Value> {
+        // Every loop without a `for` goes through a call, so checking here
+        // is enough to stop any program
+        self.check_interrupt(span)?;
         match func_val {
             Value::Function { param, body, env } => {
                 let mut call_env = env;
                 call_env.push_scope();
                 call_env.bind(param, arg_val);
 
-                let mut call_interpreter = Interpreter::with_environment(call_env);
-                let result = call_interpreter.interpret_expression(&body)?;
+                let mut call_interpreter = self.callee(call_env, span)?;
+                let result = call_interpreter.function_body(&body)?;
 
                 Ok(result)
             }
+            Value::Native {
+                module,
+                name,
+                mut arguments,
+            } => {
+                let native = crate::stdlib::native_function(&module, &name).ok_or_else(|| {
+                    InterpreterError::UndefinedVariable {
+                        name: name.clone(),
+                        span: span.clone(),
+                    }
+                })?;
+                arguments.push(arg_val);
+                if arguments.len() < native.arity {
+                    return Ok(Value::Native {
+                        module,
+                        name,
+                        arguments,
+                    });
+                }
+                (native.call)(&self.current_directory, &arguments).map_err(|message| {
+                    InterpreterError::RuntimeError {
+                        message: format!("{}: {}", name, message),
+                        span: Some(span.clone()),
+                    }
+                })
+            }
             Value::FixedPoint { function } => {
                 if let Value::Function { param, body, env } = function.as_ref() {
                     let mut call_env = env.clone();
@@ -735,8 +1719,8 @@ impl Interpreter {
                         },
                     );
 
-                    let mut recursive_interpreter = Interpreter::with_environment(call_env);
-                    let inner_func = recursive_interpreter.interpret_expression(&body)?;
+                    let mut recursive_interpreter = self.child(call_env);
+                    let inner_func = recursive_interpreter.function_body(body)?;
 
                     // Apply the inner function to the actual argument
                     match inner_func {
@@ -749,8 +1733,8 @@ impl Interpreter {
                             final_env.push_scope();
                             final_env.bind(inner_param, arg_val);
 
-                            let mut final_interpreter = Interpreter::with_environment(final_env);
-                            final_interpreter.interpret_expression(&inner_body)
+                            let mut final_interpreter = self.callee(final_env, span)?;
+                            final_interpreter.function_body(&inner_body)
                         }
                         _ => Err(InterpreterError::RuntimeError {
                             message: "Fixed point function body must return a function".to_string(),
@@ -764,7 +1748,21 @@ impl Interpreter {
                     })
                 }
             }
-            _ => Err(InterpreterError::NotCallable { span: span.clone() }),
+            other => Err(InterpreterError::NotCallable {
+                value: abbreviate(&format!("{}", other)),
+                type_name: other.type_name(),
+                binding: None,
+                span: span.clone(),
+            }),
+        }
+    }
+
+    /// Evaluate the body of a called function, whose value is that of the
+    /// first `return` reached in it, if any
+    fn function_body(&mut self, body: &Expression) -> InterpreterResult<Value> {
+        match self.interpret_expression(body) {
+            Err(InterpreterError::Return { value, .. }) => Ok(*value),
+            result => result,
         }
     }
 
@@ -772,11 +1770,17 @@ impl Interpreter {
         &self.environment
     }
 
+    pub fn environment_mut(&mut self) -> &mut Environment {
+        &mut self.environment
+    }
+
     fn value_to_string(&self, value: &Value) -> String {
         match value {
             Value::Int(i) => i.to_string(),
+            Value::BigInt(i) => i.to_string(),
             Value::Bool(b) => b.to_string(),
             Value::String(s) => s.clone(),
+            Value::Bytes(bytes) => format!("b\"{}\"", bytes.escape_ascii()),
             Value::Unit => "()".to_string(),
             Value::List(elements) => {
                 let element_strings: Vec<String> = elements
@@ -792,18 +1796,31 @@ impl Interpreter {
                     self.value_to_string(second)
                 )
             }
+            Value::Tuple(elements) => {
+                let element_strings: Vec<String> = elements
+                    .iter()
+                    .map(|elem| self.value_to_string(elem))
+                    .collect();
+                format!("({})", element_strings.join(", "))
+            }
             Value::Function { .. } => "<function>".to_string(),
+            Value::Native { name, .. } => format!("<native {}>", name),
+            Value::Handle(id) => format!("<thread {}>", id),
+            Value::Channel(id) => format!("<channel {}>", id),
             Value::LeftInject(val) => format!("inl({})", self.value_to_string(val)),
             Value::RightInject(val) => format!("inr({})", self.value_to_string(val)),
             Value::FixedPoint { .. } => "<fixed-point>".to_string(),
             Value::Module { name, .. } => format!("<module {}>", name),
+            Value::Enum { .. } => format!("{}", value),
         }
     }
 
     fn format_for_print(&self, value: &Value) -> String {
         match value {
             Value::String(s) => s.clone(), // No quotes for print output
+            Value::Bytes(bytes) => format!("b\"{}\"", bytes.escape_ascii()),
             Value::Int(n) => n.to_string(),
+            Value::BigInt(n) => n.to_string(),
             Value::Bool(b) => b.to_string(),
             Value::Unit => "()".to_string(),
             Value::List(elements) => {
@@ -824,19 +1841,189 @@ impl Interpreter {
                     self.format_for_print(second)
                 )
             }
+            Value::Tuple(elements) => {
+                let element_strings: Vec<String> = elements
+                    .iter()
+                    .map(|elem| self.format_for_print(elem))
+                    .collect();
+                format!("({})", element_strings.join(", "))
+            }
             Value::Function { param, .. } => format!("<function {}>", param),
+            Value::Native { name, .. } => format!("<native {}>", name),
+            Value::Handle(id) => format!("<thread {}>", id),
+            Value::Channel(id) => format!("<channel {}>", id),
             Value::LeftInject(val) => format!("Left({})", self.format_for_print(val)),
             Value::RightInject(val) => format!("Right({})", self.format_for_print(val)),
             Value::FixedPoint { .. } => "<fixed_point>".to_string(),
             Value::Module { name, .. } => format!("<module {}>", name),
+            Value::Enum { .. } => format!("{}", value),
+        }
+    }
+
+    /// Integer division truncating toward zero, like Rust and C. The remainder
+    /// takes the sign of the dividend, so `quotient * divisor + remainder`
+    /// always equals the dividend: `-7 / 2` is `-3` with remainder `-1`.
+    fn divide_ints(dividend: i64, divisor: i64, span: &Span) -> InterpreterResult<(i64, i64)> {
+        if divisor == 0 {
+            return Err(InterpreterError::DivisionByZero { span: span.clone() });
+        }
+        match (dividend.checked_div(divisor), dividend.checked_rem(divisor)) {
+            (Some(quotient), Some(remainder)) => Ok((quotient, remainder)),
+            _ => Err(InterpreterError::RuntimeError {
+                message: format!("Integer overflow in {} / {}", dividend, divisor),
+                span: Some(span.clone()),
+            }),
+        }
+    }
+
+    /// `+`, `-`, `*` and `/` on integers. `Int` arithmetic is checked and
+    /// reports overflow; if either operand is a `BigInt` the other is widened
+    /// and the result is a `BigInt`.
+    fn integer_arithmetic(
+        operator: &BinaryOperator,
+        left: &Value,
+        right: &Value,
+        span: &Span,
+    ) -> InterpreterResult<Value> {
+        if let (Value::Int(l), Value::Int(r)) = (left, right) {
+            let (result, symbol) = match operator {
+                BinaryOperator::Add => (l.checked_add(*r), "+"),
+                BinaryOperator::Subtract => (l.checked_sub(*r), "-"),
+                BinaryOperator::Multiply => (l.checked_mul(*r), "*"),
+                _ => {
+                    let (quotient, _) = Self::divide_ints(*l, *r, span)?;
+                    return Ok(Value::Int(quotient));
+                }
+            };
+            return result
+                .map(Value::Int)
+                .ok_or_else(|| InterpreterError::RuntimeError {
+                    message: format!("Integer overflow in {} {} {}", l, symbol, r),
+                    span: Some(span.clone()),
+                });
+        }
+
+        let (Some(l), Some(r)) = (left.to_big_int(), right.to_big_int()) else {
+            return Err(InterpreterError::TypeError {
+                expected: "Int or BigInt".to_string(),
+                found: format!("{} and {}", left.type_name(), right.type_name()),
+                span: span.clone(),
+            });
+        };
+        let result = match operator {
+            BinaryOperator::Add => l + r,
+            BinaryOperator::Subtract => l - r,
+            BinaryOperator::Multiply => l * r,
+            _ if r.is_zero() => {
+                return Err(InterpreterError::DivisionByZero { span: span.clone() });
+            }
+            _ => l / r,
+        };
+        Ok(Value::BigInt(result))
+    }
+
+    fn interpret_int_operands(
+        &mut self,
+        left: &Expression,
+        right: &Expression,
+    ) -> InterpreterResult<(i64, i64)> {
+        let left_val = self.interpret_expression(left)?;
+        let right_val = self.interpret_expression(right)?;
+        match (left_val, right_val) {
+            (Value::Int(l), Value::Int(r)) => Ok((l, r)),
+            (Value::Int(_), other) => Err(InterpreterError::TypeError {
+                expected: "Int".to_string(),
+                found: other.type_name().to_string(),
+                span: right.span().clone(),
+            }),
+            (other, _) => Err(InterpreterError::TypeError {
+                expected: "Int".to_string(),
+                found: other.type_name().to_string(),
+                span: left.span().clone(),
+            }),
+        }
+    }
+
+    fn pattern_matches(pattern: &Pattern, value: &Value) -> bool {
+        match (pattern, value) {
+            (Pattern::Wildcard { .. }, _) => true,
+            (
+                Pattern::Variant {
+                    enum_name, variant, ..
+                },
+                Value::Enum { name, variant: tag },
+            ) => enum_name == name && variant == tag,
+            (Pattern::String { value, .. }, Value::String(string)) => value == string,
+            (Pattern::Int { value, .. }, Value::Int(n)) => value == n,
+            (Pattern::Range { start, end, .. }, Value::Int(n)) => {
+                start.is_none_or(|start| *n >= start) && end.is_none_or(|end| *n < end)
+            }
+            _ => false,
+        }
+    }
+
+    /// Runtime check behind `expr is Type`. Function values cannot be
+    /// inspected, so any function matches any function type.
+    fn value_has_type(value: &Value, ty: &TypeExpression) -> bool {
+        match (value, ty) {
+            (Value::BigInt(_), TypeExpression::Named { name, .. }) => {
+                name == "Dyn" || name == "BigInt"
+            }
+            (Value::Bytes(_), TypeExpression::Named { name, .. }) => {
+                name == "Dyn" || name == "Bytes"
+            }
+            (
+                Value::Enum {
+                    name: enum_name, ..
+                },
+                TypeExpression::Named { name, .. },
+            ) => name == "Dyn" || name == enum_name,
+            (_, TypeExpression::Named { name, .. }) => name == "Dyn",
+            (Value::Int(_), TypeExpression::Int { .. })
+            | (Value::Bool(_), TypeExpression::Bool { .. })
+            | (Value::String(_), TypeExpression::String { .. }) => true,
+            (Value::List(elements), TypeExpression::List { element, .. }) => elements
+                .iter()
+                .all(|elem| Self::value_has_type(elem, element)),
+            (
+                Value::Pair(first, second),
+                TypeExpression::Pair {
+                    first: t1,
+                    second: t2,
+                    ..
+                },
+            ) => Self::value_has_type(first, t1) && Self::value_has_type(second, t2),
+            (Value::Tuple(values), TypeExpression::Tuple { elements, .. }) => {
+                values.len() == elements.len()
+                    && values
+                        .iter()
+                        .zip(elements)
+                        .all(|(value, ty)| Self::value_has_type(value, ty))
+            }
+            (Value::LeftInject(inner), TypeExpression::Sum { left, .. }) => {
+                Self::value_has_type(inner, left)
+            }
+            (Value::RightInject(inner), TypeExpression::Sum { right, .. }) => {
+                Self::value_has_type(inner, right)
+            }
+            (
+                Value::Function { .. } | Value::Native { .. } | Value::FixedPoint { .. },
+                TypeExpression::Function { .. },
+            ) => true,
+            (Value::Handle(_), TypeExpression::Handle { .. })
+            | (Value::Channel(_), TypeExpression::Channel { .. }) => true,
+            (_, TypeExpression::Recursive { inner, .. }) => Self::value_has_type(value, inner),
+            _ => false,
         }
     }
 
     fn value_to_type_string(&self, value: &Value) -> String {
         match value {
             Value::Int(_) => "Int".to_string(),
+            Value::BigInt(_) => "BigInt".to_string(),
             Value::Bool(_) => "Bool".to_string(),
             Value::String(_) => "String".to_string(),
+            Value::Bytes(_) => "Bytes".to_string(),
             Value::Unit => "Unit".to_string(),
             Value::List(elements) => {
                 if elements.is_empty() {
@@ -852,10 +2039,33 @@ impl Interpreter {
                 let second_type = self.value_to_type_string(second);
                 format!("({}, {})", first_type, second_type)
             }
+            Value::Tuple(elements) => {
+                let element_types: Vec<String> = elements
+                    .iter()
+                    .map(|elem| self.value_to_type_string(elem))
+                    .collect();
+                format!("({})", element_types.join(", "))
+            }
             Value::Function { param, body, .. } => {
                 // Try to infer function type from parameter name and body analysis
                 self.infer_function_type_string(param, body)
             }
+            Value::Native {
+                module,
+                name,
+                arguments,
+            } => {
+                let mut ty = crate::stdlib::native_function(module, name)
+                    .map(|native| (native.signature)())
+                    .unwrap_or(Type::Unknown);
+                // Drop the parameters that have already been applied
+                for _ in arguments {
+                    if let Type::Function { result, .. } = ty {
+                        ty = *result;
+                    }
+                }
+                ty.to_string()
+            }
             Value::LeftInject(val) => {
                 let inner_type = self.value_to_type_string(val);
                 format!("({} + Unknown)", inner_type)
@@ -864,8 +2074,11 @@ impl Interpreter {
                 let inner_type = self.value_to_type_string(val);
                 format!("(Unknown + {})", inner_type)
             }
+            Value::Handle(_) => "Handle".to_string(),
+            Value::Channel(_) => "Channel".to_string(),
             Value::FixedPoint { .. } => "FixedPoint".to_string(),
             Value::Module { .. } => "Module".to_string(),
+            Value::Enum { name, .. } => name.clone(),
         }
     }
 
@@ -888,6 +2101,7 @@ impl Interpreter {
                 }
             }
             Expression::Number { .. } => "Int".to_string(),
+            Expression::BigNumber { .. } => "BigInt".to_string(),
             Expression::Boolean { .. } => "Bool".to_string(),
             Expression::String { .. } => "String".to_string(),
             Expression::Identifier { name, .. } => {
@@ -1009,11 +2223,11 @@ impl Interpreter {
                 function, argument, ..
             } => {
                 // If parameter is called as a function, it's a function type
-                if let Expression::Identifier { name, .. } = function.as_ref() {
-                    if name == param {
-                        let arg_type = self.infer_expression_type_string(argument, param);
-                        return format!("{} -> Unknown", arg_type);
-                    }
+                if let Expression::Identifier { name, .. } = function.as_ref()
+                    && name == param
+                {
+                    let arg_type = self.infer_expression_type_string(argument, param);
+                    return format!("{} -> Unknown", arg_type);
                 }
                 // Recurse
                 let func_infer = self.infer_parameter_type_from_usage(param, function);
@@ -1053,13 +2267,10 @@ impl Interpreter {
 
     fn expression_uses_param(&self, expr: &Expression, param: &str) -> bool {
         match expr {
-            Expression::Block { expression, .. } => {
-                if let Some(expr) = expression {
-                    self.expression_uses_param(expr, param)
-                } else {
-                    false
-                }
-            }
+            Expression::Block {
+                expression: Some(expr),
+                ..
+            } => self.expression_uses_param(expr, param),
             Expression::Identifier { name, .. } => name == param,
             Expression::BinaryOp { left, right, .. } => {
                 self.expression_uses_param(left, param) || self.expression_uses_param(right, param)
@@ -1082,6 +2293,14 @@ impl Interpreter {
                 self.expression_uses_param(first, param)
                     || self.expression_uses_param(second, param)
             }
+            Expression::Tuple { elements, .. } => elements
+                .iter()
+                .any(|elem| self.expression_uses_param(elem, param)),
+            Expression::TupleProjection { tuple, .. } => self.expression_uses_param(tuple, param),
+            Expression::TypeAscription { expression, .. }
+            | Expression::TypeTest { expression, .. } => {
+                self.expression_uses_param(expression, param)
+            }
             _ => false,
         }
     }
@@ -1092,3 +2311,24 @@ impl Default for Interpreter {
         Self::new()
     }
 }
+
+/// `text` cut short to fit in an error message
+fn abbreviate(text: &str) -> String {
+    const LIMIT: usize = 40;
+    match text.char_indices().nth(LIMIT) {
+        Some((end, _)) => format!("{}...", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+/// Whether `&&` and `||` treat `value` as true in editions with truthiness
+fn truthy(value: &Value) -> bool {
+    match value {
+        Value::Bool(b) => *b,
+        Value::Unit => false,
+        Value::Int(n) => *n != 0,
+        Value::String(s) => !s.is_empty(),
+        Value::List(list) => !list.is_empty(),
+        _ => true,
+    }
+}
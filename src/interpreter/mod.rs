@@ -1,5 +1,13 @@
+pub mod builder;
+pub mod convert;
+pub mod coverage;
 pub mod environment;
+#[allow(clippy::module_inception)]
 pub mod interpreter;
+mod module_cache;
+pub mod options;
+mod rng;
+mod threads;
 pub mod value;
 
 #[cfg(test)]
@@ -8,8 +16,12 @@ mod tests;
 #[cfg(test)]
 mod fix_tests;
 
-pub use environment::Environment;
+pub use builder::InterpreterBuilder;
+pub use coverage::Coverage;
+pub use environment::{BindingChange, Environment, Snapshot};
 pub use interpreter::Interpreter;
+pub use options::{ExecOptions, Input, Output};
+pub use threads::{DEFAULT_MAX_CALL_DEPTH, STACK_SIZE, with_stack};
 pub use value::Value;
 
 pub type InterpreterResult<T> = Result<T, InterpreterError>;
@@ -34,13 +46,55 @@ pub enum InterpreterError {
         span: crate::lexer::tokens::Span,
     },
     /// Function call on non-function value
-    NotCallable { span: crate::lexer::tokens::Span },
+    NotCallable {
+        /// The value called, as it prints
+        value: String,
+        /// Its type
+        type_name: &'static str,
+        /// The name it was called by, with where that name was declared
+        /// if it was bound by a declaration
+        binding: Option<Box<(String, Option<crate::lexer::tokens::Span>)>>,
+        span: crate::lexer::tokens::Span,
+    },
     /// Index out of bounds for list access
     IndexOutOfBounds {
         index: i64,
         length: usize,
         span: crate::lexer::tokens::Span,
     },
+    /// Not a failure: a `return` carrying its value out to the function call
+    /// it leaves. Reported as an error only if no call catches it.
+    Return {
+        value: Box<Value>,
+        span: crate::lexer::tokens::Span,
+    },
+}
+
+impl InterpreterError {
+    /// Stable code identifying the kind of error; see `--explain`
+    pub fn code(&self) -> &'static str {
+        match self {
+            InterpreterError::RuntimeError { .. } | InterpreterError::Return { .. } => "E0200",
+            InterpreterError::DivisionByZero { .. } => "E0201",
+            InterpreterError::UndefinedVariable { .. } => "E0202",
+            InterpreterError::TypeError { .. } => "E0203",
+            InterpreterError::NotCallable { .. } => "E0204",
+            InterpreterError::IndexOutOfBounds { .. } => "E0205",
+        }
+    }
+
+    /// Location of the expression that failed, if known
+    pub fn span(&self) -> Option<&crate::lexer::tokens::Span> {
+        match self {
+            InterpreterError::RuntimeError { span, .. } => span.as_ref(),
+            InterpreterError::DivisionByZero { span }
+            | InterpreterError::UndefinedVariable { span, .. }
+            | InterpreterError::TypeError { span, .. }
+            | InterpreterError::NotCallable { span, .. }
+            | InterpreterError::IndexOutOfBounds { span, .. }
+            | InterpreterError::Return { span, .. } => Some(span),
+        }
+    }
 }
 
 impl std::fmt::Display for InterpreterError {
@@ -82,12 +136,30 @@ impl std::fmt::Display for InterpreterError {
                     span.line, span.column, expected, found
                 )
             }
-            InterpreterError::NotCallable { span } => {
+            InterpreterError::NotCallable {
+                value,
+                type_name,
+                binding,
+                span,
+            } => {
                 write!(
                     f,
-                    "Attempt to call non-function value at line {}, column {}",
+                    "Attempt to call non-function value at line {}, column {}: ",
                     span.line, span.column
-                )
+                )?;
+                match binding.as_deref() {
+                    Some((name, _)) => write!(f, "'{}' is", name)?,
+                    None => write!(f, "it is")?,
+                }
+                write!(f, " {} of type {}", value, type_name)?;
+                if let Some((_, Some(definition))) = binding.as_deref() {
+                    write!(
+                        f,
+                        ", declared at line {}, column {}",
+                        definition.line, definition.column
+                    )?;
+                }
+                Ok(())
             }
             InterpreterError::IndexOutOfBounds {
                 index,
@@ -100,6 +172,13 @@ impl std::fmt::Display for InterpreterError {
                     index, length, span.line, span.column
                 )
             }
+            InterpreterError::Return { span, .. } => {
+                write!(
+                    f,
+                    "Runtime error at line {}, column {}: 'return' outside a function",
+                    span.line, span.column
+                )
+            }
         }
     }
 }
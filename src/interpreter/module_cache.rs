@@ -0,0 +1,92 @@
+use super::Value;
+use crate::ast::nodes::{Child, Expression, Program, Statement};
+use crate::lexer::edition::Edition;
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex};
+
+type Exports = HashMap<String, Value>;
+
+/// Exports of pure modules, keyed by everything that decides them, so a
+/// module is only executed again when its file or the way it is read changes
+static CACHE: LazyLock<Mutex<HashMap<Key, Exports>>> = LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// What running a pure module depends on: its path and source, the edition
+/// its source is read in, and whether it starts with the prelude
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Key {
+    path: PathBuf,
+    source_hash: u64,
+    edition: Edition,
+    prelude: bool,
+}
+
+impl Key {
+    pub fn new(path: &Path, source: &str, edition: Edition, prelude: bool) -> Self {
+        let mut hasher = DefaultHasher::new();
+        source.hash(&mut hasher);
+        Self {
+            path: path.to_path_buf(),
+            source_hash: hasher.finish(),
+            edition,
+            prelude,
+        }
+    }
+}
+
+/// A module is pure when running it cannot have effects or depend on anything
+/// but its own source: no input or output, randomness, threads, file access
+/// or imports. Only the module's own syntax is looked at; the prelude it can
+/// call has no effects.
+/// Imports are excluded because the cache key does not cover the imported files.
+pub fn is_pure(program: &Program) -> bool {
+    program.statements.iter().all(statement_is_pure)
+}
+
+fn statement_is_pure(statement: &Statement) -> bool {
+    match statement {
+        Statement::Import { .. } => false,
+        Statement::EnumDeclaration { .. } => true,
+        Statement::VariableDeclaration { value, .. }
+        | Statement::ConstantDeclaration { value, .. }
+        | Statement::FunctionDeclaration { body: value, .. }
+        | Statement::Expression {
+            expression: value, ..
+        }
+        | Statement::Return { value, .. } => expression_is_pure(value),
+    }
+}
+
+fn expression_is_pure(expression: &Expression) -> bool {
+    let effect = matches!(
+        expression,
+        Expression::Print { .. }
+            | Expression::Random { .. }
+            | Expression::Spawn { .. }
+            | Expression::Join { .. }
+            | Expression::Sleep { .. }
+            | Expression::Channel { .. }
+            | Expression::Send { .. }
+            | Expression::Recv { .. }
+            | Expression::ReadFileBytes { .. }
+            | Expression::Input { .. }
+            | Expression::ReadAll { .. }
+    );
+    !effect
+        && expression.children().into_iter().all(|child| match child {
+            Child::Statement(statement) => statement_is_pure(statement),
+            Child::Expression(_, expression) => expression_is_pure(expression),
+        })
+}
+
+pub fn lookup(key: &Key) -> Option<Exports> {
+    let cache = CACHE.lock().unwrap_or_else(|e| e.into_inner());
+    cache.get(key).cloned()
+}
+
+pub fn store(key: Key, exports: Exports) {
+    let mut cache = CACHE.lock().unwrap_or_else(|e| e.into_inner());
+    cache.insert(key, exports);
+}
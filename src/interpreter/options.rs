@@ -0,0 +1,85 @@
+//! Settings for running a whole program with [`Interpreter::execute`]
+//!
+//! [`Interpreter::execute`]: super::Interpreter::execute
+
+use super::Coverage;
+use std::io::{BufRead, Write};
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+
+/// A writer shared by an interpreter and the threads it spawns
+pub type Output = Arc<Mutex<dyn Write + Send>>;
+
+/// A reader shared by an interpreter and the threads it spawns
+pub type Input = Arc<Mutex<dyn BufRead + Send>>;
+
+/// How [`Interpreter::execute`](super::Interpreter::execute) runs a program.
+/// The default runs it as a file: top-level names are bound once, the result
+/// is `Unit`, only `print` writes anything and `input` reads standard input.
+#[derive(Clone, Default)]
+pub struct ExecOptions {
+    /// Return the value of the last statement instead of `Unit`
+    pub return_last_value: bool,
+    /// Let a top-level `let` or `fn` replace an earlier binding of the same
+    /// name instead of failing
+    pub allow_redefinition: bool,
+    /// Write the value of every top-level expression that is not `Unit`, as
+    /// if it had been printed
+    pub echo_results: bool,
+    /// Where `print` writes, from now on and in threads spawned later.
+    /// `None` keeps the current destination, standard output by default.
+    pub output: Option<Output>,
+    /// Where `input` and `readAll` read from, from now on and in threads
+    /// spawned later. `None` keeps the current source, standard input by
+    /// default.
+    pub input: Option<Input>,
+    /// Once set, the running program and the threads it spawned stop with a
+    /// runtime error at the next function call or loop iteration. Whoever
+    /// sets it clears it before running more code.
+    pub interrupt: Option<Arc<AtomicBool>>,
+    /// Count which statements run, from now on and in threads spawned later
+    pub coverage: Option<Coverage>,
+}
+
+impl ExecOptions {
+    /// Options for running a file
+    pub fn file() -> Self {
+        Self::default()
+    }
+
+    /// Options for one REPL entry: names can be redefined and the last
+    /// value is returned for the REPL to show
+    pub fn repl() -> Self {
+        Self {
+            return_last_value: true,
+            allow_redefinition: true,
+            ..Self::default()
+        }
+    }
+
+    /// Send output to `writer` instead
+    pub fn with_output(mut self, writer: impl Write + Send + 'static) -> Self {
+        self.output = Some(Arc::new(Mutex::new(writer)));
+        self
+    }
+
+    /// Read input from `reader` instead
+    pub fn with_input(mut self, reader: impl BufRead + Send + 'static) -> Self {
+        self.input = Some(Arc::new(Mutex::new(reader)));
+        self
+    }
+}
+
+impl std::fmt::Debug for ExecOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ExecOptions")
+            .field("return_last_value", &self.return_last_value)
+            .field("allow_redefinition", &self.allow_redefinition)
+            .field("echo_results", &self.echo_results)
+            .field("output", &self.output.as_ref().map(|_| "writer"))
+            .field("input", &self.input.as_ref().map(|_| "reader"))
+            .field("interrupt", &self.interrupt)
+            .field("coverage", &self.coverage.as_ref().map(|_| "counting"))
+            .finish()
+    }
+}
@@ -1,6 +1,7 @@
 #[cfg(test)]
+#[allow(clippy::module_inception)]
 mod tests {
-    use crate::ast::nodes::{BinaryOperator, Expression, Program, Statement};
+    use crate::ast::nodes::{BinaryOperator, Expression, NodeId, Program, Statement};
     use crate::interpreter::{Interpreter, InterpreterError, Value};
     use crate::lexer::tokens::Span;
 
@@ -142,11 +143,13 @@ mod tests {
                         value: 42,
                         span: create_test_span(),
                     },
+                    id: NodeId::default(),
                     span: create_test_span(),
                 },
                 Statement::Expression {
                     expression: Expression::Identifier {
                         name: "x".to_string(),
+                        id: NodeId::default(),
                         span: create_test_span(),
                     },
                     span: create_test_span(),
@@ -163,6 +166,7 @@ mod tests {
         let x_value = interpreter
             .interpret_expression(&Expression::Identifier {
                 name: "x".to_string(),
+                id: NodeId::default(),
                 span: create_test_span(),
             })
             .unwrap();
@@ -180,6 +184,7 @@ mod tests {
             body: Box::new(Expression::BinaryOp {
                 left: Box::new(Expression::Identifier {
                     name: "x".to_string(),
+                    id: NodeId::default(),
                     span: create_test_span(),
                 }),
                 operator: BinaryOperator::Add,
@@ -189,6 +194,7 @@ mod tests {
                 }),
                 span: create_test_span(),
             }),
+            param_id: NodeId::default(),
             span: create_test_span(),
         };
 
@@ -255,6 +261,7 @@ mod tests {
         let mut interpreter = Interpreter::new();
         let expr = Expression::Identifier {
             name: "undefined_var".to_string(),
+            id: NodeId::default(),
             span: create_test_span(),
         };
 
@@ -715,21 +722,21 @@ mod tests {
             param: "x".to_string(),
             param_type: None,
             body: Box::new(Expression::Block {
-                statements: vec![
-                    Statement::Expression {
-                        expression: Expression::Print {
-                            value: Box::new(Expression::Identifier {
-                                name: "x".to_string(),
-                                span: create_test_span(),
-                            }),
+                statements: vec![Statement::Expression {
+                    expression: Expression::Print {
+                        value: Box::new(Expression::Identifier {
+                            name: "x".to_string(),
+                            id: NodeId::default(),
                             span: create_test_span(),
-                        },
+                        }),
                         span: create_test_span(),
                     },
-                ],
+                    span: create_test_span(),
+                }],
                 expression: Some(Box::new(Expression::BinaryOp {
                     left: Box::new(Expression::Identifier {
                         name: "x".to_string(),
+                        id: NodeId::default(),
                         span: create_test_span(),
                     }),
                     operator: crate::ast::nodes::BinaryOperator::Add,
@@ -741,12 +748,13 @@ mod tests {
                 })),
                 span: create_test_span(),
             }),
+            param_id: NodeId::default(),
             span: create_test_span(),
         };
 
         // Verify the function can be created
         let _func_value = interpreter.interpret_expression(&multiline_func).unwrap();
-        
+
         // Call the function with argument 5
         let call_expr = Expression::FunctionCall {
             function: Box::new(multiline_func),
@@ -761,4 +769,327 @@ mod tests {
         // Should return 15 (5 + 10) and print 5 as a side effect
         assert_eq!(result, Value::Int(15));
     }
+
+    #[test]
+    fn test_values_as_hash_keys() {
+        use std::collections::HashSet;
+
+        let values = [
+            Value::Int(1),
+            Value::Bool(true),
+            Value::Unit,
+            Value::List(vec![]),
+            Value::Pair(
+                Box::new(Value::Int(1)),
+                Box::new(Value::String("a".to_string())),
+            ),
+            Value::LeftInject(Box::new(Value::Int(1))),
+            Value::RightInject(Box::new(Value::Int(1))),
+        ];
+        let mut set: HashSet<Value> = values.iter().cloned().collect();
+        assert_eq!(set.len(), values.len());
+
+        // Structurally equal values collapse to one entry
+        set.insert(Value::Pair(
+            Box::new(Value::Int(1)),
+            Box::new(Value::String("a".to_string())),
+        ));
+        set.insert(Value::List(Vec::new()));
+        assert_eq!(set.len(), values.len());
+        assert!(set.contains(&Value::Int(1)));
+        assert!(!set.contains(&Value::Int(2)));
+    }
+
+    #[test]
+    fn test_exec_options() {
+        use crate::interpreter::ExecOptions;
+        use std::sync::{Arc, Mutex};
+
+        let parse = |source: &str| {
+            let tokens = crate::lexer::Tokenizer::new(source).tokenize().unwrap();
+            crate::ast::Parser::new(tokens).parse().unwrap()
+        };
+        let buffer = Arc::new(Mutex::new(Vec::new()));
+        let options = ExecOptions {
+            echo_results: true,
+            output: Some(buffer.clone()),
+            ..ExecOptions::repl()
+        };
+
+        let mut interpreter = Interpreter::new();
+        let program = parse("let x = 1; print(\"hi\"); x + 1; let x = 5; x;");
+        let result = interpreter.execute(&program, &options).unwrap();
+        assert_eq!(result, Value::Int(5));
+        assert_eq!(
+            String::from_utf8(buffer.lock().unwrap().clone()).unwrap(),
+            "hi\n2\n5\n"
+        );
+
+        // Files bind each name once and produce no value
+        let mut interpreter = Interpreter::new();
+        let program = parse("let x = 1; x;");
+        assert_eq!(
+            interpreter.execute(&program, &ExecOptions::file()),
+            Ok(Value::Unit)
+        );
+        let error = interpreter
+            .execute(&parse("let x = 2;"), &ExecOptions::file())
+            .unwrap_err();
+        assert!(error.to_string().contains("'x' is already defined"));
+    }
+
+    #[test]
+    fn test_logical_operators_need_bools() {
+        let run = |source: &str| {
+            let tokens = crate::lexer::Tokenizer::new(source).tokenize().unwrap();
+            let program = crate::ast::Parser::new(tokens).parse().unwrap();
+            Interpreter::new().interpret_program_repl(&program)
+        };
+
+        assert_eq!(run("true && !false || false;"), Ok(Value::Bool(true)));
+        // Values that other languages treat as true or false are errors
+        for source in ["1 && true;", "false || [];", "\"\" || true;"] {
+            let error = run(source).unwrap_err();
+            assert!(error.to_string().contains("needs two Bools"), "{}", error);
+        }
+    }
+
+    #[test]
+    fn test_input_reads_from_the_given_source() {
+        use crate::interpreter::ExecOptions;
+
+        let tokens = crate::lexer::Tokenizer::new("(input(), input(), readAll(), input());")
+            .tokenize()
+            .unwrap();
+        let program = crate::ast::Parser::new(tokens).parse().unwrap();
+        let options = ExecOptions::repl().with_input("first\r\nsecond\nthe\nrest\n".as_bytes());
+
+        let mut interpreter = Interpreter::new();
+        let result = interpreter.execute(&program, &options).unwrap();
+        let line = |text: &str| Value::LeftInject(Box::new(Value::String(text.to_string())));
+        assert_eq!(
+            result,
+            Value::Tuple(vec![
+                line("first"),
+                line("second"),
+                Value::String("the\nrest\n".to_string()),
+                // Input has ended
+                Value::RightInject(Box::new(Value::Unit)),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_interrupt_stops_the_program() {
+        use crate::interpreter::ExecOptions;
+        use std::sync::Arc;
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        let parse = |source: &str| {
+            let tokens = crate::lexer::Tokenizer::new(source).tokenize().unwrap();
+            crate::ast::Parser::new(tokens).parse().unwrap()
+        };
+        let interrupt = Arc::new(AtomicBool::new(false));
+        let options = ExecOptions {
+            interrupt: Some(interrupt.clone()),
+            ..ExecOptions::repl()
+        };
+
+        let mut interpreter = Interpreter::new();
+        let program = parse("fn twice(n) { n * 2 } twice(4);");
+        assert_eq!(interpreter.execute(&program, &options), Ok(Value::Int(8)));
+
+        interrupt.store(true, Ordering::Relaxed);
+        for source in ["twice(1);", "for x in [1, 2] { x; };", "sleep(10000);"] {
+            let error = interpreter.execute(&parse(source), &options).unwrap_err();
+            assert!(error.to_string().contains("Interrupted"), "{}", source);
+        }
+
+        // Waiting on a channel or a thread ends when the interrupt comes
+        for source in [
+            "let c = channel(); recv(c);",
+            "let c = channel(); join(spawn(fn(u) { recv(c) }));",
+        ] {
+            interrupt.store(false, Ordering::Relaxed);
+            let later = interrupt.clone();
+            let pressed = std::thread::spawn(move || {
+                std::thread::sleep(std::time::Duration::from_millis(100));
+                later.store(true, Ordering::Relaxed);
+            });
+            let error = interpreter.execute(&parse(source), &options).unwrap_err();
+            pressed.join().unwrap();
+            assert!(error.to_string().contains("Interrupted"), "{}", source);
+        }
+    }
+
+    #[test]
+    fn test_coverage_counts_lines_that_ran() {
+        use crate::interpreter::{Coverage, ExecOptions};
+
+        let source = "fn sign(n) {\n    if n < 0 {\n        -1\n    } else {\n        1\n    }\n}\nlet a = sign(5);\nlet b = sign(6);\nlet total = count([a, b]);\n";
+        let tokens = crate::lexer::Tokenizer::new(source).tokenize().unwrap();
+        let program = crate::ast::Parser::new(tokens).parse().unwrap();
+        let coverage = Coverage::new();
+        let options = ExecOptions {
+            coverage: Some(coverage.clone()),
+            ..ExecOptions::file()
+        };
+        Interpreter::new().execute(&program, &options).unwrap();
+
+        // Lines of the prelude's `count` are not mixed in with the program's
+        let lines: Vec<(usize, usize)> = coverage.lines(&program).into_iter().collect();
+        assert_eq!(
+            lines,
+            vec![(1, 1), (2, 2), (3, 0), (5, 2), (8, 1), (9, 1), (10, 1)]
+        );
+
+        let report = coverage.lcov(&program, "sign.corr");
+        assert!(report.starts_with("TN:\nSF:sign.corr\nDA:1,1\n"));
+        assert!(report.ends_with("LF:7\nLH:6\nend_of_record\n"));
+    }
+
+    #[test]
+    fn test_calling_a_non_function_names_the_value_and_its_declaration() {
+        let run = |source: &str| {
+            let tokens = crate::lexer::Tokenizer::new(source).tokenize().unwrap();
+            let program = crate::ast::Parser::new(tokens).parse().unwrap();
+            Interpreter::new().interpret_program(&program).unwrap_err()
+        };
+
+        let error = run("let x = 5;\nx(1);");
+        assert_eq!(error.code(), "E0204");
+        assert_eq!(
+            error.to_string(),
+            "Attempt to call non-function value at line 2, column 1: 'x' is 5 of type Int, declared at line 1, column 1"
+        );
+
+        // Parameters have no declaration, and other callees no name
+        let error = run("let call = fn(f) { f(1) };\ncall(true);");
+        assert!(error.to_string().ends_with(": 'f' is true of type Bool"));
+        let error = run("[1, 2](3);");
+        assert!(error.to_string().ends_with(": it is [1, 2] of type List"));
+    }
+
+    #[test]
+    fn test_builder_configures_the_interpreter() {
+        use crate::interpreter::ExecOptions;
+
+        let parse = |source: &str| {
+            let tokens = crate::lexer::Tokenizer::new(source).tokenize().unwrap();
+            crate::ast::Parser::new(tokens).parse().unwrap()
+        };
+        let rolls = parse("let a = random(1000000); let b = random(1000000); (a, b);");
+        let roll = || {
+            Interpreter::builder()
+                .rng_seed(7)
+                .build()
+                .execute(&rolls, &ExecOptions::repl())
+                .unwrap()
+        };
+        assert_eq!(roll(), roll());
+
+        let mut interpreter = Interpreter::builder()
+            .prelude(false)
+            .input("line\n".as_bytes())
+            .build();
+        assert!(!interpreter.environment().is_bound("map"));
+        assert_eq!(
+            interpreter
+                .execute(&parse("input();"), &ExecOptions::repl())
+                .unwrap(),
+            Value::LeftInject(Box::new(Value::String("line".to_string())))
+        );
+
+        let countdown = parse("fn down(n) { if n == 0 { 0 } else { down(n - 1) } }\ndown(3);");
+        let mut limited = Interpreter::builder().max_call_depth(2).build();
+        let error = limited
+            .execute(&countdown, &ExecOptions::repl())
+            .unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("Calls nested deeper than the limit of 2"),
+            "{}",
+            error
+        );
+        let mut unlimited = Interpreter::builder().max_call_depth(4).build();
+        assert_eq!(
+            unlimited.execute(&countdown, &ExecOptions::repl()),
+            Ok(Value::Int(0))
+        );
+    }
+
+    #[test]
+    fn test_tutorial_recursion_depth_fits_the_interpreter_stack() {
+        // The Big Integers example from the tutorial, which recurses 25 levels
+        let source = "let factorial = fix(fn(f) {
+                          fn(n: Int) { if n == 0 { 1n } else { n * f(n - 1) } }
+                      });
+                      factorial(25);";
+        let result = crate::interpreter::with_stack(|| {
+            let tokens = crate::lexer::Tokenizer::new(source).tokenize().unwrap();
+            let program = crate::ast::Parser::new(tokens).parse().unwrap();
+            Interpreter::new()
+                .execute(&program, &crate::interpreter::ExecOptions::repl())
+                .map(|value| format!("{}", value))
+        });
+        assert_eq!(result, Ok("15511210043330985984000000".to_string()));
+    }
+
+    #[test]
+    fn test_unchanged_pure_modules_are_not_run_again() {
+        use crate::ast::Parser;
+        use crate::interpreter::module_cache::{self, Key};
+        use crate::lexer::Tokenizer;
+        use crate::lexer::edition::Edition;
+
+        let dir = std::env::temp_dir().join(format!("corrosion-cache-hit-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let source = "let answer = 6 * 7;";
+        std::fs::write(dir.join("constants.corr"), source).unwrap();
+
+        let run = |edition: Edition| {
+            let tokens = Tokenizer::new("import \"constants.corr\" as c; c.answer;")
+                .tokenize()
+                .unwrap();
+            let program = Parser::new(tokens).parse().unwrap();
+            let mut interpreter = Interpreter::builder()
+                .current_directory(&dir)
+                .edition(edition)
+                .build();
+            interpreter.interpret_program_repl(&program).unwrap()
+        };
+
+        assert_eq!(run(Edition::E2026), Value::Int(42));
+
+        // A second import takes the cached exports instead of running the module
+        let key = Key::new(&dir.join("constants.corr"), source, Edition::E2026, true);
+        let mut exports = module_cache::lookup(&key).expect("the module should be cached");
+        exports.insert("answer".to_string(), Value::Int(0));
+        module_cache::store(key, exports);
+        assert_eq!(run(Edition::E2026), Value::Int(0));
+
+        // Another edition may read the source differently, so it runs the module
+        assert_eq!(run(Edition::E2025), Value::Int(42));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_module_purity_follows_the_syntax_tree() {
+        use crate::ast::Parser;
+        use crate::interpreter::module_cache::is_pure;
+        use crate::lexer::Tokenizer;
+
+        let pure = |source: &str| {
+            let tokens = Tokenizer::new(source).tokenize().unwrap();
+            is_pure(&Parser::new(tokens).parse().unwrap())
+        };
+
+        // Keywords in strings are not effects
+        assert!(pure("let help = \"print input random\";\nlet n = 1 + 2;"));
+        assert!(!pure("fn f(x) {\n    print(x)\n}"));
+        assert!(!pure("let roll = fn(n: Int) { random(n) + 1 };"));
+        assert!(!pure("import \"other.corr\" as other;"));
+    }
 }
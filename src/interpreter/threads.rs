@@ -0,0 +1,177 @@
+use super::{InterpreterResult, Value};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// Threads and channels a program has opened and not yet released, shared by
+/// every interpreter running it so they can all be cleaned up at the end
+pub type Handles = Arc<Mutex<Vec<Value>>>;
+
+/// Threads started with `spawn`, indexed by the id stored in `Value::Handle`.
+/// A slot is emptied once its thread has been joined.
+static THREADS: Mutex<Vec<Option<JoinHandle<InterpreterResult<Value>>>>> = Mutex::new(Vec::new());
+
+/// Stack for every thread an interpreter runs on. Each level of recursion in
+/// a Corrosion program takes several `interpret_expression` frames, which
+/// are large in debug builds, so the 8 MiB main thread stack runs out after
+/// a few dozen levels.
+pub const STACK_SIZE: usize = 64 * 1024 * 1024;
+
+/// How deeply calls may nest where the host must not crash, such as in an
+/// embedding or the REPL. It leaves room on a `STACK_SIZE` stack for the
+/// frames of large function bodies; debug builds use far more per level.
+pub const DEFAULT_MAX_CALL_DEPTH: usize = if cfg!(debug_assertions) { 100 } else { 1000 };
+
+/// Run `work` on a thread with a stack of `STACK_SIZE`, waiting for it to
+/// finish. A panic in `work` is passed on.
+pub fn with_stack<R: Send>(work: impl FnOnce() -> R + Send) -> R {
+    thread::scope(|scope| {
+        let thread = thread::Builder::new()
+            .stack_size(STACK_SIZE)
+            .spawn_scoped(scope, work)
+            .expect("failed to start the interpreter thread");
+        thread
+            .join()
+            .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+    })
+}
+
+/// Run `work` on a new OS thread and return its handle id
+pub fn spawn<F>(work: F) -> std::io::Result<usize>
+where
+    F: FnOnce() -> InterpreterResult<Value> + Send + 'static,
+{
+    let handle = thread::Builder::new().stack_size(STACK_SIZE).spawn(work)?;
+    let mut threads = THREADS.lock().unwrap_or_else(|e| e.into_inner());
+    threads.push(Some(handle));
+    Ok(threads.len() - 1)
+}
+
+/// Wait for a thread to finish. `None` if the handle was already joined.
+pub fn join(id: usize) -> Option<thread::Result<InterpreterResult<Value>>> {
+    // Take the handle out first so other threads can spawn while we wait
+    let handle = THREADS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get_mut(id)?
+        .take()?;
+    Some(handle.join())
+}
+
+/// Wait until a thread has finished, or until `interrupt` is set. `false` if
+/// interrupted, leaving the thread to be joined later.
+pub fn wait(id: usize, interrupt: Option<&AtomicBool>) -> bool {
+    // Without an interrupt to watch, `join` can block by itself
+    let Some(interrupt) = interrupt else {
+        return true;
+    };
+    loop {
+        let finished = THREADS
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(id)
+            .and_then(Option::as_ref)
+            .is_none_or(JoinHandle::is_finished);
+        if finished {
+            return true;
+        }
+        if interrupt.load(Ordering::Relaxed) {
+            return false;
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+type ChannelEnds = (Sender<Value>, Mutex<Receiver<Value>>);
+
+/// Channels created with `channel()`, indexed by the id in `Value::Channel`.
+/// Values are moved through the channel, so each receiver gets its own copy.
+/// A slot is emptied once its channel has been closed.
+static CHANNELS: Mutex<Vec<Option<Arc<ChannelEnds>>>> = Mutex::new(Vec::new());
+
+/// How often a blocked `recv` or `join` checks whether its channel has been
+/// closed or the program interrupted
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Create a channel and return its id
+pub fn channel() -> usize {
+    let (sender, receiver) = mpsc::channel();
+    let mut channels = CHANNELS.lock().unwrap_or_else(|e| e.into_inner());
+    channels.push(Some(Arc::new((sender, Mutex::new(receiver)))));
+    channels.len() - 1
+}
+
+fn channel_ends(id: usize) -> Option<Arc<ChannelEnds>> {
+    CHANNELS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(id)
+        .and_then(Option::clone)
+}
+
+/// Close a channel, waking anyone waiting on it. `false` if it was already
+/// closed.
+pub fn close(id: usize) -> bool {
+    CHANNELS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get_mut(id)
+        .and_then(Option::take)
+        .is_some()
+}
+
+/// Queue a value on a channel. `false` if the channel is closed.
+pub fn send(id: usize, value: Value) -> bool {
+    channel_ends(id).is_some_and(|ends| ends.0.send(value).is_ok())
+}
+
+/// Block until a value arrives on a channel. `None` if the channel is or
+/// becomes closed, or `interrupt` is set while waiting.
+pub fn recv(id: usize, interrupt: Option<&AtomicBool>) -> Option<Value> {
+    let ends = channel_ends(id)?;
+    let receiver = ends.1.lock().unwrap_or_else(|e| e.into_inner());
+    loop {
+        match receiver.recv_timeout(POLL_INTERVAL) {
+            Ok(value) => return Some(value),
+            Err(RecvTimeoutError::Timeout)
+                if channel_ends(id).is_some()
+                    && !interrupt.is_some_and(|interrupt| interrupt.load(Ordering::Relaxed)) => {}
+            Err(_) => return None,
+        }
+    }
+}
+
+/// Add the threads and channels inside `value` to `handles`
+pub fn collect_handles(value: &Value, handles: &mut Vec<Value>) {
+    match value {
+        Value::Handle(_) | Value::Channel(_) => handles.push(value.clone()),
+        Value::List(items) | Value::Tuple(items) => {
+            for item in items {
+                collect_handles(item, handles);
+            }
+        }
+        Value::Pair(first, second) => {
+            collect_handles(first, handles);
+            collect_handles(second, handles);
+        }
+        Value::LeftInject(inner) | Value::RightInject(inner) => collect_handles(inner, handles),
+        _ => {}
+    }
+}
+
+/// Close the channels, then join the threads. Closing first wakes threads
+/// waiting on the channels, so they can finish.
+pub fn release_all(handles: Vec<Value>) {
+    for handle in &handles {
+        if let Value::Channel(id) = handle {
+            close(*id);
+        }
+    }
+    for handle in handles {
+        if let Value::Handle(id) = handle {
+            let _ = join(id);
+        }
+    }
+}
@@ -1,18 +1,28 @@
+use crate::typechecker::Type;
+use num_bigint::BigInt;
+use std::cmp::Ordering;
+
 /// Runtime values in the Corrosion language
 #[derive(Debug, Clone, PartialEq)]
 pub enum Value {
     /// Integer value
     Int(i64),
+    /// Arbitrary-precision integer value
+    BigInt(BigInt),
     /// Boolean value
     Bool(bool),
     /// String value
     String(String),
+    /// Raw byte string value
+    Bytes(Vec<u8>),
     /// Unit value (void)
     Unit,
     /// List of values
     List(Vec<Value>),
     /// Pair of two values
     Pair(Box<Value>, Box<Value>),
+    /// Tuple of three or more values
+    Tuple(Vec<Value>),
     /// Function value (closure)
     Function {
         param: String,
@@ -23,13 +33,25 @@ pub enum Value {
     LeftInject(Box<Value>),
     /// Right injection of sum type
     RightInject(Box<Value>),
+    /// Native function from a `std:` module, with the arguments applied so far
+    Native {
+        module: String,
+        name: String,
+        arguments: Vec<Value>,
+    },
+    /// Handle to a thread started with `spawn`
+    Handle(usize),
+    /// Channel created with `channel()`
+    Channel(usize),
     /// Fixed point value for recursive functions
     FixedPoint { function: Box<Value> },
-    /// Module value for imports
+    /// Module value for imports and enum namespaces
     Module {
         name: String,
         exports: std::collections::HashMap<String, Value>,
     },
+    /// One variant of an enum, `Color.Red`
+    Enum { name: String, variant: String },
 }
 
 impl Value {
@@ -37,29 +59,100 @@ impl Value {
     pub fn type_name(&self) -> &'static str {
         match self {
             Value::Int(_) => "Int",
+            Value::BigInt(_) => "BigInt",
             Value::Bool(_) => "Bool",
             Value::String(_) => "String",
+            Value::Bytes(_) => "Bytes",
             Value::Unit => "Unit",
             Value::List(_) => "List",
             Value::Pair(_, _) => "Pair",
-            Value::Function { .. } => "Function",
+            Value::Tuple(_) => "Tuple",
+            Value::Function { .. } | Value::Native { .. } => "Function",
             Value::LeftInject(_) => "LeftInject",
             Value::RightInject(_) => "RightInject",
+            Value::Handle(_) => "Handle",
+            Value::Channel(_) => "Channel",
             Value::FixedPoint { .. } => "FixedPoint",
             Value::Module { .. } => "Module",
+            Value::Enum { .. } => "Enum",
         }
     }
 
-    /// Check if this value is truthy (for conditional expressions)
-    pub fn is_truthy(&self) -> bool {
+    /// The type the checker gives a binding of this value. Parts that
+    /// cannot be told from the value alone, such as a function's parameter
+    /// or the element of an empty list, are `Dyn`.
+    pub fn static_type(&self) -> Type {
+        let dyn_function = || Type::Function {
+            param: Box::new(Type::Dyn),
+            result: Box::new(Type::Dyn),
+        };
         match self {
-            Value::Bool(b) => *b,
-            Value::Unit => false,
-            Value::Int(n) => *n != 0,
-            Value::String(s) => !s.is_empty(),
-            Value::List(list) => !list.is_empty(),
-            Value::FixedPoint { .. } => true, // Fixed point functions are truthy
-            _ => true,                        // Other values are considered truthy
+            Value::Int(_) => Type::Int,
+            Value::BigInt(_) => Type::BigInt,
+            Value::Bool(_) => Type::Bool,
+            Value::String(_) => Type::String,
+            Value::Bytes(_) => Type::Bytes,
+            Value::Unit => Type::Unit,
+            Value::List(elements) => Type::List {
+                element: Box::new(elements.first().map_or(Type::Dyn, Value::static_type)),
+            },
+            Value::Pair(first, second) => Type::Pair {
+                first: Box::new(first.static_type()),
+                second: Box::new(second.static_type()),
+            },
+            Value::Tuple(elements) => Type::Tuple {
+                elements: elements.iter().map(Value::static_type).collect(),
+            },
+            Value::Function { .. } | Value::Native { .. } | Value::FixedPoint { .. } => {
+                dyn_function()
+            }
+            Value::LeftInject(value) => Type::Sum {
+                left: Box::new(value.static_type()),
+                right: Box::new(Type::Dyn),
+            },
+            Value::RightInject(value) => Type::Sum {
+                left: Box::new(Type::Dyn),
+                right: Box::new(value.static_type()),
+            },
+            Value::Handle(_) => Type::Handle {
+                result: Box::new(Type::Dyn),
+            },
+            Value::Channel(_) => Type::Channel {
+                element: Box::new(Type::Dyn),
+            },
+            Value::Enum { name, .. } => Type::Enum {
+                name: name.as_str().into(),
+            },
+            Value::Module { .. } => Type::Dyn,
+        }
+    }
+
+    /// Order two values of the same kind; `None` if they cannot be compared.
+    /// Lists, pairs and tuples are ordered lexicographically.
+    pub fn compare(&self, other: &Value) -> Option<Ordering> {
+        match (self, other) {
+            (Value::Int(l), Value::Int(r)) => Some(l.cmp(r)),
+            (Value::BigInt(l), Value::BigInt(r)) => Some(l.cmp(r)),
+            (Value::Bool(l), Value::Bool(r)) => Some(l.cmp(r)),
+            (Value::String(l), Value::String(r)) => Some(l.cmp(r)),
+            (Value::Bytes(l), Value::Bytes(r)) => Some(l.cmp(r)),
+            (Value::Unit, Value::Unit) => Some(Ordering::Equal),
+            (Value::List(l), Value::List(r)) | (Value::Tuple(l), Value::Tuple(r)) => {
+                for (l_elem, r_elem) in l.iter().zip(r.iter()) {
+                    match l_elem.compare(r_elem)? {
+                        Ordering::Equal => continue,
+                        ordering => return Some(ordering),
+                    }
+                }
+                Some(l.len().cmp(&r.len()))
+            }
+            (Value::Pair(l_first, l_second), Value::Pair(r_first, r_second)) => {
+                match l_first.compare(r_first)? {
+                    Ordering::Equal => l_second.compare(r_second),
+                    ordering => Some(ordering),
+                }
+            }
+            _ => None,
         }
     }
 
@@ -79,6 +172,15 @@ impl Value {
         }
     }
 
+    /// Convert to an arbitrary-precision integer, widening an `Int`
+    pub fn to_big_int(&self) -> Option<BigInt> {
+        match self {
+            Value::Int(n) => Some(BigInt::from(*n)),
+            Value::BigInt(n) => Some(n.clone()),
+            _ => None,
+        }
+    }
+
     /// Convert to a string value if possible
     pub fn to_string(&self) -> Option<&String> {
         match self {
@@ -92,8 +194,10 @@ impl std::fmt::Display for Value {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Value::Int(n) => write!(f, "{}", n),
+            Value::BigInt(n) => write!(f, "{}", n),
             Value::Bool(b) => write!(f, "{}", b),
             Value::String(s) => write!(f, "\"{}\"", s),
+            Value::Bytes(bytes) => write!(f, "b\"{}\"", bytes.escape_ascii()),
             Value::Unit => write!(f, "()"),
             Value::List(elements) => {
                 write!(f, "[")?;
@@ -108,21 +212,85 @@ impl std::fmt::Display for Value {
             Value::Pair(first, second) => {
                 write!(f, "({}, {})", first, second)
             }
+            Value::Tuple(elements) => {
+                write!(f, "(")?;
+                for (i, elem) in elements.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", elem)?;
+                }
+                write!(f, ")")
+            }
             Value::Function { param, .. } => {
                 write!(f, "<function {}>", param)
             }
+            Value::Native { name, .. } => {
+                write!(f, "<native {}>", name)
+            }
             Value::LeftInject(value) => {
                 write!(f, "Left({})", value)
             }
             Value::RightInject(value) => {
                 write!(f, "Right({})", value)
             }
+            Value::Handle(id) => {
+                write!(f, "<thread {}>", id)
+            }
+            Value::Channel(id) => {
+                write!(f, "<channel {}>", id)
+            }
             Value::FixedPoint { .. } => {
                 write!(f, "<recursive function>")
             }
             Value::Module { name, .. } => {
                 write!(f, "<module {}>", name)
             }
+            Value::Enum { name, variant } => write!(f, "{}.{}", name, variant),
+        }
+    }
+}
+
+// Values contain no floats, so equality is reflexive and `Value` can be used as
+// a map key. Scalars, `Unit` and the empty list are stored inline without any
+// allocation, so there is nothing to gain from interning them.
+impl Eq for Value {}
+
+impl std::hash::Hash for Value {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            Value::Int(n) => n.hash(state),
+            Value::BigInt(n) => n.hash(state),
+            Value::Bool(b) => b.hash(state),
+            Value::String(s) => s.hash(state),
+            Value::Bytes(bytes) => bytes.hash(state),
+            Value::Unit => {}
+            Value::List(elements) | Value::Tuple(elements) => elements.hash(state),
+            Value::Pair(first, second) => {
+                first.hash(state);
+                second.hash(state);
+            }
+            Value::LeftInject(value) | Value::RightInject(value) => value.hash(state),
+            Value::Native {
+                module,
+                name,
+                arguments,
+            } => {
+                module.hash(state);
+                name.hash(state);
+                arguments.hash(state);
+            }
+            Value::Handle(id) | Value::Channel(id) => id.hash(state),
+            // Closures and modules hash by their name only; equal values still
+            // hash equally, which is all `Hash` requires
+            Value::Function { param, .. } => param.hash(state),
+            Value::FixedPoint { function } => function.hash(state),
+            Value::Module { name, .. } => name.hash(state),
+            Value::Enum { name, variant } => {
+                name.hash(state);
+                variant.hash(state);
+            }
         }
     }
 }
@@ -1,10 +1,10 @@
 #[cfg(test)]
+#[allow(clippy::module_inception)]
 mod comment_tests {
     use crate::lexer::{Token, TokenizeError, Tokenizer};
 
     fn tokenize_input(input: &str) -> Result<Vec<Token>, TokenizeError> {
-        let mut tokenizer = Tokenizer::new(input);
-        let tokens_with_span = tokenizer.tokenize(input)?;
+        let tokens_with_span = Tokenizer::new(input).tokenize()?;
         Ok(tokens_with_span.into_iter().map(|t| t.token).collect())
     }
 
@@ -96,4 +96,28 @@ mod comment_tests {
             ]
         );
     }
+
+    #[test]
+    fn test_multi_byte_characters_in_comments_and_strings() {
+        let tokens = tokenize_input("/* héllo → */ \"\\é\"; // ünïcode").unwrap();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::StringLiteral("é".to_string()),
+                Token::Semicolon,
+                Token::Eof
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unterminated_multi_line_comment() {
+        let err = tokenize_input("let x = 1;\n/* never\n closed").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Unterminated block comment starting at line 2, column 1: missing closing */"
+        );
+        let span = err.span();
+        assert_eq!((span.start, span.end), (11, 13));
+    }
 }
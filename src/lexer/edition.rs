@@ -0,0 +1,95 @@
+//! Language editions. A program is read by the rules of the edition it was
+//! written for, so a change that would break older programs, such as a new
+//! keyword, only applies from the edition that introduced it.
+
+use std::fmt;
+
+/// A version of the language's syntax, selected with `--edition` or the
+/// `edition` key of `corrosion.toml`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub enum Edition {
+    /// The first edition, the language as it was before editions
+    E2025,
+    /// Reserves the keywords added since, such as `spawn`, `return` and
+    /// `with`, makes `&&` and `||` take only Bools, and requires types that
+    /// cannot be inferred to be written
+    #[default]
+    E2026,
+}
+
+/// Keywords added after the first edition, with the edition that reserved
+/// them. In older editions they are ordinary names.
+const NEW_KEYWORDS: &[(&str, Edition)] = &[
+    ("Channel", Edition::E2026),
+    ("Handle", Edition::E2026),
+    ("byteAt", Edition::E2026),
+    ("channel", Edition::E2026),
+    ("const", Edition::E2026),
+    ("divmod", Edition::E2026),
+    ("enum", Edition::E2026),
+    ("export", Edition::E2026),
+    ("fromBytes", Edition::E2026),
+    ("graphemes", Edition::E2026),
+    ("input", Edition::E2026),
+    ("is", Edition::E2026),
+    ("join", Edition::E2026),
+    ("random", Edition::E2026),
+    ("readAll", Edition::E2026),
+    ("readFileBytes", Edition::E2026),
+    ("recv", Edition::E2026),
+    ("return", Edition::E2026),
+    ("safeDiv", Edition::E2026),
+    ("send", Edition::E2026),
+    ("sleep", Edition::E2026),
+    ("spawn", Edition::E2026),
+    ("toBytes", Edition::E2026),
+    ("toInt", Edition::E2026),
+    ("with", Edition::E2026),
+];
+
+impl Edition {
+    /// Every edition, oldest first
+    pub const ALL: &[Edition] = &[Edition::E2025, Edition::E2026];
+
+    /// The edition named `name`, such as `"2025"`
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .find(|edition| edition.name() == name)
+            .copied()
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Edition::E2025 => "2025",
+            Edition::E2026 => "2026",
+        }
+    }
+
+    /// Whether `keyword` is reserved in this edition rather than a name
+    pub fn reserves(self, keyword: &str) -> bool {
+        NEW_KEYWORDS
+            .iter()
+            .find(|(word, _)| *word == keyword)
+            .is_none_or(|(_, since)| self >= *since)
+    }
+
+    /// Whether `&&` and `||` treat other values as true or false, with `0`,
+    /// `""`, `[]` and `()` false, as they did before the 2026 edition
+    pub fn has_truthiness(self) -> bool {
+        self < Edition::E2026
+    }
+
+    /// Whether a declaration or parameter whose type cannot be fully
+    /// inferred must be annotated, or written `Dyn`, as it must from the
+    /// 2026 edition unless `--no-strict` is given
+    pub fn requires_precise_types(self) -> bool {
+        self >= Edition::E2026
+    }
+}
+
+impl fmt::Display for Edition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
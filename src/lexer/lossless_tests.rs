@@ -0,0 +1,80 @@
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod lossless_tests {
+    use crate::lexer::{LosslessToken, Token, Tokenizer, TriviaKind};
+
+    fn tokenize_lossless(input: &str) -> Vec<LosslessToken> {
+        Tokenizer::new(input).tokenize_lossless().unwrap()
+    }
+
+    fn source_text(tokens: &[LosslessToken]) -> String {
+        tokens.iter().map(LosslessToken::source_text).collect()
+    }
+
+    #[test]
+    fn test_lossless_tokens_reproduce_the_input() {
+        let inputs = [
+            "",
+            "   \n\t",
+            "let x = 42;",
+            "  let x = 42; // answer\n\n/* block\n comment */ print(x);\n",
+            "fn f(n: Int) -> Int {\r\n    n + 1 /* inline */\r\n}\r\n",
+            "let s = \"héllo → wörld\"; // ünïcode\n",
+            "// only a comment",
+        ];
+        for input in inputs {
+            assert_eq!(source_text(&tokenize_lossless(input)), input);
+        }
+    }
+
+    #[test]
+    fn test_lossless_tokens_match_tokenize() {
+        let input = "let xs = [1, 2] ++ tail([3]); // done\n";
+        let tokens = Tokenizer::new(input).tokenize().unwrap();
+        let lossless = tokenize_lossless(input);
+        assert_eq!(lossless.len(), tokens.len());
+        for (lossless, token) in lossless.iter().zip(&tokens) {
+            assert_eq!(lossless.token, token.token);
+            assert_eq!(lossless.span, token.span);
+            assert_eq!(lossless.text, &input[token.span.start..token.span.end]);
+        }
+    }
+
+    #[test]
+    fn test_trivia_attachment() {
+        let input = "// header\nlet x = 1; // one\n\n/* two */ let y = 2;\n";
+        let tokens = tokenize_lossless(input);
+
+        // A comment on its own line leads the next token
+        assert_eq!(tokens[0].token, Token::Let);
+        let kinds: Vec<TriviaKind> = tokens[0].leading.iter().map(|t| t.kind).collect();
+        assert_eq!(kinds, vec![TriviaKind::LineComment, TriviaKind::Whitespace]);
+        assert_eq!(tokens[0].leading[0].text, "// header");
+
+        // A comment after a token on the same line trails it
+        assert_eq!(tokens[4].token, Token::Semicolon);
+        let trailing: Vec<&str> = tokens[4].trailing.iter().map(|t| t.text.as_str()).collect();
+        assert_eq!(trailing, vec![" ", "// one"]);
+
+        let second_let = &tokens[5];
+        assert_eq!(second_let.token, Token::Let);
+        let leading: Vec<&str> = second_let.leading.iter().map(|t| t.text.as_str()).collect();
+        assert_eq!(leading, vec!["\n\n", "/* two */", " "]);
+        assert_eq!(second_let.leading[1].span.line, 4);
+        assert_eq!(second_let.leading[1].span.column, 1);
+
+        // Whatever is left at the end leads `Eof`
+        let eof = tokens.last().unwrap();
+        assert_eq!(eof.token, Token::Eof);
+        assert_eq!(eof.text, "");
+        assert_eq!(tokens[tokens.len() - 2].trailing, vec![]);
+        assert_eq!(eof.leading.len(), 1);
+        assert_eq!(eof.leading[0].text, "\n");
+    }
+
+    #[test]
+    fn test_lossless_rejects_what_tokenize_rejects() {
+        let input = "let s = \"unterminated";
+        assert!(Tokenizer::new(input).tokenize_lossless().is_err());
+    }
+}
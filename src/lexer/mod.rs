@@ -1,3 +1,4 @@
+pub mod edition;
 pub mod tokenizer;
 pub mod tokens;
 
@@ -7,5 +8,9 @@ mod tests;
 #[cfg(test)]
 mod comment_tests;
 
-pub use tokenizer::{TokenizeError, Tokenizer};
-pub use tokens::{Span, Token, TokenWithSpan};
+#[cfg(test)]
+mod lossless_tests;
+
+pub use edition::Edition;
+pub use tokenizer::{LenientWarning, TokenizeError, Tokenizer};
+pub use tokens::{LosslessToken, Span, Token, TokenWithSpan, Trivia, TriviaKind};
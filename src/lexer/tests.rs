@@ -1,10 +1,11 @@
 #[cfg(test)]
+#[allow(clippy::module_inception)]
 mod tests {
+    use crate::lexer::tokens::KEYWORDS;
     use crate::lexer::{Token, TokenizeError, Tokenizer};
 
     fn tokenize_input(input: &str) -> Result<Vec<Token>, TokenizeError> {
-        let mut tokenizer = Tokenizer::new(input);
-        let tokens_with_span = tokenizer.tokenize(input)?;
+        let tokens_with_span = Tokenizer::new(input).tokenize()?;
         Ok(tokens_with_span.into_iter().map(|t| t.token).collect())
     }
 
@@ -236,8 +237,7 @@ mod tests {
 
     #[test]
     fn test_span_information() {
-        let mut tokenizer = Tokenizer::new("");
-        let tokens_with_span = tokenizer.tokenize("let x = 42").unwrap();
+        let tokens_with_span = Tokenizer::new("let x = 42").tokenize().unwrap();
 
         // Check that spans are properly calculated
         assert_eq!(tokens_with_span.len(), 5); // let, x, =, 42, EOF
@@ -283,8 +283,7 @@ mod tests {
 
     #[test]
     fn test_line_and_column_calculation() {
-        let mut tokenizer = Tokenizer::new("");
-        let tokens_with_span = tokenizer.tokenize("let x = 1;\nlet y = 2;").unwrap();
+        let tokens_with_span = Tokenizer::new("let x = 1;\nlet y = 2;").tokenize().unwrap();
 
         // First line tokens should have line 1
         assert_eq!(tokens_with_span[0].span.line, 1); // let
@@ -298,21 +297,19 @@ mod tests {
 
     #[test]
     fn test_error_on_invalid_character() {
-        let mut tokenizer = Tokenizer::new("");
-        let result = tokenizer.tokenize("let x = @");
+        let result = Tokenizer::new("let x = @").tokenize();
         assert!(result.is_err());
 
-        if let Err(TokenizeError::ParseError(msg)) = result {
-            assert!(msg.contains("Parse error"));
-        } else {
-            panic!("Expected ParseError");
-        }
+        let Err(TokenizeError::InvalidCharacter { ch, span }) = result else {
+            panic!("Expected InvalidCharacter, got {:?}", result);
+        };
+        assert_eq!(ch, '@');
+        assert_eq!((span.line, span.column, span.end - span.start), (1, 9, 1));
     }
 
     #[test]
     fn test_error_on_invalid_input() {
-        let mut tokenizer = Tokenizer::new("");
-        let result = tokenizer.tokenize("let x # invalid");
+        let result = Tokenizer::new("let x # invalid").tokenize();
         assert!(result.is_err());
     }
 
@@ -480,4 +477,345 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn test_composition_operators() {
+        let tokens = tokenize_input("f >> g << h").unwrap();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Identifier("f".to_string()),
+                Token::ComposeForward,
+                Token::Identifier("g".to_string()),
+                Token::ComposeBackward,
+                Token::Identifier("h".to_string()),
+                Token::Eof
+            ]
+        );
+
+        // Comparison operators are still recognized on their own
+        let tokens = tokenize_input("a >= b > c").unwrap();
+        assert_eq!(tokens[1], Token::GreaterThanEqual);
+        assert_eq!(tokens[3], Token::GreaterThan);
+    }
+
+    #[test]
+    fn test_double_colon_operator() {
+        let tokens = tokenize_input("x :: xs").unwrap();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Identifier("x".to_string()),
+                Token::DoubleColon,
+                Token::Identifier("xs".to_string()),
+                Token::Eof
+            ]
+        );
+
+        // A single colon is still a type annotation separator
+        let tokens = tokenize_input("x: Int").unwrap();
+        assert_eq!(tokens[1], Token::Colon);
+    }
+
+    #[test]
+    fn test_append_operator() {
+        let tokens = tokenize_input("xs ++ ys + 1").unwrap();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Identifier("xs".to_string()),
+                Token::Append,
+                Token::Identifier("ys".to_string()),
+                Token::Plus,
+                Token::Number(1),
+                Token::Eof
+            ]
+        );
+    }
+
+    #[test]
+    fn test_is_keyword() {
+        let tokens = tokenize_input("x is Int isolated").unwrap();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Identifier("x".to_string()),
+                Token::Is,
+                Token::Int,
+                Token::Identifier("isolated".to_string()),
+                Token::Eof
+            ]
+        );
+    }
+
+    #[test]
+    fn test_division_builtin_keywords() {
+        let tokens = tokenize_input("divmod safeDiv").unwrap();
+        assert_eq!(tokens, vec![Token::DivMod, Token::SafeDiv, Token::Eof]);
+    }
+
+    #[test]
+    fn test_prefixed_and_separated_integer_literals() {
+        let tokens = tokenize_input("0xFF 0o777 0b1010 1_000_000 0x7fff_ffff").unwrap();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Number(255),
+                Token::Number(511),
+                Token::Number(10),
+                Token::Number(1_000_000),
+                Token::Number(0x7fff_ffff),
+                Token::Eof
+            ]
+        );
+    }
+
+    #[test]
+    fn test_integer_literal_overflow() {
+        let err = tokenize_input("let x = 0x1_0000_0000_0000_0000;").unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("'0x1_0000_0000_0000_0000' out of range at line 1, column 9")
+        );
+        let TokenizeError::NumberTooLarge { literal, span } = err else {
+            panic!("Expected NumberTooLarge, got {:?}", err);
+        };
+        assert_eq!(literal, "0x1_0000_0000_0000_0000");
+        assert_eq!((span.start, span.end), (8, 31));
+
+        assert!(tokenize_input("9223372036854775808").is_err());
+        assert_eq!(
+            tokenize_input("9223372036854775807").unwrap(),
+            vec![Token::Number(i64::MAX), Token::Eof]
+        );
+    }
+
+    #[test]
+    fn test_big_integer_literals() {
+        let tokens = tokenize_input("12n 0xFF_FFFF_FFFF_FFFF_FFFFn").unwrap();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::BigNumber(12.into()),
+                Token::BigNumber(num_bigint::BigInt::from(u128::MAX >> 56)),
+                Token::Eof
+            ]
+        );
+    }
+
+    #[test]
+    fn test_bytes_literal() {
+        let tokens = tokenize_input(r#"b"A\x00\xff\n" bytes"#).unwrap();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::BytesLiteral(vec![b'A', 0x00, 0xff, b'\n']),
+                Token::Identifier("bytes".to_string()),
+                Token::Eof
+            ]
+        );
+    }
+
+    #[test]
+    fn test_holes() {
+        let tokens = tokenize_input("? ??total (?)").unwrap();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Hole(None),
+                Token::Hole(Some("total".to_string())),
+                Token::LeftParen,
+                Token::Hole(None),
+                Token::RightParen,
+                Token::Eof
+            ]
+        );
+    }
+
+    #[test]
+    fn test_spans_count_characters_after_multi_byte_text() {
+        let input = "let s = \"héllo\";\n  \"日本\" + x";
+        let tokens = Tokenizer::new(input).tokenize().unwrap();
+
+        // `;` sits after a two-byte character
+        let semicolon = &tokens[4];
+        assert_eq!(semicolon.token, Token::Semicolon);
+        assert_eq!((semicolon.span.start, semicolon.span.char_start), (16, 15));
+        assert_eq!((semicolon.span.line, semicolon.span.column), (1, 16));
+
+        let japanese = &tokens[5];
+        assert_eq!(japanese.span.end - japanese.span.start, 8);
+        assert_eq!(japanese.span.char_end - japanese.span.char_start, 4);
+        assert_eq!(&input[japanese.span.start..japanese.span.end], "\"日本\"");
+
+        // `x` follows two three-byte characters on the second line
+        let x = &tokens[7];
+        assert_eq!((x.span.line, x.span.column), (2, 10));
+        assert_eq!(x.span.char_start, x.span.char_end - 1);
+        assert_eq!(x.span.char_start, input.chars().count() - 1);
+    }
+
+    #[test]
+    fn test_keywords_read_back_as_the_words_they_are_written_as() {
+        for word in ["let", "head", "range", "safeDiv", "Int", "return", "of"] {
+            let tokens = Tokenizer::new(word).tokenize().unwrap();
+            assert_eq!(tokens[0].token.keyword(), Some(word));
+            assert_eq!(Token::from_keyword(word), Some(tokens[0].token.clone()));
+        }
+        assert_eq!(Token::from_keyword("head_"), None);
+        assert_eq!(Token::Identifier("x".to_string()).keyword(), None);
+    }
+
+    #[test]
+    fn test_raw_string_literal() {
+        let tokens = tokenize_input("r\"C:\\new\" r#\"say \"hi\"\"# \"two\nlines\" r").unwrap();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::StringLiteral("C:\\new".to_string()),
+                Token::StringLiteral("say \"hi\"".to_string()),
+                Token::StringLiteral("two\nlines".to_string()),
+                Token::Identifier("r".to_string()),
+                Token::Eof
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unterminated_strings_report_where_they_open() {
+        for (input, what, opening, closing) in [
+            ("let s = \"abc", "string", "\"", "\""),
+            ("[b\"ab\\\"]", "byte string", "b\"", "\""),
+            ("x;\n  r##\"a\"#", "raw string", "r##\"", "\"##"),
+        ] {
+            let err = Tokenizer::new(input).tokenize().unwrap_err();
+            let TokenizeError::Unterminated {
+                what: found,
+                closing: expected,
+                span,
+            } = &err
+            else {
+                panic!("Expected Unterminated, got {:?}", err);
+            };
+            assert_eq!((*found, expected.as_str()), (what, closing), "{}", input);
+            assert_eq!(&input[span.start..span.end], opening, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_invalid_byte_escape_reports_the_escape() {
+        for (input, escape) in [("b\"\\xZZ\"", "\\xZZ"), ("x = b\"ok\\x4\"", "\\x4")] {
+            let err = Tokenizer::new(input).tokenize().unwrap_err();
+            let TokenizeError::InvalidEscape {
+                escape: found,
+                span,
+            } = &err
+            else {
+                panic!("Expected InvalidEscape, got {:?}", err);
+            };
+            assert_eq!(found, escape, "{}", input);
+            assert_eq!(&input[span.start..span.end], escape, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_keywords_end_at_word_boundaries() {
+        for (word, token) in KEYWORDS {
+            assert_eq!(
+                tokenize_input(word).unwrap(),
+                vec![token.clone(), Token::Eof]
+            );
+            // A keyword at the start of a longer name does not split it
+            for name in [
+                format!("{}s", word),
+                format!("{}_1", word),
+                format!("my{}", word),
+            ] {
+                assert_eq!(
+                    tokenize_input(&name).unwrap(),
+                    vec![Token::Identifier(name.clone()), Token::Eof]
+                );
+            }
+        }
+
+        let tokens = tokenize_input("Interface Integer letter iffy fnord Boolean").unwrap();
+        assert!(
+            tokens[..6]
+                .iter()
+                .all(|token| matches!(token, Token::Identifier(_)))
+        );
+    }
+
+    #[test]
+    fn test_lenient_tokenizing_reads_misspelled_keywords() {
+        let source = "Let x = TRUE; if x { 1 } esle { retrun_ }";
+        let (tokens, warnings) = Tokenizer::new(source).tokenize_lenient().unwrap();
+        let tokens: Vec<Token> = tokens.into_iter().map(|t| t.token).collect();
+        assert_eq!(tokens[0], Token::Let);
+        assert_eq!(tokens[3], Token::True);
+        assert_eq!(tokens[10], Token::Else);
+        assert_eq!(tokens[12], Token::Identifier("retrun_".to_string()));
+
+        let read: Vec<(&str, &str)> = warnings
+            .iter()
+            .map(|w| (w.written.as_str(), w.keyword))
+            .collect();
+        assert_eq!(
+            read,
+            vec![("Let", "let"), ("TRUE", "true"), ("esle", "else")]
+        );
+        assert_eq!(warnings[0].code(), "W0100");
+        assert_eq!(
+            warnings[2].to_string(),
+            "'esle' at line 1, column 26 is read as 'else'"
+        );
+
+        // Without lenient mode they stay names, and type names are never folded
+        assert_eq!(
+            tokenize_input("True").unwrap()[0],
+            Token::Identifier("True".to_string())
+        );
+        let (tokens, warnings) = Tokenizer::new("int string").tokenize_lenient().unwrap();
+        assert!(warnings.is_empty());
+        assert_eq!(tokens[0].token, Token::Identifier("int".to_string()));
+    }
+
+    #[test]
+    fn test_editions_reserve_their_own_keywords() {
+        use crate::lexer::Edition;
+
+        let read = |edition: Edition| -> Vec<Token> {
+            Tokenizer::new("with input readAll spawn")
+                .with_edition(edition)
+                .tokenize()
+                .unwrap()
+                .into_iter()
+                .map(|t| t.token)
+                .collect()
+        };
+        assert_eq!(
+            read(Edition::E2026),
+            vec![
+                Token::With,
+                Token::Input,
+                Token::ReadAll,
+                Token::Spawn,
+                Token::Eof
+            ]
+        );
+        // Keywords added later are names in the first edition
+        assert_eq!(
+            read(Edition::E2025),
+            vec![
+                Token::Identifier("with".to_string()),
+                Token::Identifier("input".to_string()),
+                Token::Identifier("readAll".to_string()),
+                Token::Identifier("spawn".to_string()),
+                Token::Eof
+            ]
+        );
+        assert_eq!(Edition::from_name("2025"), Some(Edition::E2025));
+        assert_eq!(Edition::from_name("2024"), None);
+        assert_eq!(Edition::default(), *Edition::ALL.last().unwrap());
+    }
 }
@@ -1,107 +1,494 @@
 use nom::{
     IResult, Parser,
     branch::alt,
-    bytes::complete::{tag, take_while},
-    character::complete::{alpha1, alphanumeric1, char, digit1, multispace0},
-    combinator::{recognize, value},
+    bytes::complete::{tag, take_while, take_while1},
+    character::complete::{alpha1, alphanumeric1, char, digit1, multispace0, satisfy},
+    combinator::{not, recognize, value},
     multi::many0,
-    sequence::pair,
+    sequence::{pair, preceded, terminated},
 };
 
-use super::tokens::{Span, Token, TokenWithSpan};
+use num_bigint::BigInt;
+
+use super::edition::Edition;
+use super::tokens::{LosslessToken, Span, Token, TokenWithSpan, Trivia, TriviaKind};
 
 #[derive(Debug, Clone)]
 pub enum TokenizeError {
-    ParseError(String),
+    /// A character that cannot start any token
+    InvalidCharacter { ch: char, span: Span },
+    /// An integer literal that does not fit in an `Int`. The span covers the
+    /// literal.
+    NumberTooLarge { literal: String, span: Span },
+    /// A string or block comment still open at the end of the input. The
+    /// span covers its opening delimiter.
+    Unterminated {
+        what: &'static str,
+        closing: String,
+        span: Span,
+    },
+    /// A `\x` escape in a byte string not followed by two hex digits. The
+    /// span covers the escape.
+    InvalidEscape { escape: String, span: Span },
+}
+
+impl TokenizeError {
+    /// Stable code identifying the kind of error; see `--explain`
+    pub fn code(&self) -> &'static str {
+        match self {
+            TokenizeError::InvalidCharacter { .. }
+            | TokenizeError::NumberTooLarge { .. }
+            | TokenizeError::Unterminated { .. }
+            | TokenizeError::InvalidEscape { .. } => "E0100",
+        }
+    }
+
+    pub fn span(&self) -> &Span {
+        match self {
+            TokenizeError::InvalidCharacter { span, .. }
+            | TokenizeError::NumberTooLarge { span, .. }
+            | TokenizeError::Unterminated { span, .. }
+            | TokenizeError::InvalidEscape { span, .. } => span,
+        }
+    }
 }
 
 impl std::fmt::Display for TokenizeError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
-            TokenizeError::ParseError(msg) => write!(f, "Parse error: {}", msg),
+            TokenizeError::InvalidCharacter { ch, span } => write!(
+                f,
+                "Unexpected character {:?} at line {}, column {}",
+                ch, span.line, span.column
+            ),
+            TokenizeError::NumberTooLarge { literal, span } => write!(
+                f,
+                "Integer literal '{}' out of range at line {}, column {}",
+                literal, span.line, span.column
+            ),
+            TokenizeError::Unterminated {
+                what,
+                closing,
+                span,
+            } => write!(
+                f,
+                "Unterminated {} starting at line {}, column {}: missing closing {}",
+                what, span.line, span.column, closing
+            ),
+            TokenizeError::InvalidEscape { escape, span } => write!(
+                f,
+                "Invalid escape '{}' in byte string at line {}, column {}: expected two hex digits",
+                escape, span.line, span.column
+            ),
         }
     }
 }
 
 impl std::error::Error for TokenizeError {}
 
-pub struct Tokenizer;
+/// A word that lenient mode read as a keyword although it is spelled
+/// differently, such as `True` for `true`
+#[derive(Debug, Clone, PartialEq)]
+pub struct LenientWarning {
+    pub written: String,
+    pub keyword: &'static str,
+    pub span: Span,
+}
 
-impl Tokenizer {
-    pub fn new(_input: &str) -> Self {
-        Self
+impl LenientWarning {
+    /// Stable code identifying the kind of warning; see `--explain`
+    pub fn code(&self) -> &'static str {
+        "W0100"
     }
+}
 
-    pub fn tokenize(&mut self, input: &str) -> Result<Vec<TokenWithSpan>, TokenizeError> {
-        match parse_tokens(input) {
-            Ok((remaining, tokens)) => {
-                if remaining.is_empty() {
-                    Ok(tokens)
-                } else {
-                    Err(TokenizeError::ParseError(format!(
-                        "Parse error: Unexpected remaining input: '{}'",
-                        remaining
-                    )))
-                }
+impl std::fmt::Display for LenientWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "'{}' at line {}, column {} is read as '{}'",
+            self.written, self.span.line, self.span.column, self.keyword
+        )
+    }
+}
+
+/// Keywords lenient mode accepts in any mix of upper and lower case
+const CASELESS_KEYWORDS: &[&str] = &[
+    "let", "const", "fn", "return", "if", "else", "for", "in", "case", "of", "true", "false",
+    "import", "export", "from", "as", "enum",
+];
+
+/// Misspellings lenient mode reads as the keyword they were meant to be
+const KEYWORD_TYPOS: &[(&str, &str)] = &[
+    ("esle", "else"),
+    ("retrun", "return"),
+    ("reutrn", "return"),
+    ("ture", "true"),
+    ("treu", "true"),
+    ("flase", "false"),
+    ("fasle", "false"),
+    ("cosnt", "const"),
+];
+
+/// The keyword lenient mode reads `word` as, if any
+fn forgiven_keyword(word: &str) -> Option<&'static str> {
+    if let Some((_, keyword)) = KEYWORD_TYPOS.iter().find(|(typo, _)| *typo == word) {
+        return Some(keyword);
+    }
+    let lower = word.to_ascii_lowercase();
+    CASELESS_KEYWORDS
+        .iter()
+        .find(|keyword| **keyword == lower)
+        .copied()
+}
+
+/// Splits one source text into tokens
+pub struct Tokenizer<'a> {
+    input: &'a str,
+    edition: Edition,
+}
+
+impl<'a> Tokenizer<'a> {
+    pub fn new(input: &'a str) -> Self {
+        Self {
+            input,
+            edition: Edition::default(),
+        }
+    }
+
+    /// Read the input by the rules of `edition` instead of the latest one
+    pub fn with_edition(mut self, edition: Edition) -> Self {
+        self.edition = edition;
+        self
+    }
+
+    pub fn tokenize(&self) -> Result<Vec<TokenWithSpan>, TokenizeError> {
+        let input = self.input;
+        let (remaining, mut tokens) = parse_tokens(input);
+        let unlexed = remaining.trim_start();
+        let Some(ch) = unlexed.chars().next() else {
+            self.apply_edition(&mut tokens);
+            return Ok(tokens);
+        };
+        let start = input.len() - unlexed.len();
+
+        if let Some(escape_start) = invalid_byte_escape(unlexed) {
+            let escape_start = start + escape_start;
+            let escape: String = input[escape_start..].chars().take(4).collect();
+            let escape = escape.trim_end_matches('"');
+            Err(TokenizeError::InvalidEscape {
+                escape: escape.to_string(),
+                span: Span::locate(input, escape_start, escape_start + escape.len()),
+            })
+        } else if let Some((what, opening, closing)) = unterminated(unlexed) {
+            Err(TokenizeError::Unterminated {
+                what,
+                closing,
+                span: Span::locate(input, start, start + opening),
+            })
+        } else if ch.is_ascii_digit() {
+            // Numbers only fail to lex when they do not fit in an Int
+            let literal: &str = unlexed
+                .split(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+                .next()
+                .unwrap_or(unlexed);
+            Err(TokenizeError::NumberTooLarge {
+                literal: literal.to_string(),
+                span: Span::locate(input, start, start + literal.len()),
+            })
+        } else {
+            Err(TokenizeError::InvalidCharacter {
+                ch,
+                span: Span::locate(input, start, start + ch.len_utf8()),
+            })
+        }
+    }
+
+    /// The tokens of the input up to the first text that does not lex, such
+    /// as a string that is not closed yet, without the `Eof`. For tools that
+    /// work on unfinished input, like highlighting a line as it is typed.
+    pub fn tokenize_prefix(&self) -> Vec<TokenWithSpan> {
+        let (_, mut tokens) = parse_tokens(self.input);
+        tokens.pop();
+        self.apply_edition(&mut tokens);
+        tokens
+    }
+
+    /// Read keywords newer than the edition as the names they are there
+    fn apply_edition(&self, tokens: &mut [TokenWithSpan]) {
+        for token in tokens {
+            if let Some(word) = token.token.keyword()
+                && !self.edition.reserves(word)
+            {
+                token.token = Token::Identifier(word.to_string());
             }
-            Err(e) => Err(TokenizeError::ParseError(format!("Parse error: {}", e))),
         }
     }
+
+    /// Tokenize for beginners: keywords written in the wrong case or with a
+    /// common typo are read as the keyword, with a warning for each instead
+    /// of the error the parser would give for a stray name
+    pub fn tokenize_lenient(
+        &self,
+    ) -> Result<(Vec<TokenWithSpan>, Vec<LenientWarning>), TokenizeError> {
+        let mut tokens = self.tokenize()?;
+        let mut warnings = Vec::new();
+
+        for token in &mut tokens {
+            let Token::Identifier(word) = &token.token else {
+                continue;
+            };
+            if let Some(keyword) = forgiven_keyword(word) {
+                warnings.push(LenientWarning {
+                    written: word.clone(),
+                    keyword,
+                    span: token.span.clone(),
+                });
+                token.token = Token::from_keyword(keyword).expect("lenient words are keywords");
+            }
+        }
+
+        Ok((tokens, warnings))
+    }
+
+    /// Tokenize keeping whitespace and comments, for tools that need to
+    /// reproduce or edit the source exactly. Accepts the same inputs as
+    /// `tokenize`, and the tokens are the same, ending with `Eof`.
+    pub fn tokenize_lossless(&self) -> Result<Vec<LosslessToken>, TokenizeError> {
+        let input = self.input;
+        let tokens = self.tokenize()?;
+        let mut lossless: Vec<LosslessToken> = Vec::with_capacity(tokens.len());
+        let mut gap_start = 0;
+
+        for TokenWithSpan { token, span } in tokens {
+            let mut leading = split_trivia(input, gap_start, span.start);
+            // Trivia up to the end of the previous token's line stays with it
+            if let Some(previous) = lossless.last_mut() {
+                let same_line = leading
+                    .iter()
+                    .position(|trivia| {
+                        trivia.kind == TriviaKind::Whitespace && trivia.text.contains('\n')
+                    })
+                    .unwrap_or(leading.len());
+                previous.trailing = leading.drain(..same_line).collect();
+            }
+            gap_start = span.end;
+            lossless.push(LosslessToken {
+                token,
+                text: input[span.start..span.end].to_string(),
+                span,
+                leading,
+                trailing: Vec::new(),
+            });
+        }
+
+        Ok(lossless)
+    }
 }
 
-// Helper function to calculate line and column from position
-fn calculate_position(input: &str, pos: usize) -> (usize, usize) {
-    let prefix = &input[..pos.min(input.len())];
-    let line = prefix.matches('\n').count() + 1;
-    let column = prefix
-        .rfind('\n')
-        .map_or(pos + 1, |last_newline| pos - last_newline);
-    (line, column)
+/// If lexing stopped at `unlexed` because a byte string there has a `\x`
+/// escape without two hex digits, the offset of that escape in `unlexed`
+fn invalid_byte_escape(unlexed: &str) -> Option<usize> {
+    match parse_bytes_literal(unlexed) {
+        Err(nom::Err::Failure(error)) if error.code == nom::error::ErrorKind::HexDigit => {
+            Some(unlexed.len() - error.input.len())
+        }
+        _ => None,
+    }
+}
+
+/// If lexing stopped at `unlexed` because a string or block comment opened
+/// there is never closed, what it is, the length of its opening delimiter
+/// and the delimiter that would close it
+fn unterminated(unlexed: &str) -> Option<(&'static str, usize, String)> {
+    if unlexed.starts_with("/*") {
+        return Some(("block comment", 2, "*/".to_string()));
+    }
+    if unlexed.starts_with("b\"") {
+        return Some(("byte string", 2, "\"".to_string()));
+    }
+    if unlexed.starts_with('"') {
+        return Some(("string", 1, "\"".to_string()));
+    }
+    let hashes = unlexed.strip_prefix('r')?;
+    let hashes = &hashes[..hashes.len() - hashes.trim_start_matches('#').len()];
+    unlexed[1 + hashes.len()..]
+        .starts_with('"')
+        .then(|| ("raw string", hashes.len() + 2, format!("\"{}", hashes)))
+}
+
+/// Split the whitespace and comments in `input[start..end]` into trivia. The
+/// range is a gap between two tokens, so it holds nothing else.
+fn split_trivia(input: &str, start: usize, end: usize) -> Vec<Trivia> {
+    let mut trivia = Vec::new();
+    let mut position = start;
+
+    while position < end {
+        let rest = &input[position..end];
+        let (kind, length) = if rest.starts_with("//") {
+            (
+                TriviaKind::LineComment,
+                rest.find('\n').unwrap_or(rest.len()),
+            )
+        } else if rest.starts_with("/*") {
+            let length = rest.find("*/").map_or(rest.len(), |close| close + 2);
+            (TriviaKind::BlockComment, length)
+        } else {
+            let length = rest
+                .find(|c: char| !c.is_whitespace())
+                .unwrap_or(rest.len());
+            // Anything else would have been a token, but never loop forever
+            (
+                TriviaKind::Whitespace,
+                length.max(rest.chars().next().map_or(1, char::len_utf8)),
+            )
+        };
+
+        trivia.push(Trivia {
+            kind,
+            text: rest[..length].to_string(),
+            span: Span::locate(input, position, position + length),
+        });
+        position += length;
+    }
+
+    trivia
 }
 
 fn parse_identifier_or_keyword(input: &str) -> IResult<&str, Token> {
     recognize(pair(alpha1, many0(alt((alphanumeric1, tag("_"))))))
-        .map(|s: &str| match s {
-            "let" => Token::Let,
-            "import" => Token::Import,
-            "from" => Token::From,
-            "as" => Token::As,
-            "Int" => Token::Int,
-            "Bool" => Token::Bool,
-            "String" => Token::String,
-            "List" => Token::List,
-            "Rec" => Token::Rec,
-            "fn" => Token::Fn,
-            "fix" => Token::Fix,
-            "fst" => Token::Fst,
-            "snd" => Token::Snd,
-            "cons" => Token::Cons,
-            "head" => Token::Head,
-            "tail" => Token::Tail,
-            "print" => Token::Print,
-            "type" => Token::Type,
-            "if" => Token::If,
-            "else" => Token::Else,
-            "for" => Token::For,
-            "in" => Token::In,
-            "range" => Token::Range,
-            "concat" => Token::Concat,
-            "char" => Token::Char,
-            "length" => Token::Length,
-            "toString" => Token::ToString,
-            "inl" => Token::Inl,
-            "inr" => Token::Inr,
-            "case" => Token::Case,
-            "of" => Token::Of,
-            "true" => Token::True,
-            "false" => Token::False,
-            _ => Token::Identifier(s.to_string()),
+        .map(|s: &str| match Token::from_keyword(s) {
+            Some(keyword) => keyword,
+            None => Token::Identifier(s.to_string()),
         })
         .parse(input)
 }
 
+/// Parses an integer literal. A trailing `n` makes it a `BigInt` literal of
+/// any size; otherwise a value that does not fit in an `Int` is a hard failure
+/// so that `0xFF...` is not re-read as a decimal `0` followed by an identifier.
 fn parse_number(input: &str) -> IResult<&str, Token> {
-    digit1.map_res(str::parse).map(Token::Number).parse(input)
+    let (rest, (digits, radix)) = alt((
+        parse_radix_digits("0x", 16),
+        parse_radix_digits("0o", 8),
+        parse_radix_digits("0b", 2),
+        parse_decimal_digits,
+    ))
+    .parse(input)?;
+    let digits = digits.replace('_', "");
+    let out_of_range =
+        || nom::Err::Failure(nom::error::Error::new(input, nom::error::ErrorKind::MapRes));
+
+    if let Some(rest) = rest.strip_prefix('n') {
+        let value = BigInt::parse_bytes(digits.as_bytes(), radix).ok_or_else(out_of_range)?;
+        return Ok((rest, Token::BigNumber(value)));
+    }
+
+    match i64::from_str_radix(&digits, radix) {
+        Ok(value) => Ok((rest, Token::Number(value))),
+        Err(_) => Err(out_of_range()),
+    }
+}
+
+fn parse_decimal_digits(input: &str) -> IResult<&str, (&str, u32)> {
+    recognize(pair(
+        digit1,
+        take_while(|c: char| c.is_ascii_digit() || c == '_'),
+    ))
+    .map(|digits| (digits, 10))
+    .parse(input)
+}
+
+/// Digits of a prefixed literal such as `0xFF`, `0o777` or `0b1010`
+fn parse_radix_digits(
+    prefix: &'static str,
+    radix: u32,
+) -> impl Fn(&str) -> IResult<&str, (&str, u32)> {
+    move |input: &str| {
+        preceded(
+            tag(prefix),
+            recognize(pair(
+                take_while1(|c: char| c.is_digit(radix)),
+                take_while(|c: char| c.is_digit(radix) || c == '_'),
+            )),
+        )
+        .map(|digits| (digits, radix))
+        .parse(input)
+    }
+}
+
+/// Parses a byte string `b"..."`. It takes the same escapes as a string plus
+/// `\0` and `\xNN` for arbitrary bytes; other characters are stored as UTF-8.
+fn parse_bytes_literal(input: &str) -> IResult<&str, Token> {
+    let (mut remaining, _) = tag("b\"")(input)?;
+    let mut bytes = Vec::new();
+    // A bad escape or running out of input fails outright, or the `b` would
+    // be read as an identifier
+    let invalid_escape =
+        |at| nom::Err::Failure(nom::error::Error::new(at, nom::error::ErrorKind::HexDigit));
+    let unclosed = || nom::Err::Failure(nom::error::Error::new(input, nom::error::ErrorKind::Char));
+
+    loop {
+        let mut chars = remaining.chars();
+        match chars.next().ok_or_else(unclosed)? {
+            '"' => return Ok((&remaining[1..], Token::BytesLiteral(bytes))),
+            '\\' => {
+                let escaped = chars.next().ok_or_else(unclosed)?;
+                if escaped == 'x' {
+                    let byte = remaining
+                        .get(2..4)
+                        .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                        .ok_or_else(|| invalid_escape(remaining))?;
+                    bytes.push(byte);
+                    remaining = &remaining[4..];
+                    continue;
+                }
+                let byte = match escaped {
+                    'n' => b'\n',
+                    't' => b'\t',
+                    'r' => b'\r',
+                    '0' => b'\0',
+                    '\\' => b'\\',
+                    '"' => b'"',
+                    other => {
+                        // Keep unknown escapes as-is, like string literals do
+                        let mut buffer = [0; 4];
+                        bytes.extend_from_slice(other.encode_utf8(&mut buffer).as_bytes());
+                        remaining = &remaining[1 + other.len_utf8()..];
+                        continue;
+                    }
+                };
+                bytes.push(byte);
+                remaining = &remaining[2..];
+            }
+            other => {
+                let mut buffer = [0; 4];
+                bytes.extend_from_slice(other.encode_utf8(&mut buffer).as_bytes());
+                remaining = &remaining[other.len_utf8()..];
+            }
+        }
+    }
+}
+
+/// Parses a raw string `r"..."`, which takes no escapes. Any number of `#`
+/// may follow the `r` and must then close the string too, so `r#"say "hi""#`
+/// can hold quotes.
+fn parse_raw_string_literal(input: &str) -> IResult<&str, Token> {
+    let (rest, _) = char('r')(input)?;
+    let (rest, hashes) = take_while(|c| c == '#')(rest)?;
+    let (rest, _) = char('"')(rest)?;
+    let closing = format!("\"{}", hashes);
+    match rest.find(&closing) {
+        Some(end) => Ok((
+            &rest[end + closing.len()..],
+            Token::StringLiteral(rest[..end].to_string()),
+        )),
+        // Fail outright, or the `r` would be read as an identifier
+        None => Err(nom::Err::Failure(nom::error::Error::new(
+            input,
+            nom::error::ErrorKind::Char,
+        ))),
+    }
 }
 
 fn parse_string_literal(input: &str) -> IResult<&str, Token> {
@@ -128,7 +515,8 @@ fn parse_string_literal(input: &str) -> IResult<&str, Token> {
                     _ => escaped_char, // Default: keep the character as-is
                 };
                 chars.push(escaped);
-                remaining = &remaining[2..]; // consume backslash and escaped char
+                // consume backslash and escaped char
+                remaining = &remaining[1 + escaped_char.len_utf8()..];
             } else {
                 return Err(nom::Err::Error(nom::error::Error::new(
                     remaining,
@@ -156,10 +544,18 @@ fn parse_semicolon(input: &str) -> IResult<&str, Token> {
     value(Token::Semicolon, char(';')).parse(input)
 }
 
+fn parse_double_colon(input: &str) -> IResult<&str, Token> {
+    value(Token::DoubleColon, tag("::")).parse(input)
+}
+
 fn parse_colon(input: &str) -> IResult<&str, Token> {
     value(Token::Colon, char(':')).parse(input)
 }
 
+fn parse_dot_dot(input: &str) -> IResult<&str, Token> {
+    value(Token::DotDot, tag("..")).parse(input)
+}
+
 fn parse_period(input: &str) -> IResult<&str, Token> {
     value(Token::Period, char('.')).parse(input)
 }
@@ -168,6 +564,10 @@ fn parse_arrow(input: &str) -> IResult<&str, Token> {
     value(Token::Arrow, tag("->")).parse(input)
 }
 
+fn parse_append(input: &str) -> IResult<&str, Token> {
+    value(Token::Append, tag("++")).parse(input)
+}
+
 fn parse_plus(input: &str) -> IResult<&str, Token> {
     value(Token::Plus, char('+')).parse(input)
 }
@@ -181,7 +581,8 @@ fn parse_multiply(input: &str) -> IResult<&str, Token> {
 }
 
 fn parse_divide(input: &str) -> IResult<&str, Token> {
-    value(Token::Divide, char('/')).parse(input)
+    // `/*` always opens a comment, even one that is never closed
+    value(Token::Divide, terminated(char('/'), not(char('*')))).parse(input)
 }
 
 fn parse_left_paren(input: &str) -> IResult<&str, Token> {
@@ -204,6 +605,26 @@ fn parse_comma(input: &str) -> IResult<&str, Token> {
     value(Token::Comma, char(',')).parse(input)
 }
 
+/// A lone `_`, not the start of a longer name
+fn parse_underscore(input: &str) -> IResult<&str, Token> {
+    let name_char = satisfy(|c: char| c.is_ascii_alphanumeric() || c == '_');
+    value(Token::Underscore, terminated(char('_'), not(name_char))).parse(input)
+}
+
+/// `?`, or `??name` for a hole with a name
+fn parse_hole(input: &str) -> IResult<&str, Token> {
+    alt((
+        // Any name will do, even a reserved word
+        preceded(
+            tag("??"),
+            recognize(pair(alpha1, many0(alt((alphanumeric1, tag("_")))))),
+        )
+        .map(|name: &str| Token::Hole(Some(name.to_string()))),
+        value(Token::Hole(None), char('?')),
+    ))
+    .parse(input)
+}
+
 fn parse_left_brace(input: &str) -> IResult<&str, Token> {
     value(Token::LeftBrace, char('{')).parse(input)
 }
@@ -248,6 +669,14 @@ fn parse_logical_not(input: &str) -> IResult<&str, Token> {
     value(Token::LogicalNot, char('!')).parse(input)
 }
 
+fn parse_compose_forward(input: &str) -> IResult<&str, Token> {
+    value(Token::ComposeForward, tag(">>")).parse(input)
+}
+
+fn parse_compose_backward(input: &str) -> IResult<&str, Token> {
+    value(Token::ComposeBackward, tag("<<")).parse(input)
+}
+
 fn parse_pipe(input: &str) -> IResult<&str, Token> {
     value(Token::Pipe, char('|')).parse(input)
 }
@@ -263,10 +692,13 @@ fn parse_operators(input: &str) -> IResult<&str, Token> {
         parse_arrow,              // -> must come before -
         parse_equal,              // == must come before =
         parse_not_equal,          // != must come before !
+        parse_compose_forward,    // >> must come before >= and >
+        parse_compose_backward,   // << must come before <= and <
         parse_less_than_equal,    // <= must come before <
         parse_greater_than_equal, // >= must come before >
         parse_logical_and,        // && must come before individual &
         parse_logical_or,         // || must come before individual |
+        parse_append,             // ++ must come before +
         // Single character operators
         parse_assign,
         parse_plus,
@@ -284,7 +716,9 @@ fn parse_operators(input: &str) -> IResult<&str, Token> {
 fn parse_punctuation(input: &str) -> IResult<&str, Token> {
     alt((
         parse_semicolon,
+        parse_double_colon, // :: must come before :
         parse_colon,
+        parse_dot_dot, // .. must come before .
         parse_period,
         parse_left_paren,
         parse_right_paren,
@@ -293,6 +727,8 @@ fn parse_punctuation(input: &str) -> IResult<&str, Token> {
         parse_left_brace,
         parse_right_brace,
         parse_comma,
+        parse_underscore,
+        parse_hole,
     ))
     .parse(input)
 }
@@ -320,7 +756,9 @@ fn parse_multi_line_comment(input: &str) -> IResult<&str, ()> {
             return Ok((rest, ()));
         }
 
-        remaining = &remaining[1..];
+        // Step over a whole character, which may be several bytes long
+        let width = remaining.chars().next().map_or(1, char::len_utf8);
+        remaining = &remaining[width..];
     }
 }
 
@@ -331,6 +769,8 @@ fn parse_comment(input: &str) -> IResult<&str, ()> {
 fn parse_single_token(input: &str) -> IResult<&str, Token> {
     alt((
         parse_operators,
+        parse_bytes_literal,
+        parse_raw_string_literal,
         parse_identifier_or_keyword,
         parse_number,
         parse_string_literal,
@@ -380,13 +820,14 @@ fn parse_token_with_whitespace<'a>(
     let token_len = input_after_ws.len() - rest.len();
     let end_offset = start_offset + token_len;
 
-    let (line, column) = calculate_position(original_input, start_offset);
-    let span = Span::new(start_offset, end_offset, line, column);
+    let span = Span::locate(original_input, start_offset, end_offset);
 
     Ok((rest, Some(TokenWithSpan::new(token, span))))
 }
 
-fn parse_tokens(input: &str) -> IResult<&str, Vec<TokenWithSpan>> {
+/// Lex tokens until the input runs out or a token cannot be read, returning
+/// the rest of the input with the tokens so far and a final `Eof`
+fn parse_tokens(input: &str) -> (&str, Vec<TokenWithSpan>) {
     let mut tokens = Vec::new();
     let mut remaining = input;
     let original_input = input;
@@ -408,9 +849,8 @@ fn parse_tokens(input: &str) -> IResult<&str, Vec<TokenWithSpan>> {
 
     // Add EOF token
     let eof_offset = original_input.len() - remaining.len();
-    let (line, column) = calculate_position(original_input, eof_offset);
-    let eof_span = Span::new(eof_offset, eof_offset, line, column);
+    let eof_span = Span::locate(original_input, eof_offset, eof_offset);
     tokens.push(TokenWithSpan::new(Token::Eof, eof_span));
 
-    Ok((remaining, tokens))
+    (remaining, tokens)
 }
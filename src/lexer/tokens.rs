@@ -2,19 +2,25 @@
 pub enum Token {
     // Keywords
     Let,
+    Const,  // const (compile-time constant)
+    Enum,   // enum (namespace of tagged constants)
     Import, // import (file import)
+    Export, // export (re-export an import)
     From,   // from (import source)
 
     // Type keywords
-    Int,    // Int
-    Bool,   // Bool
-    String, // String
-    List,   // List
-    Rec,    // Rec
+    Int,     // Int
+    Bool,    // Bool
+    String,  // String
+    List,    // List
+    Handle,  // Handle (thread handle type)
+    Channel, // Channel (channel type)
+    Rec,     // Rec
 
     // Function keywords
-    Fn,  // fn
-    Fix, // fix
+    Fn,     // fn
+    Fix,    // fix
+    Return, // return (leave the enclosing function)
 
     // Pair destructuring keywords
     Fst, // fst (first element)
@@ -26,8 +32,21 @@ pub enum Token {
     Tail, // tail (rest of list)
 
     // Built-in functions
-    Print, // print (output to console)
-    Type,  // type (get type of value)
+    Print,   // print (output to console)
+    Type,    // type (get type of value)
+    DivMod,  // divmod (quotient and remainder)
+    SafeDiv, // safeDiv (division returning a sum)
+    Random,  // random (random integer below a bound)
+    Input,   // input (read a line of standard input)
+    ReadAll, // readAll (read the rest of standard input)
+
+    // Concurrency
+    Spawn,       // spawn (run a function on a new thread)
+    Join,        // join (wait for a spawned thread)
+    Sleep,       // sleep (pause for milliseconds)
+    MakeChannel, // channel (create a channel)
+    Send,        // send (send a value on a channel)
+    Recv,        // recv (receive a value from a channel)
 
     // Control flow
     If,    // if
@@ -35,12 +54,22 @@ pub enum Token {
     For,   // for (list iteration)
     In,    // in (for iteration keyword)
     Range, // range (numeric range generation)
+    Is,    // is (runtime type test)
+    With,  // with (release a resource after a block)
 
     // String operations
-    Concat,   // concat (string concatenation)
-    Char,     // char (character access)
-    Length,   // length (string length)
-    ToString, // toString (convert to string)
+    Concat,    // concat (string concatenation)
+    Char,      // char (character access)
+    Length,    // length (string length)
+    Graphemes, // graphemes (split into grapheme clusters)
+
+    // Byte operations
+    ReadFileBytes, // readFileBytes (read a file as Bytes)
+    ByteAt,        // byteAt (byte access)
+    ToBytes,       // toBytes (UTF-8 encode a String)
+    FromBytes,     // fromBytes (UTF-8 decode Bytes)
+    ToString,      // toString (convert to string)
+    ToInt,         // toInt (read a String as an Int)
 
     // Sum type constructors
     Inl, // inl (left injection)
@@ -60,12 +89,16 @@ pub enum Token {
     // Identifiers and literals
     Identifier(String),
     Number(i64),
+    BigNumber(num_bigint::BigInt), // 123n
     StringLiteral(String),
+    BytesLiteral(Vec<u8>), // b"..."
+    Hole(Option<String>),  // ? or ??name (a typed hole)
 
     // Operators
     Assign,           // =
     Arrow,            // ->
     Plus,             // +
+    Append,           // ++
     Minus,            // -
     Multiply,         // *
     Divide,           // /
@@ -78,11 +111,15 @@ pub enum Token {
     LogicalAnd,       // &&
     LogicalOr,        // ||
     LogicalNot,       // !
+    ComposeForward,   // >>
+    ComposeBackward,  // <<
 
     // Punctuation
     Semicolon,    // ;
     Colon,        // :
+    DoubleColon,  // ::
     Period,       // .
+    DotDot,       // .. (range pattern)
     LeftParen,    // (
     RightParen,   // )
     LeftBracket,  // [
@@ -90,30 +127,218 @@ pub enum Token {
     LeftBrace,    // {
     RightBrace,   // }
     Comma,        // ,
+    Underscore,   // _ (wildcard pattern)
 
     // Special
     Eof,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+/// Every reserved word with the token it is read as. None of them can be
+/// used as a name. The tokenizer, the parser's messages and the formatter all
+/// spell keywords from here, so a new keyword is one more entry.
+pub(crate) const KEYWORDS: &[(&str, Token)] = &[
+    ("let", Token::Let),
+    ("const", Token::Const),
+    ("enum", Token::Enum),
+    ("import", Token::Import),
+    ("export", Token::Export),
+    ("from", Token::From),
+    ("as", Token::As),
+    ("Int", Token::Int),
+    ("Bool", Token::Bool),
+    ("String", Token::String),
+    ("List", Token::List),
+    ("Handle", Token::Handle),
+    ("Channel", Token::Channel),
+    ("Rec", Token::Rec),
+    ("fn", Token::Fn),
+    ("fix", Token::Fix),
+    ("return", Token::Return),
+    ("fst", Token::Fst),
+    ("snd", Token::Snd),
+    ("cons", Token::Cons),
+    ("head", Token::Head),
+    ("tail", Token::Tail),
+    ("print", Token::Print),
+    ("type", Token::Type),
+    ("divmod", Token::DivMod),
+    ("safeDiv", Token::SafeDiv),
+    ("random", Token::Random),
+    ("input", Token::Input),
+    ("readAll", Token::ReadAll),
+    ("spawn", Token::Spawn),
+    ("join", Token::Join),
+    ("sleep", Token::Sleep),
+    ("channel", Token::MakeChannel),
+    ("send", Token::Send),
+    ("recv", Token::Recv),
+    ("if", Token::If),
+    ("else", Token::Else),
+    ("for", Token::For),
+    ("in", Token::In),
+    ("is", Token::Is),
+    ("with", Token::With),
+    ("range", Token::Range),
+    ("concat", Token::Concat),
+    ("char", Token::Char),
+    ("length", Token::Length),
+    ("graphemes", Token::Graphemes),
+    ("readFileBytes", Token::ReadFileBytes),
+    ("byteAt", Token::ByteAt),
+    ("toBytes", Token::ToBytes),
+    ("fromBytes", Token::FromBytes),
+    ("toString", Token::ToString),
+    ("toInt", Token::ToInt),
+    ("inl", Token::Inl),
+    ("inr", Token::Inr),
+    ("case", Token::Case),
+    ("of", Token::Of),
+    ("true", Token::True),
+    ("false", Token::False),
+];
+
+impl Token {
+    /// The token a word is read as if it is reserved
+    pub fn from_keyword(word: &str) -> Option<Token> {
+        KEYWORDS
+            .iter()
+            .find(|(keyword, _)| *keyword == word)
+            .map(|(_, token)| token.clone())
+    }
+
+    /// The reserved word this token is written as, if it is one
+    pub fn keyword(&self) -> Option<&'static str> {
+        KEYWORDS
+            .iter()
+            .find(|(_, token)| token == self)
+            .map(|(keyword, _)| *keyword)
+    }
+
+    /// Whether this is a reserved word for a builtin operation, called like
+    /// a function as in `head(xs)`, rather than for syntax such as `in`
+    pub fn is_builtin(&self) -> bool {
+        self.keyword().is_some()
+            && !matches!(
+                self,
+                Token::Let
+                    | Token::Const
+                    | Token::Enum
+                    | Token::Import
+                    | Token::Export
+                    | Token::From
+                    | Token::As
+                    | Token::Int
+                    | Token::Bool
+                    | Token::String
+                    | Token::List
+                    | Token::Handle
+                    | Token::Channel
+                    | Token::Rec
+                    | Token::Fn
+                    | Token::Return
+                    | Token::If
+                    | Token::Else
+                    | Token::For
+                    | Token::In
+                    | Token::Is
+                    | Token::With
+                    | Token::Case
+                    | Token::Of
+                    | Token::True
+                    | Token::False
+            )
+    }
+}
+
+/// A range of source text. `start` and `end` are byte offsets, for slicing
+/// the source; `char_start` and `char_end` are the same positions counted in
+/// characters, as editors and language servers count them. `line` and
+/// `column` give the start position and `end_line` and `end_column` the
+/// position just past the end, all from 1, with columns counted in characters
+/// so that they are right on lines with non-ASCII text.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Span {
     pub start: usize,
     pub end: usize,
     pub line: usize,
     pub column: usize,
+    pub char_start: usize,
+    pub char_end: usize,
+    pub end_line: usize,
+    pub end_column: usize,
 }
 
 impl Span {
+    /// A span over ASCII text on a single line, where byte and character
+    /// offsets agree. Spans of real source come from `Span::locate`.
     pub fn new(start: usize, end: usize, line: usize, column: usize) -> Self {
         Self {
             start,
             end,
             line,
             column,
+            char_start: start,
+            char_end: end,
+            end_line: line,
+            end_column: column + end.saturating_sub(start),
+        }
+    }
+
+    /// The span of `source[start..end]`, given as byte offsets
+    pub fn locate(source: &str, start: usize, end: usize) -> Self {
+        let end = end.min(source.len());
+        let start = start.min(end);
+        let (line, column) = line_and_column(&source[..start]);
+        let (lines, end_column) = line_and_column(&source[start..end]);
+        let char_start = source[..start].chars().count();
+        Self {
+            start,
+            end,
+            line,
+            column,
+            char_start,
+            char_end: char_start + source[start..end].chars().count(),
+            end_line: line + lines - 1,
+            end_column: if lines == 1 {
+                column + end_column - 1
+            } else {
+                end_column
+            },
+        }
+    }
+
+    /// The span from the start of this one to the end of `end`
+    pub fn to(&self, end: &Span) -> Span {
+        Span {
+            end: end.end,
+            char_end: end.char_end,
+            end_line: end.end_line,
+            end_column: end.end_column,
+            ..self.clone()
+        }
+    }
+
+    /// The empty span just past the end of this one
+    pub fn end_point(&self) -> Span {
+        Span {
+            start: self.end,
+            char_start: self.char_end,
+            line: self.end_line,
+            column: self.end_column,
+            ..self.clone()
         }
     }
 }
 
+/// Line and column, from 1, of the position just past the end of `text`
+fn line_and_column(text: &str) -> (usize, usize) {
+    let line_start = text.rfind('\n').map_or(0, |newline| newline + 1);
+    (
+        text.matches('\n').count() + 1,
+        text[line_start..].chars().count() + 1,
+    )
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct TokenWithSpan {
     pub token: Token,
@@ -125,3 +350,47 @@ impl TokenWithSpan {
         Self { token, span }
     }
 }
+
+/// Source text between tokens that the parser never sees
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TriviaKind {
+    Whitespace,
+    LineComment,  // `// ...`, not including the newline
+    BlockComment, // `/* ... */`
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trivia {
+    pub kind: TriviaKind,
+    pub text: String,
+    pub span: Span,
+}
+
+/// A token together with its exact source text and the trivia around it, as
+/// produced by `Tokenizer::tokenize_lossless`. Trailing trivia is whatever
+/// follows the token on the same line; everything else leads the next token,
+/// so a comment on its own line belongs to the token it precedes. Joining
+/// `source_text` of every token gives back the input unchanged.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LosslessToken {
+    pub token: Token,
+    pub text: String,
+    pub span: Span,
+    pub leading: Vec<Trivia>,
+    pub trailing: Vec<Trivia>,
+}
+
+impl LosslessToken {
+    /// The token's text with its leading and trailing trivia
+    pub fn source_text(&self) -> String {
+        let mut text = String::new();
+        for trivia in &self.leading {
+            text.push_str(&trivia.text);
+        }
+        text.push_str(&self.text);
+        for trivia in &self.trailing {
+            text.push_str(&trivia.text);
+        }
+        text
+    }
+}
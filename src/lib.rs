@@ -0,0 +1,17 @@
+//! The Corrosion language front end, type checker, interpreter and code
+//! generators, for embedding and for tools such as fuzzers. The
+//! `corrosion-language` binary is a thin command line wrapper around them.
+
+pub mod ast;
+pub mod codegen;
+pub mod diff;
+pub mod engine;
+pub mod fuzz;
+pub mod interpreter;
+pub mod lexer;
+pub mod navigation;
+pub mod packages;
+pub mod stdlib;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+pub mod typechecker;
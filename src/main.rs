@@ -1,83 +1,616 @@
-pub mod ast;
-pub mod interpreter;
-pub mod lexer;
+mod explain;
 mod repl;
 mod tests;
-pub mod typechecker;
+
+use corrosion_language::{ast, codegen, diff, interpreter, lexer, packages, stdlib, typechecker};
+
+use lexer::Edition;
 
 use repl::Repl;
 use std::env;
 use std::process;
 
+/// Checking options set by command line flags
+#[derive(Default)]
+struct RunOptions {
+    /// Whether declarations and parameters whose types are not fully
+    /// inferred are rejected, if `--strict` or `--no-strict` says so rather
+    /// than the edition
+    strict: Option<bool>,
+    /// Reject IO at the top level of imported modules
+    pure_imports: bool,
+    /// Print an intermediate representation instead of running the program
+    emit: Option<Emit>,
+    /// Script to run when the REPL starts, instead of `~/.corrosionrc`
+    init: Option<String>,
+    /// Leave the prelude out of the program and its imports
+    no_prelude: bool,
+    /// Read misspelled or miscapitalized keywords, with a warning
+    lenient: bool,
+    /// File to write an lcov report of which lines ran to
+    coverage: Option<String>,
+    /// Fail if the imported modules differ from the lockfile, instead of
+    /// updating it
+    locked: bool,
+    /// Edition to read the program in, instead of the project's or the latest
+    edition: Option<Edition>,
+    /// Reject deprecated builtins and syntax instead of warning about them
+    deny_deprecated: bool,
+}
+
+/// Representations that can be printed with `--emit=<name>`
+#[derive(Clone, Copy, PartialEq)]
+enum Emit {
+    /// The type-checked program as JSON, with spans and resolved types
+    TypedAstJson,
+    /// The parsed syntax tree as a Graphviz DOT diagram
+    AstDot,
+    /// Which named functions call which, as a Graphviz DOT diagram
+    CallGraphDot,
+    /// A standalone JavaScript translation of the program
+    Js,
+    /// A standalone Rust translation of the program
+    Rust,
+}
+
+impl Emit {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "typed-ast-json" => Some(Emit::TypedAstJson),
+            "ast-dot" => Some(Emit::AstDot),
+            "callgraph-dot" => Some(Emit::CallGraphDot),
+            "js" => Some(Emit::Js),
+            "rust" => Some(Emit::Rust),
+            _ => None,
+        }
+    }
+}
+
 fn main() {
+    // Deeply recursive programs need more stack than the main thread has
+    interpreter::with_stack(run)
+}
+
+fn run() {
     let args: Vec<String> = env::args().collect();
 
-    match args.len() {
-        1 => {
-            // No arguments - start REPL
-            let mut repl = Repl::new();
-            repl.run();
+    // Flags come before the filename; anything after it belongs to the program
+    let mut options = RunOptions::default();
+    let mut rest = &args[1..];
+    while let Some(flag) = rest.first() {
+        match flag.as_str() {
+            "--strict" => options.strict = Some(true),
+            "--no-strict" => options.strict = Some(false),
+            "--pure-imports" => options.pure_imports = true,
+            "--no-prelude" => options.no_prelude = true,
+            "--lenient" => options.lenient = true,
+            "--locked" => options.locked = true,
+            "--deny-deprecated" => options.deny_deprecated = true,
+            "--init" => match rest.get(1) {
+                Some(path) => {
+                    options.init = Some(path.clone());
+                    rest = &rest[1..];
+                }
+                None => {
+                    eprintln!("--init needs a file to run when the REPL starts");
+                    process::exit(1);
+                }
+            },
+            _ if flag.starts_with("--edition=") => {
+                let name = &flag["--edition=".len()..];
+                match Edition::from_name(name) {
+                    Some(edition) => options.edition = Some(edition),
+                    None => {
+                        let editions: Vec<&str> =
+                            Edition::ALL.iter().map(|edition| edition.name()).collect();
+                        eprintln!(
+                            "Unknown edition '{}', expected one of {}",
+                            name,
+                            editions.join(", ")
+                        );
+                        process::exit(1);
+                    }
+                }
+            }
+            _ if flag.starts_with("--coverage=") => {
+                options.coverage = Some(flag["--coverage=".len()..].to_string());
+            }
+            _ if flag.starts_with("--emit=") => {
+                let name = &flag["--emit=".len()..];
+                match Emit::from_name(name) {
+                    Some(emit) => options.emit = Some(emit),
+                    None => {
+                        eprintln!(
+                            "Unknown --emit target '{}', expected typed-ast-json, ast-dot, callgraph-dot, js or rust",
+                            name
+                        );
+                        process::exit(1);
+                    }
+                }
+            }
+            _ => break,
+        }
+        rest = &rest[1..];
+    }
+
+    match rest.split_first() {
+        Some((command, [url, name @ ..])) if command == "add" && name.len() <= 1 => {
+            exit_with(add_package(url, name.first()).map(|()| 0));
+        }
+        Some((command, [old, new])) if command == "diff" => {
+            exit_with(diff_files(old, new, &options));
+        }
+        Some((flag, [code, program_args @ ..])) if flag == "-e" => {
+            exit_with(execute_expression(code, &options, program_args));
+        }
+        Some((flag, [])) if flag == "-e" => {
+            eprintln!("-e needs code to run");
+            process::exit(1);
         }
-        2 => {
-            // One argument - load and execute file
-            let filename = &args[1];
-            if let Err(e) = load_and_execute_file(filename) {
+        Some((flag, [code])) if flag == "--explain" => {
+            println!("{}", explain::render(code));
+            if explain::explanation(code).is_none() {
+                process::exit(1);
+            }
+        }
+        // Only --init and --no-prelude apply to the REPL
+        None if args.len()
+            == 1 + 2 * usize::from(options.init.is_some()) + usize::from(options.no_prelude) =>
+        {
+            // No arguments - start REPL
+            let mut repl = if options.no_prelude {
+                Repl::without_prelude()
+            } else {
+                Repl::new()
+            };
+            let script = match &options.init {
+                Some(path) => Some(std::path::PathBuf::from(path)),
+                None => Repl::default_startup_script(),
+            };
+            if let Some(script) = script
+                && let Err(e) = repl.run_startup_script(&script)
+            {
                 eprintln!("Error: {}", e);
                 process::exit(1);
             }
+            repl.run();
+        }
+        Some((filename, program_args)) => {
+            // Load and execute the file, passing any remaining arguments to main
+            exit_with(load_and_execute_file(filename, &options, program_args));
         }
-        _ => {
-            eprintln!("Usage: {} [filename]", args[0]);
-            eprintln!("  - Run without arguments to start the REPL");
+        None => {
+            eprintln!(
+                "Usage: {} [--no-strict] [--pure-imports] [--no-prelude] [--lenient] [--deny-deprecated] [--locked] [--edition=<year>] [--emit=<target>] [--coverage=<file>] [filename [args...]]",
+                args[0]
+            );
+            eprintln!("       {} [--init <file>] [--no-prelude]", args[0]);
+            eprintln!("       {} [options] -e <code> [args...]", args[0]);
+            eprintln!("       {} --explain <code>", args[0]);
+            eprintln!("       {} add <git-url> [name]", args[0]);
+            eprintln!("       {} [options] diff <old-file> <new-file>", args[0]);
+            eprintln!("  - Run without a filename to start the REPL");
+            eprintln!(
+                "  - Use --init to run a file when the REPL starts instead of ~/.corrosionrc"
+            );
             eprintln!("  - Provide a filename to execute that file");
+            eprintln!("  - Use -e to run code given on the command line and print its value");
+            eprintln!("  - Arguments after the filename are passed to its main function");
+            eprintln!("  - Use --no-strict to accept types that inference cannot work out");
+            eprintln!("  - Use --pure-imports to reject direct IO at the top level of imports");
+            eprintln!("  - Use --no-prelude to leave out the functions every program starts with");
+            eprintln!("  - Use --lenient to accept keywords such as True or esle, with a warning");
+            eprintln!("  - Use --deny-deprecated to make uses of deprecated builtins errors");
+            eprintln!("  - Use --locked to fail if imported modules changed since the last run");
+            eprintln!("  - Use --edition=2025 to read a program written for an older edition");
+            eprintln!("  - Use --emit=typed-ast-json to print the type-checked program as JSON");
+            eprintln!("  - Use --emit=ast-dot or --emit=callgraph-dot for Graphviz diagrams");
+            eprintln!("  - Use --emit=js or --emit=rust to compile to JavaScript or Rust");
+            eprintln!("  - Use --coverage=<file> to write an lcov report of the lines that ran");
+            eprintln!("  - Use --explain with an error code such as E0001 to describe it");
+            eprintln!("  - Use add to fetch a package and declare it in corrosion.toml");
+            eprintln!("  - Use diff to list the definitions added, removed or changed in a file");
             process::exit(1);
         }
     }
 }
 
-fn load_and_execute_file(filename: &str) -> Result<(), String> {
+/// Tell the user a package is being cloned, which can take a while
+fn report_fetch(name: &str, url: &str) {
+    eprintln!("Fetching {} from {}", name, url);
+}
+
+/// Declare a package in the manifest of the project in the current directory,
+/// creating the manifest if there is none, and fetch it
+fn add_package(url: &str, name: Option<&String>) -> Result<(), String> {
+    let name = match name {
+        Some(name) => name.clone(),
+        None => packages::package_name(url).ok_or_else(|| {
+            format!(
+                "Cannot tell a package name from '{}'; give one after the url",
+                url
+            )
+        })?,
+    };
+    let project = match packages::find_project(std::path::Path::new("")) {
+        Some(project) => project,
+        None => env::current_dir().map_err(|e| format!("No current directory: {}", e))?,
+    };
+    let path = project.join(packages::MANIFEST);
+    let manifest = match std::fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(format!("Failed to read '{}': {}", path.display(), e)),
+    };
+    let manifest = packages::add_dependency(&manifest, &name, url)?;
+
+    // Only declare the package once it has been fetched
+    packages::fetch(
+        &project,
+        &packages::Manifest::parse(&manifest)?,
+        report_fetch,
+    )?;
+    std::fs::write(&path, manifest)
+        .map_err(|e| format!("Failed to write '{}': {}", path.display(), e))?;
+    println!(
+        "Added '{}'; import it with import \"{}\" as {};",
+        name, name, name
+    );
+    Ok(())
+}
+
+/// Print how the top-level definitions of `new` differ from those of `old`,
+/// one per line. Like `diff`, the status is 1 if there are differences.
+fn diff_files(old: &str, new: &str, options: &RunOptions) -> Result<i32, String> {
+    let changes = diff::diff(
+        &check_definitions(old, options)?,
+        &check_definitions(new, options)?,
+    );
+    for change in &changes {
+        println!("{}", change);
+    }
+    Ok(i32::from(!changes.is_empty()))
+}
+
+/// Check a file as it would be checked to run it, without running it, and
+/// return its top-level definitions
+fn check_definitions(
+    filename: &str,
+    options: &RunOptions,
+) -> Result<Vec<diff::Definition>, String> {
     use crate::ast::Parser;
-    use crate::interpreter::Interpreter;
     use crate::lexer::Tokenizer;
     use crate::typechecker::TypeChecker;
-    use std::fs;
 
-    // Read the file contents
-    let contents = fs::read_to_string(filename)
+    let contents = std::fs::read_to_string(filename)
         .map_err(|e| format!("Failed to read file '{}': {}", filename, e))?;
+    let directory = std::path::Path::new(filename)
+        .parent()
+        .unwrap_or(std::path::Path::new(""));
+    let project = packages::load_project(directory)?;
+    let edition = edition(options, project.as_ref());
+    let tokens = Tokenizer::new(&contents)
+        .with_edition(edition)
+        .tokenize()
+        .map_err(|e| format!("{}: Tokenization error[{}]: {}", filename, e.code(), e))?;
+    let program = Parser::new(tokens)
+        .parse()
+        .map_err(|e| format!("{}: Parse error[{}]: {}", filename, e.code(), e))?;
 
-    // Process the file contents
-    let mut tokenizer = Tokenizer::new("");
-    let tokens = tokenizer
-        .tokenize(&contents)
-        .map_err(|e| format!("Tokenization error: {}", e))?;
+    let mut type_checker = if options.no_prelude {
+        TypeChecker::without_prelude()
+    } else {
+        TypeChecker::new()
+    };
+    type_checker.set_strict(
+        options
+            .strict
+            .unwrap_or_else(|| edition.requires_precise_types()),
+    );
+    type_checker.set_edition(edition);
+    if let Some((root, manifest)) = &project {
+        type_checker.set_module_search_path(packages::search_path(root, manifest, report_fetch)?);
+    }
+    type_checker.set_current_directory(directory);
+    let typed_program = type_checker
+        .check_program(&program)
+        .map_err(|e| format!("{}: Type error[{}]: {}", filename, e.code(), e))?;
+    Ok(diff::Definition::of_program(&program, &typed_program))
+}
+
+/// The edition to read a program in: the one given on the command line,
+/// else its project's, else the latest
+fn edition(
+    options: &RunOptions,
+    project: Option<&(std::path::PathBuf, packages::Manifest)>,
+) -> Edition {
+    options
+        .edition
+        .or_else(|| project.and_then(|(_, manifest)| manifest.edition()))
+        .unwrap_or_default()
+}
+
+/// Exit with the status of a program that ran, or report why it failed
+fn exit_with(result: Result<i32, String>) {
+    match result {
+        Ok(0) => {}
+        Ok(status) => process::exit(status),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+    }
+}
+
+/// The process exit status for what `main` returned: an `Int` is the status,
+/// reduced to 0-255 the way a shell reports it, and anything else is success
+fn exit_status(result: Option<&interpreter::Value>) -> i32 {
+    match result {
+        Some(interpreter::Value::Int(code)) => code.rem_euclid(256) as i32,
+        _ => 0,
+    }
+}
+
+/// Run a file, returning the exit status for the process
+fn load_and_execute_file(
+    filename: &str,
+    options: &RunOptions,
+    args: &[String],
+) -> Result<i32, String> {
+    let contents = std::fs::read_to_string(filename)
+        .map_err(|e| format!("Failed to read file '{}': {}", filename, e))?;
+    execute_source(&contents, Some(filename), options, args, false)
+}
+
+/// Compare the modules a program imports with its lockfile. With `locked`,
+/// any difference is an error; otherwise the lockfile is written when it is
+/// out of date, once the program imports any files.
+fn check_lockfile(
+    program: &std::path::Path,
+    type_checker: &typechecker::TypeChecker,
+    locked: bool,
+) -> Result<(), String> {
+    use crate::typechecker::lockfile::{Lockfile, lockfile_path};
+
+    let path = lockfile_path(program);
+    let directory = program.parent().unwrap_or(std::path::Path::new(""));
+    let current =
+        Lockfile::from_modules(directory, type_checker.get_module_loader().module_hashes());
+    let recorded = std::fs::read_to_string(&path)
+        .ok()
+        .map(|text| Lockfile::parse(&text));
+
+    if locked {
+        let recorded = match recorded {
+            Some(Ok(recorded)) => recorded,
+            Some(Err(e)) => return Err(format!("Invalid {}: {}", path.display(), e)),
+            None => {
+                return Err(format!(
+                    "--locked needs {}; run once without --locked to write it",
+                    path.display()
+                ));
+            }
+        };
+        let mismatches = recorded.mismatches(&current);
+        if mismatches.is_empty() {
+            return Ok(());
+        }
+        let list: Vec<String> = mismatches.iter().map(|m| format!("  {}", m)).collect();
+        return Err(format!(
+            "Imported modules do not match {}:\n{}",
+            path.display(),
+            list.join("\n")
+        ));
+    }
+
+    // A lockfile that no longer parses is replaced
+    let out_of_date = match &recorded {
+        Some(Ok(recorded)) => *recorded != current,
+        Some(Err(_)) => true,
+        None => !current.is_empty(),
+    };
+    // A lockfile that cannot be written should not stop the program
+    if out_of_date && let Err(e) = std::fs::write(&path, current.render()) {
+        eprintln!("Warning: failed to write {}: {}", path.display(), e);
+    }
+    Ok(())
+}
+
+/// Run the code given with `-e`, printing its value. A final `;` may be left
+/// out, so `-e '1 + 2'` prints 3.
+fn execute_expression(code: &str, options: &RunOptions, args: &[String]) -> Result<i32, String> {
+    use crate::ast::Parser;
+    use crate::lexer::Tokenizer;
+
+    // Code that parses as it is, such as a function declaration, cannot take
+    // a `;` after it. Anything else gets one on a line of its own, so it does
+    // not end up in a trailing comment.
+    let code = code.trim_end();
+    let project = packages::load_project(std::path::Path::new(""))?;
+    let complete = code.ends_with(';')
+        || Tokenizer::new(code)
+            .with_edition(edition(options, project.as_ref()))
+            .tokenize()
+            .is_ok_and(|tokens| Parser::new(tokens).parse().is_ok());
+    let source = if complete {
+        code.to_string()
+    } else {
+        format!("{}\n;", code)
+    };
+    execute_source(&source, None, options, args, true)
+}
+
+/// Check and run a program, returning the exit status for the process.
+/// Imports are looked up next to `filename`, or in the current directory for
+/// code without a file; `print_result` prints the value of the program's last
+/// statement.
+fn execute_source(
+    contents: &str,
+    filename: Option<&str>,
+    options: &RunOptions,
+    args: &[String],
+    print_result: bool,
+) -> Result<i32, String> {
+    use crate::ast::Parser;
+    use crate::interpreter::{Coverage, ExecOptions, Interpreter, Value};
+    use crate::lexer::Tokenizer;
+    use crate::typechecker::{TypeChecker, TypeError, TypeWarning};
+
+    let directory = filename.and_then(|filename| std::path::Path::new(filename).parent());
+    // The project the program is in gives its edition and packages
+    let project = packages::load_project(directory.unwrap_or(std::path::Path::new("")))?;
+    let edition = edition(options, project.as_ref());
+    let tokenizer = Tokenizer::new(contents).with_edition(edition);
+    let tokens = if options.lenient {
+        tokenizer.tokenize_lenient().map(|(tokens, warnings)| {
+            for warning in warnings {
+                eprintln!("Warning[{}]: {}", warning.code(), warning);
+            }
+            tokens
+        })
+    } else {
+        tokenizer.tokenize()
+    }
+    .map_err(|e| format!("Tokenization error[{}]: {}", e.code(), e))?;
 
     let mut parser = Parser::new(tokens);
-    let program = parser.parse().map_err(|e| format!("Parse error: {}", e))?;
+    let program = parser
+        .parse()
+        .map_err(|e| format!("Parse error[{}]: {}", e.code(), e))?;
 
-    let mut type_checker = TypeChecker::new();
+    // Diagrams are drawn straight after parsing, without type checking
+    match options.emit {
+        Some(Emit::AstDot) => {
+            print!("{}", program.to_ast_dot());
+            return Ok(0);
+        }
+        Some(Emit::CallGraphDot) => {
+            print!("{}", program.to_call_graph_dot());
+            return Ok(0);
+        }
+        _ => {}
+    }
+
+    let mut type_checker = if options.no_prelude {
+        TypeChecker::without_prelude()
+    } else {
+        TypeChecker::new()
+    };
+    type_checker.set_strict(
+        options
+            .strict
+            .unwrap_or_else(|| edition.requires_precise_types()),
+    );
+    type_checker.set_pure_imports(options.pure_imports);
+    type_checker.set_edition(edition);
+    // Packages of the project the program is in are found by import too
+    let search_path = match &project {
+        Some((root, manifest)) => packages::search_path(root, manifest, report_fetch)?,
+        None => Vec::new(),
+    };
+    type_checker.set_module_search_path(search_path.clone());
 
     // Set the current directory for import resolution
-    if let Some(parent_dir) = std::path::Path::new(filename).parent() {
+    if let Some(parent_dir) = directory {
         type_checker.set_current_directory(parent_dir);
     }
 
     // Type check the program and fail if there are errors
-    let _typed_program = type_checker
+    let typed_program = type_checker
         .check_program(&program)
-        .map_err(|e| format!("Type error: {}", e))?;
+        .map_err(|e| format!("Type error[{}]: {}", e.code(), e))?;
+    for warning in type_checker.warnings() {
+        match warning {
+            TypeWarning::Deprecated {
+                name,
+                replacement,
+                span,
+            } if options.deny_deprecated => {
+                let error = TypeError::Deprecated {
+                    name: name.clone(),
+                    replacement: replacement.clone(),
+                    span: span.clone(),
+                };
+                return Err(format!("Type error[{}]: {}", error.code(), error));
+            }
+            _ => eprintln!("Warning[{}]: {}", warning.code(), warning),
+        }
+    }
+    if let Some(filename) = filename {
+        check_lockfile(
+            std::path::Path::new(filename),
+            &type_checker,
+            options.locked,
+        )?;
+    }
+    // Compiled programs carry the prelude functions they use with them
+    let standalone = || {
+        if options.no_prelude {
+            program.clone()
+        } else {
+            stdlib::prelude::with_prelude(&program)
+        }
+    };
+    match options.emit {
+        Some(Emit::TypedAstJson) => {
+            println!("{}", typed_program.to_json());
+            return Ok(0);
+        }
+        Some(Emit::Js) => {
+            let js =
+                codegen::js::generate(&program, type_checker.operator_types(), !options.no_prelude)
+                    .map_err(|e| format!("Codegen error: {}", e))?;
+            print!("{}", js);
+            return Ok(0);
+        }
+        Some(Emit::Rust) => {
+            let rust = codegen::rust::generate(&standalone())
+                .map_err(|e| format!("Codegen error: {}", e))?;
+            print!("{}", rust);
+            return Ok(0);
+        }
+        _ => {}
+    }
+    type_checker
+        .check_entry_point(&program)
+        .map_err(|e| format!("Type error[{}]: {}", e.code(), e))?;
 
     // Execute the program with the interpreter
-    let mut interpreter = Interpreter::new();
-
+    let mut builder = Interpreter::builder()
+        .prelude(!options.no_prelude)
+        .module_search_path(search_path)
+        .edition(edition);
     // Set the current directory to the file's directory for import resolution
-    if let Some(parent_dir) = std::path::Path::new(filename).parent() {
-        interpreter.set_current_directory(parent_dir);
+    if let Some(parent_dir) = directory {
+        builder = builder.current_directory(parent_dir);
     }
+    let mut interpreter = builder.build();
 
-    let _result = interpreter
-        .interpret_program(&program)
-        .map_err(|e| format!("Runtime error: {}", e))?;
+    // Only `-e` shows the value of the last statement
+    let coverage = options.coverage.as_ref().map(|_| Coverage::new());
+    let exec_options = ExecOptions {
+        return_last_value: print_result,
+        coverage: coverage.clone(),
+        ..ExecOptions::file()
+    };
+    let result = interpreter
+        .execute(&program, &exec_options)
+        .and_then(|result| {
+            if result != Value::Unit {
+                println!("{}", result);
+            }
+            // Scripts run top to bottom; programs that define main continue there
+            interpreter.run_main(args)
+        });
+    // Close the channels and wait for the threads the program left open
+    interpreter.release_handles();
+    // The report covers runs that failed too
+    if let (Some(path), Some(coverage)) = (&options.coverage, &coverage) {
+        let report = coverage.lcov(&program, filename.unwrap_or("-e"));
+        std::fs::write(path, report)
+            .map_err(|e| format!("Failed to write coverage report '{}': {}", path, e))?;
+    }
+    let result = result.map_err(|e| format!("Runtime error[{}]: {}", e.code(), e))?;
 
-    Ok(())
+    Ok(exit_status(result.as_ref()))
 }
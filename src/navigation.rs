@@ -0,0 +1,139 @@
+//! Going from a name in a program to its definition and to its other uses,
+//! for editors and the language server. A file is checked as the command
+//! line checks it, and names are resolved with the type checker's symbol
+//! table, so tools agree with the checker on which binding a name is. A file
+//! being edited often has type errors, so names are still resolved in one
+//! that does, and the errors are given with what was found.
+
+use crate::ast::{NodeId, Parser};
+use crate::lexer::Tokenizer;
+use crate::lexer::tokens::Span;
+use crate::packages;
+use crate::typechecker::{SymbolTable, TypeChecker};
+use std::path::Path;
+
+/// A place in a source file, as editors give it: a line and a column in
+/// characters, both from 1 like those of a `Span`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Position {
+    pub fn new(line: usize, column: usize) -> Self {
+        Self { line, column }
+    }
+}
+
+/// What looking a name up found, with the type errors checking the file
+/// reported, formatted as the command line formats them
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lookup<T> {
+    pub found: T,
+    pub diagnostics: Vec<String>,
+}
+
+/// Where the name at `position` in `file` is bound: the span of the name in
+/// its `let`, `fn`, parameter or pattern. `None` if there is no name there,
+/// or it is bound outside the file, as the prelude's names are.
+pub fn goto_definition(file: &Path, position: Position) -> Result<Lookup<Option<Span>>, String> {
+    let names = Names::of_file(file)?;
+    let found = names
+        .symbol_at(position)
+        .and_then(|definition| names.span(definition))
+        .cloned();
+    Ok(names.lookup(found))
+}
+
+/// The uses of the name at `position` in `file`, in source order, whether
+/// the position is on one of them or on where the name is bound. The
+/// binding itself is not included.
+pub fn find_references(file: &Path, position: Position) -> Result<Lookup<Vec<Span>>, String> {
+    let names = Names::of_file(file)?;
+    let found = match names.symbol_at(position) {
+        Some(definition) => names
+            .symbols
+            .references(definition)
+            .filter_map(|(reference, _)| names.span(reference))
+            .cloned()
+            .collect(),
+        None => Vec::new(),
+    };
+    Ok(names.lookup(found))
+}
+
+/// The names of a checked file: where the parser found each one, what the
+/// checker resolved them to, and the type errors it found on the way
+struct Names {
+    parser: Parser,
+    symbols: SymbolTable,
+    diagnostics: Vec<String>,
+}
+
+impl Names {
+    /// Only a file that cannot be read, tokenized or parsed is an error
+    fn of_file(file: &Path) -> Result<Self, String> {
+        let source = std::fs::read_to_string(file)
+            .map_err(|e| format!("Failed to read '{}': {}", file.display(), e))?;
+        let directory = file.parent().unwrap_or(Path::new(""));
+        let project = packages::load_project(directory)?;
+        let edition = project
+            .as_ref()
+            .and_then(|(_, manifest)| manifest.edition())
+            .unwrap_or_default();
+
+        let tokens = Tokenizer::new(&source)
+            .with_edition(edition)
+            .tokenize()
+            .map_err(|e| format!("Tokenization error[{}]: {}", e.code(), e))?;
+        let mut parser = Parser::new(tokens);
+        let program = parser
+            .parse()
+            .map_err(|e| format!("Parse error[{}]: {}", e.code(), e))?;
+
+        let mut checker = TypeChecker::new();
+        checker.set_edition(edition);
+        checker.set_current_directory(directory);
+        // Packages are looked for where they were fetched to, but never
+        // fetched just to look a name up
+        if let Some((root, _)) = &project {
+            checker.set_module_search_path(vec![root.join(packages::PACKAGES_DIRECTORY)]);
+        }
+        let diagnostics = checker
+            .check_program_recovering(&program)
+            .iter()
+            .map(|e| format!("Type error[{}]: {}", e.code(), e))
+            .collect();
+
+        Ok(Self {
+            parser,
+            symbols: checker.symbols().clone(),
+            diagnostics,
+        })
+    }
+
+    fn lookup<T>(self, found: T) -> Lookup<T> {
+        Lookup {
+            found,
+            diagnostics: self.diagnostics,
+        }
+    }
+
+    fn span(&self, id: NodeId) -> Option<&Span> {
+        self.parser.name_span(id)
+    }
+
+    /// The symbol of the name at `position`, which is either where it is
+    /// bound or a reference to it. A position just past the end of a name
+    /// is on it, as an editor's cursor is after typing it.
+    fn symbol_at(&self, position: Position) -> Option<NodeId> {
+        let (id, _) = self.parser.names().find(|(_, span)| {
+            span.line == position.line && (span.column..=span.end_column).contains(&position.column)
+        })?;
+        match self.symbols.symbol(id) {
+            Some(_) => Some(id),
+            None => self.symbols.definition(id),
+        }
+    }
+}
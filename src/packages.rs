@@ -0,0 +1,250 @@
+//! Packages: modules fetched from git repositories, declared as dependencies
+//! in a project's `corrosion.toml`:
+//!
+//! ```toml
+//! [dependencies]
+//! json = "https://github.com/someone/corrosion-json.git"
+//! ```
+//!
+//! Each dependency is cloned into `.corrosion/packages/<name>` next to the
+//! manifest. That directory is searched for imports that are not found next
+//! to the importing file, so `import "json" as json;` loads the package as a
+//! directory module.
+//!
+//! A `[package]` table may also give the edition of the language the
+//! project's programs are written in:
+//!
+//! ```toml
+//! [package]
+//! edition = "2025"
+//! ```
+
+use crate::lexer::Edition;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// File declaring a project's dependencies
+pub const MANIFEST: &str = "corrosion.toml";
+
+/// Where dependencies are fetched to, relative to the manifest
+pub const PACKAGES_DIRECTORY: &str = ".corrosion/packages";
+
+const DEPENDENCIES: &str = "[dependencies]";
+const PACKAGE: &str = "[package]";
+
+/// What a manifest declares: the project's edition and its dependencies
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Manifest {
+    edition: Option<Edition>,
+    dependencies: BTreeMap<String, String>,
+}
+
+impl Manifest {
+    /// Read a manifest. Only a `[package]` table with an `edition` and a
+    /// `[dependencies]` table of `name = "url"` lines are understood so far;
+    /// anything else is an error.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut manifest = Self::default();
+        let mut table = None;
+        for (number, line) in text.lines().enumerate() {
+            let error = |message: &str| format!("{} line {}: {}", MANIFEST, number + 1, message);
+            let line = strip_comment(line).trim();
+            if line.is_empty() {
+                continue;
+            }
+            if line.starts_with('[') {
+                if line != DEPENDENCIES && line != PACKAGE {
+                    return Err(error(&format!("unknown table {}", line)));
+                }
+                table = Some(line);
+                continue;
+            }
+            let (key, value) = line
+                .split_once('=')
+                .map(|(key, value)| (key.trim(), value.trim()))
+                .ok_or_else(|| error("expected key = \"value\""))?;
+            let value = unquote(value).ok_or_else(|| error("the value must be a quoted string"))?;
+            match table {
+                Some(PACKAGE) if key == "edition" => {
+                    let edition = Edition::from_name(&value)
+                        .ok_or_else(|| error(&format!("unknown edition '{}'", value)))?;
+                    if manifest.edition.replace(edition).is_some() {
+                        return Err(error("the edition is given twice"));
+                    }
+                }
+                Some(PACKAGE) => return Err(error(&format!("unknown key '{}'", key))),
+                Some(_) => {
+                    if !is_package_name(key) {
+                        return Err(error(&format!("'{}' is not a valid package name", key)));
+                    }
+                    if manifest
+                        .dependencies
+                        .insert(key.to_string(), value)
+                        .is_some()
+                    {
+                        return Err(error(&format!("'{}' is declared twice", key)));
+                    }
+                }
+                None => return Err(error("expected [package] or [dependencies]")),
+            }
+        }
+        Ok(manifest)
+    }
+
+    /// The edition the project's programs are written in, if given
+    pub fn edition(&self) -> Option<Edition> {
+        self.edition
+    }
+
+    /// Git URL of each dependency, by name
+    pub fn dependencies(&self) -> &BTreeMap<String, String> {
+        &self.dependencies
+    }
+}
+
+/// A line without its `#` comment, if it has one outside a string
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    let mut escaped = false;
+    for (index, c) in line.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            '#' if !in_string => return &line[..index],
+            _ => {}
+        }
+    }
+    line
+}
+
+/// `value` as a quoted string, with `\` and `"` escaped by a backslash
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// The contents of the quoted string `value`, undoing [`quote`]
+fn unquote(value: &str) -> Option<String> {
+    let mut chars = value.strip_prefix('"')?.chars();
+    let mut contents = String::new();
+    loop {
+        match chars.next()? {
+            '"' => return chars.as_str().is_empty().then_some(contents),
+            '\\' => match chars.next()? {
+                c @ ('\\' | '"') => contents.push(c),
+                _ => return None,
+            },
+            c => contents.push(c),
+        }
+    }
+}
+
+/// The name a package is imported by when none is given: the last part of
+/// its URL, without `.git`
+pub fn package_name(url: &str) -> Option<String> {
+    let last = url.trim_end_matches('/').rsplit(['/', ':']).next()?;
+    let name = last.strip_suffix(".git").unwrap_or(last);
+    is_package_name(name).then(|| name.to_string())
+}
+
+fn is_package_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+/// Add a dependency to the manifest text `manifest`, which may be empty,
+/// keeping the rest of it as written
+pub fn add_dependency(manifest: &str, name: &str, url: &str) -> Result<String, String> {
+    if !is_package_name(name) {
+        return Err(format!("'{}' is not a valid package name", name));
+    }
+    if Manifest::parse(manifest)?.dependencies.contains_key(name) {
+        return Err(format!("'{}' is already a dependency", name));
+    }
+    let entry = format!("{} = {}", name, quote(url));
+    let mut lines: Vec<&str> = manifest.lines().collect();
+    match lines.iter().position(|line| line.trim() == DEPENDENCIES) {
+        // After the last entry of the table, before any table following it
+        Some(start) => {
+            let end = lines[start + 1..]
+                .iter()
+                .position(|line| line.trim_start().starts_with('['))
+                .map_or(lines.len(), |offset| start + 1 + offset);
+            let last = lines[start..end]
+                .iter()
+                .rposition(|line| !strip_comment(line).trim().is_empty())
+                .map_or(start, |offset| start + offset);
+            lines.insert(last + 1, &entry);
+        }
+        None => lines.extend([DEPENDENCIES, &entry]),
+    }
+    Ok(lines.join("\n") + "\n")
+}
+
+/// The nearest directory from `start` upwards that has a manifest
+pub fn find_project(start: &Path) -> Option<PathBuf> {
+    // Relative paths are made absolute so their parents can be searched too
+    let start = match start.as_os_str().is_empty() {
+        true => std::path::absolute("."),
+        false => std::path::absolute(start),
+    }
+    .ok()?;
+    start
+        .ancestors()
+        .find(|directory| directory.join(MANIFEST).is_file())
+        .map(Path::to_path_buf)
+}
+
+/// Clone the project's dependencies that have not been fetched yet.
+/// `on_fetch` is called with each package's name and URL before it is cloned.
+pub fn fetch(
+    project: &Path,
+    manifest: &Manifest,
+    mut on_fetch: impl FnMut(&str, &str),
+) -> Result<(), String> {
+    let packages = project.join(PACKAGES_DIRECTORY);
+    for (name, url) in &manifest.dependencies {
+        let destination = packages.join(name);
+        if destination.exists() {
+            continue;
+        }
+        on_fetch(name, url);
+        let status = Command::new("git")
+            // `--` so a URL starting with `-` is not taken for an option
+            .args(["clone", "--quiet", "--depth", "1", "--", url])
+            .arg(&destination)
+            .status()
+            .map_err(|e| format!("Failed to run git to fetch '{}': {}", name, e))?;
+        if !status.success() {
+            return Err(format!("Failed to fetch '{}' from {}", name, url));
+        }
+    }
+    Ok(())
+}
+
+/// The project a program in `directory` belongs to, with its manifest.
+/// `None` outside a project.
+pub fn load_project(directory: &Path) -> Result<Option<(PathBuf, Manifest)>, String> {
+    let Some(project) = find_project(directory) else {
+        return Ok(None);
+    };
+    let path = project.join(MANIFEST);
+    let text = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read '{}': {}", path.display(), e))?;
+    let manifest = Manifest::parse(&text)?;
+    Ok(Some((project, manifest)))
+}
+
+/// Directories to search for imports in a program of `project`: its
+/// packages, fetched first if need be
+pub fn search_path(
+    project: &Path,
+    manifest: &Manifest,
+    on_fetch: impl FnMut(&str, &str),
+) -> Result<Vec<PathBuf>, String> {
+    fetch(project, manifest, on_fetch)?;
+    Ok(vec![project.join(PACKAGES_DIRECTORY)])
+}
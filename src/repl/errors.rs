@@ -0,0 +1,167 @@
+use crate::ast::parser::ParseError;
+use crate::interpreter::InterpreterError;
+use crate::lexer::tokenizer::TokenizeError;
+use crate::lexer::tokens::Span;
+use crate::typechecker::{TypeError, TypeWarning};
+use std::io::IsTerminal;
+
+/// An error from one stage of evaluating REPL input
+#[derive(Debug)]
+pub enum ReplError {
+    Tokenize(TokenizeError),
+    Parse(ParseError),
+    Type(TypeError),
+    Runtime(InterpreterError),
+}
+
+impl ReplError {
+    fn header(&self) -> (&'static str, &'static str) {
+        // (label, ANSI color)
+        match self {
+            ReplError::Tokenize(_) | ReplError::Parse(_) => ("syntax error", "\x1b[1;35m"),
+            ReplError::Type(_) => ("type error", "\x1b[1;33m"),
+            ReplError::Runtime(_) => ("runtime error", "\x1b[1;31m"),
+        }
+    }
+
+    fn span(&self) -> Option<&Span> {
+        match self {
+            ReplError::Tokenize(error) => Some(error.span()),
+            ReplError::Parse(error) => error.span(),
+            ReplError::Type(error) => Some(error.span()),
+            ReplError::Runtime(error) => error.span(),
+        }
+    }
+
+    /// The name that could not be found at runtime, for did-you-mean
+    /// suggestions. Type errors already carry their own suggestion.
+    pub fn undefined_name(&self) -> Option<&str> {
+        match self {
+            ReplError::Runtime(InterpreterError::UndefinedVariable { name, .. }) => Some(name),
+            _ => None,
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            ReplError::Tokenize(error) => error.code(),
+            ReplError::Parse(error) => error.code(),
+            ReplError::Type(error) => error.code(),
+            ReplError::Runtime(error) => error.code(),
+        }
+    }
+
+    /// Render the error for the terminal: a colored header, the offending
+    /// part of `source` underlined, and an optional suggestion
+    pub fn render(&self, source: &str, suggestion: Option<&str>) -> String {
+        let color = use_color();
+        let paint = |code: &str, text: &str| {
+            if color {
+                format!("{}{}\x1b[0m", code, text)
+            } else {
+                text.to_string()
+            }
+        };
+
+        let (label, code) = self.header();
+        let header = format!("{}[{}]", label, self.code());
+        let mut output = format!("{}: {}", paint(code, &header), self);
+
+        if let Some(span) = self.span() {
+            output.push_str(&underline(source, span, |text| paint(code, text)));
+        }
+
+        if let Some(suggestion) = suggestion {
+            output.push_str(&format!(
+                "\n{}: did you mean `{}`?",
+                paint("\x1b[1;36m", "help"),
+                suggestion
+            ));
+        }
+        output
+    }
+}
+
+impl std::fmt::Display for ReplError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReplError::Tokenize(error) => write!(f, "{}", error),
+            ReplError::Parse(error) => write!(f, "{}", error),
+            ReplError::Type(error) => write!(f, "{}", error),
+            ReplError::Runtime(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+/// Render a type checker warning for the terminal, like an error, with the
+/// declaration it is about underlined
+pub fn render_warning(warning: &TypeWarning, source: &str) -> String {
+    let color = use_color();
+    let paint = |text: &str| {
+        if color {
+            format!("\x1b[1;33m{}\x1b[0m", text)
+        } else {
+            text.to_string()
+        }
+    };
+    let header = format!("warning[{}]", warning.code());
+    let mut output = format!("{}: {}", paint(&header), warning);
+    output.push_str(&underline(source, warning.span(), paint));
+    output
+}
+
+/// Spans covering more lines than this show only their first two lines and
+/// their last
+const MAX_UNDERLINED_LINES: usize = 4;
+
+/// The lines of `source` that `span` covers, each followed by a line of `^`
+/// under the part inside the span. Leading indentation on lines after the
+/// first is not underlined.
+fn underline(source: &str, span: &Span, paint: impl Fn(&str) -> String) -> String {
+    let lines: Vec<&str> = source.lines().collect();
+    if span.line == 0 || span.line > lines.len() {
+        return String::new();
+    }
+    let last = span.end_line.clamp(span.line, lines.len());
+    let width = last.to_string().len();
+    let gutter = " ".repeat(width);
+    let elide = last - span.line + 1 > MAX_UNDERLINED_LINES;
+
+    let mut output = format!("\n{} |", gutter);
+    for number in span.line..=last {
+        if elide && number > span.line + 1 && number < last {
+            if number == span.line + 2 {
+                output.push_str("\n...");
+            }
+            continue;
+        }
+        let line = lines[number - 1];
+        let length = line.chars().count();
+        let start = if number == span.line {
+            span.column.saturating_sub(1).min(length)
+        } else {
+            line.chars().take_while(|c| c.is_whitespace()).count()
+        };
+        let end = if number == span.end_line {
+            span.end_column.saturating_sub(1).min(length)
+        } else {
+            length
+        };
+        let carets = end.saturating_sub(start).clamp(1, (length - start).max(1));
+        output.push_str(&format!(
+            "\n{:>width$} | {}\n{} | {}{}",
+            number,
+            line,
+            gutter,
+            " ".repeat(start),
+            paint(&"^".repeat(carets)),
+            width = width
+        ));
+    }
+    output
+}
+
+/// Color output only on a terminal, and never when NO_COLOR is set
+pub(super) fn use_color() -> bool {
+    std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none()
+}
@@ -0,0 +1,170 @@
+//! Highlighting of the line being typed at the prompt. Keywords, literals
+//! and comments are colored by how the lexer reads them, and the bracket
+//! under or just before the cursor is shown together with its match.
+
+use crate::lexer::{Token, TokenWithSpan, Tokenizer};
+use rustyline::Helper;
+use rustyline::completion::Completer;
+use rustyline::highlight::{CmdKind, Highlighter};
+use rustyline::hint::Hinter;
+use rustyline::validate::Validator;
+use std::borrow::Cow;
+use std::cell::Cell;
+
+const KEYWORD: &str = "\x1b[1;35m";
+const TYPE_NAME: &str = "\x1b[33m";
+const NUMBER: &str = "\x1b[36m";
+const STRING: &str = "\x1b[32m";
+const COMMENT: &str = "\x1b[2m";
+const MATCHING_BRACKET: &str = "\x1b[1;4;34m";
+const RESET: &str = "\x1b[0m";
+
+/// The line editor's helper, which only highlights
+#[derive(Default)]
+pub(crate) struct ReplHelper {
+    /// Whether to show the matching bracket; not on the line left behind
+    /// once it is entered
+    show_brackets: Cell<bool>,
+}
+
+impl Helper for ReplHelper {}
+
+impl Completer for ReplHelper {
+    type Candidate = String;
+}
+
+impl Hinter for ReplHelper {
+    type Hint = String;
+}
+
+impl Validator for ReplHelper {}
+
+impl Highlighter for ReplHelper {
+    fn highlight<'l>(&self, line: &'l str, pos: usize) -> Cow<'l, str> {
+        if !super::errors::use_color() {
+            return Cow::Borrowed(line);
+        }
+        let cursor = self.show_brackets.get().then_some(pos);
+        Cow::Owned(highlight(line, cursor))
+    }
+
+    fn highlight_char(&self, _line: &str, _pos: usize, kind: CmdKind) -> bool {
+        // Any edit or cursor move can change the colors or the bracket shown
+        self.show_brackets.set(kind != CmdKind::ForcedRefresh);
+        true
+    }
+}
+
+/// `line` with ANSI colors, and the bracket at `cursor` and its match
+/// marked if there is a cursor
+pub(crate) fn highlight(line: &str, cursor: Option<usize>) -> String {
+    let tokens = Tokenizer::new(line).tokenize_prefix();
+    let brackets = cursor.and_then(|cursor| matching_brackets(&tokens, cursor));
+
+    let mut output = String::with_capacity(line.len() * 2);
+    let mut end = 0;
+    for (index, TokenWithSpan { token, span }) in tokens.iter().enumerate() {
+        // Between tokens there is only whitespace and comments
+        paint(
+            &mut output,
+            &line[end..span.start],
+            gap_color(&line[end..span.start]),
+        );
+        let color = if brackets.is_some_and(|brackets| brackets.contains(&index)) {
+            Some(MATCHING_BRACKET)
+        } else {
+            token_color(token)
+        };
+        paint(&mut output, &line[span.start..span.end], color);
+        end = span.end;
+    }
+
+    // What is left did not lex, such as a string still being typed
+    let rest = &line[end..];
+    let unlexed = rest.trim_start();
+    let color = match unlexed.chars().next() {
+        Some('"') => Some(STRING),
+        Some('b' | 'r') if unlexed[1..].trim_start_matches('#').starts_with('"') => Some(STRING),
+        Some('/') if unlexed.starts_with("/*") || unlexed.starts_with("//") => Some(COMMENT),
+        _ => None,
+    };
+    output.push_str(&rest[..rest.len() - unlexed.len()]);
+    paint(&mut output, unlexed, color);
+    output
+}
+
+fn paint(output: &mut String, text: &str, color: Option<&str>) {
+    match color {
+        Some(color) if !text.is_empty() => {
+            output.push_str(color);
+            output.push_str(text);
+            output.push_str(RESET);
+        }
+        _ => output.push_str(text),
+    }
+}
+
+fn gap_color(gap: &str) -> Option<&'static str> {
+    (!gap.trim().is_empty()).then_some(COMMENT)
+}
+
+fn token_color(token: &Token) -> Option<&'static str> {
+    match token {
+        Token::Number(_) | Token::BigNumber(_) | Token::True | Token::False => Some(NUMBER),
+        Token::StringLiteral(_) | Token::BytesLiteral(_) => Some(STRING),
+        Token::Int
+        | Token::Bool
+        | Token::String
+        | Token::List
+        | Token::Handle
+        | Token::Channel
+        | Token::Rec => Some(TYPE_NAME),
+        _ if token.keyword().is_some() => Some(KEYWORD),
+        _ => None,
+    }
+}
+
+/// Indices of the bracket token under or just before `cursor` and of the
+/// bracket matching it, if it has one
+fn matching_brackets(tokens: &[TokenWithSpan], cursor: usize) -> Option<[usize; 2]> {
+    let at = |offset: usize| {
+        tokens
+            .iter()
+            .position(|token| token.span.start == offset && bracket(&token.token).is_some())
+    };
+    let index = at(cursor).or_else(|| at(cursor.checked_sub(1)?))?;
+    let (kind, opens) = bracket(&tokens[index].token)?;
+
+    let mut depth = 0;
+    let mut step = |candidate: usize| {
+        let (other, other_opens) = bracket(&tokens[candidate].token)?;
+        if other != kind {
+            return None;
+        }
+        if other_opens == opens {
+            depth += 1;
+            return None;
+        }
+        depth -= 1;
+        (depth == 0).then_some(candidate)
+    };
+    let found = if opens {
+        (index..tokens.len()).find_map(&mut step)
+    } else {
+        (0..=index).rev().find_map(&mut step)
+    }?;
+    Some([index, found])
+}
+
+/// Which kind of bracket a token is, and whether it opens
+fn bracket(token: &Token) -> Option<(char, bool)> {
+    match token {
+        Token::LeftParen => Some(('(', true)),
+        Token::RightParen => Some(('(', false)),
+        Token::LeftBracket => Some(('[', true)),
+        Token::RightBracket => Some(('[', false)),
+        Token::LeftBrace => Some(('{', true)),
+        Token::RightBrace => Some(('{', false)),
+        _ => None,
+    }
+}
@@ -1,8 +1,26 @@
-use crate::ast::Parser;
-use crate::interpreter::Interpreter;
-use crate::lexer::Tokenizer;
-use crate::typechecker::TypeChecker;
-use std::io::{self, Write};
+pub(crate) mod errors;
+mod highlight;
+
+#[cfg(test)]
+mod tests;
+
+use crate::ast::{Parser, Program, Spanned, Statement};
+use crate::interpreter::{
+    BindingChange, DEFAULT_MAX_CALL_DEPTH, ExecOptions, Interpreter, Snapshot, Value,
+};
+use crate::lexer::{Edition, Span, Token, Tokenizer};
+use crate::typechecker::{Constant, Type, TypeChecker, TypeCompatibility};
+use errors::ReplError;
+use highlight::ReplHelper;
+use rustyline::error::ReadlineError;
+use rustyline::history::DefaultHistory;
+use rustyline::{Editor, config::Configurer};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Script run when the REPL starts, looked up in the home directory
+const STARTUP_SCRIPT: &str = ".corrosionrc";
 
 pub struct Repl {
     /// REPL version
@@ -11,14 +29,136 @@ pub struct Repl {
     interpreter: Interpreter,
     /// Type checker instance that maintains type bindings across evaluations
     type_checker: TypeChecker,
+    /// Prompt shown before a new entry; `{line}` becomes the line number
+    prompt: String,
+    /// Prompt shown while an entry continues over several lines
+    continuation_prompt: String,
+    /// Lines read so far in this session
+    line_number: usize,
+    /// An entry still waiting for its closing bracket, string or comment
+    pending: String,
+    /// Whether `:paste` is collecting lines until `:end`
+    pasting: bool,
+    /// Set by Ctrl-C to stop the entry being evaluated
+    interrupt: Arc<AtomicBool>,
+    /// Source of each definition entered, oldest first
+    definitions: Vec<Definition>,
+    /// Entries that ran successfully, numbering the definitions
+    entries: usize,
+    /// The environment before any entry, which `:env` compares against
+    startup: Snapshot,
+}
+
+/// A top level `let`, `fn` or `import` as it was entered
+struct Definition {
+    name: String,
+    source: String,
+    /// The entry it was made in, so `:undo` can remove the entry's
+    /// definitions together
+    entry: usize,
+    /// What `:undo` puts back, or `None` for imports, which stay
+    undo: Option<Replaced>,
+}
+
+/// The binding a `let`, `const` or `fn` replaced, if there was one
+enum Replaced {
+    Nothing,
+    /// The value and type, and the value the checker had computed if the
+    /// binding was a constant
+    Binding(Value, Type, Option<Constant>),
+}
+
+/// What to do after reading a line
+#[derive(Debug, PartialEq)]
+enum Step {
+    /// Leave the REPL
+    Exit,
+    /// Nothing to evaluate yet
+    Continue,
+    /// A complete entry to evaluate
+    Evaluate(String),
 }
 
 impl Repl {
     pub fn new() -> Self {
+        Self::with_session(Self::interpreter(true), TypeChecker::new())
+    }
+
+    /// A REPL without the prelude, started with `--no-prelude`
+    pub fn without_prelude() -> Self {
+        Self::with_session(Self::interpreter(false), TypeChecker::without_prelude())
+    }
+
+    /// Runaway recursion in an entry is an error rather than a stack
+    /// overflow that would end the session
+    fn interpreter(prelude: bool) -> Interpreter {
+        Interpreter::builder()
+            .prelude(prelude)
+            .max_call_depth(DEFAULT_MAX_CALL_DEPTH)
+            .build()
+    }
+
+    fn with_session(interpreter: Interpreter, mut type_checker: TypeChecker) -> Self {
+        // Entering a definition again replaces it
+        type_checker.set_allow_redefinition(true);
+        type_checker.set_strict(Edition::default().requires_precise_types());
+        let startup = interpreter.environment().snapshot();
         Self {
             version: env!("CARGO_PKG_VERSION"),
-            interpreter: Interpreter::new(),
-            type_checker: TypeChecker::new(),
+            interpreter,
+            type_checker,
+            prompt: "> ".to_string(),
+            continuation_prompt: "... ".to_string(),
+            line_number: 0,
+            pending: String::new(),
+            pasting: false,
+            interrupt: Arc::new(AtomicBool::new(false)),
+            definitions: Vec::new(),
+            entries: 0,
+            startup,
+        }
+    }
+
+    /// The startup script to run when none is given with `--init`, if the
+    /// user has one
+    pub fn default_startup_script() -> Option<PathBuf> {
+        let home = std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE"))?;
+        let path = Path::new(&home).join(STARTUP_SCRIPT);
+        path.is_file().then_some(path)
+    }
+
+    /// Run a startup script as if its lines were typed at the prompt, without
+    /// printing results. Errors are reported and the rest of the script still
+    /// runs; only failing to read the file is returned as an error.
+    pub fn run_startup_script(&mut self, path: &Path) -> Result<(), String> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read '{}': {}", path.display(), e))?;
+
+        for line in contents.lines() {
+            match self.accept_line(line) {
+                Step::Exit => break,
+                Step::Continue => {}
+                Step::Evaluate(entry) => self.evaluate_quietly(&entry, path),
+            }
+        }
+        self.pasting = false;
+        if !self.pending.is_empty() {
+            self.line_number += 1;
+            let entry = std::mem::take(&mut self.pending);
+            self.evaluate_quietly(&entry, path);
+        }
+        // Line numbers in the prompt count from the start of the session
+        self.line_number = 0;
+        Ok(())
+    }
+
+    /// Evaluate an entry from a startup script that ended on the line just
+    /// read, reporting any error with the line it started on
+    fn evaluate_quietly(&mut self, entry: &str, path: &Path) {
+        if let Err(error) = self.process_content(entry) {
+            let first_line = self.line_number + 1 - entry.lines().count().max(1);
+            println!("In {}, line {}:", path.display(), first_line);
+            println!("{}", self.render_error(&error, entry));
         }
     }
 
@@ -26,50 +166,139 @@ impl Repl {
         println!("Corrosion Language REPL v{}", self.version);
         println!("Type 'exit' or 'quit' to exit\n");
 
-        let mut input = String::new();
-
-        loop {
-            print!("> ");
-            io::stdout().flush().unwrap();
+        // Lines are edited with history and highlighted as they are typed
+        let mut editor = match Editor::<ReplHelper, DefaultHistory>::new() {
+            Ok(editor) => editor,
+            Err(error) => {
+                println!("Error starting the line editor: {}", error);
+                return;
+            }
+        };
+        editor.set_helper(Some(ReplHelper::default()));
+        editor.set_auto_add_history(true);
 
-            input.clear();
-            match io::stdin().read_line(&mut input) {
-                Ok(_) => {
-                    let line = input.trim();
+        // Ctrl-C stops the running entry instead of the whole REPL. This comes
+        // after the editor is created, as on a terminal it installs a SIGINT
+        // handler of its own that would replace this one.
+        let interrupt = self.interrupt.clone();
+        if let Err(error) = ctrlc::set_handler(move || interrupt.store(true, Ordering::Relaxed)) {
+            println!("Ctrl-C will exit the REPL: {}", error);
+        }
 
-                    if line == "exit" || line == "quit" {
+        loop {
+            match editor.readline(&self.current_prompt()) {
+                Ok(line) => match self.accept_line(&line) {
+                    Step::Exit => {
                         println!("Goodbye!");
                         break;
                     }
-
-                    if line.is_empty() {
-                        continue;
-                    }
-
-                    if self.handle_command(line) {
-                        continue;
-                    }
-
-                    match self.process_line(line) {
-                        Ok(result) => {
-                            if !result.is_empty() && result != "()" {
-                                println!("{}", result);
-                            }
-                        }
-                        Err(error) => println!("Error: {}", error),
+                    Step::Continue => {}
+                    Step::Evaluate(entry) => self.evaluate_and_print(&entry),
+                },
+                // Ctrl-C at the prompt drops the unfinished entry
+                Err(ReadlineError::Interrupted) => {
+                    self.pending.clear();
+                    self.pasting = false;
+                }
+                // Ctrl-D ends a paste; reading can carry on after it
+                Err(ReadlineError::Eof) if self.pasting => {
+                    if let Step::Evaluate(entry) = self.end_paste() {
+                        self.evaluate_and_print(&entry);
                     }
                 }
+                // End of input
+                Err(ReadlineError::Eof) => break,
                 Err(error) => {
                     println!("Error reading input: {}", error);
                     break;
                 }
             }
         }
+        // Threads started in the session finish before the REPL exits
+        self.interpreter.release_handles();
+    }
+
+    fn evaluate_and_print(&mut self, entry: &str) {
+        match self.process_content(entry) {
+            Ok(result) => {
+                if !result.is_empty() && result != "()" {
+                    println!("{}", result);
+                }
+            }
+            Err(error) => println!("{}", self.render_error(&error, entry)),
+        }
+    }
+
+    /// The prompt for the next line, with its placeholders filled in
+    fn current_prompt(&self) -> String {
+        let template = if self.pending.is_empty() && !self.pasting {
+            &self.prompt
+        } else {
+            &self.continuation_prompt
+        };
+        template.replace("{line}", &(self.line_number + 1).to_string())
+    }
+
+    /// Take one line of input. Commands run straight away; code is collected
+    /// until it no longer stops inside a bracket, string or comment. A blank
+    /// line submits an unfinished entry as it is, to show what is wrong.
+    fn accept_line(&mut self, line: &str) -> Step {
+        self.line_number += 1;
+
+        // A paste takes every line as it is, blank ones included
+        if self.pasting {
+            if line.trim() == ":end" {
+                return self.end_paste();
+            }
+            self.pending.push_str(line);
+            self.pending.push('\n');
+            return Step::Continue;
+        }
+
+        if self.pending.is_empty() {
+            let trimmed = line.trim();
+            if trimmed == "exit" || trimmed == "quit" {
+                return Step::Exit;
+            }
+            if trimmed.is_empty() || self.handle_command(trimmed) {
+                return Step::Continue;
+            }
+        } else if line.trim().is_empty() {
+            return Step::Evaluate(std::mem::take(&mut self.pending));
+        }
+
+        if !self.pending.is_empty() {
+            self.pending.push('\n');
+        }
+        self.pending.push_str(line);
+        if is_incomplete(&self.pending) {
+            Step::Continue
+        } else {
+            Step::Evaluate(std::mem::take(&mut self.pending))
+        }
+    }
+
+    /// Leave paste mode, submitting what was pasted as one entry
+    fn end_paste(&mut self) -> Step {
+        self.pasting = false;
+        let entry = std::mem::take(&mut self.pending);
+        if entry.trim().is_empty() {
+            Step::Continue
+        } else {
+            Step::Evaluate(entry)
+        }
     }
 
     fn handle_command(&mut self, line: &str) -> bool {
         if let Some(cmd) = line.strip_prefix(':') {
             match cmd {
+                "paste" => {
+                    println!(
+                        "Paste mode: enter ':end' on a line of its own, or press Ctrl-D, to finish"
+                    );
+                    self.pasting = true;
+                    true
+                }
                 "help" => {
                     self.show_help();
                     true
@@ -79,6 +308,72 @@ impl Repl {
                     print!("{}[2J{}[H", 27 as char, 27 as char);
                     true
                 }
+                _ if cmd.starts_with("explain ") => {
+                    let code = cmd.strip_prefix("explain ").unwrap().trim();
+                    println!("{}", crate::explain::render(code));
+                    true
+                }
+                _ if cmd.starts_with("reload-module ") => {
+                    let name = cmd.strip_prefix("reload-module ").unwrap().trim();
+                    match self.reload_module(name) {
+                        Ok(changes) => {
+                            println!("Reloaded module '{}'", name);
+                            for change in changes {
+                                println!("  warning: {}", change);
+                            }
+                        }
+                        Err(error) => println!("Error reloading module: {}", error),
+                    }
+                    true
+                }
+                "list" => {
+                    if self.definitions.is_empty() {
+                        println!("No definitions yet");
+                    } else {
+                        println!("{}", self.list_definitions());
+                    }
+                    true
+                }
+                "env" => {
+                    match self.session_bindings().as_str() {
+                        "" => println!("Nothing bound yet"),
+                        bindings => println!("{}", bindings),
+                    }
+                    true
+                }
+                "undo" => {
+                    match self.undo().as_slice() {
+                        [] => println!("Nothing to undo"),
+                        names => println!("Removed {}", names.join(", ")),
+                    }
+                    true
+                }
+                _ if cmd.starts_with("forget ") => {
+                    let name = cmd.strip_prefix("forget ").unwrap().trim();
+                    if self.forget(name) {
+                        println!("Removed {}", name);
+                    } else {
+                        println!("No definition named '{}' to forget", name);
+                    }
+                    true
+                }
+                _ if cmd.starts_with("source ") => {
+                    let name = cmd.strip_prefix("source ").unwrap().trim();
+                    match self.definition_source(name) {
+                        Some(source) => println!("{}", source),
+                        None => println!("No definition named '{}'", name),
+                    }
+                    true
+                }
+                _ if cmd.starts_with("prompt ") => {
+                    self.prompt = prompt_template(cmd.strip_prefix("prompt ").unwrap());
+                    true
+                }
+                _ if cmd.starts_with("continuation-prompt ") => {
+                    let template = cmd.strip_prefix("continuation-prompt ").unwrap();
+                    self.continuation_prompt = prompt_template(template);
+                    true
+                }
                 _ if cmd.starts_with("load ") => {
                     let filename = cmd.strip_prefix("load ").unwrap().trim();
                     match self.load_file(filename) {
@@ -113,6 +408,16 @@ impl Repl {
         println!("  help, :help       - Show this help message");
         println!("  clear, :clear     - Clear the screen");
         println!("  :load <filename>  - Load and execute a Corrosion file");
+        println!("  :paste            - Read lines until ':end' or Ctrl-D, then run them at once");
+        println!("  :reload-module <name> - Re-import a module after editing its file");
+        println!("  :explain <code>   - Describe an error code such as E0001");
+        println!("  :list             - Show the source of every definition so far");
+        println!("  :source <name>    - Show the source of the latest definition of a name");
+        println!("  :env              - Show the values bound since the session started");
+        println!("  :undo             - Remove the definitions made by the latest entry");
+        println!("  :forget <name>    - Remove the latest definition of a name");
+        println!("  :prompt <text>    - Set the prompt; {{line}} shows the line number");
+        println!("  :continuation-prompt <text> - Set the prompt for unfinished input");
         println!("  exit, quit        - Exit the REPL");
         println!("  <expression>      - Evaluate a Corrosion expression");
         println!();
@@ -127,38 +432,363 @@ impl Repl {
 
         // Process the file contents using the same pipeline as process_line
         match self.process_content(&contents) {
-            Ok(_result) => Ok(format!("loaded")),
+            Ok(_result) => Ok("loaded".to_string()),
             Err(error) => Err(format!("Error processing '{}': {}", filename, error)),
         }
     }
 
-    fn process_content(&mut self, content: &str) -> Result<String, String> {
+    /// Import a module again under the same name, replacing its binding.
+    /// Returns a description of each export whose type changed incompatibly.
+    fn reload_module(&mut self, name: &str) -> Result<Vec<String>, String> {
+        let loader = self.type_checker.get_module_loader();
+        let path = loader
+            .get_module_path(name)
+            .cloned()
+            .ok_or_else(|| format!("No module named '{}' has been imported", name))?;
+        let old_exports = loader.get_module_exports(name).cloned().unwrap_or_default();
+
+        // The import is built directly rather than written out as source, so
+        // the path is used as stored whatever characters it contains
+        let span = Span::new(0, 0, 1, 1);
+        let import = Program {
+            statements: vec![Statement::Import {
+                path,
+                alias: Some(name.to_string()),
+                exported: false,
+                span: span.clone(),
+            }],
+            span,
+        };
+        self.run_program(&import, None).map_err(|e| e.to_string())?;
+
+        let new_exports = self
+            .type_checker
+            .get_module_loader()
+            .get_module_exports(name)
+            .cloned()
+            .unwrap_or_default();
+
+        let mut changes: Vec<String> = old_exports
+            .iter()
+            .filter_map(|(export, old_type)| match new_exports.get(export) {
+                None => Some(format!("'{}' was removed", export)),
+                Some(new_type) if !TypeCompatibility::types_compatible(old_type, new_type) => {
+                    Some(format!(
+                        "'{}' changed type from {} to {}",
+                        export, old_type, new_type
+                    ))
+                }
+                Some(_) => None,
+            })
+            .collect();
+        changes.sort();
+        Ok(changes)
+    }
+
+    fn process_content(&mut self, content: &str) -> Result<String, ReplError> {
         // Step 1: Tokenize the input using the tokenizer
-        let mut tokenizer = Tokenizer::new("");
-        let tokens = tokenizer.tokenize(content).map_err(|e| e.to_string())?;
+        let tokens = Tokenizer::new(content)
+            .tokenize()
+            .map_err(ReplError::Tokenize)?;
 
         // Step 2: Parse tokens into an AST
         let mut parser = Parser::new(tokens);
-        let program = parser.parse().map_err(|e| e.to_string())?;
+        let program = parser.parse().map_err(ReplError::Parse)?;
 
-        // Step 3: Type check the AST using persistent type checker
-        let _typed_program = self
-            .type_checker
-            .check_program(&program)
-            .map_err(|e| e.to_string())?;
+        self.run_program(&program, Some(content))
+    }
+
+    /// Type check and run a parsed entry, where `content` is its source. A
+    /// program with no source, such as a reloaded import, is not an entry
+    /// `:list` shows.
+    fn run_program(
+        &mut self,
+        program: &Program,
+        content: Option<&str>,
+    ) -> Result<String, ReplError> {
+        // What each definition replaces, for undoing it later
+        let replaced: Vec<Option<Replaced>> = program
+            .statements
+            .iter()
+            .map(|statement| match statement {
+                Statement::VariableDeclaration { name, .. }
+                | Statement::ConstantDeclaration { name, .. }
+                | Statement::FunctionDeclaration { name, .. } => Some(self.binding_of(name)),
+                _ => None,
+            })
+            .collect();
+
+        // Step 3: Type check the AST using persistent type checker. Warnings
+        // are taken either way so they are not reported with the next entry.
+        let checked = self.type_checker.check_program(program);
+        let warnings = self.type_checker.take_warnings();
+        checked.map_err(ReplError::Type)?;
+        for warning in warnings {
+            println!(
+                "{}",
+                errors::render_warning(&warning, content.unwrap_or_default())
+            );
+        }
 
-        // Step 4: Execute the program with the interpreter
+        // Step 4: Execute the program with the interpreter. A Ctrl-C pressed
+        // at the prompt is not meant for this entry.
+        self.interrupt.store(false, Ordering::Relaxed);
+        let options = ExecOptions {
+            interrupt: Some(self.interrupt.clone()),
+            ..ExecOptions::repl()
+        };
         let result = self
             .interpreter
-            .interpret_program_repl(&program)
-            .map_err(|e| e.to_string())?;
+            .execute(program, &options)
+            .map_err(ReplError::Runtime)?;
 
+        if let Some(content) = content {
+            self.record_definitions(&program.statements, replaced, content);
+        }
         Ok(format!("{}", result))
     }
 
-    fn process_line(&mut self, input: &str) -> Result<String, String> {
-        self.process_content(input)
+    /// Remember the source of each definition in a successfully run entry
+    fn record_definitions(
+        &mut self,
+        statements: &[Statement],
+        replaced: Vec<Option<Replaced>>,
+        content: &str,
+    ) {
+        self.entries += 1;
+        for (statement, undo) in statements.iter().zip(replaced) {
+            let name = match statement {
+                Statement::VariableDeclaration { name, .. }
+                | Statement::ConstantDeclaration { name, .. }
+                | Statement::EnumDeclaration { name, .. }
+                | Statement::FunctionDeclaration { name, .. } => name,
+                Statement::Import { path, alias, .. } => alias.as_ref().unwrap_or(path),
+                Statement::Expression { .. } | Statement::Return { .. } => continue,
+            };
+            let span = statement.span();
+            if let Some(source) = content.get(span.start..span.end) {
+                self.definitions.push(Definition {
+                    name: name.clone(),
+                    source: source.to_string(),
+                    entry: self.entries,
+                    undo,
+                });
+            }
+        }
+    }
+
+    /// The current top level binding of `name`
+    fn binding_of(&self, name: &str) -> Replaced {
+        let value = self.interpreter.environment().lookup(name);
+        let ty = self.type_checker.get_environment().lookup(name);
+        match (value, ty) {
+            (Some(value), Some(ty)) => Replaced::Binding(
+                value.clone(),
+                ty.clone(),
+                self.type_checker.constant(name).cloned(),
+            ),
+            _ => Replaced::Nothing,
+        }
+    }
+
+    /// Remove the `let` and `fn` definitions of the latest entry that made
+    /// any, putting back whatever they replaced. Returns the names removed.
+    fn undo(&mut self) -> Vec<String> {
+        let Some(entry) = self
+            .definitions
+            .iter()
+            .rev()
+            .find(|definition| definition.undo.is_some())
+            .map(|definition| definition.entry)
+        else {
+            return Vec::new();
+        };
+
+        let mut removed = Vec::new();
+        // Newest first, so a name defined twice ends up as it was before
+        while let Some(index) = self
+            .definitions
+            .iter()
+            .rposition(|definition| definition.entry == entry && definition.undo.is_some())
+        {
+            let definition = self.definitions.remove(index);
+            removed.push(definition.name.clone());
+            self.restore(definition);
+        }
+        removed.reverse();
+        removed
+    }
+
+    /// Remove the latest `let` or `fn` definition of `name`, putting back
+    /// whatever it replaced
+    fn forget(&mut self, name: &str) -> bool {
+        let Some(index) = self
+            .definitions
+            .iter()
+            .rposition(|definition| definition.name == name && definition.undo.is_some())
+        else {
+            return false;
+        };
+        let definition = self.definitions.remove(index);
+        self.restore(definition);
+        true
+    }
+
+    fn restore(&mut self, definition: Definition) {
+        match definition.undo {
+            Some(Replaced::Binding(value, ty, constant)) => {
+                self.interpreter
+                    .environment_mut()
+                    .bind(definition.name.clone(), value);
+                self.type_checker.set_constant(&definition.name, constant);
+                self.type_checker
+                    .get_environment_mut()
+                    .bind(definition.name, ty);
+            }
+            Some(Replaced::Nothing) => {
+                self.interpreter.environment_mut().unbind(&definition.name);
+                self.type_checker.set_constant(&definition.name, None);
+                self.type_checker
+                    .get_environment_mut()
+                    .unbind(&definition.name);
+            }
+            None => {}
+        }
+    }
+
+    /// Every definition so far, in the order entered, ready to paste into a file
+    fn list_definitions(&self) -> String {
+        let sources: Vec<&str> = self
+            .definitions
+            .iter()
+            .map(|definition| definition.source.as_str())
+            .collect();
+        sources.join("\n")
+    }
+
+    /// Each name bound or rebound since the session started, with its value
+    fn session_bindings(&self) -> String {
+        let lines: Vec<String> = self
+            .startup
+            .environment()
+            .diff(self.interpreter.environment())
+            .into_iter()
+            .map(|change| match change {
+                BindingChange::Added { name, value }
+                | BindingChange::Changed {
+                    name, new: value, ..
+                } => {
+                    format!("{} = {}", name, value)
+                }
+                BindingChange::Removed { name, .. } => format!("{} (removed)", name),
+            })
+            .collect();
+        lines.join("\n")
+    }
+
+    /// Source of the latest definition of `name`
+    fn definition_source(&self, name: &str) -> Option<&str> {
+        self.definitions
+            .iter()
+            .rev()
+            .find(|definition| definition.name == name)
+            .map(|definition| definition.source.as_str())
+    }
+
+    /// Format an error for display, suggesting a similar name in scope when
+    /// the error is about an undefined one
+    fn render_error(&self, error: &ReplError, source: &str) -> String {
+        let suggestion = error
+            .undefined_name()
+            .and_then(|name| self.type_checker.suggest_name(name));
+        error.render(source, suggestion.as_deref())
+    }
+}
+
+/// A prompt as given to `:prompt`. Quotes keep surrounding spaces; without
+/// them a space is added to separate the prompt from the input.
+fn prompt_template(text: &str) -> String {
+    let text = text.trim();
+    match text
+        .strip_prefix('"')
+        .and_then(|text| text.strip_suffix('"'))
+    {
+        Some(quoted) => quoted.to_string(),
+        None => format!("{} ", text),
+    }
+}
+
+/// Whether `source` stops inside a bracket, string or block comment, so more
+/// lines are needed before it can be evaluated
+fn is_incomplete(source: &str) -> bool {
+    if ends_inside_string_or_comment(source) {
+        return true;
+    }
+    // Other tokenize errors are for the evaluation to report
+    let Ok(tokens) = Tokenizer::new(source).tokenize() else {
+        return false;
+    };
+    let depth = tokens.iter().fold(0i64, |depth, token| match token.token {
+        Token::LeftParen | Token::LeftBracket | Token::LeftBrace => depth + 1,
+        Token::RightParen | Token::RightBracket | Token::RightBrace => depth - 1,
+        _ => depth,
+    });
+    depth > 0
+}
+
+fn ends_inside_string_or_comment(source: &str) -> bool {
+    let mut chars = source.chars().peekable();
+    let mut previous = ' ';
+    while let Some(c) = chars.next() {
+        let in_word = previous.is_alphanumeric() || previous == '_';
+        previous = c;
+        match c {
+            // A raw string ends at a quote followed by as many '#' as opened it
+            'r' if !in_word && matches!(chars.peek(), Some('"' | '#')) => {
+                let mut hashes = 0;
+                while chars.next_if_eq(&'#').is_some() {
+                    hashes += 1;
+                }
+                if chars.next_if_eq(&'"').is_none() {
+                    continue;
+                }
+                let closing: String = std::iter::once('"')
+                    .chain(std::iter::repeat_n('#', hashes))
+                    .collect();
+                let rest: String = chars.collect();
+                return match rest.find(&closing) {
+                    Some(end) => ends_inside_string_or_comment(&rest[end + closing.len()..]),
+                    None => true,
+                };
+            }
+            '"' => loop {
+                match chars.next() {
+                    None => return true,
+                    Some('"') => break,
+                    Some('\\') => {
+                        chars.next();
+                    }
+                    Some(_) => {}
+                }
+            },
+            '/' if chars.peek() == Some(&'/') => {
+                chars.find(|&c| c == '\n');
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut previous = ' ';
+                loop {
+                    match chars.next() {
+                        None => return true,
+                        Some('/') if previous == '*' => break,
+                        Some(c) => previous = c,
+                    }
+                }
+            }
+            _ => {}
+        }
     }
+    false
 }
 
 impl Default for Repl {
@@ -0,0 +1,259 @@
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod tests {
+    use super::super::{Repl, Step, is_incomplete, prompt_template};
+
+    #[test]
+    fn test_incomplete_input() {
+        assert!(is_incomplete("fn f(x) {"));
+        assert!(is_incomplete("let xs = [1,\n2"));
+        assert!(is_incomplete("let s = \"open"));
+        assert!(is_incomplete("/* still\n in a comment"));
+        assert!(!is_incomplete("let x = 1;"));
+        assert!(!is_incomplete("let s = \"{\"; // ("));
+        // Unbalanced closers are errors for the parser to report
+        assert!(!is_incomplete("1 + );"));
+        assert!(!is_incomplete("let s = \"a\\\"b\";"));
+        // Raw strings take no escapes, and may hold quotes between '#'s
+        assert!(!is_incomplete("let s = r\"C:\\\";"));
+        assert!(is_incomplete("let s = r#\"say \"hi\""));
+        assert!(!is_incomplete("let s = r#\"say \"hi\"\"#; // ("));
+        assert!(!is_incomplete("let bar = 1;"));
+    }
+
+    #[test]
+    fn test_lines_are_collected_until_complete() {
+        let mut repl = Repl::new();
+        assert_eq!(repl.accept_line("fn add(x) {"), Step::Continue);
+        assert_eq!(repl.accept_line("    x + 1"), Step::Continue);
+        assert_eq!(
+            repl.accept_line("}"),
+            Step::Evaluate("fn add(x) {\n    x + 1\n}".to_string())
+        );
+        assert_eq!(repl.accept_line("exit"), Step::Exit);
+    }
+
+    #[test]
+    fn test_blank_line_submits_unfinished_input() {
+        let mut repl = Repl::new();
+        assert_eq!(repl.accept_line(""), Step::Continue);
+        assert_eq!(repl.accept_line("[1, 2"), Step::Continue);
+        assert_eq!(repl.accept_line("  "), Step::Evaluate("[1, 2".to_string()));
+    }
+
+    #[test]
+    fn test_prompt_shows_line_number_and_continuation() {
+        let mut repl = Repl::new();
+        assert_eq!(repl.accept_line(":prompt \"[{line}]> \""), Step::Continue);
+        assert_eq!(repl.accept_line(":continuation-prompt ..."), Step::Continue);
+        assert_eq!(repl.current_prompt(), "[3]> ");
+        repl.accept_line("let x = (1 +");
+        assert_eq!(repl.current_prompt(), "... ");
+        repl.accept_line("2);");
+        assert_eq!(repl.current_prompt(), "[5]> ");
+
+        assert_eq!(prompt_template("  λ  "), "λ ");
+        assert_eq!(prompt_template("\" > \""), " > ");
+    }
+
+    #[test]
+    fn test_startup_script_preloads_definitions() {
+        let path = std::env::temp_dir().join(format!("corrosionrc-{}", std::process::id()));
+        std::fs::write(
+            &path,
+            ":prompt \"rc> \"\nfn double(x) {\n    x * 2\n}\nlet four = double(2);\n",
+        )
+        .unwrap();
+
+        let mut repl = Repl::new();
+        repl.run_startup_script(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(repl.current_prompt(), "rc> ");
+        assert_eq!(repl.process_content("double(four);").unwrap(), "8");
+        assert!(repl.run_startup_script(&path).is_err());
+    }
+
+    #[test]
+    fn test_definitions_keep_their_source() {
+        let mut repl = Repl::new();
+        repl.process_content("let x = 1; x + 1;").unwrap();
+        repl.process_content("fn inc(n: Int) -> Int {\n    n + 1\n}")
+            .unwrap();
+        assert!(repl.process_content("let bad = 1 + true;").is_err());
+
+        assert_eq!(repl.definition_source("x"), Some("let x = 1;"));
+        assert_eq!(
+            repl.definition_source("inc"),
+            Some("fn inc(n: Int) -> Int {\n    n + 1\n}")
+        );
+        assert_eq!(repl.definition_source("bad"), None);
+        assert_eq!(
+            repl.list_definitions(),
+            "let x = 1;\nfn inc(n: Int) -> Int {\n    n + 1\n}"
+        );
+    }
+
+    #[test]
+    fn test_undo_removes_the_latest_entry() {
+        let mut repl = Repl::new();
+        repl.process_content("let a = 1;").unwrap();
+        repl.process_content("let b = 2; fn f(x) { x + b }")
+            .unwrap();
+        repl.process_content("f(a);").unwrap();
+
+        assert_eq!(repl.undo(), vec!["b".to_string(), "f".to_string()]);
+        assert!(repl.process_content("b;").is_err());
+        assert!(repl.process_content("f(1);").is_err());
+        assert_eq!(repl.process_content("a;").unwrap(), "1");
+        assert_eq!(repl.list_definitions(), "let a = 1;");
+
+        // With the bad definition gone the name can be defined again
+        repl.process_content("let b = \"two\";").unwrap();
+        assert_eq!(repl.process_content("b;").unwrap(), "\"two\"");
+
+        assert_eq!(repl.undo(), vec!["b".to_string()]);
+        assert_eq!(repl.undo(), vec!["a".to_string()]);
+        assert!(repl.undo().is_empty());
+    }
+
+    #[test]
+    fn test_forget_removes_one_name() {
+        let mut repl = Repl::new();
+        repl.process_content("let keep = 1; let drop = 2;").unwrap();
+
+        assert!(repl.forget("drop"));
+        assert!(!repl.forget("drop"));
+        assert!(!repl.forget("missing"));
+        assert!(repl.process_content("drop;").is_err());
+        assert_eq!(repl.process_content("keep;").unwrap(), "1");
+        assert_eq!(repl.definition_source("drop"), None);
+    }
+
+    #[test]
+    fn test_definitions_can_be_entered_again() {
+        let mut repl = Repl::new();
+        repl.process_content("let x = 5;").unwrap();
+        repl.process_content("let x = \"five\";").unwrap();
+        assert_eq!(repl.process_content("x;").unwrap(), "\"five\"");
+
+        repl.process_content("fn f(n: Int) { n + 1 }").unwrap();
+        repl.process_content("fn f(n: Int) { n * 2 }").unwrap();
+        assert_eq!(repl.process_content("f(5);").unwrap(), "10");
+
+        // Undoing a redefinition brings back the earlier one
+        repl.undo();
+        assert_eq!(repl.process_content("f(5);").unwrap(), "6");
+    }
+
+    #[test]
+    fn test_paste_collects_lines_until_end() {
+        let mut repl = Repl::new();
+        assert_eq!(repl.accept_line(":paste"), Step::Continue);
+        assert_eq!(repl.current_prompt(), "... ");
+        assert_eq!(repl.accept_line("fn f(x) {"), Step::Continue);
+        assert_eq!(repl.accept_line(""), Step::Continue);
+        assert_eq!(repl.accept_line("  x }"), Step::Continue);
+        assert_eq!(
+            repl.accept_line(" :end "),
+            Step::Evaluate("fn f(x) {\n\n  x }\n".to_string())
+        );
+        assert_eq!(repl.current_prompt(), "> ");
+
+        // An empty paste submits nothing
+        repl.accept_line(":paste");
+        assert_eq!(repl.accept_line(":end"), Step::Continue);
+        assert_eq!(repl.accept_line("exit"), Step::Exit);
+    }
+
+    #[test]
+    fn test_highlighting_follows_the_lexer() {
+        use super::super::highlight::highlight;
+
+        let plain = |text: &str| {
+            let mut plain = String::new();
+            let mut rest = text;
+            while let Some(start) = rest.find('\x1b') {
+                plain.push_str(&rest[..start]);
+                rest = &rest[start + rest[start..].find('m').unwrap() + 1..];
+            }
+            plain + rest
+        };
+
+        // Colors never change what is shown
+        let line = "let s: String = \"a (\" ++ toString(f(1)) // done";
+        let highlighted = highlight(line, None);
+        assert_eq!(plain(&highlighted), line);
+        assert!(highlighted.starts_with("\x1b[1;35mlet\x1b[0m"));
+        assert!(highlighted.contains("\x1b[33mString\x1b[0m"));
+        assert!(highlighted.contains("\x1b[32m\"a (\"\x1b[0m"));
+        assert!(highlighted.contains("\x1b[2m// done\x1b[0m"));
+
+        // The bracket before the cursor and its match, not the one in the string
+        let marked = "\x1b[1;4;34m";
+        let highlighted = highlight(line, Some(line.find(")) ").unwrap() + 2));
+        assert_eq!(highlighted.matches(marked).count(), 2);
+        assert!(highlighted.contains(&format!("toString\x1b[0m{}(", marked)));
+        let highlighted = highlight("f((1)", Some(1));
+        assert!(!highlighted.contains(marked));
+
+        // An unfinished string is still colored as a string
+        let highlighted = highlight("print(\"hel", None);
+        assert!(highlighted.ends_with("\x1b[32m\"hel\x1b[0m"));
+    }
+
+    #[test]
+    fn test_session_bindings_leave_out_the_prelude() {
+        let mut repl = Repl::new();
+        assert_eq!(repl.session_bindings(), "");
+        repl.process_content("let b = 2; let a = \"one\";").unwrap();
+        repl.process_content("let b = 3;").unwrap();
+        assert_eq!(repl.session_bindings(), "a = \"one\"\nb = 3");
+    }
+
+    #[test]
+    fn test_reload_module_picks_up_changes() {
+        // A directory name the import syntax would have to escape
+        let dir =
+            std::env::temp_dir().join(format!("corrosion-reload-\"ü\"-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let module = dir.join("shapes.corr");
+        std::fs::write(&module, "let sides = 3;\nlet name = \"triangle\";").unwrap();
+
+        let mut repl = Repl::new();
+        repl.process_content(&format!("import r#\"{}\"# as shapes;", module.display()))
+            .unwrap();
+        assert_eq!(repl.process_content("shapes.sides;").unwrap(), "3");
+
+        std::fs::write(&module, "let sides = 4;\nlet name = 4;").unwrap();
+        assert_eq!(
+            repl.reload_module("shapes"),
+            Ok(vec!["'name' changed type from String to Int".to_string()])
+        );
+        assert_eq!(repl.process_content("shapes.sides;").unwrap(), "4");
+        // The import is listed once, as it was typed
+        let listed = repl.list_definitions();
+        assert_eq!(listed.lines().count(), 1, "{}", listed);
+        assert!(listed.contains("as shapes;"), "{}", listed);
+
+        assert!(repl.reload_module("circles").is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_runaway_recursion_ends_the_entry_not_the_session() {
+        let (error, after) = crate::interpreter::with_stack(|| {
+            let mut repl = Repl::new();
+            repl.process_content("fn forever(n: Int) -> Int { forever(n + 1) }")
+                .unwrap();
+            let error = repl.process_content("forever(0);").unwrap_err();
+            (error.to_string(), repl.process_content("1 + 1;").unwrap())
+        });
+        assert!(
+            error.contains("Calls nested deeper than the limit"),
+            "{}",
+            error
+        );
+        assert_eq!(after, "2");
+    }
+}
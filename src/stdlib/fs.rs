@@ -0,0 +1,95 @@
+//! `std:fs`: file system access. Paths are Strings resolved relative to the
+//! running program, and failures are returned as `inr(message)`.
+
+use super::{NativeFunction, string_argument, to_sum};
+use crate::interpreter::Value;
+use crate::typechecker::Type;
+use std::fs;
+use std::path::Path;
+
+pub const FUNCTIONS: &[NativeFunction] = &[
+    NativeFunction {
+        name: "listDir",
+        arity: 1,
+        signature: || {
+            Type::function(
+                Type::String,
+                Type::sum(Type::list(Type::String), Type::String),
+            )
+        },
+        call: list_dir,
+    },
+    NativeFunction {
+        name: "exists",
+        arity: 1,
+        signature: || Type::function(Type::String, Type::Bool),
+        call: |base, arguments| {
+            let path = base.join(string_argument(arguments, 0)?);
+            Ok(Value::Bool(path.exists()))
+        },
+    },
+    NativeFunction {
+        name: "isDir",
+        arity: 1,
+        signature: || Type::function(Type::String, Type::Bool),
+        call: |base, arguments| {
+            let path = base.join(string_argument(arguments, 0)?);
+            Ok(Value::Bool(path.is_dir()))
+        },
+    },
+    NativeFunction {
+        name: "mkdir",
+        arity: 1,
+        signature: || Type::function(Type::String, unit_or_error()),
+        call: |base, arguments| {
+            let path = base.join(string_argument(arguments, 0)?);
+            Ok(to_sum(fs::create_dir_all(path), |_| Value::Unit))
+        },
+    },
+    NativeFunction {
+        name: "remove",
+        arity: 1,
+        signature: || Type::function(Type::String, unit_or_error()),
+        call: remove,
+    },
+    NativeFunction {
+        name: "copy",
+        arity: 2,
+        signature: || Type::function(Type::String, Type::function(Type::String, unit_or_error())),
+        call: |base, arguments| {
+            let from = base.join(string_argument(arguments, 0)?);
+            let to = base.join(string_argument(arguments, 1)?);
+            Ok(to_sum(fs::copy(from, to), |_| Value::Unit))
+        },
+    },
+];
+
+fn unit_or_error() -> Type {
+    Type::sum(Type::Unit, Type::String)
+}
+
+/// Entry names in the directory, sorted so output does not depend on the OS
+fn list_dir(base: &Path, arguments: &[Value]) -> Result<Value, String> {
+    let path = base.join(string_argument(arguments, 0)?);
+    let entries = fs::read_dir(path).and_then(|entries| {
+        let mut names = entries
+            .map(|entry| Ok(entry?.file_name().to_string_lossy().into_owned()))
+            .collect::<std::io::Result<Vec<String>>>()?;
+        names.sort();
+        Ok(names)
+    });
+    Ok(to_sum(entries, |names| {
+        Value::List(names.into_iter().map(Value::String).collect())
+    }))
+}
+
+/// Removes a file or an empty directory; non-empty directories are an error
+fn remove(base: &Path, arguments: &[Value]) -> Result<Value, String> {
+    let path = base.join(string_argument(arguments, 0)?);
+    let result = if path.is_dir() {
+        fs::remove_dir(path)
+    } else {
+        fs::remove_file(path)
+    };
+    Ok(to_sum(result, |_| Value::Unit))
+}
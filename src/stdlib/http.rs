@@ -0,0 +1,66 @@
+//! `std:http`: blocking HTTP requests, available with the `http` feature.
+//! Responses are `(status, body)` pairs; any status code counts as a
+//! response, and only transport failures are returned as `inr(message)`.
+
+use super::{NativeFunction, string_argument, to_sum};
+use crate::interpreter::Value;
+use crate::typechecker::Type;
+use std::path::Path;
+
+pub const FUNCTIONS: &[NativeFunction] = &[
+    NativeFunction {
+        name: "get",
+        arity: 1,
+        signature: || Type::function(Type::String, response_or_error()),
+        call: get,
+    },
+    NativeFunction {
+        name: "post",
+        arity: 2,
+        signature: || {
+            Type::function(
+                Type::String,
+                Type::function(Type::String, response_or_error()),
+            )
+        },
+        call: post,
+    },
+];
+
+fn response_or_error() -> Type {
+    Type::sum(Type::pair(Type::Int, Type::String), Type::String)
+}
+
+fn agent() -> ureq::Agent {
+    ureq::Agent::config_builder()
+        .http_status_as_error(false)
+        .build()
+        .into()
+}
+
+fn get(_base: &Path, arguments: &[Value]) -> Result<Value, String> {
+    let url = string_argument(arguments, 0)?;
+    Ok(to_sum(
+        agent().get(url).call().and_then(read),
+        std::convert::identity,
+    ))
+}
+
+/// `http.post(url)(body)` sends `body` as the request's text payload
+fn post(_base: &Path, arguments: &[Value]) -> Result<Value, String> {
+    let url = string_argument(arguments, 0)?;
+    let body = string_argument(arguments, 1)?;
+    Ok(to_sum(
+        agent().post(url).send(body).and_then(read),
+        std::convert::identity,
+    ))
+}
+
+fn read(mut response: ureq::http::Response<ureq::Body>) -> Result<Value, ureq::Error> {
+    let status = response.status().as_u16() as i64;
+    let body = response.body_mut().read_to_string()?;
+    Ok(Value::Pair(
+        Box::new(Value::Int(status)),
+        Box::new(Value::String(body)),
+    ))
+}
@@ -0,0 +1,62 @@
+//! Native modules implemented in Rust, imported with `import "std:fs" as fs;`,
+//! and the prelude every program starts with
+
+pub mod fs;
+#[cfg(feature = "http")]
+pub mod http;
+pub mod prelude;
+
+use crate::interpreter::Value;
+use crate::typechecker::Type;
+use std::path::Path;
+
+/// Import paths starting with this prefix name a native module
+pub const STD_PREFIX: &str = "std:";
+
+/// A function implemented in Rust. Natives taking several arguments are
+/// curried: the interpreter collects arguments until `arity` is reached.
+pub struct NativeFunction {
+    pub name: &'static str,
+    pub arity: usize,
+    /// The function's type as registered in the type checker
+    pub signature: fn() -> Type,
+    /// Called with the program's directory, for resolving relative paths,
+    /// and exactly `arity` arguments. An `Err` becomes a runtime error.
+    pub call: fn(&Path, &[Value]) -> Result<Value, String>,
+}
+
+/// Look up the functions of a native module by its import path
+pub fn native_module(path: &str) -> Option<&'static [NativeFunction]> {
+    match path.strip_prefix(STD_PREFIX)? {
+        "fs" => Some(fs::FUNCTIONS),
+        #[cfg(feature = "http")]
+        "http" => Some(http::FUNCTIONS),
+        _ => None,
+    }
+}
+
+/// Look up a single native function by module path and name
+pub fn native_function(path: &str, name: &str) -> Option<&'static NativeFunction> {
+    native_module(path)?
+        .iter()
+        .find(|function| function.name == name)
+}
+
+/// Wrap the outcome of a fallible operation as `inl(value)` or `inr(message)`
+fn to_sum<T, E: std::fmt::Display>(result: Result<T, E>, value: impl FnOnce(T) -> Value) -> Value {
+    match result {
+        Ok(ok) => Value::LeftInject(Box::new(value(ok))),
+        Err(error) => Value::RightInject(Box::new(Value::String(error.to_string()))),
+    }
+}
+
+fn string_argument(arguments: &[Value], index: usize) -> Result<&str, String> {
+    match arguments.get(index) {
+        Some(Value::String(s)) => Ok(s),
+        Some(other) => Err(format!(
+            "Expected String argument, found {}",
+            other.type_name()
+        )),
+        None => Err("Missing argument".to_string()),
+    }
+}
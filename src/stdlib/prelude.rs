@@ -0,0 +1,86 @@
+//! The prelude: functions written in Corrosion that every interpreter and
+//! type checker defines before running a program, unless it is created
+//! without one. Programs can shadow any of them with their own definitions.
+
+use crate::ast::nodes::Child;
+use crate::ast::{Expression, Parser, Program, Statement};
+use crate::lexer::Tokenizer;
+use std::collections::HashSet;
+use std::sync::OnceLock;
+
+/// Source of the prelude
+pub const SOURCE: &str = include_str!("prelude.corr");
+
+/// The parsed prelude, parsed once per process
+pub fn program() -> &'static Program {
+    static PROGRAM: OnceLock<Program> = OnceLock::new();
+    PROGRAM.get_or_init(|| {
+        let tokens = Tokenizer::new(SOURCE)
+            .tokenize()
+            .expect("the prelude tokenizes");
+        Parser::new(tokens).parse().expect("the prelude parses")
+    })
+}
+
+/// `program` preceded by the prelude definitions it refers to and does not
+/// define itself, for backends that translate a program on its own
+pub fn with_prelude(program: &Program) -> Program {
+    let statements = used_by(program)
+        .into_iter()
+        .chain(&program.statements)
+        .cloned()
+        .collect();
+    Program::new(statements, program.span.clone())
+}
+
+/// The prelude definitions `program` refers to and does not define itself
+pub fn used_by(program: &Program) -> Vec<&'static Statement> {
+    let defined: HashSet<&str> = program.statements.iter().filter_map(defined_name).collect();
+    let mut used = HashSet::new();
+    for statement in &program.statements {
+        statement_names(statement, &mut used);
+    }
+    self::program()
+        .statements
+        .iter()
+        .filter(|statement| {
+            defined_name(statement)
+                .is_some_and(|name| used.contains(name) && !defined.contains(name))
+        })
+        .collect()
+}
+
+fn defined_name(statement: &Statement) -> Option<&str> {
+    match statement {
+        Statement::VariableDeclaration { name, .. }
+        | Statement::ConstantDeclaration { name, .. }
+        | Statement::FunctionDeclaration { name, .. } => Some(name),
+        _ => None,
+    }
+}
+
+/// Collect every plain identifier a statement mentions
+fn statement_names<'a>(statement: &'a Statement, names: &mut HashSet<&'a str>) {
+    match statement {
+        Statement::VariableDeclaration { value, .. }
+        | Statement::ConstantDeclaration { value, .. } => expression_names(value, names),
+        Statement::FunctionDeclaration { body, .. } => expression_names(body, names),
+        Statement::Expression { expression, .. }
+        | Statement::Return {
+            value: expression, ..
+        } => expression_names(expression, names),
+        Statement::Import { .. } | Statement::EnumDeclaration { .. } => {}
+    }
+}
+
+fn expression_names<'a>(expression: &'a Expression, names: &mut HashSet<&'a str>) {
+    if let Expression::Identifier { name, .. } = expression {
+        names.insert(name);
+    }
+    for child in expression.children() {
+        match child {
+            Child::Statement(statement) => statement_names(statement, names),
+            Child::Expression(_, expression) => expression_names(expression, names),
+        }
+    }
+}
@@ -0,0 +1,464 @@
+//! Helpers for testing code that works with syntax trees, available to this
+//! crate's tests and to other crates through the `testing` feature.
+//!
+//! The main check is the formatter round trip: formatting a tree and parsing
+//! the result must give back the same tree, and formatting that again must
+//! give the same text. [`strategy`] generates random trees to run it on, so a
+//! grammar change that the formatter does not follow shows up as a failing
+//! property test instead of as source that silently changes meaning.
+
+pub mod strategy;
+
+#[cfg(test)]
+mod tests;
+
+use crate::ast::nodes::{NodeId, Pattern};
+use crate::ast::{Expression, Parser, Program, Statement, TypeExpression};
+use crate::lexer::Tokenizer;
+use crate::lexer::tokens::Span;
+
+/// Why a tree did not survive formatting and parsing
+#[derive(Debug, Clone, PartialEq)]
+pub enum RoundTripError {
+    /// The formatted source failed to tokenize or parse
+    Unparsable { source: String, message: String },
+    /// The formatted source parsed to a different tree
+    Changed { source: String, reparsed: String },
+    /// Formatting the reparsed tree gave different source
+    Unstable { first: String, second: String },
+}
+
+impl std::fmt::Display for RoundTripError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RoundTripError::Unparsable { source, message } => {
+                write!(
+                    f,
+                    "formatted source does not parse: {}\n{}",
+                    message, source
+                )
+            }
+            RoundTripError::Changed { source, reparsed } => write!(
+                f,
+                "formatted source parses to a different tree\n{}\nwhich formats as\n{}",
+                source, reparsed
+            ),
+            RoundTripError::Unstable { first, second } => write!(
+                f,
+                "formatting is not stable\nfirst:\n{}\nsecond:\n{}",
+                first, second
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RoundTripError {}
+
+/// Tokenize and parse source text, with errors rendered as text
+pub fn parse_source(source: &str) -> Result<Program, String> {
+    let tokens = Tokenizer::new(source)
+        .tokenize()
+        .map_err(|error| error.to_string())?;
+    Parser::new(tokens)
+        .parse()
+        .map_err(|error| error.to_string())
+}
+
+/// Whether two trees are equal apart from their spans and node ids
+pub fn same_tree(a: &Program, b: &Program) -> bool {
+    without_spans(a) == without_spans(b)
+}
+
+/// Check that `parse(format(program)) == program`, ignoring spans, and that
+/// formatting the reparsed tree gives the same text again
+pub fn check_round_trip(program: &Program) -> Result<(), RoundTripError> {
+    let source = program.format();
+    let reparsed = parse_source(&source).map_err(|message| RoundTripError::Unparsable {
+        source: source.clone(),
+        message,
+    })?;
+    if !same_tree(program, &reparsed) {
+        return Err(RoundTripError::Changed {
+            reparsed: reparsed.format(),
+            source,
+        });
+    }
+    let second = reparsed.format();
+    if second != source {
+        return Err(RoundTripError::Unstable {
+            first: source,
+            second,
+        });
+    }
+    Ok(())
+}
+
+/// Parse source text and check that its tree round trips. Errors in the
+/// original source are reported as `Unparsable` too.
+pub fn check_source_round_trip(source: &str) -> Result<(), RoundTripError> {
+    let program = parse_source(source).map_err(|message| RoundTripError::Unparsable {
+        source: source.to_string(),
+        message,
+    })?;
+    check_round_trip(&program)
+}
+
+/// A copy of a tree with every span and node id reset, so trees parsed from
+/// differently laid out source compare equal
+fn without_spans(program: &Program) -> Program {
+    let mut program = program.clone();
+    program.span = blank();
+    program.statements.iter_mut().for_each(clear_statement);
+    program
+}
+
+fn blank() -> Span {
+    Span::new(0, 0, 0, 0)
+}
+
+fn clear_statement(statement: &mut Statement) {
+    match statement {
+        Statement::VariableDeclaration {
+            type_annotation,
+            value,
+            id,
+            span,
+            ..
+        }
+        | Statement::ConstantDeclaration {
+            type_annotation,
+            value,
+            id,
+            span,
+            ..
+        } => {
+            type_annotation.iter_mut().for_each(clear_type);
+            clear_expression(value);
+            *id = NodeId::default();
+            *span = blank();
+        }
+        Statement::FunctionDeclaration {
+            param_type,
+            return_type,
+            body,
+            id,
+            param_id,
+            span,
+            ..
+        } => {
+            param_type
+                .iter_mut()
+                .chain(return_type.iter_mut())
+                .for_each(clear_type);
+            clear_expression(body);
+            *id = NodeId::default();
+            *param_id = NodeId::default();
+            *span = blank();
+        }
+        Statement::EnumDeclaration { span, .. } | Statement::Import { span, .. } => {
+            *span = blank();
+        }
+        Statement::Expression {
+            expression: value,
+            span,
+        }
+        | Statement::Return { value, span } => {
+            clear_expression(value);
+            *span = blank();
+        }
+    }
+}
+
+fn clear_expression(expression: &mut Expression) {
+    match expression {
+        Expression::Identifier { id, span, .. } => {
+            *id = NodeId::default();
+            *span = blank();
+        }
+        Expression::QualifiedIdentifier { span, .. }
+        | Expression::Number { span, .. }
+        | Expression::BigNumber { span, .. }
+        | Expression::Boolean { span, .. }
+        | Expression::String { span, .. }
+        | Expression::Bytes { span, .. }
+        | Expression::Hole { span, .. }
+        | Expression::Channel { span }
+        | Expression::Input { span }
+        | Expression::ReadAll { span } => *span = blank(),
+        Expression::UnaryOp {
+            operand: inner,
+            span,
+            ..
+        }
+        | Expression::LeftInject { value: inner, span }
+        | Expression::RightInject { value: inner, span }
+        | Expression::Fix {
+            function: inner,
+            span,
+        }
+        | Expression::FirstProjection { pair: inner, span }
+        | Expression::SecondProjection { pair: inner, span }
+        | Expression::TupleProjection {
+            tuple: inner, span, ..
+        }
+        | Expression::HeadProjection { list: inner, span }
+        | Expression::TailProjection { list: inner, span }
+        | Expression::Print { value: inner, span }
+        | Expression::Length {
+            string: inner,
+            span,
+        }
+        | Expression::Graphemes {
+            string: inner,
+            span,
+        }
+        | Expression::Spawn {
+            function: inner,
+            span,
+        }
+        | Expression::Join {
+            handle: inner,
+            span,
+        }
+        | Expression::Sleep {
+            milliseconds: inner,
+            span,
+        }
+        | Expression::Random { bound: inner, span }
+        | Expression::Recv {
+            channel: inner,
+            span,
+        }
+        | Expression::ReadFileBytes { path: inner, span }
+        | Expression::ToBytes {
+            string: inner,
+            span,
+        }
+        | Expression::FromBytes { bytes: inner, span }
+        | Expression::ToString {
+            expression: inner,
+            span,
+        }
+        | Expression::ToInt {
+            string: inner,
+            span,
+        }
+        | Expression::TypeOf {
+            expression: inner,
+            span,
+        } => {
+            clear_expression(inner);
+            *span = blank();
+        }
+        Expression::BinaryOp {
+            left, right, span, ..
+        }
+        | Expression::FunctionCall {
+            function: left,
+            argument: right,
+            span,
+        }
+        | Expression::Pair {
+            first: left,
+            second: right,
+            span,
+        }
+        | Expression::Cons {
+            head: left,
+            tail: right,
+            span,
+        }
+        | Expression::Range {
+            start: left,
+            end: right,
+            span,
+        }
+        | Expression::DivMod {
+            dividend: left,
+            divisor: right,
+            span,
+        }
+        | Expression::SafeDiv {
+            dividend: left,
+            divisor: right,
+            span,
+        }
+        | Expression::Concat { left, right, span }
+        | Expression::CharAt {
+            string: left,
+            index: right,
+            span,
+        }
+        | Expression::Send {
+            channel: left,
+            value: right,
+            span,
+        }
+        | Expression::ByteAt {
+            bytes: left,
+            index: right,
+            span,
+        } => {
+            clear_expression(left);
+            clear_expression(right);
+            *span = blank();
+        }
+        Expression::List { elements, span } | Expression::Tuple { elements, span } => {
+            elements.iter_mut().for_each(clear_expression);
+            *span = blank();
+        }
+        Expression::Function {
+            param_type,
+            body,
+            param_id,
+            span,
+            ..
+        } => {
+            if let Some(param_type) = param_type {
+                clear_type(param_type);
+            }
+            clear_expression(body);
+            *param_id = NodeId::default();
+            *span = blank();
+        }
+        Expression::Block {
+            statements,
+            expression,
+            span,
+        } => {
+            statements.iter_mut().for_each(clear_statement);
+            if let Some(expression) = expression {
+                clear_expression(expression);
+            }
+            *span = blank();
+        }
+        Expression::If {
+            condition,
+            then_branch,
+            else_branch,
+            span,
+        } => {
+            clear_expression(condition);
+            clear_expression(then_branch);
+            if let Some(else_branch) = else_branch {
+                clear_expression(else_branch);
+            }
+            *span = blank();
+        }
+        Expression::For {
+            iterable: value,
+            body,
+            id,
+            span,
+            ..
+        }
+        | Expression::With {
+            value,
+            body,
+            id,
+            span,
+            ..
+        } => {
+            clear_expression(value);
+            clear_expression(body);
+            *id = NodeId::default();
+            *span = blank();
+        }
+        Expression::Case {
+            expression,
+            left_body,
+            right_body,
+            left_id,
+            right_id,
+            span,
+            ..
+        } => {
+            clear_expression(expression);
+            clear_expression(left_body);
+            clear_expression(right_body);
+            *left_id = NodeId::default();
+            *right_id = NodeId::default();
+            *span = blank();
+        }
+        Expression::Match {
+            expression,
+            arms,
+            span,
+        } => {
+            clear_expression(expression);
+            for arm in arms {
+                clear_pattern(&mut arm.pattern);
+                clear_expression(&mut arm.body);
+            }
+            *span = blank();
+        }
+        Expression::TypeTest {
+            expression,
+            type_annotation,
+            span,
+        }
+        | Expression::TypeAscription {
+            expression,
+            type_annotation,
+            span,
+        } => {
+            clear_expression(expression);
+            clear_type(type_annotation);
+            *span = blank();
+        }
+    }
+}
+
+fn clear_pattern(pattern: &mut Pattern) {
+    match pattern {
+        Pattern::Wildcard { span }
+        | Pattern::Variant { span, .. }
+        | Pattern::String { span, .. }
+        | Pattern::Int { span, .. }
+        | Pattern::Range { span, .. } => *span = blank(),
+    }
+}
+
+fn clear_type(type_expression: &mut TypeExpression) {
+    match type_expression {
+        TypeExpression::Int { span }
+        | TypeExpression::Bool { span }
+        | TypeExpression::String { span }
+        | TypeExpression::Named { span, .. } => *span = blank(),
+        TypeExpression::List {
+            element: inner,
+            span,
+        }
+        | TypeExpression::Handle {
+            result: inner,
+            span,
+        }
+        | TypeExpression::Channel {
+            element: inner,
+            span,
+        }
+        | TypeExpression::Recursive { inner, span } => {
+            clear_type(inner);
+            *span = blank();
+        }
+        TypeExpression::Function {
+            param: left,
+            result: right,
+            span,
+        }
+        | TypeExpression::Pair {
+            first: left,
+            second: right,
+            span,
+        }
+        | TypeExpression::Sum { left, right, span } => {
+            clear_type(left);
+            clear_type(right);
+            *span = blank();
+        }
+        TypeExpression::Tuple { elements, span } => {
+            elements.iter_mut().for_each(clear_type);
+            *span = blank();
+        }
+    }
+}
@@ -0,0 +1,595 @@
+//! Proptest strategies for random syntax trees. Every generated tree is one
+//! the parser can produce, so it can be formatted and parsed back: names are
+//! never keywords or `_`, numbers are never negative (`-1` parses as a
+//! negation), tuples have at least three elements and blocks only appear as
+//! the bodies of functions, `if`, `if let` and `for`. All spans are the same
+//! placeholder, since round trip checks ignore them.
+
+use proptest::collection::vec;
+use proptest::prelude::*;
+use proptest::sample::select;
+use proptest::strategy::{BoxedStrategy, Union};
+
+use crate::ast::{
+    BinaryOperator, CaseArm, Expression, NodeId, Pattern, Program, Statement, TypeExpression,
+    UnaryOperator,
+};
+use crate::lexer::Span;
+
+const NAMES: &[&str] = &["x", "y", "acc", "item", "total", "xs"];
+const TYPE_NAMES: &[&str] = &["Tree", "Shape"];
+const MODULE_NAMES: &[&str] = &["utils", "math"];
+
+const BINARY_OPERATORS: &[BinaryOperator] = &[
+    BinaryOperator::Add,
+    BinaryOperator::Subtract,
+    BinaryOperator::Multiply,
+    BinaryOperator::Divide,
+    BinaryOperator::Equal,
+    BinaryOperator::NotEqual,
+    BinaryOperator::LessThan,
+    BinaryOperator::LessThanEqual,
+    BinaryOperator::GreaterThan,
+    BinaryOperator::GreaterThanEqual,
+    BinaryOperator::LogicalAnd,
+    BinaryOperator::LogicalOr,
+    BinaryOperator::ComposeForward,
+    BinaryOperator::ComposeBackward,
+    BinaryOperator::Append,
+];
+
+fn span() -> Span {
+    Span::new(0, 0, 1, 1)
+}
+
+/// A variable, parameter or pattern name
+pub fn name() -> impl Strategy<Value = String> {
+    select(NAMES).prop_map(str::to_string)
+}
+
+/// A name bound by a `case` branch, or `_` for a value it does not use
+fn binder() -> impl Strategy<Value = String> {
+    prop_oneof![name(), Just("_".to_string())]
+}
+
+/// Text for string literals, including characters that need escaping
+fn text() -> impl Strategy<Value = String> {
+    "[a-z0-9 \"\\\\\n\t\ré→]{0,8}"
+}
+
+/// A random type annotation
+pub fn type_expression() -> impl Strategy<Value = TypeExpression> {
+    let leaf = prop_oneof![
+        Just(TypeExpression::Int { span: span() }),
+        Just(TypeExpression::Bool { span: span() }),
+        Just(TypeExpression::String { span: span() }),
+        select(TYPE_NAMES).prop_map(|name| TypeExpression::Named {
+            name: name.to_string(),
+            span: span(),
+        }),
+    ];
+    leaf.prop_recursive(4, 24, 3, |inner| {
+        prop_oneof![
+            inner.clone().prop_map(|element| TypeExpression::List {
+                element: Box::new(element),
+                span: span(),
+            }),
+            inner.clone().prop_map(|result| TypeExpression::Handle {
+                result: Box::new(result),
+                span: span(),
+            }),
+            inner.clone().prop_map(|element| TypeExpression::Channel {
+                element: Box::new(element),
+                span: span(),
+            }),
+            inner.clone().prop_map(|inner| TypeExpression::Recursive {
+                inner: Box::new(inner),
+                span: span(),
+            }),
+            (inner.clone(), inner.clone()).prop_map(|(param, result)| {
+                TypeExpression::Function {
+                    param: Box::new(param),
+                    result: Box::new(result),
+                    span: span(),
+                }
+            }),
+            (inner.clone(), inner.clone()).prop_map(|(first, second)| TypeExpression::Pair {
+                first: Box::new(first),
+                second: Box::new(second),
+                span: span(),
+            }),
+            vec(inner.clone(), 3..5).prop_map(|elements| TypeExpression::Tuple {
+                elements,
+                span: span(),
+            }),
+            (inner.clone(), inner).prop_map(|(left, right)| TypeExpression::Sum {
+                left: Box::new(left),
+                right: Box::new(right),
+                span: span(),
+            }),
+        ]
+    })
+}
+
+fn literal() -> impl Strategy<Value = Expression> {
+    prop_oneof![
+        name().prop_map(|name| Expression::Identifier {
+            name,
+            id: NodeId::default(),
+            span: span(),
+        }),
+        (select(MODULE_NAMES), name()).prop_map(|(module, name)| {
+            Expression::QualifiedIdentifier {
+                module: module.to_string(),
+                name,
+                span: span(),
+            }
+        }),
+        (0..1000i64).prop_map(|value| Expression::Number {
+            value,
+            span: span()
+        }),
+        any::<u64>().prop_map(|value| Expression::BigNumber {
+            value: value.into(),
+            span: span(),
+        }),
+        any::<bool>().prop_map(|value| Expression::Boolean {
+            value,
+            span: span()
+        }),
+        text().prop_map(|value| Expression::String {
+            value,
+            span: span()
+        }),
+        vec(any::<u8>(), 0..6).prop_map(|value| Expression::Bytes {
+            value,
+            span: span()
+        }),
+        Just(Expression::Channel { span: span() }),
+        Just(Expression::Input { span: span() }),
+        Just(Expression::ReadAll { span: span() }),
+        proptest::option::of(name()).prop_map(|name| Expression::Hole { name, span: span() }),
+    ]
+}
+
+/// A block of `let` and expression statements with an optional result, and
+/// `return` statements too when it is a function body
+fn block(expression: BoxedStrategy<Expression>, function_body: bool) -> BoxedStrategy<Expression> {
+    let statement = prop_oneof![
+        (
+            name(),
+            proptest::option::of(type_expression()),
+            expression.clone()
+        )
+            .prop_map(
+                |(name, type_annotation, value)| Statement::VariableDeclaration {
+                    name,
+                    type_annotation,
+                    value,
+                    id: NodeId::default(),
+                    span: span(),
+                }
+            ),
+        expression
+            .clone()
+            .prop_map(|expression| Statement::Expression {
+                expression,
+                span: span(),
+            }),
+    ];
+    let statement = if function_body {
+        let returns = expression.clone().prop_map(|value| Statement::Return {
+            value,
+            span: span(),
+        });
+        prop_oneof![3 => statement, 1 => returns].boxed()
+    } else {
+        statement.boxed()
+    };
+    (vec(statement, 0..3), proptest::option::of(expression))
+        .prop_map(|(statements, expression)| Expression::Block {
+            statements,
+            expression: expression.map(Box::new),
+            span: span(),
+        })
+        .boxed()
+}
+
+fn unary(
+    expression: &BoxedStrategy<Expression>,
+    build: fn(Box<Expression>, Span) -> Expression,
+) -> BoxedStrategy<Expression> {
+    expression
+        .clone()
+        .prop_map(move |operand| build(Box::new(operand), span()))
+        .boxed()
+}
+
+fn binary(
+    expression: &BoxedStrategy<Expression>,
+    build: fn(Box<Expression>, Box<Expression>, Span) -> Expression,
+) -> BoxedStrategy<Expression> {
+    (expression.clone(), expression.clone())
+        .prop_map(move |(first, second)| build(Box::new(first), Box::new(second), span()))
+        .boxed()
+}
+
+/// A random expression
+pub fn expression() -> impl Strategy<Value = Expression> {
+    literal().prop_recursive(4, 48, 4, |inner| {
+        let inner = inner.boxed();
+        let body = block(inner.clone(), false);
+        Union::new(vec![
+            (inner.clone(), select(BINARY_OPERATORS), inner.clone())
+                .prop_map(|(left, operator, right)| Expression::BinaryOp {
+                    left: Box::new(left),
+                    operator,
+                    right: Box::new(right),
+                    span: span(),
+                })
+                .boxed(),
+            (
+                select(&[UnaryOperator::LogicalNot, UnaryOperator::Negate][..]),
+                inner.clone(),
+            )
+                .prop_map(|(operator, operand)| Expression::UnaryOp {
+                    operator,
+                    operand: Box::new(operand),
+                    span: span(),
+                })
+                .boxed(),
+            (
+                name(),
+                proptest::option::of(type_expression()),
+                body.clone(),
+            )
+                .prop_map(|(param, param_type, body)| Expression::Function {
+                    param,
+                    param_type: param_type.map(Box::new),
+                    body: Box::new(body),
+                    param_id: NodeId::default(),
+                    span: span(),
+                })
+                .boxed(),
+            binary(&inner, |function, argument, span| {
+                Expression::FunctionCall {
+                    function,
+                    argument,
+                    span,
+                }
+            }),
+            vec(inner.clone(), 0..4)
+                .prop_map(|elements| Expression::List {
+                    elements,
+                    span: span(),
+                })
+                .boxed(),
+            binary(&inner, |first, second, span| Expression::Pair {
+                first,
+                second,
+                span,
+            }),
+            vec(inner.clone(), 3..5)
+                .prop_map(|elements| Expression::Tuple {
+                    elements,
+                    span: span(),
+                })
+                .boxed(),
+            (inner.clone(), 0..3usize)
+                .prop_map(|(tuple, index)| Expression::TupleProjection {
+                    tuple: Box::new(tuple),
+                    index,
+                    span: span(),
+                })
+                .boxed(),
+            binary(&inner, |head, tail, span| Expression::Cons {
+                head,
+                tail,
+                span,
+            }),
+            (
+                inner.clone(),
+                body.clone(),
+                proptest::option::of(body.clone()),
+            )
+                .prop_map(|(condition, then_branch, else_branch)| Expression::If {
+                    condition: Box::new(condition),
+                    then_branch: Box::new(then_branch),
+                    else_branch: else_branch.map(Box::new),
+                    span: span(),
+                })
+                .boxed(),
+            (name(), inner.clone(), body.clone())
+                .prop_map(|(variable, iterable, body)| Expression::For {
+                    variable,
+                    iterable: Box::new(iterable),
+                    body: Box::new(body),
+                    id: NodeId::default(),
+                    span: span(),
+                })
+                .boxed(),
+            (name(), inner.clone(), body.clone())
+                .prop_map(|(name, value, body)| Expression::With {
+                    name,
+                    value: Box::new(value),
+                    body: Box::new(body),
+                    id: NodeId::default(),
+                    span: span(),
+                })
+                .boxed(),
+            (
+                inner.clone(),
+                binder(),
+                inner.clone(),
+                binder(),
+                inner.clone(),
+            )
+                .prop_map(
+                    |(expression, left_pattern, left_body, right_pattern, right_body)| {
+                        Expression::Case {
+                            expression: Box::new(expression),
+                            left_pattern,
+                            left_body: Box::new(left_body),
+                            right_pattern,
+                            right_body: Box::new(right_body),
+                            left_id: NodeId::default(),
+                            right_id: NodeId::default(),
+                            span: span(),
+                        }
+                    },
+                )
+                .boxed(),
+            (inner.clone(), vec((pattern(), inner.clone()), 1..4))
+                .prop_map(|(expression, arms)| Expression::Match {
+                    expression: Box::new(expression),
+                    arms: arms
+                        .into_iter()
+                        .map(|(pattern, body)| CaseArm { pattern, body })
+                        .collect(),
+                    span: span(),
+                })
+                .boxed(),
+            // `if let`, which binds `_` on the branch it does not name
+            (any::<bool>(), name(), inner.clone(), body.clone(), body)
+                .prop_map(|(is_left, pattern, expression, matched, other)| {
+                    let (left_pattern, left_body, right_pattern, right_body) = if is_left {
+                        (pattern, matched, "_".to_string(), other)
+                    } else {
+                        ("_".to_string(), other, pattern, matched)
+                    };
+                    Expression::Case {
+                        expression: Box::new(expression),
+                        left_pattern,
+                        left_body: Box::new(left_body),
+                        right_pattern,
+                        right_body: Box::new(right_body),
+                        left_id: NodeId::default(),
+                        right_id: NodeId::default(),
+                        span: span(),
+                    }
+                })
+                .boxed(),
+            (inner.clone(), type_expression())
+                .prop_map(|(expression, type_annotation)| Expression::TypeTest {
+                    expression: Box::new(expression),
+                    type_annotation,
+                    span: span(),
+                })
+                .boxed(),
+            (inner.clone(), type_expression())
+                .prop_map(|(expression, type_annotation)| Expression::TypeAscription {
+                    expression: Box::new(expression),
+                    type_annotation,
+                    span: span(),
+                })
+                .boxed(),
+            Union::new(vec![
+                unary(&inner, |value, span| Expression::LeftInject { value, span }),
+                unary(&inner, |value, span| Expression::RightInject {
+                    value,
+                    span,
+                }),
+                unary(&inner, |function, span| Expression::Fix { function, span }),
+                unary(&inner, |pair, span| Expression::FirstProjection {
+                    pair,
+                    span,
+                }),
+                unary(&inner, |pair, span| Expression::SecondProjection {
+                    pair,
+                    span,
+                }),
+                unary(&inner, |list, span| Expression::HeadProjection {
+                    list,
+                    span,
+                }),
+                unary(&inner, |list, span| Expression::TailProjection {
+                    list,
+                    span,
+                }),
+                unary(&inner, |value, span| Expression::Print { value, span }),
+                unary(&inner, |string, span| Expression::Length { string, span }),
+                unary(&inner, |string, span| Expression::Graphemes {
+                    string,
+                    span,
+                }),
+                unary(&inner, |path, span| Expression::ReadFileBytes {
+                    path,
+                    span,
+                }),
+                unary(&inner, |string, span| Expression::ToBytes { string, span }),
+                unary(&inner, |bytes, span| Expression::FromBytes { bytes, span }),
+                unary(&inner, |string, span| Expression::ToInt { string, span }),
+                unary(&inner, |function, span| Expression::Spawn {
+                    function,
+                    span,
+                }),
+                unary(&inner, |handle, span| Expression::Join { handle, span }),
+                unary(&inner, |milliseconds, span| Expression::Sleep {
+                    milliseconds,
+                    span,
+                }),
+                unary(&inner, |bound, span| Expression::Random { bound, span }),
+                unary(&inner, |channel, span| Expression::Recv { channel, span }),
+                unary(&inner, |expression, span| Expression::ToString {
+                    expression,
+                    span,
+                }),
+                unary(&inner, |expression, span| Expression::TypeOf {
+                    expression,
+                    span,
+                }),
+            ])
+            .boxed(),
+            Union::new(vec![
+                binary(&inner, |start, end, span| Expression::Range {
+                    start,
+                    end,
+                    span,
+                }),
+                binary(&inner, |dividend, divisor, span| Expression::DivMod {
+                    dividend,
+                    divisor,
+                    span,
+                }),
+                binary(&inner, |dividend, divisor, span| Expression::SafeDiv {
+                    dividend,
+                    divisor,
+                    span,
+                }),
+                binary(&inner, |left, right, span| Expression::Concat {
+                    left,
+                    right,
+                    span,
+                }),
+                binary(&inner, |string, index, span| Expression::CharAt {
+                    string,
+                    index,
+                    span,
+                }),
+                binary(&inner, |bytes, index, span| Expression::ByteAt {
+                    bytes,
+                    index,
+                    span,
+                }),
+                binary(&inner, |channel, value, span| Expression::Send {
+                    channel,
+                    value,
+                    span,
+                }),
+            ])
+            .boxed(),
+        ])
+    })
+}
+
+/// A random `case` arm pattern
+fn pattern() -> impl Strategy<Value = Pattern> {
+    prop_oneof![
+        Just(Pattern::Wildcard { span: span() }),
+        (select(TYPE_NAMES), name()).prop_map(|(enum_name, variant)| Pattern::Variant {
+            enum_name: enum_name.to_string(),
+            variant,
+            span: span(),
+        }),
+        "[a-z ]{0,6}".prop_map(|value| Pattern::String {
+            value,
+            span: span()
+        }),
+        (-1000..1000i64).prop_map(|value| Pattern::Int {
+            value,
+            span: span()
+        }),
+        // Ranges are never empty and keep at least one bound
+        (
+            proptest::option::of(-1000..1000i64),
+            1..100i64,
+            any::<bool>()
+        )
+            .prop_map(|(start, length, open)| Pattern::Range {
+                start,
+                end: (!open || start.is_none()).then(|| start.unwrap_or(0) + length),
+                span: span(),
+            }),
+    ]
+}
+
+/// A random top level statement
+pub fn statement() -> impl Strategy<Value = Statement> {
+    let expression = expression().boxed();
+    prop_oneof![
+        (
+            name(),
+            proptest::option::of(type_expression()),
+            expression.clone()
+        )
+            .prop_map(
+                |(name, type_annotation, value)| Statement::VariableDeclaration {
+                    name,
+                    type_annotation,
+                    value,
+                    id: NodeId::default(),
+                    span: span(),
+                }
+            ),
+        (
+            name(),
+            proptest::option::of(type_expression()),
+            expression.clone()
+        )
+            .prop_map(
+                |(name, type_annotation, value)| Statement::ConstantDeclaration {
+                    name,
+                    type_annotation,
+                    value,
+                    id: NodeId::default(),
+                    span: span(),
+                }
+            ),
+        (
+            name(),
+            name(),
+            proptest::option::of(type_expression()),
+            proptest::option::of(type_expression()),
+            block(expression.clone(), true),
+        )
+            .prop_map(|(name, param, param_type, return_type, body)| {
+                Statement::FunctionDeclaration {
+                    name,
+                    param,
+                    param_type,
+                    return_type,
+                    body,
+                    id: NodeId::default(),
+                    param_id: NodeId::default(),
+                    span: span(),
+                }
+            }),
+        (select(TYPE_NAMES), vec(name(), 1..4)).prop_map(|(name, variants)| {
+            Statement::EnumDeclaration {
+                name: name.to_string(),
+                variants,
+                span: span(),
+            }
+        }),
+        (
+            text(),
+            proptest::option::of(select(MODULE_NAMES)),
+            any::<bool>()
+        )
+            .prop_map(|(path, alias, exported)| Statement::Import {
+                path,
+                alias: alias.map(str::to_string),
+                exported,
+                span: span(),
+            }),
+        expression.prop_map(|expression| Statement::Expression {
+            expression,
+            span: span(),
+        }),
+    ]
+}
+
+/// A random program of a few statements
+pub fn program() -> impl Strategy<Value = Program> {
+    vec(statement(), 0..5).prop_map(|statements| Program::new(statements, span()))
+}
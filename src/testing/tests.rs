@@ -0,0 +1,80 @@
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod tests {
+    use super::super::strategy;
+    use super::super::*;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn formatted_programs_parse_back_to_the_same_tree(program in strategy::program()) {
+            if let Err(error) = check_round_trip(&program) {
+                prop_assert!(false, "{}", error);
+            }
+        }
+    }
+
+    #[test]
+    fn test_exercises_round_trip() {
+        for exercise in 1..=5 {
+            let path = format!(
+                "{}/exercises/exercise-{}.corr",
+                env!("CARGO_MANIFEST_DIR"),
+                exercise
+            );
+            let source = std::fs::read_to_string(&path).unwrap();
+            if let Err(error) = check_source_round_trip(&source) {
+                panic!("{}: {}", path, error);
+            }
+        }
+    }
+
+    #[test]
+    fn test_same_tree_ignores_spans() {
+        let spaced = parse_source("let   x =\n  1 + 2;").unwrap();
+        let compact = parse_source("let x = 1+2;").unwrap();
+        assert!(same_tree(&spaced, &compact));
+        assert_ne!(spaced, compact);
+
+        let different = parse_source("let x = 1 + 3;").unwrap();
+        assert!(!same_tree(&spaced, &different));
+    }
+
+    #[test]
+    fn test_format_keeps_needed_parentheses() {
+        let program = parse_source("let x = (1 + 2) * 3 - (4 - 5);").unwrap();
+        assert_eq!(program.format(), "let x = (1 + 2) * 3 - (4 - 5);\n");
+        check_round_trip(&program).unwrap();
+    }
+
+    #[test]
+    fn test_same_tree_compares_everything_but_spans() {
+        let spaced = parse_source(
+            "fn f(p: (Int, Bool)) -> Int {\n    case p.0 of 0 => 0 | _ => 1\n}\nlet y: Int = f((1, true));",
+        )
+        .unwrap();
+        let compact = parse_source(
+            "fn f(p: (Int,Bool)) -> Int { case p.0 of 0=>0|_=>1 }\nlet y: Int = f((1,true));",
+        )
+        .unwrap();
+        assert!(same_tree(&spaced, &compact));
+
+        // Text that looks like a span inside a string is still compared
+        let first = parse_source("let s = \"span: Span { a }\";").unwrap();
+        let second = parse_source("let s = \"span: Span { b }\";").unwrap();
+        assert!(!same_tree(&first, &second));
+    }
+
+    #[test]
+    fn test_case_with_an_unused_branch_round_trips() {
+        // The `describe` example from the tutorial
+        let source = "let describe = fn(r: (Int + String) + Bool) {
+                          case r of
+                              inl inner => case inner of inl n => \"number\" | inr s => \"text\"
+                            | inr _ => \"flag\"
+                      };
+                      let first = fn(r: Int + Int) { case r of inr _ => 0 | inl n => n };
+                      let shown = if let inr(b) = inr(true) { b } else { false };";
+        check_source_round_trip(source).unwrap();
+    }
+}
@@ -8,8 +8,7 @@ mod new_features_tests {
 
     #[test]
     fn test_boolean_literal_true() {
-        let mut tokenizer = Tokenizer::new("");
-        let tokens = tokenizer.tokenize("true;").unwrap();
+        let tokens = Tokenizer::new("true;").tokenize().unwrap();
 
         let mut parser = Parser::new(tokens);
         let program = parser.parse().unwrap();
@@ -29,8 +28,7 @@ mod new_features_tests {
 
     #[test]
     fn test_boolean_literal_false() {
-        let mut tokenizer = Tokenizer::new("");
-        let tokens = tokenizer.tokenize("false;").unwrap();
+        let tokens = Tokenizer::new("false;").tokenize().unwrap();
 
         let mut parser = Parser::new(tokens);
         let program = parser.parse().unwrap();
@@ -50,8 +48,7 @@ mod new_features_tests {
 
     #[test]
     fn test_type_annotation_int() {
-        let mut tokenizer = Tokenizer::new("");
-        let tokens = tokenizer.tokenize("let x: Int = 42;").unwrap();
+        let tokens = Tokenizer::new("let x: Int = 42;").tokenize().unwrap();
 
         let mut parser = Parser::new(tokens);
         let program = parser.parse().unwrap();
@@ -82,8 +79,7 @@ mod new_features_tests {
 
     #[test]
     fn test_type_annotation_bool() {
-        let mut tokenizer = Tokenizer::new("");
-        let tokens = tokenizer.tokenize("let flag: Bool = true;").unwrap();
+        let tokens = Tokenizer::new("let flag: Bool = true;").tokenize().unwrap();
 
         let mut parser = Parser::new(tokens);
         let program = parser.parse().unwrap();
@@ -103,8 +99,7 @@ mod new_features_tests {
 
     #[test]
     fn test_type_annotation_mismatch() {
-        let mut tokenizer = Tokenizer::new("");
-        let tokens = tokenizer.tokenize("let x: Int = true;").unwrap();
+        let tokens = Tokenizer::new("let x: Int = true;").tokenize().unwrap();
 
         let mut parser = Parser::new(tokens);
         let program = parser.parse().unwrap();
@@ -118,9 +113,8 @@ mod new_features_tests {
 
     #[test]
     fn test_function_type_annotation() {
-        let mut tokenizer = Tokenizer::new("");
-        let tokens = tokenizer
-            .tokenize("let f: Int -> Int = fn(x) { x };")
+        let tokens = Tokenizer::new("let f: Int -> Int = fn(x) { x };")
+            .tokenize()
             .unwrap();
 
         let mut parser = Parser::new(tokens);
@@ -166,9 +160,8 @@ mod new_features_tests {
 
     #[test]
     fn test_higher_order_function_type_annotation() {
-        let mut tokenizer = Tokenizer::new("");
-        let tokens = tokenizer
-            .tokenize("let g: (Int -> Int) -> Bool = fn(f) { true };")
+        let tokens = Tokenizer::new("let g: (Int -> Int) -> Bool = fn(f) { true };")
+            .tokenize()
             .unwrap();
 
         let mut parser = Parser::new(tokens);
@@ -219,9 +212,8 @@ mod new_features_tests {
 
     #[test]
     fn test_type_inference_with_arithmetic() {
-        let mut tokenizer = Tokenizer::new("");
-        let tokens = tokenizer
-            .tokenize("let add: Int -> Int = fn(x) { x + 1 };")
+        let tokens = Tokenizer::new("let add: Int -> Int = fn(x) { x + 1 };")
+            .tokenize()
             .unwrap();
 
         let mut parser = Parser::new(tokens);
@@ -247,8 +239,7 @@ mod new_features_tests {
 
     #[test]
     fn test_sum_type_inl() {
-        let mut tokenizer = Tokenizer::new("");
-        let tokens = tokenizer.tokenize("inl(5);").unwrap();
+        let tokens = Tokenizer::new("inl(5);").tokenize().unwrap();
         let mut parser = Parser::new(tokens);
         let program = parser.parse().unwrap();
         let mut type_checker = TypeChecker::new();
@@ -276,8 +267,7 @@ mod new_features_tests {
           inl a => a + 1
         | inr b => 0;
         ";
-        let mut tokenizer = Tokenizer::new("");
-        let tokens = tokenizer.tokenize(input).unwrap();
+        let tokens = Tokenizer::new(input).tokenize().unwrap();
         let mut parser = Parser::new(tokens);
         let program = parser.parse().unwrap();
         let mut type_checker = TypeChecker::new();
@@ -294,8 +284,7 @@ mod new_features_tests {
     #[test]
     fn test_sum_type_annotation() {
         let input = "let x: Int + Bool = inl(5);";
-        let mut tokenizer = Tokenizer::new("");
-        let tokens = tokenizer.tokenize(input).unwrap();
+        let tokens = Tokenizer::new(input).tokenize().unwrap();
         let mut parser = Parser::new(tokens);
         let program = parser.parse().unwrap();
         let mut type_checker = TypeChecker::new();
@@ -315,8 +304,7 @@ mod new_features_tests {
 
     #[test]
     fn test_string_literal() {
-        let mut tokenizer = Tokenizer::new("");
-        let tokens = tokenizer.tokenize(r#""Hello World";"#).unwrap();
+        let tokens = Tokenizer::new(r#""Hello World";"#).tokenize().unwrap();
 
         let mut parser = Parser::new(tokens);
         let program = parser.parse().unwrap();
@@ -337,8 +325,7 @@ mod new_features_tests {
 
     #[test]
     fn test_string_concatenation() {
-        let mut tokenizer = Tokenizer::new("");
-        let tokens = tokenizer.tokenize(r#""Hello" + " World";"#).unwrap();
+        let tokens = Tokenizer::new(r#""Hello" + " World";"#).tokenize().unwrap();
 
         let mut parser = Parser::new(tokens);
         let program = parser.parse().unwrap();
@@ -359,8 +346,7 @@ mod new_features_tests {
 
     #[test]
     fn test_string_length() {
-        let mut tokenizer = Tokenizer::new("");
-        let tokens = tokenizer.tokenize(r#"length("Hello");"#).unwrap();
+        let tokens = Tokenizer::new(r#"length("Hello");"#).tokenize().unwrap();
 
         let mut parser = Parser::new(tokens);
         let program = parser.parse().unwrap();
@@ -381,8 +367,7 @@ mod new_features_tests {
 
     #[test]
     fn test_string_char_at() {
-        let mut tokenizer = Tokenizer::new("");
-        let tokens = tokenizer.tokenize(r#"char("Hello", 0);"#).unwrap();
+        let tokens = Tokenizer::new(r#"char("Hello", 0);"#).tokenize().unwrap();
 
         let mut parser = Parser::new(tokens);
         let program = parser.parse().unwrap();
@@ -403,8 +388,9 @@ mod new_features_tests {
 
     #[test]
     fn test_string_concat_function() {
-        let mut tokenizer = Tokenizer::new("");
-        let tokens = tokenizer.tokenize(r#"concat("Hello", " World");"#).unwrap();
+        let tokens = Tokenizer::new(r#"concat("Hello", " World");"#)
+            .tokenize()
+            .unwrap();
 
         let mut parser = Parser::new(tokens);
         let program = parser.parse().unwrap();
@@ -425,8 +411,7 @@ mod new_features_tests {
 
     #[test]
     fn test_tostring_int() {
-        let mut tokenizer = Tokenizer::new("");
-        let tokens = tokenizer.tokenize("toString(42);").unwrap();
+        let tokens = Tokenizer::new("toString(42);").tokenize().unwrap();
 
         let mut parser = Parser::new(tokens);
         let program = parser.parse().unwrap();
@@ -447,8 +432,7 @@ mod new_features_tests {
 
     #[test]
     fn test_tostring_bool() {
-        let mut tokenizer = Tokenizer::new("");
-        let tokens = tokenizer.tokenize("toString(true);").unwrap();
+        let tokens = Tokenizer::new("toString(true);").tokenize().unwrap();
 
         let mut parser = Parser::new(tokens);
         let program = parser.parse().unwrap();
@@ -469,8 +453,7 @@ mod new_features_tests {
 
     #[test]
     fn test_tostring_list() {
-        let mut tokenizer = Tokenizer::new("");
-        let tokens = tokenizer.tokenize("toString([1, 2, 3]);").unwrap();
+        let tokens = Tokenizer::new("toString([1, 2, 3]);").tokenize().unwrap();
 
         let mut parser = Parser::new(tokens);
         let program = parser.parse().unwrap();
@@ -491,8 +474,7 @@ mod new_features_tests {
 
     #[test]
     fn test_type_expression_int() {
-        let mut tokenizer = Tokenizer::new("");
-        let tokens = tokenizer.tokenize("type(42);").unwrap();
+        let tokens = Tokenizer::new("type(42);").tokenize().unwrap();
 
         let mut parser = Parser::new(tokens);
         let program = parser.parse().unwrap();
@@ -518,8 +500,7 @@ mod new_features_tests {
 
     #[test]
     fn test_type_expression_string() {
-        let mut tokenizer = Tokenizer::new("");
-        let tokens = tokenizer.tokenize("type(\"hello\");").unwrap();
+        let tokens = Tokenizer::new("type(\"hello\");").tokenize().unwrap();
 
         let mut parser = Parser::new(tokens);
         let program = parser.parse().unwrap();
@@ -531,8 +512,7 @@ mod new_features_tests {
 
     #[test]
     fn test_type_expression_bool() {
-        let mut tokenizer = Tokenizer::new("");
-        let tokens = tokenizer.tokenize("type(true);").unwrap();
+        let tokens = Tokenizer::new("type(true);").tokenize().unwrap();
 
         let mut parser = Parser::new(tokens);
         let program = parser.parse().unwrap();
@@ -544,8 +524,7 @@ mod new_features_tests {
 
     #[test]
     fn test_type_expression_list() {
-        let mut tokenizer = Tokenizer::new("");
-        let tokens = tokenizer.tokenize("type([1, 2, 3]);").unwrap();
+        let tokens = Tokenizer::new("type([1, 2, 3]);").tokenize().unwrap();
 
         let mut parser = Parser::new(tokens);
         let program = parser.parse().unwrap();
@@ -557,8 +536,7 @@ mod new_features_tests {
 
     #[test]
     fn test_type_expression_empty_list() {
-        let mut tokenizer = Tokenizer::new("");
-        let tokens = tokenizer.tokenize("type([]);").unwrap();
+        let tokens = Tokenizer::new("type([]);").tokenize().unwrap();
 
         let mut parser = Parser::new(tokens);
         let program = parser.parse().unwrap();
@@ -570,8 +548,7 @@ mod new_features_tests {
 
     #[test]
     fn test_type_expression_pair() {
-        let mut tokenizer = Tokenizer::new("");
-        let tokens = tokenizer.tokenize("type((42, true));").unwrap();
+        let tokens = Tokenizer::new("type((42, true));").tokenize().unwrap();
 
         let mut parser = Parser::new(tokens);
         let program = parser.parse().unwrap();
@@ -583,8 +560,9 @@ mod new_features_tests {
 
     #[test]
     fn test_type_expression_nested_list() {
-        let mut tokenizer = Tokenizer::new("");
-        let tokens = tokenizer.tokenize("type([[1, 2], [3, 4]]);").unwrap();
+        let tokens = Tokenizer::new("type([[1, 2], [3, 4]]);")
+            .tokenize()
+            .unwrap();
 
         let mut parser = Parser::new(tokens);
         let program = parser.parse().unwrap();
@@ -596,8 +574,7 @@ mod new_features_tests {
 
     #[test]
     fn test_type_expression_unit() {
-        let mut tokenizer = Tokenizer::new("");
-        let tokens = tokenizer.tokenize("type(print(42));").unwrap();
+        let tokens = Tokenizer::new("type(print(42));").tokenize().unwrap();
 
         let mut parser = Parser::new(tokens);
         let program = parser.parse().unwrap();
@@ -609,8 +586,7 @@ mod new_features_tests {
 
     #[test]
     fn test_type_expression_function_arithmetic() {
-        let mut tokenizer = Tokenizer::new("");
-        let tokens = tokenizer.tokenize("type(fn(x) { x + 1 });").unwrap();
+        let tokens = Tokenizer::new("type(fn(x) { x + 1 });").tokenize().unwrap();
 
         let mut parser = Parser::new(tokens);
         let program = parser.parse().unwrap();
@@ -622,8 +598,9 @@ mod new_features_tests {
 
     #[test]
     fn test_type_expression_function_comparison() {
-        let mut tokenizer = Tokenizer::new("");
-        let tokens = tokenizer.tokenize("type(fn(x) { x == 42 });").unwrap();
+        let tokens = Tokenizer::new("type(fn(x) { x == 42 });")
+            .tokenize()
+            .unwrap();
 
         let mut parser = Parser::new(tokens);
         let program = parser.parse().unwrap();
@@ -635,8 +612,9 @@ mod new_features_tests {
 
     #[test]
     fn test_type_expression_function_pair_access() {
-        let mut tokenizer = Tokenizer::new("");
-        let tokens = tokenizer.tokenize("type(fn(p) { fst(p) });").unwrap();
+        let tokens = Tokenizer::new("type(fn(p) { fst(p) });")
+            .tokenize()
+            .unwrap();
 
         let mut parser = Parser::new(tokens);
         let program = parser.parse().unwrap();
@@ -651,8 +629,9 @@ mod new_features_tests {
 
     #[test]
     fn test_type_expression_function_list_access() {
-        let mut tokenizer = Tokenizer::new("");
-        let tokens = tokenizer.tokenize("type(fn(lst) { head(lst) });").unwrap();
+        let tokens = Tokenizer::new("type(fn(lst) { head(lst) });")
+            .tokenize()
+            .unwrap();
 
         let mut parser = Parser::new(tokens);
         let program = parser.parse().unwrap();
@@ -661,4 +640,1633 @@ mod new_features_tests {
         let result = interpreter.interpret_program_repl(&program).unwrap();
         assert_eq!(result, Value::String("List Unknown -> Unknown".to_string()));
     }
+
+    #[test]
+    fn test_function_composition_operators() {
+        let source = "
+            let add_one = fn(x: Int) { x + 1 };
+            let double = fn(x: Int) { x * 2 };
+            let forward = add_one >> double;
+            let backward = add_one << double;
+            (forward(3), backward(3));
+        ";
+        let tokens = Tokenizer::new(source).tokenize().unwrap();
+
+        let mut parser = Parser::new(tokens);
+        let program = parser.parse().unwrap();
+
+        let mut type_checker = TypeChecker::new();
+        let typed_program = type_checker.check_program(&program).unwrap();
+        match &typed_program.statements[2] {
+            crate::typechecker::TypedStatement::VariableDeclaration { ty, .. } => {
+                assert_eq!(*ty, Type::function(Type::Int, Type::Int));
+            }
+            _ => panic!("Expected variable declaration"),
+        }
+
+        let mut interpreter = Interpreter::new();
+        let result = interpreter.interpret_program_repl(&program).unwrap();
+        assert_eq!(
+            result,
+            Value::Pair(Box::new(Value::Int(8)), Box::new(Value::Int(7)))
+        );
+    }
+
+    #[test]
+    fn test_function_composition_type_mismatch() {
+        let source = "
+            let is_zero = fn(x: Int) { x == 0 };
+            let double = fn(x: Int) { x * 2 };
+            is_zero >> double;
+        ";
+        let tokens = Tokenizer::new(source).tokenize().unwrap();
+
+        let mut parser = Parser::new(tokens);
+        let program = parser.parse().unwrap();
+
+        let mut type_checker = TypeChecker::new();
+        assert!(type_checker.check_program(&program).is_err());
+    }
+
+    #[test]
+    fn test_cons_operator_builds_list() {
+        let tokens = Tokenizer::new("let xs = [3]; 1 :: 2 :: xs;")
+            .tokenize()
+            .unwrap();
+
+        let mut parser = Parser::new(tokens);
+        let program = parser.parse().unwrap();
+
+        let mut type_checker = TypeChecker::new();
+        type_checker.check_program(&program).unwrap();
+
+        let mut interpreter = Interpreter::new();
+        let result = interpreter.interpret_program_repl(&program).unwrap();
+        assert_eq!(
+            result,
+            Value::List(vec![Value::Int(1), Value::Int(2), Value::Int(3)])
+        );
+    }
+
+    #[test]
+    fn test_append_operator_on_lists_and_strings() {
+        let tokens = Tokenizer::new("let xs = [1, 2] ++ [3] ++ []; (xs, \"ab\" ++ \"cd\");")
+            .tokenize()
+            .unwrap();
+
+        let mut parser = Parser::new(tokens);
+        let program = parser.parse().unwrap();
+
+        let mut type_checker = TypeChecker::new();
+        let typed_program = type_checker.check_program(&program).unwrap();
+        match &typed_program.statements[0] {
+            crate::typechecker::TypedStatement::VariableDeclaration { ty, .. } => {
+                assert_eq!(*ty, Type::list(Type::Int));
+            }
+            _ => panic!("Expected variable declaration"),
+        }
+
+        let mut interpreter = Interpreter::new();
+        let result = interpreter.interpret_program_repl(&program).unwrap();
+        assert_eq!(
+            result,
+            Value::Pair(
+                Box::new(Value::List(vec![
+                    Value::Int(1),
+                    Value::Int(2),
+                    Value::Int(3)
+                ])),
+                Box::new(Value::String("abcd".to_string()))
+            )
+        );
+    }
+
+    #[test]
+    fn test_append_operator_rejects_mismatched_elements() {
+        let tokens = Tokenizer::new("[1] ++ [\"a\"];").tokenize().unwrap();
+
+        let mut parser = Parser::new(tokens);
+        let program = parser.parse().unwrap();
+
+        let mut type_checker = TypeChecker::new();
+        assert!(type_checker.check_program(&program).is_err());
+    }
+
+    #[test]
+    fn test_tuple_construction_and_projection() {
+        let tokens =
+            Tokenizer::new("let t: (Int, String, Bool) = (1, \"x\", true); (t.2, t.0 + 1);")
+                .tokenize()
+                .unwrap();
+
+        let mut parser = Parser::new(tokens);
+        let program = parser.parse().unwrap();
+
+        let mut type_checker = TypeChecker::new();
+        let typed_program = type_checker.check_program(&program).unwrap();
+        match &typed_program.statements[0] {
+            crate::typechecker::TypedStatement::VariableDeclaration { ty, .. } => {
+                assert_eq!(
+                    *ty,
+                    Type::Tuple {
+                        elements: vec![Type::Int, Type::String, Type::Bool]
+                    }
+                );
+            }
+            _ => panic!("Expected variable declaration"),
+        }
+
+        let mut interpreter = Interpreter::new();
+        let result = interpreter.interpret_program_repl(&program).unwrap();
+        assert_eq!(
+            result,
+            Value::Pair(Box::new(Value::Bool(true)), Box::new(Value::Int(2)))
+        );
+    }
+
+    #[test]
+    fn test_tuple_projection_out_of_range_is_type_error() {
+        let tokens = Tokenizer::new("(1, 2, 3).3;").tokenize().unwrap();
+
+        let mut parser = Parser::new(tokens);
+        let program = parser.parse().unwrap();
+
+        let mut type_checker = TypeChecker::new();
+        assert!(matches!(
+            type_checker.check_program(&program),
+            Err(crate::typechecker::TypeError::TupleIndexOutOfRange { index: 3, .. })
+        ));
+    }
+
+    #[test]
+    fn test_if_let_desugars_to_case() {
+        let tokens = Tokenizer::new(
+            "let r: (Int + String) = inl(41); \
+                 let a = if let inl(n) = r { n + 1 } else { 0 }; \
+                 let b = if let inr(msg) = r { 1 } else { 2 }; \
+                 (a, b);",
+        )
+        .tokenize()
+        .unwrap();
+
+        let mut parser = Parser::new(tokens);
+        let program = parser.parse().unwrap();
+
+        let mut type_checker = TypeChecker::new();
+        type_checker.check_program(&program).unwrap();
+
+        let mut interpreter = Interpreter::new();
+        let result = interpreter.interpret_program_repl(&program).unwrap();
+        assert_eq!(
+            result,
+            Value::Pair(Box::new(Value::Int(42)), Box::new(Value::Int(2)))
+        );
+    }
+
+    #[test]
+    fn test_ordering_on_strings_lists_and_tuples() {
+        let tokens = Tokenizer::new("[\"apple\" < \"banana\", [1, 2] < [1, 3], (2, \"a\") <= (1, \"b\"), (1, 2, 3) == (1, 2, 3)];")
+            .tokenize()
+            .unwrap();
+
+        let mut parser = Parser::new(tokens);
+        let program = parser.parse().unwrap();
+
+        let mut type_checker = TypeChecker::new();
+        type_checker.check_program(&program).unwrap();
+
+        let mut interpreter = Interpreter::new();
+        let result = interpreter.interpret_program_repl(&program).unwrap();
+        assert_eq!(
+            result,
+            Value::List(vec![
+                Value::Bool(true),
+                Value::Bool(true),
+                Value::Bool(false),
+                Value::Bool(true)
+            ])
+        );
+    }
+
+    #[test]
+    fn test_comparing_functions_violates_eq_constraint() {
+        let tokens = Tokenizer::new("let f = fn(x: Int) { x }; f == f;")
+            .tokenize()
+            .unwrap();
+
+        let mut parser = Parser::new(tokens);
+        let program = parser.parse().unwrap();
+
+        let mut type_checker = TypeChecker::new();
+        assert!(matches!(
+            type_checker.check_program(&program),
+            Err(crate::typechecker::TypeError::UnsatisfiedConstraint {
+                constraint: crate::typechecker::Constraint::Eq,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_type_ascription_guides_inference() {
+        let tokens = Tokenizer::new("let xs = ([] : List Int); let r = (inl(5) : Int + String);")
+            .tokenize()
+            .unwrap();
+
+        let mut parser = Parser::new(tokens);
+        let program = parser.parse().unwrap();
+
+        let mut type_checker = TypeChecker::new();
+        let typed_program = type_checker.check_program(&program).unwrap();
+        let declared_types: Vec<Type> = typed_program
+            .statements
+            .iter()
+            .map(|statement| match statement {
+                crate::typechecker::TypedStatement::VariableDeclaration { ty, .. } => ty.clone(),
+                _ => panic!("Expected variable declaration"),
+            })
+            .collect();
+        assert_eq!(
+            declared_types,
+            vec![Type::list(Type::Int), Type::sum(Type::Int, Type::String)]
+        );
+
+        // The ascribed element type is enforced afterwards
+        let tokens = Tokenizer::new("let xs = ([] : List Int); cons(\"a\", xs);")
+            .tokenize()
+            .unwrap();
+        let mut parser = Parser::new(tokens);
+        let program = parser.parse().unwrap();
+        let mut type_checker = TypeChecker::new();
+        assert!(type_checker.check_program(&program).is_err());
+    }
+
+    #[test]
+    fn test_expected_type_flows_into_empty_lists_and_injections() {
+        let tokens = Tokenizer::new(
+            "fn total(xs: List Int) -> Int { 0 } \
+                 let ys = cons(1, []); \
+                 let r: (Int + String, List Bool) = (inr(\"no\"), []); \
+                 total([]);",
+        )
+        .tokenize()
+        .unwrap();
+
+        let mut parser = Parser::new(tokens);
+        let program = parser.parse().unwrap();
+
+        let mut type_checker = TypeChecker::new();
+        let typed_program = type_checker.check_program(&program).unwrap();
+        match &typed_program.statements[1] {
+            crate::typechecker::TypedStatement::VariableDeclaration { ty, .. } => {
+                assert_eq!(*ty, Type::list(Type::Int));
+            }
+            _ => panic!("Expected variable declaration"),
+        }
+        match &typed_program.statements[2] {
+            crate::typechecker::TypedStatement::VariableDeclaration { value, .. } => {
+                assert_eq!(
+                    value.ty,
+                    Type::pair(Type::sum(Type::Int, Type::String), Type::list(Type::Bool))
+                );
+            }
+            _ => panic!("Expected variable declaration"),
+        }
+    }
+
+    #[test]
+    fn test_injections_only_match_sum_parameters() {
+        let tokens = Tokenizer::new(
+            "let describe = fn(r: Int + String) { 0 }; \
+                 describe(inl(5)); \
+                 [(inl(1) : Int + String), inr(\"failed\")];",
+        )
+        .tokenize()
+        .unwrap();
+        let mut parser = Parser::new(tokens);
+        let program = parser.parse().unwrap();
+        let mut type_checker = TypeChecker::new();
+        assert!(type_checker.check_program(&program).is_ok());
+
+        let tokens = Tokenizer::new("let double = fn(x: Int) { x * 2 }; double(inl(5));")
+            .tokenize()
+            .unwrap();
+        let mut parser = Parser::new(tokens);
+        let program = parser.parse().unwrap();
+        let mut type_checker = TypeChecker::new();
+        assert!(matches!(
+            type_checker.check_program(&program),
+            Err(crate::typechecker::TypeError::TypeMismatch {
+                expected: Type::Int,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_strict_mode_requires_precise_types_or_dyn() {
+        let check_strict = |source: &str| {
+            let tokens = Tokenizer::new(source).tokenize().unwrap();
+            let mut parser = Parser::new(tokens);
+            let program = parser.parse().unwrap();
+            let mut type_checker = TypeChecker::new();
+            type_checker.set_strict(true);
+            type_checker.check_program(&program)
+        };
+
+        assert!(matches!(
+            check_strict("let xs = [];"),
+            Err(crate::typechecker::TypeError::ImpreciseType { .. })
+        ));
+        assert!(matches!(
+            check_strict("let id = fn(x) { x };"),
+            Err(crate::typechecker::TypeError::ImpreciseType { .. })
+        ));
+        assert!(
+            check_strict("let xs: List Dyn = []; let id = fn(x: Dyn) { x }; id(1) + 2;").is_ok()
+        );
+
+        // A function value is checked where it is written, bound or not
+        let imprecise = |source: &str| match check_strict(source) {
+            Err(crate::typechecker::TypeError::ImpreciseType { name, .. }) => name,
+            other => panic!("expected an imprecise type, found {:?}", other),
+        };
+        assert_eq!(imprecise("print(fn(x) { x });"), "x");
+        assert_eq!(imprecise("fn apply(f) { f(1) }"), "apply");
+        assert_eq!(imprecise("let v = inl(1);"), "v");
+
+        // Types known from where the function is written are used
+        assert!(
+            check_strict(
+                "fn twice(f: Int -> Int) { f(f(1)) }
+                 twice(fn(x) { x });
+                 join(spawn(fn(u) { 1 }));
+                 fn add_one(x) { x + 1 }
+                 let size = fix(fn(self) {
+                     fn(xs: List Int) { if xs == [] { 0 } else { 1 + self(tail(xs)) } }
+                 });"
+            )
+            .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_strict_checking_is_the_default_from_the_2026_edition() {
+        use corrosion_language::lexer::Edition;
+
+        let run = |options: &crate::RunOptions| {
+            crate::execute_source(
+                "let id = fn(x) { x }; fn main(args: List String) { id(4) }",
+                None,
+                options,
+                &[],
+                false,
+            )
+        };
+        let error = run(&Default::default()).unwrap_err();
+        assert!(error.contains("Type error[E0008]"), "{}", error);
+        let lenient = crate::RunOptions {
+            strict: Some(false),
+            ..Default::default()
+        };
+        assert_eq!(run(&lenient), Ok(4));
+        let old = crate::RunOptions {
+            edition: Some(Edition::E2025),
+            ..Default::default()
+        };
+        assert_eq!(run(&old), Ok(4));
+    }
+
+    #[test]
+    fn test_is_operator_narrows_dynamic_variables() {
+        let tokens = Tokenizer::new(
+            "let size = fn(x: Dyn) { if x is String { length(x) } else { 0 } }; \
+                 (size(\"abc\"), size(7), inl(3) is Int + String);",
+        )
+        .tokenize()
+        .unwrap();
+
+        let mut parser = Parser::new(tokens);
+        let program = parser.parse().unwrap();
+
+        let mut type_checker = TypeChecker::new();
+        type_checker.check_program(&program).unwrap();
+
+        let mut interpreter = Interpreter::new();
+        let result = interpreter.interpret_program_repl(&program).unwrap();
+        assert_eq!(
+            result,
+            Value::Tuple(vec![Value::Int(3), Value::Int(0), Value::Bool(true)])
+        );
+    }
+
+    #[test]
+    fn test_division_truncates_and_divmod_safe_div() {
+        let tokens = Tokenizer::new(
+            "let min = -9223372036854775807 - 1; \
+                 (-7 / 2, divmod(-7, 2), safeDiv(7, 2), safeDiv(1, 0), safeDiv(min, -1));",
+        )
+        .tokenize()
+        .unwrap();
+
+        let mut parser = Parser::new(tokens);
+        let program = parser.parse().unwrap();
+
+        let mut type_checker = TypeChecker::new();
+        type_checker.check_program(&program).unwrap();
+
+        let mut interpreter = Interpreter::new();
+        let result = interpreter.interpret_program_repl(&program).unwrap();
+        assert_eq!(
+            result,
+            Value::Tuple(vec![
+                Value::Int(-3),
+                Value::Pair(Box::new(Value::Int(-3)), Box::new(Value::Int(-1))),
+                Value::LeftInject(Box::new(Value::Int(3))),
+                Value::RightInject(Box::new(Value::String("Division by zero".to_string()))),
+                Value::RightInject(Box::new(Value::String("Integer overflow".to_string()))),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_big_int_arithmetic_and_int_overflow() {
+        let tokens = Tokenizer::new(
+            "let max = 9223372036854775807; \
+                 let big: BigInt = max * 2n + 2; \
+                 (big, big / -4, -big < 0n, big is BigInt);",
+        )
+        .tokenize()
+        .unwrap();
+
+        let mut parser = Parser::new(tokens);
+        let program = parser.parse().unwrap();
+
+        let mut type_checker = TypeChecker::new();
+        type_checker.check_program(&program).unwrap();
+
+        let mut interpreter = Interpreter::new();
+        let result = interpreter.interpret_program_repl(&program).unwrap();
+        let big = num_bigint::BigInt::from(2u8).pow(64);
+        assert_eq!(
+            result,
+            Value::Tuple(vec![
+                Value::BigInt(big.clone()),
+                Value::BigInt(-big / 4),
+                Value::Bool(true),
+                Value::Bool(true),
+            ])
+        );
+
+        // Plain Int arithmetic reports overflow instead of wrapping
+        let tokens = Tokenizer::new("9223372036854775807 + 1;")
+            .tokenize()
+            .unwrap();
+        let program = Parser::new(tokens).parse().unwrap();
+        let err = Interpreter::new()
+            .interpret_program_repl(&program)
+            .unwrap_err();
+        assert!(err.to_string().contains("Integer overflow"));
+    }
+
+    #[test]
+    fn test_graphemes_keeps_clusters_whole() {
+        let tokens =
+            Tokenizer::new("let word = \"cafe\u{301}!\"; (length(word), graphemes(word));")
+                .tokenize()
+                .unwrap();
+
+        let mut parser = Parser::new(tokens);
+        let program = parser.parse().unwrap();
+
+        let mut type_checker = TypeChecker::new();
+        type_checker.check_program(&program).unwrap();
+
+        let mut interpreter = Interpreter::new();
+        let result = interpreter.interpret_program_repl(&program).unwrap();
+        let clusters = ["c", "a", "f", "e\u{301}", "!"]
+            .iter()
+            .map(|g| Value::String(g.to_string()))
+            .collect();
+        assert_eq!(
+            result,
+            Value::Pair(Box::new(Value::Int(6)), Box::new(Value::List(clusters)))
+        );
+    }
+
+    #[test]
+    fn test_bytes_round_trip_and_decode_errors() {
+        let tokens = Tokenizer::new(
+            "let data = toBytes(\"hé\") ++ b\"\\xff\"; \
+                 (length(data), byteAt(data, 2), fromBytes(b\"ok\"), fromBytes(data));",
+        )
+        .tokenize()
+        .unwrap();
+
+        let mut parser = Parser::new(tokens);
+        let program = parser.parse().unwrap();
+
+        let mut type_checker = TypeChecker::new();
+        type_checker.check_program(&program).unwrap();
+
+        let mut interpreter = Interpreter::new();
+        let result = interpreter.interpret_program_repl(&program).unwrap();
+        let Value::Tuple(elements) = result else {
+            panic!("Expected a tuple, found {:?}", result);
+        };
+        assert_eq!(elements[0], Value::Int(4));
+        assert_eq!(elements[1], Value::Int(0xa9));
+        assert_eq!(
+            elements[2],
+            Value::LeftInject(Box::new(Value::String("ok".to_string())))
+        );
+        assert!(matches!(&elements[3], Value::RightInject(message)
+            if message.to_string().contains("Invalid UTF-8")));
+    }
+
+    #[test]
+    fn test_std_fs_module() {
+        let dir = std::env::temp_dir().join(format!("corrosion-fs-test-{}", std::process::id()));
+        let dir = dir.to_string_lossy();
+        let source = format!(
+            "import \"std:fs\" as fs; \
+             let dir = \"{dir}\"; \
+             fs.mkdir(dir ++ \"/sub\"); \
+             let copyMissing = fs.copy(dir ++ \"/missing.txt\"); \
+             let listing = fs.listDir(dir); \
+             let stillThere = fn(path: String) {{ fs.exists(path) }}; \
+             (fs.isDir(dir ++ \"/sub\"), listing, copyMissing(dir ++ \"/copy.txt\"), \
+              fs.remove(dir ++ \"/sub\"), fs.remove(dir), stillThere(dir));"
+        );
+
+        let tokens = Tokenizer::new(&source).tokenize().unwrap();
+
+        let mut parser = Parser::new(tokens);
+        let program = parser.parse().unwrap();
+
+        let mut type_checker = TypeChecker::new();
+        type_checker.check_program(&program).unwrap();
+
+        let mut interpreter = Interpreter::new();
+        let result = interpreter.interpret_program_repl(&program).unwrap();
+        let Value::Tuple(elements) = result else {
+            panic!("Expected a tuple, found {:?}", result);
+        };
+        let unit_ok = Value::LeftInject(Box::new(Value::Unit));
+        assert_eq!(elements[0], Value::Bool(true));
+        assert_eq!(
+            elements[1],
+            Value::LeftInject(Box::new(Value::List(vec![Value::String(
+                "sub".to_string()
+            )])))
+        );
+        assert!(matches!(elements[2], Value::RightInject(_)));
+        assert_eq!(elements[3], unit_ok);
+        assert_eq!(elements[4], unit_ok);
+        assert_eq!(elements[5], Value::Bool(false));
+    }
+
+    #[test]
+    fn test_unknown_std_module_is_rejected() {
+        let tokens = Tokenizer::new("import \"std:nope\" as nope;")
+            .tokenize()
+            .unwrap();
+
+        let mut parser = Parser::new(tokens);
+        let program = parser.parse().unwrap();
+
+        let mut type_checker = TypeChecker::new();
+        let err = type_checker.check_program(&program).unwrap_err();
+        assert!(err.to_string().contains("Unknown standard module"));
+    }
+
+    #[cfg(feature = "http")]
+    #[test]
+    fn test_std_http_module() {
+        use std::io::{Read, Write};
+
+        // Serve two canned responses from a local socket
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = std::thread::spawn(move || {
+            for response in ["200 OK", "404 Not Found"] {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut request = [0; 1024];
+                let _ = stream.read(&mut request).unwrap();
+                write!(
+                    stream,
+                    "HTTP/1.1 {}\r\nContent-Length: 5\r\nConnection: close\r\n\r\nhello",
+                    response
+                )
+                .unwrap();
+            }
+        });
+
+        let source = format!(
+            "import \"std:http\" as http; \
+             (http.get(\"http://{address}/\"), http.post(\"http://{address}/missing\")(\"data\"));"
+        );
+        let tokens = Tokenizer::new(&source).tokenize().unwrap();
+
+        let mut parser = Parser::new(tokens);
+        let program = parser.parse().unwrap();
+
+        let mut type_checker = TypeChecker::new();
+        type_checker.check_program(&program).unwrap();
+
+        let mut interpreter = Interpreter::new();
+        let result = interpreter.interpret_program_repl(&program).unwrap();
+        server.join().unwrap();
+
+        let response = |status: i64| {
+            Value::LeftInject(Box::new(Value::Pair(
+                Box::new(Value::Int(status)),
+                Box::new(Value::String("hello".to_string())),
+            )))
+        };
+        assert_eq!(
+            result,
+            Value::Pair(Box::new(response(200)), Box::new(response(404)))
+        );
+    }
+
+    #[test]
+    fn test_spawn_join_and_sleep() {
+        let tokens = Tokenizer::new(
+            "let base = 10; \
+                 let work = fn(n: Int) { sleep(10); base * n }; \
+                 let first: Handle Int = spawn(fn(u) { work(1) }); \
+                 let second = spawn(fn(u) { work(2) }); \
+                 join(first) + join(second);",
+        )
+        .tokenize()
+        .unwrap();
+
+        let mut parser = Parser::new(tokens);
+        let program = parser.parse().unwrap();
+
+        let mut type_checker = TypeChecker::new();
+        type_checker.check_program(&program).unwrap();
+
+        let mut interpreter = Interpreter::new();
+        let result = interpreter.interpret_program_repl(&program).unwrap();
+        assert_eq!(result, Value::Int(30));
+
+        // A handle can only be joined once, and only handles can be joined
+        let tokens = Tokenizer::new("let h = spawn(fn(u) { 1 }); join(h); join(h);")
+            .tokenize()
+            .unwrap();
+        let program = Parser::new(tokens).parse().unwrap();
+        let err = Interpreter::new()
+            .interpret_program_repl(&program)
+            .unwrap_err();
+        assert!(err.to_string().contains("already been joined"));
+
+        let tokens = Tokenizer::new("join(5);").tokenize().unwrap();
+        let program = Parser::new(tokens).parse().unwrap();
+        assert!(TypeChecker::new().check_program(&program).is_err());
+    }
+
+    #[test]
+    fn test_channels_between_threads() {
+        let tokens = Tokenizer::new(
+            "let results: Channel Int = channel(); \
+                 let worker = fn(n: Int) { spawn(fn(u) { send(results, n * n) }) }; \
+                 join(worker(3)); \
+                 join(worker(4)); \
+                 let names = channel(); \
+                 send(names, \"ok\"); \
+                 (recv(results) + recv(results), recv(names));",
+        )
+        .tokenize()
+        .unwrap();
+
+        let mut parser = Parser::new(tokens);
+        let program = parser.parse().unwrap();
+
+        let mut type_checker = TypeChecker::new();
+        type_checker.check_program(&program).unwrap();
+
+        let mut interpreter = Interpreter::new();
+        let result = interpreter.interpret_program_repl(&program).unwrap();
+        assert_eq!(
+            result,
+            Value::Pair(
+                Box::new(Value::Int(25)),
+                Box::new(Value::String("ok".to_string()))
+            )
+        );
+
+        // The first send fixes the element type of an unannotated channel
+        let tokens = Tokenizer::new("let ch = channel(); send(ch, 1); send(ch, \"two\");")
+            .tokenize()
+            .unwrap();
+        let program = Parser::new(tokens).parse().unwrap();
+        assert!(matches!(
+            TypeChecker::new().check_program(&program),
+            Err(crate::typechecker::TypeError::TypeMismatch {
+                expected: Type::Int,
+                found: Type::String,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_with_releases_handles() {
+        let tokens = Tokenizer::new(
+            "let results = channel(); \
+                 let answer = with worker = spawn(fn(u) { send(results, 41) }) { recv(results) + 1 }; \
+                 let kept = with ch = channel() { send(ch, \"ok\"); recv(ch) }; \
+                 (answer, kept);",
+        )
+        .tokenize()
+        .unwrap();
+
+        let mut parser = Parser::new(tokens);
+        let program = parser.parse().unwrap();
+
+        let mut type_checker = TypeChecker::new();
+        type_checker.check_program(&program).unwrap();
+
+        let mut interpreter = Interpreter::new();
+        let result = interpreter.interpret_program_repl(&program).unwrap();
+        assert_eq!(
+            result,
+            Value::Pair(
+                Box::new(Value::Int(42)),
+                Box::new(Value::String("ok".to_string()))
+            )
+        );
+
+        // The channel is closed once the block is left, even by an error
+        for source in [
+            "let ch = channel(); with c = ch { 0 };",
+            "let ch = channel(); with c = ch { head([]) };",
+        ] {
+            let tokens = Tokenizer::new(source).tokenize().unwrap();
+            let program = Parser::new(tokens).parse().unwrap();
+            let mut interpreter = Interpreter::new();
+            let _ = interpreter.interpret_program_repl(&program);
+            let tokens = Tokenizer::new("send(ch, 1);").tokenize().unwrap();
+            let program = Parser::new(tokens).parse().unwrap();
+            let err = interpreter.interpret_program_repl(&program).unwrap_err();
+            assert!(err.to_string().contains("is closed"), "{}", source);
+        }
+
+        // Releasing every handle at the end wakes a thread waiting to receive
+        let tokens = Tokenizer::new("let ch = channel(); let waiter = spawn(fn(u) { recv(ch) });")
+            .tokenize()
+            .unwrap();
+        let program = Parser::new(tokens).parse().unwrap();
+        let mut interpreter = Interpreter::new();
+        interpreter.interpret_program(&program).unwrap();
+        interpreter.release_handles();
+    }
+
+    #[test]
+    fn test_seeded_random_is_reproducible() {
+        // Draws inside functions, blocks and spawned threads all come from the seed
+        let source = "let roll = fn(sides: Int) { random(sides) }; \
+                      let rolls = [roll(6), roll(6), if true { random(6) } else { 0 }, roll(6), random(6)]; \
+                      (rolls, join(spawn(fn(u) { random(1000) })));";
+        let run = |seed: u64| {
+            let tokens = Tokenizer::new(source).tokenize().unwrap();
+            let program = Parser::new(tokens).parse().unwrap();
+            TypeChecker::new().check_program(&program).unwrap();
+
+            let mut interpreter = Interpreter::new();
+            interpreter.set_rng_seed(seed);
+            interpreter.interpret_program_repl(&program).unwrap()
+        };
+
+        let first = run(42);
+        assert_eq!(first, run(42));
+        let Value::Pair(rolls, _) = &first else {
+            panic!("Expected a pair, found {:?}", first);
+        };
+        let Value::List(rolls) = rolls.as_ref() else {
+            panic!("Expected a list, found {:?}", rolls);
+        };
+        assert_eq!(rolls.len(), 5);
+        assert!(
+            rolls
+                .iter()
+                .all(|roll| matches!(roll, Value::Int(n) if (0..6).contains(n)))
+        );
+
+        let tokens = Tokenizer::new("random(0);").tokenize().unwrap();
+        let program = Parser::new(tokens).parse().unwrap();
+        assert!(Interpreter::new().interpret_program_repl(&program).is_err());
+    }
+
+    #[test]
+    fn test_pure_module_exports_follow_file_changes() {
+        let dir = std::env::temp_dir().join(format!("corrosion-cache-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let module = dir.join("constants.corr");
+
+        let run = || {
+            let tokens = Tokenizer::new("import \"constants.corr\" as c; c.answer;")
+                .tokenize()
+                .unwrap();
+            let program = Parser::new(tokens).parse().unwrap();
+
+            let mut type_checker = TypeChecker::new();
+            type_checker.set_current_directory(&dir);
+            type_checker.check_program(&program).unwrap();
+
+            let mut interpreter = Interpreter::new();
+            interpreter.set_current_directory(&dir);
+            interpreter.interpret_program_repl(&program).unwrap()
+        };
+
+        // Cached exports are reused only while the source is unchanged
+        std::fs::write(&module, "let answer = 6 * 7;").unwrap();
+        assert_eq!(run(), Value::Int(42));
+        assert_eq!(run(), Value::Int(42));
+        std::fs::write(&module, "let answer = 6 * 9;").unwrap();
+        assert_eq!(run(), Value::Int(54));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_nested_modules() {
+        let dir =
+            std::env::temp_dir().join(format!("corrosion-nested-test-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("utils/math")).unwrap();
+        std::fs::write(dir.join("utils/mod.corr"), "let version = 2;").unwrap();
+        std::fs::write(
+            dir.join("utils/strings.corr"),
+            "let greet = fn(name: String) { \"hi \" ++ name };",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("utils/math/ops.corr"),
+            "let double = fn(n: Int) { n * 2 };",
+        )
+        .unwrap();
+        // Only imports marked `export` are reachable through the importing module
+        std::fs::write(
+            dir.join("app.corr"),
+            "export import \"utils/strings.corr\" as text; \
+             import \"utils/mod.corr\" as hidden; \
+             let name = \"app\";",
+        )
+        .unwrap();
+
+        let tokens = Tokenizer::new(
+            "import \"utils\" as utils; \
+                 import \"app.corr\" as app; \
+                 (utils.version, utils.strings.greet(app.name), utils.math.ops.double(21), \
+                  app.text.greet(\"there\"));",
+        )
+        .tokenize()
+        .unwrap();
+        let program = Parser::new(tokens).parse().unwrap();
+
+        let mut type_checker = TypeChecker::new();
+        type_checker.set_current_directory(&dir);
+        type_checker.check_program(&program).unwrap();
+
+        let mut interpreter = Interpreter::new();
+        interpreter.set_current_directory(&dir);
+        let result = interpreter.interpret_program_repl(&program).unwrap();
+        assert_eq!(
+            result,
+            Value::Tuple(vec![
+                Value::Int(2),
+                Value::String("hi app".to_string()),
+                Value::Int(42),
+                Value::String("hi there".to_string()),
+            ])
+        );
+
+        let tokens = Tokenizer::new("import \"app.corr\" as app; app.hidden.version;")
+            .tokenize()
+            .unwrap();
+        let program = Parser::new(tokens).parse().unwrap();
+        let mut type_checker = TypeChecker::new();
+        type_checker.set_current_directory(&dir);
+        assert!(type_checker.check_program(&program).is_err());
+
+        let mut interpreter = Interpreter::new();
+        interpreter.set_current_directory(&dir);
+        assert!(interpreter.interpret_program_repl(&program).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_importing_again_without_export_stops_re_exporting() {
+        let dir =
+            std::env::temp_dir().join(format!("corrosion-reexport-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("inner.corr"), "let x = 1;").unwrap();
+        std::fs::write(
+            dir.join("facade.corr"),
+            "export import \"inner.corr\" as inner; \
+             import \"inner.corr\" as inner; \
+             let y = inner.x;",
+        )
+        .unwrap();
+
+        let tokens = Tokenizer::new("import \"facade.corr\" as facade; facade.inner.x;")
+            .tokenize()
+            .unwrap();
+        let program = Parser::new(tokens).parse().unwrap();
+
+        let mut type_checker = TypeChecker::new();
+        type_checker.set_current_directory(&dir);
+        assert!(type_checker.check_program(&program).is_err());
+
+        let mut interpreter = Interpreter::new();
+        interpreter.set_current_directory(&dir);
+        assert!(interpreter.interpret_program_repl(&program).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_main_entry_point() {
+        let tokens = Tokenizer::new(
+            "let prefix = \"arg: \"; \
+                 fn main(args: List String) { prefix ++ head(args) }",
+        )
+        .tokenize()
+        .unwrap();
+        let program = Parser::new(tokens).parse().unwrap();
+
+        let mut type_checker = TypeChecker::new();
+        type_checker.check_program(&program).unwrap();
+        type_checker.check_entry_point(&program).unwrap();
+
+        let mut interpreter = Interpreter::new();
+        interpreter.interpret_program(&program).unwrap();
+        let result = interpreter.run_main(&["first".to_string()]).unwrap();
+        assert_eq!(result, Some(Value::String("arg: first".to_string())));
+
+        // Scripts without main only run top to bottom
+        let tokens = Tokenizer::new("let x = 1;").tokenize().unwrap();
+        let program = Parser::new(tokens).parse().unwrap();
+        let mut interpreter = Interpreter::new();
+        interpreter.interpret_program(&program).unwrap();
+        assert_eq!(interpreter.run_main(&[]).unwrap(), None);
+
+        // main must accept the argument list
+        let tokens = Tokenizer::new("fn main(n: Int) { n }").tokenize().unwrap();
+        let program = Parser::new(tokens).parse().unwrap();
+        let mut type_checker = TypeChecker::new();
+        type_checker.check_program(&program).unwrap();
+        assert!(matches!(
+            type_checker.check_entry_point(&program),
+            Err(crate::typechecker::TypeError::TypeMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_main_result_is_the_exit_status() {
+        // An Int from main is the exit status, as a shell reports it
+        assert_eq!(crate::exit_status(Some(&Value::Int(3))), 3);
+        assert_eq!(crate::exit_status(Some(&Value::Int(-1))), 255);
+        assert_eq!(crate::exit_status(Some(&Value::Unit)), 0);
+        assert_eq!(crate::exit_status(None), 0);
+    }
+
+    #[test]
+    fn test_code_given_with_e_is_checked_and_run() {
+        // `-e` code runs through the same checks, and may leave out the final ';'
+        let options = crate::RunOptions::default();
+        assert_eq!(
+            crate::execute_expression("fn main(args: List String) { 7 }", &options, &[]),
+            Ok(7)
+        );
+        for code in [
+            "if true { 1 } else { 2 }",
+            "with c = channel() { send(c, 1); recv(c) }",
+            "1 + 2 // sum",
+        ] {
+            assert_eq!(
+                crate::execute_expression(code, &options, &[]),
+                Ok(0),
+                "{}",
+                code
+            );
+        }
+        let error = crate::execute_expression("1 + true", &options, &[]).unwrap_err();
+        assert!(error.starts_with("Type error[E0003]"), "{}", error);
+    }
+
+    #[test]
+    fn test_pure_imports_reject_top_level_io() {
+        let dir = std::env::temp_dir().join(format!("corrosion-pure-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("noisy.corr"), "print(\"loading\"); let x = 1;").unwrap();
+        std::fs::write(
+            dir.join("quiet.corr"),
+            "let log = fn(x: Int) { print(x) }; \
+             let files = fn(dir: String) { readFileBytes(dir) };",
+        )
+        .unwrap();
+
+        let check = |source: &str, pure_imports: bool| {
+            let tokens = Tokenizer::new(source).tokenize().unwrap();
+            let program = Parser::new(tokens).parse().unwrap();
+            let mut type_checker = TypeChecker::new();
+            type_checker.set_current_directory(&dir);
+            type_checker.set_pure_imports(pure_imports);
+            type_checker.check_program(&program).map(|_| ())
+        };
+
+        // IO inside functions is fine, and the importing program itself may print
+        assert!(check("import \"quiet.corr\" as q; print(1);", true).is_ok());
+        assert!(check("import \"noisy.corr\" as n;", false).is_ok());
+        let error = check("import \"noisy.corr\" as n;", true).unwrap_err();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(error.to_string().contains("'print' performs IO"));
+    }
+
+    #[test]
+    fn test_undefined_name_suggestions() {
+        let check = |source: &str| {
+            let tokens = Tokenizer::new(source).tokenize().unwrap();
+            let program = Parser::new(tokens).parse().unwrap();
+            TypeChecker::new().check_program(&program).unwrap_err()
+        };
+
+        let error = check("let counter = 1; let f = fn(step: Int) { countr + stpe };");
+        assert!(matches!(
+            &error,
+            crate::typechecker::TypeError::UndefinedVariable { name, suggestion: Some(suggestion), .. }
+                if name == "countr" && suggestion == "counter"
+        ));
+        assert!(error.to_string().ends_with("(did you mean 'counter'?)"));
+
+        let error = check("let step = 1; stpe;");
+        assert!(error.to_string().contains("did you mean 'step'?"));
+
+        // Module members are suggested with their module prefix
+        let error = check("import \"std:fs\" as fs; fs.exsts(\".\");");
+        assert!(matches!(
+            error,
+            crate::typechecker::TypeError::UndefinedVariable { suggestion: Some(suggestion), .. }
+                if suggestion == "fs.exists"
+        ));
+
+        let error = check("let total = 1; banana;");
+        assert!(matches!(
+            error,
+            crate::typechecker::TypeError::UndefinedVariable {
+                suggestion: None,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_error_codes_have_explanations() {
+        let tokenize_error = Tokenizer::new("let s = \"open;").tokenize().unwrap_err();
+        let parse_error = Parser::new(Tokenizer::new("let y = 1 +;").tokenize().unwrap())
+            .parse()
+            .unwrap_err();
+        let type_error = TypeChecker::new()
+            .check_program(
+                &Parser::new(Tokenizer::new("missing;").tokenize().unwrap())
+                    .parse()
+                    .unwrap(),
+            )
+            .unwrap_err();
+        let runtime_error = Interpreter::new()
+            .interpret_program_repl(
+                &Parser::new(Tokenizer::new("1 / 0;").tokenize().unwrap())
+                    .parse()
+                    .unwrap(),
+            )
+            .unwrap_err();
+
+        assert_eq!(tokenize_error.code(), "E0100");
+        assert_eq!(parse_error.code(), "E0101");
+        assert_eq!(type_error.code(), "E0001");
+        assert_eq!(runtime_error.code(), "E0201");
+        for code in ["E0100", "E0101", "E0001", "E0201", "e0007"] {
+            assert!(crate::explain::explanation(code).is_some(), "{}", code);
+        }
+        assert!(crate::explain::explanation("E9999").is_none());
+    }
+
+    #[test]
+    fn test_error_column_counts_characters() {
+        let source = "let s = \"éé\"; let t = 1 + s;";
+        let program = Parser::new(Tokenizer::new(source).tokenize().unwrap())
+            .parse()
+            .unwrap();
+        let error = TypeChecker::new().check_program(&program).unwrap_err();
+        assert!(error.to_string().contains("line 1, column 23"), "{}", error);
+    }
+
+    #[test]
+    fn test_error_underlines_every_line_of_its_span() {
+        use crate::repl::errors::ReplError;
+
+        let source = "let n = 1;\nlet s: String = if true {\n    n\n} else {\n    2\n};";
+        let program = Parser::new(Tokenizer::new(source).tokenize().unwrap())
+            .parse()
+            .unwrap();
+        let error = TypeChecker::new().check_program(&program).unwrap_err();
+        let rendered = ReplError::Type(error).render(source, None);
+        let snippet = rendered.split_once('\n').unwrap().1;
+        assert_eq!(
+            snippet,
+            [
+                "  |",
+                "2 | let s: String = if true {",
+                "  | ^^^^^^^^^^^^^^^^^^^^^^^^^",
+                "3 |     n",
+                "  |     ^",
+                "...",
+                "6 | };",
+                "  | ^^",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn test_prelude_is_defined_in_every_program() {
+        let source = "let doubled = map(fn(x) { x * 2 })([1, 2, 3]); \
+                      let big = filter(fn(x) { x > 2 })(doubled); \
+                      (fold(fn(a) { fn(b) { a + b } })(0)(big), reverse(big), \
+                       withDefault(0)(inr(\"missing\")), count(doubled));";
+        let program = Parser::new(Tokenizer::new(source).tokenize().unwrap())
+            .parse()
+            .unwrap();
+
+        TypeChecker::new().check_program(&program).unwrap();
+        let result = Interpreter::new().interpret_program_repl(&program).unwrap();
+        assert_eq!(
+            result,
+            Value::Tuple(vec![
+                Value::Int(10),
+                Value::List(vec![Value::Int(6), Value::Int(4)]),
+                Value::Int(0),
+                Value::Int(3),
+            ])
+        );
+
+        let error = TypeChecker::without_prelude()
+            .check_program(&program)
+            .unwrap_err();
+        assert!(error.to_string().contains("Undefined variable 'map'"));
+        assert!(
+            Interpreter::without_prelude()
+                .interpret_program(&program)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_programs_can_shadow_the_prelude() {
+        let source = "fn map(x: Int) { x + 1 } let count = \"mine\"; (map(1), count);";
+        let program = Parser::new(Tokenizer::new(source).tokenize().unwrap())
+            .parse()
+            .unwrap();
+
+        TypeChecker::new().check_program(&program).unwrap();
+        let result = Interpreter::new().interpret_program_repl(&program).unwrap();
+        assert_eq!(
+            result,
+            Value::Pair(
+                Box::new(Value::Int(2)),
+                Box::new(Value::String("mine".to_string()))
+            )
+        );
+    }
+
+    #[test]
+    fn test_compiled_programs_include_the_prelude_they_use() {
+        use corrosion_language::stdlib::prelude::with_prelude;
+
+        let source = "fn double(x: Int) { x * 2 } print(map(double)([1]));";
+        let program = Parser::new(Tokenizer::new(source).tokenize().unwrap())
+            .parse()
+            .unwrap();
+        let names: Vec<String> = with_prelude(&program)
+            .statements
+            .iter()
+            .filter_map(|statement| match statement {
+                crate::ast::Statement::FunctionDeclaration { name, .. } => Some(name.clone()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(names, ["map", "double"]);
+    }
+
+    #[test]
+    fn test_constants_run_like_lets() {
+        let source =
+            "const WIDTH = 80;\nconst MARGIN = WIDTH / 10;\nlet line = WIDTH - 2 * MARGIN;\nline;";
+        let program = Parser::new(Tokenizer::new(source).tokenize().unwrap())
+            .parse()
+            .unwrap();
+
+        TypeChecker::new().check_program(&program).unwrap();
+        let result = Interpreter::new().interpret_program_repl(&program).unwrap();
+        assert_eq!(result, Value::Int(64));
+    }
+
+    #[test]
+    fn test_enum_variants_compare_and_match() {
+        let source = "enum Light { Red, Amber, Green }\n\
+                      fn next(light: Light) {\n\
+                          case light of Light.Red => Light.Green | Light.Green => Light.Amber | Light.Amber => Light.Red\n\
+                      }\n\
+                      (next(Light.Red) == Light.Green, next(Light.Amber));";
+        let program = Parser::new(Tokenizer::new(source).tokenize().unwrap())
+            .parse()
+            .unwrap();
+
+        TypeChecker::new().check_program(&program).unwrap();
+        let result = Interpreter::new().interpret_program_repl(&program).unwrap();
+        assert_eq!(format!("{}", result), "(true, Light.Red)");
+    }
+
+    #[test]
+    fn test_string_case_arms() {
+        let source = "fn run(command: String) {\n\
+                          case command of \"start\" => 1 | \"stop\" => 2 | _ => 0\n\
+                      }\n\
+                      [run(\"start\"), run(\"stop\"), run(\"jump\")];";
+        let program = Parser::new(Tokenizer::new(source).tokenize().unwrap())
+            .parse()
+            .unwrap();
+
+        TypeChecker::new().check_program(&program).unwrap();
+        let result = Interpreter::new().interpret_program_repl(&program).unwrap();
+        assert_eq!(format!("{}", result), "[1, 2, 0]");
+    }
+
+    #[test]
+    fn test_int_case_arms() {
+        let source = "fn describe(n: Int) {\n\
+                          case n of ..0 => \"negative\" | 0 => \"zero\" | 1..10 => \"digit\" | _ => \"large\"\n\
+                      }\n\
+                      [describe(-5), describe(0), describe(9), describe(10)];";
+        let program = Parser::new(Tokenizer::new(source).tokenize().unwrap())
+            .parse()
+            .unwrap();
+
+        TypeChecker::new().check_program(&program).unwrap();
+        let result = Interpreter::new().interpret_program_repl(&program).unwrap();
+        assert_eq!(
+            format!("{}", result),
+            "[\"negative\", \"zero\", \"digit\", \"large\"]"
+        );
+    }
+
+    #[test]
+    fn test_else_if_chains() {
+        let source = "fn sign(n: Int) {\n\
+                          if n < 0 { \"negative\" } else if n == 0 { \"zero\" } else { \"positive\" }\n\
+                      }\n\
+                      [sign(-3), sign(0), sign(7), if true { if false { \"a\" } else { \"b\" } } else { \"c\" }];";
+        let program = Parser::new(Tokenizer::new(source).tokenize().unwrap())
+            .parse()
+            .unwrap();
+
+        TypeChecker::new().check_program(&program).unwrap();
+        let result = Interpreter::new().interpret_program_repl(&program).unwrap();
+        assert_eq!(
+            format!("{}", result),
+            "[\"negative\", \"zero\", \"positive\", \"b\"]"
+        );
+    }
+
+    #[test]
+    fn test_return_leaves_the_innermost_function() {
+        let source = "fn firstBig(xs: List Int) {\n\
+                          for x in xs { if x > 10 { return x; }; };\n\
+                          0\n\
+                      }\n\
+                      let outer = fn(n: Int) {\n\
+                          let inner = fn(m: Int) { return m * 2; };\n\
+                          inner(n) + 1\n\
+                      };\n\
+                      [firstBig([1, 20, 30]), firstBig([1]), outer(3)];";
+        let program = Parser::new(Tokenizer::new(source).tokenize().unwrap())
+            .parse()
+            .unwrap();
+
+        TypeChecker::new().check_program(&program).unwrap();
+        let result = Interpreter::new().interpret_program_repl(&program).unwrap();
+        assert_eq!(format!("{}", result), "[20, 0, 7]");
+    }
+
+    #[test]
+    fn test_to_int_reads_numbers_from_strings() {
+        let source =
+            "(toInt(\"42\"), toInt(\"-7\"), toInt(\"4x2\"), toInt(\"99999999999999999999\"));";
+        let program = Parser::new(Tokenizer::new(source).tokenize().unwrap())
+            .parse()
+            .unwrap();
+
+        TypeChecker::new().check_program(&program).unwrap();
+        let result = Interpreter::new().interpret_program_repl(&program).unwrap();
+        assert_eq!(
+            format!("{}", result),
+            "(Left(42), Left(-7), Right(\"'4x2' is not an Int\"), Right(\"'99999999999999999999' does not fit in an Int\"))"
+        );
+    }
+
+    #[test]
+    fn test_locked_runs_report_changed_modules() {
+        let dir = std::env::temp_dir().join(format!("corrosion-lock-test-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("lib")).unwrap();
+        std::fs::write(
+            dir.join("lib/math.corr"),
+            "let double = fn(x: Int) { x * 2 };",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("lib/quad.corr"),
+            "import \"math.corr\" as m; let quad = fn(x: Int) { m.double(m.double(x)) };",
+        )
+        .unwrap();
+        let program = dir.join("main.corr");
+        std::fs::write(
+            &program,
+            "import \"lib/quad.corr\" as q; let x = q.quad(3);",
+        )
+        .unwrap();
+        let program = program.to_str().unwrap();
+
+        let unlocked = crate::RunOptions::default();
+        let locked = crate::RunOptions {
+            locked: true,
+            ..Default::default()
+        };
+        let missing = crate::load_and_execute_file(program, &locked, &[]).unwrap_err();
+        assert!(missing.contains("run once without --locked"), "{}", missing);
+
+        // Imports of imports are locked too, by their path from the program
+        assert_eq!(crate::load_and_execute_file(program, &unlocked, &[]), Ok(0));
+        let lockfile = std::fs::read_to_string(dir.join("main.lock")).unwrap();
+        let paths: Vec<&str> = lockfile
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .filter_map(|line| line.split_once("  ").map(|(_, path)| path))
+            .collect();
+        assert_eq!(paths, ["lib/math.corr", "lib/quad.corr"]);
+        assert_eq!(crate::load_and_execute_file(program, &locked, &[]), Ok(0));
+
+        std::fs::write(
+            dir.join("lib/math.corr"),
+            "let double = fn(x: Int) { x + x };",
+        )
+        .unwrap();
+        let changed = crate::load_and_execute_file(program, &locked, &[]).unwrap_err();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(
+            changed.ends_with("main.lock:\n  lib/math.corr has changed"),
+            "{}",
+            changed
+        );
+    }
+
+    #[test]
+    fn test_packages_are_found_by_import() {
+        use corrosion_language::packages::{Manifest, add_dependency, package_name};
+
+        assert_eq!(
+            package_name("https://example.com/someone/json-tools.git").as_deref(),
+            Some("json-tools")
+        );
+        assert_eq!(
+            package_name("git@example.com:someone/text").as_deref(),
+            Some("text")
+        );
+        let manifest =
+            add_dependency("# shared code\n", "text", "https://example.com/text#v1").unwrap();
+        assert_eq!(
+            manifest,
+            "# shared code\n[dependencies]\ntext = \"https://example.com/text#v1\"\n"
+        );
+        let parsed = Manifest::parse(&manifest).unwrap();
+        assert_eq!(parsed.dependencies()["text"], "https://example.com/text#v1");
+        assert!(add_dependency(&manifest, "text", "elsewhere").is_err());
+        // Quotes and backslashes in a URL are escaped, and read back as written
+        let odd = add_dependency("", "odd", "https://example.com/\"odd\\#").unwrap();
+        assert_eq!(
+            odd,
+            "[dependencies]\nodd = \"https://example.com/\\\"odd\\\\#\"\n"
+        );
+        assert_eq!(
+            Manifest::parse(&odd).unwrap().dependencies()["odd"],
+            "https://example.com/\"odd\\#"
+        );
+        assert!(Manifest::parse("[package]\nname = \"x\"\n").is_err());
+
+        // A fetched package is a directory module, found from anywhere in the project
+        let dir =
+            std::env::temp_dir().join(format!("corrosion-package-test-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join(".corrosion/packages/text")).unwrap();
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        std::fs::write(dir.join("corrosion.toml"), &manifest).unwrap();
+        std::fs::write(
+            dir.join(".corrosion/packages/text/mod.corr"),
+            "let shout = fn(s: String) { s ++ \"!\" };",
+        )
+        .unwrap();
+        let program = dir.join("src/main.corr");
+        std::fs::write(
+            &program,
+            "import \"text\" as text; fn main(args: List String) { length(text.shout(\"hi\")) }",
+        )
+        .unwrap();
+        // Packages already on disk are not fetched again
+        let mut fetched = Vec::new();
+        let search_path = corrosion_language::packages::search_path(&dir, &parsed, |name, _| {
+            fetched.push(name.to_string())
+        });
+        assert_eq!(search_path, Ok(vec![dir.join(".corrosion/packages")]));
+        assert!(fetched.is_empty());
+        let result = crate::load_and_execute_file(
+            program.to_str().unwrap(),
+            &crate::RunOptions::default(),
+            &[],
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(result, Ok(3));
+    }
+
+    #[test]
+    fn test_manifest_edition_selects_keywords() {
+        use corrosion_language::lexer::Edition;
+        use corrosion_language::packages::{Manifest, add_dependency};
+
+        let text = "[dependencies]\njson = \"https://example.com/json\"\n\n[package]\nedition = \"2025\"\n";
+        let manifest = Manifest::parse(text).unwrap();
+        assert_eq!(manifest.edition(), Some(Edition::E2025));
+        assert!(Manifest::parse("[package]\nedition = \"1999\"\n").is_err());
+        // New dependencies stay in their table
+        assert_eq!(
+            add_dependency(text, "text", "https://example.com/text").unwrap(),
+            "[dependencies]\njson = \"https://example.com/json\"\ntext = \"https://example.com/text\"\n\n[package]\nedition = \"2025\"\n"
+        );
+
+        let dir =
+            std::env::temp_dir().join(format!("corrosion-edition-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("corrosion.toml"),
+            "[package]\nedition = \"2025\"\n",
+        )
+        .unwrap();
+        let program = dir.join("main.corr");
+        std::fs::write(
+            &program,
+            "let with = 2; fn main(args: List String) { with + 1 }",
+        )
+        .unwrap();
+        let program = program.to_str().unwrap();
+        let old = crate::load_and_execute_file(program, &crate::RunOptions::default(), &[]);
+        // The flag overrides the manifest
+        let latest = crate::RunOptions {
+            edition: Some(Edition::E2026),
+            ..Default::default()
+        };
+        let new = crate::load_and_execute_file(program, &latest, &[]);
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(old, Ok(3));
+        assert!(new.unwrap_err().contains("'with' is a reserved word"));
+    }
+
+    #[test]
+    fn test_deny_deprecated_rejects_deprecated_builtins() {
+        let code = "fn main(args: List String) { length(concat(\"a\", \"b\")) }";
+        let options = crate::RunOptions::default();
+        assert_eq!(crate::execute_expression(code, &options, &[]), Ok(2));
+
+        let options = crate::RunOptions {
+            deny_deprecated: true,
+            ..Default::default()
+        };
+        let error = crate::execute_expression(code, &options, &[]).unwrap_err();
+        assert!(error.starts_with("Type error[E0014]"), "{}", error);
+        assert!(crate::explain::explanation("E0014").is_some());
+    }
+
+    #[test]
+    fn test_navigation_resolves_names_through_the_symbol_table() {
+        use corrosion_language::navigation::{Position, find_references, goto_definition};
+
+        let dir = std::env::temp_dir().join(format!("corrosion-navigation-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("main.corr");
+        std::fs::write(
+            &file,
+            "let total = 1;\nfn add(n: Int) { n + total }\nlet total2 = add(total);\nprint(add(2));\n",
+        )
+        .unwrap();
+        let place = |span: &corrosion_language::lexer::Span| (span.line, span.column);
+
+        // From a use to the binding, and from the binding to every use
+        let definition = goto_definition(&file, Position::new(3, 18)).unwrap();
+        assert_eq!(definition.found.as_ref().map(place), Some((1, 5)));
+        assert!(definition.diagnostics.is_empty());
+        let references = find_references(&file, Position::new(1, 6)).unwrap().found;
+        assert_eq!(
+            references.iter().map(place).collect::<Vec<_>>(),
+            [(2, 22), (3, 18)]
+        );
+        // A parameter is found within its function
+        let uses = find_references(&file, Position::new(2, 18)).unwrap().found;
+        assert_eq!(uses.iter().map(place).collect::<Vec<_>>(), [(2, 18)]);
+        let functions = find_references(&file, Position::new(2, 4)).unwrap().found;
+        assert_eq!(functions.len(), 2);
+        // Names from the prelude have no definition in the file
+        let print = goto_definition(&file, Position::new(4, 1)).unwrap().found;
+        assert_eq!(print, None);
+
+        // A file with type errors still has its names resolved, before and
+        // after the errors and inside a function body that has one
+        std::fs::write(
+            &file,
+            "let total = 1;\nlet bad = total + \"x\";\nfn add(n: Int) { let m = n; m + \"y\" }\nprint(bad + total);\n",
+        )
+        .unwrap();
+        let references = find_references(&file, Position::new(1, 5)).unwrap();
+        let local = goto_definition(&file, Position::new(3, 29)).unwrap().found;
+        let bad = goto_definition(&file, Position::new(4, 7)).unwrap().found;
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(
+            references.found.iter().map(place).collect::<Vec<_>>(),
+            [(2, 11), (4, 13)]
+        );
+        assert_eq!(
+            references.diagnostics.len(),
+            2,
+            "{:?}",
+            references.diagnostics
+        );
+        assert!(references.diagnostics[0].starts_with("Type error[E0003]"));
+        assert_eq!(local.as_ref().map(place), Some((3, 22)));
+        assert_eq!(bad.as_ref().map(place), Some((2, 5)));
+    }
+
+    #[test]
+    fn test_diff_reports_changed_top_level_definitions() {
+        let dir = std::env::temp_dir().join(format!("corrosion-diff-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let old = dir.join("old.corr");
+        let new = dir.join("new.corr");
+        std::fs::write(
+            &old,
+            "let rate = 3;\nfn area(w: Int) { w * rate }\nfn old(x: Int) { x }\nenum Color { Red }",
+        )
+        .unwrap();
+        std::fs::write(
+            &new,
+            "// Only the layout of rate changes\nlet rate   =   3;\nfn area(w: Int) { w * rate > 4 }\nenum Color { Red, Blue }\nlet fresh = \"x\";",
+        )
+        .unwrap();
+        let options = crate::RunOptions::default();
+        let definitions = |file: &std::path::Path| {
+            crate::check_definitions(file.to_str().unwrap(), &options).unwrap()
+        };
+        let changes = corrosion_language::diff::diff(&definitions(&old), &definitions(&new));
+        let same = crate::diff_files(old.to_str().unwrap(), old.to_str().unwrap(), &options);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let lines: Vec<String> = changes.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            lines,
+            [
+                "~ fn area: (Int -> Int), now fn area: (Int -> Bool)",
+                "- fn old: (Int -> Int)",
+                "~ enum Color: Color, definition changed",
+                "+ let fresh: String",
+            ]
+        );
+        assert_eq!(same, Ok(0));
+    }
+
+    #[test]
+    fn test_first_edition_runs_programs_from_before_editions() {
+        use corrosion_language::lexer::Edition;
+
+        // Names the later keywords took, and `&&` on a value that is not a Bool
+        let source = "let join = fn(a: String) { fn(b: String) { a ++ b } };
+                      let random = 4;
+                      let enabled = fn(x) { x && true };
+                      fn main(args: List String) {
+                          print(join(\"a\")(\"b\"));
+                          if enabled(random) && !enabled(0) { random } else { 0 }
+                      }";
+        let old = crate::RunOptions {
+            edition: Some(Edition::E2025),
+            ..Default::default()
+        };
+        assert_eq!(crate::execute_source(source, None, &old, &[], false), Ok(4));
+        let error = crate::execute_source(source, None, &Default::default(), &[], false);
+        assert!(error.unwrap_err().contains("'join' is a reserved word"));
+    }
 }
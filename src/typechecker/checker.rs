@@ -1,27 +1,433 @@
-use crate::ast::{Expression, Program, Spanned, Statement, TypeExpression};
+use crate::ast::{
+    BinaryOperator, CaseArm, Child, ENTRY_POINT, Expression, NodeId, Pattern, Program, Spanned,
+    Statement, TypeExpression,
+};
+use crate::lexer::edition::Edition;
+use crate::lexer::tokens::Span;
+use crate::stdlib::prelude;
+use crate::typechecker::constants::{self, Constant};
+use crate::typechecker::coverage::{self, IntCoverage, Overlap};
+use crate::typechecker::deprecations;
+use crate::typechecker::suggestions::closest_name;
+use crate::typechecker::symbols::SymbolTable;
 use crate::typechecker::{
-    BinaryOp, Environment, ModuleLoader, Type, TypeCompatibility, TypeError, TypeInference,
-    TypeResult, TypedExpression, TypedProgram, TypedStatement,
+    BinaryOp, Constraint, Environment, ModuleLoader, OperatorTypes, Type, TypeCompatibility,
+    TypeError, TypeInference, TypeResult, TypeWarning, TypedExpression, TypedProgram,
+    TypedStatement,
 };
-use std::path::Path;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Types of the prelude definitions, checked once per process
+fn prelude_environment() -> &'static Environment {
+    &checked_prelude().0
+}
+
+/// Types of the operator expressions in the prelude. They are kept apart
+/// from a program's own, since the prelude's spans point into another source.
+pub fn prelude_operator_types() -> &'static OperatorTypes {
+    &checked_prelude().1
+}
+
+fn checked_prelude() -> &'static (Environment, OperatorTypes) {
+    static PRELUDE: OnceLock<(Environment, OperatorTypes)> = OnceLock::new();
+    PRELUDE.get_or_init(|| {
+        let mut checker = TypeChecker::without_prelude();
+        checker
+            .check_program(prelude::program())
+            .expect("the prelude type checks");
+        // Keep only the types: declarations that shadow the prelude are not
+        // worth a warning, and its spans point into another source
+        let mut environment = Environment::new();
+        for (name, ty) in checker.environment.local_bindings() {
+            environment.bind(name.clone(), ty.clone());
+        }
+        (environment, checker.operator_types)
+    })
+}
 
 /// Type checker for the Corrosion language
 pub struct TypeChecker {
     environment: Environment,
     errors: Vec<TypeError>,
+    warnings: Vec<TypeWarning>,
     module_loader: ModuleLoader,
+    /// Reject declarations whose types are not fully inferred unless `Dyn` is written
+    strict: bool,
+    /// Reject IO outside of function bodies; set when checking an imported
+    /// module under `--pure-imports`
+    forbid_io: bool,
+    /// Let a declaration replace a name already bound in the same scope, as
+    /// the REPL does, instead of reporting `RedefinedVariable`
+    allow_redefinition: bool,
+    /// Values of the top-level `const` declarations checked so far
+    constants: HashMap<String, Constant>,
+    /// Types of the `return`s checked so far in the innermost function body
+    returns: Vec<(Type, Span)>,
+    /// The names bound and referred to in what has been checked so far
+    symbols: SymbolTable,
+    /// Types of the operator expressions checked so far, by span, for
+    /// backends whose arithmetic depends on them
+    operator_types: OperatorTypes,
 }
 
 impl TypeChecker {
-    /// Create a new type checker
+    /// Create a new type checker with the prelude defined
     pub fn new() -> Self {
+        Self::create(true)
+    }
+
+    /// Create a new type checker with nothing defined, for checking programs
+    /// and their imports without the prelude
+    pub fn without_prelude() -> Self {
+        Self::create(false)
+    }
+
+    fn create(prelude: bool) -> Self {
+        let environment = if prelude {
+            Environment::with_parent(prelude_environment().clone())
+        } else {
+            Environment::new()
+        };
+        let mut module_loader = ModuleLoader::new();
+        module_loader.set_prelude(prelude);
         Self {
-            environment: Environment::new(),
+            environment,
+            errors: Vec::new(),
+            warnings: Vec::new(),
+            module_loader,
+            strict: false,
+            forbid_io: false,
+            allow_redefinition: false,
+            constants: HashMap::new(),
+            returns: Vec::new(),
+            symbols: SymbolTable::default(),
+            operator_types: HashMap::new(),
+        }
+    }
+
+    /// Enable or disable strict checking of imprecise types
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+        self.module_loader.set_strict(strict);
+    }
+
+    /// Warnings found so far, in the order they were found
+    pub fn warnings(&self) -> &[TypeWarning] {
+        &self.warnings
+    }
+
+    /// Remove and return the warnings found so far, so that a checker used
+    /// for several programs reports each warning once
+    pub fn take_warnings(&mut self) -> Vec<TypeWarning> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    /// The symbol table of what has been checked so far: each name the
+    /// program binds, with its type, and the binding each identifier
+    /// refers to
+    pub fn symbols(&self) -> &SymbolTable {
+        &self.symbols
+    }
+
+    /// Types of the operator expressions in what has been checked so far
+    pub fn operator_types(&self) -> &OperatorTypes {
+        &self.operator_types
+    }
+
+    /// The value of a `const` declared at the top level of the checked
+    /// program, if `name` refers to one
+    pub fn constant(&self, name: &str) -> Option<&Constant> {
+        self.constants.get(name)
+    }
+
+    /// Make `name` a constant with the given value, or no longer a constant,
+    /// as when a definition is undone
+    pub fn set_constant(&mut self, name: &str, constant: Option<Constant>) {
+        match constant {
+            Some(constant) => self.constants.insert(name.to_string(), constant),
+            None => self.constants.remove(name),
+        };
+    }
+
+    /// Make `name` a module with these exports, read as `name.export`, such
+    /// as a record an embedding program passes in
+    pub fn bind_module(&mut self, name: &str, exports: HashMap<String, Type>) {
+        self.module_loader
+            .store_module_exports(name.to_string(), exports);
+    }
+
+    /// Allow or reject declaring a name again in the scope that already binds
+    /// it. Files keep this off; interactive sessions turn it on so an entry
+    /// can be corrected by entering it again.
+    pub fn set_allow_redefinition(&mut self, allow_redefinition: bool) {
+        self.allow_redefinition = allow_redefinition;
+    }
+
+    /// Reject IO at the top level of imported modules, so importing a module
+    /// never has side effects
+    pub fn set_pure_imports(&mut self, pure_imports: bool) {
+        self.module_loader.set_pure_imports(pure_imports);
+    }
+
+    /// Look for imports in these directories when they are not found next to
+    /// the importing file, such as where packages are fetched to
+    pub fn set_module_search_path(&mut self, search_path: Vec<PathBuf>) {
+        self.module_loader.set_search_path(search_path);
+    }
+
+    /// Read imported modules in `edition`, the one the program is in
+    pub fn set_edition(&mut self, edition: Edition) {
+        self.module_loader.set_edition(edition);
+    }
+
+    /// Reject IO outside of function bodies in this program
+    pub fn set_forbid_io(&mut self, forbid_io: bool) {
+        self.forbid_io = forbid_io;
+    }
+
+    /// Warn about a use of `name` at `span` if it is deprecated. An
+    /// expression checked twice is warned about once.
+    fn warn_if_deprecated(&mut self, name: &str, span: &Span) {
+        let Some(deprecation) = deprecations::deprecation(name) else {
+            return;
+        };
+        let warning = TypeWarning::Deprecated {
+            name: deprecation.name.to_string(),
+            replacement: deprecation.replacement.to_string(),
+            span: span.clone(),
+        };
+        if !self.warnings.contains(&warning) {
+            self.warnings.push(warning);
+        }
+    }
+
+    /// Record a `let` or `fn` declaration in the current scope. Declaring a
+    /// name the scope already binds is an error unless redefinition is
+    /// allowed; hiding a declaration from an enclosing scope is allowed but
+    /// warned about.
+    fn check_declaration(&mut self, name: &str, span: &Span) -> TypeResult<()> {
+        if self.environment.is_bound_locally(name) {
+            if !self.allow_redefinition {
+                return Err(TypeError::RedefinedVariable {
+                    name: name.to_string(),
+                    span: span.clone(),
+                });
+            }
+        } else if let Some(shadowed) = self.environment.outer_declaration(name) {
+            self.warnings.push(TypeWarning::Shadowed {
+                name: name.to_string(),
+                span: span.clone(),
+                shadowed: shadowed.clone(),
+            });
+        }
+        self.environment.declare(name.to_string(), span.clone());
+        Ok(())
+    }
+
+    /// Warn about an expression statement whose value is thrown away. `Unit`
+    /// values carry nothing, and values of unknown or dynamic type may well
+    /// be `Unit` when the program runs.
+    fn warn_if_unused(&mut self, statement: &TypedStatement) {
+        if let TypedStatement::Expression { expression, span } = statement
+            && !matches!(
+                expression.ty,
+                Type::Unit | Type::Unknown | Type::Dyn | Type::Error
+            )
+        {
+            self.warnings.push(TypeWarning::UnusedResult {
+                ty: expression.ty.clone(),
+                span: span.clone(),
+            });
+        }
+    }
+
+    /// Warn about an `if` whose condition folds to a constant. Conditions
+    /// naming a `const` are left alone, as a flag such as `DEBUG` is meant
+    /// to switch code off.
+    fn warn_if_constant(
+        &mut self,
+        condition: &Expression,
+        then_branch: &Expression,
+        else_branch: Option<&Expression>,
+    ) {
+        let Ok(Constant::Bool(value)) = constants::evaluate(condition, &HashMap::new()) else {
+            return;
+        };
+        let dead_branch = match value {
+            true => else_branch,
+            false => Some(then_branch),
+        };
+        self.warnings.push(TypeWarning::ConstantCondition {
+            value,
+            condition: condition.span().clone(),
+            dead_branch: dead_branch.map(|branch| branch.span().clone()),
+        });
+    }
+
+    /// Check a function value whose parameter has the type `param_type`.
+    /// A function need not be bound by a declaration, as when it is passed
+    /// straight to a call, so its parameter is checked for precision here.
+    fn check_function(
+        &mut self,
+        param: &str,
+        param_type: Type,
+        body: &Expression,
+        param_id: NodeId,
+        span: &Span,
+    ) -> TypeResult<TypedExpression> {
+        self.check_precision(param, &param_type, span)?;
+
+        let mut function_checker = TypeChecker {
+            environment: Environment::with_parent(self.environment.clone()),
             errors: Vec::new(),
-            module_loader: ModuleLoader::new(),
+            warnings: Vec::new(),
+            module_loader: self.module_loader.clone(),
+            strict: self.strict,
+            // The body only runs when the function is called
+            forbid_io: false,
+            allow_redefinition: self.allow_redefinition,
+            constants: HashMap::new(),
+            returns: Vec::new(),
+            symbols: SymbolTable::default(),
+            operator_types: HashMap::new(),
+        };
+        function_checker
+            .module_loader
+            .set_current_directory(self.module_loader.get_current_directory());
+
+        // Bind the parameter in the function's scope
+        function_checker
+            .environment
+            .bind(param.to_string(), param_type.clone());
+        function_checker.define_symbol(param_id, param, span, &param_type);
+
+        // Type check the function body. The names in it are kept even if it
+        // has an error, so tools can still find them.
+        let typed_body = function_checker.check_expression(body);
+        self.symbols.append(&mut function_checker.symbols);
+        let typed_body = typed_body?;
+        self.warnings.append(&mut function_checker.warnings);
+        self.operator_types
+            .extend(std::mem::take(&mut function_checker.operator_types));
+        let ends_with = (!always_returns(body)).then_some(typed_body.ty);
+        let result = function_result(ends_with, function_checker.returns)?;
+
+        // Create the function type
+        let function_type = Type::Function {
+            param: Box::new(param_type),
+            result: Box::new(result),
+        };
+
+        Ok(TypedExpression::new(function_type, span.clone()))
+    }
+
+    /// The type of the function `fix` gives for `fn(param) { body }`, which is
+    /// also the type of `param`: what `body` is when `param` is checked only
+    /// at runtime, or `Unknown` if that is not a function
+    fn fixed_point_type(
+        &mut self,
+        param: &str,
+        body: &Expression,
+        param_id: NodeId,
+        span: &Span,
+    ) -> Type {
+        let strict = std::mem::replace(&mut self.strict, false);
+        let warnings = self.warnings.len();
+        let typed = self.check_function(param, Type::Dyn, body, param_id, span);
+        self.strict = strict;
+        // The function is checked again with the type found, which warns
+        self.warnings.truncate(warnings);
+        match typed.map(|typed| typed.ty) {
+            Ok(Type::Function { result, .. }) if matches!(*result, Type::Function { .. }) => {
+                *result
+            }
+            _ => Type::Unknown,
         }
     }
 
+    /// Make `name`, just bound in the current scope to `ty`, the symbol
+    /// `id` defined at `span`. `_` binds nothing, and nodes built by hand
+    /// rather than parsed have no id to record.
+    fn define_symbol(&mut self, id: NodeId, name: &str, span: &Span, ty: &Type) {
+        if id == NodeId::default() || name == "_" {
+            return;
+        }
+        self.environment
+            .bind_symbol(name.to_string(), id, span.clone());
+        self.symbols.define(id, name, span, ty);
+    }
+
+    /// Report the hole `name` at `span`, where the code around it wants a
+    /// value of type `expected`, with the names in scope of that type. The
+    /// hole takes that type, so checking goes on as if it were filled in.
+    fn check_hole(
+        &mut self,
+        name: &Option<String>,
+        expected: &Type,
+        span: &Span,
+    ) -> TypedExpression {
+        let mut candidates: Vec<String> = if expected.contains_unknown() {
+            Vec::new()
+        } else {
+            self.environment
+                .get_all_bindings_types()
+                .into_iter()
+                .filter(|(_, ty)| {
+                    !ty.contains_unknown() && TypeCompatibility::types_compatible(expected, ty)
+                })
+                .map(|(name, _)| name)
+                .collect()
+        };
+        candidates.sort();
+        let warning = TypeWarning::Hole {
+            name: name.clone(),
+            expected: expected.clone(),
+            candidates,
+            span: span.clone(),
+        };
+        // A hole checked again, as when an expected type is tried first,
+        // is reported once, with the most that is known about it
+        let reported = self
+            .warnings
+            .iter_mut()
+            .find(|reported| matches!(reported, TypeWarning::Hole { span: at, .. } if at == span));
+        match reported {
+            Some(TypeWarning::Hole {
+                expected: known, ..
+            }) if !known.contains_unknown() => {}
+            Some(reported) => *reported = warning,
+            None => self.warnings.push(warning),
+        }
+        TypedExpression::new(expected.clone(), span.clone())
+    }
+
+    fn check_io_allowed(&self, effect: &str, span: &Span) -> TypeResult<()> {
+        if self.forbid_io {
+            return Err(TypeError::EffectAtImport {
+                effect: effect.to_string(),
+                span: span.clone(),
+            });
+        }
+        Ok(())
+    }
+
+    /// A name in scope that `name` is probably a misspelling of. The
+    /// program's own names are preferred to the prelude's.
+    pub fn suggest_name(&self, name: &str) -> Option<String> {
+        let names = self.environment.names();
+        let prelude = prelude_environment();
+        closest_name(
+            name,
+            names
+                .iter()
+                .copied()
+                .filter(|candidate| !prelude.is_bound_locally(candidate)),
+        )
+        .or_else(|| closest_name(name, names))
+    }
+
     /// Set the current directory for import resolution
     pub fn set_current_directory<P: AsRef<Path>>(&mut self, path: P) {
         self.module_loader.set_current_directory(path);
@@ -30,10 +436,20 @@ impl TypeChecker {
     /// Type check a program and return the typed AST
     pub fn check_program(&mut self, program: &Program) -> TypeResult<TypedProgram> {
         let mut typed_statements = Vec::new();
+        // Only this program's `export import`s decide what it re-exports
+        self.module_loader.clear_exported();
 
-        for statement in &program.statements {
+        // The last statement is the program's result, which `-e` and the
+        // REPL show, so its value is used
+        let last = program.statements.len().saturating_sub(1);
+        for (index, statement) in program.statements.iter().enumerate() {
             match self.check_statement(statement) {
-                Ok(typed_stmt) => typed_statements.push(typed_stmt),
+                Ok(typed_stmt) => {
+                    if index < last {
+                        self.warn_if_unused(&typed_stmt);
+                    }
+                    typed_statements.push(typed_stmt)
+                }
                 Err(err) => {
                     self.errors.push(err.clone());
                     return Err(err);
@@ -44,6 +460,123 @@ impl TypeChecker {
         Ok(TypedProgram::new(typed_statements, program.span.clone()))
     }
 
+    /// Check a program as [`check_program`](Self::check_program) does, but
+    /// go on past a statement with an error, taking the names it binds to be
+    /// of unknown type, so the symbol table covers the whole program as tools
+    /// need it to. Returns the errors found, in source order.
+    pub fn check_program_recovering(&mut self, program: &Program) -> Vec<TypeError> {
+        self.module_loader.clear_exported();
+        let mut errors = Vec::new();
+        for statement in &program.statements {
+            let Err(error) = self.check_statement(statement) else {
+                continue;
+            };
+            match statement {
+                Statement::VariableDeclaration { name, id, span, .. }
+                | Statement::ConstantDeclaration { name, id, span, .. }
+                | Statement::FunctionDeclaration { name, id, span, .. } => {
+                    self.environment.bind(name.clone(), Type::Unknown);
+                    self.define_symbol(*id, name, span, &Type::Unknown);
+                }
+                _ => {}
+            }
+            self.errors.push(error.clone());
+            errors.push(error);
+        }
+        errors
+    }
+
+    /// Check that a top-level `main`, if the checked program declares one,
+    /// can be called with the command line arguments
+    pub fn check_entry_point(&self, program: &Program) -> TypeResult<()> {
+        let Some(main_type) = self.environment.lookup(ENTRY_POINT) else {
+            return Ok(());
+        };
+        let span = program
+            .statements
+            .iter()
+            .rev()
+            .find_map(|statement| match statement {
+                Statement::VariableDeclaration { name, span, .. }
+                | Statement::ConstantDeclaration { name, span, .. }
+                | Statement::FunctionDeclaration { name, span, .. }
+                    if name == ENTRY_POINT =>
+                {
+                    Some(span.clone())
+                }
+                _ => None,
+            })
+            .unwrap_or_else(|| program.span.clone());
+
+        let expected = Type::function(Type::list(Type::String), Type::Unknown);
+        if matches!(main_type, Type::Function { .. })
+            && TypeCompatibility::types_compatible(main_type, &expected)
+        {
+            Ok(())
+        } else {
+            Err(TypeError::TypeMismatch {
+                expected,
+                found: main_type.clone(),
+                span,
+            })
+        }
+    }
+
+    /// Check the value of a `let` or `const` against its annotation, if it
+    /// has one, and bind the name to the resulting type
+    fn check_binding(
+        &mut self,
+        name: &str,
+        type_annotation: Option<&TypeExpression>,
+        value: &Expression,
+        span: &Span,
+    ) -> TypeResult<(Type, TypedExpression)> {
+        // `let _ = value;` only evaluates the value
+        let wildcard = name == "_";
+        if !wildcard {
+            self.check_declaration(name, span)?;
+        }
+
+        let annotated_type = type_annotation
+            .map(|annotation| self.convert_type_expression(annotation))
+            .transpose()?;
+
+        // Type check the value expression, guided by the annotation if present
+        let typed_value = match &annotated_type {
+            Some(expected) => self.check_expression_with_expected(value, expected)?,
+            None => self.check_expression(value)?,
+        };
+        let inferred_type = typed_value.ty.clone();
+
+        let final_type = if let Some(annotated_type) = annotated_type {
+            // Special handling for function types with Unknown parameters/results
+            let refined_type =
+                TypeCompatibility::refine_type_with_annotation(&inferred_type, &annotated_type)?;
+
+            if !TypeCompatibility::types_compatible(&annotated_type, &refined_type) {
+                if let Some(error) = missing_arguments(value, &refined_type, &annotated_type) {
+                    return Err(error);
+                }
+                return Err(TypeError::TypeMismatch {
+                    expected: annotated_type,
+                    found: refined_type,
+                    span: span.clone(),
+                });
+            }
+            annotated_type
+        } else {
+            inferred_type
+        };
+        self.check_precision(name, &final_type, span)?;
+
+        // Bind the variable to its type
+        if !wildcard {
+            self.environment.bind(name.to_string(), final_type.clone());
+        }
+
+        Ok((final_type, typed_value))
+    }
+
     /// Type check a statement
     fn check_statement(&mut self, statement: &Statement) -> TypeResult<TypedStatement> {
         match statement {
@@ -51,48 +584,69 @@ impl TypeChecker {
                 name,
                 type_annotation,
                 value,
+                id,
                 span,
             } => {
-                // Check if variable is already defined in current scope
-                if self.environment.is_bound_locally(name) {
+                let (ty, value) =
+                    self.check_binding(name, type_annotation.as_ref(), value, span)?;
+                self.define_symbol(*id, name, span, &ty);
+                self.constants.remove(name);
+                Ok(TypedStatement::VariableDeclaration {
+                    name: name.clone(),
+                    ty,
+                    value,
+                    span: span.clone(),
+                })
+            }
+            Statement::EnumDeclaration {
+                name,
+                variants,
+                span,
+            } => {
+                if self.module_loader.get_module_exports(name).is_some() && !self.allow_redefinition
+                {
                     return Err(TypeError::RedefinedVariable {
                         name: name.clone(),
                         span: span.clone(),
                     });
                 }
-
-                // Type check the value expression
-                let typed_value = self.check_expression(value)?;
-                let inferred_type = typed_value.ty.clone();
-
-                let final_type = if let Some(annotation) = type_annotation {
-                    let annotated_type = self.convert_type_expression(annotation)?;
-
-                    // Special handling for function types with Unknown parameters/results
-                    let refined_type = TypeCompatibility::refine_type_with_annotation(
-                        &inferred_type,
-                        &annotated_type,
-                    )?;
-
-                    if !TypeCompatibility::types_compatible(&annotated_type, &refined_type) {
-                        return Err(TypeError::TypeMismatch {
-                            expected: annotated_type,
-                            found: refined_type,
+                // The enum is a namespace holding one constant per variant
+                let ty = Type::enumeration(name);
+                let mut exports = HashMap::new();
+                for variant in variants {
+                    if exports.insert(variant.clone(), ty.clone()).is_some() {
+                        return Err(TypeError::RedefinedVariable {
+                            name: format!("{}.{}", name, variant),
                             span: span.clone(),
                         });
                     }
-                    annotated_type
-                } else {
-                    inferred_type
-                };
-
-                // Bind the variable to its type
-                self.environment.bind(name.clone(), final_type.clone());
+                }
+                self.module_loader
+                    .store_module_exports(name.clone(), exports);
 
-                Ok(TypedStatement::VariableDeclaration {
+                Ok(TypedStatement::EnumDeclaration {
                     name: name.clone(),
-                    ty: final_type,
+                    variants: variants.clone(),
+                    span: span.clone(),
+                })
+            }
+            Statement::ConstantDeclaration {
+                name,
+                type_annotation,
+                value,
+                id,
+                span,
+            } => {
+                let (ty, typed_value) =
+                    self.check_binding(name, type_annotation.as_ref(), value, span)?;
+                self.define_symbol(*id, name, span, &ty);
+                let constant = constants::evaluate(value, &self.constants)?;
+                self.constants.insert(name.clone(), constant.clone());
+                Ok(TypedStatement::ConstantDeclaration {
+                    name: name.clone(),
+                    ty,
                     value: typed_value,
+                    constant,
                     span: span.clone(),
                 })
             }
@@ -102,21 +656,21 @@ impl TypeChecker {
                 param_type,
                 return_type,
                 body,
+                id,
+                param_id,
                 span,
             } => {
-                // Check if function is already defined in current scope
-                if self.environment.is_bound_locally(name) {
-                    return Err(TypeError::RedefinedVariable {
-                        name: name.clone(),
-                        span: span.clone(),
-                    });
-                }
+                self.check_declaration(name, span)?;
+                self.constants.remove(name);
 
-                // Use explicit parameter type if provided, otherwise Unknown for inference
+                // Use explicit parameter type if provided, otherwise infer
+                // it from the body as for a function value
                 let param_type = if let Some(param_type_expr) = param_type {
                     self.convert_type_expression(param_type_expr)?
                 } else {
-                    Type::Unknown
+                    TypeInference::new(self.environment.clone())
+                        .with_edition(self.module_loader.edition())
+                        .infer_parameter_type(param, body)?
                 };
 
                 // Convert return type annotation if provided
@@ -132,38 +686,75 @@ impl TypeChecker {
 
                 // Bind the function name BEFORE checking the body (enables recursion)
                 self.environment
-                    .bind(name.clone(), preliminary_function_type);
+                    .bind(name.clone(), preliminary_function_type.clone());
+                self.define_symbol(*id, name, span, &preliminary_function_type);
 
                 // Create a new scope for the function body
                 self.environment.enter_scope();
 
                 // Bind the parameter in the function body scope
                 self.environment.bind(param.clone(), param_type.clone());
-
-                // Type check the function body
-                let typed_body = self.check_expression(body)?;
-                let actual_return_type = typed_body.ty.clone();
+                self.define_symbol(*param_id, param, span, &param_type);
+
+                // Type check the function body; it only runs when called, so
+                // IO is allowed there even under --pure-imports
+                let forbid_io = std::mem::replace(&mut self.forbid_io, false);
+                let outer_returns = std::mem::take(&mut self.returns);
+                let typed_body = match &expected_return_type {
+                    Some(expected) => self.check_expression_with_expected(body, expected),
+                    None => self.check_expression(body),
+                };
+                self.forbid_io = forbid_io;
+                let returns = std::mem::replace(&mut self.returns, outer_returns);
+                let typed_body = match typed_body {
+                    Ok(typed_body) => typed_body,
+                    Err(error) => {
+                        self.environment.exit_scope();
+                        return Err(error);
+                    }
+                };
 
                 // Check return type matches annotation if provided
                 let final_return_type = if let Some(expected) = expected_return_type {
-                    if !TypeCompatibility::types_compatible(&expected, &actual_return_type) {
+                    if !always_returns(body)
+                        && !TypeCompatibility::types_compatible(&expected, &typed_body.ty)
+                    {
                         self.environment.exit_scope();
                         return Err(TypeError::TypeMismatch {
                             expected,
-                            found: actual_return_type,
+                            found: typed_body.ty,
                             span: span.clone(),
                         });
                     }
+                    if let Err(error) = function_result(Some(expected.clone()), returns) {
+                        self.environment.exit_scope();
+                        return Err(error);
+                    }
                     expected
                 } else {
-                    actual_return_type
+                    let ends_with = (!always_returns(body)).then(|| typed_body.ty.clone());
+                    match function_result(ends_with, returns) {
+                        Ok(result) => result,
+                        Err(error) => {
+                            self.environment.exit_scope();
+                            return Err(error);
+                        }
+                    }
                 };
 
                 self.environment.exit_scope();
+                if param != name && always_recurses(body, name) {
+                    self.warnings.push(TypeWarning::InfiniteRecursion {
+                        name: name.clone(),
+                        span: span.clone(),
+                    });
+                }
 
                 // Update the function type with the actual return type
                 let final_function_type =
                     Type::function(param_type.clone(), final_return_type.clone());
+                self.check_precision(name, &final_function_type, span)?;
+                self.symbols.define(*id, name, span, &final_function_type);
                 self.environment.update(name.clone(), final_function_type);
 
                 Ok(TypedStatement::FunctionDeclaration {
@@ -176,7 +767,12 @@ impl TypeChecker {
                 })
             }
 
-            Statement::Import { path, alias, span } => {
+            Statement::Import {
+                path,
+                alias,
+                exported,
+                span,
+            } => {
                 let import_name = alias.as_ref().unwrap_or(path);
 
                 // Load and type-check the module
@@ -187,10 +783,15 @@ impl TypeChecker {
                 // Store the module's exports for later lookup
                 self.module_loader
                     .store_module_exports(import_name.clone(), module_exports);
+                self.module_loader
+                    .store_module_path(import_name.clone(), path.clone());
+                self.module_loader
+                    .set_exported(import_name.clone(), *exported);
 
                 Ok(TypedStatement::Import {
                     path: path.clone(),
                     alias: alias.clone(),
+                    exported: *exported,
                     span: span.clone(),
                 })
             }
@@ -201,6 +802,14 @@ impl TypeChecker {
                     span: span.clone(),
                 })
             }
+            Statement::Return { value, span } => {
+                let value = self.check_expression(value)?;
+                self.returns.push((value.ty.clone(), span.clone()));
+                Ok(TypedStatement::Return {
+                    value,
+                    span: span.clone(),
+                })
+            }
         }
     }
 
@@ -210,16 +819,26 @@ impl TypeChecker {
             Expression::Number { value: _, span } => {
                 Ok(TypedExpression::new(Type::Int, span.clone()))
             }
+            Expression::BigNumber { value: _, span } => {
+                Ok(TypedExpression::new(Type::BigInt, span.clone()))
+            }
             Expression::Boolean { value: _, span } => {
                 Ok(TypedExpression::new(Type::Bool, span.clone()))
             }
             Expression::String { value: _, span } => {
                 Ok(TypedExpression::new(Type::String, span.clone()))
             }
-            Expression::Identifier { name, span } => match self.environment.lookup(name) {
-                Some(ty) => Ok(TypedExpression::new(ty.clone(), span.clone())),
+            Expression::Identifier { name, id, span } => match self.environment.lookup(name) {
+                Some(ty) => {
+                    let typed = TypedExpression::new(ty.clone(), span.clone());
+                    if let Some(definition) = self.environment.symbol(name) {
+                        self.symbols.refer(*id, definition, span);
+                    }
+                    Ok(typed)
+                }
                 None => Err(TypeError::UndefinedVariable {
                     name: name.clone(),
+                    suggestion: self.suggest_name(name),
                     span: span.clone(),
                 }),
             },
@@ -229,8 +848,11 @@ impl TypeChecker {
                     if let Some(export_type) = module_exports.get(name) {
                         Ok(TypedExpression::new(export_type.clone(), span.clone()))
                     } else {
+                        let suggestion =
+                            closest_name(name, module_exports.keys().map(String::as_str));
                         Err(TypeError::UndefinedVariable {
                             name: format!("{}.{}", module, name),
+                            suggestion: suggestion.map(|member| format!("{}.{}", module, member)),
                             span: span.clone(),
                         })
                     }
@@ -248,13 +870,65 @@ impl TypeChecker {
                 right,
                 span,
             } => {
-                let typed_left = self.check_expression(left)?;
-                let typed_right = self.check_expression(right)?;
+                // A hole on one side of an operator other than composition
+                // takes the type of the other side
+                let same_types = !matches!(
+                    operator,
+                    BinaryOperator::ComposeForward | BinaryOperator::ComposeBackward
+                );
+                let (typed_left, typed_right) = match (left.as_ref(), right.as_ref()) {
+                    (Expression::Hole { .. }, _) if same_types => {
+                        let typed_right = self.check_expression(right)?;
+                        let typed_left =
+                            self.check_expression_with_expected(left, &typed_right.ty)?;
+                        (typed_left, typed_right)
+                    }
+                    (_, Expression::Hole { .. }) if same_types => {
+                        let typed_left = self.check_expression(left)?;
+                        let typed_right =
+                            self.check_expression_with_expected(right, &typed_left.ty)?;
+                        (typed_left, typed_right)
+                    }
+                    _ => (self.check_expression(left)?, self.check_expression(right)?),
+                };
 
                 let op = BinaryOp::from(operator.clone());
 
-                match typed_left.ty.can_binary_op(&op, &typed_right.ty) {
-                    Some(result_type) => Ok(TypedExpression::new(result_type, span.clone())),
+                // An operand left partly applied is missing arguments
+                if same_types
+                    && let Some(error) = missing_arguments(left, &typed_left.ty, &typed_right.ty)
+                        .or_else(|| missing_arguments(right, &typed_right.ty, &typed_left.ty))
+                {
+                    return Err(error);
+                }
+
+                if let Some(constraint) = op.required_constraint() {
+                    for operand in [&typed_left, &typed_right] {
+                        if !operand.ty.satisfies(constraint) {
+                            return Err(TypeError::UnsatisfiedConstraint {
+                                ty: operand.ty.clone(),
+                                constraint,
+                                span: operand.span.clone(),
+                            });
+                        }
+                    }
+                }
+
+                // Before the 2026 edition values were truthy, so an operand
+                // whose type is not known yet let any other one through
+                let truthy = self.module_loader.edition().has_truthiness()
+                    && matches!(op, BinaryOp::LogicalAnd | BinaryOp::LogicalOr)
+                    && (typed_left.ty == Type::Unknown || typed_right.ty == Type::Unknown);
+                let result_type = match truthy {
+                    true => Some(Type::Bool),
+                    false => typed_left.ty.can_binary_op(&op, &typed_right.ty),
+                };
+                match result_type {
+                    Some(result_type) => {
+                        self.operator_types
+                            .insert(span.clone(), result_type.clone());
+                        Ok(TypedExpression::new(result_type, span.clone()))
+                    }
                     None => Err(TypeError::InvalidBinaryOperation {
                         left: typed_left.ty,
                         op,
@@ -283,8 +957,10 @@ impl TypeChecker {
                         }
                     }
                     crate::ast::nodes::UnaryOperator::Negate => {
-                        if typed_operand.ty == Type::Int {
-                            Ok(TypedExpression::new(Type::Int, span.clone()))
+                        if matches!(typed_operand.ty, Type::Int | Type::BigInt) {
+                            self.operator_types
+                                .insert(span.clone(), typed_operand.ty.clone());
+                            Ok(TypedExpression::new(typed_operand.ty, span.clone()))
                         } else {
                             Err(TypeError::TypeMismatch {
                                 expected: Type::Int,
@@ -299,48 +975,40 @@ impl TypeChecker {
                 param,
                 param_type,
                 body,
+                param_id,
                 span,
             } => {
                 // Use explicit parameter type if provided, otherwise infer
                 let param_type = if let Some(param_type_expr) = param_type {
                     self.convert_type_expression(param_type_expr)?
                 } else {
-                    let inference = TypeInference::new(self.environment.clone());
+                    let inference = TypeInference::new(self.environment.clone())
+                        .with_edition(self.module_loader.edition());
                     inference.infer_parameter_type(param, body)?
                 };
-
-                let mut function_checker = TypeChecker {
-                    environment: Environment::with_parent(self.environment.clone()),
-                    errors: Vec::new(),
-                    module_loader: ModuleLoader::new(),
-                };
-                function_checker
-                    .module_loader
-                    .set_current_directory(self.module_loader.get_current_directory());
-
-                // Bind the parameter in the function's scope
-                function_checker
-                    .environment
-                    .bind(param.clone(), param_type.clone());
-
-                // Type check the function body
-                let typed_body = function_checker.check_expression(body)?;
-
-                // Create the function type
-                let function_type = Type::Function {
-                    param: Box::new(param_type),
-                    result: Box::new(typed_body.ty.clone()),
-                };
-
-                Ok(TypedExpression::new(function_type, span.clone()))
+                self.check_function(param, param_type, body, *param_id, span)
             }
             Expression::FunctionCall {
                 function,
                 argument,
                 span,
             } => {
+                // Calling into a standard module such as std:fs is IO
+                if let Expression::QualifiedIdentifier { module, name, .. } = function.as_ref()
+                    && self
+                        .module_loader
+                        .get_module_path(module)
+                        .is_some_and(|path| path.starts_with(crate::stdlib::STD_PREFIX))
+                {
+                    self.check_io_allowed(&format!("{}.{}", module, name), span)?;
+                }
                 let function_typed = self.check_expression(function)?;
-                let argument_typed = self.check_expression(argument)?;
+                let argument_typed = match &function_typed.ty {
+                    Type::Function { param, .. } => {
+                        self.check_expression_with_expected(argument, param)?
+                    }
+                    _ => self.check_expression(argument)?,
+                };
 
                 match &function_typed.ty {
                     Type::Function { param, result } => {
@@ -350,17 +1018,12 @@ impl TypeChecker {
                         let refined_result =
                             TypeCompatibility::refine_type_with_context(result, &Type::Unknown);
 
-                        let is_compatible = TypeCompatibility::types_compatible(
-                            &argument_typed.ty,
-                            &refined_param,
-                        ) || (argument_typed.ty == Type::Unknown
-                            && matches!(refined_param, Type::List { .. }))
-                            || matches!(&argument_typed.ty, Type::List { element } if **element == Type::Unknown)
-                            || matches!(&argument_typed.ty, Type::Sum { left, right } if **left == Type::Unknown || **right == Type::Unknown)
-                            || matches!(&refined_param, Type::Sum { left, right } if **left == Type::Unknown || **right == Type::Unknown);
-
-                        if is_compatible {
+                        if TypeCompatibility::types_compatible(&argument_typed.ty, &refined_param) {
                             Ok(TypedExpression::new(refined_result, span.clone()))
+                        } else if let Some(error) =
+                            missing_arguments(argument, &argument_typed.ty, &refined_param)
+                        {
+                            Err(error)
                         } else {
                             Err(TypeError::TypeMismatch {
                                 expected: refined_param,
@@ -369,13 +1032,30 @@ impl TypeChecker {
                             })
                         }
                     }
-                    Type::Unknown => Ok(TypedExpression::new(Type::Unknown, span.clone())),
-                    _ => Err(TypeError::TypeMismatch {
-                        expected: Type::Function {
-                            param: Box::new(Type::Unknown),
-                            result: Box::new(Type::Unknown),
+                    Type::Unknown | Type::Dyn => Ok(TypedExpression::new(
+                        function_typed.ty.clone(),
+                        span.clone(),
+                    )),
+                    // Every argument before this one was taken, so the
+                    // function called takes one fewer than it is given
+                    _ if matches!(function.as_ref(), Expression::FunctionCall { .. }) => {
+                        let (given, name) = call_chain(expression);
+                        Err(TypeError::ArityMismatch {
+                            function: name,
+                            expected: given - 1,
+                            found: given,
+                            span: span.clone(),
+                        })
+                    }
+                    _ => Err(TypeError::NotCallable {
+                        ty: function_typed.ty.clone(),
+                        binding: match function.as_ref() {
+                            Expression::Identifier { name, .. } => Some(Box::new((
+                                name.clone(),
+                                self.environment.definition(name).cloned(),
+                            ))),
+                            _ => None,
                         },
-                        found: function_typed.ty.clone(),
                         span: span.clone(),
                     }),
                 }
@@ -391,30 +1071,25 @@ impl TypeChecker {
                         span.clone(),
                     ))
                 } else {
-                    // Type check all elements and ensure they're the same type
-                    let typed_elements: Result<Vec<_>, _> = elements
-                        .iter()
-                        .map(|elem| self.check_expression(elem))
-                        .collect();
-                    let typed_elements = typed_elements?;
-
-                    // Get the type of the first element
-                    let element_type = &typed_elements[0].ty;
-
-                    // Check that all elements have the same type
-                    for (i, typed_elem) in typed_elements.iter().enumerate().skip(1) {
-                        if !typed_elem.ty.is_assignable_to(element_type) {
+                    // The first element determines the type of the list
+                    let element_type = self.check_expression(&elements[0])?.ty;
+
+                    // Check that all other elements have the same type
+                    for elem in elements.iter().skip(1) {
+                        let typed_elem =
+                            self.check_expression_with_expected(elem, &element_type)?;
+                        if !typed_elem.ty.is_assignable_to(&element_type) {
                             return Err(TypeError::TypeMismatch {
                                 expected: element_type.clone(),
                                 found: typed_elem.ty.clone(),
-                                span: elements[i].span().clone(),
+                                span: elem.span().clone(),
                             });
                         }
                     }
 
                     Ok(TypedExpression::new(
                         Type::List {
-                            element: Box::new(element_type.clone()),
+                            element: Box::new(element_type),
                         },
                         span.clone(),
                     ))
@@ -435,6 +1110,19 @@ impl TypeChecker {
 
                 Ok(TypedExpression::new(pair_type, span.clone()))
             }
+            Expression::Tuple { elements, span } => {
+                let mut element_types = Vec::new();
+                for element in elements {
+                    element_types.push(self.check_expression(element)?.ty);
+                }
+
+                Ok(TypedExpression::new(
+                    Type::Tuple {
+                        elements: element_types,
+                    },
+                    span.clone(),
+                ))
+            }
             Expression::LeftInject { value, span } => {
                 let typed_value = self.check_expression(value)?;
                 Ok(TypedExpression::new(
@@ -455,12 +1143,47 @@ impl TypeChecker {
                     span.clone(),
                 ))
             }
+            Expression::TypeTest {
+                expression,
+                type_annotation,
+                span,
+            } => {
+                self.check_expression(expression)?;
+                self.convert_type_expression(type_annotation)?;
+                Ok(TypedExpression::new(Type::Bool, span.clone()))
+            }
+            Expression::TypeAscription {
+                expression,
+                type_annotation,
+                span,
+            } => {
+                let annotated_type = self.convert_type_expression(type_annotation)?;
+                let typed_expression =
+                    self.check_expression_with_expected(expression, &annotated_type)?;
+
+                let refined_type = TypeCompatibility::refine_type_with_annotation(
+                    &typed_expression.ty,
+                    &annotated_type,
+                )?;
+
+                if !TypeCompatibility::types_compatible(&annotated_type, &refined_type) {
+                    return Err(TypeError::TypeMismatch {
+                        expected: annotated_type,
+                        found: refined_type,
+                        span: span.clone(),
+                    });
+                }
+
+                Ok(TypedExpression::new(annotated_type, span.clone()))
+            }
             Expression::Case {
                 expression,
                 left_pattern,
                 left_body,
                 right_pattern,
                 right_body,
+                left_id,
+                right_id,
                 span,
             } => {
                 let typed_expr = self.check_expression(expression)?;
@@ -471,7 +1194,15 @@ impl TypeChecker {
                         let mut left_checker = TypeChecker {
                             environment: Environment::with_parent(self.environment.clone()),
                             errors: Vec::new(),
-                            module_loader: ModuleLoader::new(),
+                            warnings: Vec::new(),
+                            module_loader: self.module_loader.clone(),
+                            strict: self.strict,
+                            forbid_io: self.forbid_io,
+                            allow_redefinition: self.allow_redefinition,
+                            constants: HashMap::new(),
+                            returns: Vec::new(),
+                            symbols: SymbolTable::default(),
+                            operator_types: HashMap::new(),
                         };
                         left_checker
                             .module_loader
@@ -485,14 +1216,29 @@ impl TypeChecker {
 
                         left_checker
                             .environment
-                            .bind(left_pattern.clone(), left_type);
-                        let typed_left_body = left_checker.check_expression(left_body)?;
+                            .bind(left_pattern.clone(), left_type.clone());
+                        left_checker.define_symbol(*left_id, left_pattern, span, &left_type);
+                        let typed_left_body = left_checker.check_expression(left_body);
+                        self.symbols.append(&mut left_checker.symbols);
+                        let typed_left_body = typed_left_body?;
+                        self.warnings.append(&mut left_checker.warnings);
+                        self.operator_types
+                            .extend(std::mem::take(&mut left_checker.operator_types));
+                        self.returns.append(&mut left_checker.returns);
 
                         // Check right branch
                         let mut right_checker = TypeChecker {
                             environment: Environment::with_parent(self.environment.clone()),
                             errors: Vec::new(),
-                            module_loader: ModuleLoader::new(),
+                            warnings: Vec::new(),
+                            module_loader: self.module_loader.clone(),
+                            strict: self.strict,
+                            forbid_io: self.forbid_io,
+                            allow_redefinition: self.allow_redefinition,
+                            constants: HashMap::new(),
+                            returns: Vec::new(),
+                            symbols: SymbolTable::default(),
+                            operator_types: HashMap::new(),
                         };
                         right_checker
                             .module_loader
@@ -505,8 +1251,15 @@ impl TypeChecker {
 
                         right_checker
                             .environment
-                            .bind(right_pattern.clone(), right_type);
-                        let typed_right_body = right_checker.check_expression(right_body)?;
+                            .bind(right_pattern.clone(), right_type.clone());
+                        right_checker.define_symbol(*right_id, right_pattern, span, &right_type);
+                        let typed_right_body = right_checker.check_expression(right_body);
+                        self.symbols.append(&mut right_checker.symbols);
+                        let typed_right_body = typed_right_body?;
+                        self.warnings.append(&mut right_checker.warnings);
+                        self.operator_types
+                            .extend(std::mem::take(&mut right_checker.operator_types));
+                        self.returns.append(&mut right_checker.returns);
 
                         // Ensure branches return compatible types
                         if TypeCompatibility::types_compatible(
@@ -538,9 +1291,27 @@ impl TypeChecker {
                     }),
                 }
             }
+            Expression::Match {
+                expression,
+                arms,
+                span,
+            } => self.check_case_arms(expression, arms, span),
             Expression::Fix { function, span } => {
                 // Type check the function expression
-                let func_typed = self.check_expression(function)?;
+                let func_typed = match function.as_ref() {
+                    Expression::Function {
+                        param,
+                        param_type: None,
+                        body,
+                        param_id,
+                        span: function_span,
+                    } => {
+                        let self_type =
+                            self.fixed_point_type(param, body, *param_id, function_span);
+                        self.check_function(param, self_type, body, *param_id, function_span)?
+                    }
+                    _ => self.check_expression(function)?,
+                };
 
                 // The function should have type (T -> T) -> T for some T
                 // Where T is typically a function type for recursive functions
@@ -614,23 +1385,40 @@ impl TypeChecker {
                 let mut block_checker = TypeChecker {
                     environment: Environment::with_parent(self.environment.clone()),
                     errors: Vec::new(),
-                    module_loader: ModuleLoader::new(),
+                    warnings: Vec::new(),
+                    module_loader: self.module_loader.clone(),
+                    strict: self.strict,
+                    forbid_io: self.forbid_io,
+                    allow_redefinition: self.allow_redefinition,
+                    constants: HashMap::new(),
+                    returns: Vec::new(),
+                    symbols: SymbolTable::default(),
+                    operator_types: HashMap::new(),
                 };
                 block_checker
                     .module_loader
                     .set_current_directory(self.module_loader.get_current_directory());
 
-                // Check all statements in the block
-                for stmt in statements {
-                    block_checker.check_statement(stmt)?;
-                }
-
-                // Return the type of the final expression, or Unit if none
-                if let Some(expr) = expression {
-                    block_checker.check_expression(expr)
-                } else {
-                    Ok(TypedExpression::new(Type::Unit, span.clone()))
-                }
+                // Check all statements in the block, then the final
+                // expression, whose type is the block's, or Unit if none
+                let typed = statements
+                    .iter()
+                    .try_for_each(|stmt| {
+                        let typed_stmt = block_checker.check_statement(stmt)?;
+                        block_checker.warn_if_unused(&typed_stmt);
+                        Ok(())
+                    })
+                    .and_then(|()| match expression {
+                        Some(expr) => block_checker.check_expression(expr),
+                        None => Ok(TypedExpression::new(Type::Unit, span.clone())),
+                    });
+                self.symbols.append(&mut block_checker.symbols);
+                let typed = typed?;
+                self.warnings.append(&mut block_checker.warnings);
+                self.operator_types
+                    .extend(std::mem::take(&mut block_checker.operator_types));
+                self.returns.append(&mut block_checker.returns);
+                Ok(typed)
             }
             Expression::FirstProjection { pair, span } => {
                 let pair_typed = self.check_expression(pair)?;
@@ -664,9 +1452,38 @@ impl TypeChecker {
                     }),
                 }
             }
+            Expression::TupleProjection { tuple, index, span } => {
+                let tuple_typed = self.check_expression(tuple)?;
+                let element_types = match &tuple_typed.ty {
+                    Type::Pair { first, second } => vec![(**first).clone(), (**second).clone()],
+                    Type::Tuple { elements } => elements.clone(),
+                    Type::Unknown => return Ok(TypedExpression::new(Type::Unknown, span.clone())),
+                    _ => {
+                        return Err(TypeError::TypeMismatch {
+                            expected: Type::Tuple {
+                                elements: vec![Type::Error; *index + 1],
+                            },
+                            found: tuple_typed.ty.clone(),
+                            span: span.clone(),
+                        });
+                    }
+                };
+
+                match element_types.get(*index) {
+                    Some(element_type) => {
+                        Ok(TypedExpression::new(element_type.clone(), span.clone()))
+                    }
+                    None => Err(TypeError::TupleIndexOutOfRange {
+                        index: *index,
+                        tuple_type: tuple_typed.ty.clone(),
+                        span: span.clone(),
+                    }),
+                }
+            }
             Expression::Cons { head, tail, span } => {
                 let head_typed = self.check_expression(head)?;
-                let tail_typed = self.check_expression(tail)?;
+                let tail_typed =
+                    self.check_expression_with_expected(tail, &Type::list(head_typed.ty.clone()))?;
 
                 match &tail_typed.ty {
                     Type::List { element } => {
@@ -722,6 +1539,7 @@ impl TypeChecker {
                 }
             }
             Expression::Print { value, span } => {
+                self.check_io_allowed("print", span)?;
                 // Type check the value being printed (but we don't need the result)
                 let _ = self.check_expression(value)?;
                 // Print always returns Unit type
@@ -731,6 +1549,7 @@ impl TypeChecker {
                 variable,
                 iterable,
                 body,
+                id,
                 span,
             } => {
                 let iterable_typed = self.check_expression(iterable)?;
@@ -753,20 +1572,78 @@ impl TypeChecker {
                 let mut for_checker = TypeChecker {
                     environment: Environment::with_parent(self.environment.clone()),
                     errors: Vec::new(),
-                    module_loader: ModuleLoader::new(),
+                    warnings: Vec::new(),
+                    module_loader: self.module_loader.clone(),
+                    strict: self.strict,
+                    forbid_io: self.forbid_io,
+                    allow_redefinition: self.allow_redefinition,
+                    constants: HashMap::new(),
+                    returns: Vec::new(),
+                    symbols: SymbolTable::default(),
+                    operator_types: HashMap::new(),
                 };
                 for_checker
                     .module_loader
                     .set_current_directory(self.module_loader.get_current_directory());
-                for_checker.environment.bind(variable.clone(), element_type);
-                let _ = for_checker.check_expression(body)?;
+                for_checker
+                    .environment
+                    .bind(variable.clone(), element_type.clone());
+                for_checker.define_symbol(*id, variable, span, &element_type);
+                let typed_body = for_checker.check_expression(body);
+                self.symbols.append(&mut for_checker.symbols);
+                typed_body?;
+                self.warnings.append(&mut for_checker.warnings);
+                self.operator_types
+                    .extend(std::mem::take(&mut for_checker.operator_types));
+                self.returns.append(&mut for_checker.returns);
 
                 // For loops return Unit
                 Ok(TypedExpression::new(Type::Unit, span.clone()))
             }
+            Expression::With {
+                name,
+                value,
+                body,
+                id,
+                span,
+            } => {
+                let value_typed = self.check_expression(value)?;
+
+                // Type check the body in a new scope with the resource bound
+                let mut with_checker = TypeChecker {
+                    environment: Environment::with_parent(self.environment.clone()),
+                    errors: Vec::new(),
+                    warnings: Vec::new(),
+                    module_loader: self.module_loader.clone(),
+                    strict: self.strict,
+                    forbid_io: self.forbid_io,
+                    allow_redefinition: self.allow_redefinition,
+                    constants: HashMap::new(),
+                    returns: Vec::new(),
+                    symbols: SymbolTable::default(),
+                    operator_types: HashMap::new(),
+                };
+                with_checker
+                    .module_loader
+                    .set_current_directory(self.module_loader.get_current_directory());
+                with_checker
+                    .environment
+                    .bind(name.clone(), value_typed.ty.clone());
+                with_checker.define_symbol(*id, name, span, &value_typed.ty);
+                let body_typed = with_checker.check_expression(body);
+                self.symbols.append(&mut with_checker.symbols);
+                let body_typed = body_typed?;
+                self.warnings.append(&mut with_checker.warnings);
+                self.operator_types
+                    .extend(std::mem::take(&mut with_checker.operator_types));
+                self.returns.append(&mut with_checker.returns);
+
+                // The block's value is the value of the whole expression
+                Ok(TypedExpression::new(body_typed.ty, span.clone()))
+            }
             Expression::Range { start, end, span } => {
-                let start_typed = self.check_expression(start)?;
-                let end_typed = self.check_expression(end)?;
+                let start_typed = self.check_expression_with_expected(start, &Type::Int)?;
+                let end_typed = self.check_expression_with_expected(end, &Type::Int)?;
 
                 // Both start and end must be integers
                 if start_typed.ty != Type::Int {
@@ -792,9 +1669,40 @@ impl TypeChecker {
                     span.clone(),
                 ))
             }
+            Expression::DivMod {
+                dividend,
+                divisor,
+                span,
+            }
+            | Expression::SafeDiv {
+                dividend,
+                divisor,
+                span,
+            } => {
+                // Both operands must be integers
+                for operand in [dividend, divisor] {
+                    let operand_typed = self.check_expression_with_expected(operand, &Type::Int)?;
+                    if !TypeCompatibility::types_compatible(&operand_typed.ty, &Type::Int) {
+                        return Err(TypeError::TypeMismatch {
+                            expected: Type::Int,
+                            found: operand_typed.ty,
+                            span: operand.span().clone(),
+                        });
+                    }
+                }
+
+                // divmod returns (quotient, remainder); safeDiv returns the
+                // quotient on the left or an error message on the right
+                let result_type = match expression {
+                    Expression::DivMod { .. } => Type::pair(Type::Int, Type::Int),
+                    _ => Type::sum(Type::Int, Type::String),
+                };
+                Ok(TypedExpression::new(result_type, span.clone()))
+            }
             Expression::Concat { left, right, span } => {
-                let left_typed = self.check_expression(left)?;
-                let right_typed = self.check_expression(right)?;
+                self.warn_if_deprecated("concat", span);
+                let left_typed = self.check_expression_with_expected(left, &Type::String)?;
+                let right_typed = self.check_expression_with_expected(right, &Type::String)?;
 
                 // Both operands must be strings
                 if left_typed.ty != Type::String {
@@ -819,8 +1727,8 @@ impl TypeChecker {
                 index,
                 span,
             } => {
-                let string_typed = self.check_expression(string)?;
-                let index_typed = self.check_expression(index)?;
+                let string_typed = self.check_expression_with_expected(string, &Type::String)?;
+                let index_typed = self.check_expression_with_expected(index, &Type::Int)?;
 
                 // String must be String type
                 if string_typed.ty != Type::String {
@@ -843,10 +1751,10 @@ impl TypeChecker {
                 Ok(TypedExpression::new(Type::String, span.clone()))
             }
             Expression::Length { string, span } => {
-                let string_typed = self.check_expression(string)?;
+                let string_typed = self.check_expression_with_expected(string, &Type::String)?;
 
-                // String must be String type
-                if string_typed.ty != Type::String {
+                // Length counts the characters of a String or the bytes of Bytes
+                if !matches!(string_typed.ty, Type::String | Type::Bytes) {
                     return Err(TypeError::TypeMismatch {
                         expected: Type::String,
                         found: string_typed.ty,
@@ -857,22 +1765,241 @@ impl TypeChecker {
                 // Returns length as integer
                 Ok(TypedExpression::new(Type::Int, span.clone()))
             }
+            Expression::Hole { name, span } => Ok(self.check_hole(name, &Type::Unknown, span)),
+            Expression::Bytes { value: _, span } => {
+                Ok(TypedExpression::new(Type::Bytes, span.clone()))
+            }
+            Expression::Spawn { function, span } => {
+                let function_typed = self.check_expression_with_expected(
+                    function,
+                    &Type::function(Type::Unit, Type::Unknown),
+                )?;
+
+                // The spawned function is called with the unit value
+                let result = match function_typed.ty {
+                    Type::Function { param, result }
+                        if TypeCompatibility::types_compatible(&param, &Type::Unit) =>
+                    {
+                        *result
+                    }
+                    Type::Unknown | Type::Dyn | Type::Error => function_typed.ty,
+                    other => {
+                        return Err(TypeError::TypeMismatch {
+                            expected: Type::function(Type::Unit, Type::Unknown),
+                            found: other,
+                            span: function.span().clone(),
+                        });
+                    }
+                };
+
+                Ok(TypedExpression::new(
+                    Type::Handle {
+                        result: Box::new(result),
+                    },
+                    span.clone(),
+                ))
+            }
+            Expression::Join { handle, span } => {
+                let handle_typed = self.check_expression(handle)?;
+                match handle_typed.ty {
+                    Type::Handle { result } => Ok(TypedExpression::new(*result, span.clone())),
+                    Type::Unknown | Type::Dyn | Type::Error => {
+                        Ok(TypedExpression::new(handle_typed.ty, span.clone()))
+                    }
+                    other => Err(TypeError::TypeMismatch {
+                        expected: Type::Handle {
+                            result: Box::new(Type::Unknown),
+                        },
+                        found: other,
+                        span: handle.span().clone(),
+                    }),
+                }
+            }
+            Expression::Channel { span } => Ok(TypedExpression::new(
+                Type::Channel {
+                    element: Box::new(Type::Unknown),
+                },
+                span.clone(),
+            )),
+            Expression::Send {
+                channel,
+                value,
+                span,
+            } => {
+                let channel_typed = self.check_expression(channel)?;
+                let element = self.channel_element(channel, channel_typed.ty)?;
+                let value_typed = self.check_expression_with_expected(value, &element)?;
+
+                if !TypeCompatibility::types_compatible(&value_typed.ty, &element) {
+                    return Err(TypeError::TypeMismatch {
+                        expected: element,
+                        found: value_typed.ty,
+                        span: value.span().clone(),
+                    });
+                }
+
+                // The first send fixes the element type of a fresh local channel
+                if let Expression::Identifier { name, .. } = channel.as_ref()
+                    && element == Type::Unknown
+                    && self.environment.is_bound_locally(name)
+                {
+                    self.environment.update(
+                        name.clone(),
+                        Type::Channel {
+                            element: Box::new(value_typed.ty),
+                        },
+                    );
+                }
+
+                Ok(TypedExpression::new(Type::Unit, span.clone()))
+            }
+            Expression::Recv { channel, span } => {
+                let channel_typed = self.check_expression(channel)?;
+                let element = self.channel_element(channel, channel_typed.ty)?;
+                Ok(TypedExpression::new(element, span.clone()))
+            }
+            Expression::Sleep { milliseconds, span } => {
+                let milliseconds_typed =
+                    self.check_expression_with_expected(milliseconds, &Type::Int)?;
+                if !TypeCompatibility::types_compatible(&milliseconds_typed.ty, &Type::Int) {
+                    return Err(TypeError::TypeMismatch {
+                        expected: Type::Int,
+                        found: milliseconds_typed.ty,
+                        span: milliseconds.span().clone(),
+                    });
+                }
+                Ok(TypedExpression::new(Type::Unit, span.clone()))
+            }
+            Expression::Random { bound, span } => {
+                let bound_typed = self.check_expression_with_expected(bound, &Type::Int)?;
+                if !TypeCompatibility::types_compatible(&bound_typed.ty, &Type::Int) {
+                    return Err(TypeError::TypeMismatch {
+                        expected: Type::Int,
+                        found: bound_typed.ty,
+                        span: bound.span().clone(),
+                    });
+                }
+                Ok(TypedExpression::new(Type::Int, span.clone()))
+            }
+            Expression::Input { span } => {
+                self.check_io_allowed("input", span)?;
+                // The line, or `inr(())` once the input has ended
+                Ok(TypedExpression::new(
+                    Type::sum(Type::String, Type::Unit),
+                    span.clone(),
+                ))
+            }
+            Expression::ReadAll { span } => {
+                self.check_io_allowed("readAll", span)?;
+                Ok(TypedExpression::new(Type::String, span.clone()))
+            }
+            Expression::ReadFileBytes { path, span } => {
+                self.check_io_allowed("readFileBytes", span)?;
+                let path_typed = self.check_expression_with_expected(path, &Type::String)?;
+                if !TypeCompatibility::types_compatible(&path_typed.ty, &Type::String) {
+                    return Err(TypeError::TypeMismatch {
+                        expected: Type::String,
+                        found: path_typed.ty,
+                        span: path.span().clone(),
+                    });
+                }
+
+                // The contents, or the reason the file could not be read
+                Ok(TypedExpression::new(
+                    Type::sum(Type::Bytes, Type::String),
+                    span.clone(),
+                ))
+            }
+            Expression::ByteAt { bytes, index, span } => {
+                let bytes_typed = self.check_expression_with_expected(bytes, &Type::Bytes)?;
+                let index_typed = self.check_expression_with_expected(index, &Type::Int)?;
+
+                if !TypeCompatibility::types_compatible(&bytes_typed.ty, &Type::Bytes) {
+                    return Err(TypeError::TypeMismatch {
+                        expected: Type::Bytes,
+                        found: bytes_typed.ty,
+                        span: bytes.span().clone(),
+                    });
+                }
+                if !TypeCompatibility::types_compatible(&index_typed.ty, &Type::Int) {
+                    return Err(TypeError::TypeMismatch {
+                        expected: Type::Int,
+                        found: index_typed.ty,
+                        span: index.span().clone(),
+                    });
+                }
+
+                // Returns the byte as an Int in 0..=255
+                Ok(TypedExpression::new(Type::Int, span.clone()))
+            }
+            Expression::ToBytes { string, span } => {
+                let string_typed = self.check_expression_with_expected(string, &Type::String)?;
+                if !TypeCompatibility::types_compatible(&string_typed.ty, &Type::String) {
+                    return Err(TypeError::TypeMismatch {
+                        expected: Type::String,
+                        found: string_typed.ty,
+                        span: string.span().clone(),
+                    });
+                }
+                Ok(TypedExpression::new(Type::Bytes, span.clone()))
+            }
+            Expression::ToInt { string, span } => {
+                let string_typed = self.check_expression_with_expected(string, &Type::String)?;
+                if !TypeCompatibility::types_compatible(&string_typed.ty, &Type::String) {
+                    return Err(TypeError::TypeMismatch {
+                        expected: Type::String,
+                        found: string_typed.ty,
+                        span: string.span().clone(),
+                    });
+                }
+
+                // The number, or why the text is not one
+                Ok(TypedExpression::new(
+                    Type::sum(Type::Int, Type::String),
+                    span.clone(),
+                ))
+            }
+            Expression::FromBytes { bytes, span } => {
+                let bytes_typed = self.check_expression_with_expected(bytes, &Type::Bytes)?;
+                if !TypeCompatibility::types_compatible(&bytes_typed.ty, &Type::Bytes) {
+                    return Err(TypeError::TypeMismatch {
+                        expected: Type::Bytes,
+                        found: bytes_typed.ty,
+                        span: bytes.span().clone(),
+                    });
+                }
+
+                // The decoded text, or a description of the invalid UTF-8
+                Ok(TypedExpression::new(
+                    Type::sum(Type::String, Type::String),
+                    span.clone(),
+                ))
+            }
+            Expression::Graphemes { string, span } => {
+                let string_typed = self.check_expression_with_expected(string, &Type::String)?;
+
+                if string_typed.ty != Type::String {
+                    return Err(TypeError::TypeMismatch {
+                        expected: Type::String,
+                        found: string_typed.ty,
+                        span: span.clone(),
+                    });
+                }
+
+                Ok(TypedExpression::new(Type::list(Type::String), span.clone()))
+            }
             Expression::ToString { expression, span } => {
                 let expression_typed = self.check_expression(expression)?;
 
-                // toString can convert any type to string
-                // We accept Int, Bool, String, List, Pair types
-                match &expression_typed.ty {
-                    Type::Int
-                    | Type::Bool
-                    | Type::String
-                    | Type::List { .. }
-                    | Type::Pair { .. }
-                    | Type::Unit => Ok(TypedExpression::new(Type::String, span.clone())),
-                    _ => {
-                        // For now, we'll allow other types but may need to extend this later
-                        Ok(TypedExpression::new(Type::String, span.clone()))
-                    }
+                // toString can convert any value whose type satisfies Show
+                if expression_typed.ty.satisfies(Constraint::Show) {
+                    Ok(TypedExpression::new(Type::String, span.clone()))
+                } else {
+                    Err(TypeError::UnsatisfiedConstraint {
+                        ty: expression_typed.ty,
+                        constraint: Constraint::Show,
+                        span: expression.span().clone(),
+                    })
                 }
             }
             Expression::TypeOf { expression, span } => {
@@ -887,7 +2014,8 @@ impl TypeChecker {
                 else_branch,
                 span,
             } => {
-                let condition_typed = self.check_expression(condition)?;
+                let condition_typed =
+                    self.check_expression_with_expected(condition, &Type::Bool)?;
                 if condition_typed.ty != Type::Bool {
                     return Err(TypeError::TypeMismatch {
                         expected: Type::Bool,
@@ -895,16 +2023,31 @@ impl TypeChecker {
                         span: condition.span().clone(),
                     });
                 }
-
-                let then_typed = self.check_expression(then_branch)?;
+                self.warn_if_constant(condition, then_branch, else_branch.as_deref());
+
+                let then_typed = match self.narrowed_variable(condition)? {
+                    Some((name, narrowed_type)) => {
+                        // Check the then branch with the variable refined to the tested type
+                        self.environment.enter_scope();
+                        self.environment.bind(name, narrowed_type);
+                        let then_typed = self.check_expression(then_branch);
+                        self.environment.exit_scope();
+                        then_typed?
+                    }
+                    None => self.check_expression(then_branch)?,
+                };
 
                 if let Some(else_branch) = else_branch {
                     let else_typed = self.check_expression(else_branch)?;
 
-                    // If both branches have the same type, use that type
-                    if then_typed.ty.is_assignable_to(&else_typed.ty)
-                        && else_typed.ty.is_assignable_to(&then_typed.ty)
+                    // A branch that always returns gives the `if` no value
+                    if always_returns(then_branch) {
+                        Ok(TypedExpression::new(else_typed.ty, span.clone()))
+                    } else if always_returns(else_branch)
+                        || then_typed.ty.is_assignable_to(&else_typed.ty)
+                            && else_typed.ty.is_assignable_to(&then_typed.ty)
                     {
+                        // If both branches have the same type, use that type
                         Ok(TypedExpression::new(then_typed.ty, span.clone()))
                     } else {
                         // Different types - create a sum type
@@ -930,6 +2073,108 @@ impl TypeChecker {
         }
     }
 
+    /// Type check an expression against the type its context expects.
+    ///
+    /// The expected type is pushed into empty lists, sum injections, pairs and
+    /// tuples so that they take their missing types from the context instead of
+    /// defaulting to `Unknown`. Other expressions are inferred as usual; the
+    /// caller remains responsible for reporting a mismatch with `expected`.
+    pub fn check_expression_with_expected(
+        &mut self,
+        expression: &Expression,
+        expected: &Type,
+    ) -> TypeResult<TypedExpression> {
+        match (expression, expected) {
+            (Expression::List { elements, span }, Type::List { element }) => {
+                if elements.is_empty() {
+                    return Ok(TypedExpression::new(expected.clone(), span.clone()));
+                }
+                let mut element_type = (**element).clone();
+                for elem in elements {
+                    let typed_elem = self.check_expression_with_expected(elem, &element_type)?;
+                    if !TypeCompatibility::types_compatible(&typed_elem.ty, &element_type) {
+                        // Let regular inference report the inconsistent element
+                        return self.check_expression(expression);
+                    }
+                    element_type =
+                        TypeCompatibility::refine_type_with_context(&element_type, &typed_elem.ty);
+                }
+                Ok(TypedExpression::new(Type::list(element_type), span.clone()))
+            }
+            (Expression::LeftInject { value, span }, Type::Sum { left, right }) => {
+                let typed_value = self.check_expression_with_expected(value, left)?;
+                Ok(TypedExpression::new(
+                    Type::sum(typed_value.ty, (**right).clone()),
+                    span.clone(),
+                ))
+            }
+            (Expression::RightInject { value, span }, Type::Sum { left, right }) => {
+                let typed_value = self.check_expression_with_expected(value, right)?;
+                Ok(TypedExpression::new(
+                    Type::sum((**left).clone(), typed_value.ty),
+                    span.clone(),
+                ))
+            }
+            (
+                Expression::Pair {
+                    first,
+                    second,
+                    span,
+                },
+                Type::Pair {
+                    first: expected_first,
+                    second: expected_second,
+                },
+            ) => {
+                let typed_first = self.check_expression_with_expected(first, expected_first)?;
+                let typed_second = self.check_expression_with_expected(second, expected_second)?;
+                Ok(TypedExpression::new(
+                    Type::pair(typed_first.ty, typed_second.ty),
+                    span.clone(),
+                ))
+            }
+            (
+                Expression::Tuple { elements, span },
+                Type::Tuple {
+                    elements: expected_elements,
+                },
+            ) if elements.len() == expected_elements.len() => {
+                let mut element_types = Vec::new();
+                for (element, expected_element) in elements.iter().zip(expected_elements) {
+                    element_types.push(
+                        self.check_expression_with_expected(element, expected_element)?
+                            .ty,
+                    );
+                }
+                Ok(TypedExpression::new(
+                    Type::Tuple {
+                        elements: element_types,
+                    },
+                    span.clone(),
+                ))
+            }
+            // A function passed where its parameter type is known takes
+            // that type, rather than one inferred from its body
+            (
+                Expression::Function {
+                    param,
+                    param_type: None,
+                    body,
+                    param_id,
+                    span,
+                },
+                Type::Function {
+                    param: expected_param,
+                    ..
+                },
+            ) if !expected_param.contains_unknown() && **expected_param != Type::Dyn => {
+                self.check_function(param, (**expected_param).clone(), body, *param_id, span)
+            }
+            (Expression::Hole { name, span }, _) => Ok(self.check_hole(name, expected, span)),
+            _ => self.check_expression(expression),
+        }
+    }
+
     /// Convert a TypeExpression to a Type
     fn convert_type_expression(&self, type_expr: &TypeExpression) -> TypeResult<Type> {
         match type_expr {
@@ -942,6 +2187,18 @@ impl TypeChecker {
                     element: Box::new(element_type),
                 })
             }
+            TypeExpression::Handle { result, .. } => {
+                let result_type = self.convert_type_expression(result)?;
+                Ok(Type::Handle {
+                    result: Box::new(result_type),
+                })
+            }
+            TypeExpression::Channel { element, .. } => {
+                let element_type = self.convert_type_expression(element)?;
+                Ok(Type::Channel {
+                    element: Box::new(element_type),
+                })
+            }
             TypeExpression::Function { param, result, .. } => {
                 let param_type = self.convert_type_expression(param)?;
                 let result_type = self.convert_type_expression(result)?;
@@ -958,6 +2215,15 @@ impl TypeChecker {
                     second: Box::new(second_type),
                 })
             }
+            TypeExpression::Tuple { elements, .. } => {
+                let mut element_types = Vec::new();
+                for element in elements {
+                    element_types.push(self.convert_type_expression(element)?);
+                }
+                Ok(Type::Tuple {
+                    elements: element_types,
+                })
+            }
             TypeExpression::Sum { left, right, .. } => {
                 let left_type = self.convert_type_expression(left)?;
                 let right_type = self.convert_type_expression(right)?;
@@ -972,14 +2238,225 @@ impl TypeChecker {
                     inner: Box::new(inner_type),
                 })
             }
-            TypeExpression::Named { name, span } => {
-                // For now, we don't support named types - this could be extended later
-                Err(TypeError::UndefinedVariable {
+            TypeExpression::Named { name, .. } if name == "Dyn" => Ok(Type::Dyn),
+            TypeExpression::Named { name, .. } if name == "BigInt" => Ok(Type::BigInt),
+            TypeExpression::Named { name, .. } if name == "Bytes" => Ok(Type::Bytes),
+            TypeExpression::Named { name, span } => self
+                .enum_variants(name)
+                .map(|_| Type::enumeration(name))
+                .ok_or_else(|| TypeError::UndefinedVariable {
                     name: name.clone(),
+                    suggestion: None,
                     span: span.clone(),
-                })
+                }),
+        }
+    }
+
+    /// Variants of the enum declared as `name` in alphabetical order, if
+    /// there is one
+    fn enum_variants(&self, name: &str) -> Option<Vec<String>> {
+        let mut variants: Vec<String> = self
+            .module_loader
+            .get_module_exports(name)?
+            .iter()
+            .filter(|(_, ty)| matches!(ty, Type::Enum { name: enum_name } if **enum_name == *name))
+            .map(|(variant, _)| variant.clone())
+            .collect();
+        variants.sort_unstable();
+        (!variants.is_empty()).then_some(variants)
+    }
+
+    /// Check a `case` on values. Each pattern must fit the value and each arm
+    /// must produce a compatible type. Without a `_` arm every variant of the
+    /// matched enum needs an arm, integer arms must cover every `Int` and a
+    /// case on strings needs a `_` arm. Repeated patterns and arms after the
+    /// value is fully covered are reported as unreachable, and integer arms
+    /// sharing some values as overlapping.
+    fn check_case_arms(
+        &mut self,
+        expression: &Expression,
+        arms: &[CaseArm],
+        span: &Span,
+    ) -> TypeResult<TypedExpression> {
+        let scrutinee = self.check_expression(expression)?.ty;
+        let mut pattern_type = matches!(scrutinee, Type::Enum { .. }).then(|| scrutinee.clone());
+        let mut covered: Vec<&str> = Vec::new();
+        let mut numbers = IntCoverage::default();
+        let mut exhausted = false;
+        let mut result_type = Type::Unknown;
+
+        for arm in arms {
+            if exhausted {
+                self.warnings.push(TypeWarning::UnreachableArm {
+                    span: arm.pattern.span().clone(),
+                });
             }
+            // The type of the pattern, and the variants to cover for an enum
+            let (ty, variants) = match &arm.pattern {
+                Pattern::Wildcard { .. } => {
+                    exhausted = true;
+                    (None, None)
+                }
+                Pattern::Variant {
+                    enum_name,
+                    variant,
+                    span,
+                } => {
+                    let (ty, variants) = self.variant_type(enum_name, variant, span)?;
+                    (Some(ty), Some(variants))
+                }
+                Pattern::String { .. } => (Some(Type::String), None),
+                Pattern::Int { .. } | Pattern::Range { .. } => (Some(Type::Int), None),
+            };
+            if let Some(ty) = ty {
+                let span = arm.pattern.span();
+                let expected = pattern_type.get_or_insert_with(|| ty.clone());
+                if *expected != ty || !TypeCompatibility::types_compatible(&scrutinee, &ty) {
+                    let expected = match scrutinee {
+                        Type::Unknown | Type::Dyn => expected.clone(),
+                        _ => scrutinee,
+                    };
+                    return Err(TypeError::TypeMismatch {
+                        expected,
+                        found: ty,
+                        span: span.clone(),
+                    });
+                }
+                let overlap = match coverage::bounds(&arm.pattern) {
+                    Some((low, high)) => numbers.add(low, high),
+                    None => {
+                        let key = arm.pattern.label();
+                        let repeated = covered.contains(&key);
+                        covered.push(key);
+                        if repeated {
+                            Overlap::Full
+                        } else {
+                            Overlap::None
+                        }
+                    }
+                };
+                if !exhausted {
+                    let span = span.clone();
+                    match overlap {
+                        Overlap::Full => self.warnings.push(TypeWarning::UnreachableArm { span }),
+                        Overlap::Partial => {
+                            self.warnings.push(TypeWarning::OverlappingArm { span })
+                        }
+                        Overlap::None => {}
+                    }
+                }
+                if let Some(variants) = variants {
+                    exhausted |= variants.iter().all(|v| covered.contains(&v.as_str()));
+                }
+                exhausted |= numbers.is_complete();
+            }
+
+            let body = self.check_expression(&arm.body)?;
+            if !TypeCompatibility::types_compatible(&result_type, &body.ty) {
+                return Err(TypeError::TypeMismatch {
+                    expected: result_type,
+                    found: body.ty,
+                    span: arm.body.span().clone(),
+                });
+            }
+            if result_type == Type::Unknown {
+                result_type = body.ty;
+            }
+        }
+
+        if !exhausted {
+            let missing = match &pattern_type {
+                Some(Type::Enum { name }) => self
+                    .enum_variants(name)
+                    .unwrap_or_default()
+                    .iter()
+                    .filter(|variant| !covered.contains(&variant.as_str()))
+                    .map(|variant| format!("{}.{}", name, variant))
+                    .collect(),
+                Some(Type::Int) => numbers.missing(),
+                other => vec![format!("every {}", other.as_ref().unwrap_or(&scrutinee))],
+            };
+            return Err(TypeError::NonExhaustiveCase {
+                missing,
+                span: span.clone(),
+            });
+        }
+        Ok(TypedExpression::new(result_type, span.clone()))
+    }
+
+    /// Type of the pattern `enum_name.variant`, with all variants of its enum
+    fn variant_type(
+        &self,
+        enum_name: &str,
+        variant: &str,
+        span: &Span,
+    ) -> TypeResult<(Type, Vec<String>)> {
+        let Some(variants) = self.enum_variants(enum_name) else {
+            return Err(TypeError::UndefinedVariable {
+                name: enum_name.to_string(),
+                suggestion: None,
+                span: span.clone(),
+            });
+        };
+        if !variants.iter().any(|v| v == variant) {
+            return Err(TypeError::UndefinedVariable {
+                name: format!("{}.{}", enum_name, variant),
+                suggestion: closest_name(variant, variants.iter().map(String::as_str))
+                    .map(|v| format!("{}.{}", enum_name, v)),
+                span: span.clone(),
+            });
         }
+        Ok((Type::enumeration(enum_name), variants))
+    }
+
+    /// Element type of the channel used by `send` or `recv`
+    fn channel_element(&self, channel: &Expression, ty: Type) -> TypeResult<Type> {
+        match ty {
+            Type::Channel { element } => Ok(*element),
+            Type::Unknown | Type::Dyn | Type::Error => Ok(ty),
+            other => Err(TypeError::TypeMismatch {
+                expected: Type::Channel {
+                    element: Box::new(Type::Unknown),
+                },
+                found: other,
+                span: channel.span().clone(),
+            }),
+        }
+    }
+
+    /// Find the variable an `if` condition of the form `x is T` narrows, along
+    /// with its refined type. Only variables without a precise static type
+    /// (`Dyn` or not yet inferred) are narrowed.
+    fn narrowed_variable(&self, condition: &Expression) -> TypeResult<Option<(String, Type)>> {
+        if let Expression::TypeTest {
+            expression,
+            type_annotation,
+            ..
+        } = condition
+            && let Expression::Identifier { name, .. } = expression.as_ref()
+            && matches!(
+                self.environment.lookup(name),
+                Some(Type::Dyn | Type::Unknown)
+            )
+        {
+            let narrowed_type = self.convert_type_expression(type_annotation)?;
+            return Ok(Some((name.clone(), narrowed_type)));
+        }
+        Ok(None)
+    }
+
+    /// In strict mode, reject a declaration or parameter whose type inference
+    /// could not complete. Writing `Dyn` in an annotation opts out of this
+    /// check.
+    fn check_precision(&self, name: &str, ty: &Type, span: &Span) -> TypeResult<()> {
+        if self.strict && ty.contains_unknown() {
+            return Err(TypeError::ImpreciseType {
+                name: name.to_string(),
+                ty: ty.clone(),
+                span: span.clone(),
+            });
+        }
+        Ok(())
     }
 
     /// Get all accumulated type errors
@@ -996,6 +2473,197 @@ impl TypeChecker {
     pub fn get_environment(&self) -> &Environment {
         &self.environment
     }
+
+    /// Get the current type environment for changing bindings directly
+    pub fn get_environment_mut(&mut self) -> &mut Environment {
+        &mut self.environment
+    }
+
+    /// Get the loader holding the imported modules
+    pub fn get_module_loader(&self) -> &ModuleLoader {
+        &self.module_loader
+    }
+}
+
+/// The result type of a function, given the type its body ends with (`None`
+/// when the body always returns) and the types of its `return`s, which must
+/// all agree with it and with each other
+fn function_result(mut result: Option<Type>, returns: Vec<(Type, Span)>) -> TypeResult<Type> {
+    for (ty, span) in returns {
+        match result {
+            Some(ref expected) if !TypeCompatibility::types_compatible(expected, &ty) => {
+                return Err(TypeError::TypeMismatch {
+                    expected: expected.clone(),
+                    found: ty,
+                    span,
+                });
+            }
+            Some(ref expected) if *expected != Type::Unknown => {}
+            _ => result = Some(ty),
+        }
+    }
+    Ok(result.unwrap_or(Type::Unit))
+}
+
+/// The number of arguments `expression` passes to the function at the head of
+/// its chain of calls, as in `f(a)(b)`, with the name of that function if it
+/// is called by name
+fn call_chain(expression: &Expression) -> (usize, Option<String>) {
+    match expression {
+        Expression::FunctionCall { function, .. } => {
+            let (arguments, name) = call_chain(function);
+            (arguments + 1, name)
+        }
+        Expression::Identifier { name, .. } => (0, Some(name.clone())),
+        Expression::QualifiedIdentifier { module, name, .. } => {
+            (0, Some(format!("{}.{}", module, name)))
+        }
+        _ => (0, None),
+    }
+}
+
+/// An arity error if `call`, a call giving the function `found` where a value
+/// of type `expected` is wanted, would give that value with more arguments
+fn missing_arguments(call: &Expression, found: &Type, expected: &Type) -> Option<TypeError> {
+    if !matches!(call, Expression::FunctionCall { .. })
+        || matches!(
+            expected,
+            Type::Function { .. } | Type::Unknown | Type::Dyn | Type::Error
+        )
+    {
+        return None;
+    }
+    let mut missing = 0;
+    let mut result = found;
+    while let Type::Function { result: next, .. } = result {
+        missing += 1;
+        result = next;
+        if TypeCompatibility::types_compatible(result, expected) {
+            let (given, name) = call_chain(call);
+            return Some(TypeError::ArityMismatch {
+                function: name,
+                expected: given + missing,
+                found: given,
+                span: call.span().clone(),
+            });
+        }
+    }
+    None
+}
+
+/// Whether evaluating `expression` always calls the function `name` before it
+/// can finish, on every path through it. Best effort: a branch counts as a
+/// way out unless it is seen to call `name`, and code only run later, such as
+/// the body of a function or loop, is not looked into.
+fn always_recurses(expression: &Expression, name: &str) -> bool {
+    let recurses = |expression: &Expression| always_recurses(expression, name);
+    match expression {
+        Expression::FunctionCall {
+            function, argument, ..
+        } => {
+            matches!(function.as_ref(), Expression::Identifier { name: callee, .. } if callee == name)
+                || recurses(function)
+                || recurses(argument)
+        }
+        Expression::Function { .. } | Expression::Fix { .. } | Expression::Spawn { .. } => false,
+        Expression::If {
+            condition,
+            then_branch,
+            else_branch,
+            ..
+        } => {
+            recurses(condition)
+                || else_branch
+                    .as_deref()
+                    .is_some_and(|else_branch| recurses(then_branch) && recurses(else_branch))
+        }
+        Expression::Case {
+            expression,
+            left_body,
+            right_body,
+            ..
+        } => recurses(expression) || recurses(left_body) && recurses(right_body),
+        Expression::Match {
+            expression, arms, ..
+        } => recurses(expression) || !arms.is_empty() && arms.iter().all(|arm| recurses(&arm.body)),
+        Expression::For { iterable, .. } => recurses(iterable),
+        Expression::BinaryOp {
+            left,
+            operator: BinaryOperator::LogicalAnd | BinaryOperator::LogicalOr,
+            ..
+        } => recurses(left),
+        Expression::Block {
+            statements,
+            expression,
+            ..
+        } => {
+            for statement in statements {
+                let value = match statement {
+                    Statement::VariableDeclaration { value, .. }
+                    | Statement::ConstantDeclaration { value, .. }
+                    | Statement::Return { value, .. } => value,
+                    Statement::Expression { expression, .. } => expression,
+                    _ => continue,
+                };
+                if recurses(value) {
+                    return true;
+                }
+                // A `return` on any path is a way out
+                if matches!(statement, Statement::Return { .. }) || may_return(value) {
+                    return false;
+                }
+            }
+            expression.as_deref().is_some_and(recurses)
+        }
+        _ => expression
+            .children()
+            .into_iter()
+            .any(|child| matches!(child, Child::Expression(_, child) if recurses(child))),
+    }
+}
+
+/// Whether some path through `expression` reaches a `return` of the
+/// function it is in
+fn may_return(expression: &Expression) -> bool {
+    match expression {
+        Expression::Function { .. } | Expression::Fix { .. } | Expression::Spawn { .. } => false,
+        _ => expression.children().into_iter().any(|child| match child {
+            Child::Statement(Statement::Return { .. }) => true,
+            Child::Statement(
+                Statement::VariableDeclaration { value, .. }
+                | Statement::ConstantDeclaration { value, .. }
+                | Statement::Expression {
+                    expression: value, ..
+                },
+            ) => may_return(value),
+            Child::Statement(_) => false,
+            Child::Expression(_, child) => may_return(child),
+        }),
+    }
+}
+
+/// Whether evaluating `expression` always reaches a `return`, so that its own
+/// value is never used
+fn always_returns(expression: &Expression) -> bool {
+    match expression {
+        Expression::Block {
+            statements,
+            expression,
+            ..
+        } => {
+            statements.iter().any(|statement| match statement {
+                Statement::Return { .. } => true,
+                Statement::Expression { expression, .. } => always_returns(expression),
+                _ => false,
+            }) || expression.as_deref().is_some_and(always_returns)
+        }
+        Expression::If {
+            then_branch,
+            else_branch: Some(else_branch),
+            ..
+        } => always_returns(then_branch) && always_returns(else_branch),
+        _ => false,
+    }
 }
 
 impl Default for TypeChecker {
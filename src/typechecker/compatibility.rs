@@ -124,8 +124,9 @@ impl TypeCompatibility {
     /// Check if two types are compatible
     pub fn types_compatible(t1: &Type, t2: &Type) -> bool {
         match (t1, t2) {
-            // Unknown types are compatible with anything
+            // Unknown and dynamic types are compatible with anything
             (Type::Unknown, _) | (_, Type::Unknown) => true,
+            (Type::Dyn, _) | (_, Type::Dyn) => true,
 
             // Function types are compatible if their parameters and results are compatible
             (
@@ -144,6 +145,13 @@ impl TypeCompatibility {
                 Self::types_compatible(e1, e2)
             }
 
+            (Type::Handle { result: r1 }, Type::Handle { result: r2 }) => {
+                Self::types_compatible(r1, r2)
+            }
+            (Type::Channel { element: e1 }, Type::Channel { element: e2 }) => {
+                Self::types_compatible(e1, e2)
+            }
+
             // Pair types are compatible if their first and second types are compatible
             (
                 Type::Pair {
@@ -156,6 +164,15 @@ impl TypeCompatibility {
                 },
             ) => Self::types_compatible(f1, f2) && Self::types_compatible(s1, s2),
 
+            // Tuple types are compatible if they have the same arity and compatible elements
+            (Type::Tuple { elements: e1 }, Type::Tuple { elements: e2 }) => {
+                e1.len() == e2.len()
+                    && e1
+                        .iter()
+                        .zip(e2.iter())
+                        .all(|(a, b)| Self::types_compatible(a, b))
+            }
+
             // Sum types are compatible if their left and right types are compatible
             (
                 Type::Sum {
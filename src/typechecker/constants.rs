@@ -0,0 +1,190 @@
+//! Evaluation of `const` declarations while a program is checked.
+//!
+//! Only a small part of the language is constant: literals, earlier
+//! constants, arithmetic, comparisons, boolean logic, string concatenation,
+//! `if` and type ascriptions. Anything else, such as a call, is reported as
+//! `NotConstant`.
+
+use crate::ast::{BinaryOperator, Expression, Spanned, UnaryOperator};
+use crate::lexer::tokens::Span;
+use crate::typechecker::{Type, TypeError, TypeResult};
+use std::collections::HashMap;
+
+/// The value of a constant, known before the program runs
+#[derive(Debug, Clone, PartialEq)]
+pub enum Constant {
+    Int(i64),
+    Bool(bool),
+    String(String),
+}
+
+impl Constant {
+    pub fn ty(&self) -> Type {
+        match self {
+            Constant::Int(_) => Type::Int,
+            Constant::Bool(_) => Type::Bool,
+            Constant::String(_) => Type::String,
+        }
+    }
+}
+
+impl std::fmt::Display for Constant {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Constant::Int(value) => write!(f, "{}", value),
+            Constant::Bool(value) => write!(f, "{}", value),
+            Constant::String(value) => write!(f, "{:?}", value),
+        }
+    }
+}
+
+/// Evaluate `expression`, which may refer to the already evaluated `constants`
+pub fn evaluate(
+    expression: &Expression,
+    constants: &HashMap<String, Constant>,
+) -> TypeResult<Constant> {
+    match expression {
+        Expression::Number { value, .. } => Ok(Constant::Int(*value)),
+        Expression::Boolean { value, .. } => Ok(Constant::Bool(*value)),
+        Expression::String { value, .. } => Ok(Constant::String(value.clone())),
+        Expression::Identifier { name, span, .. } => constants.get(name).cloned().ok_or_else(|| {
+            not_constant(format!("'{}' is not a constant", name), span)
+        }),
+        Expression::TypeAscription { expression, .. } => evaluate(expression, constants),
+        Expression::UnaryOp {
+            operator,
+            operand,
+            span,
+        } => match (operator, evaluate(operand, constants)?) {
+            (UnaryOperator::Negate, Constant::Int(value)) => value
+                .checked_neg()
+                .map(Constant::Int)
+                .ok_or_else(|| not_constant(format!("-{} overflows", value), span)),
+            (UnaryOperator::LogicalNot, Constant::Bool(value)) => Ok(Constant::Bool(!value)),
+            (_, operand) => Err(not_constant(
+                format!("{:?} cannot be applied to {}", operator, operand),
+                span,
+            )),
+        },
+        Expression::BinaryOp {
+            left,
+            operator: BinaryOperator::LogicalAnd,
+            right,
+            span,
+        } => match evaluate(left, constants)? {
+            Constant::Bool(false) => Ok(Constant::Bool(false)),
+            Constant::Bool(true) => expect_bool(evaluate(right, constants)?, span),
+            other => expect_bool(other, span),
+        },
+        Expression::BinaryOp {
+            left,
+            operator: BinaryOperator::LogicalOr,
+            right,
+            span,
+        } => match evaluate(left, constants)? {
+            Constant::Bool(true) => Ok(Constant::Bool(true)),
+            Constant::Bool(false) => expect_bool(evaluate(right, constants)?, span),
+            other => expect_bool(other, span),
+        },
+        Expression::BinaryOp {
+            left,
+            operator,
+            right,
+            span,
+        } => binary(
+            operator,
+            evaluate(left, constants)?,
+            evaluate(right, constants)?,
+            span,
+        ),
+        Expression::If {
+            condition,
+            then_branch,
+            else_branch: Some(else_branch),
+            span,
+        } => match evaluate(condition, constants)? {
+            Constant::Bool(true) => evaluate(then_branch, constants),
+            Constant::Bool(false) => evaluate(else_branch, constants),
+            other => expect_bool(other, span),
+        },
+        Expression::Block {
+            statements,
+            expression: Some(expression),
+            ..
+        } if statements.is_empty() => evaluate(expression, constants),
+        other => Err(not_constant(
+            "only literals, constants and operators on them can be evaluated before the program runs"
+                .to_string(),
+            other.span(),
+        )),
+    }
+}
+
+fn binary(
+    operator: &BinaryOperator,
+    left: Constant,
+    right: Constant,
+    span: &Span,
+) -> TypeResult<Constant> {
+    use BinaryOperator::*;
+
+    let unsupported = || {
+        not_constant(
+            format!("{:?} cannot be applied to {} and {}", operator, left, right),
+            span,
+        )
+    };
+    let ordering = match (&left, &right) {
+        (Constant::Int(l), Constant::Int(r)) => Some(l.cmp(r)),
+        (Constant::String(l), Constant::String(r)) => Some(l.cmp(r)),
+        _ => None,
+    };
+    let result = match (operator, &left, &right) {
+        (Add | Subtract | Multiply | Divide, Constant::Int(l), Constant::Int(r)) => {
+            let (l, r) = (*l, *r);
+            let result = match operator {
+                Add => l.checked_add(r),
+                Subtract => l.checked_sub(r),
+                Multiply => l.checked_mul(r),
+                _ if r == 0 => return Err(not_constant("division by zero".to_string(), span)),
+                _ => l.checked_div(r),
+            };
+            result.map(Constant::Int).ok_or_else(|| {
+                not_constant(format!("{} {:?} {} overflows", l, operator, r), span)
+            })?
+        }
+        (Add | Append, Constant::String(l), Constant::String(r)) => {
+            Constant::String(format!("{}{}", l, r))
+        }
+        (Equal, _, _) => Constant::Bool(left == right),
+        (NotEqual, _, _) => Constant::Bool(left != right),
+        (LessThan | LessThanEqual | GreaterThan | GreaterThanEqual, _, _) => {
+            let ordering = ordering.ok_or_else(unsupported)?;
+            Constant::Bool(match operator {
+                LessThan => ordering.is_lt(),
+                LessThanEqual => ordering.is_le(),
+                GreaterThan => ordering.is_gt(),
+                _ => ordering.is_ge(),
+            })
+        }
+        _ => return Err(unsupported()),
+    };
+    Ok(result)
+}
+
+fn expect_bool(constant: Constant, span: &Span) -> TypeResult<Constant> {
+    match constant {
+        Constant::Bool(_) => Ok(constant),
+        other => Err(not_constant(
+            format!("expected a Bool, found {}", other),
+            span,
+        )),
+    }
+}
+
+fn not_constant(reason: String, span: &Span) -> TypeError {
+    TypeError::NotConstant {
+        reason,
+        span: span.clone(),
+    }
+}
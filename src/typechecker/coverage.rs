@@ -0,0 +1,91 @@
+//! Which integers the arms of a `case` on `Int` have matched so far, for
+//! finding overlapping arms and the values no arm matches.
+
+use crate::ast::Pattern;
+
+/// How much of a new arm's values earlier arms already match
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Overlap {
+    None,
+    Partial,
+    Full,
+}
+
+/// The integers matched so far, as sorted, disjoint and non-adjacent
+/// inclusive intervals
+#[derive(Debug, Default)]
+pub struct IntCoverage {
+    intervals: Vec<(i64, i64)>,
+}
+
+impl IntCoverage {
+    /// Add the integers from `low` to `high` inclusive, reporting how many of
+    /// them were already covered
+    pub fn add(&mut self, low: i64, high: i64) -> Overlap {
+        let overlapping: i128 = self
+            .intervals
+            .iter()
+            .map(|&(start, end)| (end.min(high) as i128 - start.max(low) as i128 + 1).max(0))
+            .sum();
+        let overlap = if overlapping == 0 {
+            Overlap::None
+        } else if overlapping == high as i128 - low as i128 + 1 {
+            Overlap::Full
+        } else {
+            Overlap::Partial
+        };
+
+        self.intervals.push((low, high));
+        self.intervals.sort_unstable();
+        let mut merged: Vec<(i64, i64)> = Vec::with_capacity(self.intervals.len());
+        for &(start, end) in &self.intervals {
+            match merged.last_mut() {
+                Some(last) if start as i128 <= last.1 as i128 + 1 => last.1 = last.1.max(end),
+                _ => merged.push((start, end)),
+            }
+        }
+        self.intervals = merged;
+        overlap
+    }
+
+    /// Whether every `Int` is covered
+    pub fn is_complete(&self) -> bool {
+        self.intervals == [(i64::MIN, i64::MAX)]
+    }
+
+    /// The integers not covered yet, written as patterns
+    pub fn missing(&self) -> Vec<String> {
+        let mut gaps = Vec::new();
+        let mut next = Some(i64::MIN);
+        for &(start, end) in &self.intervals {
+            if let Some(low) = next.filter(|&low| low < start) {
+                gaps.push((low, start - 1));
+            }
+            next = end.checked_add(1);
+        }
+        if let Some(low) = next {
+            gaps.push((low, i64::MAX));
+        }
+        gaps.into_iter()
+            .map(|(low, high)| match (low, high) {
+                _ if low == high => low.to_string(),
+                (i64::MIN, _) => format!("..{}", high + 1),
+                (_, i64::MAX) => format!("{}..", low),
+                _ => format!("{}..{}", low, high + 1),
+            })
+            .collect()
+    }
+}
+
+/// The inclusive interval an `Int` pattern matches, or `None` for other
+/// patterns. Ranges exclude their end and are never empty once parsed.
+pub fn bounds(pattern: &Pattern) -> Option<(i64, i64)> {
+    match pattern {
+        Pattern::Int { value, .. } => Some((*value, *value)),
+        Pattern::Range { start, end, .. } => Some((
+            start.unwrap_or(i64::MIN),
+            end.map_or(i64::MAX, |end| end - 1),
+        )),
+        _ => None,
+    }
+}
@@ -0,0 +1,26 @@
+//! Builtins and syntax that still work but have a better replacement. Using
+//! one is a warning, or an error under `--deny-deprecated`, so programs can
+//! move off it before it is removed in a later edition.
+
+/// A deprecated builtin or piece of syntax
+#[derive(Debug)]
+pub struct Deprecation {
+    /// The builtin or syntax as written
+    pub name: &'static str,
+    /// What to write instead
+    pub replacement: &'static str,
+}
+
+/// Everything deprecated; deprecating something is one more entry here and
+/// a call to `TypeChecker::warn_if_deprecated` where it is checked
+pub const DEPRECATIONS: &[Deprecation] = &[Deprecation {
+    name: "concat",
+    replacement: "a + b",
+}];
+
+/// The deprecation of `name`, if it is deprecated
+pub fn deprecation(name: &str) -> Option<&'static Deprecation> {
+    DEPRECATIONS
+        .iter()
+        .find(|deprecation| deprecation.name == name)
+}
@@ -1,9 +1,16 @@
+use crate::ast::NodeId;
+use crate::lexer::tokens::Span;
 use crate::typechecker::Type;
 use std::collections::HashMap;
 
 #[derive(Debug, Clone)]
 pub struct Environment {
     bindings: HashMap<String, Type>,
+    /// Where each `let` or `fn` in this scope was declared
+    declarations: HashMap<String, Span>,
+    /// The symbol each name in this scope is, if the program bound it, with
+    /// where it was bound
+    symbols: HashMap<String, (NodeId, Span)>,
     parent: Option<Box<Environment>>,
 }
 
@@ -12,6 +19,8 @@ impl Environment {
     pub fn new() -> Self {
         Self {
             bindings: HashMap::new(),
+            declarations: HashMap::new(),
+            symbols: HashMap::new(),
             parent: None,
         }
     }
@@ -20,21 +29,70 @@ impl Environment {
     pub fn with_parent(parent: Environment) -> Self {
         Self {
             bindings: HashMap::new(),
+            declarations: HashMap::new(),
+            symbols: HashMap::new(),
             parent: Some(Box::new(parent)),
         }
     }
 
     /// Bind a variable to a type in the current scope
     pub fn bind(&mut self, name: String, ty: Type) {
+        self.symbols.remove(&name);
         self.bindings.insert(name, ty);
     }
 
+    /// Remember that `name`, bound in the current scope at `span`, is the
+    /// symbol `id`
+    pub fn bind_symbol(&mut self, name: String, id: NodeId, span: Span) {
+        self.symbols.insert(name, (id, span));
+    }
+
+    /// The symbol `name` refers to, from the nearest scope binding it
+    pub fn symbol(&self, name: &str) -> Option<NodeId> {
+        self.binding_site(name).map(|(id, _)| *id)
+    }
+
+    /// Where the symbol `name` refers to was bound
+    pub fn definition(&self, name: &str) -> Option<&Span> {
+        self.binding_site(name).map(|(_, span)| span)
+    }
+
+    fn binding_site(&self, name: &str) -> Option<&(NodeId, Span)> {
+        if self.bindings.contains_key(name) {
+            self.symbols.get(name)
+        } else {
+            self.parent.as_ref()?.binding_site(name)
+        }
+    }
+
     /// Update a variable's type in the current scope (for recursive functions)
     /// This should only be called for variables that are already bound in the current scope
     pub fn update(&mut self, name: String, ty: Type) {
         self.bindings.insert(name, ty);
     }
 
+    /// Remember where a name in the current scope was declared
+    pub fn declare(&mut self, name: String, span: Span) {
+        self.declarations.insert(name, span);
+    }
+
+    /// Where the nearest enclosing scope, not the current one, declares `name`
+    pub fn outer_declaration(&self, name: &str) -> Option<&Span> {
+        let parent = self.parent.as_ref()?;
+        if parent.bindings.contains_key(name) {
+            parent.declarations.get(name)
+        } else {
+            parent.outer_declaration(name)
+        }
+    }
+
+    /// Remove a variable from the current scope, returning its type
+    pub fn unbind(&mut self, name: &str) -> Option<Type> {
+        self.declarations.remove(name);
+        self.symbols.remove(name);
+        self.bindings.remove(name)
+    }
+
     /// Look up a variable type, searching parent scopes if necessary
     pub fn lookup(&self, name: &str) -> Option<&Type> {
         self.bindings
@@ -67,9 +125,18 @@ impl Environment {
         all_bindings
     }
 
+    /// Names bound in any scope, for suggesting corrections to misspellings
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.bindings.keys().map(String::as_str).collect();
+        if let Some(parent) = &self.parent {
+            names.extend(parent.names());
+        }
+        names
+    }
+
     /// Enter a new scope (create a new environment with current as parent)
     pub fn enter_scope(&mut self) {
-        let current = std::mem::replace(self, Self::new());
+        let current = std::mem::take(self);
         *self = Self::with_parent(current);
     }
 
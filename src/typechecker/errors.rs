@@ -1,10 +1,12 @@
 use crate::lexer::tokens::Span;
-use crate::typechecker::{BinaryOp, Type};
+use crate::typechecker::{BinaryOp, Constraint, Type};
 
 #[derive(Debug, Clone)]
 pub enum TypeError {
     UndefinedVariable {
         name: String,
+        /// A similarly named binding, if there is one
+        suggestion: Option<String>,
         span: Span,
     },
     TypeMismatch {
@@ -27,17 +29,122 @@ pub enum TypeError {
         message: String,
         span: Span,
     },
+    TupleIndexOutOfRange {
+        index: usize,
+        tuple_type: Type,
+        span: Span,
+    },
+    UnsatisfiedConstraint {
+        ty: Type,
+        constraint: Constraint,
+        span: Span,
+    },
+    ImpreciseType {
+        name: String,
+        ty: Type,
+        span: Span,
+    },
+    /// IO at the top level of a module imported under `--pure-imports`
+    EffectAtImport {
+        effect: String,
+        span: Span,
+    },
+    /// A `const` whose value cannot be computed while checking
+    NotConstant {
+        reason: String,
+        span: Span,
+    },
+    /// A `case` on values without an arm for every possible value
+    NonExhaustiveCase {
+        /// Patterns that would complete the case
+        missing: Vec<String>,
+        span: Span,
+    },
+    /// A curried function called with more or fewer arguments than it
+    /// takes before giving the value wanted where the call is
+    ArityMismatch {
+        /// The function called, if it is called by name
+        function: Option<String>,
+        expected: usize,
+        found: usize,
+        span: Span,
+    },
+    /// A deprecated builtin or piece of syntax, when deprecations are denied
+    /// rather than warned about
+    Deprecated {
+        name: String,
+        replacement: String,
+        span: Span,
+    },
+    /// A call of a value whose type is not a function
+    NotCallable {
+        ty: Type,
+        /// The name it was called by, with where that name was bound if
+        /// the program bound it
+        binding: Option<Box<(String, Option<Span>)>>,
+        span: Span,
+    },
+}
+
+impl TypeError {
+    /// Stable code identifying the kind of error; see `--explain`
+    pub fn code(&self) -> &'static str {
+        match self {
+            TypeError::UndefinedVariable { .. } => "E0001",
+            TypeError::TypeMismatch { .. } => "E0002",
+            TypeError::InvalidBinaryOperation { .. } => "E0003",
+            TypeError::RedefinedVariable { .. } => "E0004",
+            TypeError::ImportError { .. } => "E0005",
+            TypeError::TupleIndexOutOfRange { .. } => "E0006",
+            TypeError::UnsatisfiedConstraint { .. } => "E0007",
+            TypeError::ImpreciseType { .. } => "E0008",
+            TypeError::EffectAtImport { .. } => "E0009",
+            TypeError::NotConstant { .. } => "E0010",
+            TypeError::NonExhaustiveCase { .. } => "E0011",
+            TypeError::ArityMismatch { .. } => "E0012",
+            TypeError::NotCallable { .. } => "E0013",
+            TypeError::Deprecated { .. } => "E0014",
+        }
+    }
+
+    /// Location of the code that caused the error
+    pub fn span(&self) -> &Span {
+        match self {
+            TypeError::UndefinedVariable { span, .. }
+            | TypeError::TypeMismatch { span, .. }
+            | TypeError::InvalidBinaryOperation { span, .. }
+            | TypeError::RedefinedVariable { span, .. }
+            | TypeError::ImportError { span, .. }
+            | TypeError::TupleIndexOutOfRange { span, .. }
+            | TypeError::UnsatisfiedConstraint { span, .. }
+            | TypeError::ImpreciseType { span, .. }
+            | TypeError::EffectAtImport { span, .. }
+            | TypeError::NotConstant { span, .. }
+            | TypeError::NonExhaustiveCase { span, .. }
+            | TypeError::ArityMismatch { span, .. }
+            | TypeError::NotCallable { span, .. }
+            | TypeError::Deprecated { span, .. } => span,
+        }
+    }
 }
 
 impl std::fmt::Display for TypeError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            TypeError::UndefinedVariable { name, span } => {
+            TypeError::UndefinedVariable {
+                name,
+                suggestion,
+                span,
+            } => {
                 write!(
                     f,
                     "Undefined variable '{}' at line {}, column {}",
                     name, span.line, span.column
-                )
+                )?;
+                if let Some(suggestion) = suggestion {
+                    write!(f, " (did you mean '{}'?)", suggestion)?;
+                }
+                Ok(())
             }
             TypeError::TypeMismatch {
                 expected,
@@ -48,7 +155,12 @@ impl std::fmt::Display for TypeError {
                     f,
                     "Type mismatch at line {}, column {}: expected '{}', found '{}'",
                     span.line, span.column, expected, found
-                )
+                )?;
+                // Large types are repeated aligned, with where they differ marked
+                if let Some((expected, found)) = expected.diff(found) {
+                    write!(f, "\n  expected: {}\n     found: {}", expected, found)?;
+                }
+                Ok(())
             }
             TypeError::InvalidBinaryOperation {
                 left,
@@ -60,7 +172,11 @@ impl std::fmt::Display for TypeError {
                     f,
                     "Invalid binary operation at line {}, column {}: '{}' {:?} '{}'",
                     span.line, span.column, left, op, right
-                )
+                )?;
+                if let Some(hint) = operation_hint(left, op, right) {
+                    write!(f, " ({})", hint)?;
+                }
+                Ok(())
             }
             TypeError::RedefinedVariable { name, span } => {
                 write!(
@@ -80,6 +196,112 @@ impl std::fmt::Display for TypeError {
                     span.line, span.column, message, path
                 )
             }
+            TypeError::TupleIndexOutOfRange {
+                index,
+                tuple_type,
+                span,
+            } => {
+                write!(
+                    f,
+                    "Tuple index {} out of range at line {}, column {}: '{}' has no such element",
+                    index, span.line, span.column, tuple_type
+                )
+            }
+            TypeError::UnsatisfiedConstraint {
+                ty,
+                constraint,
+                span,
+            } => {
+                write!(
+                    f,
+                    "Type '{}' does not satisfy '{}' at line {}, column {}",
+                    ty, constraint, span.line, span.column
+                )
+            }
+            TypeError::ImpreciseType { name, ty, span } => {
+                write!(
+                    f,
+                    "Type of '{}' could not be fully inferred at line {}, column {}: '{}' (add a type annotation, or write 'Dyn' to opt out of checking)",
+                    name, span.line, span.column, ty
+                )
+            }
+            TypeError::EffectAtImport { effect, span } => {
+                write!(
+                    f,
+                    "'{}' performs IO at the top level of an imported module at line {}, column {} (move it into a function)",
+                    effect, span.line, span.column
+                )
+            }
+            TypeError::NotConstant { reason, span } => {
+                write!(
+                    f,
+                    "Not a constant expression at line {}, column {}: {}",
+                    span.line, span.column, reason
+                )
+            }
+            TypeError::NonExhaustiveCase { missing, span } => {
+                write!(
+                    f,
+                    "Case at line {}, column {} does not cover {} (add the missing arms or a '_' arm)",
+                    span.line,
+                    span.column,
+                    missing.join(", ")
+                )
+            }
+            TypeError::ArityMismatch {
+                function,
+                expected,
+                found,
+                span,
+            } => {
+                let function = match function {
+                    Some(name) => format!("'{}'", name),
+                    None => "the function".to_string(),
+                };
+                write!(
+                    f,
+                    "Wrong number of arguments at line {}, column {}: {} expected {} {}, found {}",
+                    span.line,
+                    span.column,
+                    function,
+                    expected,
+                    if *expected == 1 {
+                        "argument"
+                    } else {
+                        "arguments"
+                    },
+                    found
+                )
+            }
+            TypeError::Deprecated {
+                name,
+                replacement,
+                span,
+            } => write!(
+                f,
+                "'{}' at line {}, column {} is deprecated; use {} instead (denied by --deny-deprecated)",
+                name, span.line, span.column, replacement
+            ),
+            TypeError::NotCallable { ty, binding, span } => {
+                write!(
+                    f,
+                    "Attempt to call non-function value at line {}, column {}: ",
+                    span.line, span.column
+                )?;
+                match binding.as_deref() {
+                    Some((name, _)) => write!(f, "'{}' is", name)?,
+                    None => write!(f, "it is")?,
+                }
+                write!(f, " of type '{}'", ty)?;
+                if let Some((_, Some(definition))) = binding.as_deref() {
+                    write!(
+                        f,
+                        ", declared at line {}, column {}",
+                        definition.line, definition.column
+                    )?;
+                }
+                Ok(())
+            }
         }
     }
 }
@@ -87,3 +309,41 @@ impl std::fmt::Display for TypeError {
 impl std::error::Error for TypeError {}
 
 pub type TypeResult<T> = Result<T, TypeError>;
+
+/// How to fix a logical operation on values that are not Bools, or an
+/// operation mixing a `String` with another type. Values are never truthy and
+/// never converted implicitly, apart from `Int` widening to `BigInt`.
+fn operation_hint(left: &Type, op: &BinaryOp, right: &Type) -> Option<String> {
+    if matches!(op, BinaryOp::LogicalAnd | BinaryOp::LogicalOr) {
+        return Some(
+            "'&&' and '||' take Bools; other values are not treated as true or false, so compare them explicitly, as in 'n != 0' or 'xs != []'"
+                .to_string(),
+        );
+    }
+    let other = match (left, right) {
+        (Type::String, other) | (other, Type::String) if other != &Type::String => other,
+        _ => return None,
+    };
+    match (op, other) {
+        (BinaryOp::Add | BinaryOp::Append, other) if other.satisfies(Constraint::Show) => {
+            Some(format!(
+                "convert the '{}' with toString(...) to join it to the String",
+                other
+            ))
+        }
+        (
+            BinaryOp::Add
+            | BinaryOp::Subtract
+            | BinaryOp::Multiply
+            | BinaryOp::Divide
+            | BinaryOp::LessThan
+            | BinaryOp::LessThanEqual
+            | BinaryOp::GreaterThan
+            | BinaryOp::GreaterThanEqual,
+            Type::Int | Type::BigInt,
+        ) => Some(
+            "read the String with toInt(...), which gives inl(number) or inr(reason)".to_string(),
+        ),
+        _ => None,
+    }
+}
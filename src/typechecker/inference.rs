@@ -1,13 +1,24 @@
 use crate::ast::{Expression, Statement};
+use crate::lexer::edition::Edition;
 use crate::typechecker::{Environment, Type, TypeResult};
 
 pub struct TypeInference {
     environment: Environment,
+    edition: Edition,
 }
 
 impl TypeInference {
     pub fn new(environment: Environment) -> Self {
-        Self { environment }
+        Self {
+            environment,
+            edition: Edition::default(),
+        }
+    }
+
+    /// Infer types by the rules of `edition`
+    pub fn with_edition(mut self, edition: Edition) -> Self {
+        self.edition = edition;
+        self
     }
 
     /// Infer the parameter type based on how it's used in the function body
@@ -42,16 +53,16 @@ impl TypeInference {
             Expression::FunctionCall {
                 function, argument, ..
             } => {
-                if let Expression::Identifier { name, .. } = function.as_ref() {
-                    if name == param {
-                        // Parameter is called as a function
-                        // Try to infer the argument type
-                        let arg_type = self.get_expression_type_hint(argument);
-                        return Some(Type::Function {
-                            param: Box::new(arg_type),
-                            result: Box::new(Type::Unknown),
-                        });
-                    }
+                if let Expression::Identifier { name, .. } = function.as_ref()
+                    && name == param
+                {
+                    // Parameter is called as a function
+                    // Try to infer the argument type
+                    let arg_type = self.get_expression_type_hint(argument);
+                    return Some(Type::Function {
+                        param: Box::new(arg_type),
+                        result: Box::new(Type::Unknown),
+                    });
                 }
                 // Recursively check sub-expressions
                 if let Some(func_type) = self.analyze_function_usage(param, function) {
@@ -73,10 +84,9 @@ impl TypeInference {
                     if let Statement::Expression {
                         expression: expr, ..
                     } = stmt
+                        && let Some(func_type) = self.analyze_function_usage(param, expr)
                     {
-                        if let Some(func_type) = self.analyze_function_usage(param, expr) {
-                            return Some(func_type);
-                        }
+                        return Some(func_type);
                     }
                 }
                 if let Some(expr) = expression {
@@ -92,10 +102,10 @@ impl TypeInference {
     pub fn parameter_used_as_function(&self, param: &str, expr: &Expression) -> bool {
         match expr {
             Expression::FunctionCall { function, .. } => {
-                if let Expression::Identifier { name, .. } = function.as_ref() {
-                    if name == param {
-                        return true;
-                    }
+                if let Expression::Identifier { name, .. } = function.as_ref()
+                    && name == param
+                {
+                    return true;
                 }
                 false
             }
@@ -109,10 +119,9 @@ impl TypeInference {
                     if let Statement::Expression {
                         expression: expr, ..
                     } = stmt
+                        && self.parameter_used_as_function(param, expr)
                     {
-                        if self.parameter_used_as_function(param, expr) {
-                            return true;
-                        }
+                        return true;
                     }
                 }
                 if let Some(expr) = expression {
@@ -163,15 +172,15 @@ impl TypeInference {
                 let right_pair_usage = self.analyze_parameter_usage(param, right);
 
                 // If we find pair usage, prioritize it
-                if let Some(ref pair_type) = left_pair_usage {
-                    if matches!(pair_type, Type::Pair { .. }) {
-                        return left_pair_usage;
-                    }
+                if let Some(ref pair_type) = left_pair_usage
+                    && matches!(pair_type, Type::Pair { .. })
+                {
+                    return left_pair_usage;
                 }
-                if let Some(ref pair_type) = right_pair_usage {
-                    if matches!(pair_type, Type::Pair { .. }) {
-                        return right_pair_usage;
-                    }
+                if let Some(ref pair_type) = right_pair_usage
+                    && matches!(pair_type, Type::Pair { .. })
+                {
+                    return right_pair_usage;
                 }
 
                 // Check if parameter is used in arithmetic operations
@@ -202,13 +211,27 @@ impl TypeInference {
                     }
                 }
 
+                // A parameter used directly as an operand of `&&` or `||` is a
+                // Bool, unless the edition lets any value be true or false
+                if !self.edition.has_truthiness()
+                    && matches!(
+                    operator,
+                    crate::ast::BinaryOperator::LogicalAnd | crate::ast::BinaryOperator::LogicalOr
+                ) && [left, right].iter().any(|side| {
+                    matches!(side.as_ref(), Expression::Identifier { name, .. } if name == param)
+                }) {
+                    return Some(Type::Bool);
+                }
+
                 if left_uses_param || right_uses_param {
                     match operator {
                         crate::ast::BinaryOperator::Add
                         | crate::ast::BinaryOperator::Subtract
                         | crate::ast::BinaryOperator::Multiply
                         | crate::ast::BinaryOperator::Divide => Some(Type::Int),
-                        _ => None,
+                        // Comparing tells nothing itself, but an operand
+                        // such as `x * 2` may
+                        _ => left_pair_usage.or(right_pair_usage),
                     }
                 } else {
                     // Return any other inferred type
@@ -219,26 +242,26 @@ impl TypeInference {
                 function, argument, ..
             } => {
                 // If parameter is used as a function, infer it's a function type
-                if let Expression::Identifier { name, .. } = function.as_ref() {
-                    if name == param {
-                        let arg_type = self.get_expression_type_hint(argument);
-                        return Some(Type::Function {
-                            param: Box::new(arg_type),
-                            result: Box::new(Type::Unknown),
-                        });
-                    }
+                if let Expression::Identifier { name, .. } = function.as_ref()
+                    && name == param
+                {
+                    let arg_type = self.get_expression_type_hint(argument);
+                    return Some(Type::Function {
+                        param: Box::new(arg_type),
+                        result: Box::new(Type::Unknown),
+                    });
                 }
 
                 // Special case: if argument is tail(param), infer function takes lists
-                if let Expression::TailProjection { list, .. } = argument.as_ref() {
-                    if self.expression_uses_parameter(param, list) {
-                        return Some(Type::Function {
-                            param: Box::new(Type::List {
-                                element: Box::new(Type::Unknown),
-                            }),
-                            result: Box::new(Type::Unknown),
-                        });
-                    }
+                if let Expression::TailProjection { list, .. } = argument.as_ref()
+                    && self.expression_uses_parameter(param, list)
+                {
+                    return Some(Type::Function {
+                        param: Box::new(Type::List {
+                            element: Box::new(Type::Unknown),
+                        }),
+                        result: Box::new(Type::Unknown),
+                    });
                 }
 
                 // Recursively check sub-expressions
@@ -309,6 +332,19 @@ impl TypeInference {
 
                 left_usage.or(right_usage)
             }
+            Expression::If {
+                condition,
+                then_branch,
+                else_branch,
+                ..
+            } => self
+                .analyze_parameter_usage(param, condition)
+                .or_else(|| self.analyze_parameter_usage(param, then_branch))
+                .or_else(|| {
+                    else_branch
+                        .as_ref()
+                        .and_then(|branch| self.analyze_parameter_usage(param, branch))
+                }),
             Expression::Block {
                 statements,
                 expression,
@@ -362,6 +398,14 @@ impl TypeInference {
                     || self.expression_uses_parameter(param, left_body)
                     || self.expression_uses_parameter(param, right_body)
             }
+            Expression::Match {
+                expression, arms, ..
+            } => {
+                self.expression_uses_parameter(param, expression)
+                    || arms
+                        .iter()
+                        .any(|arm| self.expression_uses_parameter(param, &arm.body))
+            }
             Expression::Block {
                 statements,
                 expression,
@@ -372,7 +416,7 @@ impl TypeInference {
                     .any(|stmt| self.statement_uses_parameter(param, stmt))
                     || expression
                         .as_ref()
-                        .map_or(false, |expr| self.expression_uses_parameter(param, expr))
+                        .is_some_and(|expr| self.expression_uses_parameter(param, expr))
             }
             _ => false,
         }
@@ -381,16 +425,18 @@ impl TypeInference {
     /// Check if a statement uses the given parameter
     pub fn statement_uses_parameter(&self, param: &str, stmt: &Statement) -> bool {
         match stmt {
-            Statement::VariableDeclaration { value, .. } => {
+            Statement::VariableDeclaration { value, .. }
+            | Statement::ConstantDeclaration { value, .. } => {
                 self.expression_uses_parameter(param, value)
             }
             Statement::FunctionDeclaration { body, .. } => {
                 self.expression_uses_parameter(param, body)
             }
-            Statement::Import { .. } => false,
-            Statement::Expression { expression, .. } => {
-                self.expression_uses_parameter(param, expression)
-            }
+            Statement::Import { .. } | Statement::EnumDeclaration { .. } => false,
+            Statement::Expression { expression, .. }
+            | Statement::Return {
+                value: expression, ..
+            } => self.expression_uses_parameter(param, expression),
         }
     }
 
@@ -401,16 +447,18 @@ impl TypeInference {
         stmt: &Statement,
     ) -> Option<Type> {
         match stmt {
-            Statement::VariableDeclaration { value, .. } => {
+            Statement::VariableDeclaration { value, .. }
+            | Statement::ConstantDeclaration { value, .. } => {
                 self.analyze_parameter_usage(param, value)
             }
             Statement::FunctionDeclaration { body, .. } => {
                 self.analyze_parameter_usage(param, body)
             }
-            Statement::Import { .. } => None,
-            Statement::Expression { expression, .. } => {
-                self.analyze_parameter_usage(param, expression)
-            }
+            Statement::Import { .. } | Statement::EnumDeclaration { .. } => None,
+            Statement::Expression { expression, .. }
+            | Statement::Return {
+                value: expression, ..
+            } => self.analyze_parameter_usage(param, expression),
         }
     }
 }
@@ -5,8 +5,7 @@ use crate::typechecker::TypeChecker;
 #[test]
 fn test_full_pipeline_with_typechecker() {
     // Test 1: Simple number expression
-    let mut tokenizer = Tokenizer::new("");
-    let tokens = tokenizer.tokenize("42;").unwrap();
+    let tokens = Tokenizer::new("42;").tokenize().unwrap();
     let mut parser = Parser::new(tokens);
     let program = parser.parse().unwrap();
     let mut type_checker = TypeChecker::new();
@@ -16,8 +15,7 @@ fn test_full_pipeline_with_typechecker() {
     println!("Test 1 - Type checked: {:#?}", typed_program);
 
     // Test 2: Variable declaration with type inference
-    let mut tokenizer = Tokenizer::new("");
-    let tokens = tokenizer.tokenize("let x = 42;").unwrap();
+    let tokens = Tokenizer::new("let x = 42;").tokenize().unwrap();
     let mut parser = Parser::new(tokens);
     let program = parser.parse().unwrap();
     let mut type_checker = TypeChecker::new();
@@ -27,8 +25,7 @@ fn test_full_pipeline_with_typechecker() {
     println!("Test 2 - Type checked: {:#?}", typed_program);
 
     // Test 3: Variable usage after declaration
-    let mut tokenizer = Tokenizer::new("");
-    let tokens = tokenizer.tokenize("let x = 42; x;").unwrap();
+    let tokens = Tokenizer::new("let x = 42; x;").tokenize().unwrap();
     let mut parser = Parser::new(tokens);
     let program = parser.parse().unwrap();
     let mut type_checker = TypeChecker::new();
@@ -38,8 +35,7 @@ fn test_full_pipeline_with_typechecker() {
     println!("Test 3 - Type checked: {:#?}", typed_program);
 
     // Test 4: Type error - undefined variable
-    let mut tokenizer = Tokenizer::new("");
-    let tokens = tokenizer.tokenize("undefined_var;").unwrap();
+    let tokens = Tokenizer::new("undefined_var;").tokenize().unwrap();
     let mut parser = Parser::new(tokens);
     let program = parser.parse().unwrap();
     let mut type_checker = TypeChecker::new();
@@ -52,8 +48,7 @@ fn test_full_pipeline_with_typechecker() {
 #[test]
 fn test_list_typechecking_integration() {
     // Test 1: Empty list type checking
-    let mut tokenizer = Tokenizer::new("");
-    let tokens = tokenizer.tokenize("[];").unwrap();
+    let tokens = Tokenizer::new("[];").tokenize().unwrap();
     let mut parser = Parser::new(tokens);
     let program = parser.parse().unwrap();
     let mut type_checker = TypeChecker::new();
@@ -63,8 +58,7 @@ fn test_list_typechecking_integration() {
     println!("List Type Test 1 - Empty list: {:#?}", typed_program);
 
     // Test 2: Homogeneous integer list
-    let mut tokenizer = Tokenizer::new("");
-    let tokens = tokenizer.tokenize("[1, 2, 3];").unwrap();
+    let tokens = Tokenizer::new("[1, 2, 3];").tokenize().unwrap();
     let mut parser = Parser::new(tokens);
     let program = parser.parse().unwrap();
     let mut type_checker = TypeChecker::new();
@@ -74,8 +68,7 @@ fn test_list_typechecking_integration() {
     println!("List Type Test 2 - Integer list: {:#?}", typed_program);
 
     // Test 3: Homogeneous boolean list
-    let mut tokenizer = Tokenizer::new("");
-    let tokens = tokenizer.tokenize("[true, false, true];").unwrap();
+    let tokens = Tokenizer::new("[true, false, true];").tokenize().unwrap();
     let mut parser = Parser::new(tokens);
     let program = parser.parse().unwrap();
     let mut type_checker = TypeChecker::new();
@@ -85,8 +78,7 @@ fn test_list_typechecking_integration() {
     println!("List Type Test 3 - Boolean list: {:#?}", typed_program);
 
     // Test 4: List variable declaration with explicit type
-    let mut tokenizer = Tokenizer::new("");
-    let tokens = tokenizer.tokenize("let numbers: List Int = [1, 2, 3];").unwrap();
+    let tokens = Tokenizer::new("let numbers: List Int = [1, 2, 3];").tokenize().unwrap();
     let mut parser = Parser::new(tokens);
     let program = parser.parse().unwrap();
     let mut type_checker = TypeChecker::new();
@@ -96,8 +88,7 @@ fn test_list_typechecking_integration() {
     println!("List Type Test 4 - Explicit type: {:#?}", typed_program);
 
     // Test 5: Type error - heterogeneous list
-    let mut tokenizer = Tokenizer::new("");
-    let tokens = tokenizer.tokenize("[1, true, 3];").unwrap();
+    let tokens = Tokenizer::new("[1, true, 3];").tokenize().unwrap();
     let mut parser = Parser::new(tokens);
     let program = parser.parse().unwrap();
     let mut type_checker = TypeChecker::new();
@@ -107,8 +98,7 @@ fn test_list_typechecking_integration() {
     println!("List Type Test 5 - Type error (as expected): {:?}", result.unwrap_err());
 
     // Test 6: Empty list with type annotation
-    let mut tokenizer = Tokenizer::new("");
-    let tokens = tokenizer.tokenize("let empty: List Bool = [];").unwrap();
+    let tokens = Tokenizer::new("let empty: List Bool = [];").tokenize().unwrap();
     let mut parser = Parser::new(tokens);
     let program = parser.parse().unwrap();
     let mut type_checker = TypeChecker::new();
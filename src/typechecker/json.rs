@@ -0,0 +1,210 @@
+//! JSON serialization of the typed AST, used by `--emit=typed-ast-json`
+
+use super::constants::Constant;
+use super::types::{Type, TypedExpression, TypedProgram, TypedStatement};
+use crate::lexer::tokens::Span;
+
+impl TypedProgram {
+    /// Serialize the program, its statements, spans and resolved types as JSON
+    pub fn to_json(&self) -> String {
+        let statements: Vec<String> = self.statements.iter().map(statement_json).collect();
+        format!(
+            "{{\"statements\":[{}],\"span\":{}}}",
+            statements.join(","),
+            span_json(&self.span)
+        )
+    }
+}
+
+fn statement_json(statement: &TypedStatement) -> String {
+    match statement {
+        TypedStatement::VariableDeclaration {
+            name,
+            ty,
+            value,
+            span,
+        } => format!(
+            "{{\"kind\":\"VariableDeclaration\",\"name\":{},\"type\":{},\"value\":{},\"span\":{}}}",
+            string_json(name),
+            type_json(ty),
+            expression_json(value),
+            span_json(span)
+        ),
+        TypedStatement::EnumDeclaration {
+            name,
+            variants,
+            span,
+        } => format!(
+            "{{\"kind\":\"EnumDeclaration\",\"name\":{},\"variants\":{},\"span\":{}}}",
+            string_json(name),
+            strings_json(variants),
+            span_json(span)
+        ),
+        TypedStatement::ConstantDeclaration {
+            name,
+            ty,
+            value,
+            constant,
+            span,
+        } => format!(
+            "{{\"kind\":\"ConstantDeclaration\",\"name\":{},\"type\":{},\"value\":{},\"constant\":{},\"span\":{}}}",
+            string_json(name),
+            type_json(ty),
+            expression_json(value),
+            constant_json(constant),
+            span_json(span)
+        ),
+        TypedStatement::FunctionDeclaration {
+            name,
+            param,
+            param_type,
+            return_type,
+            body,
+            span,
+        } => format!(
+            "{{\"kind\":\"FunctionDeclaration\",\"name\":{},\"param\":{},\"param_type\":{},\"return_type\":{},\"body\":{},\"span\":{}}}",
+            string_json(name),
+            string_json(param),
+            type_json(param_type),
+            type_json(return_type),
+            expression_json(body),
+            span_json(span)
+        ),
+        TypedStatement::Import {
+            path,
+            alias,
+            exported,
+            span,
+        } => format!(
+            "{{\"kind\":\"Import\",\"path\":{},\"alias\":{},\"exported\":{},\"span\":{}}}",
+            string_json(path),
+            alias.as_deref().map_or("null".to_string(), string_json),
+            exported,
+            span_json(span)
+        ),
+        TypedStatement::Expression { expression, span } => format!(
+            "{{\"kind\":\"Expression\",\"expression\":{},\"span\":{}}}",
+            expression_json(expression),
+            span_json(span)
+        ),
+        TypedStatement::Return { value, span } => format!(
+            "{{\"kind\":\"Return\",\"value\":{},\"span\":{}}}",
+            expression_json(value),
+            span_json(span)
+        ),
+    }
+}
+
+fn expression_json(expression: &TypedExpression) -> String {
+    format!(
+        "{{\"type\":{},\"span\":{}}}",
+        type_json(&expression.ty),
+        span_json(&expression.span)
+    )
+}
+
+/// Types are written structurally, with the readable form under `display`
+fn type_json(ty: &Type) -> String {
+    let fields = match ty {
+        Type::Function { param, result } => format!(
+            ",\"param\":{},\"result\":{}",
+            type_json(param),
+            type_json(result)
+        ),
+        Type::Pair { first, second } => format!(
+            ",\"first\":{},\"second\":{}",
+            type_json(first),
+            type_json(second)
+        ),
+        Type::Tuple { elements } => {
+            let elements: Vec<String> = elements.iter().map(type_json).collect();
+            format!(",\"elements\":[{}]", elements.join(","))
+        }
+        Type::List { element } | Type::Channel { element } => {
+            format!(",\"element\":{}", type_json(element))
+        }
+        Type::Handle { result } => format!(",\"result\":{}", type_json(result)),
+        Type::Sum { left, right } => format!(
+            ",\"left\":{},\"right\":{}",
+            type_json(left),
+            type_json(right)
+        ),
+        Type::Recursive { inner } => format!(",\"inner\":{}", type_json(inner)),
+        Type::Enum { name } => format!(",\"name\":{}", string_json(name)),
+        _ => String::new(),
+    };
+    format!(
+        "{{\"kind\":{},\"display\":{}{}}}",
+        string_json(type_kind(ty)),
+        string_json(&ty.to_string()),
+        fields
+    )
+}
+
+fn type_kind(ty: &Type) -> &'static str {
+    match ty {
+        Type::Int => "Int",
+        Type::BigInt => "BigInt",
+        Type::Bool => "Bool",
+        Type::String => "String",
+        Type::Bytes => "Bytes",
+        Type::Unit => "Unit",
+        Type::Function { .. } => "Function",
+        Type::Pair { .. } => "Pair",
+        Type::Tuple { .. } => "Tuple",
+        Type::List { .. } => "List",
+        Type::Handle { .. } => "Handle",
+        Type::Channel { .. } => "Channel",
+        Type::Sum { .. } => "Sum",
+        Type::Recursive { .. } => "Recursive",
+        Type::Enum { .. } => "Enum",
+        Type::Unknown => "Unknown",
+        Type::Dyn => "Dyn",
+        Type::Error => "Error",
+    }
+}
+
+fn span_json(span: &Span) -> String {
+    format!(
+        "{{\"start\":{},\"end\":{},\"line\":{},\"column\":{},\"end_line\":{},\"end_column\":{},\"char_start\":{},\"char_end\":{}}}",
+        span.start,
+        span.end,
+        span.line,
+        span.column,
+        span.end_line,
+        span.end_column,
+        span.char_start,
+        span.char_end
+    )
+}
+
+fn constant_json(constant: &Constant) -> String {
+    match constant {
+        Constant::Int(value) => value.to_string(),
+        Constant::Bool(value) => value.to_string(),
+        Constant::String(value) => string_json(value),
+    }
+}
+
+fn strings_json(values: &[String]) -> String {
+    let values: Vec<String> = values.iter().map(|value| string_json(value)).collect();
+    format!("[{}]", values.join(","))
+}
+
+fn string_json(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len() + 2);
+    escaped.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
@@ -0,0 +1,150 @@
+//! Lockfiles: content hashes of the module files a program imports, so that
+//! a later run with `--locked` can tell which of them changed. Each line is a
+//! SHA-256 hash and a path, as `sha256sum` writes them, so a lockfile can
+//! also be checked with `sha256sum -c`.
+
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+
+/// The lockfile of a program: `main.lock` next to `main.corr`, so programs
+/// sharing a directory keep separate lockfiles
+pub fn lockfile_path(program: &Path) -> PathBuf {
+    program.with_extension("lock")
+}
+
+const HEADER: &str = "# Hashes of the modules this program imports, checked by --locked\n";
+
+/// SHA-256 of a module's source, in hex
+pub fn hash(content: &str) -> String {
+    Sha256::digest(content.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Hashes of module files by their path relative to the program
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Lockfile {
+    modules: BTreeMap<String, String>,
+}
+
+/// A way the modules a program imports differ from its lockfile
+#[derive(Debug, Clone, PartialEq)]
+pub enum LockMismatch {
+    /// The file's content is not what was recorded
+    Changed(String),
+    /// The file is imported but was not recorded
+    Added(String),
+    /// The file was recorded but is no longer imported
+    Removed(String),
+}
+
+impl Lockfile {
+    /// The lockfile for the module files a program in `directory` read,
+    /// given as the hashes collected by the module loader
+    pub fn from_modules(directory: &Path, hashes: &BTreeMap<PathBuf, String>) -> Self {
+        let modules = hashes
+            .iter()
+            .map(|(path, hash)| (relative_path(directory, path), hash.clone()))
+            .collect();
+        Self { modules }
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut modules = BTreeMap::new();
+        for (number, line) in text.lines().enumerate() {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            match line.split_once("  ") {
+                Some((hash, path)) if hash.len() == 64 && !path.is_empty() => {
+                    modules.insert(path.to_string(), hash.to_string());
+                }
+                _ => return Err(format!("malformed line {}", number + 1)),
+            }
+        }
+        Ok(Self { modules })
+    }
+
+    pub fn render(&self) -> String {
+        let mut text = HEADER.to_string();
+        for (path, hash) in &self.modules {
+            text.push_str(&format!("{}  {}\n", hash, path));
+        }
+        text
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.modules.is_empty()
+    }
+
+    /// How the modules in `current` differ from this recorded lockfile, in
+    /// path order
+    pub fn mismatches(&self, current: &Lockfile) -> Vec<LockMismatch> {
+        let mut paths: Vec<&String> = self.modules.keys().chain(current.modules.keys()).collect();
+        paths.sort();
+        paths.dedup();
+        paths
+            .into_iter()
+            .filter_map(
+                |path| match (self.modules.get(path), current.modules.get(path)) {
+                    (Some(recorded), Some(hash)) if recorded != hash => {
+                        Some(LockMismatch::Changed(path.clone()))
+                    }
+                    (None, Some(_)) => Some(LockMismatch::Added(path.clone())),
+                    (Some(_), None) => Some(LockMismatch::Removed(path.clone())),
+                    _ => None,
+                },
+            )
+            .collect()
+    }
+}
+
+impl fmt::Display for LockMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LockMismatch::Changed(path) => write!(f, "{} has changed", path),
+            LockMismatch::Added(path) => write!(f, "{} is imported but not locked", path),
+            LockMismatch::Removed(path) => write!(f, "{} is locked but no longer imported", path),
+        }
+    }
+}
+
+/// `path` as seen from `directory`, written with `/`, such as
+/// `../lib/math.corr`, wherever the two are relative to
+fn relative_path(directory: &Path, path: &Path) -> String {
+    let from = parts(&absolute(directory));
+    let to = parts(&absolute(path));
+    let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+    let mut relative = vec!["..".to_string(); from.len() - common];
+    relative.extend_from_slice(&to[common..]);
+    relative.join("/")
+}
+
+/// `path` joined onto the current directory, if it is relative
+fn absolute(path: &Path) -> PathBuf {
+    // An empty path is the current directory, which `std::path::absolute` refuses
+    let path = if path.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        path
+    };
+    std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// The steps of a path, with `.` and `dir/..` steps removed
+fn parts(path: &Path) -> Vec<String> {
+    let mut parts: Vec<String> = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir | Component::RootDir => {}
+            Component::ParentDir if parts.last().is_some_and(|last| last != "..") => {
+                parts.pop();
+            }
+            other => parts.push(other.as_os_str().to_string_lossy().into_owned()),
+        }
+    }
+    parts
+}
@@ -1,10 +1,18 @@
 pub mod checker;
 pub mod compatibility;
+pub mod constants;
+pub mod coverage;
+pub mod deprecations;
 pub mod environment;
 pub mod errors;
 pub mod inference;
+pub mod json;
+pub mod lockfile;
 pub mod module_loader;
+pub mod suggestions;
+pub mod symbols;
 pub mod types;
+pub mod warnings;
 
 #[cfg(test)]
 mod tests;
@@ -14,8 +22,11 @@ mod integration_tests;
 
 pub use checker::TypeChecker;
 pub use compatibility::TypeCompatibility;
+pub use constants::Constant;
 pub use environment::Environment;
 pub use errors::{TypeError, TypeResult};
 pub use inference::TypeInference;
 pub use module_loader::ModuleLoader;
+pub use symbols::{Symbol, SymbolTable};
 pub use types::*;
+pub use warnings::TypeWarning;
@@ -1,15 +1,69 @@
+use crate::lexer::edition::Edition;
 use crate::lexer::tokens::Span;
 use crate::typechecker::{Type, TypeError, TypeResult};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
 
+/// File that holds a directory module's own exports
+pub const MODULE_DIRECTORY_FILE: &str = "mod.corr";
+
+/// Module files and subdirectories inside a directory module, excluding
+/// `mod.corr`, in a stable order
+pub fn module_directory_entries(directory: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut entries = Vec::new();
+    for entry in fs::read_dir(directory)? {
+        let entry_path = entry?.path();
+        let is_module_file = entry_path.extension().is_some_and(|ext| ext == "corr")
+            && entry_path
+                .file_name()
+                .is_some_and(|name| name != MODULE_DIRECTORY_FILE);
+        if entry_path.is_dir() || is_module_file {
+            entries.push(entry_path);
+        }
+    }
+    entries.sort();
+    Ok(entries)
+}
+
+/// Where an import path leads: next to the importing file, or else in the
+/// first directory of `search_path` that has it
+pub fn resolve_import(current_directory: &Path, search_path: &[PathBuf], path: &str) -> PathBuf {
+    let local = current_directory.join(path);
+    if local.exists() {
+        return local;
+    }
+    search_path
+        .iter()
+        .map(|directory| directory.join(path))
+        .find(|candidate| candidate.exists())
+        .unwrap_or(local)
+}
+
 /// Module loader for handling imports
+#[derive(Clone)]
 pub struct ModuleLoader {
     /// Current directory for resolving imports
     current_directory: PathBuf,
     /// Cache of loaded modules
     modules: HashMap<String, HashMap<String, Type>>,
+    /// Import path of each loaded module, for reloading
+    paths: HashMap<String, String>,
+    /// Modules imported with `export import`, visible to importers of this one
+    exported: HashSet<String>,
+    /// Reject IO at the top level of imported modules
+    pure_imports: bool,
+    /// Reject imprecise types in imported modules, as in the importing program
+    strict: bool,
+    /// Check imported modules with the prelude defined
+    prelude: bool,
+    /// Directories searched for imports not found next to the importing file
+    search_path: Vec<PathBuf>,
+    /// Edition imported modules are read in, the importing program's
+    edition: Edition,
+    /// Hash of every module file read, imports of imports included, for the
+    /// lockfile
+    hashes: BTreeMap<PathBuf, String>,
 }
 
 impl ModuleLoader {
@@ -17,6 +71,14 @@ impl ModuleLoader {
         Self {
             current_directory: std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")),
             modules: HashMap::new(),
+            paths: HashMap::new(),
+            exported: HashSet::new(),
+            pure_imports: false,
+            strict: false,
+            prelude: true,
+            search_path: Vec::new(),
+            edition: Edition::default(),
+            hashes: BTreeMap::new(),
         }
     }
 
@@ -25,7 +87,38 @@ impl ModuleLoader {
         self.current_directory = path.as_ref().to_path_buf();
     }
 
+    /// Reject IO at the top level of imported modules
+    pub fn set_pure_imports(&mut self, pure_imports: bool) {
+        self.pure_imports = pure_imports;
+    }
+
+    /// Reject imprecise types in imported modules
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    /// Define the prelude in imported modules, or leave it out
+    pub fn set_prelude(&mut self, prelude: bool) {
+        self.prelude = prelude;
+    }
+
+    /// Look for imports in these directories when they are not found next to
+    /// the importing file
+    pub fn set_search_path(&mut self, search_path: Vec<PathBuf>) {
+        self.search_path = search_path;
+    }
+
+    /// Read imported modules in this edition
+    pub fn set_edition(&mut self, edition: Edition) {
+        self.edition = edition;
+    }
+
     /// Get the current directory
+    /// The edition programs and their imports are read in
+    pub fn edition(&self) -> Edition {
+        self.edition
+    }
+
     pub fn get_current_directory(&self) -> &PathBuf {
         &self.current_directory
     }
@@ -37,20 +130,101 @@ impl ModuleLoader {
         module_name: &str,
         span: &Span,
     ) -> TypeResult<HashMap<String, Type>> {
+        // Native modules have their types registered in Rust
+        if path.starts_with(crate::stdlib::STD_PREFIX) {
+            let functions =
+                crate::stdlib::native_module(path).ok_or_else(|| TypeError::ImportError {
+                    message: format!("Unknown standard module: {}", path),
+                    path: path.to_string(),
+                    span: span.clone(),
+                })?;
+            return Ok(functions
+                .iter()
+                .map(|function| (function.name.to_string(), (function.signature)()))
+                .collect());
+        }
+
         // Resolve the import path relative to current directory
-        let import_path = self.current_directory.join(path);
+        let import_path = resolve_import(&self.current_directory, &self.search_path, path);
+        self.check_module_path(&import_path, path, module_name, span)
+    }
+
+    /// Type-check a module file, or every module in a directory
+    fn check_module_path(
+        &mut self,
+        import_path: &Path,
+        path: &str,
+        module_name: &str,
+        span: &Span,
+    ) -> TypeResult<HashMap<String, Type>> {
+        if import_path.is_dir() {
+            self.check_module_directory(import_path, path, module_name, span)
+        } else {
+            self.check_module_file(import_path, path, module_name, span)
+        }
+    }
+
+    /// A directory is a module whose submodules are its `.corr` files and
+    /// subdirectories. Its own exports come from `mod.corr`, if present.
+    fn check_module_directory(
+        &mut self,
+        import_path: &Path,
+        path: &str,
+        module_name: &str,
+        span: &Span,
+    ) -> TypeResult<HashMap<String, Type>> {
+        let own_file = import_path.join(MODULE_DIRECTORY_FILE);
+        let exports = if own_file.is_file() {
+            self.check_module_file(&own_file, path, module_name, span)?
+        } else {
+            HashMap::new()
+        };
 
+        for submodule in
+            module_directory_entries(import_path).map_err(|e| TypeError::ImportError {
+                message: format!(
+                    "Failed to read module directory {}: {}",
+                    import_path.display(),
+                    e
+                ),
+                path: path.to_string(),
+                span: span.clone(),
+            })?
+        {
+            let Some(stem) = submodule.file_stem().and_then(|stem| stem.to_str()) else {
+                continue;
+            };
+            let submodule_name = format!("{}.{}", module_name, stem);
+            let submodule_exports =
+                self.check_module_path(&submodule, path, &submodule_name, span)?;
+            self.modules.insert(submodule_name, submodule_exports);
+        }
+
+        Ok(exports)
+    }
+
+    fn check_module_file(
+        &mut self,
+        import_path: &Path,
+        path: &str,
+        module_name: &str,
+        span: &Span,
+    ) -> TypeResult<HashMap<String, Type>> {
         // Read the file content
-        let content = fs::read_to_string(&import_path).map_err(|_| TypeError::ImportError {
+        let content = fs::read_to_string(import_path).map_err(|_| TypeError::ImportError {
             message: format!("Failed to read module file: {}", import_path.display()),
             path: path.to_string(),
             span: span.clone(),
         })?;
+        self.hashes.insert(
+            import_path.to_path_buf(),
+            crate::typechecker::lockfile::hash(&content),
+        );
 
         // Parse the file content
-        let mut lexer = crate::lexer::tokenizer::Tokenizer::new("");
-        let tokens = lexer
-            .tokenize(&content)
+        let tokens = crate::lexer::tokenizer::Tokenizer::new(&content)
+            .with_edition(self.edition)
+            .tokenize()
             .map_err(|e| TypeError::ImportError {
                 message: format!("Failed to tokenize module {}: {}", module_name, e),
                 path: path.to_string(),
@@ -65,12 +239,21 @@ impl ModuleLoader {
         })?;
 
         // Create a new type checker for the module
-        let mut module_checker = crate::typechecker::TypeChecker::new();
+        let mut module_checker = if self.prelude {
+            crate::typechecker::TypeChecker::new()
+        } else {
+            crate::typechecker::TypeChecker::without_prelude()
+        };
 
         // Set the module's current directory to the imported file's directory
         if let Some(parent) = import_path.parent() {
             module_checker.set_current_directory(parent);
         }
+        module_checker.set_pure_imports(self.pure_imports);
+        module_checker.set_strict(self.strict);
+        module_checker.set_module_search_path(self.search_path.clone());
+        module_checker.set_edition(self.edition);
+        module_checker.set_forbid_io(self.pure_imports);
 
         // Type-check the module
         let _typed_program =
@@ -82,8 +265,27 @@ impl ModuleLoader {
                     span: span.clone(),
                 })?;
 
-        // Extract all top-level bindings as exports
-        Ok(module_checker.get_environment().get_all_bindings_types())
+        // Modules this one re-exports become its nested modules
+        let inner_loader = module_checker.get_module_loader();
+        self.hashes.extend(inner_loader.hashes.clone());
+        for (inner_name, inner_exports) in inner_loader.get_modules() {
+            let root = inner_name.split('.').next().unwrap_or_default();
+            if !inner_loader.exported.contains(root) {
+                continue;
+            }
+            self.modules.insert(
+                format!("{}.{}", module_name, inner_name),
+                inner_exports.clone(),
+            );
+        }
+
+        // Extract the module's own top-level bindings, not the prelude's, as exports
+        Ok(module_checker.get_environment().local_bindings().clone())
+    }
+
+    /// Hashes of the module files read so far, by the path they were read from
+    pub fn module_hashes(&self) -> &BTreeMap<PathBuf, String> {
+        &self.hashes
     }
 
     /// Get a module's exports
@@ -96,6 +298,30 @@ impl ModuleLoader {
         self.modules.insert(module_name, exports);
     }
 
+    /// Get the path a module was imported from
+    pub fn get_module_path(&self, module_name: &str) -> Option<&String> {
+        self.paths.get(module_name)
+    }
+
+    /// Store the path a module was imported from
+    pub fn store_module_path(&mut self, module_name: String, path: String) {
+        self.paths.insert(module_name, path);
+    }
+
+    /// Record whether a module was last imported with `export import`
+    pub fn set_exported(&mut self, module_name: String, exported: bool) {
+        if exported {
+            self.exported.insert(module_name);
+        } else {
+            self.exported.remove(&module_name);
+        }
+    }
+
+    /// Forget which modules were re-exported, before checking another program
+    pub fn clear_exported(&mut self) {
+        self.exported.clear();
+    }
+
     /// Get all loaded modules
     pub fn get_modules(&self) -> &HashMap<String, HashMap<String, Type>> {
         &self.modules
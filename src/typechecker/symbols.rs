@@ -0,0 +1,91 @@
+//! The symbol table: what the type checker learns about the names in a
+//! program, keyed by the `NodeId`s the parser gives them. Each place that
+//! binds a name defines a symbol, and each identifier is resolved to the
+//! symbol it refers to, so tools such as an editor can find a name's
+//! definition, its type, and every use of it without resolving scopes again.
+
+use crate::ast::NodeId;
+use crate::lexer::tokens::Span;
+use crate::typechecker::Type;
+use std::collections::BTreeMap;
+
+/// A name bound by a `let`, `const`, `fn`, parameter, loop variable, `with`
+/// or `case` pattern
+#[derive(Debug, Clone, PartialEq)]
+pub struct Symbol {
+    pub name: String,
+    /// The declaration or expression that binds the name
+    pub definition: Span,
+    pub ty: Type,
+}
+
+/// An identifier resolved to the symbol it refers to
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reference {
+    pub definition: NodeId,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SymbolTable {
+    symbols: BTreeMap<NodeId, Symbol>,
+    references: BTreeMap<NodeId, Reference>,
+}
+
+impl SymbolTable {
+    /// The symbol defined by the binding with this id
+    pub fn symbol(&self, id: NodeId) -> Option<&Symbol> {
+        self.symbols.get(&id)
+    }
+
+    /// Every symbol, in the order their bindings appear in the source
+    pub fn symbols(&self) -> impl Iterator<Item = (NodeId, &Symbol)> {
+        self.symbols.iter().map(|(id, symbol)| (*id, symbol))
+    }
+
+    /// The binding the identifier with this id refers to. Names from the
+    /// prelude and from modules have no binding in the program.
+    pub fn definition(&self, reference: NodeId) -> Option<NodeId> {
+        self.references
+            .get(&reference)
+            .map(|reference| reference.definition)
+    }
+
+    /// The identifiers referring to the binding with this id, in source order
+    pub fn references(&self, definition: NodeId) -> impl Iterator<Item = (NodeId, &Span)> {
+        self.references
+            .iter()
+            .filter(move |(_, reference)| reference.definition == definition)
+            .map(|(id, reference)| (*id, &reference.span))
+    }
+
+    /// Record the binding `id` of `name`, replacing what was recorded for it
+    /// before, as when its type becomes known after its body is checked
+    pub(crate) fn define(&mut self, id: NodeId, name: &str, definition: &Span, ty: &Type) {
+        self.symbols.insert(
+            id,
+            Symbol {
+                name: name.to_string(),
+                definition: definition.clone(),
+                ty: ty.clone(),
+            },
+        );
+    }
+
+    /// Record that the identifier `id` at `span` refers to `definition`
+    pub(crate) fn refer(&mut self, id: NodeId, definition: NodeId, span: &Span) {
+        self.references.insert(
+            id,
+            Reference {
+                definition,
+                span: span.clone(),
+            },
+        );
+    }
+
+    /// Move everything recorded in `other` into this table
+    pub(crate) fn append(&mut self, other: &mut SymbolTable) {
+        self.symbols.append(&mut other.symbols);
+        self.references.append(&mut other.references);
+    }
+}
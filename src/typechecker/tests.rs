@@ -1,9 +1,10 @@
 #[cfg(test)]
+#[allow(clippy::module_inception)]
 mod tests {
-    use crate::ast::{Expression, Program, Statement};
+    use crate::ast::{Expression, NodeId, Program, Statement};
     use crate::lexer::tokens::Span;
     use crate::typechecker::TypeError;
-    use crate::typechecker::{BinaryOp, Environment, Type, TypeChecker, TypedStatement};
+    use crate::typechecker::{BinaryOp, Constant, Environment, Type, TypeChecker, TypedStatement};
 
     fn create_test_span() -> Span {
         Span::new(0, 1, 1, 1)
@@ -92,6 +93,7 @@ mod tests {
                     value: 42,
                     span: create_test_span(),
                 },
+                id: NodeId::default(),
                 span: create_test_span(),
             }],
             create_test_span(),
@@ -115,6 +117,7 @@ mod tests {
 
         let expr = Expression::Identifier {
             name: "undefined".to_string(),
+            id: NodeId::default(),
             span: create_test_span(),
         };
 
@@ -142,11 +145,13 @@ mod tests {
                         value: 42,
                         span: create_test_span(),
                     },
+                    id: NodeId::default(),
                     span: create_test_span(),
                 },
                 Statement::Expression {
                     expression: Expression::Identifier {
                         name: "x".to_string(),
+                        id: NodeId::default(),
                         span: create_test_span(),
                     },
                     span: create_test_span(),
@@ -180,6 +185,7 @@ mod tests {
                         value: 42,
                         span: create_test_span(),
                     },
+                    id: NodeId::default(),
                     span: create_test_span(),
                 },
                 Statement::VariableDeclaration {
@@ -189,6 +195,7 @@ mod tests {
                         value: 24,
                         span: create_test_span(),
                     },
+                    id: NodeId::default(),
                     span: create_test_span(),
                 },
             ],
@@ -271,6 +278,7 @@ mod tests {
                         value: 25,
                         span: create_test_span(),
                     },
+                    id: NodeId::default(),
                     span: create_test_span(),
                 },
                 Statement::VariableDeclaration {
@@ -282,6 +290,7 @@ mod tests {
                         value: true,
                         span: create_test_span(),
                     },
+                    id: NodeId::default(),
                     span: create_test_span(),
                 },
             ],
@@ -834,6 +843,36 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_type_diff_marks_the_differing_components() {
+        let expected = Type::list(Type::pair(Type::Int, Type::Bool));
+        let found = Type::list(Type::pair(Type::Int, Type::String));
+        assert_eq!(
+            expected.diff(&found),
+            Some((
+                "List (Int, >>Bool<<)".to_string(),
+                "List (Int, >>String<<)".to_string()
+            ))
+        );
+
+        // Nothing to mark inside types that differ outright, or where one
+        // side is still unknown
+        assert_eq!(Type::Int.diff(&Type::String), None);
+        assert_eq!(expected.diff(&expected), None);
+        assert_eq!(expected.diff(&Type::list(Type::Unknown)), None);
+
+        let error = TypeError::TypeMismatch {
+            expected,
+            found,
+            span: create_test_span(),
+        };
+        assert!(
+            error.to_string().ends_with(
+                "\n  expected: List (Int, >>Bool<<)\n     found: List (Int, >>String<<)"
+            )
+        );
+    }
+
     #[test]
     fn test_type_mismatch_in_variable_declaration() {
         let mut checker = TypeChecker::new();
@@ -850,6 +889,7 @@ mod tests {
                     value: true,
                     span: create_test_span(),
                 },
+                id: NodeId::default(),
                 span: create_test_span(),
             }],
             create_test_span(),
@@ -895,7 +935,6 @@ mod tests {
             }
             Err(_) => {
                 // If it fails, that's also acceptable behavior
-                assert!(true);
             }
         }
     }
@@ -1096,6 +1135,7 @@ mod tests {
             name: "a".to_string(),
             type_annotation: Some(bool_list_type),
             value: empty_list,
+            id: NodeId::default(),
             span: create_test_span(),
         };
 
@@ -1126,8 +1166,9 @@ mod tests {
     fn test_sum_type_inference() {
         let source = "let x = if true { 42 } else { false };";
 
-        let mut tokenizer = crate::lexer::tokenizer::Tokenizer::new(source);
-        let tokens = tokenizer.tokenize(source).expect("Tokenization failed");
+        let tokens = crate::lexer::tokenizer::Tokenizer::new(source)
+            .tokenize()
+            .expect("Tokenization failed");
 
         let mut parser = crate::ast::parser::Parser::new(tokens);
         let ast = parser.parse().expect("Parsing failed");
@@ -1160,8 +1201,9 @@ mod tests {
               | inr y => 0;
         "#;
 
-        let mut tokenizer = crate::lexer::tokenizer::Tokenizer::new(source);
-        let tokens = tokenizer.tokenize(source).expect("Tokenization failed");
+        let tokens = crate::lexer::tokenizer::Tokenizer::new(source)
+            .tokenize()
+            .expect("Tokenization failed");
 
         let mut parser = crate::ast::parser::Parser::new(tokens);
         let ast = parser.parse().expect("Parsing failed");
@@ -1190,4 +1232,663 @@ mod tests {
             _ => panic!("Expected variable declaration"),
         }
     }
+
+    #[test]
+    fn test_comparison_constraints() {
+        use crate::typechecker::Constraint;
+
+        let int_to_int = Type::function(Type::Int, Type::Int);
+        assert!(Type::list(Type::String).satisfies(Constraint::Ord));
+        assert!(Type::sum(Type::Int, Type::Bool).satisfies(Constraint::Eq));
+        assert!(!Type::sum(Type::Int, Type::Bool).satisfies(Constraint::Ord));
+        assert!(!Type::pair(Type::Int, int_to_int.clone()).satisfies(Constraint::Eq));
+        assert!(!int_to_int.satisfies(Constraint::Show));
+
+        assert_eq!(
+            Type::String.can_binary_op(&BinaryOp::LessThan, &Type::String),
+            Some(Type::Bool)
+        );
+        assert_eq!(
+            Type::pair(Type::Int, Type::Bool)
+                .can_binary_op(&BinaryOp::Equal, &Type::pair(Type::Int, Type::Bool)),
+            Some(Type::Bool)
+        );
+        assert_eq!(
+            int_to_int.can_binary_op(&BinaryOp::Equal, &Type::Unknown),
+            None
+        );
+        assert_eq!(
+            Type::Int.can_binary_op(&BinaryOp::LessThan, &Type::String),
+            None
+        );
+    }
+
+    #[test]
+    fn test_closest_name_suggestions() {
+        use crate::typechecker::suggestions::{closest_name, edit_distance};
+
+        assert_eq!(edit_distance("count", "cuont"), 1);
+        assert_eq!(edit_distance("count", "cont"), 1);
+        assert_eq!(edit_distance("", "abc"), 3);
+
+        let names = ["count", "counter", "total", "x"];
+        assert_eq!(
+            closest_name("coutn", names.iter().copied()),
+            Some("count".to_string())
+        );
+        assert_eq!(closest_name("totl", names), Some("total".to_string()));
+        // Too far from anything in scope, or already correct
+        assert_eq!(closest_name("banana", names), None);
+        assert_eq!(closest_name("count", ["count"]), None);
+    }
+
+    #[test]
+    fn test_typed_program_json() {
+        use crate::typechecker::{TypedExpression, TypedProgram};
+
+        let span = create_test_span();
+        let program = TypedProgram::new(
+            vec![TypedStatement::VariableDeclaration {
+                name: "say \"hi\"".to_string(),
+                ty: Type::list(Type::Int),
+                value: TypedExpression::new(Type::list(Type::Int), span.clone()),
+                span: span.clone(),
+            }],
+            span,
+        );
+
+        let json = program.to_json();
+        assert!(json.starts_with("{\"statements\":[{\"kind\":\"VariableDeclaration\""));
+        assert!(json.contains("\"name\":\"say \\\"hi\\\"\""));
+        assert!(json.contains(
+            "\"type\":{\"kind\":\"List\",\"display\":\"List Int\",\"element\":{\"kind\":\"Int\",\"display\":\"Int\"}}"
+        ));
+        assert!(json.ends_with(
+            "\"span\":{\"start\":0,\"end\":1,\"line\":1,\"column\":1,\"end_line\":1,\"end_column\":2,\"char_start\":0,\"char_end\":1}}"
+        ));
+    }
+
+    #[test]
+    fn test_redefinition_can_be_allowed() {
+        let source = "let x = 1; let x = \"one\"; fn f(n: Int) { n } fn f(s: String) { s }";
+        let tokens = crate::lexer::Tokenizer::new(source).tokenize().unwrap();
+        let program = crate::ast::Parser::new(tokens).parse().unwrap();
+
+        assert!(matches!(
+            TypeChecker::new().check_program(&program),
+            Err(TypeError::RedefinedVariable { .. })
+        ));
+
+        let mut checker = TypeChecker::new();
+        checker.set_allow_redefinition(true);
+        checker.check_program(&program).unwrap();
+        assert_eq!(checker.get_environment().lookup("x"), Some(&Type::String));
+        assert_eq!(
+            checker.get_environment().lookup("f"),
+            Some(&Type::function(Type::String, Type::String))
+        );
+    }
+
+    #[test]
+    fn test_shadowing_an_outer_declaration_warns() {
+        let check = |source: &str| {
+            let tokens = crate::lexer::Tokenizer::new(source).tokenize().unwrap();
+            let program = crate::ast::Parser::new(tokens).parse().unwrap();
+            let mut checker = TypeChecker::new();
+            checker.check_program(&program).unwrap();
+            checker.take_warnings()
+        };
+
+        let warnings = check("let x = 1;\nfn f(n: Int) {\n    let x = \"inner\";\n    n\n}");
+        assert_eq!(warnings.len(), 1);
+        let crate::typechecker::TypeWarning::Shadowed {
+            name,
+            span,
+            shadowed,
+        } = &warnings[0]
+        else {
+            panic!("expected a shadowing warning, found {:?}", warnings[0]);
+        };
+        assert_eq!(name, "x");
+        assert_eq!((span.line, span.column), (3, 5));
+        assert_eq!((shadowed.line, shadowed.column), (1, 1));
+
+        // Blocks and branches shadow too, and inner bindings still win
+        let warnings =
+            check("let x = 1; let y: Bool = if x == 1 { let x = true; x } else { false };");
+        assert_eq!(warnings.len(), 1);
+
+        // Parameters, loop variables and prelude functions are not declarations
+        assert!(check("let x = 1; let f = fn(x) { x }; fn map(y: Int) { y }").is_empty());
+        assert!(check("let x = 1; for x in [1] { x };").is_empty());
+    }
+
+    #[test]
+    fn test_constants_are_evaluated_while_checking() {
+        let check = |source: &str| {
+            let tokens = crate::lexer::Tokenizer::new(source).tokenize().unwrap();
+            let program = crate::ast::Parser::new(tokens).parse().unwrap();
+            let mut checker = TypeChecker::new();
+            checker.check_program(&program).map(|_| checker)
+        };
+
+        let checker = check(
+            "const SIZE = 1024;\nconst HALF = SIZE / 2 - 1;\nconst NAME = \"buf\" ++ \"fer\";\nconst BIG = if HALF > 500 && !false { true } else { false };",
+        )
+        .unwrap();
+        assert_eq!(checker.constant("HALF"), Some(&Constant::Int(511)));
+        assert_eq!(
+            checker.constant("NAME"),
+            Some(&Constant::String("buffer".to_string()))
+        );
+        assert_eq!(checker.constant("BIG"), Some(&Constant::Bool(true)));
+        assert_eq!(checker.get_environment().lookup("HALF"), Some(&Type::Int));
+
+        for source in [
+            "let n = 3; const DOUBLE = n * 2;",
+            "fn f(x: Int) { x } const Y = f(1);",
+            "const MAX = 9223372036854775807; const OVER = MAX + 1;",
+            "const ZERO = 0; const BAD = 1 / ZERO;",
+        ] {
+            assert!(
+                matches!(check(source), Err(TypeError::NotConstant { .. })),
+                "{} should not be constant",
+                source
+            );
+        }
+
+        // A constant is still type checked like a `let`
+        assert!(matches!(
+            check("const S: String = 1;"),
+            Err(TypeError::TypeMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_enum_cases_must_cover_every_variant() {
+        let check = |source: &str| {
+            let tokens = crate::lexer::Tokenizer::new(source).tokenize().unwrap();
+            let program = crate::ast::Parser::new(tokens).parse().unwrap();
+            let mut checker = TypeChecker::new();
+            checker.check_program(&program).map(|_| checker)
+        };
+        let color = "enum Color { Red, Green, Blue }\n";
+
+        let mut checker = check(&format!(
+            "{}fn name(c: Color) {{ case c of Color.Red => \"red\" | _ => \"other\" }}",
+            color
+        ))
+        .unwrap();
+        assert_eq!(
+            checker.get_environment().lookup("name"),
+            Some(&Type::function(Type::enumeration("Color"), Type::String))
+        );
+        assert!(checker.take_warnings().is_empty());
+
+        match check(&format!(
+            "{}let n = case Color.Red of Color.Red => 1 | Color.Blue => 2;",
+            color
+        )) {
+            Err(TypeError::NonExhaustiveCase { missing, .. }) => {
+                assert_eq!(missing, ["Color.Green"])
+            }
+            other => panic!("expected a non-exhaustive case, got {:?}", other.err()),
+        }
+
+        // Arms after every variant is covered are never taken
+        let mut checker = check(&format!(
+            "{}let n = case Color.Red of Color.Red => 1 | Color.Green => 2 | Color.Blue => 3 | _ => 4;",
+            color
+        ))
+        .unwrap();
+        assert!(matches!(
+            checker.take_warnings().as_slice(),
+            [crate::typechecker::TypeWarning::UnreachableArm { .. }]
+        ));
+
+        for source in [
+            "let n = case 1 of Color.Red => 1 | _ => 2;",
+            "let n = case Color.Red of Color.Purple => 1 | _ => 2;",
+            "let n = Color.Red + 1;",
+        ] {
+            assert!(
+                check(&format!("{}{}", color, source)).is_err(),
+                "{}",
+                source
+            );
+        }
+    }
+
+    #[test]
+    fn test_string_cases_need_a_default_arm() {
+        let check = |source: &str| {
+            let tokens = crate::lexer::Tokenizer::new(source).tokenize().unwrap();
+            let program = crate::ast::Parser::new(tokens).parse().unwrap();
+            let mut checker = TypeChecker::new();
+            checker.check_program(&program).map(|_| checker)
+        };
+
+        let mut checker =
+            check("fn run(c: String) { case c of \"go\" => 1 | \"stop\" => 2 | _ => 0 }").unwrap();
+        assert_eq!(
+            checker.get_environment().lookup("run"),
+            Some(&Type::function(Type::String, Type::Int))
+        );
+        assert!(checker.take_warnings().is_empty());
+
+        match check("let n = case \"go\" of \"go\" => 1 | \"stop\" => 2;") {
+            Err(TypeError::NonExhaustiveCase { missing, .. }) => {
+                assert_eq!(missing, ["every String"])
+            }
+            other => panic!("expected a non-exhaustive case, got {:?}", other.err()),
+        }
+
+        // A repeated literal never matches
+        let mut checker =
+            check("let n = case \"go\" of \"go\" => 1 | \"go\" => 2 | _ => 0;").unwrap();
+        assert!(matches!(
+            checker.take_warnings().as_slice(),
+            [crate::typechecker::TypeWarning::UnreachableArm { .. }]
+        ));
+
+        assert!(matches!(
+            check("let n = case 1 of \"go\" => 1 | _ => 0;"),
+            Err(TypeError::TypeMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_int_cases_report_gaps_and_overlaps() {
+        use crate::typechecker::TypeWarning;
+
+        let check = |source: &str| {
+            let tokens = crate::lexer::Tokenizer::new(source).tokenize().unwrap();
+            let program = crate::ast::Parser::new(tokens).parse().unwrap();
+            let mut checker = TypeChecker::new();
+            checker.check_program(&program).map(|_| checker)
+        };
+
+        // Open ranges can cover every Int without a `_` arm
+        let mut checker =
+            check("fn sign(n: Int) { case n of ..0 => -1 | 0 => 0 | 1.. => 1 }").unwrap();
+        assert_eq!(
+            checker.get_environment().lookup("sign"),
+            Some(&Type::function(Type::Int, Type::Int))
+        );
+        assert!(checker.take_warnings().is_empty());
+
+        match check("let n = case 5 of 0 => 1 | 3..10 => 2;") {
+            Err(TypeError::NonExhaustiveCase { missing, .. }) => {
+                assert_eq!(missing, ["..0", "1..3", "10.."])
+            }
+            other => panic!("expected a non-exhaustive case, got {:?}", other.err()),
+        }
+
+        let mut checker =
+            check("let n = case 5 of 0..10 => 1 | 5..20 => 2 | 7 => 3 | _ => 4;").unwrap();
+        assert!(matches!(
+            checker.take_warnings().as_slice(),
+            [
+                TypeWarning::OverlappingArm { .. },
+                TypeWarning::UnreachableArm { .. }
+            ]
+        ));
+
+        assert!(matches!(
+            check("let n = case \"five\" of 5 => 1 | _ => 0;"),
+            Err(TypeError::TypeMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_mixing_strings_and_numbers_suggests_a_conversion() {
+        let check = |source: &str| {
+            let tokens = crate::lexer::Tokenizer::new(source).tokenize().unwrap();
+            let program = crate::ast::Parser::new(tokens).parse().unwrap();
+            let mut checker = TypeChecker::new();
+            checker.check_program(&program).map(|_| checker)
+        };
+
+        let error = check("let a = 1 + \"x\";").err().unwrap();
+        assert!(matches!(error, TypeError::InvalidBinaryOperation { .. }));
+        assert!(error.to_string().contains("toString"), "{}", error);
+
+        let error = check("let a = \"5\" * 2;").err().unwrap();
+        assert!(error.to_string().contains("toInt"), "{}", error);
+
+        let checker = check("let n = toInt(\"5\");").unwrap();
+        assert_eq!(
+            checker.get_environment().lookup("n"),
+            Some(&Type::sum(Type::Int, Type::String))
+        );
+        assert!(matches!(
+            check("let n = toInt(5);"),
+            Err(TypeError::TypeMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_logical_operators_reject_non_bool_operands() {
+        let check = |source: &str| {
+            let tokens = crate::lexer::Tokenizer::new(source).tokenize().unwrap();
+            let program = crate::ast::Parser::new(tokens).parse().unwrap();
+            TypeChecker::new().check_program(&program)
+        };
+
+        assert!(check("fn f(x) { x && true }").is_ok());
+        // An unknown operand does not let a non-Bool through
+        assert!(matches!(
+            check("fn f(x) { x || 1 }"),
+            Err(TypeError::InvalidBinaryOperation { .. })
+        ));
+        // A parameter used as an operand is inferred to be a Bool
+        assert!(matches!(
+            check("let f = fn(x) { x && true }; f(1);"),
+            Err(TypeError::TypeMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_returns_agree_with_the_function_result() {
+        let check = |source: &str| {
+            let tokens = crate::lexer::Tokenizer::new(source).tokenize().unwrap();
+            let program = crate::ast::Parser::new(tokens).parse().unwrap();
+            let mut checker = TypeChecker::new();
+            checker
+                .check_program(&program)
+                .map(|_| checker.get_environment().lookup("f").cloned())
+        };
+
+        // A body ending in a return takes its type from the returns
+        assert_eq!(
+            check("fn f(n: Int) { let m = n * 2; return m; }").unwrap(),
+            Some(Type::function(Type::Int, Type::Int))
+        );
+        // A branch that always returns leaves the other branch's type
+        assert_eq!(
+            check("let f = fn(n: Int) { if n < 0 { return 0; } else { n + 1 } };").unwrap(),
+            Some(Type::function(Type::Int, Type::Int))
+        );
+        match check("fn f(n: Int) { if n < 0 { return \"negative\"; }; n }") {
+            Err(TypeError::TypeMismatch {
+                expected,
+                found,
+                span,
+            }) => {
+                assert_eq!((expected, found), (Type::Int, Type::String));
+                assert_eq!(span.column, 27);
+            }
+            other => panic!("Expected a mismatch at the return, found {:?}", other),
+        }
+        assert!(matches!(
+            check("fn f(n: Int) -> Int { return \"x\"; }"),
+            Err(TypeError::TypeMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_lockfile_mismatches() {
+        use crate::typechecker::lockfile::{LockMismatch, Lockfile, hash};
+        use std::collections::BTreeMap;
+        use std::path::{Path, PathBuf};
+
+        let lock = |files: &[(&str, &str)]| {
+            let hashes: BTreeMap<PathBuf, String> = files
+                .iter()
+                .map(|(path, content)| (Path::new("project").join(path), hash(content)))
+                .collect();
+            Lockfile::from_modules(Path::new("project"), &hashes)
+        };
+        let recorded = lock(&[("a.corr", "let a = 1;"), ("sub/../b.corr", "let b = 2;")]);
+        assert_eq!(Lockfile::parse(&recorded.render()), Ok(recorded.clone()));
+        assert!(recorded.render().contains("  b.corr\n"));
+
+        let current = lock(&[("b.corr", "let b = 3;"), ("c.corr", "let c = 4;")]);
+        assert_eq!(
+            recorded.mismatches(&current),
+            vec![
+                LockMismatch::Removed("a.corr".to_string()),
+                LockMismatch::Changed("b.corr".to_string()),
+                LockMismatch::Added("c.corr".to_string()),
+            ]
+        );
+        assert!(recorded.mismatches(&recorded).is_empty());
+        assert!(Lockfile::parse("not a hash line\n").is_err());
+    }
+
+    #[test]
+    fn test_deprecated_builtins_are_warned_about() {
+        use crate::typechecker::TypeWarning;
+        use crate::typechecker::deprecations::{DEPRECATIONS, deprecation};
+
+        let source = "fn greet(name: String) { concat(\"hi \", name) }\nlet s = \"a\" + \"b\";";
+        let tokens = crate::lexer::Tokenizer::new(source).tokenize().unwrap();
+        let program = crate::ast::Parser::new(tokens).parse().unwrap();
+        let mut checker = TypeChecker::new();
+        checker.check_program(&program).unwrap();
+        let warnings = checker.take_warnings();
+        assert_eq!(warnings.len(), 1);
+        assert!(matches!(
+            &warnings[0],
+            TypeWarning::Deprecated { name, .. } if name == "concat"
+        ));
+        assert_eq!(warnings[0].code(), "W0004");
+        assert_eq!(
+            warnings[0].to_string(),
+            "'concat' at line 1, column 26 is deprecated; use a + b instead"
+        );
+
+        assert!(deprecation("concat").is_some());
+        assert!(deprecation("length").is_none());
+        assert!(DEPRECATIONS.iter().all(|d| !d.replacement.is_empty()));
+    }
+
+    #[test]
+    fn test_holes_report_the_type_expected_where_they_stand() {
+        use crate::typechecker::TypeWarning;
+
+        let source = "let count = 3;\nlet name = \"a\";\nfn scale(n: Int) { n * 2 }\nlet total: Int = scale(count) + ??rest;\nlet label = length(?);\nlet anything = ?;";
+        let tokens = crate::lexer::Tokenizer::new(source).tokenize().unwrap();
+        let program = crate::ast::Parser::new(tokens).parse().unwrap();
+        let mut checker = TypeChecker::new();
+        checker.check_program(&program).unwrap();
+        let warnings = checker.take_warnings();
+        assert_eq!(warnings.len(), 3);
+        assert!(warnings.iter().all(|warning| warning.code() == "W0005"));
+        assert!(matches!(
+            &warnings[0],
+            TypeWarning::Hole { name: Some(name), expected: Type::Int, candidates, .. }
+                if name == "rest" && candidates == &["count"]
+        ));
+        assert_eq!(
+            warnings[1].to_string(),
+            "Hole ? at line 5, column 20 needs a value of type String; in scope with that type: name"
+        );
+        assert_eq!(
+            warnings[2].to_string(),
+            "Hole ? at line 6, column 16 can be of any type here"
+        );
+    }
+
+    #[test]
+    fn test_symbol_table_resolves_names_to_their_bindings() {
+        let source = "let x = 1;\nfn double(n) { n * 2 }\nlet y = double(x) + x;\nlet x = true;\nfor i in [y] { print(i); };\nlet z = x;";
+        let parse = || {
+            let tokens = crate::lexer::Tokenizer::new(source).tokenize().unwrap();
+            crate::ast::Parser::new(tokens).parse().unwrap()
+        };
+        let program = parse();
+        // The same source always gets the same ids
+        assert_eq!(program, parse());
+
+        let mut checker = TypeChecker::new();
+        checker.set_allow_redefinition(true);
+        checker.check_program(&program).unwrap();
+        let symbols = checker.symbols();
+        let defined = |name: &str| {
+            symbols
+                .symbols()
+                .filter(|(_, symbol)| symbol.name == name)
+                .map(|(id, symbol)| (id, symbol.ty.clone()))
+                .collect::<Vec<_>>()
+        };
+        let uses = |id| symbols.references(id).count();
+
+        // Each `let x` is its own symbol, referred to until the next one
+        let xs = defined("x");
+        assert_eq!(xs.len(), 2);
+        assert_eq!((xs[0].1.clone(), uses(xs[0].0)), (Type::Int, 2));
+        assert_eq!((xs[1].1.clone(), uses(xs[1].0)), (Type::Bool, 1));
+
+        let (double, ty) = defined("double")[0].clone();
+        assert_eq!(uses(double), 1);
+        assert!(matches!(ty, Type::Function { .. }));
+        for name in ["n", "y", "i"] {
+            let (id, _) = defined(name)[0];
+            assert_eq!(uses(id), 1, "{}", name);
+        }
+        for (reference, _) in symbols.references(xs[1].0) {
+            assert_eq!(symbols.definition(reference), Some(xs[1].0));
+        }
+
+        // Names from the prelude are not the program's symbols
+        assert!(defined("print").is_empty());
+    }
+
+    #[test]
+    fn test_unused_results_are_warned_about() {
+        use crate::typechecker::TypeWarning;
+
+        let source = "let x = 1;\nx == 5;\nlet _ = x == 5;\nlet _ = x;\nfn f(n: Int) { n + 1; print(n); n }\nprint(x);\nf(x);";
+        let tokens = crate::lexer::Tokenizer::new(source).tokenize().unwrap();
+        let program = crate::ast::Parser::new(tokens).parse().unwrap();
+        let mut checker = TypeChecker::new();
+        checker.check_program(&program).unwrap();
+        let warnings = checker.take_warnings();
+        let unused: Vec<(usize, &Type)> = warnings
+            .iter()
+            .map(|warning| match warning {
+                TypeWarning::UnusedResult { ty, span } => (span.line, ty),
+                other => panic!("unexpected warning {}", other),
+            })
+            .collect();
+        assert_eq!(unused, [(2, &Type::Bool), (5, &Type::Int)]);
+        assert_eq!(warnings[0].code(), "W0006");
+    }
+
+    #[test]
+    fn test_constant_conditions_are_warned_about() {
+        use crate::typechecker::TypeWarning;
+
+        let source = "const DEBUG = false;\nlet x = 1;\nif 1 > 2 { print(x); } else { print(0); };\nif DEBUG { print(x); };\nif x > 2 { print(x); };\nif !false {\n    print(x);\n};";
+        let tokens = crate::lexer::Tokenizer::new(source).tokenize().unwrap();
+        let program = crate::ast::Parser::new(tokens).parse().unwrap();
+        let mut checker = TypeChecker::new();
+        checker.check_program(&program).unwrap();
+        let warnings = checker.take_warnings();
+        assert_eq!(warnings.len(), 2);
+        assert!(matches!(
+            &warnings[0],
+            TypeWarning::ConstantCondition { value: false, dead_branch: Some(branch), .. }
+                if (branch.line, branch.column) == (3, 12)
+        ));
+        assert_eq!(warnings[0].code(), "W0007");
+        assert_eq!(
+            warnings[1].to_string(),
+            "Condition at line 6, column 4 is always true"
+        );
+    }
+
+    #[test]
+    fn test_recursion_without_a_base_case_is_warned_about() {
+        use crate::typechecker::TypeWarning;
+
+        let recursing = |source: &str| {
+            let tokens = crate::lexer::Tokenizer::new(source).tokenize().unwrap();
+            let program = crate::ast::Parser::new(tokens).parse().unwrap();
+            let mut checker = TypeChecker::new();
+            checker.check_program(&program).unwrap();
+            checker
+                .take_warnings()
+                .into_iter()
+                .filter_map(|warning| match warning {
+                    TypeWarning::InfiniteRecursion { name, .. } => Some(name),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(recursing("fn f(n: Int) -> Int { f(n - 1) }"), ["f"]);
+        assert_eq!(
+            recursing("fn f(n: Int) -> Int { if n == 0 { f(1) } else { 1 + f(n - 1) } }"),
+            ["f"]
+        );
+        assert_eq!(recursing("fn f(n: Int) -> Int { let m = f(n); m }"), ["f"]);
+
+        // A branch, an early return or a closure is a way out
+        assert!(recursing("fn f(n: Int) -> Int { if n == 0 { 0 } else { f(n - 1) } }").is_empty());
+        assert!(recursing("fn f(n: Int) -> Int { if n == 0 { return 0; }; f(n - 1) }").is_empty());
+        assert!(recursing("fn f(a: Int) { fn(b: Int) { f(a)(b) } }").is_empty());
+        assert!(recursing("fn f(n: Int) -> Bool { n == 0 || f(n - 1) }").is_empty());
+    }
+
+    #[test]
+    fn test_calling_a_non_function_names_the_binding() {
+        let check = |source: &str| {
+            let tokens = crate::lexer::Tokenizer::new(source).tokenize().unwrap();
+            let program = crate::ast::Parser::new(tokens).parse().unwrap();
+            TypeChecker::new().check_program(&program).unwrap_err()
+        };
+
+        let error = check("let n = 5;\nfn f(x: Int) { n(x) }");
+        assert_eq!(error.code(), "E0013");
+        assert_eq!(
+            error.to_string(),
+            "Attempt to call non-function value at line 2, column 16: 'n' is of type 'Int', declared at line 1, column 1"
+        );
+        assert_eq!(
+            check("(1 + 2)(3);").to_string(),
+            "Attempt to call non-function value at line 1, column 2: it is of type 'Int'"
+        );
+    }
+
+    #[test]
+    fn test_curried_calls_with_the_wrong_number_of_arguments() {
+        let check = |source: &str| {
+            let source = format!("fn add(a: Int) {{ fn(b: Int) {{ a + b }} }}\n{}", source);
+            let tokens = crate::lexer::Tokenizer::new(&source).tokenize().unwrap();
+            let program = crate::ast::Parser::new(tokens).parse().unwrap();
+            TypeChecker::new().check_program(&program).map(|_| ())
+        };
+        let arity = |source: &str| match check(source) {
+            Err(TypeError::ArityMismatch {
+                function,
+                expected,
+                found,
+                ..
+            }) => (function, expected, found),
+            other => panic!("expected an arity mismatch, found {:?}", other),
+        };
+
+        assert_eq!(arity("add(1)(2)(3);"), (Some("add".to_string()), 2, 3));
+        assert_eq!(
+            arity("let x: Int = add(1);"),
+            (Some("add".to_string()), 2, 1)
+        );
+        assert_eq!(
+            arity("let x = 2 * add(1);"),
+            (Some("add".to_string()), 2, 1)
+        );
+        assert_eq!(
+            arity("fn twice(n: Int) { n * 2 } twice(add(1));"),
+            (Some("add".to_string()), 2, 1)
+        );
+        assert_eq!(
+            check("let x = add(1)(2)(3);").unwrap_err().to_string(),
+            "Wrong number of arguments at line 2, column 9: 'add' expected 2 arguments, found 3"
+        );
+
+        // Partial application where a function is wanted is fine
+        assert!(check("let inc: Int -> Int = add(1); let y = inc(2) + add(1)(2);").is_ok());
+    }
 }
@@ -1,28 +1,43 @@
 use crate::lexer::tokens::Span;
+use crate::typechecker::Constant;
 
 /// Type system for the Corrosion language
 #[derive(Debug, Clone, PartialEq)]
 pub enum Type {
     /// Integer type
     Int,
+    /// Arbitrary-precision integer type, written `BigInt`
+    BigInt,
     /// Boolean type  
     Bool,
     /// String type
     String,
+    /// Raw byte string type, written `Bytes`
+    Bytes,
     /// Unit type (void)
     Unit,
     /// Function type (T1 -> T2)
     Function { param: Box<Type>, result: Box<Type> },
     /// Pair type (T1, T2)
     Pair { first: Box<Type>, second: Box<Type> },
+    /// Tuple type (T1, T2, T3, ...) with three or more elements
+    Tuple { elements: Vec<Type> },
     /// List type (List T)
     List { element: Box<Type> },
+    /// Handle to a spawned thread producing a T (Handle T)
+    Handle { result: Box<Type> },
+    /// Channel carrying values of type T (Channel T)
+    Channel { element: Box<Type> },
     /// Sum type (T1 + T2)
     Sum { left: Box<Type>, right: Box<Type> },
     /// Recursive type (Rec T)
     Recursive { inner: Box<Type> },
+    /// Enum declared with `enum Name { A, B }`, written `Name`
+    Enum { name: Box<str> },
     /// Unknown type (for type inference)
     Unknown,
+    /// Dynamic type, written `Dyn`, explicitly opting out of static checking
+    Dyn,
     /// Error type (for type errors)
     Error,
 }
@@ -33,6 +48,7 @@ impl Type {
         match (self, other) {
             (Type::Error, _) | (_, Type::Error) => true, // Error type is compatible with anything
             (Type::Unknown, _) | (_, Type::Unknown) => true, // Unknown can be inferred
+            (Type::Dyn, _) | (_, Type::Dyn) => true,     // Dyn is checked at runtime
             // For complex types like lists, require exact structural equality for now
             // This prevents List Unknown from being automatically assignable to List Int
             (Type::List { element: e1 }, Type::List { element: e2 }) => e1 == e2,
@@ -42,6 +58,22 @@ impl Type {
 
     /// Check if a binary operation is valid between two types
     pub fn can_binary_op(&self, op: &BinaryOp, other: &Type) -> Option<Type> {
+        // Equality and ordering work on any two values of the same type, as
+        // long as both operands satisfy the operator's constraint (Eq or Ord)
+        if let Some(constraint) = op.required_constraint() {
+            return match (self, other) {
+                (Type::Error, _) | (_, Type::Error) => Some(Type::Error),
+                (lhs, rhs)
+                    if lhs.satisfies(constraint)
+                        && rhs.satisfies(constraint)
+                        && crate::typechecker::TypeCompatibility::types_compatible(lhs, rhs) =>
+                {
+                    Some(Type::Bool)
+                }
+                _ => None,
+            };
+        }
+
         match (self, op, other) {
             // Arithmetic operations on integers
             (Type::Int, BinaryOp::Add, Type::Int) => Some(Type::Int),
@@ -49,75 +81,244 @@ impl Type {
             (Type::Int, BinaryOp::Multiply, Type::Int) => Some(Type::Int),
             (Type::Int, BinaryOp::Divide, Type::Int) => Some(Type::Int),
 
-            // Comparison operations on integers
-            (Type::Int, BinaryOp::Equal, Type::Int) => Some(Type::Bool),
-            (Type::Int, BinaryOp::NotEqual, Type::Int) => Some(Type::Bool),
-            (Type::Int, BinaryOp::LessThan, Type::Int) => Some(Type::Bool),
-            (Type::Int, BinaryOp::LessThanEqual, Type::Int) => Some(Type::Bool),
-            (Type::Int, BinaryOp::GreaterThan, Type::Int) => Some(Type::Bool),
-            (Type::Int, BinaryOp::GreaterThanEqual, Type::Int) => Some(Type::Bool),
-
-            // Boolean equality
-            (Type::Bool, BinaryOp::Equal, Type::Bool) => Some(Type::Bool),
-            (Type::Bool, BinaryOp::NotEqual, Type::Bool) => Some(Type::Bool),
+            // Arithmetic involving a BigInt widens the Int operand
+            (
+                Type::BigInt | Type::Int,
+                BinaryOp::Add | BinaryOp::Subtract | BinaryOp::Multiply | BinaryOp::Divide,
+                Type::BigInt | Type::Int,
+            ) => Some(Type::BigInt),
 
             // String operations
             (Type::String, BinaryOp::Add, Type::String) => Some(Type::String), // String concatenation
-            (Type::String, BinaryOp::Equal, Type::String) => Some(Type::Bool),
-            (Type::String, BinaryOp::NotEqual, Type::String) => Some(Type::Bool),
 
             // Logical operations on booleans
             (Type::Bool, BinaryOp::LogicalAnd, Type::Bool) => Some(Type::Bool),
             (Type::Bool, BinaryOp::LogicalOr, Type::Bool) => Some(Type::Bool),
 
+            // List and string concatenation
+            (Type::String, BinaryOp::Append, Type::String) => Some(Type::String),
+            (Type::Bytes, BinaryOp::Append, Type::Bytes) => Some(Type::Bytes),
+            (Type::List { element: e1 }, BinaryOp::Append, Type::List { element: e2 }) => {
+                if crate::typechecker::TypeCompatibility::types_compatible(e1, e2) {
+                    let element = if **e1 == Type::Unknown { e2 } else { e1 };
+                    Some(Type::list(element.as_ref().clone()))
+                } else {
+                    None
+                }
+            }
+
+            // Function composition
+            (lhs, BinaryOp::ComposeForward, rhs) => Type::compose(lhs, rhs),
+            (lhs, BinaryOp::ComposeBackward, rhs) => Type::compose(rhs, lhs),
+
             // Assignment
             (_, BinaryOp::Assign, rhs) if self.is_assignable_to(rhs) => Some(rhs.clone()),
 
             // Error propagation
             (Type::Error, _, _) | (_, _, Type::Error) => Some(Type::Error),
 
-            // Unknown type inference - but respect operation semantics
-            (Type::Unknown, BinaryOp::Equal, _) | (_, BinaryOp::Equal, Type::Unknown) => {
-                Some(Type::Bool)
+            // Operations involving Dyn are only checked at runtime
+            (Type::Dyn, _, _) | (_, _, Type::Dyn) => Some(Type::Dyn),
+
+            // Unknown type inference - but respect operation semantics. Values
+            // are not truthy, so logical operators only ever take Bools.
+            (
+                Type::Unknown | Type::Bool,
+                BinaryOp::LogicalAnd | BinaryOp::LogicalOr,
+                Type::Unknown | Type::Bool,
+            ) => Some(Type::Bool),
+            (_, BinaryOp::LogicalAnd | BinaryOp::LogicalOr, _) => None,
+            (Type::Unknown, _, rhs) => Some(rhs.clone()),
+            (lhs, _, Type::Unknown) => Some(lhs.clone()),
+
+            _ => None, // Invalid operation
+        }
+    }
+
+    /// Check whether values of this type satisfy a built-in constraint
+    pub fn satisfies(&self, constraint: Constraint) -> bool {
+        match self {
+            Type::Int | Type::BigInt | Type::Bool | Type::String | Type::Bytes | Type::Unit => true,
+            Type::Unknown | Type::Dyn | Type::Error => true,
+            // Functions and thread handles are opaque
+            Type::Function { .. } | Type::Handle { .. } | Type::Channel { .. } => false,
+            Type::List { element } => element.satisfies(constraint),
+            Type::Pair { first, second } => {
+                first.satisfies(constraint) && second.satisfies(constraint)
             }
-            (Type::Unknown, BinaryOp::NotEqual, _) | (_, BinaryOp::NotEqual, Type::Unknown) => {
-                Some(Type::Bool)
+            Type::Tuple { elements } => elements.iter().all(|e| e.satisfies(constraint)),
+            // Sums have no natural order between their two sides
+            Type::Sum { left, right } => {
+                constraint != Constraint::Ord
+                    && left.satisfies(constraint)
+                    && right.satisfies(constraint)
             }
+            Type::Recursive { inner } => inner.satisfies(constraint),
+            // Variants are tags without an order
+            Type::Enum { .. } => constraint != Constraint::Ord,
+        }
+    }
 
-            // List equality operations (including unknown element types)
-            (Type::List { element: e1 }, BinaryOp::Equal, Type::List { element: e2 }) => {
-                if e1.is_assignable_to(e2) || e2.is_assignable_to(e1) {
-                    Some(Type::Bool)
-                } else {
-                    None
-                }
+    /// Check whether inference left any part of this type unknown
+    pub fn contains_unknown(&self) -> bool {
+        match self {
+            Type::Unknown => true,
+            Type::Function { param, result } => {
+                param.contains_unknown() || result.contains_unknown()
             }
-            (Type::List { element: e1 }, BinaryOp::NotEqual, Type::List { element: e2 }) => {
-                if e1.is_assignable_to(e2) || e2.is_assignable_to(e1) {
-                    Some(Type::Bool)
-                } else {
-                    None
+            Type::Pair { first, second } => first.contains_unknown() || second.contains_unknown(),
+            Type::Tuple { elements } => elements.iter().any(|e| e.contains_unknown()),
+            Type::List { element } => element.contains_unknown(),
+            Type::Handle { result } => result.contains_unknown(),
+            Type::Channel { element } => element.contains_unknown(),
+            Type::Sum { left, right } => left.contains_unknown() || right.contains_unknown(),
+            Type::Recursive { inner } => inner.contains_unknown(),
+            Type::Int
+            | Type::BigInt
+            | Type::Bool
+            | Type::String
+            | Type::Bytes
+            | Type::Unit
+            | Type::Enum { .. }
+            | Type::Dyn
+            | Type::Error => false,
+        }
+    }
+
+    /// `self` and `other` written out with the components that differ marked
+    /// `>>like this<<`, so two large types can be compared at a glance. `None`
+    /// if the types are equal, or differ already in their outermost form, when
+    /// marking would only wrap both of them whole.
+    pub fn diff(&self, other: &Type) -> Option<(String, String)> {
+        if self == other || std::mem::discriminant(self) != std::mem::discriminant(other) {
+            return None;
+        }
+        if let (Type::Tuple { elements: a }, Type::Tuple { elements: b }) = (self, other)
+            && a.len() != b.len()
+        {
+            return None;
+        }
+        let (mut left, mut right) = (String::new(), String::new());
+        Self::write_diff(self, other, &mut left, &mut right);
+        left.contains(">>").then_some((left, right))
+    }
+
+    /// Write `a` to `left` and `b` to `right` as `Display` does, marking the
+    /// largest components where they differ. Unknown parts differ from nothing,
+    /// as inference could still make them anything.
+    fn write_diff(a: &Type, b: &Type, left: &mut String, right: &mut String) {
+        use std::fmt::Write;
+
+        let both = |text: &str, left: &mut String, right: &mut String| {
+            left.push_str(text);
+            right.push_str(text);
+        };
+        match (a, b) {
+            _ if a == b
+                || [a, b]
+                    .iter()
+                    .any(|t| matches!(t, Type::Unknown | Type::Error)) =>
+            {
+                let _ = write!(left, "{}", a);
+                let _ = write!(right, "{}", b);
+            }
+            (
+                Type::Function { param, result },
+                Type::Function {
+                    param: other_param,
+                    result: other_result,
+                },
+            ) => {
+                both("(", left, right);
+                Self::write_diff(param, other_param, left, right);
+                both(" -> ", left, right);
+                Self::write_diff(result, other_result, left, right);
+                both(")", left, right);
+            }
+            (
+                Type::Pair { first, second },
+                Type::Pair {
+                    first: other_first,
+                    second: other_second,
+                },
+            ) => {
+                both("(", left, right);
+                Self::write_diff(first, other_first, left, right);
+                both(", ", left, right);
+                Self::write_diff(second, other_second, left, right);
+                both(")", left, right);
+            }
+            (Type::Tuple { elements }, Type::Tuple { elements: others })
+                if elements.len() == others.len() =>
+            {
+                both("(", left, right);
+                for (index, (element, other)) in elements.iter().zip(others).enumerate() {
+                    if index > 0 {
+                        both(", ", left, right);
+                    }
+                    Self::write_diff(element, other, left, right);
                 }
+                both(")", left, right);
             }
-            (Type::Unknown, BinaryOp::LessThan, _) | (_, BinaryOp::LessThan, Type::Unknown) => {
-                Some(Type::Bool)
+            (Type::List { element }, Type::List { element: other }) => {
+                both("List ", left, right);
+                Self::write_diff(element, other, left, right);
             }
-            (Type::Unknown, BinaryOp::LessThanEqual, _)
-            | (_, BinaryOp::LessThanEqual, Type::Unknown) => Some(Type::Bool),
-            (Type::Unknown, BinaryOp::GreaterThan, _)
-            | (_, BinaryOp::GreaterThan, Type::Unknown) => Some(Type::Bool),
-            (Type::Unknown, BinaryOp::GreaterThanEqual, _)
-            | (_, BinaryOp::GreaterThanEqual, Type::Unknown) => Some(Type::Bool),
-            (Type::Unknown, BinaryOp::LogicalAnd, _) | (_, BinaryOp::LogicalAnd, Type::Unknown) => {
-                Some(Type::Bool)
+            (Type::Handle { result }, Type::Handle { result: other }) => {
+                both("Handle ", left, right);
+                Self::write_diff(result, other, left, right);
             }
-            (Type::Unknown, BinaryOp::LogicalOr, _) | (_, BinaryOp::LogicalOr, Type::Unknown) => {
-                Some(Type::Bool)
+            (Type::Channel { element }, Type::Channel { element: other }) => {
+                both("Channel ", left, right);
+                Self::write_diff(element, other, left, right);
             }
-            (Type::Unknown, _, rhs) => Some(rhs.clone()),
-            (lhs, _, Type::Unknown) => Some(lhs.clone()),
+            (
+                Type::Sum {
+                    left: a_left,
+                    right: a_right,
+                },
+                Type::Sum {
+                    left: b_left,
+                    right: b_right,
+                },
+            ) => {
+                both("(", left, right);
+                Self::write_diff(a_left, b_left, left, right);
+                both(" + ", left, right);
+                Self::write_diff(a_right, b_right, left, right);
+                both(")", left, right);
+            }
+            (Type::Recursive { inner }, Type::Recursive { inner: other }) => {
+                both("Rec ", left, right);
+                Self::write_diff(inner, other, left, right);
+            }
+            _ => {
+                let _ = write!(left, ">>{}<<", a);
+                let _ = write!(right, ">>{}<<", b);
+            }
+        }
+    }
 
-            _ => None, // Invalid operation
+    /// Type of `first >> second`: a function applying `first`, then `second`
+    fn compose(first: &Type, second: &Type) -> Option<Type> {
+        let (first_param, first_result) = match first {
+            Type::Function { param, result } => (param.as_ref().clone(), result.as_ref()),
+            Type::Unknown => (Type::Unknown, &Type::Unknown),
+            Type::Error => return Some(Type::Error),
+            _ => return None,
+        };
+        let (second_param, second_result) = match second {
+            Type::Function { param, result } => (param.as_ref(), result.as_ref().clone()),
+            Type::Unknown => (&Type::Unknown, Type::Unknown),
+            Type::Error => return Some(Type::Error),
+            _ => return None,
+        };
+
+        // The output of the first function must be accepted by the second
+        if crate::typechecker::TypeCompatibility::types_compatible(first_result, second_param) {
+            Some(Type::function(first_param, second_result))
+        } else {
+            None
         }
     }
 }
@@ -126,20 +327,51 @@ impl std::fmt::Display for Type {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Type::Int => write!(f, "Int"),
+            Type::BigInt => write!(f, "BigInt"),
             Type::Bool => write!(f, "Bool"),
             Type::String => write!(f, "String"),
+            Type::Bytes => write!(f, "Bytes"),
             Type::Unit => write!(f, "Unit"),
             Type::Function { param, result } => write!(f, "({} -> {})", param, result),
             Type::Pair { first, second } => write!(f, "({}, {})", first, second),
+            Type::Tuple { elements } => {
+                let elements: Vec<String> = elements.iter().map(|e| e.to_string()).collect();
+                write!(f, "({})", elements.join(", "))
+            }
             Type::List { element } => write!(f, "List {}", element),
+            Type::Handle { result } => write!(f, "Handle {}", result),
+            Type::Channel { element } => write!(f, "Channel {}", element),
             Type::Sum { left, right } => write!(f, "({} + {})", left, right),
             Type::Recursive { inner } => write!(f, "Rec {}", inner),
+            Type::Enum { name, .. } => write!(f, "{}", name),
             Type::Unknown => write!(f, "unknown"),
+            Type::Dyn => write!(f, "Dyn"),
             Type::Error => write!(f, "error"),
         }
     }
 }
 
+/// Built-in type constraints, similar to type classes that cannot be extended
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Constraint {
+    /// Values can be compared with `==` and `!=`
+    Eq,
+    /// Values can be ordered with `<`, `<=`, `>` and `>=`
+    Ord,
+    /// Values can be converted to a string with `toString`
+    Show,
+}
+
+impl std::fmt::Display for Constraint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Constraint::Eq => write!(f, "Eq"),
+            Constraint::Ord => write!(f, "Ord"),
+            Constraint::Show => write!(f, "Show"),
+        }
+    }
+}
+
 /// Binary operations in type checking context
 #[derive(Debug, Clone, PartialEq)]
 pub enum BinaryOp {
@@ -156,6 +388,23 @@ pub enum BinaryOp {
     GreaterThanEqual,
     LogicalAnd,
     LogicalOr,
+    ComposeForward,
+    ComposeBackward,
+    Append,
+}
+
+impl BinaryOp {
+    /// Constraint that both operands of this operation must satisfy
+    pub fn required_constraint(&self) -> Option<Constraint> {
+        match self {
+            BinaryOp::Equal | BinaryOp::NotEqual => Some(Constraint::Eq),
+            BinaryOp::LessThan
+            | BinaryOp::LessThanEqual
+            | BinaryOp::GreaterThan
+            | BinaryOp::GreaterThanEqual => Some(Constraint::Ord),
+            _ => None,
+        }
+    }
 }
 
 impl From<crate::ast::BinaryOperator> for BinaryOp {
@@ -174,10 +423,18 @@ impl From<crate::ast::BinaryOperator> for BinaryOp {
             crate::ast::BinaryOperator::GreaterThanEqual => BinaryOp::GreaterThanEqual,
             crate::ast::BinaryOperator::LogicalAnd => BinaryOp::LogicalAnd,
             crate::ast::BinaryOperator::LogicalOr => BinaryOp::LogicalOr,
+            crate::ast::BinaryOperator::ComposeForward => BinaryOp::ComposeForward,
+            crate::ast::BinaryOperator::ComposeBackward => BinaryOp::ComposeBackward,
+            crate::ast::BinaryOperator::Append => BinaryOp::Append,
         }
     }
 }
 
+/// Types of operator expressions such as `a + b`, by the span of the whole
+/// expression. Backends that represent `Int` and `BigInt` alike look up here
+/// which arithmetic has to fail on overflow.
+pub type OperatorTypes = std::collections::HashMap<Span, Type>;
+
 /// Type information with source location
 #[derive(Debug, Clone, PartialEq)]
 pub struct TypedExpression {
@@ -200,6 +457,19 @@ pub enum TypedStatement {
         value: TypedExpression,
         span: Span,
     },
+    EnumDeclaration {
+        name: String,
+        variants: Vec<String>,
+        span: Span,
+    },
+    ConstantDeclaration {
+        name: String,
+        ty: Type,
+        value: TypedExpression,
+        /// The value computed while checking
+        constant: Constant,
+        span: Span,
+    },
     FunctionDeclaration {
         name: String,
         param: String,
@@ -211,12 +481,17 @@ pub enum TypedStatement {
     Import {
         path: String,
         alias: Option<String>,
+        exported: bool,
         span: Span,
     },
     Expression {
         expression: TypedExpression,
         span: Span,
     },
+    Return {
+        value: TypedExpression,
+        span: Span,
+    },
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -259,6 +534,10 @@ impl Type {
         }
     }
 
+    pub fn enumeration(name: &str) -> Type {
+        Type::Enum { name: name.into() }
+    }
+
     pub fn recursive(inner: Type) -> Type {
         Type::Recursive {
             inner: Box::new(inner),
@@ -0,0 +1,173 @@
+use crate::ast::hole_text;
+use crate::lexer::tokens::Span;
+use crate::typechecker::Type;
+
+/// Something legal but likely to be a mistake. Warnings never stop a
+/// program from being checked or run.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypeWarning {
+    /// A declaration inside a block, function body or branch reuses the
+    /// name of a declaration from an enclosing scope, hiding it until the
+    /// inner scope ends
+    Shadowed {
+        name: String,
+        span: Span,
+        /// The declaration that is hidden
+        shadowed: Span,
+    },
+    /// A `case` arm that can never be taken because earlier arms already
+    /// match everything it matches
+    UnreachableArm { span: Span },
+    /// A `case` arm matching some values that an earlier arm already
+    /// matches; those values take the earlier arm
+    OverlappingArm { span: Span },
+    /// A builtin or syntax that has a replacement and may be removed; see
+    /// `deprecations`
+    Deprecated {
+        name: String,
+        replacement: String,
+        span: Span,
+    },
+    /// A hole, `?` or `??name`, with the type the code around it expects
+    /// there (`Unknown` if nothing tells) and the names in scope of that
+    /// type, any of which would fill it
+    Hole {
+        name: Option<String>,
+        expected: Type,
+        candidates: Vec<String>,
+        span: Span,
+    },
+    /// An expression statement whose value, of a type other than `Unit`, is
+    /// thrown away, as with `x == 5;` written for a binding
+    UnusedResult { ty: Type, span: Span },
+    /// An `if` whose condition is the same every time, because it folds to
+    /// `true` or `false` without naming any binding, with the branch that is
+    /// never taken. `if true` without an `else` has no such branch.
+    ConstantCondition {
+        value: bool,
+        condition: Span,
+        dead_branch: Option<Span>,
+    },
+    /// A named function that calls itself on every path through its body,
+    /// so that no call to it can finish
+    InfiniteRecursion { name: String, span: Span },
+}
+
+impl TypeWarning {
+    /// Stable code identifying the kind of warning; see `--explain`
+    pub fn code(&self) -> &'static str {
+        match self {
+            TypeWarning::Shadowed { .. } => "W0001",
+            TypeWarning::UnreachableArm { .. } => "W0002",
+            TypeWarning::OverlappingArm { .. } => "W0003",
+            TypeWarning::Deprecated { .. } => "W0004",
+            TypeWarning::Hole { .. } => "W0005",
+            TypeWarning::UnusedResult { .. } => "W0006",
+            TypeWarning::ConstantCondition { .. } => "W0007",
+            TypeWarning::InfiniteRecursion { .. } => "W0008",
+        }
+    }
+
+    /// Location of the code the warning is about
+    pub fn span(&self) -> &Span {
+        match self {
+            TypeWarning::Shadowed { span, .. }
+            | TypeWarning::UnreachableArm { span }
+            | TypeWarning::OverlappingArm { span }
+            | TypeWarning::Deprecated { span, .. }
+            | TypeWarning::Hole { span, .. }
+            | TypeWarning::UnusedResult { span, .. }
+            | TypeWarning::InfiniteRecursion { span, .. } => span,
+            TypeWarning::ConstantCondition {
+                condition,
+                dead_branch,
+                ..
+            } => dead_branch.as_ref().unwrap_or(condition),
+        }
+    }
+}
+
+impl std::fmt::Display for TypeWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TypeWarning::Shadowed {
+                name,
+                span,
+                shadowed,
+            } => write!(
+                f,
+                "'{}' at line {}, column {} shadows the declaration at line {}, column {}",
+                name, span.line, span.column, shadowed.line, shadowed.column
+            ),
+            TypeWarning::UnreachableArm { span } => write!(
+                f,
+                "Case arm at line {}, column {} is never taken: earlier arms match everything it does",
+                span.line, span.column
+            ),
+            TypeWarning::OverlappingArm { span } => write!(
+                f,
+                "Case arm at line {}, column {} overlaps an earlier arm, which takes the values both match",
+                span.line, span.column
+            ),
+            TypeWarning::Deprecated {
+                name,
+                replacement,
+                span,
+            } => write!(
+                f,
+                "'{}' at line {}, column {} is deprecated; use {} instead",
+                name, span.line, span.column, replacement
+            ),
+            TypeWarning::Hole {
+                name,
+                expected,
+                candidates,
+                span,
+            } => {
+                let hole = hole_text(name.as_deref());
+                write!(
+                    f,
+                    "Hole {} at line {}, column {}",
+                    hole, span.line, span.column
+                )?;
+                if expected.contains_unknown() {
+                    return write!(f, " can be of any type here");
+                }
+                write!(f, " needs a value of type {}", expected)?;
+                match candidates.as_slice() {
+                    [] => write!(f, "; nothing in scope has that type"),
+                    names => write!(f, "; in scope with that type: {}", names.join(", ")),
+                }
+            }
+            TypeWarning::UnusedResult { ty, span } => write!(
+                f,
+                "The {} value of the statement at line {}, column {} is not used; write `let _ = ...;` to discard it",
+                ty, span.line, span.column
+            ),
+            TypeWarning::ConstantCondition {
+                value,
+                condition,
+                dead_branch,
+            } => {
+                write!(
+                    f,
+                    "Condition at line {}, column {} is always {}",
+                    condition.line, condition.column, value
+                )?;
+                match dead_branch {
+                    Some(branch) => write!(
+                        f,
+                        ", so the branch at line {}, column {} is never taken",
+                        branch.line, branch.column
+                    ),
+                    None => Ok(()),
+                }
+            }
+            TypeWarning::InfiniteRecursion { name, span } => write!(
+                f,
+                "'{}' at line {}, column {} calls itself on every path, so it never returns; add a branch that gives a result without calling '{}'",
+                name, span.line, span.column, name
+            ),
+        }
+    }
+}